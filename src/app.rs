@@ -7,7 +7,10 @@ mod handler;
 pub mod terminal;
 
 use std::{
+    ffi::{OsStr, OsString},
+    io,
     path::PathBuf,
+    process::{ExitStatus, Stdio},
     sync::mpsc::{self, Receiver, Sender},
 };
 
@@ -17,7 +20,10 @@ use ratatui::Frame;
 use self::{
     clipboard::Clipboard,
     config::Config,
-    events::{receive_commands, spawn_command_sender, spawn_signal_watcher},
+    events::{
+        handled_keys, leaving_interrupts_to_child, receive_commands, spawn_command_sender,
+        spawn_signal_watcher,
+    },
     terminal::CleanupOnDropTerminal,
 };
 use crate::{
@@ -179,6 +185,10 @@ impl App {
         loop {
             let commands = receive_commands(&self.rx);
             let received = commands.len();
+            let keys = commands
+                .iter()
+                .filter(|command| matches!(command, Command::Key(_, _)))
+                .count();
 
             let remaining_commands = broadcast_commands(&mut self.handlers, &self.tx, commands);
 
@@ -186,14 +196,37 @@ impl App {
                 return Ok(());
             }
 
+            let (foreground, remaining_commands) = take_foreground_runs(remaining_commands);
+            for command_line in &foreground {
+                self.run_in_foreground(command_line)?;
+            }
+            // Only now, with any program those keys started already exited, may
+            // the reader go back to the terminal.
+            handled_keys(keys);
+
             must_not_contain_unhandled(&remaining_commands)?;
-            if changed_nothing_visible(received, &remaining_commands) {
+            if foreground.is_empty() && changed_nothing_visible(received, &remaining_commands) {
                 continue;
             }
             self.render()?;
         }
     }
 
+    /// Hands the terminal to `command_line` and waits for it to exit. Its exit
+    /// status is not reported: a diff tool's says whether the files differ,
+    /// which the user has just seen for themselves.
+    fn run_in_foreground(&mut self, command_line: &OsStr) -> Result<()> {
+        let result =
+            leaving_interrupts_to_child(|| self.terminal.suspended(|| run_attached(command_line)))?;
+        if let Err(error) = result {
+            let _ = self.tx.send(Command::AlertError(format!(
+                "Failed to run {:?}: {error}",
+                command_line.to_string_lossy()
+            )));
+        }
+        Ok(())
+    }
+
     fn render(&mut self) -> Result<()> {
         let root = &mut self.handlers.root;
         self.terminal.draw(|frame: &mut Frame| {
@@ -299,6 +332,32 @@ fn changed_nothing_visible(received: usize, remaining: &[Command]) -> bool {
             .all(|command| matches!(command, Command::Key(_, _) | Command::Mouse(_)))
 }
 
+/// Runs `command_line` through the shell on this terminal, with this process's
+/// stdin, stdout and stderr, and waits for it to exit.
+fn run_attached(command_line: &OsStr) -> io::Result<ExitStatus> {
+    std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command_line)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+}
+
+/// Splits the command lines to run in the foreground from the rest, which keep
+/// their order.
+fn take_foreground_runs(commands: Vec<Command>) -> (Vec<OsString>, Vec<Command>) {
+    let mut foreground = Vec::new();
+    let mut rest = Vec::new();
+    for command in commands {
+        match command {
+            Command::RunInForeground(command_line) => foreground.push(command_line),
+            command => rest.push(command),
+        }
+    }
+    (foreground, rest)
+}
+
 fn should_quit(commands: &[Command]) -> bool {
     commands
         .iter()
@@ -317,6 +376,7 @@ mod tests {
     };
 
     use super::*;
+    use crate::test_support::TempDir;
 
     /// A `CommandHandler` that records the order in which it is visited and can
     /// be configured to consume keys or to derive a follow-up command.
@@ -412,12 +472,8 @@ mod tests {
         root.children = vec![Spy::new("a", &log), Spy::new("b", &log)];
 
         let mut derived = Vec::new();
-        let handled = recursively_handle_command(
-            &mut derived,
-            &Command::SearchTick,
-            InputMode::Normal,
-            &mut root,
-        );
+        let handled =
+            recursively_handle_command(&mut derived, &Command::Tick, InputMode::Normal, &mut root);
 
         assert!(!handled); // none of the spies handle it
         assert_eq!(vec!["root", "a", "b"], *log.borrow());
@@ -428,16 +484,12 @@ mod tests {
         let log = Rc::new(RefCell::new(Vec::new()));
         let mut root = Spy::new("root", &log);
         let mut a = Spy::new("a", &log);
-        a.derive_on = Some((Command::SearchTick, Command::ResetView));
+        a.derive_on = Some((Command::Tick, Command::ResetView));
         root.children = vec![a, Spy::new("b", &log)];
 
         let mut derived = Vec::new();
-        let handled = recursively_handle_command(
-            &mut derived,
-            &Command::SearchTick,
-            InputMode::Normal,
-            &mut root,
-        );
+        let handled =
+            recursively_handle_command(&mut derived, &Command::Tick, InputMode::Normal, &mut root);
 
         // Only a key short-circuits. A claimed non-key command must still
         // reach the rest of the tree: `SelectionChanged` is read by both
@@ -452,15 +504,11 @@ mod tests {
     fn handled_with_pushes_derived_command() {
         let log = Rc::new(RefCell::new(Vec::new()));
         let mut root = Spy::new("root", &log);
-        root.derive_on = Some((Command::SearchTick, Command::Quit));
+        root.derive_on = Some((Command::Tick, Command::Quit));
 
         let mut derived = Vec::new();
-        let handled = recursively_handle_command(
-            &mut derived,
-            &Command::SearchTick,
-            InputMode::Normal,
-            &mut root,
-        );
+        let handled =
+            recursively_handle_command(&mut derived, &Command::Tick, InputMode::Normal, &mut root);
 
         assert!(handled);
         assert_eq!(vec![Command::Quit], derived);
@@ -473,12 +521,8 @@ mod tests {
         root.derive_many = vec![Command::CancelTask, Command::Quit];
 
         let mut derived = Vec::new();
-        let handled = recursively_handle_command(
-            &mut derived,
-            &Command::SearchTick,
-            InputMode::Normal,
-            &mut root,
-        );
+        let handled =
+            recursively_handle_command(&mut derived, &Command::Tick, InputMode::Normal, &mut root);
 
         assert!(handled);
         assert_eq!(vec![Command::CancelTask, Command::Quit], derived);
@@ -502,9 +546,9 @@ mod tests {
     fn a_derived_command_is_broadcast_in_turn() {
         let log = Rc::new(RefCell::new(Vec::new()));
         let (mut root, tx, _rx) = broadcaster(&log);
-        root.derive_on = Some((Command::SearchTick, Command::ResetView));
+        root.derive_on = Some((Command::Tick, Command::ResetView));
 
-        let unhandled = broadcast_command(&mut root, &tx, Command::SearchTick);
+        let unhandled = broadcast_command(&mut root, &tx, Command::Tick);
 
         // Two cycles: the input, then what it derived. Resolving an intent
         // into a result is the whole reason the loop exists, and a handler
@@ -532,12 +576,12 @@ mod tests {
     fn a_chain_is_bounded_by_the_cycle_limit() {
         let log = Rc::new(RefCell::new(Vec::new()));
         let (mut root, tx, rx) = broadcaster(&log);
-        // A handler that answers every SearchTick with another one: the
+        // A handler that answers every Tick with another one: the
         // stuck-deriving bug the limit exists to stop.
-        root.derive_on = Some((Command::SearchTick, Command::SearchTick));
+        root.derive_on = Some((Command::Tick, Command::Tick));
 
         let unhandled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            broadcast_command(&mut root, &tx, Command::SearchTick)
+            broadcast_command(&mut root, &tx, Command::Tick)
         }));
 
         // `debug_assert!` makes it a panic in a debug build, which is where a
@@ -639,6 +683,35 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn a_foreground_run_is_waited_for() {
+        let root = TempDir::new("app_foreground");
+        let output = root.join("out");
+        let command_line = format!("sleep 0.1; echo done > '{}'", output.display());
+
+        let status = run_attached(OsStr::new(&command_line)).unwrap();
+
+        // Finished on return, where a detached launch would still be sleeping.
+        assert!(status.success());
+        assert_eq!("done\n", std::fs::read_to_string(&output).unwrap());
+    }
+
+    #[test]
+    fn foreground_runs_are_taken_out_of_the_unhandled_list() {
+        let (foreground, rest) = take_foreground_runs(vec![
+            Command::Key(KeyCode::Esc, KeyModifiers::NONE),
+            Command::RunInForeground("nvim -d a b".into()),
+            Command::Resize {
+                width: 1,
+                height: 1,
+            },
+        ]);
+
+        assert_eq!(vec![OsString::from("nvim -d a b")], foreground);
+        assert!(must_not_contain_unhandled(&rest).is_ok());
+        assert_eq!(2, rest.len());
+    }
+
     #[test]
     fn should_quit_detects_quit_command() {
         assert!(should_quit(&[
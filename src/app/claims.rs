@@ -15,10 +15,21 @@ use super::*;
 use crate::{
     app::{clipboard::ClipboardEntry, config::Openers},
     command::{
-        ConflictChoice, PromptAction,
+        ConflictChoice, PromptAction, TouchTime,
         progress::{ActiveTask, TaskKind},
     },
-    file_system::path_info::PathInfo,
+    file_system::{
+        checksums::{ChecksumAlgorithm, ChecksumEntry, ChecksumReport},
+        compare::{CompareEntry, CompareStatus},
+        diff::FileDiff,
+        disk_usage::Usage,
+        duplicates::{DuplicateFile, DuplicateProgress, DuplicateSet},
+        path_info::PathInfo,
+        peek::PeekColumn,
+        preview::FilePreview,
+        sftp::Address,
+        summary::TreeSummary,
+    },
     test_support::TempDir,
 };
 
@@ -68,6 +79,7 @@ fn test_handlers(tx: Sender<Command>, fixture: &Fixture) -> Handlers {
     let mut config = Config::builtin();
     config.config_dir = fixture.root.path().to_path_buf();
     config.openers = Openers {
+        diff: String::new(),
         open_directory: String::new(),
         open_file: String::new(),
         open_filectrl_window: String::new(),
@@ -93,6 +105,8 @@ fn test_handlers(tx: Sender<Command>, fixture: &Fixture) -> Handlers {
 /// - `Quit`: it must stay *unclaimed*. `App::run` detects it in the unhandled
 ///   list and returns before `must_not_contain_unhandled` runs, so a handler
 ///   that claimed it would stop the app from ever exiting.
+/// - `RunInForeground`: unclaimed for the same reason. `App::run` takes it out
+///   of the unhandled list and runs it there, where the terminal is.
 ///
 /// See `every_variant_is_accounted_for` for why this list cannot silently fall
 /// behind the enum.
@@ -104,39 +118,293 @@ fn claimable_commands(fixture: &Fixture, tx: &Sender<Command>) -> Vec<Command> {
         },
         100,
     );
+    [
+        vec![
+            Command::Tick,
+            Command::OpenCurrentDirectory,
+            Command::OpenNewWindow,
+        ],
+        tool_commands(fixture),
+        vec![
+            // The empty-argv backstop, so no process is spawned.
+            Command::OpenWith {
+                argv: Vec::new(),
+                label: "app".to_string(),
+                working_dir: None,
+            },
+            Command::GoToPreviousDirectory,
+            Command::Open(fixture.directory()),
+            // The empty-host backstop, so no ssh process is spawned.
+            Command::OpenRemote(Address {
+                host: String::new(),
+                port: None,
+                path: String::new(),
+            }),
+            Command::NavigatedDirectory {
+                directory: fixture.directory(),
+                generation: 1,
+            },
+            Command::RefreshDirectory,
+            Command::SwitchedPane {
+                directory: fixture.directory(),
+                filter: String::new(),
+                mark_count: 0,
+                previous_directory: None,
+            },
+            // Claimed by RootView, which keeps the tabs.
+            Command::NewTab,
+            Command::SwitchTab(0),
+            Command::CloseTab,
+            Command::RefreshedDirectory {
+                directory: fixture.directory(),
+                generation: 2,
+            },
+            Command::ListingBatch {
+                items: vec![fixture.file()],
+                generation: 2,
+            },
+            Command::DirectoryListingComplete { generation: 2 },
+            // Nothing to count, so no walk starts.
+            Command::SizeDirectories {
+                directories: Vec::new(),
+                generation: 2,
+            },
+            Command::DirectorySizes {
+                sizes: vec![(fixture.cwd(), 1)],
+                generation: 2,
+            },
+        ],
+        operation_commands(fixture),
+        vec![
+            Command::AddBookmark {
+                directory: fixture.directory(),
+                name: "bookmark".to_string(),
+            },
+            Command::GetBookmarks,
+            Command::Bookmarks {
+                bookmarks: vec![fixture.file()],
+            },
+            Command::CancelPrompt,
+            Command::OpenPrompt(PromptAction::CreateDirectory),
+            Command::OpenPrompt(PromptAction::CreateFile),
+            Command::SetClipboardEntry(Some(ClipboardEntry::Copy(vec![fixture.file()]))),
+            Command::SetClipboardEntry(None),
+            Command::GetClipboardText,
+            Command::ClipboardText("text".to_string()),
+            Command::SetClipboardText("text".to_string()),
+            Command::CancelSearch,
+            Command::ExitedSearch { generation: 3 },
+            Command::SearchStarted { generation: 3 },
+            // The empty-query backstop, so no search or tick thread is spawned.
+            Command::StartSearch(String::new()),
+            Command::FilterChanged("f".to_string()),
+            Command::SelectionChanged {
+                selected: Some(fixture.file()),
+                mark_count: 0,
+            },
+            Command::ResetView,
+            Command::AlertError("e".to_string()),
+            Command::AlertInfo("i".to_string()),
+            Command::AlertWarn("w".to_string()),
+            Command::CancelTask,
+            Command::Progress(task),
+            // Navigating out of the working directory comes last so the commands
+            // above all run against the fixture's cwd.
+            Command::GoToParentDirectory,
+        ],
+    ]
+    .concat()
+}
+
+/// The commands of the tools that open a view of their own, and of the
+/// previews beside the listing.
+fn tool_commands(fixture: &Fixture) -> Vec<Command> {
+    [
+        vec![
+            // Claimed by RootView, which enumerates the applications that can open
+            // the path. That reads the host's MIME and desktop entry databases, but
+            // it is read-only, bounded, and spawns nothing, so host variance cannot
+            // affect whether the command is claimed.
+            Command::OpenWithPrompt(fixture.file()),
+            Command::OpenPermissionEditor(vec![fixture.file()]),
+            // Claimed by RootView, which lists the host's templates directory:
+            // read-only, so its contents cannot affect whether it is claimed.
+            Command::OpenTemplatePicker,
+        ],
+        scan_commands(fixture),
+        vec![
+            // A missing file, so the task reports it unreadable and reads
+            // nothing.
+            Command::ComputeChecksums {
+                paths: vec![fixture.missing().path],
+                directory: fixture.cwd(),
+                algorithm: ChecksumAlgorithm::Blake3,
+            },
+            // A missing checksum file, refused before a task starts.
+            Command::VerifyChecksums(fixture.missing().path),
+            // Claimed by RootView, which shows it.
+            Command::Checksummed(ChecksumReport {
+                algorithm: ChecksumAlgorithm::Sha256,
+                directory: fixture.cwd(),
+                sums: None,
+                entries: vec![ChecksumEntry {
+                    path: fixture.file().path,
+                    digest: Ok("00".into()),
+                    expected: None,
+                }],
+            }),
+            // A missing file, refused with an alert.
+            Command::DiffFiles {
+                left: fixture.file().path,
+                right: fixture.missing().path,
+            },
+            // Claimed by RootView, which shows it.
+            Command::Diffed(FileDiff {
+                left: fixture.file().path,
+                right: fixture.file().path,
+                hunks: Vec::new(),
+            }),
+            Command::Peek {
+                path: fixture.directory(),
+                column: PeekColumn::Preview,
+            },
+            Command::Peeked {
+                path: fixture.directory().path,
+                column: PeekColumn::Preview,
+                entries: Ok(Vec::new()),
+            },
+            Command::PreviewFile(fixture.file()),
+            Command::Previewed {
+                path: fixture.file().path,
+                preview: Ok(FilePreview::Binary),
+            },
+        ],
+    ]
+    .concat()
+}
+
+/// The commands of the disk usage view, the duplicate finder and the
+/// directory comparison.
+fn scan_commands(fixture: &Fixture) -> Vec<Command> {
     vec![
-        Command::OpenCurrentDirectory,
-        Command::OpenNewWindow,
-        // Claimed by RootView, which enumerates the applications that can open
-        // the path. That reads the host's MIME and desktop entry databases, but
-        // it is read-only, bounded, and spawns nothing, so host variance cannot
-        // affect whether the command is claimed.
-        Command::OpenWithPrompt(fixture.file()),
-        // The empty-argv backstop, so no process is spawned.
-        Command::OpenWith {
-            argv: Vec::new(),
-            label: "app".to_string(),
-            working_dir: None,
+        // Claimed by RootView, whose view asks for a scan of the fixture:
+        // read-only, and a tree of two entries.
+        Command::OpenDiskUsage(fixture.directory()),
+        // A missing root, so the scan finds nothing and ends at once.
+        Command::ScanDiskUsage(fixture.missing().path),
+        Command::DiskUsageProgress {
+            entries: 1,
+            bytes: 4096,
+        },
+        Command::DiskUsageScanned(Usage {
+            path: fixture.cwd(),
+            bytes: 4096,
+            is_directory: true,
+            children: Vec::new(),
+        }),
+        Command::CancelDiskUsage,
+        // Claimed by RootView, whose view asks for a scan of the fixture:
+        // read-only, and a single file.
+        Command::OpenDuplicates(fixture.directory()),
+        // A missing root, so the scan finds nothing and ends at once.
+        Command::FindDuplicates(fixture.missing().path),
+        Command::DuplicatesProgress(DuplicateProgress::Walking { files: 1 }),
+        Command::DuplicatesFound(vec![DuplicateSet {
+            bytes: 1,
+            files: vec![
+                DuplicateFile {
+                    path: fixture.file().path,
+                    modified: None,
+                },
+                DuplicateFile {
+                    path: fixture.missing().path,
+                    modified: None,
+                },
+            ],
+        }]),
+        // A missing duplicate, so nothing is linked and the alert says so.
+        Command::HardLinkDuplicates(vec![(
+            DuplicateFile {
+                path: fixture.file().path,
+                modified: None,
+            },
+            DuplicateFile {
+                path: fixture.missing().path,
+                modified: None,
+            },
+        )]),
+        Command::DuplicatesLinked(vec![fixture.missing().path]),
+        Command::CancelDuplicates,
+        // Claimed by RootView, whose view asks for a comparison of the
+        // fixture with itself: read-only, and a single file.
+        Command::OpenCompare {
+            left: fixture.directory(),
+            right: fixture.directory(),
         },
-        Command::GoToPreviousDirectory,
-        Command::Open(fixture.directory()),
-        Command::NavigatedDirectory {
-            directory: fixture.directory(),
-            generation: 1,
+        // Missing roots, so the walk finds nothing and ends at once.
+        Command::CompareDirectories {
+            left: fixture.missing().path,
+            right: fixture.missing().path,
+            contents: true,
         },
-        Command::RefreshDirectory,
-        Command::RefreshedDirectory {
-            directory: fixture.directory(),
-            generation: 2,
+        Command::CompareProgress { entries: 1 },
+        Command::Compared(vec![CompareEntry {
+            relative: "file.txt".into(),
+            left: None,
+            right: None,
+            status: CompareStatus::Identical,
+        }]),
+        // A missing source, so the task records it and writes nothing.
+        Command::CopyCompared {
+            left: fixture.cwd(),
+            right: fixture.cwd(),
+            copies: vec![(fixture.missing().path, fixture.missing().path)],
         },
-        Command::ListingBatch {
-            items: vec![fixture.file()],
-            generation: 2,
+        // Likewise, with a removal that is already gone.
+        Command::MirrorCompared {
+            source: fixture.cwd(),
+            destination: fixture.cwd(),
+            copies: vec![(fixture.missing().path, fixture.missing().path)],
+            removals: vec![fixture.missing().path],
         },
-        Command::DirectoryListingComplete { generation: 2 },
+        Command::CancelCompare,
+    ]
+}
+
+/// The commands that change what is on disk.
+fn operation_commands(fixture: &Fixture) -> Vec<Command> {
+    vec![
         Command::Chmod {
             paths: vec![fixture.file()],
             mode: "644".to_string(),
+            recursive: false,
+        },
+        Command::Chown {
+            paths: vec![fixture.missing()],
+            owner: "0".to_string(),
+            recursive: false,
+        },
+        // A time that does not parse, so nothing is touched.
+        Command::Touch {
+            paths: vec![fixture.file()],
+            time: TouchTime::Typed("never".to_string()),
+            recursive: false,
+        },
+        // A name without an archive extension, so no task starts.
+        Command::Compress {
+            paths: vec![fixture.file()],
+            name: "archive".to_string(),
+        },
+        // Not an archive by its name, so nothing is read.
+        Command::Extract {
+            archive: fixture.file(),
+            here: true,
+        },
+        // Nothing to extract, so no task starts.
+        Command::ArchiveSurveyed {
+            archive: fixture.missing().path,
+            destination: fixture.cwd(),
+            names: Vec::new(),
         },
         Command::Copy {
             srcs: vec![fixture.missing()],
@@ -151,47 +419,22 @@ fn claimable_commands(fixture: &Fixture, tx: &Sender<Command>) -> Vec<Command> {
         // arm still has to claim it.
         Command::ResolveConflict(ConflictChoice::Skip),
         Command::CreateDirectory("created".to_string()),
+        Command::CreateFile("created.txt".to_string()),
+        Command::Created(fixture.cwd().join("created.txt")),
+        // A missing template, so nothing is copied.
+        Command::CreateFromTemplate {
+            template: fixture.missing(),
+            name: "copy.txt".to_string(),
+        },
         Command::ConfirmDelete,
         Command::Delete(vec![fixture.missing()]),
+        // A missing path, so the walk counts nothing and ends at once.
+        Command::SummarizeDelete(vec![fixture.missing()]),
+        Command::DeleteSummary(TreeSummary::default()),
         Command::Rename {
             path: fixture.missing(),
             name: "renamed".to_string(),
         },
-        Command::AddBookmark {
-            directory: fixture.directory(),
-            name: "bookmark".to_string(),
-        },
-        Command::GetBookmarks,
-        Command::Bookmarks {
-            bookmarks: vec![fixture.file()],
-        },
-        Command::CancelPrompt,
-        Command::OpenPrompt(PromptAction::CreateDirectory),
-        Command::SetClipboardEntry(Some(ClipboardEntry::Copy(vec![fixture.file()]))),
-        Command::SetClipboardEntry(None),
-        Command::GetClipboardText,
-        Command::ClipboardText("text".to_string()),
-        Command::SetClipboardText("text".to_string()),
-        Command::CancelSearch,
-        Command::ExitedSearch { generation: 3 },
-        Command::SearchStarted { generation: 3 },
-        Command::SearchTick,
-        // The empty-query backstop, so no search or tick thread is spawned.
-        Command::StartSearch(String::new()),
-        Command::FilterChanged("f".to_string()),
-        Command::SelectionChanged {
-            selected: Some(fixture.file()),
-            mark_count: 0,
-        },
-        Command::ResetView,
-        Command::AlertError("e".to_string()),
-        Command::AlertInfo("i".to_string()),
-        Command::AlertWarn("w".to_string()),
-        Command::CancelTask,
-        Command::Progress(task),
-        // Navigating out of the working directory comes last so the commands
-        // above all run against the fixture's cwd.
-        Command::GoToParentDirectory,
     ]
 }
 
@@ -206,28 +449,80 @@ fn claimable_commands(fixture: &Fixture, tx: &Sender<Command>) -> Vec<Command> {
 fn every_variant_is_accounted_for(command: &Command) {
     match command {
         // Exempt (see `claimable_commands`).
-        Command::Key(_, _) | Command::Mouse(_) | Command::Resize { .. } | Command::Quit => {}
+        Command::Key(_, _)
+        | Command::Mouse(_)
+        | Command::Resize { .. }
+        | Command::Quit
+        | Command::RunInForeground(_) => {}
         // Must be claimed.
-        Command::OpenCurrentDirectory
+        Command::Tick
+        | Command::OpenCurrentDirectory
         | Command::OpenNewWindow
         | Command::OpenWith { .. }
         | Command::OpenWithPrompt(_)
+        | Command::OpenPermissionEditor(_)
+        | Command::OpenTemplatePicker
+        | Command::OpenDiskUsage(_)
+        | Command::ScanDiskUsage(_)
+        | Command::DiskUsageProgress { .. }
+        | Command::DiskUsageScanned(_)
+        | Command::CancelDiskUsage
+        | Command::OpenDuplicates(_)
+        | Command::FindDuplicates(_)
+        | Command::DuplicatesProgress(_)
+        | Command::DuplicatesFound(_)
+        | Command::CancelDuplicates
+        | Command::HardLinkDuplicates(_)
+        | Command::DuplicatesLinked(_)
+        | Command::OpenCompare { .. }
+        | Command::CompareDirectories { .. }
+        | Command::CompareProgress { .. }
+        | Command::Compared(_)
+        | Command::CancelCompare
+        | Command::CopyCompared { .. }
+        | Command::MirrorCompared { .. }
+        | Command::ComputeChecksums { .. }
+        | Command::VerifyChecksums(_)
+        | Command::Checksummed(_)
+        | Command::DiffFiles { .. }
+        | Command::Diffed(_)
+        | Command::Peek { .. }
+        | Command::Peeked { .. }
+        | Command::PreviewFile(_)
+        | Command::Previewed { .. }
         | Command::GoToParentDirectory
         | Command::GoToPreviousDirectory
         | Command::Open(_)
+        | Command::OpenRemote(_)
         | Command::NavigatedDirectory { .. }
         | Command::RefreshDirectory
+        | Command::SwitchedPane { .. }
+        | Command::NewTab
+        | Command::CloseTab
+        | Command::SwitchTab(_)
         | Command::RefreshedDirectory { .. }
         | Command::ListingBatch { .. }
         | Command::DirectoryListingComplete { .. }
+        | Command::SizeDirectories { .. }
+        | Command::DirectorySizes { .. }
         | Command::Chmod { .. }
+        | Command::Chown { .. }
+        | Command::Touch { .. }
+        | Command::Compress { .. }
+        | Command::Extract { .. }
+        | Command::ArchiveSurveyed { .. }
         | Command::Copy { .. }
         | Command::Move { .. }
         | Command::Paste(_)
         | Command::ResolveConflict(_)
         | Command::CreateDirectory(_)
+        | Command::CreateFile(_)
+        | Command::Created(_)
+        | Command::CreateFromTemplate { .. }
         | Command::ConfirmDelete
         | Command::Delete(_)
+        | Command::SummarizeDelete(_)
+        | Command::DeleteSummary(_)
         | Command::Rename { .. }
         | Command::AddBookmark { .. }
         | Command::GetBookmarks
@@ -241,7 +536,6 @@ fn every_variant_is_accounted_for(command: &Command) {
         | Command::CancelSearch
         | Command::ExitedSearch { .. }
         | Command::SearchStarted { .. }
-        | Command::SearchTick
         | Command::StartSearch(_)
         | Command::FilterChanged(_)
         | Command::SelectionChanged { .. }
@@ -292,3 +586,22 @@ fn quit_is_deliberately_unclaimed() {
     assert!(!handled);
     assert!(derived.is_empty());
 }
+
+#[test]
+fn foreground_runs_are_deliberately_unclaimed() {
+    let fixture = Fixture::new();
+    let (tx, _rx) = mpsc::channel();
+    let mut handlers = test_handlers(tx, &fixture);
+
+    let mut derived = Vec::new();
+    let handled = recursively_handle_command(
+        &mut derived,
+        &Command::RunInForeground("nvim -d a b".into()),
+        InputMode::Normal,
+        &mut handlers,
+    );
+
+    // Only `App::run` holds the terminal the program needs.
+    assert!(!handled);
+    assert!(derived.is_empty());
+}
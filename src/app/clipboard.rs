@@ -1,6 +1,9 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
 
-use crate::file_system::path_info::PathInfo;
+use crate::file_system::{path_info::PathInfo, sftp};
 use anyhow::{Context, Error, Result, anyhow};
 use arboard::Clipboard as ArboardClipboard;
 use log::warn;
@@ -129,7 +132,7 @@ impl Display for ClipboardEntry {
         };
         write!(f, "{name}")?;
         for path in self.paths() {
-            write!(f, " {}", shell_words::quote(&path.path.to_string_lossy()))?;
+            write!(f, " {}", shell_words::quote(&path.location()))?;
         }
         Ok(())
     }
@@ -162,18 +165,31 @@ fn parse_clipboard_text(text: &str) -> Result<Option<ClipboardEntry>> {
 }
 
 /// True when the tokens are shaped like an entry filectrl writes: a "cp"/"mv"
-/// token followed by absolute paths. Requiring absolute paths keeps an ordinary
-/// copied shell line ("cp build dist") from raising an alert.
+/// token followed by absolute paths or remote locations. Requiring those keeps
+/// an ordinary copied shell line ("cp build dist") from raising an alert.
 fn is_entry_shaped(parts: &[String]) -> bool {
     matches!(parts.first().map(String::as_str), Some("cp" | "mv"))
-        && parts[1..].iter().all(|part| part.starts_with('/'))
+        && parts[1..]
+            .iter()
+            .all(|part| part.starts_with('/') || part.starts_with(sftp::SCHEME))
 }
 
 fn parse_clipboard_parts(parts: &[String]) -> Result<ClipboardEntry> {
     let command_str = &parts[0];
     let paths: Vec<_> = parts[1..]
         .iter()
-        .map(|p| PathInfo::try_from(p.as_str()).with_context(|| format!("Failed to access {p}")))
+        .map(|p| {
+            // An entry copied out of an archive being browsed is found by
+            // reading the archive, having no path of its own on disk. One
+            // copied on another host is read again over its session.
+            PathInfo::remote_at(p)
+                .unwrap_or_else(|| {
+                    PathInfo::try_from(p.as_str()).or_else(|error| {
+                        PathInfo::in_archive_at(Path::new(p)).unwrap_or(Err(error))
+                    })
+                })
+                .with_context(|| format!("Failed to access {p}"))
+        })
         .collect::<Result<Vec<_>, _>>()?;
     match command_str.as_str() {
         "cp" => Ok(ClipboardEntry::Copy(paths)),
@@ -331,6 +347,57 @@ mod tests {
         assert_eq!(ClipboardEntry::Move(paths), parsed);
     }
 
+    /// An entry copied inside a browsed archive has no path on disk, so it
+    /// only survives the clipboard by being looked up in the archive again.
+    #[test]
+    fn an_archive_entry_parses_back_from_the_archive() {
+        use std::io::Write;
+
+        use crate::test_support::TempDir;
+
+        let dir = TempDir::new("clipboard_archive");
+        let archive = dir.join("a.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        zip.start_file("dir/a.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"a").unwrap();
+        zip.finish().unwrap();
+
+        let entry = parse_clipboard_text(&format!("cp {}", archive.join("dir").display()))
+            .unwrap()
+            .unwrap();
+
+        let [path] = entry.paths() else {
+            panic!("expected one path, got {entry:?}");
+        };
+        assert_eq!(Some(archive.as_path()), path.archive());
+        assert!(path.is_directory());
+        assert!(parse_clipboard_text(&format!("cp {}", archive.join("nope").display())).is_err());
+    }
+
+    /// A remote entry is written as its location, and read back over the
+    /// session it came from.
+    #[test]
+    fn a_remote_entry_parses_back_over_its_session() {
+        use std::sync::Arc;
+
+        use crate::file_system::backend::Memory;
+
+        let memory = Arc::new(Memory::default());
+        memory.file("/srv/a b.txt", b"a");
+        let remote = sftp::stand_in("clipboard.test", memory);
+        let path = PathInfo::read_remote(&remote, Path::new("/srv/a b.txt")).unwrap();
+
+        let entry = ClipboardEntry::Copy(vec![path.clone()]);
+        assert_eq!("cp 'sftp://clipboard.test/srv/a b.txt'", entry.to_string());
+        assert_eq!(
+            Some(entry),
+            parse_clipboard_text("cp 'sftp://clipboard.test/srv/a b.txt'").unwrap()
+        );
+        // Shaped like an entry, so a host never connected to is reported.
+        assert!(parse_clipboard_text("cp sftp://elsewhere.test/srv/a.txt").is_err());
+    }
+
     #[test]
     fn parse_clipboard_text_errors_on_tab_separated_missing_path() {
         // The entry parser splits on any whitespace, so classification must
@@ -1,5 +1,6 @@
 pub mod keybindings;
 mod ls_colors;
+pub mod protected_paths;
 mod serde;
 pub mod theme;
 
@@ -18,6 +19,7 @@ use log::{LevelFilter, debug, info};
 use toml::Value;
 
 use self::keybindings::{KeyBindings, TomlKeybindings};
+use self::protected_paths::ProtectedPaths;
 use self::theme::Theme;
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -31,6 +33,7 @@ const DEFAULT_THEME_FILENAME: &str = "theme.toml";
 pub struct FileSystemConfig {
     pub buffer_max_bytes: u64,
     pub buffer_min_bytes: u64,
+    pub protected_paths: ProtectedPaths,
     pub refresh_debounce_milliseconds: u64,
     pub search_max_depth: u32,
     pub search_max_results: u32,
@@ -38,6 +41,9 @@ pub struct FileSystemConfig {
 
 #[derive(Debug, Deserialize)]
 pub struct Openers {
+    /// Diffs two files in place of the built-in viewer, which an empty one
+    /// leaves in use. Its `%s`s are the two paths in turn.
+    pub diff: String,
     pub open_directory: String,
     pub open_file: String,
     pub open_filectrl_window: String,
@@ -53,10 +59,16 @@ struct PlatformOpeners {
     macos: Openers,
 }
 
+// Each bool is a switch of its own in the `[ui]` table, read as it is
+// written there.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Deserialize)]
 pub struct UiConfig {
     pub double_click_interval_milliseconds: u16,
     pub show_hidden_files: bool,
+    pub show_directory_sizes: bool,
+    pub miller_columns: bool,
+    pub show_preview: bool,
     pub sort_directories_first: bool,
 }
 
@@ -664,6 +676,17 @@ open_directory = "alacritty --working-directory %s"
         );
     }
 
+    #[test]
+    fn a_relative_protected_path_is_rejected() {
+        // Relative to what the process happens to be in, it would protect a
+        // different entry depending on where FileCTRL was started.
+        let err = parse_err("[file_system]\nprotected_paths = [\"etc\"]\n");
+        assert!(
+            err.contains("protected_paths"),
+            "error should name the key: {err}"
+        );
+    }
+
     #[test_case("[file_system]\nbuffer_min_bytes = 200\nbuffer_max_bytes = 100\n" ; "min exceeds max")]
     #[test_case("[file_system]\nbuffer_min_bytes = 0\n" ; "min is zero")]
     fn invalid_buffer_sizes_are_rejected(toml: &str) {
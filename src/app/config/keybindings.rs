@@ -24,6 +24,30 @@ pub enum Action {
     OpenNewWindow,
     OpenWith,
     Refresh,
+    DiskUsage,
+    FindDuplicates,
+    CompareDirectories,
+    CompareContents,
+    Diff,
+
+    // Layout
+    ToggleMillerColumns,
+    TogglePreview,
+    ToggleDualPane,
+    SwitchPane,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    GoToTab1,
+    GoToTab2,
+    GoToTab3,
+    GoToTab4,
+    GoToTab5,
+    GoToTab6,
+    GoToTab7,
+    GoToTab8,
+    GoToTab9,
 
     // Selection
     SelectNext,
@@ -36,21 +60,46 @@ pub enum Action {
     SelectLastVisible,
     PageUp,
     PageDown,
+    NextHunk,
+    PreviousHunk,
 
     // Marks
     ToggleMark,
     RangeMark,
+    MarkAllButNewest,
+    MarkAllButOldest,
 
     // Clipboard
     Copy,
     Cut,
     Paste,
+    CopyToOtherPane,
+    MoveToOtherPane,
 
     // File operations
     AddBookmark,
     Chmod,
+    ChmodRecursive,
+    Chown,
+    ChownRecursive,
+    EditPermissions,
+    Touch,
+    TouchRecursive,
+    TouchFromClipboard,
+    Compress,
+    Extract,
+    ExtractHere,
     CreateDirectory,
+    CreateFile,
+    NewFromTemplate,
     Delete,
+    HardLinkDuplicates,
+    CopyCompared,
+    MirrorRight,
+    MirrorLeft,
+    Checksum,
+    ChecksumBlake3,
+    VerifyChecksums,
     Filter,
     Goto,
     Rename,
@@ -62,6 +111,7 @@ pub enum Action {
     SortByName,
     SortBySize,
     ToggleShowHidden,
+    ToggleDirectorySizes,
 
     // Prompt
     PromptCancel,
@@ -76,6 +126,23 @@ pub enum Action {
     PromptSubmit,
 }
 
+impl Action {
+    /// The actions that go to a tab, in the order of the tabs they go to. One
+    /// per tab, rather than one reading the tab from the digit pressed, so
+    /// that any key can be bound to any tab.
+    pub const GO_TO_TAB: [Self; 9] = [
+        Self::GoToTab1,
+        Self::GoToTab2,
+        Self::GoToTab3,
+        Self::GoToTab4,
+        Self::GoToTab5,
+        Self::GoToTab6,
+        Self::GoToTab7,
+        Self::GoToTab8,
+        Self::GoToTab9,
+    ];
+}
+
 /// A key combination (key code + modifiers).
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct KeyCombo {
@@ -156,16 +223,53 @@ keybindings! {
         go_to_previous_directory => GoToPreviousDirectory,
         add_bookmark => AddBookmark,
         cancel_task => CancelTask,
+        checksum => Checksum,
+        checksum_blake3 => ChecksumBlake3,
         chmod => Chmod,
+        chmod_recursive => ChmodRecursive,
+        chown => Chown,
+        chown_recursive => ChownRecursive,
+        edit_permissions => EditPermissions,
         clear_alerts => ClearAlerts,
         clear_progress => ClearProgress,
+        close_tab => CloseTab,
+        compare => CompareDirectories,
+        compare_contents => CompareContents,
+        compress => Compress,
         copy => Copy,
+        copy_compared => CopyCompared,
+        copy_to_other_pane => CopyToOtherPane,
         create_directory => CreateDirectory,
+        create_file => CreateFile,
         cut => Cut,
         delete => Delete,
+        diff => Diff,
+        disk_usage => DiskUsage,
+        extract => Extract,
+        extract_here => ExtractHere,
         filter => Filter,
+        find_duplicates => FindDuplicates,
         go_home => GoHome,
+        go_to_tab_1 => GoToTab1,
+        go_to_tab_2 => GoToTab2,
+        go_to_tab_3 => GoToTab3,
+        go_to_tab_4 => GoToTab4,
+        go_to_tab_5 => GoToTab5,
+        go_to_tab_6 => GoToTab6,
+        go_to_tab_7 => GoToTab7,
+        go_to_tab_8 => GoToTab8,
+        go_to_tab_9 => GoToTab9,
         goto => Goto,
+        hard_link_duplicates => HardLinkDuplicates,
+        mark_all_but_newest => MarkAllButNewest,
+        mark_all_but_oldest => MarkAllButOldest,
+        mirror_left => MirrorLeft,
+        mirror_right => MirrorRight,
+        move_to_other_pane => MoveToOtherPane,
+        new_from_template => NewFromTemplate,
+        new_tab => NewTab,
+        next_hunk => NextHunk,
+        next_tab => NextTab,
         open => Open,
         open_current_directory => OpenCurrentDirectory,
         open_new_window => OpenNewWindow,
@@ -173,6 +277,8 @@ keybindings! {
         page_down => PageDown,
         page_up => PageUp,
         paste => Paste,
+        previous_hunk => PreviousHunk,
+        previous_tab => PreviousTab,
         quit => Quit,
         range_mark => RangeMark,
         refresh => Refresh,
@@ -190,9 +296,18 @@ keybindings! {
         sort_by_modified => SortByModified,
         sort_by_name => SortByName,
         sort_by_size => SortBySize,
+        switch_pane => SwitchPane,
+        toggle_directory_sizes => ToggleDirectorySizes,
+        toggle_dual_pane => ToggleDualPane,
         toggle_help => ToggleHelp,
         toggle_mark => ToggleMark,
+        toggle_miller_columns => ToggleMillerColumns,
+        toggle_preview => TogglePreview,
         toggle_show_hidden => ToggleShowHidden,
+        touch => Touch,
+        touch_from_clipboard => TouchFromClipboard,
+        touch_recursive => TouchRecursive,
+        verify_checksums => VerifyChecksums,
     }
     prompt {
         prompt_copy => PromptCopy,
@@ -595,6 +710,27 @@ mod tests {
         KeyBindings::new(&toml_kb)
     }
 
+    #[test]
+    fn a_tab_can_be_gone_to_by_a_key_other_than_its_digit() {
+        let kb = keybindings_with_override(
+            "[keybindings]\ngo_to_tab_1 = \"Alt+F1\"\ngo_to_tab_2 = \"F9\"",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(Action::GoToTab1),
+            kb.normal_action(KeyCode::F(1), KeyModifiers::ALT)
+        );
+        assert_eq!(
+            Some(Action::GoToTab2),
+            kb.normal_action(KeyCode::F(9), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            None,
+            kb.normal_action(KeyCode::Char('1'), KeyModifiers::NONE)
+        );
+    }
+
     #[test]
     fn parse_single_char() {
         let combo = parse_key_combo("q").unwrap();
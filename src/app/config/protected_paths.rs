@@ -0,0 +1,301 @@
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+/// The `file_system.protected_paths` globs: entries that a delete, a move or
+/// rename, an overwrite, a chmod, a chown or a touch refuses to change.
+///
+/// A pattern is an absolute path, or one starting with `~/`, matched one
+/// component at a time: `*` matches any run of characters within a component,
+/// `?` any single one, and a `**` component any number of whole components.
+/// It protects everything below what it names too, as if `P/**` were listed
+/// beside `P`: protecting `~/.ssh` would mean little if the keys inside it
+/// could be deleted one by one.
+/// Compiled when the config loads, so a malformed entry fails the load rather
+/// than surfacing on the first delete.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+pub struct ProtectedPaths(Vec<Glob>);
+
+#[derive(Clone, Debug)]
+struct Glob {
+    /// As written in the config, for the refusal message: the expanded form
+    /// would show a home directory the user never typed.
+    source: String,
+    /// The expanded pattern's components below the root.
+    components: Vec<String>,
+}
+
+impl ProtectedPaths {
+    /// Compiles `globs`, expanding a leading `~` to `home`.
+    pub fn new(globs: &[String], home: Option<&Path>) -> Result<Self> {
+        globs
+            .iter()
+            .map(|glob| Glob::new(glob, home))
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    /// The pattern that protects `path`, if any.
+    ///
+    /// `recursive` is for an operation that reaches below `path` too, a
+    /// directory delete or move: it is also refused for a directory that
+    /// contains a protected entry, or `rm` on `~` would take `~/.ssh` with it.
+    /// Only the literal leading components of a pattern count for that, since
+    /// finding out whether `**/.git` matches anything below a directory would
+    /// take a walk of it.
+    ///
+    /// The last component is matched as named rather than resolved, so
+    /// deleting a symlink to a protected directory, which only unlinks it, is
+    /// let through. Its parents are resolved, so a symlinked directory higher
+    /// up cannot disguise the entry.
+    pub fn protecting(&self, path: &Path, recursive: bool) -> Option<&str> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let path = resolve_parent(path);
+        let components = path_components(&path);
+        self.0
+            .iter()
+            .find(|glob| glob.matches(&components) || (recursive && glob.is_below(&components)))
+            .map(|glob| glob.source.as_str())
+    }
+
+    /// Refuses `operation` on `path` when a pattern protects it, with a message
+    /// naming the pattern so the user knows which entry to edit.
+    pub fn check(&self, operation: &str, path: &Path, recursive: bool) -> Result<()> {
+        match self.protecting(path, recursive) {
+            None => Ok(()),
+            Some(glob) => Err(anyhow!(
+                "Cannot {operation} {}: it is protected by {glob:?} in file_system.protected_paths",
+                crate::file_system::path_info::compact(path)
+            )),
+        }
+    }
+}
+
+impl TryFrom<Vec<String>> for ProtectedPaths {
+    type Error = anyhow::Error;
+
+    fn try_from(globs: Vec<String>) -> Result<Self> {
+        let base_dirs = directories::BaseDirs::new();
+        Self::new(
+            &globs,
+            base_dirs.as_ref().map(directories::BaseDirs::home_dir),
+        )
+    }
+}
+
+impl Glob {
+    fn new(source: &str, home: Option<&Path>) -> Result<Self> {
+        let expanded = match source.strip_prefix('~') {
+            // `~user` is not expanded: it would need a passwd lookup for a
+            // form nobody is likely to write here, and read as a relative
+            // path it could never match.
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                let Some(home) = home else {
+                    return Err(anyhow!(
+                        "file_system.protected_paths: cannot expand {source:?}: no home directory"
+                    ));
+                };
+                home.join(rest.trim_start_matches('/'))
+            }
+            _ => PathBuf::from(source),
+        };
+        if !expanded.is_absolute() {
+            return Err(anyhow!(
+                "file_system.protected_paths: {source:?} must be an absolute path or start with ~/"
+            ));
+        }
+        let components = path_components(&expanded)
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect();
+        Ok(Self {
+            source: source.to_string(),
+            components,
+        })
+    }
+
+    /// Whether `path` is what the pattern names, or anything below it.
+    fn matches(&self, path: &[Cow<'_, str>]) -> bool {
+        (0..=path.len()).any(|len| matches_components(&self.components, &path[..len]))
+    }
+
+    /// Whether `path` is a directory the pattern's literal prefix runs through,
+    /// so that something it protects may be inside it.
+    fn is_below(&self, path: &[Cow<'_, str>]) -> bool {
+        let literal = self
+            .components
+            .iter()
+            .take_while(|component| !is_wildcard(component));
+        path.len() <= literal.clone().count()
+            && literal.zip(path).all(|(pattern, name)| pattern == name)
+    }
+}
+
+fn is_wildcard(component: &str) -> bool {
+    component.contains(['*', '?'])
+}
+
+/// `path`'s components below the root, with `.` and `..` collapsed. Lossy: a
+/// pattern comes from a UTF-8 config file, so a non-UTF-8 name could only be
+/// matched by a wildcard anyway.
+fn path_components(path: &Path) -> Vec<Cow<'_, str>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_string_lossy()),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    components
+}
+
+/// `path` absolute, with its parent directory resolved and its own name
+/// rejoined as is.
+fn resolve_parent(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .unwrap_or_else(|_| parent.to_path_buf())
+            .join(name),
+        _ => absolute,
+    }
+}
+
+fn matches_components(pattern: &[String], path: &[Cow<'_, str>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| matches_components(rest, &path[skip..]))
+        }
+        Some((first, rest)) => path.split_first().is_some_and(|(name, path)| {
+            matches_component(first, name) && matches_components(rest, path)
+        }),
+    }
+}
+
+/// Matches one name against one pattern component, `*` and `?` included.
+fn matches_component(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // The classic greedy match with one backtrack point: the last `*` seen,
+    // and where in `name` it was last tried up to.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn protected(globs: &[&str]) -> ProtectedPaths {
+        let globs: Vec<String> = globs.iter().map(ToString::to_string).collect();
+        ProtectedPaths::new(&globs, Some(Path::new("/home/me"))).unwrap()
+    }
+
+    #[test_case("/etc", "/etc", true ; "literal")]
+    #[test_case("/etc", "/etc/passwd", true ; "a literal reaches inside")]
+    #[test_case("/etc", "/etcetera", false ; "a literal is matched whole")]
+    #[test_case("~/.ssh", "/home/me/.ssh/id_rsa", true ; "what a directory holds is protected with it")]
+    #[test_case("/srv/*/data", "/srv/app/data/db/file", true ; "a wildcard pattern reaches inside too")]
+    #[test_case("/etc/**", "/etc/ssh/sshd_config", true ; "double star reaches inside")]
+    #[test_case("/srv/*/data", "/srv/app/data", true ; "star within a component")]
+    #[test_case("/srv/*/data", "/srv/a/b/data", false ; "star stays within one component")]
+    #[test_case("/srv/app?", "/srv/app1", true ; "question mark")]
+    #[test_case("/**/.git", "/home/me/project/.git", true ; "double star anywhere")]
+    #[test_case("~/.ssh", "/home/me/.ssh", true ; "tilde expands to home")]
+    #[test_case("~", "/home/me", true ; "bare tilde")]
+    #[test_case("/srv/*.db", "/srv/app.db.bak", false ; "star must reach the end")]
+    fn a_pattern_matches_by_component(glob: &str, path: &str, expected: bool) {
+        assert_eq!(
+            expected,
+            protected(&[glob])
+                .protecting(Path::new(path), false)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn a_recursive_operation_is_refused_above_a_protected_entry() {
+        let paths = protected(&["~/.ssh"]);
+
+        // Deleting the home directory would take `~/.ssh` with it.
+        assert_eq!(
+            Some("~/.ssh"),
+            paths.protecting(Path::new("/home/me"), true)
+        );
+        // Changing the home directory's own mode does not reach inside it.
+        assert_eq!(None, paths.protecting(Path::new("/home/me"), false));
+        // A sibling holds nothing protected.
+        assert_eq!(None, paths.protecting(Path::new("/home/me/src"), true));
+    }
+
+    #[test]
+    fn only_the_literal_prefix_protects_the_directories_above_a_pattern() {
+        let paths = protected(&["/srv/*/data"]);
+
+        assert!(paths.protecting(Path::new("/srv"), true).is_some());
+        // Whether `/srv/app` holds a `data` is not looked up: the directories
+        // the wildcard stands for would each need a walk.
+        assert!(paths.protecting(Path::new("/srv/app"), true).is_none());
+    }
+
+    #[test]
+    fn an_empty_list_protects_nothing() {
+        assert_eq!(None, protected(&[]).protecting(Path::new("/"), true));
+    }
+
+    #[test_case("etc" ; "relative")]
+    #[test_case("~root/.ssh" ; "another user's home")]
+    fn a_pattern_that_is_not_absolute_is_rejected(glob: &str) {
+        let error = ProtectedPaths::new(&[glob.to_string()], Some(Path::new("/home/me")))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains(glob),
+            "error should name the pattern: {error}"
+        );
+    }
+
+    #[test]
+    fn the_refusal_names_the_pattern_as_written() {
+        let error = protected(&["~/.ssh"])
+            .check("delete", Path::new("/home/me/.ssh"), true)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"~/.ssh\""), "{error}");
+    }
+}
@@ -305,6 +305,13 @@ impl Table {
     style_getter!(selected);
 }
 
+style_struct!(Diff {
+    base,
+    added,
+    hunk,
+    removed,
+});
+
 style_struct!(Help {
     base,
     actions,
@@ -319,6 +326,24 @@ style_struct!(OpenWith {
     shortcut,
 });
 
+style_struct!(Permissions {
+    base,
+    changed,
+    header,
+    selected,
+});
+
+style_struct!(Preview {
+    base,
+    comment,
+    keyword,
+    marker,
+    number,
+    string,
+});
+
+style_struct!(Tabs { base, active });
+
 #[derive(Deserialize)]
 pub struct Theme {
     #[serde(flatten)]
@@ -327,16 +352,20 @@ pub struct Theme {
     pub alert: Alert,
     pub breadcrumbs: Breadcrumbs,
     pub clipboard: Clipboard,
+    pub diff: Diff,
     pub file_modified_date: FileModifiedDate,
     pub file_size: FileSize,
     pub file_type: FileType,
     pub help: Help,
     pub notice: Notice,
     pub open_with: OpenWith,
+    pub permissions: Permissions,
+    pub preview: Preview,
     pub prompt: Prompt,
     pub scrollbar: ScrollbarConfig,
     pub status: Status,
     pub table: Table,
+    pub tabs: Tabs,
 }
 
 impl Theme {
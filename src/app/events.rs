@@ -3,7 +3,7 @@ use std::{
     os::fd::{AsFd, BorrowedFd, IntoRawFd, OwnedFd},
     panic::{self, AssertUnwindSafe},
     sync::{
-        OnceLock,
+        Condvar, Mutex, OnceLock, PoisonError,
         atomic::{AtomicBool, AtomicI32, Ordering},
         mpsc::{Receiver, Sender},
     },
@@ -17,8 +17,10 @@ use ratatui::crossterm::event::{Event, poll, read};
 use crate::command::Command;
 
 // Signal handling for graceful shutdown on SIGTERM / SIGINT / SIGHUP.
-// Keyboard Ctrl+C never reaches this path: raw mode disables ISIG, so
-// only an externally sent SIGINT does.
+// Keyboard Ctrl+C never reaches this path while the app draws: raw mode
+// disables ISIG, so only an externally sent SIGINT does. A program run in the
+// foreground (see `Command::RunInForeground`) has the terminal out of raw mode,
+// and its Ctrl+C is meant for it alone; see `leaving_interrupts_to_child`.
 //
 // Without a handler, kill(1) terminates the process instantly, leaving the
 // terminal in raw mode with the alternate screen active (broken shell).
@@ -65,6 +67,66 @@ static SIGNAL_PIPE_READ: OnceLock<OwnedFd> = OnceLock::new();
 /// must tolerate: a signal can arrive between `sigaction` and the store.
 static SIGNAL_PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
 
+/// Set while a program runs in the foreground. It shares this process group, so
+/// the kernel delivers its Ctrl+C here as well, where it must not quit the app.
+static CHILD_IN_FOREGROUND: AtomicBool = AtomicBool::new(false);
+
+/// Keys the main loop has finished handling, which the reader waits on before
+/// polling for more (see `event_loop`).
+static KEY_GATE: KeyGate = KeyGate::new();
+
+/// Holds the reader back until the main loop has handled every key it sent.
+///
+/// A key can hand the terminal to a program run in the foreground, and a reader
+/// already polling again would take that program's input for itself. Only keys
+/// wait: nothing else starts such a program, and mouse and resize events keep
+/// arriving in batches the main loop draws once.
+struct KeyGate {
+    handled: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl KeyGate {
+    const fn new() -> Self {
+        Self {
+            handled: Mutex::new(0),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Blocks until `sent` keys, counted since startup, have been handled.
+    fn wait_for(&self, sent: u64) {
+        let handled = self.handled.lock().unwrap_or_else(PoisonError::into_inner);
+        let _handled = self
+            .changed
+            .wait_while(handled, |handled| *handled < sent)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+
+    fn handled(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        *self.handled.lock().unwrap_or_else(PoisonError::into_inner) += count as u64;
+        self.changed.notify_all();
+    }
+}
+
+/// Tells the reader the main loop is done with `count` more keys, including any
+/// program one of them ran in the foreground.
+pub(super) fn handled_keys(count: usize) {
+    KEY_GATE.handled(count);
+}
+
+/// Runs `run`, a program in the foreground, with a Ctrl+C typed into it left to
+/// it rather than quitting the app, as a shell does for the command it waits on.
+pub(super) fn leaving_interrupts_to_child<T>(run: impl FnOnce() -> T) -> T {
+    CHILD_IN_FOREGROUND.store(true, Ordering::Relaxed);
+    let result = run();
+    CHILD_IN_FOREGROUND.store(false, Ordering::Relaxed);
+    result
+}
+
 /// Terminal input, abstracted so `event_loop` can be driven by fakes in tests.
 trait EventSource {
     fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
@@ -86,7 +148,10 @@ impl EventSource for TerminalEventSource {
 
 // SAFETY: Stores to an AtomicBool are single-instruction writes to a
 // fixed address, async-signal-safe per POSIX, as is `write(2)`.
-extern "C" fn handle_signal(_: i32) {
+extern "C" fn handle_signal(signal: i32) {
+    if signal == nix::libc::SIGINT && CHILD_IN_FOREGROUND.load(Ordering::Relaxed) {
+        return;
+    }
     SIGNAL_RECEIVED.store(true, Ordering::Relaxed);
 
     let fd = SIGNAL_PIPE_WRITE_FD.load(Ordering::Relaxed);
@@ -243,7 +308,12 @@ pub(super) fn spawn_command_sender(tx: &Sender<Command>) {
         // Without catch_unwind a panic kills only this thread, silently,
         // leaving the main loop blocked on rx.recv() forever.
         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-            event_loop(&reader_tx, poll_interval, &mut TerminalEventSource);
+            event_loop(
+                &reader_tx,
+                poll_interval,
+                &mut TerminalEventSource,
+                &KEY_GATE,
+            );
         }));
         if let Err(payload) = result {
             let message = panic_message(payload.as_ref());
@@ -261,7 +331,13 @@ pub(super) fn spawn_command_sender(tx: &Sender<Command>) {
     }
 }
 
-fn event_loop<S: EventSource>(tx: &Sender<Command>, poll_interval: Duration, source: &mut S) {
+fn event_loop<S: EventSource>(
+    tx: &Sender<Command>,
+    poll_interval: Duration,
+    source: &mut S,
+    gate: &KeyGate,
+) {
+    let mut keys_sent = 0;
     loop {
         // Bounds the window between a signal arriving and this thread noticing
         // by the poll timeout. The watcher normally quits first; this answers a
@@ -307,11 +383,16 @@ fn event_loop<S: EventSource>(tx: &Sender<Command>, poll_interval: Duration, sou
         };
 
         if let Some(command) = Command::maybe_from(&event) {
+            let is_key = matches!(command, Command::Key(_, _));
             // A dropped receiver means App is shutting down. Exit cleanly
             // rather than panicking on a late keystroke during teardown.
             if tx.send(command).is_err() {
                 return;
             }
+            if is_key {
+                keys_sent += 1;
+                gate.wait_for(keys_sent);
+            }
         }
     }
 }
@@ -330,16 +411,18 @@ fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
 mod tests {
     use std::{collections::VecDeque, sync::mpsc, time::Duration};
 
-    use ratatui::crossterm::event::Event;
+    use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 
     use std::os::fd::AsFd;
 
-    use super::{Command, EventSource, event_loop, panic_message, watch_signal_pipe};
+    use super::{Command, EventSource, KeyGate, event_loop, panic_message, watch_signal_pipe};
 
     const INTERVAL: Duration = Duration::from_millis(500);
     // Long enough that the watcher is blocked in `read` before the byte is
     // written, short enough not to slow the suite down.
     const WRITE_DELAY: Duration = Duration::from_millis(5);
+    // How long a held-back key must stay unsent to count as held back.
+    const GATE_WAIT: Duration = Duration::from_millis(50);
 
     /// Scripted input. Exhausting the poll script ends `event_loop` through its
     /// production error path, which keeps the tests independent of the global
@@ -382,7 +465,7 @@ mod tests {
         let (tx, rx) = mpsc::channel();
         let mut source = FakeEventSource::new((0..10).map(|_| Ok(false)).collect());
 
-        event_loop(&tx, INTERVAL, &mut source);
+        event_loop(&tx, INTERVAL, &mut source, &KeyGate::new());
 
         // The whole script ran, so the only Quit came from its exhaustion.
         assert!(source.polls.is_empty());
@@ -397,7 +480,7 @@ mod tests {
             std::io::ErrorKind::UnexpectedEof,
         ))]);
 
-        event_loop(&tx, INTERVAL, &mut source);
+        event_loop(&tx, INTERVAL, &mut source, &KeyGate::new());
 
         assert_eq!(Some(Command::Quit), rx.try_recv().ok());
     }
@@ -407,7 +490,7 @@ mod tests {
         let (tx, rx) = mpsc::channel();
         let mut source = FakeEventSource::new(vec![Ok(true)]);
 
-        event_loop(&tx, INTERVAL, &mut source);
+        event_loop(&tx, INTERVAL, &mut source, &KeyGate::new());
 
         assert_eq!(Some(Command::Quit), rx.try_recv().ok());
     }
@@ -418,7 +501,7 @@ mod tests {
         let mut source =
             FakeEventSource::new(vec![Ok(true)]).with_events(vec![Event::Resize(10, 20)]);
 
-        event_loop(&tx, INTERVAL, &mut source);
+        event_loop(&tx, INTERVAL, &mut source, &KeyGate::new());
 
         assert_eq!(
             Some(Command::Resize {
@@ -429,6 +512,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn a_key_is_handled_before_the_next_is_read() {
+        let (tx, rx) = mpsc::channel();
+        let gate = KeyGate::new();
+        let key = |c| Event::Key(KeyEvent::from(KeyCode::Char(c)));
+        let mut source =
+            FakeEventSource::new(vec![Ok(true), Ok(true)]).with_events(vec![key('a'), key('b')]);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| event_loop(&tx, INTERVAL, &mut source, &gate));
+
+            let first = rx.recv_timeout(INTERVAL);
+            assert_eq!(
+                Ok(Command::Key(KeyCode::Char('a'), KeyModifiers::NONE)),
+                first
+            );
+            // Whatever `a` does with the terminal, `b` stays unread until then.
+            assert!(rx.recv_timeout(GATE_WAIT).is_err());
+
+            gate.handled(1);
+            let second = rx.recv_timeout(INTERVAL);
+            assert_eq!(
+                Ok(Command::Key(KeyCode::Char('b'), KeyModifiers::NONE)),
+                second
+            );
+            gate.handled(1);
+        });
+    }
+
     #[test]
     fn a_byte_already_written_quits_without_waiting() {
         let (tx, rx) = mpsc::channel();
@@ -62,6 +62,17 @@ fn restore_terminal() {
     let _ = disable_raw_mode();
 }
 
+/// Everything `try_new` sets up once raw mode is on, shared with `suspended` so
+/// taking the terminal back cannot drift from taking it the first time.
+fn enter_alternate_screen(stdout: &mut Stdout) -> Result<()> {
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES),
+    )
+}
+
 /// A terminal wrapper that restores the terminal state on drop.
 ///
 /// Two cleanup paths, each covering what the other cannot: `Drop` runs on normal
@@ -97,12 +108,7 @@ impl CleanupOnDropTerminal {
         // never fires, so an early `?` would leave the shell in raw mode.
         let build = || -> Result<Self> {
             let mut stdout = stdout();
-            execute!(
-                stdout,
-                EnterAlternateScreen,
-                EnableMouseCapture,
-                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES),
-            )?;
+            enter_alternate_screen(&mut stdout)?;
 
             let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
             terminal.hide_cursor()?;
@@ -112,6 +118,23 @@ impl CleanupOnDropTerminal {
         build().inspect_err(|_| restore_terminal_once())
     }
 
+    /// Gives the terminal back for `run`, a program that draws on it itself, as
+    /// `Drop` would, then takes it again as `try_new` did and clears it, so the
+    /// next draw repaints the whole screen over whatever the program left.
+    pub fn suspended<T>(&mut self, run: impl FnOnce() -> T) -> Result<T> {
+        restore_terminal_once();
+        let result = run();
+
+        enable_raw_mode()?;
+        // Armed again as soon as there is something to restore, so a failure
+        // below still leaves `Drop` to undo it.
+        TERMINAL_RESTORED.store(false, Ordering::SeqCst);
+        enter_alternate_screen(&mut stdout())?;
+        self.terminal.hide_cursor()?;
+        self.terminal.clear()?;
+        Ok(result)
+    }
+
     fn cleanup() {
         restore_terminal_once();
     }
@@ -16,7 +16,18 @@ use self::progress::Task;
 #[cfg(test)]
 use self::result::CommandResult;
 use crate::app::clipboard::ClipboardEntry;
-use crate::file_system::path_info::PathInfo;
+use crate::file_system::{
+    checksums::{ChecksumAlgorithm, ChecksumReport},
+    compare::CompareEntry,
+    diff::FileDiff,
+    disk_usage::Usage,
+    duplicates::{DuplicateFile, DuplicateProgress, DuplicateSet},
+    path_info::PathInfo,
+    peek::PeekColumn,
+    preview::FilePreview,
+    sftp::Address,
+    summary::TreeSummary,
+};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum InputMode {
@@ -42,6 +53,22 @@ pub enum PromptAction {
     Chmod {
         paths: Vec<PathInfo>,
         mode: String,
+        recursive: bool,
+    },
+    Chown {
+        paths: Vec<PathInfo>,
+        owner: String,
+        recursive: bool,
+    },
+    Touch {
+        paths: Vec<PathInfo>,
+        recursive: bool,
+    },
+    /// `name` is the pre-filled archive name, whose extension picks the
+    /// format.
+    Compress {
+        paths: Vec<PathInfo>,
+        name: String,
     },
     AddBookmark {
         directory: PathInfo,
@@ -49,6 +76,12 @@ pub enum PromptAction {
     },
     #[default]
     CreateDirectory,
+    CreateFile,
+    /// `name` is the pre-filled name of the copy: the template's own.
+    CreateFromTemplate {
+        template: PathInfo,
+        name: String,
+    },
     Delete(usize),
     Filter(String),
     Goto {
@@ -59,6 +92,13 @@ pub enum PromptAction {
         name: String,
     },
     Search(String),
+    /// Copying or moving `paths` to another directory, pre-filled with
+    /// `destination`: the other pane's.
+    CopyTo {
+        paths: Vec<PathInfo>,
+        destination: PathInfo,
+        is_move: bool,
+    },
     /// A paste found `name` already present in the destination directory.
     /// `can_overwrite` is false when the existing entry is a directory, which
     /// is never replaced, so the prompt offers only the skip choices.
@@ -79,6 +119,15 @@ impl PromptAction {
     }
 }
 
+/// The times a touch sets: typed into the prompt, or copied from a reference
+/// entry as `touch -r` does.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum TouchTime {
+    /// `now`, a date, a date and time, or a time today.
+    Typed(String),
+    Like(PathInfo),
+}
+
 /// How a paste resolves a destination that already exists. The `*All` variants
 /// answer for the rest of the batch as well as for the collision in front of
 /// the user, so a paste of many sources need not be answered many times.
@@ -111,6 +160,10 @@ pub enum Command {
         width: u16,
         height: u16,
     },
+    // Result: wakes the event loop to redraw what animates with the clock: the
+    // search-loading indicator, and the spinners of directories whose sizes
+    // are still being counted. Sent by each of them while it runs.
+    Tick,
 
     // External commands, handled by FileSystem (shell out via open_in)
     OpenCurrentDirectory,
@@ -128,7 +181,100 @@ pub enum Command {
     GoToParentDirectory, // Intent: resolved by FileSystem into NavigatedDirectory
     GoToPreviousDirectory, // Intent: resolved by FileSystem into NavigatedDirectory
     Open(PathInfo),      // Intent: FileSystem -> NavigatedDirectory (dir) or external open (file)
+    // Intent: Goto named a directory on another host; FileSystem connects to
+    // it in the background and resolves into Open.
+    OpenRemote(Address),
     OpenWithPrompt(PathInfo), // Intent: RootView shows the "open with" picker for this path
+    // Intent: RootView shows the permission editor for these paths, which
+    // resolves into a symbolic Chmod.
+    OpenPermissionEditor(Vec<PathInfo>),
+    // Intent: RootView shows the template picker, which resolves into the
+    // CreateFromTemplate prompt.
+    OpenTemplatePicker,
+    // Intent: RootView shows the disk usage view for this directory, which
+    // asks for its scan.
+    OpenDiskUsage(PathInfo),
+    // Intent: FileSystem scans the tree at the path in the background,
+    // streaming DiskUsageProgress and ending in DiskUsageScanned. Emitted by
+    // DiskUsageView when it opens, and again for an entry a delete left
+    // behind. Stopped by CancelDiskUsage.
+    ScanDiskUsage(PathBuf),
+    DiskUsageProgress {
+        // Result: of ScanDiskUsage, what it has counted so far.
+        entries: u64,
+        bytes: u64,
+    },
+    DiskUsageScanned(Usage), // Result: of ScanDiskUsage; shown by DiskUsageView
+    CancelDiskUsage,         // Intent: the disk usage view closed, so its scans stop
+    // Intent: RootView shows the duplicate finder for this directory, which
+    // asks for its scan.
+    OpenDuplicates(PathInfo),
+    // Intent: FileSystem looks for identical files in the tree at the path in
+    // the background, streaming DuplicatesProgress and ending in
+    // DuplicatesFound. Stopped by CancelDuplicates.
+    FindDuplicates(PathBuf),
+    DuplicatesProgress(DuplicateProgress), // Result: of FindDuplicates, how far it has got
+    DuplicatesFound(Vec<DuplicateSet>),    // Result: of FindDuplicates; shown by DuplicatesView
+    CancelDuplicates,                      // Intent: the duplicate finder closed, so its scans stop
+    // Intent: replaces the second file of each pair with a hard link to the
+    // first. FileSystem answers with DuplicatesLinked.
+    HardLinkDuplicates(Vec<(DuplicateFile, DuplicateFile)>),
+    DuplicatesLinked(Vec<PathBuf>), // Result: of HardLinkDuplicates, the files now links
+    // Intent: RootView shows the comparison of the two directories, which
+    // asks for it.
+    OpenCompare {
+        left: PathInfo,
+        right: PathInfo,
+    },
+    // Intent: FileSystem compares the trees at the two paths in the
+    // background, byte for byte as well when `contents` is set, streaming
+    // CompareProgress and ending in Compared. Stopped by CancelCompare.
+    CompareDirectories {
+        left: PathBuf,
+        right: PathBuf,
+        contents: bool,
+    },
+    CompareProgress {
+        // Result: of CompareDirectories, the entries compared so far.
+        entries: u64,
+    },
+    Compared(Vec<CompareEntry>), // Result: of CompareDirectories; shown by CompareView
+    CancelCompare,               // Intent: the comparison closed, so its walks stop
+    // Intent: FileSystem copies each source over its destination, in one task
+    // named after the two directories compared.
+    CopyCompared {
+        left: PathBuf,
+        right: PathBuf,
+        copies: Vec<(PathBuf, PathBuf)>,
+    },
+    // Intent: FileSystem makes `destination` match `source`, deleting the
+    // removals and then copying each source over its destination, in one
+    // task named after the two.
+    MirrorCompared {
+        source: PathBuf,
+        destination: PathBuf,
+        copies: Vec<(PathBuf, PathBuf)>,
+        removals: Vec<PathBuf>,
+    },
+    // Intent: FileSystem computes the checksums of the files in one task,
+    // answering with Checksummed. `directory` is the one they were marked in.
+    ComputeChecksums {
+        paths: Vec<PathBuf>,
+        directory: PathBuf,
+        algorithm: ChecksumAlgorithm,
+    },
+    // Intent: FileSystem checks the files the checksum file lists against
+    // it, in one task, answering with Checksummed.
+    VerifyChecksums(PathBuf),
+    Checksummed(ChecksumReport), // Result: of ComputeChecksums / VerifyChecksums; shown by ChecksumView
+    // Intent: FileSystem diffs the two text files, from `left` to `right`,
+    // answering with Diffed, or with an alert when they are identical or
+    // cannot be diffed.
+    DiffFiles {
+        left: PathBuf,
+        right: PathBuf,
+    },
+    Diffed(FileDiff), // Result: of DiffFiles; shown by DiffView
     NavigatedDirectory {
         // Result: of GoToParentDirectory / GoToPreviousDirectory / Open (emitted by FileSystem).
         // The entries are not included; they stream in afterward as ListingBatch.
@@ -136,6 +282,45 @@ pub enum Command {
         generation: u64,
     },
     RefreshDirectory, // Intent: resolved by FileSystem into RefreshedDirectory
+    // Intent: focus moved to the other pane, or to another tab, which shows
+    // `directory` filtered by `filter`, with `mark_count` entries marked.
+    // FileSystem reads it again into RefreshedDirectory, which keeps the
+    // pane's filter, marks and selection, and takes up `previous_directory`
+    // for going back; NoticesView takes up the pane's filter and count. It
+    // ends any search, and leaves the views in the directory listing: a pane
+    // that showed a search or the bookmarks follows it with StartSearch or
+    // GetBookmarks.
+    SwitchedPane {
+        directory: PathInfo,
+        filter: String,
+        mark_count: usize,
+        previous_directory: Option<PathInfo>,
+    },
+    // Intent: FileSystem lists, off the UI thread, what a Miller column shows
+    // for `path`: its parent's entries or its own. Answered with Peeked.
+    Peek {
+        path: PathInfo,
+        column: PeekColumn,
+    },
+    // Result: of Peek, for the entry at `path`; shown by MillerView, which
+    // drops it when the cursor or the listing has moved on.
+    Peeked {
+        path: PathBuf,
+        column: PeekColumn,
+        entries: Result<Vec<PathInfo>, String>,
+    },
+    // Intent: FileSystem reads, off the UI thread, the start of the file the
+    // preview shows. Answered with Previewed.
+    PreviewFile(PathInfo),
+    // Result: of PreviewFile, for the file at `path`; shown by PreviewView,
+    // which drops it when the cursor has moved on.
+    Previewed {
+        path: PathBuf,
+        preview: Result<FilePreview, String>,
+    },
+    NewTab,           // Intent: RootView opens a tab on the current directory
+    CloseTab,         // Intent: RootView closes the active tab
+    SwitchTab(usize), // Intent: RootView activates the tab at this index
     RefreshedDirectory {
         // Result: of RefreshDirectory. Entries stream in as ListingBatch.
         directory: PathInfo,
@@ -154,11 +339,64 @@ pub enum Command {
         // Result: the streamed listing finished; TableView sorts and restores selection.
         generation: u64,
     },
+    // Intent: emitted by TableView once a listing completes with directory
+    // sizes on; FileSystem counts what is under each directory in the
+    // background and streams DirectorySizes. An empty list stops the count and
+    // forgets every size counted so far.
+    SizeDirectories {
+        directories: Vec<PathInfo>,
+        generation: u64,
+    },
+    // Result: of SizeDirectories, recursive sizes in bytes as they complete.
+    // `generation` is the listing's, so sizes for a superseded one are ignored.
+    DirectorySizes {
+        sizes: Vec<(PathBuf, u64)>,
+        generation: u64,
+    },
 
     // File operations
     Chmod {
+        // Intent: `mode` is octal or symbolic. With `recursive`, it may be a
+        // directory mode and a file mode, and FileSystem runs it as a task.
         paths: Vec<PathInfo>,
         mode: String,
+        recursive: bool,
+    },
+    Chown {
+        // Intent: `owner` is `user`, `user:group` or `:group`, by name or id.
+        // With `recursive`, FileSystem runs it as a task.
+        paths: Vec<PathInfo>,
+        owner: String,
+        recursive: bool,
+    },
+    Touch {
+        // Intent: sets the access and modification times of `paths`. With
+        // `recursive`, FileSystem runs it as a task.
+        paths: Vec<PathInfo>,
+        time: TouchTime,
+        recursive: bool,
+    },
+    Compress {
+        // Intent: packs `paths` into a new archive `name` in the current
+        // directory, in the format its extension names. FileSystem runs it as
+        // a task.
+        paths: Vec<PathInfo>,
+        name: String,
+    },
+    Extract {
+        // Intent: unpacks `archive` into a new directory named after it, or
+        // with `here` into the current directory. Names already taken get the
+        // conflict prompt first; FileSystem then runs it as a task.
+        archive: PathInfo,
+        here: bool,
+    },
+    // Result: the names an `Extract` with `here` would create directly in
+    // `destination`, read from the archive off the UI thread. FileSystem asks
+    // about those already taken, then starts the task.
+    ArchiveSurveyed {
+        archive: PathBuf,
+        destination: PathBuf,
+        names: Vec<OsString>,
     },
     Copy {
         srcs: Vec<PathInfo>,
@@ -174,8 +412,25 @@ pub enum Command {
     // next task, the next prompt, or the clipboard follow-up.
     ResolveConflict(ConflictChoice),
     CreateDirectory(String),
+    CreateFile(String),
+    // Result: an entry of the current directory that a create just made (for a
+    // nested path, its topmost new directory). TableView selects it once the
+    // refresh that follows lists it.
+    Created(PathBuf),
+    // Intent: copies `template` into the current directory as `name`. When the
+    // name is taken, FileSystem opens the conflict prompt and holds the copy
+    // until it is answered.
+    CreateFromTemplate {
+        template: PathInfo,
+        name: String,
+    },
     ConfirmDelete, // Intent: resolved by TableView into Delete
     Delete(Vec<PathInfo>),
+    // Intent: emitted by TableView alongside the delete prompt; FileSystem
+    // walks the paths in the background and streams DeleteSummary. Stopped
+    // when the prompt is answered or dismissed.
+    SummarizeDelete(Vec<PathInfo>),
+    DeleteSummary(TreeSummary), // Result: of SummarizeDelete; shown by PromptView
     Rename {
         path: PathInfo,
         name: String,
@@ -213,7 +468,6 @@ pub enum Command {
     SearchStarted {
         generation: u64,
     }, // Result: FileSystem spawned the search thread
-    SearchTick,
     StartSearch(String), // Intent: spawns the search thread; streams ListingBatch
 
     // View state notifications, emitted by TableView
@@ -237,6 +491,9 @@ pub enum Command {
     Progress(Task), // Result: progress update for the running task
 
     // Global
+    // Intent: hands the terminal to `sh -c` running this command line until it
+    // exits. Stays unclaimed, like Quit, for App::run to carry out.
+    RunInForeground(OsString),
     Quit,
 }
 
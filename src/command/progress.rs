@@ -10,7 +10,7 @@ use std::{
 
 use super::Command;
 
-/// Shared fields for copy and move operations.
+/// Shared fields for copy, move and extract operations.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Transfer {
     pub source: String,
@@ -23,7 +23,50 @@ pub struct Transfer {
 pub enum TaskKind {
     Copy(Transfer),
     Move(Transfer),
-    Delete { path: String },
+    Delete {
+        path: String,
+    },
+    /// A recursive chmod; the non-recursive one is immediate and has no task.
+    Chmod {
+        path: String,
+    },
+    /// A recursive chown; like chmod, the non-recursive one has no task.
+    Chown {
+        path: String,
+    },
+    /// A recursive touch; like chmod, the non-recursive one has no task.
+    Touch {
+        path: String,
+    },
+    /// Packing entries into a new archive; `path` is the archive's. Counts
+    /// bytes read, as a copy does.
+    Compress {
+        path: String,
+    },
+    /// Unpacking an archive, the source, into a directory, the destination.
+    /// Counts bytes of the archive read, since its unpacked size is not known
+    /// until it has all been read.
+    Extract(Transfer),
+    /// Copying entries both ways between two directories; `path` names both.
+    /// Counts bytes, as a copy does.
+    Sync {
+        path: String,
+    },
+    /// Making one directory match another; `path` names both. Counts bytes
+    /// copied, as a sync does.
+    Mirror {
+        path: String,
+    },
+    /// Computing the checksums of files; `path` names them. Counts bytes
+    /// read.
+    Checksum {
+        path: String,
+    },
+    /// Checking files against the checksum file at `path`. Counts bytes read,
+    /// as a checksum does.
+    Verify {
+        path: String,
+    },
 }
 
 impl TaskKind {
@@ -34,14 +77,32 @@ impl TaskKind {
             TaskKind::Copy(_) => "Copying ",
             TaskKind::Move(_) => "Moving ",
             TaskKind::Delete { .. } => "Deleting ",
+            TaskKind::Chmod { .. } => "Changing mode of ",
+            TaskKind::Chown { .. } => "Changing owner of ",
+            TaskKind::Touch { .. } => "Touching ",
+            TaskKind::Compress { .. } => "Compressing into ",
+            TaskKind::Extract(_) => "Extracting ",
+            TaskKind::Sync { .. } => "Syncing ",
+            TaskKind::Mirror { .. } => "Mirroring ",
+            TaskKind::Checksum { .. } => "Checksumming ",
+            TaskKind::Verify { .. } => "Verifying ",
         }
     }
 
-    /// The underlying transfer, for operations that have one (copy/move).
+    /// The underlying transfer, for operations that have one (copy, move and
+    /// extract).
     fn transfer(&self) -> Option<&Transfer> {
         match self {
-            TaskKind::Copy(t) | TaskKind::Move(t) => Some(t),
-            TaskKind::Delete { .. } => None,
+            TaskKind::Copy(t) | TaskKind::Move(t) | TaskKind::Extract(t) => Some(t),
+            TaskKind::Delete { .. }
+            | TaskKind::Chmod { .. }
+            | TaskKind::Chown { .. }
+            | TaskKind::Touch { .. }
+            | TaskKind::Compress { .. }
+            | TaskKind::Sync { .. }
+            | TaskKind::Mirror { .. }
+            | TaskKind::Checksum { .. }
+            | TaskKind::Verify { .. } => None,
         }
     }
 
@@ -64,14 +125,37 @@ impl TaskKind {
     }
 
     /// The target path shown in full by the operations notice: the
-    /// destination directory for copy/move, or the path being deleted.
+    /// destination directory for copy/move, the path being deleted or
+    /// chmodded, chowned or touched, or the archive being written.
     pub fn target(&self) -> String {
         match self {
-            TaskKind::Copy(t) | TaskKind::Move(t) => dest_display(&t.source, &t.destination),
-            TaskKind::Delete { path } => path.clone(),
+            TaskKind::Copy(t) | TaskKind::Move(t) | TaskKind::Extract(t) => {
+                dest_display(&t.source, &t.destination)
+            }
+            TaskKind::Delete { path }
+            | TaskKind::Chmod { path }
+            | TaskKind::Chown { path }
+            | TaskKind::Touch { path }
+            | TaskKind::Compress { path }
+            | TaskKind::Sync { path }
+            | TaskKind::Mirror { path }
+            | TaskKind::Checksum { path }
+            | TaskKind::Verify { path } => path.clone(),
         }
     }
 
+    /// The name a sync between `left` and `right` goes by, which is how the
+    /// view that started it knows it when it finishes.
+    pub fn sync_name(left: &Path, right: &Path) -> String {
+        format!("{} and {}", left.display(), right.display())
+    }
+
+    /// The name a mirror of `source` onto `destination` goes by, as for a
+    /// sync.
+    pub fn mirror_name(source: &Path, destination: &Path) -> String {
+        format!("{} onto {}", source.display(), destination.display())
+    }
+
     /// The path portion (source + target).
     pub fn detail(&self) -> String {
         match self.source() {
@@ -495,6 +579,27 @@ mod tests {
         assert_eq!("Copying ", copy("a", "b").prefix());
         assert_eq!("Moving ", r#move("a", "b").prefix());
         assert_eq!("Deleting ", TaskKind::Delete { path: "a".into() }.prefix());
+        assert_eq!(
+            "Changing mode of ",
+            TaskKind::Chmod { path: "a".into() }.prefix()
+        );
+        assert_eq!(
+            "Changing owner of ",
+            TaskKind::Chown { path: "a".into() }.prefix()
+        );
+        assert_eq!("Touching ", TaskKind::Touch { path: "a".into() }.prefix());
+        assert_eq!(
+            "Compressing into ",
+            TaskKind::Compress { path: "a".into() }.prefix()
+        );
+        assert_eq!(
+            "Extracting ",
+            TaskKind::Extract(Transfer {
+                source: "a.zip".into(),
+                destination: "a".into(),
+            })
+            .prefix()
+        );
     }
 
     #[test]
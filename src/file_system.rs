@@ -1,51 +1,89 @@
+pub mod archive;
+pub(crate) mod backend;
+pub mod braces;
+pub mod checksums;
+pub mod compare;
 mod conflicts;
 mod debounce;
+pub mod diff;
+pub mod disk_usage;
+pub mod duplicates;
 mod handler;
+pub mod highlight;
+mod mode;
 pub mod open_with;
 mod operations;
+pub mod owner;
 pub mod path_info;
+pub mod peek;
+pub mod preview;
 mod search;
+pub mod sftp;
 mod shell;
+mod sizes;
 mod stream;
+pub mod summary;
 mod tasks;
+pub mod templates;
+pub mod times;
 mod watch;
 
 use std::{
-    collections::{HashMap, VecDeque},
-    ffi::OsString,
+    collections::{BTreeSet, HashMap, VecDeque},
+    ffi::{OsStr, OsString},
     fmt::Display,
     fs,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
     path::{Path, PathBuf},
-    sync::{atomic::Ordering, mpsc::Sender},
+    sync::{Arc, atomic::Ordering, mpsc::Sender},
     thread,
     time::Duration,
 };
 
 use anyhow::{Result, anyhow};
+use chrono::Local;
 use log::warn;
 
 use self::{
+    archive::ArchiveFormat,
+    backend::Backend,
+    checksums::ChecksumAlgorithm,
     conflicts::Conflicts,
-    operations::{open_in, spawn_argv},
+    duplicates::DuplicateFile,
+    mode::{Mode, TreeModes},
+    operations::{diff_command, open_in, spawn_argv},
+    owner::Owner,
     path_info::{PathInfo, compact},
+    peek::PeekColumn,
     search::Limits,
-    tasks::{CancelInfo, TaskCommand},
+    sftp::Address,
+    tasks::{CancelInfo, ChecksumJob, Extraction, SyncPlan, TaskCommand},
+    times::Stamp,
     watch::DirectoryWatcher,
 };
 use crate::{
-    app::{clipboard::ClipboardEntry, config::Config},
+    app::{
+        clipboard::ClipboardEntry,
+        config::{Config, protected_paths::ProtectedPaths},
+    },
     command::{
-        Command, ConflictChoice, PromptAction,
+        Command, ConflictChoice, PromptAction, TouchTime,
         progress::{CancellationToken, Task},
         result::CommandResult,
     },
 };
 
-/// How often a running search wakes the event loop to redraw its loading
-/// indicator. Only a heartbeat: the position comes from elapsed time, so this
-/// bounds how coarse the motion gets over a stretch of the walk that finds
-/// nothing, and does nothing at all while batches are arriving to redraw.
-const SEARCH_TICK_INTERVAL: Duration = Duration::from_millis(150);
+/// How often a running search or size count wakes the event loop to redraw
+/// its indicator. Only a heartbeat: the position comes from elapsed time, so
+/// this bounds how coarse the motion gets over a stretch of the walk that
+/// sends nothing, and does nothing at all while batches are arriving to redraw.
+const TICK_INTERVAL: Duration = Duration::from_millis(150);
+
+/// How long a preview or a Miller column waits before reading, so that one
+/// the cursor has already moved on from is cancelled before it reads
+/// anything. Held down, a key repeats faster than this, and only the entry
+/// the cursor stops on is read: over SFTP every read queues on the session.
+const CURSOR_SETTLE_DELAY: Duration = Duration::from_millis(50);
 
 /// A cancellable in-flight action. File operations and searches share one list,
 /// in registration order; `cancel_target` decides which entry a cancel keypress
@@ -106,6 +144,28 @@ enum PasteStep {
     Run { overwrite: bool },
 }
 
+/// An extract holding off until the names it would take in its directory are
+/// answered for, asked about one at a time as a paste's sources are. It runs
+/// once the last one is answered. Held only while the conflict prompt is open.
+struct PendingExtract {
+    /// The task to run, its `replace` and `skip` filled in as answers arrive.
+    extraction: Extraction,
+    /// Where the names are looked up: the destination for an extract here,
+    /// its parent for one into a new directory, whose name is the one asked
+    /// about. Skipping that one leaves nothing to extract, as skipping every
+    /// name of an extract here does.
+    base: PathBuf,
+    /// Taken names not yet answered for. The one being asked about stays at the
+    /// front until the answer pops it.
+    remaining: VecDeque<(OsString, Occupant)>,
+    /// How many names there are in all, so skipping every one of them starts
+    /// nothing.
+    names: usize,
+    /// The standing `*All` answer. Not shared with anything: the task only
+    /// starts once every answer is in.
+    conflicts: Conflicts,
+}
+
 impl PendingPaste {
     /// What holds `src`'s destination name, counting names an earlier source in
     /// this same paste has already claimed. Replacing a claimed name is safe
@@ -190,6 +250,8 @@ fn step(standing: Option<ConflictChoice>, occupant: Option<Occupant>) -> PasteSt
 }
 
 pub struct FileSystem {
+    /// Where the current directory's entries are listed from and changed in.
+    backend: Arc<dyn Backend>,
     /// Directory holding the bookmark symlinks, resolved from the config once
     /// so bookmark reads do not depend on the process-global `Config`.
     bookmarks_dir: PathBuf,
@@ -203,6 +265,26 @@ pub struct FileSystem {
     /// that stops it. Cancelled when a new load starts so stale batches don't
     /// bleed across, and cleared when the load reports itself complete.
     current_load: Option<(u64, CancellationToken)>,
+    /// The walk counting what the open delete prompt would remove, stopped once
+    /// the prompt is answered or dismissed.
+    delete_summary: Option<CancellationToken>,
+    /// The walk counting the current listing's directory sizes, stopped when
+    /// another listing or search starts.
+    sizing: Option<CancellationToken>,
+    /// Directory sizes counted so far, kept across listings so coming back to
+    /// a directory answers at once.
+    size_cache: sizes::SizeCache,
+    /// The disk usage view's scans, stopped when it closes.
+    disk_usage_scans: Vec<CancellationToken>,
+    /// The duplicate finder's scans, stopped when it closes.
+    duplicate_scans: Vec<CancellationToken>,
+    /// The directory comparison's walks, stopped when it closes.
+    comparisons: Vec<CancellationToken>,
+    /// The read the preview waits on, stopped when the cursor moves on and
+    /// another is asked for.
+    preview: Option<CancellationToken>,
+    /// The listing each Miller column waits on, stopped like the preview's.
+    peeks: HashMap<PeekColumn, CancellationToken>,
     /// Set when a refresh arrives while a load is already streaming, so the
     /// load runs to completion and the refresh is re-issued afterwards.
     reload_pending: bool,
@@ -214,6 +296,7 @@ pub struct FileSystem {
     /// can ignore stale `ListingBatch`es. Shared by both stream kinds so a
     /// generation is never ambiguous between them.
     next_generation: u64,
+    diff_template: String,
     open_directory_template: String,
     open_file_template: String,
     open_filectrl_window_template: String,
@@ -221,6 +304,14 @@ pub struct FileSystem {
     /// asks: a worker resolves what it finds from the paste's standing answer
     /// or records it, so there is never a second prompt to route around.
     pending_paste: Option<PendingPaste>,
+    /// The template copy awaiting a conflict answer: the template and the name
+    /// it is to take in the current directory.
+    pending_template: Option<(PathInfo, String)>,
+    /// The extract awaiting a conflict answer.
+    pending_extract: Option<PendingExtract>,
+    /// Entries that deletes, moves, overwrites, renames, chmods, chowns and
+    /// touches refuse.
+    protected_paths: ProtectedPaths,
     search_max_depth: u32,
     search_max_results: u32,
     watcher: Option<DirectoryWatcher>,
@@ -228,15 +319,13 @@ pub struct FileSystem {
 
 impl FileSystem {
     pub fn new(config: &Config, command_tx: Sender<Command>) -> Self {
-        let watcher = DirectoryWatcher::try_new(config.file_system.refresh_debounce_milliseconds)
-            .inspect_err(|e| {
-                warn!("Failed to initialize directory watcher: {e}");
-                let _ = command_tx.send(Command::AlertWarn(format!(
-                    "Directory watcher unavailable: {e}. Use Ctrl+R to refresh manually."
-                )));
-            })
-            .ok();
+        let backend: Arc<dyn Backend> = Arc::new(backend::Local);
+        let watcher = DirectoryWatcher::new(
+            Arc::clone(&backend),
+            config.file_system.refresh_debounce_milliseconds,
+        );
         Self {
+            backend,
             bookmarks_dir: config.bookmarks_dir(),
             buffer_max_bytes: config.file_system.buffer_max_bytes,
             buffer_min_bytes: config.file_system.buffer_min_bytes,
@@ -245,16 +334,28 @@ impl FileSystem {
             directory: None,
             previous_directory: None,
             current_load: None,
+            delete_summary: None,
+            sizing: None,
+            size_cache: sizes::SizeCache::default(),
+            disk_usage_scans: Vec::new(),
+            duplicate_scans: Vec::new(),
+            comparisons: Vec::new(),
+            preview: None,
+            peeks: HashMap::new(),
             reload_pending: false,
             current_search_generation: 0,
             next_generation: 0,
+            diff_template: config.openers.diff.clone(),
             open_directory_template: config.openers.open_directory.clone(),
             open_file_template: config.openers.open_file.clone(),
             open_filectrl_window_template: config.openers.open_filectrl_window.clone(),
             pending_paste: None,
+            pending_template: None,
+            pending_extract: None,
+            protected_paths: config.file_system.protected_paths.clone(),
             search_max_depth: config.file_system.search_max_depth,
             search_max_results: config.file_system.search_max_results,
-            watcher,
+            watcher: Some(watcher),
         }
     }
 
@@ -283,7 +384,7 @@ impl FileSystem {
         if let Err(error) = fs::read_dir(&directory.path) {
             let _ = self.command_tx.send(Command::AlertError(format!(
                 "Failed to change to directory {}: {error}",
-                compact(&directory.path)
+                directory.location()
             )));
             let home = directories::UserDirs::new()
                 .map(|dirs| dirs.home_dir().to_path_buf())
@@ -322,8 +423,13 @@ impl FileSystem {
     fn cd(&mut self, directory: PathInfo, navigate: bool) -> CommandResult {
         // Cheap readability pre-flight so we don't switch into a directory we
         // cannot open (e.g. permission denied). The full per-entry read happens
-        // asynchronously in `stream_cd` below.
-        if let Err(error) = fs::read_dir(&directory.path) {
+        // asynchronously in `stream_cd` below. A directory inside an archive
+        // is as readable as the archive.
+        let readable = match directory.archive() {
+            Some(archive) => fs::File::open(archive).map(drop),
+            None => self.backend_for(&directory).list(&directory.path).map(drop),
+        };
+        if let Err(error) = readable {
             return anyhow!(
                 "Failed to change to directory {}: {error}",
                 compact(&directory.path)
@@ -334,16 +440,25 @@ impl FileSystem {
         // Track the directory we're leaving so "-" can toggle back to it.
         if navigate
             && let Some(current) = &self.directory
-            && current.path != directory.path
+            && *current != directory
         {
             self.previous_directory = Some(current.clone());
         }
         self.directory = Some(directory.clone());
-        let path_buf = directory.path.clone();
-        if let Some(watcher) = &mut self.watcher
-            && let Err(e) = watcher.watch_directory(path_buf.clone())
-        {
-            self.send_directory_error(&path_buf, e);
+        // Inside an archive, what can change is the archive itself, which is
+        // an entry of the directory holding it.
+        let path_buf = match directory.archive() {
+            Some(archive) => archive.parent().unwrap_or(archive).to_path_buf(),
+            None => directory.path.clone(),
+        };
+        // Nothing reports changes on another host: its listing is refreshed
+        // by hand, and after this side's own tasks finish.
+        if let Some(watcher) = &mut self.watcher {
+            if directory.remote().is_some() {
+                watcher.unwatch();
+            } else if let Err(e) = watcher.watch_directory(path_buf.clone()) {
+                self.send_directory_error(&path_buf, e);
+            }
         }
 
         // Cancel any in-flight load so its batches don't bleed into this one,
@@ -353,6 +468,7 @@ impl FileSystem {
         let token = CancellationToken::new();
         self.current_load = Some((generation, token.clone()));
         operations::stream_cd(
+            self.backend_for(&directory),
             directory.clone(),
             generation,
             self.command_tx.clone(),
@@ -373,9 +489,19 @@ impl FileSystem {
         .into()
     }
 
+    /// What reads and writes `entry`: its host's session, or this machine's.
+    fn backend_for(&self, entry: &PathInfo) -> Arc<dyn Backend> {
+        match entry.remote() {
+            Some(remote) => Arc::new(remote.clone()),
+            None => Arc::clone(&self.backend),
+        }
+    }
+
     /// The next stream generation. Shared by directory loads and searches so
     /// a generation is never ambiguous between the two.
     fn bump_generation(&mut self) -> u64 {
+        // Sizes are only wanted for the listing they were asked for.
+        self.cancel_sizing();
         self.next_generation += 1;
         self.next_generation
     }
@@ -423,6 +549,246 @@ impl FileSystem {
         });
     }
 
+    /// Starts counting what the delete prompt is asking about, replacing any
+    /// count still running for an earlier prompt.
+    fn summarize_delete(&mut self, paths: &[PathInfo]) -> CommandResult {
+        self.cancel_delete_summary();
+        let token = CancellationToken::new();
+        summary::run_summary(self.command_tx.clone(), token.clone(), paths.to_vec());
+        self.delete_summary = Some(token);
+        CommandResult::Handled
+    }
+
+    /// Stops the delete prompt's count. Not on the cancel stack: the walk only
+    /// reads, and it ends with the prompt it belongs to.
+    fn cancel_delete_summary(&mut self) {
+        if let Some(token) = self.delete_summary.take() {
+            token.cancel();
+        }
+    }
+
+    /// Counts what is under each of the current listing's `directories`,
+    /// replacing any count still running for an earlier one. Sizes already in
+    /// the cache come back straight away. An empty list means sizes were
+    /// turned off, so the cache goes too and turning them back on recounts.
+    fn size_directories(&mut self, directories: &[PathInfo], generation: u64) -> CommandResult {
+        self.cancel_sizing();
+        if directories.is_empty() {
+            self.size_cache.lock().unwrap().clear();
+            return CommandResult::Handled;
+        }
+        let token = CancellationToken::new();
+        let known = sizes::run_sizes(
+            self.command_tx.clone(),
+            token.clone(),
+            Arc::clone(&self.size_cache),
+            directories,
+            generation,
+        );
+        self.sizing = Some(token);
+        known.map_or(CommandResult::Handled, CommandResult::from)
+    }
+
+    fn cancel_sizing(&mut self) {
+        if let Some(token) = self.sizing.take() {
+            token.cancel();
+        }
+    }
+
+    fn scan_disk_usage(&mut self, root: &Path) -> CommandResult {
+        let token = CancellationToken::new();
+        disk_usage::run_disk_usage(self.command_tx.clone(), token.clone(), root.to_path_buf());
+        self.disk_usage_scans.push(token);
+        CommandResult::Handled
+    }
+
+    fn cancel_disk_usage(&mut self) -> CommandResult {
+        for token in self.disk_usage_scans.drain(..) {
+            token.cancel();
+        }
+        CommandResult::Handled
+    }
+
+    fn find_duplicates(&mut self, root: &Path) -> CommandResult {
+        let token = CancellationToken::new();
+        duplicates::run_duplicates(self.command_tx.clone(), token.clone(), root.to_path_buf());
+        self.duplicate_scans.push(token);
+        CommandResult::Handled
+    }
+
+    fn cancel_duplicates(&mut self) -> CommandResult {
+        for token in self.duplicate_scans.drain(..) {
+            token.cancel();
+        }
+        CommandResult::Handled
+    }
+
+    fn compare_directories(&mut self, left: &Path, right: &Path, contents: bool) -> CommandResult {
+        let token = CancellationToken::new();
+        compare::run_compare(
+            self.command_tx.clone(),
+            token.clone(),
+            left.to_path_buf(),
+            right.to_path_buf(),
+            contents,
+        );
+        self.comparisons.push(token);
+        CommandResult::Handled
+    }
+
+    fn cancel_compare(&mut self) -> CommandResult {
+        for token in self.comparisons.drain(..) {
+            token.cancel();
+        }
+        CommandResult::Handled
+    }
+
+    fn copy_compared(
+        &mut self,
+        left: &Path,
+        right: &Path,
+        copies: &[(PathBuf, PathBuf)],
+    ) -> CommandResult {
+        let plan = SyncPlan {
+            left: left.to_path_buf(),
+            right: right.to_path_buf(),
+            copies: copies.to_vec(),
+            removals: Vec::new(),
+        };
+        let (_, commands) = self.run_task(TaskCommand::Sync(plan), None);
+        commands.into()
+    }
+
+    fn mirror_compared(
+        &mut self,
+        source: &Path,
+        destination: &Path,
+        copies: &[(PathBuf, PathBuf)],
+        removals: &[PathBuf],
+    ) -> CommandResult {
+        let plan = SyncPlan {
+            left: source.to_path_buf(),
+            right: destination.to_path_buf(),
+            copies: copies.to_vec(),
+            removals: removals.to_vec(),
+        };
+        let (_, commands) = self.run_task(TaskCommand::Mirror(plan), None);
+        commands.into()
+    }
+
+    fn compute_checksums(
+        &mut self,
+        paths: &[PathBuf],
+        directory: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> CommandResult {
+        let job = ChecksumJob {
+            algorithm,
+            directory: directory.to_path_buf(),
+            sums: None,
+            files: paths.iter().map(|path| (path.clone(), None)).collect(),
+        };
+        let (_, commands) = self.run_task(TaskCommand::Checksum(job), None);
+        commands.into()
+    }
+
+    /// Checks the files the checksum file at `sums` lists. One that cannot be
+    /// read, or lists nothing, is refused before a task starts.
+    fn verify_checksums(&mut self, sums: &Path) -> CommandResult {
+        let listed = match checksums::read_sums(sums) {
+            Ok(listed) => listed,
+            Err(error) => return Command::AlertWarn(error.to_string()).into(),
+        };
+        let job = ChecksumJob {
+            algorithm: ChecksumAlgorithm::of_sums_file(sums),
+            directory: sums.parent().unwrap_or(sums).to_path_buf(),
+            sums: Some(sums.to_path_buf()),
+            files: listed
+                .into_iter()
+                .map(|(path, digest)| (path, Some(digest)))
+                .collect(),
+        };
+        let (_, commands) = self.run_task(TaskCommand::Checksum(job), None);
+        commands.into()
+    }
+
+    fn peek(&mut self, path: &PathInfo, column: PeekColumn) -> CommandResult {
+        let token = CancellationToken::new();
+        if let Some(previous) = self.peeks.insert(column, token.clone()) {
+            previous.cancel();
+        }
+        peek::run_peek(
+            self.command_tx.clone(),
+            self.backend_for(path),
+            path.clone(),
+            column,
+            token,
+        );
+        CommandResult::Handled
+    }
+
+    fn preview_file(&mut self, path: &PathInfo) -> CommandResult {
+        let token = CancellationToken::new();
+        if let Some(previous) = self.preview.replace(token.clone()) {
+            previous.cancel();
+        }
+        preview::run_preview(
+            self.command_tx.clone(),
+            self.backend_for(path),
+            path.clone(),
+            token,
+        );
+        CommandResult::Handled
+    }
+
+    /// Diffs two files with `openers.diff`, or, when it is empty, two text
+    /// files for the built-in viewer. Identical files are said to be, rather
+    /// than opening an empty viewer.
+    fn diff_files(&self, left: &Path, right: &Path) -> CommandResult {
+        if !self.diff_template.is_empty() {
+            return Command::RunInForeground(diff_command(left, right, &self.diff_template)).into();
+        }
+        match diff::diff_files(left, right) {
+            Ok(diff) if diff.hunks.is_empty() => Command::AlertInfo(format!(
+                "{} and {} are identical",
+                compact(left),
+                compact(right)
+            ))
+            .into(),
+            Ok(diff) => Command::Diffed(diff).into(),
+            Err(error) => Command::AlertWarn(error.to_string()).into(),
+        }
+    }
+
+    /// Replaces each pair's second file with a hard link to its first. Each
+    /// replacement stands alone: one that fails is reported and the rest go
+    /// ahead.
+    fn hard_link_duplicates(&mut self, pairs: &[(DuplicateFile, DuplicateFile)]) -> CommandResult {
+        let mut linked = Vec::new();
+        let mut commands = Vec::new();
+        for (keep, duplicate) in pairs {
+            let result = self
+                .protected_paths
+                .check("replace", &duplicate.path, false)
+                .and_then(|()| duplicates::replace_with_hard_link(keep, duplicate));
+            match result {
+                Ok(()) => linked.push(duplicate.path.clone()),
+                Err(error) => commands.push(Command::AlertError(format!(
+                    "Failed to replace {} with a hard link: {error:#}",
+                    compact(&duplicate.path)
+                ))),
+            }
+        }
+        if !linked.is_empty() {
+            commands.push(Command::AlertInfo(match linked.len() {
+                1 => "Replaced 1 duplicate with a hard link".into(),
+                count => format!("Replaced {count} duplicates with hard links"),
+            }));
+            commands.push(Command::DuplicatesLinked(linked));
+        }
+        commands.into()
+    }
+
     /// Handles an `ExitedSearch` from a search thread. Only the current
     /// search's exit drops its entry; exits from superseded searches are
     /// ignored.
@@ -493,16 +859,53 @@ impl FileSystem {
                 Cancellable::Search(_) => true,
             });
         }
+        // No watcher reports what a task wrote on another host, so the
+        // listing is refreshed once it finishes, whether it got far or not.
+        let refresh = task.is_terminal()
+            && self
+                .directory
+                .as_ref()
+                .is_some_and(|directory| directory.remote().is_some());
         if task.is_cancelled() {
-            return CommandResult::Handled;
+            return if refresh {
+                Command::RefreshDirectory.into()
+            } else {
+                CommandResult::Handled
+            };
+        }
+        match (task.error_message(), refresh) {
+            (Some(msg), true) => vec![Command::AlertError(msg), Command::RefreshDirectory].into(),
+            (Some(msg), false) => Command::AlertError(msg).into(),
+            (None, true) => Command::RefreshDirectory.into(),
+            (None, false) => CommandResult::NotHandled,
         }
-        task.error_message()
-            .map_or(CommandResult::NotHandled, |msg| {
-                Command::AlertError(msg).into()
-            })
     }
 
     fn open(&mut self, path: &PathInfo) -> CommandResult {
+        if path.archive().is_some() {
+            if path.is_directory() {
+                return self.cd(path.clone(), true);
+            }
+            return Command::AlertWarn(format!(
+                "Cannot open {} inside an archive: copy it out first",
+                path.display_name
+            ))
+            .into();
+        }
+        if let Some(remote) = path.remote() {
+            return match remote.canonicalize(&path.path).and_then(|resolved| {
+                PathInfo::read_remote(remote, &resolved).map_err(std::io::Error::other)
+            }) {
+                Ok(path) if path.is_directory() => self.cd(path, true),
+                Ok(path) => Command::AlertWarn(format!(
+                    "Cannot open {} on {}: copy it here first",
+                    path.display_name,
+                    remote.authority()
+                ))
+                .into(),
+                Err(error) => anyhow!("Failed to open {}: {error}", path.location()).into(),
+            };
+        }
         // Name the path in the error: a broken symlink (e.g. a bookmark whose
         // target was removed) would otherwise surface a bare io error.
         match fs::canonicalize(&path.path)
@@ -512,6 +915,10 @@ impl FileSystem {
             Ok(path) => {
                 if path.is_directory() {
                     self.cd(path, true)
+                } else if path.is_file() && ArchiveFormat::from_name(&path.display_name).is_ok() {
+                    // Browsed as a read-only directory rather than handed to
+                    // another application.
+                    self.cd(PathInfo::archive_root(&path), true)
                 } else {
                     open_in(&path, &self.open_file_template, self.command_tx.clone()).into()
                 }
@@ -520,6 +927,45 @@ impl FileSystem {
         }
     }
 
+    /// Connects to `address`'s host, unless a session to it is already open,
+    /// and opens its path. Off the UI thread, since ssh may take up to its
+    /// connect timeout to answer or give up.
+    fn open_remote(&self, address: &Address) -> CommandResult {
+        // Backstop: `Address::parse` never yields an empty host, and ssh
+        // would only fail on one.
+        if address.host.is_empty() {
+            return Command::AlertWarn(format!("Cannot open {address}: it names no host")).into();
+        }
+        let authority = address.authority();
+        let connected = sftp::session(&authority).is_some();
+        let address = address.clone();
+        let tx = self.command_tx.clone();
+        thread::spawn(move || {
+            let result = sftp::session(&address.authority())
+                .map_or_else(|| sftp::connect(&address), Ok)
+                .and_then(|remote| {
+                    // An empty path is the home directory, which REALPATH
+                    // resolves from ".".
+                    let path = if address.path.is_empty() {
+                        "."
+                    } else {
+                        &address.path
+                    };
+                    let resolved = remote.canonicalize(Path::new(path))?;
+                    PathInfo::read_remote(&remote, &resolved).map_err(std::io::Error::other)
+                });
+            let _ = tx.send(match result {
+                Ok(path) => Command::Open(path),
+                Err(error) => Command::AlertError(format!("Failed to open {address}: {error}")),
+            });
+        });
+        if connected {
+            CommandResult::Handled
+        } else {
+            Command::AlertInfo(format!("Connecting to {authority}…")).into()
+        }
+    }
+
     fn open_current_directory(&self) -> CommandResult {
         open_in(
             self.current_directory(),
@@ -549,9 +995,13 @@ impl FileSystem {
         spawn_argv(working_dir, label, argv, self.command_tx.clone()).into()
     }
 
-    fn chmod(&mut self, paths: &[PathInfo], mode_str: &str) -> CommandResult {
-        let Some(mode) = parse_octal_mode(mode_str) else {
-            return anyhow!("Invalid octal mode: {mode_str:?}").into();
+    fn chmod(&mut self, paths: &[PathInfo], mode_str: &str, recursive: bool) -> CommandResult {
+        if recursive {
+            return self.chmod_recursive(paths, mode_str);
+        }
+        let mode = match Mode::parse(mode_str) {
+            Ok(mode) => mode,
+            Err(error) => return error.into(),
         };
         // Return the failures alongside the refresh instead of sending them
         // separately, so they are ordered against it rather than racing the
@@ -559,7 +1009,31 @@ impl FileSystem {
         let mut commands: Vec<Command> = paths
             .iter()
             .filter_map(|path| {
-                operations::chmod(path, mode).err().map(|error| {
+                // `set_permissions` follows a symlink, so what it points at is
+                // checked as well as the link itself.
+                let target = path.path.canonicalize().ok();
+                if let Err(error) = self
+                    .protected_paths
+                    .check("chmod", &path.path, false)
+                    .and_then(|()| match &target {
+                        Some(target) => self.protected_paths.check("chmod", target, false),
+                        None => Ok(()),
+                    })
+                {
+                    return Some(error.into());
+                }
+                // A symbolic mode is relative to the mode being changed, which
+                // for a symlink is its target's.
+                let metadata = match fs::metadata(&path.path) {
+                    Ok(metadata) => metadata,
+                    Err(error) => {
+                        return Some(
+                            anyhow!("Failed to chmod {}: {error}", compact(&path.path)).into(),
+                        );
+                    }
+                };
+                let bits = mode.apply(metadata.mode(), metadata.is_dir());
+                operations::chmod(path, bits).err().map(|error| {
                     anyhow!(
                         "Failed to chmod {} to {mode_str}: {error}",
                         compact(&path.path)
@@ -572,6 +1046,297 @@ impl FileSystem {
         commands.into()
     }
 
+    /// Queues one recursive chmod task per path. `modes_str` is one mode for
+    /// every entry, or a directory mode and a file mode.
+    fn chmod_recursive(&mut self, paths: &[PathInfo], modes_str: &str) -> CommandResult {
+        let modes = match TreeModes::parse(modes_str) {
+            Ok(modes) => modes,
+            Err(error) => return error.into(),
+        };
+        let mut commands = Vec::new();
+        for path in paths {
+            let (_, task_commands) =
+                self.run_task(TaskCommand::Chmod(path.clone(), modes.clone()), None);
+            commands.extend(task_commands);
+        }
+        commands.into()
+    }
+
+    fn chown(&mut self, paths: &[PathInfo], owner_str: &str, recursive: bool) -> CommandResult {
+        let owner = match Owner::parse(owner_str) {
+            Ok(owner) => owner,
+            Err(error) => return error.into(),
+        };
+        if recursive {
+            let mut commands = Vec::new();
+            for path in paths {
+                let (_, task_commands) =
+                    self.run_task(TaskCommand::Chown(path.clone(), owner), None);
+                commands.extend(task_commands);
+            }
+            return commands.into();
+        }
+        // As with chmod, the failures travel with the refresh.
+        let mut commands: Vec<Command> = paths
+            .iter()
+            .filter_map(|path| {
+                // Like chown(1), a symlink's target changes hands, so it is
+                // checked as well as the link itself.
+                let target = path.path.canonicalize().ok();
+                if let Err(error) = self
+                    .protected_paths
+                    .check("chown", &path.path, false)
+                    .and_then(|()| match &target {
+                        Some(target) => self.protected_paths.check("chown", target, false),
+                        None => Ok(()),
+                    })
+                {
+                    return Some(error.into());
+                }
+                owner.apply(&path.path, true).err().map(|error| {
+                    anyhow!(
+                        "Failed to chown {} to {owner_str}: {error}",
+                        compact(&path.path)
+                    )
+                    .into()
+                })
+            })
+            .collect();
+        commands.extend(self.refresh().into_commands());
+        commands.into()
+    }
+
+    fn touch(&mut self, paths: &[PathInfo], time: &TouchTime, recursive: bool) -> CommandResult {
+        let stamp = match time {
+            TouchTime::Typed(input) => match Stamp::parse(input, Local::now()) {
+                Ok(stamp) => stamp,
+                Err(error) => return error.into(),
+            },
+            TouchTime::Like(reference) => match Stamp::of(&reference.path) {
+                Ok(stamp) => stamp,
+                Err(error) => {
+                    return anyhow!(
+                        "Failed to read the times of {}: {error}",
+                        compact(&reference.path)
+                    )
+                    .into();
+                }
+            },
+        };
+        if recursive {
+            let mut commands = Vec::new();
+            for path in paths {
+                let (_, task_commands) =
+                    self.run_task(TaskCommand::Touch(path.clone(), stamp), None);
+                commands.extend(task_commands);
+            }
+            return commands.into();
+        }
+        // As with chmod, the failures travel with the refresh.
+        let mut commands: Vec<Command> = paths
+            .iter()
+            .filter_map(|path| {
+                // Like touch(1), a symlink's target is what changes, so it is
+                // checked as well as the link itself.
+                let target = path.path.canonicalize().ok();
+                if let Err(error) = self
+                    .protected_paths
+                    .check("touch", &path.path, false)
+                    .and_then(|()| match &target {
+                        Some(target) => self.protected_paths.check("touch", target, false),
+                        None => Ok(()),
+                    })
+                {
+                    return Some(error.into());
+                }
+                stamp
+                    .apply(&path.path, true)
+                    .err()
+                    .map(|error| anyhow!("Failed to touch {}: {error}", compact(&path.path)).into())
+            })
+            .collect();
+        commands.extend(self.refresh().into_commands());
+        commands.into()
+    }
+
+    /// Packs `paths` into a new archive `name` in the current directory, in
+    /// the format its extension names. The watcher lists the archive as it is
+    /// written, so there is no refresh to ask for here.
+    fn compress(&mut self, paths: &[PathInfo], name: &str) -> CommandResult {
+        let format = match operations::validate_basename("Archive name", name)
+            .and_then(|()| ArchiveFormat::from_name(name))
+        {
+            Ok(format) => format,
+            Err(error) => return error.into(),
+        };
+        let archive = self.current_directory().as_path().join(name);
+        let (_, commands) =
+            self.run_task(TaskCommand::Compress(paths.to_vec(), archive, format), None);
+        commands.into()
+    }
+
+    /// Unpacks `archive` into a new directory in the current one, named after
+    /// the archive without its extension, or with `here` straight into the
+    /// current directory. Names already taken get the conflict prompt before
+    /// anything is written.
+    ///
+    /// A new directory's name is the only one it takes, so that is asked about
+    /// at once. What an extract here takes is only known by reading the
+    /// archive, which for a compressed tar means all of it, so that is done
+    /// off the UI thread and answered by `ArchiveSurveyed`.
+    fn extract(&mut self, archive: &PathInfo, here: bool) -> CommandResult {
+        // The file name rather than the display name, which for a search
+        // result is a path below the search root.
+        let file_name = archive.path.file_name().unwrap_or_default();
+        let name = file_name.to_string_lossy();
+        let format = match ArchiveFormat::from_name(&name) {
+            Ok(format) => format,
+            Err(error) => return error.into(),
+        };
+        let directory = self.current_directory().path.clone();
+        if here {
+            let tx = self.command_tx.clone();
+            let archive = archive.path.clone();
+            thread::spawn(move || {
+                let command = match archive::top_level_names(&archive, format) {
+                    Ok(names) => Command::ArchiveSurveyed {
+                        archive,
+                        destination: directory,
+                        names,
+                    },
+                    Err(error) => Command::AlertError(format!(
+                        "Failed to read {}: {error}",
+                        compact(&archive)
+                    )),
+                };
+                let _ = tx.send(command);
+            });
+            return CommandResult::Handled;
+        }
+        // Cut from the name's own bytes, so one that is not UTF-8 keeps them:
+        // the extension is ASCII, the same length either way.
+        let extension = name.len() - ArchiveFormat::stem(&name).map_or(name.len(), str::len);
+        let bytes = file_name.as_bytes();
+        let stem = OsStr::from_bytes(&bytes[..bytes.len() - extension]).to_os_string();
+        self.begin_extract(
+            Extraction {
+                archive: archive.path.clone(),
+                format,
+                root: PathBuf::new(),
+                destination: directory.join(&stem),
+                replace: Vec::new(),
+                skip: BTreeSet::new(),
+            },
+            directory,
+            &[stem],
+        )
+    }
+
+    /// Starts the extract a survey was read for, once its taken names are
+    /// answered for.
+    fn on_archive_surveyed(
+        &mut self,
+        archive: &Path,
+        destination: &Path,
+        names: &[OsString],
+    ) -> CommandResult {
+        if names.is_empty() {
+            return Command::AlertWarn(format!("{} holds nothing to extract", compact(archive)))
+                .into();
+        }
+        // The survey ran while the user carried on, and the prompt it would
+        // open cannot share the screen with one already waiting on an answer.
+        if self.pending_paste.is_some()
+            || self.pending_template.is_some()
+            || self.pending_extract.is_some()
+        {
+            return Command::AlertWarn(format!(
+                "Extract of {} not started: answer the open prompt, then extract again",
+                compact(archive)
+            ))
+            .into();
+        }
+        let Some(format) = archive
+            .file_name()
+            .and_then(|name| ArchiveFormat::from_name(&name.to_string_lossy()).ok())
+        else {
+            return CommandResult::Handled;
+        };
+        self.begin_extract(
+            Extraction {
+                archive: archive.to_path_buf(),
+                format,
+                root: PathBuf::new(),
+                destination: destination.to_path_buf(),
+                replace: Vec::new(),
+                skip: BTreeSet::new(),
+            },
+            destination.to_path_buf(),
+            names,
+        )
+    }
+
+    fn begin_extract(
+        &mut self,
+        extraction: Extraction,
+        base: PathBuf,
+        names: &[OsString],
+    ) -> CommandResult {
+        let remaining = names
+            .iter()
+            .filter_map(|name| {
+                let metadata = base.join(name).symlink_metadata().ok()?;
+                let occupant = if metadata.is_dir() {
+                    Occupant::Directory
+                } else {
+                    Occupant::Replaceable
+                };
+                Some((name.clone(), occupant))
+            })
+            .collect();
+        self.pending_extract = Some(PendingExtract {
+            extraction,
+            base,
+            remaining,
+            names: names.len(),
+            conflicts: Conflicts::default(),
+        });
+        self.advance_extract()
+    }
+
+    /// Settles the taken names the standing answer covers, and asks about the
+    /// first one it does not; with none left, starts the extract.
+    fn advance_extract(&mut self) -> CommandResult {
+        let Some(mut pending) = self.pending_extract.take() else {
+            return CommandResult::Handled;
+        };
+        while let Some((name, occupant)) = pending.remaining.front().cloned() {
+            match step(pending.conflicts.standing(), Some(occupant)) {
+                PasteStep::Ask { can_overwrite } => {
+                    self.pending_extract = Some(pending);
+                    return Command::OpenPrompt(PromptAction::Conflict {
+                        name: name.to_string_lossy().into_owned(),
+                        can_overwrite,
+                    })
+                    .into();
+                }
+                PasteStep::Skip => {
+                    pending.remaining.pop_front();
+                    pending.extraction.skip.insert(name);
+                }
+                PasteStep::Run { .. } => {
+                    pending.remaining.pop_front();
+                    pending.extraction.replace.push(pending.base.join(name));
+                }
+            }
+        }
+        if pending.extraction.skip.len() == pending.names {
+            return CommandResult::Handled;
+        }
+        let (_, commands) = self.run_task(TaskCommand::Extract(pending.extraction), None);
+        commands.into()
+    }
+
     fn add_bookmark(&mut self, target: &PathInfo, name: &str) -> CommandResult {
         match operations::add_bookmark(&self.bookmarks_dir, target, name) {
             Err(error) => Command::AlertError(error.to_string()).into(),
@@ -580,17 +1345,93 @@ impl FileSystem {
     }
 
     fn create_directory(&mut self, name: &str) -> CommandResult {
-        match operations::create_directory(self.current_directory(), name) {
+        let directory = self.current_directory();
+        match operations::create_directory(&*self.backend_for(directory), directory, name) {
             Err(error) => anyhow!("Failed to create directory {name:?}: {error}").into(),
-            Ok(()) => self.refresh(),
+            Ok(paths) => self.created(&paths[0]),
+        }
+    }
+
+    fn create_file(&mut self, name: &str) -> CommandResult {
+        let directory = self.current_directory();
+        match operations::create_file(&*self.backend_for(directory), directory, name) {
+            Err(error) => anyhow!("Failed to create file {name:?}: {error}").into(),
+            Ok(path) => self.created(&path),
+        }
+    }
+
+    /// Refreshes the listing after a create, and has the table select what was
+    /// made. A nested path is selected by the entry of the current directory
+    /// that leads to it, which is the one the listing holds.
+    fn created(&mut self, path: &Path) -> CommandResult {
+        let directory = self.current_directory().as_path();
+        let entry = path
+            .strip_prefix(directory)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .map(|first| directory.join(first));
+        let mut commands = self.refresh().into_commands();
+        commands.extend(entry.map(Command::Created));
+        commands.into()
+    }
+
+    /// Copies a template into the current directory. A name that is already
+    /// taken gets the same conflict prompt as a paste, minus the `*All`
+    /// answers' reach: there is only the one entry, so they mean what the
+    /// single answers do.
+    fn create_from_template(&mut self, template: &PathInfo, name: &str) -> CommandResult {
+        if let Err(error) = operations::validate_relative_path("File name", name) {
+            return error.into();
+        }
+        let dest = self.current_directory().as_path().join(name);
+        match fs::symlink_metadata(&dest) {
+            Ok(metadata) => {
+                self.pending_template = Some((template.clone(), name.to_string()));
+                Command::OpenPrompt(PromptAction::Conflict {
+                    name: name.to_string(),
+                    can_overwrite: !metadata.is_dir(),
+                })
+                .into()
+            }
+            Err(_) => self.copy_template(template, name, false),
+        }
+    }
+
+    fn copy_template(&mut self, template: &PathInfo, name: &str, overwrite: bool) -> CommandResult {
+        if overwrite {
+            let dest = self.current_directory().as_path().join(name);
+            if let Err(error) = self.protected_paths.check("overwrite", &dest, false) {
+                return error.into();
+            }
+        }
+        match operations::create_from_template(
+            self.current_directory(),
+            template.as_path(),
+            name,
+            overwrite,
+        ) {
+            Err(error) => anyhow!(
+                "Failed to create {name:?} from {}: {error}",
+                compact(&template.path)
+            )
+            .into(),
+            Ok(path) => self.created(&path),
         }
     }
 
     fn rename(&mut self, path: &PathInfo, new_basename: &str) -> CommandResult {
-        match operations::rename(path, new_basename) {
+        // A rename is a move within the directory, and takes everything below
+        // a renamed directory along with it.
+        if let Err(error) = self
+            .protected_paths
+            .check("rename", &path.path, path.is_directory())
+        {
+            return error.into();
+        }
+        match operations::rename(&*self.backend_for(path), path, new_basename) {
             Err(error) => anyhow!(
                 "Failed to rename {} to {new_basename:?}: {error}",
-                compact(&path.path)
+                path.location()
             )
             .into(),
             Ok(()) => self.refresh(),
@@ -610,6 +1451,41 @@ impl FileSystem {
         self.cd(self.current_directory().clone(), false)
     }
 
+    /// Takes up the directory of the pane or tab focus moved to, reading it
+    /// again, since nothing watched it while it was out of focus. One removed
+    /// meanwhile gives way to its nearest remaining ancestor, so the pane is
+    /// not left on a listing nothing reads.
+    fn switch_pane(
+        &mut self,
+        directory: &PathInfo,
+        previous_directory: Option<&PathInfo>,
+    ) -> CommandResult {
+        // Going back is within a pane, not across to the other one or to
+        // another tab. A search belongs to the tab it was started in, which
+        // runs it again when it is brought back.
+        self.previous_directory = previous_directory.cloned();
+        self.cancel_search();
+        let result = self.cd(directory.clone(), false);
+        let is_switched = self
+            .directory
+            .as_ref()
+            .is_some_and(|current| current.path == directory.path);
+        if is_switched || directory.remote().is_some() || directory.archive().is_some() {
+            return result;
+        }
+        let Some(ancestor) = directory
+            .path
+            .ancestors()
+            .skip(1)
+            .find_map(|path| PathInfo::try_from(path).ok())
+        else {
+            return result;
+        };
+        let mut commands = result.into_commands();
+        commands.extend(self.cd(ancestor, true).into_commands());
+        commands.into()
+    }
+
     /// Runs a task, registering it on the cancel stack when it starts. Returns
     /// whether it started, together with the alerts it produced (a task that
     /// fails validation produces one and starts nothing). Started tasks send
@@ -622,6 +1498,7 @@ impl FileSystem {
         let result = task.run(
             self.command_tx.clone(),
             conflicts,
+            &self.protected_paths,
             self.buffer_min_bytes,
             self.buffer_max_bytes,
         );
@@ -635,6 +1512,13 @@ impl FileSystem {
     /// Starts a paste. Sources run one at a time so that a name already taken
     /// in the destination can be answered for before the next source starts.
     fn start_paste(&mut self, is_move: bool, srcs: &[PathInfo], dest: &PathInfo) -> CommandResult {
+        if let Some(archive) = dest.archive() {
+            return Command::AlertWarn(format!(
+                "{} is read-only: copy entries out of it instead",
+                compact(archive)
+            ))
+            .into();
+        }
         self.pending_paste = Some(PendingPaste {
             is_move,
             dest: dest.clone(),
@@ -684,6 +1568,26 @@ impl FileSystem {
     /// Applies a conflict answer to the source at the front of the queue, then
     /// keeps going.
     fn resolve_conflict(&mut self, choice: ConflictChoice) -> CommandResult {
+        if let Some((template, name)) = self.pending_template.take() {
+            return match choice {
+                ConflictChoice::Overwrite | ConflictChoice::OverwriteAll => {
+                    self.copy_template(&template, &name, true)
+                }
+                ConflictChoice::Skip | ConflictChoice::SkipAll => CommandResult::Handled,
+            };
+        }
+        if let Some(mut pending) = self.pending_extract.take() {
+            if let Some((name, _)) = pending.remaining.pop_front() {
+                pending.conflicts.answer(choice);
+                if conflicts::replaces(choice) {
+                    pending.extraction.replace.push(pending.base.join(name));
+                } else {
+                    pending.extraction.skip.insert(name);
+                }
+            }
+            self.pending_extract = Some(pending);
+            return self.advance_extract();
+        }
         let Some(mut pending) = self.pending_paste.take() else {
             return CommandResult::Handled;
         };
@@ -709,6 +1613,10 @@ impl FileSystem {
     /// running paste alone. The standing answer stands, so an `*All` already
     /// given still covers the sources already handed out.
     fn cancel_paste(&mut self) -> CommandResult {
+        // A template copy or an extract waiting on the same prompt is simply
+        // dropped: neither has written anything yet.
+        self.pending_template = None;
+        self.pending_extract = None;
         let Some(mut pending) = self.pending_paste.take() else {
             return CommandResult::NotHandled;
         };
@@ -732,7 +1640,26 @@ impl FileSystem {
         src: PathInfo,
         overwrite: bool,
     ) -> Vec<Command> {
-        let task = if pending.is_move {
+        let task = if let Some(archive) = src.archive() {
+            match extract_out(archive, &src, &pending.dest, pending.is_move, overwrite) {
+                Ok(extraction) => TaskCommand::Extract(extraction),
+                Err(error) => {
+                    pending.failed.push(src);
+                    return vec![Command::AlertError(error.to_string())];
+                }
+            }
+        } else if src.remote().is_some() || pending.dest.remote().is_some() {
+            // Between hosts a move would be a copy and a delete, and the
+            // delete is not offered on another host.
+            if pending.is_move {
+                pending.failed.push(src.clone());
+                return vec![Command::AlertError(format!(
+                    "Cannot move {} between hosts: copy it instead",
+                    src.location()
+                ))];
+            }
+            TaskCommand::CopyAcross(src.clone(), pending.dest.clone(), overwrite)
+        } else if pending.is_move {
             TaskCommand::Move(src.clone(), pending.dest.clone(), overwrite)
         } else {
             TaskCommand::Copy(src.clone(), pending.dest.clone(), overwrite)
@@ -785,11 +1712,11 @@ impl FileSystem {
         let tick_tx = self.command_tx.clone();
         thread::spawn(move || {
             while !tick_token.is_cancelled() {
-                thread::sleep(SEARCH_TICK_INTERVAL);
+                thread::sleep(TICK_INTERVAL);
                 if tick_token.is_cancelled() {
                     break;
                 }
-                if tick_tx.send(Command::SearchTick).is_err() {
+                if tick_tx.send(Command::Tick).is_err() {
                     break;
                 }
             }
@@ -820,6 +1747,45 @@ impl FileSystem {
     }
 }
 
+/// The extract that copies `src`, an entry of `archive` being browsed, into
+/// `dest`: the paste of an archive entry. Nothing is ever taken out of an
+/// archive, so it cannot be moved.
+fn extract_out(
+    archive: &Path,
+    src: &PathInfo,
+    dest: &PathInfo,
+    is_move: bool,
+    overwrite: bool,
+) -> Result<Extraction> {
+    if is_move {
+        return Err(anyhow!(
+            "Cannot move {} out of {}: it is read-only, so copy it instead",
+            src.display_name,
+            compact(archive)
+        ));
+    }
+    let format = ArchiveFormat::from_name(
+        &archive
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default(),
+    )?;
+    let root = src.path_in_archive().unwrap_or(Path::new("")).to_path_buf();
+    // Only a non-directory is ever replaced, and only the one at its name.
+    let replace = match src.path.file_name() {
+        Some(name) if overwrite => vec![dest.path.join(name)],
+        _ => Vec::new(),
+    };
+    Ok(Extraction {
+        archive: archive.to_path_buf(),
+        format,
+        root,
+        destination: dest.path.clone(),
+        replace,
+        skip: BTreeSet::new(),
+    })
+}
+
 /// Read every entry in the bookmarks directory, creating it if absent.
 /// Synchronous: one small directory of symlinks, no streaming. Returns the
 /// failure message rather than a command, so the caller can tell success from
@@ -859,6 +1825,19 @@ pub(super) fn read_bookmarks(dir: &Path) -> Result<Vec<PathInfo>, String> {
 fn existing_destination(dest: &PathInfo, src: &PathInfo) -> Option<Occupant> {
     let name = src.path.file_name()?;
     let destination = dest.path.join(name);
+    if src.remote().is_some() || dest.remote().is_some() {
+        // Nothing to resolve canonically on another host: the same location
+        // is the only way to find the source itself.
+        if src.remote() == dest.remote() && destination == src.path {
+            return None;
+        }
+        let stat = dest.backend().stat(&destination).ok()?;
+        return Some(if unix_mode::is_dir(stat.mode) {
+            Occupant::Directory
+        } else {
+            Occupant::Replaceable
+        });
+    }
     let metadata = destination.symlink_metadata().ok()?;
     // Pasting into the source's own directory finds the source itself, which is
     // no collision to ask about: the operation is refused outright, so offering
@@ -885,15 +1864,6 @@ fn is_same_entry(a: &Path, b: &Path) -> bool {
     }
 }
 
-/// Parses a chmod-style octal mode string. Returns `None` for non-octal input
-/// or values exceeding `0o7777` (the permission + setuid/setgid/sticky bits).
-fn parse_octal_mode(mode_str: &str) -> Option<u32> {
-    match u32::from_str_radix(mode_str, 8) {
-        Ok(mode) if mode <= 0o7777 => Some(mode),
-        _ => None,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -905,6 +1875,7 @@ mod tests {
 
     fn test_file_system(bookmarks: &TempDir, command_tx: Sender<Command>) -> FileSystem {
         FileSystem {
+            backend: Arc::new(backend::Local),
             // A temp path, so bookmark reads never touch the real config dir.
             bookmarks_dir: bookmarks.path().to_path_buf(),
             buffer_max_bytes: 64_000_000,
@@ -914,13 +1885,25 @@ mod tests {
             directory: None,
             previous_directory: None,
             current_load: None,
+            delete_summary: None,
+            sizing: None,
+            size_cache: sizes::SizeCache::default(),
+            disk_usage_scans: Vec::new(),
+            duplicate_scans: Vec::new(),
+            comparisons: Vec::new(),
+            preview: None,
+            peeks: HashMap::new(),
             reload_pending: false,
             current_search_generation: 0,
             next_generation: 0,
+            diff_template: String::new(),
             open_directory_template: String::new(),
             open_file_template: String::new(),
             open_filectrl_window_template: String::new(),
             pending_paste: None,
+            pending_template: None,
+            pending_extract: None,
+            protected_paths: ProtectedPaths::default(),
             search_max_depth: 20,
             search_max_results: 10_000,
             watcher: None,
@@ -1005,6 +1988,106 @@ mod tests {
             .unwrap_or_else(|| panic!("expected a conflict prompt, got {commands:?}"))
     }
 
+    #[test_case(&Command::ConfirmDelete ; "confirming")]
+    #[test_case(&Command::CancelPrompt  ; "dismissing")]
+    fn answering_the_delete_prompt_stops_its_count(answer: &Command) {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new("fs_summary");
+        let dir = PathInfo::try_from(fx.path()).unwrap();
+        file_system.handle_command(&Command::SummarizeDelete(vec![dir]));
+        let token = file_system.delete_summary.clone().unwrap();
+
+        file_system.handle_command(answer);
+
+        // A large tree can take longer to count than to answer about, and the
+        // walk has nothing left to tell once the prompt is gone.
+        assert!(token.is_cancelled());
+        assert!(file_system.delete_summary.is_none());
+    }
+
+    #[test]
+    fn a_symbolic_chmod_edits_each_entrys_own_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new("fs_chmod");
+        // The chmod refreshes the listing, which needs one to refresh.
+        file_system.run_once(Some(fx.path().to_path_buf())).unwrap();
+        let mut paths = Vec::new();
+        for (name, mode) in [("private", 0o600), ("shared", 0o664)] {
+            fs::write(fx.join(name), b"x").unwrap();
+            fs::set_permissions(fx.join(name), fs::Permissions::from_mode(mode)).unwrap();
+            paths.push(PathInfo::try_from(fx.join(name).as_path()).unwrap());
+        }
+
+        file_system.handle_command(&Command::Chmod {
+            paths,
+            mode: "u+x,o-w".to_string(),
+            recursive: false,
+        });
+
+        // Unlike an octal mode, the entries do not end up alike: each keeps
+        // whatever the clauses do not mention.
+        let mode_of = |name| fs::metadata(fx.join(name)).unwrap().mode() & 0o7777;
+        assert_eq!(0o700, mode_of("private"));
+        assert_eq!(0o764, mode_of("shared"));
+    }
+
+    #[test]
+    fn a_recursive_chmod_runs_as_a_task() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new("fs_chmod_recursive");
+        let root = fx.join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/a.txt"), b"x").unwrap();
+        fs::set_permissions(root.join("sub/a.txt"), fs::Permissions::from_mode(0o600)).unwrap();
+
+        file_system.handle_command(&Command::Chmod {
+            paths: vec![PathInfo::try_from(root.as_path()).unwrap()],
+            mode: "755 644".to_string(),
+            recursive: true,
+        });
+        await_terminal_task(&rx);
+
+        let mode_of = |path: &Path| fs::metadata(path).unwrap().mode() & 0o7777;
+        assert_eq!(0o755, mode_of(&root.join("sub")));
+        assert_eq!(0o644, mode_of(&root.join("sub/a.txt")));
+    }
+
+    #[test_case(false ; "immediate")]
+    #[test_case(true ; "recursive")]
+    fn an_unknown_owner_is_refused_before_anything_starts(recursive: bool) {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new("fs_chown");
+        fs::write(fx.join("a.txt"), b"x").unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::Chown {
+                paths: vec![PathInfo::try_from(fx.join("a.txt").as_path()).unwrap()],
+                owner: "no-such-user-here".to_string(),
+                recursive,
+            })
+            .into_commands();
+
+        // A name that resolves to nobody must not reach a single entry, and
+        // must not start a task that would fail on every one.
+        assert!(
+            matches!(&commands[..], [Command::AlertError(message)] if message.contains("Unknown user")),
+            "{commands:?}"
+        );
+        assert!(file_system.cancellables.is_empty());
+    }
+
     #[test]
     fn a_paste_where_nothing_starts_leaves_the_clipboard_alone() {
         let bookmarks = TempDir::reserved("fs_bookmarks");
@@ -1102,6 +2185,347 @@ mod tests {
         assert!(rx.try_recv().is_err());
     }
 
+    /// A file system viewing a fresh directory, with a template beside it.
+    fn template_fixture(name: &str) -> (TempDir, TempDir, FileSystem, PathInfo) {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new(name);
+        fs::create_dir_all(fx.join("cwd")).unwrap();
+        fs::write(fx.join("Template.txt"), b"template").unwrap();
+        file_system.directory = Some(PathInfo::try_from(fx.join("cwd").as_path()).unwrap());
+        let template = PathInfo::try_from(fx.join("Template.txt").as_path()).unwrap();
+        (bookmarks, fx, file_system, template)
+    }
+
+    #[test_case(ConflictChoice::Overwrite, b"template" ; "overwrite replaces it")]
+    #[test_case(ConflictChoice::Skip, b"taken" ; "skip leaves it")]
+    fn a_taken_template_name_asks_before_copying(choice: ConflictChoice, expected: &[u8]) {
+        let (_bookmarks, fx, mut file_system, template) = template_fixture("fs_template");
+        fs::write(fx.join("cwd/copy.txt"), b"taken").unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::CreateFromTemplate {
+                template,
+                name: "copy.txt".to_string(),
+            })
+            .into_commands();
+
+        assert_eq!(("copy.txt", true), conflict_prompt(&commands));
+        assert_eq!(
+            b"taken".to_vec(),
+            fs::read(fx.join("cwd/copy.txt")).unwrap()
+        );
+        file_system.handle_command(&Command::ResolveConflict(choice));
+        assert_eq!(
+            expected.to_vec(),
+            fs::read(fx.join("cwd/copy.txt")).unwrap()
+        );
+        // The answer is spent: a later conflict answer is not this copy's.
+        assert!(file_system.pending_template.is_none());
+    }
+
+    #[test]
+    fn dismissing_the_conflict_prompt_drops_the_template_copy() {
+        let (_bookmarks, fx, mut file_system, template) = template_fixture("fs_template_cancel");
+        fs::create_dir_all(fx.join("cwd/copy.txt")).unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::CreateFromTemplate {
+                template,
+                name: "copy.txt".to_string(),
+            })
+            .into_commands();
+        file_system.handle_command(&Command::CancelPrompt);
+
+        // A directory cannot be overwritten by a file.
+        assert_eq!(("copy.txt", false), conflict_prompt(&commands));
+        assert!(file_system.pending_template.is_none());
+    }
+
+    /// A current directory holding `photos.zip`, whose entries are the given
+    /// files, each holding its own name.
+    fn extract_fixture(
+        name: &str,
+        entries: &[&str],
+    ) -> (
+        TempDir,
+        TempDir,
+        FileSystem,
+        PathInfo,
+        std::sync::mpsc::Receiver<Command>,
+    ) {
+        use std::io::Write;
+
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let fx = TempDir::new(name);
+        fs::create_dir_all(fx.join("cwd")).unwrap();
+        let path = fx.join("cwd/photos.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for entry in entries {
+            zip.start_file(*entry, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(entry.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        file_system.directory = Some(PathInfo::try_from(fx.join("cwd").as_path()).unwrap());
+        let archive = PathInfo::try_from(path.as_path()).unwrap();
+        (bookmarks, fx, file_system, archive, rx)
+    }
+
+    #[test]
+    fn an_extract_into_a_taken_name_asks_before_writing_anything() {
+        let (_bookmarks, fx, mut file_system, archive, rx) =
+            extract_fixture("fs_extract", &["a.txt"]);
+        fs::write(fx.join("cwd/photos"), b"taken").unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::Extract {
+                archive,
+                here: false,
+            })
+            .into_commands();
+
+        assert_eq!(("photos", true), conflict_prompt(&commands));
+        assert_eq!(b"taken".to_vec(), fs::read(fx.join("cwd/photos")).unwrap());
+        file_system.handle_command(&Command::ResolveConflict(ConflictChoice::Overwrite));
+        await_terminal_task(&rx);
+        assert_eq!(
+            b"a.txt".to_vec(),
+            fs::read(fx.join("cwd/photos/a.txt")).unwrap()
+        );
+        assert!(file_system.pending_extract.is_none());
+    }
+
+    #[test]
+    fn skipping_the_new_directorys_name_extracts_nothing() {
+        let (_bookmarks, fx, mut file_system, archive, _rx) =
+            extract_fixture("fs_extract_skip", &["a.txt"]);
+        fs::create_dir(fx.join("cwd/photos")).unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::Extract {
+                archive,
+                here: false,
+            })
+            .into_commands();
+        file_system.handle_command(&Command::ResolveConflict(ConflictChoice::Skip));
+
+        // A directory is never offered a replace, nor merged into.
+        assert_eq!(("photos", false), conflict_prompt(&commands));
+        assert!(file_system.cancellables.is_empty());
+        assert!(!fx.join("cwd/photos/a.txt").exists());
+    }
+
+    #[test]
+    fn an_extract_here_asks_about_each_taken_name_until_an_all_answer() {
+        let (_bookmarks, fx, mut file_system, archive, rx) =
+            extract_fixture("fs_extract_here", &["a.txt", "b.txt", "new.txt"]);
+        let cwd = fx.join("cwd");
+        fs::write(cwd.join("a.txt"), b"old").unwrap();
+        fs::write(cwd.join("b.txt"), b"old").unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::ArchiveSurveyed {
+                archive: archive.path,
+                destination: cwd.clone(),
+                names: vec!["a.txt".into(), "b.txt".into(), "new.txt".into()],
+            })
+            .into_commands();
+        let answered = file_system
+            .handle_command(&Command::ResolveConflict(ConflictChoice::SkipAll))
+            .into_commands();
+
+        assert_eq!(("a.txt", true), conflict_prompt(&commands));
+        // The standing skip settles b.txt without asking.
+        assert!(
+            !answered
+                .iter()
+                .any(|command| matches!(command, Command::OpenPrompt(_))),
+            "{answered:?}"
+        );
+        await_terminal_task(&rx);
+        assert_eq!(b"old".to_vec(), fs::read(cwd.join("a.txt")).unwrap());
+        assert_eq!(b"old".to_vec(), fs::read(cwd.join("b.txt")).unwrap());
+        assert_eq!(b"new.txt".to_vec(), fs::read(cwd.join("new.txt")).unwrap());
+    }
+
+    #[test]
+    fn dismissing_the_conflict_prompt_drops_the_extract() {
+        let (_bookmarks, fx, mut file_system, archive, _rx) =
+            extract_fixture("fs_extract_cancel", &["a.txt"]);
+        fs::write(fx.join("cwd/photos"), b"taken").unwrap();
+
+        file_system.handle_command(&Command::Extract {
+            archive,
+            here: false,
+        });
+        file_system.handle_command(&Command::CancelPrompt);
+
+        assert!(file_system.pending_extract.is_none());
+        assert!(file_system.cancellables.is_empty());
+    }
+
+    #[test]
+    fn copying_out_of_a_browsed_archive_extracts_the_entry() {
+        let (_bookmarks, fx, mut file_system, archive, rx) =
+            extract_fixture("fs_archive_copy_out", &["dir/a.txt", "b.txt"]);
+        let dest = PathInfo::try_from(fx.join("cwd").as_path()).unwrap();
+        let root = PathInfo::archive_root(&archive);
+        let dir = PathInfo::in_archive(
+            &root,
+            &archive::Child {
+                name: "dir".into(),
+                kind: archive::EntryKind::Directory,
+                size: 0,
+                mode: None,
+                modified: None,
+            },
+        );
+
+        let moved = file_system
+            .handle_command(&Command::Move {
+                srcs: vec![dir.clone()],
+                dest: dest.clone(),
+            })
+            .into_commands();
+        file_system.handle_command(&Command::Copy {
+            srcs: vec![dir],
+            dest,
+        });
+        await_terminal_task(&rx);
+
+        // Nothing is ever taken out of an archive.
+        assert!(
+            matches!(moved.first(), Some(Command::AlertError(_))),
+            "{moved:?}"
+        );
+        assert_eq!(
+            b"dir/a.txt".to_vec(),
+            fs::read(fx.join("cwd/dir/a.txt")).unwrap()
+        );
+        assert!(!fx.join("cwd/b.txt").exists());
+    }
+
+    #[test]
+    fn nothing_is_pasted_into_a_browsed_archive() {
+        let (_bookmarks, fx, mut file_system, archive, _rx) =
+            extract_fixture("fs_archive_paste_in", &["a.txt"]);
+        fs::write(fx.join("cwd/b.txt"), b"b").unwrap();
+        let src = PathInfo::try_from(fx.join("cwd/b.txt").as_path()).unwrap();
+
+        let commands = file_system
+            .handle_command(&Command::Copy {
+                srcs: vec![src],
+                dest: PathInfo::archive_root(&archive),
+            })
+            .into_commands();
+
+        assert!(
+            matches!(commands[..], [Command::AlertWarn(_)]),
+            "{commands:?}"
+        );
+        assert!(file_system.cancellables.is_empty());
+    }
+
+    /// A local directory holding a file and, below it, one with its own
+    /// permissions, and a remote home directory on a stand-in host.
+    fn remote_fixture(label: &str) -> (TempDir, Arc<backend::Memory>, PathInfo, PathInfo) {
+        let dir = TempDir::new(label);
+        fs::create_dir_all(dir.join("tree/sub")).unwrap();
+        fs::write(dir.join("tree/a.txt"), b"a").unwrap();
+        fs::write(dir.join("tree/sub/b.txt"), b"bb").unwrap();
+        fs::set_permissions(
+            dir.join("tree/sub/b.txt"),
+            std::os::unix::fs::PermissionsExt::from_mode(0o640),
+        )
+        .unwrap();
+        let tree = PathInfo::try_from(dir.join("tree").as_path()).unwrap();
+        let memory = Arc::new(backend::Memory::default());
+        memory.create_dir_all(Path::new("/home")).unwrap();
+        let remote = sftp::stand_in(label, Arc::clone(&memory));
+        let home = PathInfo::read_remote(&remote, Path::new("/home")).unwrap();
+        (dir, memory, tree, home)
+    }
+
+    #[test]
+    fn a_tree_is_copied_to_another_host_and_back() {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let (dir, memory, tree, home) = remote_fixture("fs_remote_round_trip");
+
+        file_system.handle_command(&Command::Copy {
+            srcs: vec![tree],
+            dest: home.clone(),
+        });
+        await_terminal_task(&rx);
+
+        let uploaded = Path::new("/home/tree/sub/b.txt");
+        assert_eq!(2, memory.stat(uploaded).unwrap().size);
+        assert_eq!(0o640, memory.stat(uploaded).unwrap().mode & 0o7777);
+
+        fs::create_dir(dir.join("back")).unwrap();
+        let remote_tree = PathInfo::read_remote(home.remote().unwrap(), Path::new("/home/tree"));
+        file_system.handle_command(&Command::Copy {
+            srcs: vec![remote_tree.unwrap()],
+            dest: PathInfo::try_from(dir.join("back").as_path()).unwrap(),
+        });
+        await_terminal_task(&rx);
+
+        assert_eq!(
+            b"a".to_vec(),
+            fs::read(dir.join("back/tree/a.txt")).unwrap()
+        );
+        assert_eq!(
+            b"bb".to_vec(),
+            fs::read(dir.join("back/tree/sub/b.txt")).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_name_taken_on_the_other_host_asks_before_copying() {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let (_dir, memory, tree, home) = remote_fixture("fs_remote_taken");
+        memory.file("/home/tree", b"taken");
+
+        let commands = file_system
+            .handle_command(&Command::Copy {
+                srcs: vec![tree],
+                dest: home,
+            })
+            .into_commands();
+
+        assert_eq!(("tree", true), conflict_prompt(&commands));
+        assert!(file_system.cancellables.is_empty());
+    }
+
+    #[test]
+    fn nothing_is_moved_between_hosts() {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let (dir, _memory, tree, home) = remote_fixture("fs_remote_move");
+
+        let commands = file_system
+            .handle_command(&Command::Move {
+                srcs: vec![tree],
+                dest: home,
+            })
+            .into_commands();
+
+        assert!(
+            matches!(commands[..], [Command::AlertError(_), ..]),
+            "{commands:?}"
+        );
+        assert!(dir.join("tree/a.txt").exists());
+        assert!(file_system.cancellables.is_empty());
+    }
+
     // ── the paste decision, with no filesystem and no worker ─────────────────
 
     fn pending(standing: Option<ConflictChoice>) -> PendingPaste {
@@ -1810,26 +3234,56 @@ mod tests {
         assert_eq!(Some(first.path), previous_path(&file_system));
     }
 
+    #[test]
+    fn a_configured_diff_opener_is_run_in_the_foreground_in_place_of_the_viewer() {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let root = TempDir::new("fs_diff_opener");
+        fs::write(root.join("left"), "a\n").unwrap();
+        fs::write(root.join("right b"), "b\n").unwrap();
+        file_system.diff_template = "nvim -d %s %s".into();
+
+        let result = file_system.diff_files(&root.join("left"), &root.join("right b"));
+
+        let expected = format!(
+            "nvim -d {} '{}'",
+            root.join("left").display(),
+            root.join("right b").display()
+        );
+        assert_eq!(
+            vec![Command::RunInForeground(expected.into())],
+            result.into_commands()
+        );
+        // Nothing was launched in the background to report on later.
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn only_the_latest_preview_is_read() {
+        let bookmarks = TempDir::reserved("fs_bookmarks");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut file_system = test_file_system(&bookmarks, tx);
+        let root = TempDir::new("fs_preview");
+        fs::write(root.join("a"), "a").unwrap();
+        fs::write(root.join("b"), "b").unwrap();
+        let file = |name: &str| PathInfo::try_from(root.join(name).as_path()).unwrap();
+
+        file_system.preview_file(&file("a"));
+        file_system.preview_file(&file("b"));
+
+        let Ok(Command::Previewed { path, .. }) = rx.recv_timeout(Duration::from_secs(5)) else {
+            panic!("expected Previewed");
+        };
+        assert_eq!(root.join("b"), path);
+        // The cursor moved on from `a` before it settled, so it is never read.
+        assert!(rx.recv_timeout(CURSOR_SETTLE_DELAY * 4).is_err());
+    }
+
     fn previous_path(file_system: &FileSystem) -> Option<PathBuf> {
         file_system
             .previous_directory
             .as_ref()
             .map(|info| info.path.clone())
     }
-
-    #[test_case("644" => Some(0o644) ; "three digits")]
-    #[test_case("755" => Some(0o755) ; "three digits with the execute bit")]
-    #[test_case("0" => Some(0o0) ; "a single zero")]
-    #[test_case("7777" => Some(0o7777) ; "the largest accepted value")]
-    #[test_case("4755" => Some(0o4755) ; "a setuid bit")]
-    #[test_case("10000" => None ; "one past the largest")]
-    #[test_case("77777" => None ; "five digits")]
-    #[test_case("888" => None ; "digits outside octal")]
-    #[test_case("0o644" => None ; "a rust literal prefix")]
-    #[test_case("rwx" => None ; "symbolic notation")]
-    #[test_case("" => None ; "empty")]
-    #[test_case("-1" => None ; "negative")]
-    fn parse_octal_mode_accepts_only_a_mode(mode: &str) -> Option<u32> {
-        parse_octal_mode(mode)
-    }
 }
@@ -0,0 +1,940 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    os::unix::ffi::OsStringExt,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDateTime};
+use flate2::{Compression, read::MultiGzDecoder, write::GzEncoder};
+use lzma_rust2::{XzOptions, XzReader, XzWriter};
+use tar::{Builder, EntryType, Header, HeaderMode};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::backend::Stat;
+
+/// zstd's own default, which `zstd -q` also uses: most of the ratio of the
+/// higher levels at a fraction of their time.
+const ZSTD_LEVEL: i32 = 3;
+
+/// xz's own default, which `xz` and `tar -J` also use.
+const XZ_PRESET: u32 = 6;
+
+/// The extensions each format goes by, checked in order, so `.tar.gz` is found
+/// before the `.gz` it ends in could be mistaken for anything else.
+const EXTENSIONS: [(&str, ArchiveFormat); 8] = [
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tzst", ArchiveFormat::TarZst),
+    (".tar", ArchiveFormat::Tar),
+    (".zip", ArchiveFormat::Zip),
+];
+
+/// The archive formats a compress writes and an extract reads, told apart by
+/// the archive's name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// The format `name` asks for by its extension, in any case: `.tar`,
+    /// `.tar.gz` or `.tgz`, `.tar.xz` or `.txz`, `.tar.zst` or `.tzst`, or
+    /// `.zip`.
+    pub fn from_name(name: &str) -> Result<Self> {
+        split_extension(name)
+            .map(|(_, format)| format)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Cannot tell the format of {name:?}: end it in .tar, .tar.gz, .tar.xz, .tar.zst or .zip"
+                )
+            })
+    }
+
+    /// `name` without the extension its format goes by: the name of the
+    /// directory an archive is extracted into, as `a.tar.gz` unpacks into `a`.
+    pub fn stem(name: &str) -> Option<&str> {
+        split_extension(name).map(|(stem, _)| stem)
+    }
+}
+
+fn split_extension(name: &str) -> Option<(&str, ArchiveFormat)> {
+    EXTENSIONS.into_iter().find_map(|(extension, format)| {
+        // Longer than the extension, so a name that is nothing but one
+        // (".zip") is not taken for an archive with no name. Compared as
+        // bytes: the extension is ASCII, so where it starts is a character
+        // boundary whatever comes before it.
+        let start = name
+            .len()
+            .checked_sub(extension.len())
+            .filter(|&start| start > 0)?;
+        name.as_bytes()[start..]
+            .eq_ignore_ascii_case(extension.as_bytes())
+            .then(|| (&name[..start], format))
+    })
+}
+
+/// A tar stream's compression, finished explicitly so that a failure writing
+/// the last block is reported rather than lost in a `Drop`.
+pub enum Compressor {
+    None(File),
+    Gzip(GzEncoder<File>),
+    // Boxed: an xz encoder holds its match finder's tables inline.
+    Xz(Box<XzWriter<File>>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Compressor {
+    fn finish(self) -> io::Result<File> {
+        match self {
+            Compressor::None(file) => Ok(file),
+            Compressor::Gzip(encoder) => encoder.finish(),
+            Compressor::Xz(encoder) => encoder.finish(),
+            Compressor::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl Write for Compressor {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Compressor::None(file) => file.write(buf),
+            Compressor::Gzip(encoder) => encoder.write(buf),
+            Compressor::Xz(encoder) => encoder.write(buf),
+            Compressor::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Compressor::None(file) => file.flush(),
+            Compressor::Gzip(encoder) => encoder.flush(),
+            Compressor::Xz(encoder) => encoder.flush(),
+            Compressor::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Writes the entries of a new archive, whichever its format. Names are
+/// relative paths inside the archive, with `/` between components; entries
+/// are added parents first, as `tar` and `zip` add them.
+pub enum ArchiveWriter {
+    Tar(Builder<Compressor>),
+    // Boxed: a zip writer carries a few kilobytes of buffers and tables.
+    Zip(Box<ZipWriter<File>>),
+}
+
+impl ArchiveWriter {
+    /// Creates the archive at `path`, which must not exist yet: a compress
+    /// never replaces anything. The archive itself is a local `File`, not a
+    /// backend's writer: a zip seeks back to write its directory at the end.
+    pub fn create(path: &Path, format: ArchiveFormat) -> io::Result<Self> {
+        let file = File::create_new(path)?;
+        let compressor = match format {
+            ArchiveFormat::Zip => {
+                return Ok(Self::Zip(Box::new(
+                    ZipWriter::new(file).set_auto_large_file(),
+                )));
+            }
+            ArchiveFormat::Tar => Compressor::None(file),
+            ArchiveFormat::TarGz => Compressor::Gzip(GzEncoder::new(file, Compression::default())),
+            ArchiveFormat::TarXz => Compressor::Xz(Box::new(XzWriter::new(
+                file,
+                XzOptions::with_preset(XZ_PRESET),
+            )?)),
+            ArchiveFormat::TarZst => Compressor::Zstd(zstd::Encoder::new(file, ZSTD_LEVEL)?),
+        };
+        let mut builder = Builder::new(compressor);
+        // Owners, modes and times as they are, as `tar` records them.
+        builder.mode(HeaderMode::Complete);
+        Ok(Self::Tar(builder))
+    }
+
+    pub fn add_directory(&mut self, name: &Path, stat: &Stat) -> io::Result<()> {
+        match self {
+            Self::Tar(builder) => {
+                let mut header = tar_header(stat);
+                builder.append_data(&mut header, name, io::empty())
+            }
+            Self::Zip(writer) => {
+                let name = format!("{}/", zip_name(name)?);
+                Ok(writer.add_directory(name, zip_options(stat))?)
+            }
+        }
+    }
+
+    /// Stores a symlink as a link to `target`, which is not followed, so the
+    /// archive holds what the tree holds.
+    pub fn add_symlink(&mut self, name: &Path, target: &Path, stat: &Stat) -> io::Result<()> {
+        match self {
+            Self::Tar(builder) => {
+                let mut header = tar_header(stat);
+                builder.append_link(&mut header, name, target)
+            }
+            Self::Zip(writer) => {
+                let target = target.to_str().ok_or_else(|| not_utf8("target"))?;
+                Ok(writer.add_symlink(zip_name(name)?, target, zip_options(stat))?)
+            }
+        }
+    }
+
+    /// Stores a regular file whose contents `contents` reads. A tar header
+    /// records the size up front, so `contents` must yield exactly
+    /// `stat.size` bytes.
+    pub fn add_file(
+        &mut self,
+        name: &Path,
+        stat: &Stat,
+        contents: &mut dyn Read,
+    ) -> io::Result<()> {
+        match self {
+            Self::Tar(builder) => {
+                let mut header = tar_header(stat);
+                builder.append_data(&mut header, name, contents)
+            }
+            Self::Zip(writer) => {
+                writer.start_file(zip_name(name)?, zip_options(stat))?;
+                io::copy(contents, writer)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes the end of the archive, and the end of its compression, and
+    /// flushes it to the file.
+    pub fn finish(self) -> io::Result<()> {
+        let file = match self {
+            Self::Tar(builder) => builder.into_inner()?.finish()?,
+            Self::Zip(writer) => writer.finish()?,
+        };
+        file.sync_all()
+    }
+}
+
+/// What one entry of an archive holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    Directory,
+    File,
+    /// A symlink to the target, which is stored as it is and not followed.
+    Symlink(PathBuf),
+    /// A tar hard link to an earlier entry, named as the archive names it.
+    HardLink(PathBuf),
+    /// A device, FIFO or anything else an extract does not create.
+    Other,
+}
+
+/// One entry as the archive stores it. The name is the archive's own, which
+/// nothing has checked yet: see [`relative_path`].
+pub struct ArchiveEntry<'a> {
+    pub name: PathBuf,
+    pub kind: EntryKind,
+    /// A file's size, unpacked.
+    pub size: u64,
+    /// The permission bits, when the archive records them.
+    pub mode: Option<u32>,
+    pub modified: Option<SystemTime>,
+    /// A file's contents. Empty for every other kind.
+    pub contents: &'a mut dyn Read,
+}
+
+/// Reads the entries of the archive `source` holds, in the order it stores
+/// them, handing each to `visit`, which returns whether to carry on.
+///
+/// An `Err` from `visit` ends the read and is returned, as is any error
+/// reading the archive itself: past a corrupt block there is nothing left in a
+/// compressed stream to trust.
+pub fn read_entries<R: Read + Seek>(
+    source: R,
+    format: ArchiveFormat,
+    visit: &mut dyn FnMut(ArchiveEntry<'_>) -> io::Result<bool>,
+) -> io::Result<()> {
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::Zip => return read_zip_entries(source, visit),
+        ArchiveFormat::Tar => Box::new(source),
+        // Multi: `cat a.tar.gz b.tar.gz` is a valid gzip stream, and so is
+        // one `pigz` wrote in several members.
+        ArchiveFormat::TarGz => Box::new(MultiGzDecoder::new(source)),
+        ArchiveFormat::TarXz => Box::new(XzReader::new(source, true)),
+        ArchiveFormat::TarZst => Box::new(zstd::Decoder::new(source)?),
+    };
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            EntryType::Directory => EntryKind::Directory,
+            EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => EntryKind::File,
+            EntryType::Symlink => EntryKind::Symlink(link_name(&entry)?),
+            EntryType::Link => EntryKind::HardLink(link_name(&entry)?),
+            // Metadata for the entries around it, which the tar crate has
+            // already applied where it understands it.
+            EntryType::XGlobalHeader
+            | EntryType::XHeader
+            | EntryType::GNULongName
+            | EntryType::GNULongLink => continue,
+            _ => EntryKind::Other,
+        };
+        let size = header.size().unwrap_or(0);
+        let mode = header.mode().ok().map(|mode| mode & 0o7777);
+        let modified = header
+            .mtime()
+            .ok()
+            .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+        let name = entry.path()?.into_owned();
+        let entry = ArchiveEntry {
+            name,
+            kind,
+            size,
+            mode,
+            modified,
+            contents: &mut entry,
+        };
+        if !visit(entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn link_name<R: Read>(entry: &tar::Entry<'_, R>) -> io::Result<PathBuf> {
+    entry
+        .link_name()?
+        .map(std::borrow::Cow::into_owned)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "a link has no target"))
+}
+
+fn read_zip_entries<R: Read + Seek>(
+    source: R,
+    visit: &mut dyn FnMut(ArchiveEntry<'_>) -> io::Result<bool>,
+) -> io::Result<()> {
+    let mut zip = ZipArchive::new(source)?;
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index)?;
+        let kind = if file.is_dir() {
+            EntryKind::Directory
+        } else if file.is_symlink() {
+            // Zip stores a symlink as a file whose contents are the target.
+            let mut target = Vec::new();
+            file.read_to_end(&mut target)?;
+            EntryKind::Symlink(PathBuf::from(OsString::from_vec(target)))
+        } else {
+            EntryKind::File
+        };
+        let entry = ArchiveEntry {
+            name: PathBuf::from(file.name()),
+            kind,
+            size: file.size(),
+            mode: file.unix_mode().map(|mode| mode & 0o7777),
+            modified: file.last_modified().and_then(local_time),
+            contents: &mut file,
+        };
+        if !visit(entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Zip times are local, with no zone, so they are read back in this one. The
+/// earlier of the two an hour repeated by a clock change could mean.
+fn local_time(time: zip::DateTime) -> Option<SystemTime> {
+    let time = NaiveDateTime::try_from(time).ok()?;
+    time.and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// The path an archive entry named `name` takes below the directory it is
+/// extracted into, or `None` when the name would take it anywhere else: an
+/// absolute path, or one that climbs with `..`. `.` components are dropped,
+/// as `tar` writes them for `tar -cf a.tar .`; a name that is nothing else is
+/// the directory itself and comes back empty.
+pub fn relative_path(name: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(relative)
+}
+
+/// The names an extract of the archive at `path` would create directly inside
+/// the directory it is extracted into, each once, in the order the archive
+/// first reaches them. Entries [`relative_path`] refuses are left out: they
+/// create nothing.
+pub fn top_level_names(path: &Path, format: ArchiveFormat) -> io::Result<Vec<OsString>> {
+    let mut names: Vec<OsString> = Vec::new();
+    read_entries(File::open(path)?, format, &mut |entry| {
+        let top = relative_path(&entry.name).and_then(|relative| {
+            relative
+                .components()
+                .next()
+                .map(|c| c.as_os_str().to_owned())
+        });
+        if let Some(top) = top
+            && !names.contains(&top)
+        {
+            names.push(top);
+        }
+        Ok(true)
+    })?;
+    Ok(names)
+}
+
+/// One entry of an archive directory being browsed: a name directly inside it,
+/// with what the archive records about it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Child {
+    pub name: OsString,
+    pub kind: EntryKind,
+    pub size: u64,
+    pub mode: Option<u32>,
+    pub modified: Option<SystemTime>,
+}
+
+/// How many archives' indexes are kept, so moving between a few archives
+/// does not read each again, while a long session does not hold on to every
+/// archive it ever looked into.
+const MAX_INDEXED_ARCHIVES: usize = 4;
+
+/// What an archive's index was built from: a rewritten archive has a new size
+/// or modification time, and is indexed again.
+type Stamp = (u64, Option<SystemTime>);
+
+/// Every directory of the archive at `path`, each with the entries directly
+/// inside it.
+struct ArchiveIndex {
+    path: PathBuf,
+    stamp: Stamp,
+    directories: HashMap<PathBuf, Vec<Child>>,
+}
+
+/// The archives indexed in this process, the most recently used last.
+fn indexes() -> &'static Mutex<Vec<Arc<ArchiveIndex>>> {
+    static INDEXES: OnceLock<Mutex<Vec<Arc<ArchiveIndex>>>> = OnceLock::new();
+    INDEXES.get_or_init(Mutex::default)
+}
+
+/// The entries directly inside `directory`, a path inside the archive at
+/// `path` (empty for its top level), in the order the archive first reaches
+/// them.
+///
+/// The whole archive is read once, into an index every directory of it is
+/// then listed from, until it is rewritten: a compressed tar can only be read
+/// front to back, so listing one directory costs as much as listing them all.
+/// `cancelled` is asked between entries while the index is built, and ends the
+/// read early with an `Interrupted` error, leaving nothing indexed.
+///
+/// An archive need not store its directories: `a/b/c.txt` on its own implies
+/// `a` and `a/b`, which are listed as directories with nothing recorded about
+/// them. A name stored twice is listed as the later one, the one an extract
+/// leaves behind. Names an extract would refuse are left out.
+pub fn children(
+    path: &Path,
+    format: ArchiveFormat,
+    directory: &Path,
+    cancelled: &dyn Fn() -> bool,
+) -> io::Result<Vec<Child>> {
+    index(path, format, cancelled)?
+        .directories
+        .get(directory)
+        .cloned()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the archive holds no {}", directory.display()),
+            )
+        })
+}
+
+/// The index of the archive at `path`, built unless one is kept for it as it
+/// is now.
+fn index(
+    path: &Path,
+    format: ArchiveFormat,
+    cancelled: &dyn Fn() -> bool,
+) -> io::Result<Arc<ArchiveIndex>> {
+    let metadata = fs::metadata(path)?;
+    let stamp = (metadata.len(), metadata.modified().ok());
+    {
+        let mut indexes = indexes().lock().unwrap();
+        if let Some(position) = indexes.iter().position(|index| index.path == path) {
+            let kept = indexes.remove(position);
+            if kept.stamp == stamp {
+                indexes.push(Arc::clone(&kept));
+                return Ok(kept);
+            }
+        }
+    }
+    // Built without the lock held, so listing another archive meanwhile does
+    // not wait on this one.
+    let index = Arc::new(ArchiveIndex {
+        path: path.to_path_buf(),
+        stamp,
+        directories: index_directories(path, format, cancelled)?,
+    });
+    let mut indexes = indexes().lock().unwrap();
+    indexes.retain(|known| known.path != path);
+    if indexes.len() == MAX_INDEXED_ARCHIVES {
+        indexes.remove(0);
+    }
+    indexes.push(Arc::clone(&index));
+    Ok(index)
+}
+
+fn index_directories(
+    path: &Path,
+    format: ArchiveFormat,
+    cancelled: &dyn Fn() -> bool,
+) -> io::Result<HashMap<PathBuf, Vec<Child>>> {
+    let mut directories: HashMap<PathBuf, Vec<Child>> = HashMap::new();
+    // Where each name sits in its directory's list, so a name stored again
+    // replaces its first listing rather than being searched for.
+    let mut positions: HashMap<PathBuf, usize> = HashMap::new();
+    directories.insert(PathBuf::new(), Vec::new());
+    read_entries(File::open(path)?, format, &mut |entry| {
+        if cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the listing was cancelled",
+            ));
+        }
+        let Some(relative) = relative_path(&entry.name) else {
+            return Ok(true);
+        };
+        let names: Vec<_> = relative.iter().collect();
+        let mut directory = PathBuf::new();
+        for (depth, name) in names.iter().enumerate() {
+            let implied = depth + 1 < names.len();
+            let child_path = directory.join(name);
+            let child = if implied {
+                Child {
+                    name: name.to_os_string(),
+                    kind: EntryKind::Directory,
+                    size: 0,
+                    mode: None,
+                    modified: None,
+                }
+            } else {
+                Child {
+                    name: name.to_os_string(),
+                    kind: entry.kind.clone(),
+                    size: entry.size,
+                    mode: entry.mode,
+                    modified: entry.modified,
+                }
+            };
+            if child.kind == EntryKind::Directory {
+                directories.entry(child_path.clone()).or_default();
+            }
+            let listing = directories.entry(directory).or_default();
+            match positions.get(&child_path) {
+                // An entry below a name says no more than that it is a
+                // directory, which whatever is known about it already does.
+                Some(_) if implied => {}
+                Some(&index) => listing[index] = child,
+                None => {
+                    positions.insert(child_path.clone(), listing.len());
+                    listing.push(child);
+                }
+            }
+            directory = child_path;
+        }
+        Ok(true)
+    })?;
+    Ok(directories)
+}
+
+/// What `set_metadata_in_mode` would record in `HeaderMode::Complete`, from a
+/// `Stat`, which any backend can give, rather than a `Metadata`.
+fn tar_header(stat: &Stat) -> Header {
+    let mut header = Header::new_gnu();
+    let seconds = stat
+        .modified
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs());
+    header.set_mtime(seconds);
+    header.set_uid(u64::from(stat.uid));
+    header.set_gid(u64::from(stat.gid));
+    header.set_mode(stat.mode);
+    header.set_entry_type(match stat.mode & 0o170_000 {
+        0o100_000 => EntryType::file(),
+        0o120_000 => EntryType::symlink(),
+        0o020_000 => EntryType::character_special(),
+        0o060_000 => EntryType::block_special(),
+        0o040_000 => EntryType::dir(),
+        0o010_000 => EntryType::fifo(),
+        _ => EntryType::new(b' '),
+    });
+    header.set_size(if unix_mode::is_file(stat.mode) {
+        stat.size
+    } else {
+        0
+    });
+    if let Some(gnu) = header.as_gnu_mut() {
+        gnu.set_device_major(0);
+        gnu.set_device_minor(0);
+    }
+    header
+}
+
+/// Deflated, as `zip` stores files by default, with the entry's mode and
+/// modification time. A time zip cannot hold (before 1980) is left at zip's
+/// earliest.
+fn zip_options(stat: &Stat) -> SimpleFileOptions {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(stat.mode & 0o7777);
+    match stat.modified.and_then(zip_time) {
+        Some(time) => options.last_modified_time(time),
+        None => options,
+    }
+}
+
+/// Zip times are local, with no zone, as `zip` writes them.
+fn zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    zip::DateTime::try_from(DateTime::<Local>::from(time).naive_local()).ok()
+}
+
+/// Zip names are text, so a name that is not valid UTF-8 cannot be stored
+/// as it is, and is refused rather than mangled.
+fn zip_name(name: &Path) -> io::Result<&str> {
+    name.to_str().ok_or_else(|| not_utf8("name"))
+}
+
+/// Refused before anything of the entry is written, so the caller can leave
+/// the entry out and carry on.
+fn not_utf8(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidFilename,
+        format!("its {what} is not valid UTF-8, which zip requires"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test_case("a.tar" => Some(ArchiveFormat::Tar) ; "tar")]
+    #[test_case("a.tar.gz" => Some(ArchiveFormat::TarGz) ; "tar gz")]
+    #[test_case("a.tgz" => Some(ArchiveFormat::TarGz) ; "tgz")]
+    #[test_case("a.tar.zst" => Some(ArchiveFormat::TarZst) ; "tar zst")]
+    #[test_case("a.tzst" => Some(ArchiveFormat::TarZst) ; "tzst")]
+    #[test_case("a.tar.xz" => Some(ArchiveFormat::TarXz) ; "tar xz")]
+    #[test_case("a.txz" => Some(ArchiveFormat::TarXz) ; "txz")]
+    #[test_case("a.zip" => Some(ArchiveFormat::Zip) ; "zip")]
+    #[test_case("A.TAR.GZ" => Some(ArchiveFormat::TarGz) ; "upper case")]
+    #[test_case("a.gz" => None ; "gzip without tar")]
+    #[test_case(".zip" => None ; "nothing but an extension")]
+    #[test_case("a" => None ; "no extension")]
+    fn from_name_cases(name: &str) -> Option<ArchiveFormat> {
+        ArchiveFormat::from_name(name).ok()
+    }
+
+    #[test_case("photos.tar.gz" => Some("photos") ; "tar gz")]
+    #[test_case("A.ZIP" => Some("A") ; "upper case")]
+    // Only the archive extension goes: the rest is the name it was given.
+    #[test_case("v1.2.tar.xz" => Some("v1.2") ; "dots before the extension")]
+    #[test_case("notes.txt" => None ; "not an archive")]
+    fn stem_cases(name: &str) -> Option<&str> {
+        ArchiveFormat::stem(name)
+    }
+
+    #[test_case("a/b" => Some(PathBuf::from("a/b")) ; "nested")]
+    // `tar -cf a.tar .` names every entry from `./`.
+    #[test_case("./a/./b" => Some(PathBuf::from("a/b")) ; "current directory components")]
+    #[test_case("./" => Some(PathBuf::new()) ; "the destination itself")]
+    #[test_case("../a" => None ; "climbs out")]
+    #[test_case("a/../../b" => None ; "climbs out from below")]
+    // Refused even where it would end up inside: nothing is gained by working
+    // out where a `..` lands, and `tar` refuses it too.
+    #[test_case("a/../b" => None ; "climbs back in")]
+    #[test_case("/etc/passwd" => None ; "absolute")]
+    fn relative_path_cases(name: &str) -> Option<PathBuf> {
+        relative_path(Path::new(name))
+    }
+
+    /// A zip written with the given names, each a file holding its own name,
+    /// except a name ending in `/`, which is a directory.
+    fn zip_with(fx: &TempDir, names: &[&str]) -> PathBuf {
+        let path = fx.join("a.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for name in names {
+            if name.ends_with('/') {
+                zip.add_directory(*name, SimpleFileOptions::default())
+                    .unwrap();
+            } else {
+                zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(name.as_bytes()).unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn the_top_level_names_leave_out_what_would_land_elsewhere() {
+        let fx = TempDir::new("archive_top_level");
+        let archive = zip_with(
+            &fx,
+            &["dir/", "dir/a.txt", "../evil.txt", "./b.txt", "c/d.txt"],
+        );
+
+        let names = top_level_names(&archive, ArchiveFormat::Zip).unwrap();
+
+        assert_eq!(vec!["dir", "b.txt", "c"], names);
+    }
+
+    #[test]
+    fn children_lists_one_level_implying_directories_an_archive_left_out() {
+        let fx = TempDir::new("archive_children");
+        let archive = zip_with(&fx, &["a/b/c.txt", "a/d.txt", "e.txt", "a/b/"]);
+        let names = |directory: &str| -> Vec<(String, EntryKind)> {
+            children(&archive, ArchiveFormat::Zip, Path::new(directory), &|| {
+                false
+            })
+            .unwrap()
+            .into_iter()
+            .map(|child| (child.name.to_string_lossy().into_owned(), child.kind))
+            .collect()
+        };
+
+        assert_eq!(
+            vec![
+                ("a".to_string(), EntryKind::Directory),
+                ("e.txt".to_string(), EntryKind::File)
+            ],
+            names("")
+        );
+        // `b`, implied before it is stored, is listed once.
+        assert_eq!(
+            vec![
+                ("b".to_string(), EntryKind::Directory),
+                ("d.txt".to_string(), EntryKind::File)
+            ],
+            names("a")
+        );
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            children(&archive, ArchiveFormat::Zip, Path::new("x"), &|| false)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn children_lists_an_archive_rewritten_since_it_was_indexed_afresh() {
+        let fx = TempDir::new("archive_children_rewritten");
+        let list = |archive: &Path| -> Vec<OsString> {
+            children(archive, ArchiveFormat::Zip, Path::new(""), &|| false)
+                .unwrap()
+                .into_iter()
+                .map(|child| child.name)
+                .collect()
+        };
+        let archive = zip_with(&fx, &["a.txt"]);
+        assert_eq!(vec!["a.txt"], list(&archive));
+
+        zip_with(&fx, &["a.txt", "b.txt"]);
+
+        assert_eq!(vec!["a.txt", "b.txt"], list(&archive));
+    }
+
+    #[test]
+    fn a_cancelled_listing_is_interrupted() {
+        let fx = TempDir::new("archive_children_cancelled");
+        let archive = zip_with(&fx, &["a.txt"]);
+
+        let error = children(&archive, ArchiveFormat::Zip, Path::new(""), &|| true).unwrap_err();
+
+        assert_eq!(io::ErrorKind::Interrupted, error.kind());
+    }
+
+    #[test]
+    fn read_entries_hands_out_each_kind_with_its_contents() {
+        let fx = TempDir::new("archive_read_entries");
+        fs::create_dir(fx.join("dir")).unwrap();
+        fs::write(fx.join("dir/a.txt"), b"hello").unwrap();
+        std::os::unix::fs::symlink("a.txt", fx.join("dir/link")).unwrap();
+        let archive = fx.join("a.tar.xz");
+        let mut writer = ArchiveWriter::create(&archive, ArchiveFormat::TarXz).unwrap();
+        for name in ["dir", "dir/a.txt", "dir/link"] {
+            let metadata = fs::symlink_metadata(fx.join(name)).unwrap();
+            if metadata.is_dir() {
+                writer
+                    .add_directory(Path::new(name), &Stat::from(&metadata))
+                    .unwrap();
+            } else if metadata.is_symlink() {
+                writer
+                    .add_symlink(Path::new(name), Path::new("a.txt"), &Stat::from(&metadata))
+                    .unwrap();
+            } else {
+                let mut file = File::open(fx.join(name)).unwrap();
+                writer
+                    .add_file(Path::new(name), &Stat::from(&metadata), &mut file)
+                    .unwrap();
+            }
+        }
+        writer.finish().unwrap();
+
+        let mut entries = Vec::new();
+        read_entries(
+            File::open(&archive).unwrap(),
+            ArchiveFormat::TarXz,
+            &mut |entry| {
+                let mut contents = String::new();
+                entry.contents.read_to_string(&mut contents)?;
+                entries.push((entry.name, entry.kind, contents));
+                Ok(true)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                (PathBuf::from("dir"), EntryKind::Directory, String::new()),
+                (
+                    PathBuf::from("dir/a.txt"),
+                    EntryKind::File,
+                    "hello".to_string()
+                ),
+                (
+                    PathBuf::from("dir/link"),
+                    EntryKind::Symlink(PathBuf::from("a.txt")),
+                    String::new()
+                ),
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn a_visit_that_stops_ends_the_read() {
+        let fx = TempDir::new("archive_read_stops");
+        let archive = zip_with(&fx, &["a.txt", "b.txt"]);
+        let mut seen = 0;
+
+        read_entries(
+            File::open(&archive).unwrap(),
+            ArchiveFormat::Zip,
+            &mut |_| {
+                seen += 1;
+                Ok(false)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(1, seen);
+    }
+
+    /// Packs a file and a directory, and returns the names the archive
+    /// lists, read back with the same crates.
+    fn pack(format: ArchiveFormat, archive_name: &str) -> Vec<String> {
+        let fx = TempDir::new("archive");
+        fs::create_dir(fx.join("dir")).unwrap();
+        fs::write(fx.join("dir/a.txt"), b"hello").unwrap();
+        std::os::unix::fs::symlink("a.txt", fx.join("dir/link")).unwrap();
+        let archive = fx.join(archive_name);
+
+        let mut writer = ArchiveWriter::create(&archive, format).unwrap();
+        let stat = |name: &str| Stat::from(&fs::symlink_metadata(fx.join(name)).unwrap());
+        writer
+            .add_directory(Path::new("dir"), &stat("dir"))
+            .unwrap();
+        let mut file = File::open(fx.join("dir/a.txt")).unwrap();
+        writer
+            .add_file(Path::new("dir/a.txt"), &stat("dir/a.txt"), &mut file)
+            .unwrap();
+        writer
+            .add_symlink(Path::new("dir/link"), Path::new("a.txt"), &stat("dir/link"))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let file = File::open(&archive).unwrap();
+        let reader: Box<dyn Read> = match format {
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipArchive::new(file).unwrap();
+                let mut contents = String::new();
+                zip.by_name("dir/a.txt")
+                    .unwrap()
+                    .read_to_string(&mut contents)
+                    .unwrap();
+                assert_eq!("hello", contents);
+                return zip.file_names().map(str::to_string).collect();
+            }
+            ArchiveFormat::Tar => Box::new(file),
+            ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(file)),
+            ArchiveFormat::TarXz => Box::new(XzReader::new(file, false)),
+            ArchiveFormat::TarZst => Box::new(zstd::Decoder::new(file).unwrap()),
+        };
+        let mut tar = tar::Archive::new(reader);
+        tar.entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().into_owned();
+                if name == "dir/a.txt" {
+                    let mut contents = String::new();
+                    entry.read_to_string(&mut contents).unwrap();
+                    assert_eq!("hello", contents);
+                }
+                if name == "dir/link" {
+                    assert_eq!(
+                        Path::new("a.txt"),
+                        entry.link_name().unwrap().unwrap().as_ref()
+                    );
+                }
+                name
+            })
+            .collect()
+    }
+
+    #[test_case(ArchiveFormat::Tar, "a.tar" ; "tar")]
+    #[test_case(ArchiveFormat::TarGz, "a.tar.gz" ; "tar gz")]
+    #[test_case(ArchiveFormat::TarXz, "a.tar.xz" ; "tar xz")]
+    #[test_case(ArchiveFormat::TarZst, "a.tar.zst" ; "tar zst")]
+    fn a_tar_holds_every_entry(format: ArchiveFormat, archive_name: &str) {
+        assert_eq!(
+            vec!["dir", "dir/a.txt", "dir/link"],
+            pack(format, archive_name)
+        );
+    }
+
+    #[test]
+    fn a_zip_holds_every_entry() {
+        let mut names = pack(ArchiveFormat::Zip, "a.zip");
+        names.sort();
+
+        assert_eq!(vec!["dir/", "dir/a.txt", "dir/link"], names);
+    }
+
+    #[test]
+    fn an_existing_archive_is_not_replaced() {
+        let fx = TempDir::new("archive_exists");
+        fs::write(fx.join("a.zip"), b"keep").unwrap();
+
+        assert!(ArchiveWriter::create(&fx.join("a.zip"), ArchiveFormat::Zip).is_err());
+        assert_eq!(b"keep", fs::read(fx.join("a.zip")).unwrap().as_slice());
+    }
+}
@@ -0,0 +1,1020 @@
+//! Where entries live. Listing, reading and changing them goes through a
+//! `Backend` rather than straight to `std::fs`, so the same code can work on
+//! something other than the local disk: an archive, a remote host, or, in
+//! tests, a tree held in memory that needs no temp directory and answers the
+//! same way on every run.
+
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, Read, Write},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    time::SystemTime,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher, recommended_watcher};
+
+use super::times;
+
+/// What `stat` reports about an entry, without following a final symlink.
+/// Not `Metadata`, which only the standard library can construct.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stat {
+    /// `st_mode`: the file type bits and the permissions.
+    pub mode: u32,
+    pub size: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub device: u64,
+    pub inode: u64,
+    pub accessed: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+}
+
+impl From<&Metadata> for Stat {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            mode: metadata.mode(),
+            size: metadata.len(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            accessed: metadata.accessed().ok(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+        }
+    }
+}
+
+/// A change in a watched directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// An entry was created, modified or removed.
+    Changed,
+    /// The watch itself failed, and may have missed changes.
+    Failed(String),
+}
+
+/// Keeps a watch alive: dropping it stops the changes.
+// Never read: whatever keeps the watch running is held only to be dropped.
+#[allow(dead_code)]
+pub struct Watch(Box<dyn Send>);
+
+/// The entries of a directory, one `io::Result` each, as `read_dir` yields
+/// them: one unreadable entry does not lose the rest.
+pub type Entries = Box<dyn Iterator<Item = io::Result<PathBuf>> + Send>;
+
+/// A filesystem the file manager can browse and change. Paths are absolute.
+/// Every method answers as the `std::fs` function of the same purpose does,
+/// errors included, so code written against one backend behaves the same
+/// against another.
+pub trait Backend: Send + Sync {
+    /// The paths of the entries directly inside the directory at `path`, in no
+    /// particular order. Fails up front when the directory cannot be read.
+    fn list(&self, path: &Path) -> io::Result<Entries>;
+
+    /// What is at `path`, not following a final symlink.
+    fn stat(&self, path: &Path) -> io::Result<Stat>;
+
+    /// Whether something is at `path`, following symlinks: `false` for a
+    /// symlink whose target is gone, an error when that cannot be told.
+    fn exists(&self, path: &Path) -> io::Result<bool>;
+
+    /// The contents of the file at `path`.
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// A new, empty file at `path`, open for writing. Never replaces one:
+    /// anything already there is an `AlreadyExists` error.
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// A new directory at `path`, whose parent must exist.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// `path` and every missing directory above it. A directory already
+    /// there is not an error; anything else is.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self.stat(path) {
+            Ok(stat) if unix_mode::is_dir(stat.mode) => return Ok(()),
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} is not a directory", path.display()),
+                ));
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent)?;
+        }
+        match self.create_dir(path) {
+            // Made by someone else since it was looked for.
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Moves `from` to `to`, replacing a non-directory already at `to` as
+    /// `rename(2)` does. Callers that must not replace check first.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Moves `from` to `to` as `rename` does, but fails with `AlreadyExists`
+    /// rather than replace anything at `to`, in the same step as the move.
+    fn rename_no_replace(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Removes the file, symlink or empty directory at `path`.
+    fn remove(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes the empty directory at `path`. Anything else is an error.
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes the directory at `path` with everything below it, deepest
+    /// first, or the file or symlink at `path`. A symlink is never followed.
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        if !unix_mode::is_dir(self.stat(path)?.mode) {
+            return self.remove(path);
+        }
+        for entry in self.list(path)?.collect::<io::Result<Vec<_>>>()? {
+            self.remove_dir_all(&entry)?;
+        }
+        self.remove_dir(path)
+    }
+
+    /// Sets the permission bits of the entry at `path`, following a final
+    /// symlink as `chmod(2)` does.
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()>;
+
+    /// Sets the access and modification times of the entry at `path`, not
+    /// following a final symlink. `None` leaves that time as it is.
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()>;
+
+    /// Gives the entry at `path` to `uid` and `gid`, not following a final
+    /// symlink, as `lchown(2)` does. `None` leaves that id as it is.
+    fn set_owner(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()>;
+
+    /// A new symlink at `path` pointing at `target`, which is stored as given
+    /// and need not exist. Never replaces anything already at `path`.
+    fn symlink(&self, target: &Path, path: &Path) -> io::Result<()>;
+
+    /// The target the symlink at `path` stores, as it was given.
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// A second name, `link`, for the file at `original`. Never replaces
+    /// anything already at `link`.
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()>;
+
+    /// Sends a `Change` whenever an entry directly inside the directory at
+    /// `path` changes, until the returned `Watch` is dropped.
+    fn watch(&self, path: &Path, changes: Sender<Change>) -> io::Result<Watch>;
+}
+
+/// The local disk, through `std::fs` and `notify`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Local;
+
+impl Backend for Local {
+    fn list(&self, path: &Path) -> io::Result<Entries> {
+        Ok(Box::new(
+            fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())),
+        ))
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<Stat> {
+        path.symlink_metadata()
+            .map(|metadata| Stat::from(&metadata))
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        path.try_exists()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        Ok(Box::new(File::create_new(path)?))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    /// Linux uses `renameat2(RENAME_NOREPLACE)` and macOS `renameatx_np`, both
+    /// through rustix's safe wrapper, since `unsafe` is denied crate-wide.
+    /// Other targets fall back to `fs::rename`, leaving a narrow window for
+    /// something to appear at `to` between the caller's check and the move.
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn rename_no_replace(&self, from: &Path, to: &Path) -> io::Result<()> {
+        use rustix::{
+            fs::{CWD, RenameFlags, renameat_with},
+            io::Errno,
+        };
+
+        // `CWD` as both dirfds: absolute paths ignore it and relative paths
+        // resolve against the current directory, matching `fs::rename`.
+        match renameat_with(CWD, from, CWD, to, RenameFlags::NOREPLACE) {
+            Ok(()) => Ok(()),
+            // Some filesystems reject the no-replace flag; fall back to a plain
+            // rename, which reopens only the narrow window above.
+            Err(Errno::NOSYS | Errno::INVAL | Errno::NOTSUP) => fs::rename(from, to),
+            // Preserve the errno (e.g. XDEV -> CrossesDevices, EXIST ->
+            // AlreadyExists) so callers can dispatch on `error.kind()`.
+            Err(errno) => Err(errno.into()),
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn rename_no_replace(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if self.stat(path)?.mode & 0o170_000 == 0o040_000 {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        if unix_mode::is_dir(self.stat(path)?.mode) {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    /// Through `utimensat` rather than an open file, so a symlink's own times
+    /// can be set, and a directory's without reading it.
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        use rustix::fs::{AtFlags, CWD, Timestamps, utimensat};
+
+        let times = Timestamps {
+            last_access: times::timespec_or_omit(accessed),
+            last_modification: times::timespec_or_omit(modified),
+        };
+        Ok(utimensat(CWD, path, &times, AtFlags::SYMLINK_NOFOLLOW)?)
+    }
+
+    fn set_owner(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        std::os::unix::fs::lchown(path, uid, gid)
+    }
+
+    fn symlink(&self, target: &Path, path: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(target, path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        fs::hard_link(original, link)
+    }
+
+    /// One `notify` watcher per watch, so dropping the `Watch` is all it takes
+    /// to stop it: its sender goes with it.
+    fn watch(&self, path: &Path, changes: Sender<Change>) -> io::Result<Watch> {
+        let mut watcher = recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let change = match result {
+                Ok(event) => match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
+                        Change::Changed
+                    }
+                    _ => return,
+                },
+                Err(error) => Change::Failed(error.to_string()),
+            };
+            let _ = changes.send(change);
+        })
+        .map_err(io::Error::other)?;
+        watcher
+            .watch(path, RecursiveMode::NonRecursive)
+            .map_err(|error| match error.kind {
+                notify::ErrorKind::Io(error) => error,
+                notify::ErrorKind::PathNotFound => io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} does not exist", path.display()),
+                ),
+                _ => io::Error::other(error),
+            })?;
+        Ok(Watch(Box::new(watcher)))
+    }
+}
+
+#[cfg(test)]
+pub(crate) use memory::Memory;
+
+#[cfg(test)]
+mod memory {
+    use std::{
+        collections::BTreeMap,
+        io::{self, Cursor, Read, Write},
+        path::{Component, Path, PathBuf},
+        sync::{Arc, Mutex, mpsc::Sender},
+        time::SystemTime,
+    };
+
+    use super::{Backend, Change, Entries, Stat, Watch};
+
+    /// Symlinks followed before a lookup gives up, as Linux's `ELOOP` limit.
+    const MAX_SYMLINK_DEPTH: usize = 40;
+    const TYPE_DIRECTORY: u32 = 0o040_000;
+    const TYPE_FILE: u32 = 0o100_000;
+    const TYPE_SYMLINK: u32 = 0o120_000;
+
+    enum Contents {
+        Directory,
+        File(Arc<Mutex<Vec<u8>>>),
+        Symlink(PathBuf),
+    }
+
+    struct Node {
+        contents: Contents,
+        mode: u32,
+        inode: u64,
+        uid: u32,
+        gid: u32,
+        accessed: SystemTime,
+        modified: SystemTime,
+    }
+
+    #[derive(Default)]
+    struct State {
+        nodes: BTreeMap<PathBuf, Node>,
+        watches: Vec<(PathBuf, Sender<Change>)>,
+        next_inode: u64,
+    }
+
+    /// A tree held in memory, for tests. It starts with nothing but `/`.
+    /// Symlinks resolve as on disk, relative to the directory holding them.
+    pub(crate) struct Memory {
+        state: Mutex<State>,
+    }
+
+    impl Default for Memory {
+        fn default() -> Self {
+            let memory = Self {
+                state: Mutex::new(State::default()),
+            };
+            memory.insert(Path::new("/"), Contents::Directory, 0o755);
+            memory
+        }
+    }
+
+    impl Memory {
+        /// Writes a file at `path`, creating the directories above it.
+        pub(crate) fn file(&self, path: impl AsRef<Path>, contents: &[u8]) -> &Self {
+            let path = path.as_ref();
+            if let Some(parent) = path.parent() {
+                self.create_dir_all(parent).unwrap();
+            }
+            self.create(path).unwrap().write_all(contents).unwrap();
+            self
+        }
+
+        /// Adds a symlink at `path` to `target`.
+        pub(crate) fn link(&self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> &Self {
+            self.insert(
+                path.as_ref(),
+                Contents::Symlink(target.as_ref().to_path_buf()),
+                0o777,
+            );
+            self
+        }
+
+        fn insert(&self, path: &Path, contents: Contents, permissions: u32) {
+            let mut state = self.state.lock().unwrap();
+            state.next_inode += 1;
+            let kind = match contents {
+                Contents::Directory => TYPE_DIRECTORY,
+                Contents::File(_) => TYPE_FILE,
+                Contents::Symlink(_) => TYPE_SYMLINK,
+            };
+            let now = SystemTime::now();
+            let node = Node {
+                contents,
+                mode: kind | permissions,
+                inode: state.next_inode,
+                uid: 0,
+                gid: 0,
+                accessed: now,
+                modified: now,
+            };
+            state.nodes.insert(path.to_path_buf(), node);
+            notify(&mut state, path);
+        }
+
+        /// `path` with every symlink along it resolved, including a final one
+        /// when `follow`.
+        fn resolve(state: &State, path: &Path, follow: bool) -> io::Result<PathBuf> {
+            let mut resolved = PathBuf::from("/");
+            let mut pending: Vec<PathBuf> = vec![path.to_path_buf()];
+            let mut depth = 0;
+            while let Some(next) = pending.pop() {
+                let components: Vec<Component> = next.components().collect();
+                for (index, component) in components.iter().enumerate() {
+                    match component {
+                        Component::RootDir => resolved = PathBuf::from("/"),
+                        Component::CurDir | Component::Prefix(_) => {}
+                        Component::ParentDir => {
+                            resolved.pop();
+                        }
+                        Component::Normal(name) => {
+                            resolved.push(name);
+                            let last = index + 1 == components.len() && pending.is_empty();
+                            if last && !follow {
+                                continue;
+                            }
+                            let Some(Node {
+                                contents: Contents::Symlink(target),
+                                ..
+                            }) = state.nodes.get(&resolved)
+                            else {
+                                continue;
+                            };
+                            depth += 1;
+                            if depth > MAX_SYMLINK_DEPTH {
+                                return Err(io::Error::other("too many levels of symbolic links"));
+                            }
+                            resolved.pop();
+                            let rest: PathBuf = components[index + 1..].iter().collect();
+                            if !rest.as_os_str().is_empty() {
+                                pending.push(rest);
+                            }
+                            pending.push(target.clone());
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(resolved)
+        }
+
+        fn lookup<T>(
+            &self,
+            path: &Path,
+            follow: bool,
+            f: impl FnOnce(&Node) -> io::Result<T>,
+        ) -> io::Result<T> {
+            let state = self.state.lock().unwrap();
+            let resolved = Self::resolve(&state, path, follow)?;
+            match state.nodes.get(&resolved) {
+                Some(node) => f(node),
+                None => Err(not_found(path)),
+            }
+        }
+
+        /// Changes the node at `path`, not following a final symlink.
+        fn change(&self, path: &Path, f: impl FnOnce(&mut Node)) -> io::Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let resolved = Self::resolve(&state, path, false)?;
+            let node = state
+                .nodes
+                .get_mut(&resolved)
+                .ok_or_else(|| not_found(path))?;
+            f(node);
+            Ok(())
+        }
+
+        /// The directory that would hold `path`, which must exist.
+        fn check_parent(state: &State, path: &Path) -> io::Result<PathBuf> {
+            let parent = path.parent().ok_or_else(|| not_found(path))?;
+            let parent = Self::resolve(state, parent, true)?;
+            match state.nodes.get(&parent) {
+                Some(Node {
+                    contents: Contents::Directory,
+                    ..
+                }) => Ok(parent.join(path.file_name().unwrap_or_default())),
+                Some(_) => Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    format!("{} is not a directory", parent.display()),
+                )),
+                None => Err(not_found(&parent)),
+            }
+        }
+
+        fn add(&self, path: &Path, contents: Contents, permissions: u32) -> io::Result<()> {
+            let path = {
+                let state = self.state.lock().unwrap();
+                let path = Self::check_parent(&state, path)?;
+                if state.nodes.contains_key(&path) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", path.display()),
+                    ));
+                }
+                path
+            };
+            self.insert(&path, contents, permissions);
+            Ok(())
+        }
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        )
+    }
+
+    /// Tells the watches on the directory holding `path` that it changed.
+    fn notify(state: &mut State, path: &Path) {
+        state.watches.retain(|(directory, changes)| {
+            Some(directory.as_path()) != path.parent() || changes.send(Change::Changed).is_ok()
+        });
+    }
+
+    impl Backend for Memory {
+        fn list(&self, path: &Path) -> io::Result<Entries> {
+            let state = self.state.lock().unwrap();
+            let directory = Self::resolve(&state, path, true)?;
+            match state.nodes.get(&directory) {
+                Some(Node {
+                    contents: Contents::Directory,
+                    ..
+                }) => {}
+                Some(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotADirectory,
+                        format!("{} is not a directory", path.display()),
+                    ));
+                }
+                None => return Err(not_found(path)),
+            }
+            // Named as asked, as `read_dir` names them, not as resolved.
+            let entries: Vec<io::Result<PathBuf>> = state
+                .nodes
+                .keys()
+                .filter(|entry| entry.parent() == Some(directory.as_path()))
+                .filter_map(|entry| entry.file_name())
+                .map(|name| Ok(path.join(name)))
+                .collect();
+            Ok(Box::new(entries.into_iter()))
+        }
+
+        fn stat(&self, path: &Path) -> io::Result<Stat> {
+            self.lookup(path, false, |node| {
+                let size = match &node.contents {
+                    Contents::File(contents) => contents.lock().unwrap().len() as u64,
+                    Contents::Symlink(target) => target.as_os_str().len() as u64,
+                    Contents::Directory => 0,
+                };
+                Ok(Stat {
+                    mode: node.mode,
+                    size,
+                    uid: node.uid,
+                    gid: node.gid,
+                    inode: node.inode,
+                    accessed: Some(node.accessed),
+                    modified: Some(node.modified),
+                    ..Stat::default()
+                })
+            })
+        }
+
+        fn exists(&self, path: &Path) -> io::Result<bool> {
+            match self.lookup(path, true, |_| Ok(())) {
+                Ok(()) => Ok(true),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+                Err(error) => Err(error),
+            }
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+            self.lookup(path, true, |node| match &node.contents {
+                Contents::File(contents) => {
+                    Ok(Box::new(Cursor::new(contents.lock().unwrap().clone()))
+                        as Box<dyn Read + Send>)
+                }
+                _ => Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{} is not a file", path.display()),
+                )),
+            })
+        }
+
+        fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+            let contents = Arc::new(Mutex::new(Vec::new()));
+            self.add(path, Contents::File(Arc::clone(&contents)), 0o644)?;
+            Ok(Box::new(Writer(contents)))
+        }
+
+        fn create_dir(&self, path: &Path) -> io::Result<()> {
+            self.add(path, Contents::Directory, 0o755)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let from = Self::check_parent(&state, from)?;
+            let to = Self::check_parent(&state, to)?;
+            if !state.nodes.contains_key(&from) {
+                return Err(not_found(&from));
+            }
+            if let Some(Node {
+                contents: Contents::Directory,
+                ..
+            }) = state.nodes.get(&to)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{} is a directory", to.display()),
+                ));
+            }
+            // Everything below a directory moves with it.
+            let moved: Vec<PathBuf> = state
+                .nodes
+                .keys()
+                .filter(|path| path.starts_with(&from))
+                .cloned()
+                .collect();
+            for path in moved {
+                let node = state.nodes.remove(&path).expect("listed above");
+                let rest = path.strip_prefix(&from).expect("listed above");
+                state.nodes.insert(to.join(rest), node);
+            }
+            notify(&mut state, &from);
+            notify(&mut state, &to);
+            Ok(())
+        }
+
+        fn rename_no_replace(&self, from: &Path, to: &Path) -> io::Result<()> {
+            if self.stat(to).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", to.display()),
+                ));
+            }
+            self.rename(from, to)
+        }
+
+        fn remove(&self, path: &Path) -> io::Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let path = Self::check_parent(&state, path)?;
+            if !state.nodes.contains_key(&path) {
+                return Err(not_found(&path));
+            }
+            if state
+                .nodes
+                .keys()
+                .any(|entry| entry.parent() == Some(path.as_path()))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::DirectoryNotEmpty,
+                    format!("{} is not empty", path.display()),
+                ));
+            }
+            state.nodes.remove(&path);
+            notify(&mut state, &path);
+            Ok(())
+        }
+
+        fn remove_dir(&self, path: &Path) -> io::Result<()> {
+            if !unix_mode::is_dir(self.stat(path)?.mode) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotADirectory,
+                    format!("{} is not a directory", path.display()),
+                ));
+            }
+            self.remove(path)
+        }
+
+        fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+            let mut state = self.state.lock().unwrap();
+            let resolved = Self::resolve(&state, path, true)?;
+            let node = state
+                .nodes
+                .get_mut(&resolved)
+                .ok_or_else(|| not_found(path))?;
+            node.mode = (node.mode & !0o7777) | (mode & 0o7777);
+            Ok(())
+        }
+
+        fn set_times(
+            &self,
+            path: &Path,
+            accessed: Option<SystemTime>,
+            modified: Option<SystemTime>,
+        ) -> io::Result<()> {
+            self.change(path, |node| {
+                node.accessed = accessed.unwrap_or(node.accessed);
+                node.modified = modified.unwrap_or(node.modified);
+            })
+        }
+
+        fn set_owner(&self, path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+            self.change(path, |node| {
+                node.uid = uid.unwrap_or(node.uid);
+                node.gid = gid.unwrap_or(node.gid);
+            })
+        }
+
+        fn symlink(&self, target: &Path, path: &Path) -> io::Result<()> {
+            self.add(path, Contents::Symlink(target.to_path_buf()), 0o777)
+        }
+
+        fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+            self.lookup(path, false, |node| match &node.contents {
+                Contents::Symlink(target) => Ok(target.clone()),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a symlink", path.display()),
+                )),
+            })
+        }
+
+        /// The new name shares the original's contents, so a write through
+        /// either shows through both, as on disk.
+        fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+            let (contents, permissions) =
+                self.lookup(original, false, |node| match &node.contents {
+                    Contents::File(contents) => Ok((Arc::clone(contents), node.mode & 0o7777)),
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        format!("{} is not a file", original.display()),
+                    )),
+                })?;
+            self.add(link, Contents::File(contents), permissions)
+        }
+
+        fn watch(&self, path: &Path, changes: Sender<Change>) -> io::Result<Watch> {
+            let mut state = self.state.lock().unwrap();
+            let directory = Self::resolve(&state, path, true)?;
+            if !state.nodes.contains_key(&directory) {
+                return Err(not_found(path));
+            }
+            state.watches.push((directory, changes));
+            // Dropping the receiver is what ends a memory watch.
+            Ok(Watch(Box::new(())))
+        }
+    }
+
+    /// Writes into a file's contents as they are written, as a `File` does.
+    struct Writer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Writer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Read,
+        sync::{Arc, mpsc::channel},
+    };
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{file_system::sftp, test_support::TempDir};
+
+    fn names(backend: &dyn Backend, path: &str) -> Vec<String> {
+        let mut names: Vec<String> = backend
+            .list(Path::new(path))
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn contents(backend: &dyn Backend, path: &Path) -> String {
+        let mut contents = String::new();
+        backend
+            .read(path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    /// The same calls against the disk and against memory: the in-memory
+    /// backend is only worth testing with if it answers as the disk does.
+    fn exercise(backend: &dyn Backend, root: &Path) {
+        let dir = root.join("dir");
+        backend.create_dir_all(&dir.join("sub")).unwrap();
+        backend
+            .create(&dir.join("a.txt"))
+            .unwrap()
+            .write_all(b"a")
+            .unwrap();
+
+        assert_eq!(vec!["a.txt", "sub"], names(backend, &dir.to_string_lossy()));
+        assert_eq!("a", contents(backend, &dir.join("a.txt")));
+        assert!(unix_mode::is_dir(backend.stat(&dir).unwrap().mode));
+        assert_eq!(1, backend.stat(&dir.join("a.txt")).unwrap().size);
+        assert_eq!(
+            io::ErrorKind::AlreadyExists,
+            backend.create(&dir.join("a.txt")).err().unwrap().kind()
+        );
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            backend.stat(&dir.join("missing")).unwrap_err().kind()
+        );
+
+        backend
+            .rename(&dir.join("a.txt"), &dir.join("sub/b.txt"))
+            .unwrap();
+        assert_eq!("a", contents(backend, &dir.join("sub/b.txt")));
+        assert!(!backend.exists(&dir.join("a.txt")).unwrap());
+
+        assert_eq!(
+            io::ErrorKind::DirectoryNotEmpty,
+            backend.remove(&dir.join("sub")).unwrap_err().kind()
+        );
+        backend.set_mode(&dir.join("sub/b.txt"), 0o600).unwrap();
+        assert_eq!(
+            0o600,
+            backend.stat(&dir.join("sub/b.txt")).unwrap().mode & 0o7777
+        );
+        backend.remove(&dir.join("sub/b.txt")).unwrap();
+        backend.remove(&dir.join("sub")).unwrap();
+        assert!(names(backend, &dir.to_string_lossy()).is_empty());
+
+        backend.create(&dir.join("c.txt")).unwrap().flush().unwrap();
+        backend.create(&dir.join("d.txt")).unwrap().flush().unwrap();
+        assert_eq!(
+            io::ErrorKind::AlreadyExists,
+            backend
+                .rename_no_replace(&dir.join("c.txt"), &dir.join("d.txt"))
+                .unwrap_err()
+                .kind()
+        );
+        backend
+            .rename_no_replace(&dir.join("c.txt"), &dir.join("e.txt"))
+            .unwrap();
+        assert_eq!(
+            vec!["d.txt", "e.txt"],
+            names(backend, &dir.to_string_lossy())
+        );
+
+        backend
+            .symlink(Path::new("d.txt"), &dir.join("link"))
+            .unwrap();
+        assert_eq!(
+            PathBuf::from("d.txt"),
+            backend.read_link(&dir.join("link")).unwrap()
+        );
+        assert!(unix_mode::is_symlink(
+            backend.stat(&dir.join("link")).unwrap().mode
+        ));
+        assert_eq!(
+            io::ErrorKind::AlreadyExists,
+            backend
+                .symlink(Path::new("e.txt"), &dir.join("link"))
+                .unwrap_err()
+                .kind()
+        );
+
+        backend.create_dir_all(&dir.join("tree/deeper")).unwrap();
+        assert!(backend.remove_dir(&dir.join("d.txt")).is_err());
+        assert!(backend.remove_dir(&dir.join("tree")).is_err());
+        backend.remove_dir(&dir.join("tree/deeper")).unwrap();
+        backend.create_dir(&dir.join("tree/deeper")).unwrap();
+        backend
+            .create(&dir.join("tree/deeper/f.txt"))
+            .unwrap()
+            .flush()
+            .unwrap();
+        backend.remove_dir_all(&dir).unwrap();
+        assert!(!backend.exists(&dir).unwrap());
+    }
+
+    /// What SFTP cannot do, so only the disk and memory answer it.
+    fn exercise_local(backend: &dyn Backend, root: &Path) {
+        let file = root.join("a.txt");
+        let link = root.join("link");
+        backend.create(&file).unwrap().write_all(b"a").unwrap();
+        backend.symlink(Path::new("a.txt"), &link).unwrap();
+
+        let accessed = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(2_000);
+        backend
+            .set_times(&file, Some(accessed), Some(modified))
+            .unwrap();
+        backend.set_times(&link, None, Some(accessed)).unwrap();
+        let stat = backend.stat(&file).unwrap();
+        assert_eq!(Some(accessed), stat.accessed);
+        assert_eq!(Some(modified), stat.modified);
+        // The link's own time changed, not its target's.
+        assert_eq!(Some(accessed), backend.stat(&link).unwrap().modified);
+
+        // Giving an entry to its own owner is allowed to anyone.
+        backend.set_owner(&link, Some(stat.uid), None).unwrap();
+        assert_eq!(stat.uid, backend.stat(&file).unwrap().uid);
+
+        backend.hard_link(&file, &root.join("b.txt")).unwrap();
+        backend
+            .create(&root.join("c.txt"))
+            .unwrap()
+            .write_all(b"c")
+            .unwrap();
+        assert_eq!("a", contents(backend, &root.join("b.txt")));
+        assert_eq!(
+            io::ErrorKind::AlreadyExists,
+            backend
+                .hard_link(&file, &root.join("c.txt"))
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn the_local_backend_reads_and_changes_the_disk() {
+        let fx = TempDir::new("backend_local");
+        exercise(&Local, fx.path());
+        exercise_local(&Local, fx.path());
+    }
+
+    #[test]
+    fn the_memory_backend_answers_as_the_disk_does() {
+        let memory = Memory::default();
+        exercise(&memory, Path::new("/home"));
+        memory.create_dir(Path::new("/other")).unwrap();
+        exercise_local(&memory, Path::new("/other"));
+    }
+
+    #[test]
+    fn the_sftp_backend_answers_as_the_disk_does() {
+        let memory = Arc::new(Memory::default());
+        exercise(&sftp::stand_in("exercise.test", memory), Path::new("/home"));
+    }
+
+    #[test_case("/a/link/c.txt", true ; "through a symlinked directory")]
+    #[test_case("/a/up", true ; "relative to the directory holding it")]
+    #[test_case("/a/dangling", false ; "to nothing")]
+    fn memory_symlinks_resolve_as_on_disk(path: &str, exists: bool) {
+        let memory = Memory::default();
+        memory
+            .file("/b/c.txt", b"c")
+            .link("/a/link", "/b")
+            .link("/a/up", "../b/c.txt")
+            .link("/a/dangling", "nowhere");
+
+        assert_eq!(exists, memory.exists(Path::new(path)).unwrap());
+        // The link itself is there either way.
+        assert!(memory.stat(Path::new(path)).is_ok());
+    }
+
+    #[test]
+    fn a_memory_watch_hears_changes_until_it_is_dropped() {
+        let memory = Memory::default();
+        memory.create_dir(Path::new("/dir")).unwrap();
+        let (tx, rx) = channel();
+        let watch = memory.watch(Path::new("/dir"), tx).unwrap();
+
+        memory.file("/dir/a.txt", b"a");
+        memory.file("/elsewhere.txt", b"b");
+
+        assert_eq!(vec![Change::Changed], rx.try_iter().collect::<Vec<_>>());
+        drop(watch);
+        drop(rx);
+        // A watch whose receiver is gone is dropped, not an error.
+        memory.file("/dir/b.txt", b"b");
+    }
+}
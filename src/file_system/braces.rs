@@ -0,0 +1,98 @@
+use anyhow::{Result, anyhow};
+
+/// More names than anyone means to type into one prompt, and a bound on the
+/// work a nested pattern like `{a,b}{a,b}{a,b}...` can ask for.
+pub const MAX_EXPANSIONS: usize = 1024;
+
+/// Expands `{a,b}` alternatives the way a shell does, left to right, so
+/// `{2024,2025}/{q1,q2}` is `2024/q1`, `2024/q2`, `2025/q1`, `2025/q2`.
+/// Alternatives nest, and may be empty (`a{,.bak}`). As in bash, a brace pair
+/// with no top-level comma, and an unmatched brace, are left as they are:
+/// `{a}` names a directory called `{a}`.
+pub fn expand(pattern: &str) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    expand_into(pattern.to_string(), &mut names)?;
+    Ok(names)
+}
+
+fn expand_into(pattern: String, names: &mut Vec<String>) -> Result<()> {
+    let Some((open, close, commas)) = find_group(&pattern) else {
+        if names.len() == MAX_EXPANSIONS {
+            return Err(anyhow!(
+                "{pattern:?} expands to more than {MAX_EXPANSIONS} names"
+            ));
+        }
+        names.push(pattern);
+        return Ok(());
+    };
+    let prefix = &pattern[..open];
+    let suffix = &pattern[close + 1..];
+    let mut start = open + 1;
+    for end in commas.into_iter().chain([close]) {
+        let alternative = &pattern[start..end];
+        // Re-expanded whole, so a later group in the suffix (and any nested
+        // in this alternative) multiplies out in order.
+        expand_into(format!("{prefix}{alternative}{suffix}"), names)?;
+        start = end + 1;
+    }
+    Ok(())
+}
+
+/// The first brace pair that expands: its opening and closing byte offsets,
+/// and those of the commas directly inside it. A pair without one is skipped
+/// and the search goes on from just inside it, where a nested pair may still
+/// expand (`{{a,b}}` is `{a}`, `{b}`).
+fn find_group(pattern: &str) -> Option<(usize, usize, Vec<usize>)> {
+    let bytes = pattern.as_bytes();
+    for open in (0..bytes.len()).filter(|&index| bytes[index] == b'{') {
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        for (index, &byte) in bytes.iter().enumerate().skip(open) {
+            match byte {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        if !commas.is_empty() {
+                            return Some((open, index, commas));
+                        }
+                        break;
+                    }
+                }
+                b',' if depth == 1 => commas.push(index),
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("a" => vec!["a"] ; "no braces")]
+    #[test_case("{a,b}" => vec!["a", "b"] ; "a list")]
+    #[test_case("x{a,b}y" => vec!["xay", "xby"] ; "a prefix and suffix")]
+    #[test_case("{2024,2025}/{q1,q2}" => vec!["2024/q1", "2024/q2", "2025/q1", "2025/q2"] ; "groups multiply left to right")]
+    #[test_case("{a,b{1,2}}" => vec!["a", "b1", "b2"] ; "nested")]
+    #[test_case("a{,.bak}" => vec!["a", "a.bak"] ; "an empty alternative")]
+    #[test_case("{a}" => vec!["{a}"] ; "no comma is literal")]
+    #[test_case("{{a,b}}" => vec!["{a}", "{b}"] ; "a literal pair around a list")]
+    #[test_case("{a,b" => vec!["{a,b"] ; "unmatched is literal")]
+    #[test_case("a,b" => vec!["a,b"] ; "a comma outside braces is literal")]
+    #[test_case("é{ü,ö}" => vec!["éü", "éö"] ; "multibyte text around the braces")]
+    fn expand_cases(pattern: &str) -> Vec<String> {
+        expand(pattern).unwrap()
+    }
+
+    #[test]
+    fn an_expansion_past_the_limit_is_refused() {
+        // 2^11 = 2048 names.
+        let pattern = "{a,b}".repeat(11);
+
+        assert!(expand(&pattern).is_err());
+    }
+}
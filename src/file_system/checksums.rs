@@ -0,0 +1,277 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use sha2::{Digest, Sha256};
+
+use super::path_info::compact;
+
+/// The length of a digest in hex, the same for both algorithms.
+const DIGEST_HEX_LEN: usize = 64;
+
+/// What a checksum is computed with.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChecksumAlgorithm {
+    /// What releases publish, and `sha256sum` checks.
+    Sha256,
+    /// Much faster, for checking copies of large files against each other.
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "SHA-256",
+            ChecksumAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// The algorithm a checksum file holds, going by its name: `b3sum`'s are
+    /// named for it (`B3SUMS`, or a final `.b3` or `.blake3`), and anything else
+    /// is taken to be `sha256sum`'s, since both write digests of the same
+    /// length. Only the whole name or the extension counts, so `b3` elsewhere,
+    /// as in `app-1.0-3fb3.tar.gz.sha256`, does not.
+    pub fn of_sums_file(path: &Path) -> Self {
+        let is = |name: Option<&OsStr>, expected: &str| {
+            name.is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(expected))
+        };
+        if is(path.file_name(), "B3SUMS")
+            || is(path.extension(), "b3")
+            || is(path.extension(), "blake3")
+        {
+            ChecksumAlgorithm::Blake3
+        } else {
+            ChecksumAlgorithm::Sha256
+        }
+    }
+}
+
+/// One file's checksum, and what it was expected to be when it was checked
+/// against a checksum file.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChecksumEntry {
+    pub path: PathBuf,
+    /// The digest in lowercase hex, or why the file could not be read.
+    pub digest: Result<String, String>,
+    pub expected: Option<String>,
+}
+
+impl ChecksumEntry {
+    /// Whether the file matched its expected digest. `None` when it was not
+    /// checked against one.
+    pub fn verified(&self) -> Option<bool> {
+        let expected = self.expected.as_ref()?;
+        Some(
+            self.digest
+                .as_ref()
+                .is_ok_and(|digest| digest.eq_ignore_ascii_case(expected)),
+        )
+    }
+}
+
+/// The checksums one task computed, of the files it was given or of those a
+/// checksum file lists.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ChecksumReport {
+    pub algorithm: ChecksumAlgorithm,
+    /// What the entries' paths are shown relative to: the directory listed
+    /// when they were marked, or the checksum file's.
+    pub directory: PathBuf,
+    /// The checksum file checked against, when verifying.
+    pub sums: Option<PathBuf>,
+    pub entries: Vec<ChecksumEntry>,
+}
+
+/// A digest being computed, fed a file a chunk at a time.
+pub(super) enum Hasher {
+    Sha256(Sha256),
+    // Boxed: its state is far larger than SHA-256's.
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(super) fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub(super) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+            Hasher::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+        }
+    }
+
+    /// The digest in lowercase hex, as `sha256sum` and `b3sum` print it.
+    pub(super) fn finish(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Reads the checksum file at `sums`, returning each file it lists, below the
+/// directory it is in, with the digest expected of it. Fails when it cannot
+/// be read or lists nothing, so that verifying a file that is not a checksum
+/// file says so rather than showing an empty result.
+pub(super) fn read_sums(sums: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let text = fs::read_to_string(sums)
+        .map_err(|error| anyhow!("Failed to read {}: {error}", compact(sums)))?;
+    let directory = sums.parent().unwrap_or(Path::new(""));
+    let listed: Vec<_> = text
+        .lines()
+        .filter_map(|line| parse_line(line, sums))
+        .map(|(name, digest)| (directory.join(name), digest))
+        .collect();
+    if listed.is_empty() {
+        return Err(anyhow!("{} lists no checksums", compact(sums)));
+    }
+    Ok(listed)
+}
+
+/// One line of a checksum file: a name and its digest, in lowercase. Reads
+/// the `sha256sum` format (`<digest>  <name>`, or `<digest> *<name>` in
+/// binary mode, with `\` escapes when the line starts with one), the BSD
+/// format that `--tag` writes (`SHA256 (<name>) = <digest>`), and the bare
+/// digest a single `<name>.sha256` file often holds, which is of `<name>`.
+/// Comments, blank lines and anything else are skipped.
+fn parse_line(line: &str, sums: &Path) -> Option<(String, String)> {
+    let line = line.trim_end_matches('\r');
+    if line.trim().is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let is_digest =
+        |text: &str| text.len() == DIGEST_HEX_LEN && text.bytes().all(|b| b.is_ascii_hexdigit());
+    let (escaped, unprefixed) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    if let Some((digest, name)) = unprefixed.split_once(' ')
+        && is_digest(digest)
+    {
+        let name = name.strip_prefix([' ', '*'])?;
+        let name = if escaped {
+            unescape(name)
+        } else {
+            name.to_string()
+        };
+        return Some((name, digest.to_ascii_lowercase()));
+    }
+    if is_digest(line.trim()) {
+        let name = sums.file_stem()?.to_string_lossy().into_owned();
+        return Some((name, line.trim().to_ascii_lowercase()));
+    }
+    let (tagged, digest) = line.rsplit_once(") = ")?;
+    let (_, name) = tagged.split_once(" (")?;
+    is_digest(digest).then(|| (name.to_string(), digest.to_ascii_lowercase()))
+}
+
+/// Undoes the escapes `sha256sum` writes in a name holding a newline or a
+/// backslash.
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                unescaped.push('\n');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                unescaped.push('\\');
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const DIGEST: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test_case(ChecksumAlgorithm::Sha256, DIGEST ; "sha256")]
+    #[test_case(
+        ChecksumAlgorithm::Blake3,
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262" ;
+        "blake3"
+    )]
+    fn the_digest_of_nothing_is_the_well_known_one(algorithm: ChecksumAlgorithm, expected: &str) {
+        assert_eq!(expected, Hasher::new(algorithm).finish());
+    }
+
+    #[test_case("SHA256SUMS" => ChecksumAlgorithm::Sha256 ; "sha256sums")]
+    #[test_case("release.tar.gz.sha256" => ChecksumAlgorithm::Sha256 ; "single file")]
+    #[test_case("B3SUMS" => ChecksumAlgorithm::Blake3 ; "b3sums")]
+    #[test_case("release.tar.gz.b3" => ChecksumAlgorithm::Blake3 ; "single b3")]
+    #[test_case("release.tar.gz.BLAKE3" => ChecksumAlgorithm::Blake3 ; "single blake3")]
+    #[test_case("b3sums" => ChecksumAlgorithm::Blake3 ; "lowercase b3sums")]
+    #[test_case("app-1.0-3fb3.tar.gz.sha256" => ChecksumAlgorithm::Sha256 ; "b3 in a version")]
+    #[test_case("ub3r.SHA256SUMS" => ChecksumAlgorithm::Sha256 ; "b3 in a prefix")]
+    #[test_case("blake3-1.5.SHA256SUMS" => ChecksumAlgorithm::Sha256 ; "blake3 in a prefix")]
+    fn the_algorithm_of_a_checksum_file_is_in_its_name(name: &str) -> ChecksumAlgorithm {
+        ChecksumAlgorithm::of_sums_file(Path::new(name))
+    }
+
+    #[test_case(&format!("{DIGEST}  a.txt") => Some(("a.txt".into(), DIGEST.into())) ; "text mode")]
+    #[test_case(&format!("{DIGEST} *a b.bin") => Some(("a b.bin".into(), DIGEST.into())) ; "binary mode")]
+    #[test_case(&format!("\\{DIGEST}  a\\nb\\\\c") => Some(("a\nb\\c".into(), DIGEST.into())) ; "escaped")]
+    #[test_case(&format!("SHA256 (a.txt) = {DIGEST}") => Some(("a.txt".into(), DIGEST.into())) ; "bsd")]
+    #[test_case(&DIGEST.to_uppercase() => Some(("release.tar.gz".into(), DIGEST.into())) ; "bare digest")]
+    #[test_case("# a comment" => None ; "comment")]
+    #[test_case("abc  a.txt" => None ; "short digest")]
+    fn a_checksum_line_names_a_file_and_its_digest(line: &str) -> Option<(String, String)> {
+        parse_line(line, Path::new("release.tar.gz.sha256"))
+    }
+
+    #[test]
+    fn a_checksum_file_lists_files_beside_it() {
+        let fx = TempDir::new("checksums");
+        fs::write(
+            fx.join("SHA256SUMS"),
+            format!("{DIGEST}  a.txt\r\n\n{DIGEST}  sub/b.txt\n"),
+        )
+        .unwrap();
+        fs::write(fx.join("notes.txt"), "no checksums here").unwrap();
+
+        assert_eq!(
+            vec![
+                (fx.join("a.txt"), DIGEST.to_string()),
+                (fx.join("sub/b.txt"), DIGEST.to_string()),
+            ],
+            read_sums(&fx.join("SHA256SUMS")).unwrap()
+        );
+        assert!(read_sums(&fx.join("notes.txt")).is_err());
+    }
+
+    #[test]
+    fn an_entry_is_verified_only_against_an_expected_digest() {
+        let entry = |digest: Result<&str, &str>, expected: Option<&str>| ChecksumEntry {
+            path: "a".into(),
+            digest: digest.map(Into::into).map_err(Into::into),
+            expected: expected.map(Into::into),
+        };
+
+        assert_eq!(None, entry(Ok(DIGEST), None).verified());
+        assert_eq!(
+            Some(true),
+            entry(Ok(DIGEST), Some(&DIGEST.to_uppercase())).verified()
+        );
+        assert_eq!(Some(false), entry(Ok("00"), Some(DIGEST)).verified());
+        assert_eq!(Some(false), entry(Err("gone"), Some(DIGEST)).verified());
+    }
+}
@@ -0,0 +1,562 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, File, Metadata},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+
+use super::stream::BATCH_FLUSH_INTERVAL;
+use crate::command::{Command, progress::CancellationToken};
+
+/// The read size of a comparison by contents, and how often it checks for a
+/// cancel.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// How an entry below one directory relates to the entry at the same path
+/// below the other.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompareStatus {
+    OnlyLeft,
+    OnlyRight,
+    Identical,
+    NewerLeft,
+    NewerRight,
+    /// Different, with nothing to say which is newer: the same modification
+    /// time, or a directory on one side and a file on the other.
+    Differs,
+    /// A directory that could not be read in full on one side or both. What
+    /// is below it is not known there, so none of it is listed, and nothing
+    /// below it is copied, replaced or deleted: an unreadable directory is
+    /// not an empty one.
+    Unreadable,
+}
+
+impl CompareStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            CompareStatus::OnlyLeft => "only left",
+            CompareStatus::OnlyRight => "only right",
+            CompareStatus::Identical => "identical",
+            CompareStatus::NewerLeft => "newer left",
+            CompareStatus::NewerRight => "newer right",
+            CompareStatus::Differs => "differs",
+            CompareStatus::Unreadable => "unreadable",
+        }
+    }
+
+    /// Whether copying the entry across would take it from the left to the
+    /// right, from the right to the left, or nowhere: only what is missing or
+    /// newer is copied, never one of two entries that merely differ.
+    pub fn copies_to_right(self) -> Option<bool> {
+        match self {
+            CompareStatus::OnlyLeft | CompareStatus::NewerLeft => Some(true),
+            CompareStatus::OnlyRight | CompareStatus::NewerRight => Some(false),
+            CompareStatus::Identical | CompareStatus::Differs | CompareStatus::Unreadable => None,
+        }
+    }
+
+    /// What mirroring one directory onto the other does to the entry, from
+    /// the left onto the right when `to_right`: the source's copy goes across
+    /// wherever the two are not identical, whichever is newer, and what only
+    /// the destination has is deleted when `delete`, or kept. A directory
+    /// that could not be read is skipped, as `rsync --delete` refuses to
+    /// delete after an I/O error: what the destination has below it could
+    /// be all that is left of what the source holds.
+    pub fn mirror_step(self, to_right: bool, delete: bool) -> Option<MirrorStep> {
+        let (only_source, only_destination) = if to_right {
+            (CompareStatus::OnlyLeft, CompareStatus::OnlyRight)
+        } else {
+            (CompareStatus::OnlyRight, CompareStatus::OnlyLeft)
+        };
+        match self {
+            CompareStatus::Identical => None,
+            CompareStatus::Unreadable => Some(MirrorStep::Skip),
+            status if status == only_source => Some(MirrorStep::Copy),
+            status if status == only_destination => delete.then_some(MirrorStep::Delete),
+            _ => Some(MirrorStep::Replace),
+        }
+    }
+}
+
+/// What a mirror does to one entry of the destination.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MirrorStep {
+    /// Copied from the source, where the destination has nothing.
+    Copy,
+    /// Copied from the source over what the destination has.
+    Replace,
+    /// Deleted, since the source has nothing there.
+    Delete,
+    /// Left alone, since it could not be read.
+    Skip,
+}
+
+impl MirrorStep {
+    pub fn label(self) -> &'static str {
+        match self {
+            MirrorStep::Copy => "copy",
+            MirrorStep::Replace => "replace",
+            MirrorStep::Delete => "delete",
+            MirrorStep::Skip => "unreadable",
+        }
+    }
+}
+
+/// One side of an entry, as the comparison read it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CompareSide {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub is_directory: bool,
+}
+
+impl From<&Metadata> for CompareSide {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            is_directory: metadata.is_dir(),
+        }
+    }
+}
+
+/// An entry of the merged listing: a path below both directories, and what is
+/// at it on either side. The directories themselves are the empty path, listed
+/// only when one of them cannot be read.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CompareEntry {
+    pub relative: PathBuf,
+    pub left: Option<CompareSide>,
+    pub right: Option<CompareSide>,
+    pub status: CompareStatus,
+}
+
+/// Spawns a background comparison of the trees at `left` and `right`,
+/// reporting the entries compared so far as `Command::CompareProgress` at most
+/// every `BATCH_FLUSH_INTERVAL`, then the merged listing as
+/// `Command::Compared`. Nothing is sent once `cancel` is set, so a view closed
+/// mid-comparison sees no late results.
+pub(super) fn run_compare(
+    tx: Sender<Command>,
+    cancel: CancellationToken,
+    left: PathBuf,
+    right: PathBuf,
+    contents: bool,
+) {
+    thread::spawn(move || {
+        let mut comparison = Comparison {
+            cancel: &cancel,
+            contents,
+            last_report: Instant::now(),
+            report: |entries| {
+                if !cancel.is_cancelled() {
+                    let _ = tx.send(Command::CompareProgress { entries });
+                }
+            },
+        };
+        if let Some(entries) = comparison.compare(&left, &right)
+            && !cancel.is_cancelled()
+        {
+            let _ = tx.send(Command::Compared(entries));
+        }
+    });
+}
+
+/// The status of an entry found on both sides as the same kind of thing.
+/// `same_contents` is `None` unless the contents were compared.
+///
+/// Modification times are compared to the second: a filesystem that keeps
+/// less than that, or a copy that carries the time over at a coarser
+/// precision, must not make two copies of a file look different.
+fn status(left: &CompareSide, right: &CompareSide, same_contents: Option<bool>) -> CompareStatus {
+    let seconds = |modified: Option<SystemTime>| {
+        modified.map(|modified| match modified.duration_since(UNIX_EPOCH) {
+            Ok(after) => i128::from(after.as_secs()),
+            Err(before) => -i128::from(before.duration().as_secs()),
+        })
+    };
+    let (left_time, right_time) = (seconds(left.modified), seconds(right.modified));
+    let identical = same_contents
+        .unwrap_or(left.size == right.size && left_time.is_some() && left_time == right_time);
+    if identical {
+        return CompareStatus::Identical;
+    }
+    match (left_time, right_time) {
+        (Some(left), Some(right)) if left > right => CompareStatus::NewerLeft,
+        (Some(left), Some(right)) if left < right => CompareStatus::NewerRight,
+        _ => CompareStatus::Differs,
+    }
+}
+
+/// One comparison's running state.
+struct Comparison<'a, F: Fn(u64)> {
+    cancel: &'a CancellationToken,
+    /// Compare files of the same size byte for byte, rather than trusting
+    /// their modification times.
+    contents: bool,
+    last_report: Instant,
+    report: F,
+}
+
+impl<F: Fn(u64)> Comparison<'_, F> {
+    /// The merged listing of the trees at `left` and `right`, by path. `None`
+    /// when cancelled.
+    ///
+    /// A directory on both sides is not an entry of its own: what is in it
+    /// is. A directory on one side only is a single entry, the whole of it
+    /// missing from the other. Symlinks are compared as links, never
+    /// followed. A directory that cannot be read in full on either side is
+    /// a single `Unreadable` entry, with nothing below it listed.
+    fn compare(&mut self, left: &Path, right: &Path) -> Option<Vec<CompareEntry>> {
+        let root_side = |path: &Path| {
+            fs::symlink_metadata(path)
+                .ok()
+                .map(|metadata| CompareSide::from(&metadata))
+        };
+        let mut entries = Vec::new();
+        let mut stack = vec![(PathBuf::new(), root_side(left), root_side(right))];
+        while let Some((relative, left_side, right_side)) = stack.pop() {
+            let (left_path, right_path) = (left.join(&relative), right.join(&relative));
+            let (left_entries, right_entries) =
+                match (read_side(&left_path), read_side(&right_path)) {
+                    (Ok(left_entries), Ok(right_entries)) => (left_entries, right_entries),
+                    (left_entries, right_entries) => {
+                        for (path, error) in
+                            [(&left_path, left_entries), (&right_path, right_entries)]
+                                .into_iter()
+                                .filter_map(|(path, read)| Some((path, read.err()?)))
+                        {
+                            warn!("Failed to read directory {}: {error}", path.display());
+                        }
+                        entries.push(CompareEntry {
+                            relative,
+                            left: left_side,
+                            right: right_side,
+                            status: CompareStatus::Unreadable,
+                        });
+                        continue;
+                    }
+                };
+            let mut merged: BTreeMap<OsString, (Option<CompareSide>, Option<CompareSide>)> =
+                BTreeMap::new();
+            for (name, side) in left_entries {
+                merged.entry(name).or_default().0 = Some(side);
+            }
+            for (name, side) in right_entries {
+                merged.entry(name).or_default().1 = Some(side);
+            }
+            for (name, sides) in merged {
+                if self.cancel.is_cancelled() {
+                    return None;
+                }
+                let relative = relative.join(name);
+                let status = match sides {
+                    (Some(_), None) => CompareStatus::OnlyLeft,
+                    (None, Some(_)) => CompareStatus::OnlyRight,
+                    (Some(l), Some(r)) if l.is_directory && r.is_directory => {
+                        stack.push((relative, Some(l), Some(r)));
+                        continue;
+                    }
+                    (Some(l), Some(r)) if l.is_directory != r.is_directory => {
+                        CompareStatus::Differs
+                    }
+                    (Some(l), Some(r)) => {
+                        let same_contents = match (self.contents, l.size == r.size) {
+                            (false, _) => None,
+                            (true, false) => Some(false),
+                            (true, true) => Some(
+                                self.same_contents(&left.join(&relative), &right.join(&relative))?,
+                            ),
+                        };
+                        status(&l, &r, same_contents)
+                    }
+                    (None, None) => unreachable!("every name was read from one side"),
+                };
+                entries.push(CompareEntry {
+                    relative,
+                    left: sides.0,
+                    right: sides.1,
+                    status,
+                });
+                if self.last_report.elapsed() >= BATCH_FLUSH_INTERVAL {
+                    (self.report)(entries.len() as u64);
+                    self.last_report = Instant::now();
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.relative.cmp(&b.relative));
+        Some(entries)
+    }
+
+    /// Whether the files at `left` and `right` hold the same bytes, or the
+    /// symlinks the same target. A file that cannot be read is taken to
+    /// differ. `None` when cancelled.
+    fn same_contents(&self, left: &Path, right: &Path) -> Option<bool> {
+        if let (Ok(left), Ok(right)) = (fs::read_link(left), fs::read_link(right)) {
+            return Some(left == right);
+        }
+        let (Ok(mut left), Ok(mut right)) = (File::open(left), File::open(right)) else {
+            return Some(false);
+        };
+        let mut left_buffer = vec![0; CHUNK_BYTES];
+        let mut right_buffer = vec![0; CHUNK_BYTES];
+        loop {
+            if self.cancel.is_cancelled() {
+                return None;
+            }
+            let Ok(read) = left.read(&mut left_buffer) else {
+                return Some(false);
+            };
+            if read == 0 {
+                // Equal sizes, so the right side is at its end too, unless
+                // it has grown since it was read.
+                return Some(
+                    right
+                        .read(&mut right_buffer[..1])
+                        .is_ok_and(|read| read == 0),
+                );
+            }
+            if right.read_exact(&mut right_buffer[..read]).is_err()
+                || left_buffer[..read] != right_buffer[..read]
+            {
+                return Some(false);
+            }
+        }
+    }
+}
+
+/// The entries of the directory at `path`, by name, or the error that kept
+/// any of them from being read. An entry left out would read as missing on
+/// this side, for a mirror to delete from the other.
+fn read_side(path: &Path) -> io::Result<Vec<(OsString, CompareSide)>> {
+    let mut sides = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        // `DirEntry::metadata` does not follow symlinks.
+        match entry.metadata() {
+            Ok(metadata) => sides.push((entry.file_name(), CompareSide::from(&metadata))),
+            // Removed since the directory was listed, so truly missing.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(sides)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, sync::mpsc::channel, time::Duration};
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn compare(left: &Path, right: &Path, contents: bool) -> Vec<(String, CompareStatus)> {
+        Comparison {
+            cancel: &CancellationToken::new(),
+            contents,
+            last_report: Instant::now(),
+            report: |_| {},
+        }
+        .compare(left, right)
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.relative.display().to_string(), entry.status))
+        .collect()
+    }
+
+    fn write(path: &Path, contents: &[u8], seconds: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    fn side(size: u64, seconds: Option<u64>) -> CompareSide {
+        CompareSide {
+            size,
+            modified: seconds.map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+            is_directory: false,
+        }
+    }
+
+    #[test_case(CompareStatus::OnlyLeft, true, false => Some(MirrorStep::Copy) ; "missing")]
+    #[test_case(CompareStatus::NewerLeft, true, false => Some(MirrorStep::Replace) ; "older")]
+    #[test_case(CompareStatus::NewerRight, true, false => Some(MirrorStep::Replace) ; "newer")]
+    #[test_case(CompareStatus::Differs, true, false => Some(MirrorStep::Replace) ; "different")]
+    #[test_case(CompareStatus::Identical, true, true => None ; "identical")]
+    #[test_case(CompareStatus::OnlyRight, true, false => None ; "extraneous kept")]
+    #[test_case(CompareStatus::OnlyRight, true, true => Some(MirrorStep::Delete) ; "extraneous deleted")]
+    #[test_case(CompareStatus::OnlyRight, false, false => Some(MirrorStep::Copy) ; "missing on the left")]
+    #[test_case(CompareStatus::OnlyLeft, false, true => Some(MirrorStep::Delete) ; "extraneous on the left")]
+    #[test_case(CompareStatus::Unreadable, true, true => Some(MirrorStep::Skip) ; "unreadable")]
+    fn a_mirror_makes_the_destination_match_the_source_whichever_is_newer(
+        status: CompareStatus,
+        to_right: bool,
+        delete: bool,
+    ) -> Option<MirrorStep> {
+        status.mirror_step(to_right, delete)
+    }
+
+    #[test_case(side(1, Some(5)), side(1, Some(5)), None => CompareStatus::Identical ; "same size and time")]
+    #[test_case(side(1, Some(9)), side(1, Some(5)), None => CompareStatus::NewerLeft ; "newer left")]
+    #[test_case(side(2, Some(5)), side(1, Some(9)), None => CompareStatus::NewerRight ; "newer right")]
+    #[test_case(side(2, Some(5)), side(1, Some(5)), None => CompareStatus::Differs ; "same time other size")]
+    #[test_case(side(1, None), side(1, None), None => CompareStatus::Differs ; "no times")]
+    #[test_case(side(1, Some(5)), side(1, Some(5)), Some(false) => CompareStatus::Differs ; "same time other contents")]
+    #[test_case(side(1, Some(9)), side(1, Some(5)), Some(true) => CompareStatus::Identical ; "other time same contents")]
+    fn the_status_is_from_size_and_time_unless_the_contents_were_read(
+        left: CompareSide,
+        right: CompareSide,
+        same_contents: Option<bool>,
+    ) -> CompareStatus {
+        status(&left, &right, same_contents)
+    }
+
+    #[test]
+    fn the_trees_are_merged_by_path() {
+        let fx = TempDir::new("compare");
+        let (left, right) = (fx.join("left"), fx.join("right"));
+        write(&left.join("same"), b"x", 100);
+        write(&right.join("same"), b"x", 100);
+        write(&left.join("sub/changed"), b"new", 200);
+        write(&right.join("sub/changed"), b"old", 100);
+        write(&left.join("only_dir/inside"), b"x", 100);
+        write(&right.join("extra"), b"x", 100);
+        write(&left.join("kind"), b"x", 100);
+        fs::create_dir_all(right.join("kind")).unwrap();
+
+        assert_eq!(
+            vec![
+                ("extra".to_string(), CompareStatus::OnlyRight),
+                ("kind".to_string(), CompareStatus::Differs),
+                // A directory missing from one side is one entry.
+                ("only_dir".to_string(), CompareStatus::OnlyLeft),
+                ("same".to_string(), CompareStatus::Identical),
+                ("sub/changed".to_string(), CompareStatus::NewerLeft),
+            ],
+            compare(&left, &right, false)
+        );
+    }
+
+    #[test]
+    fn a_source_subdirectory_that_cannot_be_read_is_not_taken_for_empty() {
+        // Permissions do not keep root out, so there is nothing to test.
+        if nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let fx = TempDir::new("compare");
+        let (left, right) = (fx.join("left"), fx.join("right"));
+        write(&left.join("sub/kept"), b"x", 100);
+        write(&right.join("sub/kept"), b"x", 100);
+        write(&right.join("sub/extra"), b"x", 100);
+        write(&right.join("other"), b"x", 100);
+        fs::set_permissions(left.join("sub"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let entries = compare(&left, &right, false);
+        fs::set_permissions(left.join("sub"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Nothing below it is listed, for a mirror with deletions to remove.
+        assert_eq!(
+            vec![
+                ("other".to_string(), CompareStatus::OnlyRight),
+                ("sub".to_string(), CompareStatus::Unreadable),
+            ],
+            entries
+        );
+    }
+
+    #[test]
+    fn a_root_that_cannot_be_read_is_not_taken_for_empty() {
+        let fx = TempDir::new("compare");
+        let (left, right) = (fx.join("left"), fx.join("right"));
+        write(&right.join("extra"), b"x", 100);
+
+        assert_eq!(
+            vec![(String::new(), CompareStatus::Unreadable)],
+            compare(&left, &right, false)
+        );
+    }
+
+    #[test]
+    fn by_contents_files_that_look_alike_can_differ() {
+        let fx = TempDir::new("compare");
+        let (left, right) = (fx.join("left"), fx.join("right"));
+        write(&left.join("a"), b"one", 100);
+        write(&right.join("a"), b"two", 100);
+        write(&left.join("b"), b"same", 100);
+        write(&right.join("b"), b"same", 300);
+
+        assert_eq!(
+            vec![
+                ("a".to_string(), CompareStatus::Identical),
+                ("b".to_string(), CompareStatus::NewerRight),
+            ],
+            compare(&left, &right, false)
+        );
+        assert_eq!(
+            vec![
+                ("a".to_string(), CompareStatus::Differs),
+                ("b".to_string(), CompareStatus::Identical),
+            ],
+            compare(&left, &right, true)
+        );
+    }
+
+    #[test]
+    fn a_cancelled_comparison_finds_nothing() {
+        let fx = TempDir::new("compare");
+        write(&fx.join("left/a"), b"x", 100);
+        fs::create_dir(fx.join("right")).unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let mut comparison = Comparison {
+            cancel: &cancel,
+            contents: false,
+            last_report: Instant::now(),
+            report: |_| {},
+        };
+
+        assert_eq!(
+            None,
+            comparison.compare(&fx.join("left"), &fx.join("right"))
+        );
+    }
+
+    #[test]
+    fn a_comparison_ends_with_the_entries() {
+        let fx = TempDir::new("compare");
+        write(&fx.join("left/a"), b"x", 100);
+        fs::create_dir(fx.join("right")).unwrap();
+        let (tx, rx) = channel();
+
+        run_compare(
+            tx,
+            CancellationToken::new(),
+            fx.join("left"),
+            fx.join("right"),
+            false,
+        );
+
+        let entries = rx
+            .iter()
+            .find_map(|command| match command {
+                Command::Compared(entries) => Some(entries),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!(CompareStatus::OnlyLeft, entries[0].status);
+    }
+}
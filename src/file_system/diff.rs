@@ -0,0 +1,220 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use similar::{ChangeTag, TextDiff, udiff::UnifiedHunkHeader};
+
+use super::path_info::compact;
+
+/// The largest file the built-in viewer diffs. Both files are read whole and
+/// diffed on the UI thread, which stays quick at this size.
+const MAX_DIFF_BYTES: u64 = 1024 * 1024;
+
+/// How many unchanged lines a hunk shows around each change, as `diff -u`.
+const CONTEXT_LINES: usize = 3;
+
+/// When to settle for a coarser diff of two files that differ throughout,
+/// rather than keep the UI waiting for the smallest one.
+const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How much of the start of a file is looked at for a NUL byte, as `grep`
+/// and `git` do to tell a binary file from a text one.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DiffLineKind {
+    Context,
+    Removed,
+    Added,
+    /// The `\ No newline at end of file` note after the line it is about.
+    NoNewline,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// The line without its newline.
+    pub text: String,
+}
+
+/// A run of changes and the context around them, headed by its
+/// `@@ -a,b +c,d @@` line.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DiffHunk {
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The unified diff of two text files, from `left` to `right`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FileDiff {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    /// How many lines were added and how many removed, as `diff --stat`
+    /// counts them.
+    pub fn counts(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|hunk| &hunk.lines);
+        lines.fold((0, 0), |(added, removed), line| match line.kind {
+            DiffLineKind::Added => (added + 1, removed),
+            DiffLineKind::Removed => (added, removed + 1),
+            DiffLineKind::Context | DiffLineKind::NoNewline => (added, removed),
+        })
+    }
+}
+
+/// Diffs the text files `left` and `right`. Fails for a file that cannot be
+/// read, is larger than the viewer takes, or is not text, naming it.
+pub(super) fn diff_files(left: &Path, right: &Path) -> Result<FileDiff> {
+    let old = read_text(left)?;
+    let new = read_text(right)?;
+    Ok(FileDiff {
+        left: left.to_path_buf(),
+        right: right.to_path_buf(),
+        hunks: diff_text(&old, &new),
+    })
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    let len = fs::metadata(path)
+        .map_err(|error| anyhow!("Failed to read {}: {error}", compact(path)))?
+        .len();
+    if len > MAX_DIFF_BYTES {
+        return Err(anyhow!(
+            "{} is too large to diff: only files up to {} MiB are",
+            compact(path),
+            MAX_DIFF_BYTES / 1024 / 1024
+        ));
+    }
+    let bytes =
+        fs::read(path).map_err(|error| anyhow!("Failed to read {}: {error}", compact(path)))?;
+    if is_binary(&bytes) {
+        return Err(anyhow!("{} is not a text file", compact(path)));
+    }
+    String::from_utf8(bytes).map_err(|_| anyhow!("{} is not UTF-8 text", compact(path)))
+}
+
+/// Whether `bytes`, a file or the start of it, are not text.
+pub(super) fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+fn diff_text(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(old, new);
+    diff.grouped_ops(CONTEXT_LINES)
+        .iter()
+        .map(|ops| {
+            let mut lines = Vec::new();
+            for change in ops.iter().flat_map(|op| diff.iter_changes(op)) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => DiffLineKind::Context,
+                    ChangeTag::Delete => DiffLineKind::Removed,
+                    ChangeTag::Insert => DiffLineKind::Added,
+                };
+                let text = change.value();
+                let text = text.strip_suffix('\n').unwrap_or(text);
+                lines.push(DiffLine {
+                    kind,
+                    text: text.strip_suffix('\r').unwrap_or(text).to_string(),
+                });
+                if change.missing_newline() {
+                    lines.push(DiffLine {
+                        kind: DiffLineKind::NoNewline,
+                        text: "No newline at end of file".into(),
+                    });
+                }
+            }
+            DiffHunk {
+                header: UnifiedHunkHeader::new(ops).to_string(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn line(kind: DiffLineKind, text: &str) -> DiffLine {
+        DiffLine {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn a_change_is_shown_within_its_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+
+        let hunks = diff_text(old, new);
+
+        assert_eq!(1, hunks.len());
+        assert_eq!("@@ -2,7 +2,7 @@", hunks[0].header);
+        assert_eq!(
+            vec![
+                line(DiffLineKind::Context, "2"),
+                line(DiffLineKind::Context, "3"),
+                line(DiffLineKind::Context, "4"),
+                line(DiffLineKind::Removed, "5"),
+                line(DiffLineKind::Added, "five"),
+                line(DiffLineKind::Context, "6"),
+                line(DiffLineKind::Context, "7"),
+                line(DiffLineKind::Context, "8"),
+            ],
+            hunks[0].lines
+        );
+    }
+
+    #[test]
+    fn distant_changes_are_separate_hunks() {
+        let mut lines: Vec<_> = (1..=20).map(|n| n.to_string()).collect();
+        let old = lines.join("\n");
+        lines[1] = "two".into();
+        lines[18] = "nineteen".into();
+        let new = lines.join("\n");
+
+        assert_eq!(2, diff_text(&old, &new).len());
+        assert!(diff_text(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn a_missing_final_newline_is_noted() {
+        let hunks = diff_text("a\n", "a\nb");
+
+        assert_eq!(
+            vec![
+                line(DiffLineKind::Context, "a"),
+                line(DiffLineKind::Added, "b"),
+                line(DiffLineKind::NoNewline, "No newline at end of file"),
+            ],
+            hunks[0].lines
+        );
+    }
+
+    #[test]
+    fn only_text_files_are_diffed() {
+        let fx = TempDir::new("diff");
+        fs::write(fx.join("a.txt"), "a\nb\n").unwrap();
+        fs::write(fx.join("b.txt"), "a\nc\n").unwrap();
+        fs::write(fx.join("binary"), b"a\0b").unwrap();
+        fs::write(fx.join("latin1"), b"caf\xe9").unwrap();
+
+        let diff = diff_files(&fx.join("a.txt"), &fx.join("b.txt")).unwrap();
+        assert_eq!((1, 1), diff.counts());
+
+        for name in ["binary", "latin1", "missing"] {
+            assert!(diff_files(&fx.join("a.txt"), &fx.join(name)).is_err());
+        }
+    }
+}
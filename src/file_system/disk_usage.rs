@@ -0,0 +1,342 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::Instant,
+};
+
+use super::stream::BATCH_FLUSH_INTERVAL;
+use crate::command::{Command, progress::CancellationToken};
+
+/// The disk space one entry takes, with everything under it for a directory.
+/// Counted the way `du -x` counts: allocated blocks rather than apparent
+/// length, so a sparse file takes what it really uses; a file with several
+/// hard links in the tree counts once; symlinks are not followed; and the
+/// scan stays on the filesystem it started on, which is the one that is full.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Usage {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub is_directory: bool,
+    /// Largest first, so the listing reads the way it is drilled into.
+    pub children: Vec<Usage>,
+}
+
+impl Usage {
+    pub fn name(&self) -> Cow<'_, str> {
+        self.path.file_name().map_or_else(
+            || self.path.to_string_lossy(),
+            |name| name.to_string_lossy(),
+        )
+    }
+
+    /// The entry at `path`, this one or one below it.
+    pub fn find(&self, path: &Path) -> Option<&Usage> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find(|child| path.starts_with(&child.path))?
+            .find(path)
+    }
+
+    /// Takes the entry at `path`, somewhere below this one, out of the tree and
+    /// every total it was part of. Returns the bytes it took, or `None` when
+    /// the tree holds no such entry.
+    pub fn remove(&mut self, path: &Path) -> Option<u64> {
+        let index = self
+            .children
+            .iter()
+            .position(|child| path.starts_with(&child.path))?;
+        let removed = if self.children[index].path == path {
+            self.children.remove(index).bytes
+        } else {
+            self.children[index].remove(path)?
+        };
+        self.bytes = self.bytes.saturating_sub(removed);
+        sort_largest_first(&mut self.children);
+        Some(removed)
+    }
+
+    /// Puts a fresh scan of an entry somewhere below this one in place of the
+    /// old one, moving every total it was part of by the difference. Returns
+    /// the bytes it took before, or `None` when the tree holds no such entry.
+    pub fn replace(&mut self, usage: Usage) -> Option<u64> {
+        let index = self
+            .children
+            .iter()
+            .position(|child| usage.path.starts_with(&child.path))?;
+        let new = usage.bytes;
+        let old = if self.children[index].path == usage.path {
+            std::mem::replace(&mut self.children[index], usage).bytes
+        } else {
+            self.children[index].replace(usage)?
+        };
+        self.bytes = self.bytes.saturating_sub(old) + new;
+        sort_largest_first(&mut self.children);
+        Some(old)
+    }
+}
+
+fn sort_largest_first(children: &mut [Usage]) {
+    children.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// Spawns a background scan of the tree at `root`, reporting the entries and
+/// bytes counted so far as `Command::DiskUsageProgress` at most every
+/// `BATCH_FLUSH_INTERVAL`, then the whole tree as `Command::DiskUsageScanned`.
+/// Nothing is sent once `cancel` is set, so a view closed mid-scan sees no
+/// late results.
+pub(super) fn run_disk_usage(tx: Sender<Command>, cancel: CancellationToken, root: PathBuf) {
+    thread::spawn(move || {
+        let mut scan = Scan {
+            cancel: &cancel,
+            device: None,
+            entries: 0,
+            bytes: 0,
+            linked: HashSet::new(),
+            last_report: Instant::now(),
+            report: |entries, bytes| {
+                if !cancel.is_cancelled() {
+                    let _ = tx.send(Command::DiskUsageProgress { entries, bytes });
+                }
+            },
+        };
+        let usage = scan.usage(&root);
+        if let Some(usage) = usage
+            && !cancel.is_cancelled()
+        {
+            let _ = tx.send(Command::DiskUsageScanned(usage));
+        }
+    });
+}
+
+/// One scan's running state.
+struct Scan<'a, F: Fn(u64, u64)> {
+    cancel: &'a CancellationToken,
+    /// The root's device, which the scan does not leave.
+    device: Option<u64>,
+    entries: u64,
+    bytes: u64,
+    /// Files with more than one name, already counted under one of them.
+    linked: HashSet<(u64, u64)>,
+    last_report: Instant,
+    report: F,
+}
+
+impl<F: Fn(u64, u64)> Scan<'_, F> {
+    /// The usage of the tree at `root`. `None` when cancelled, or when `root`
+    /// cannot be read at all.
+    fn usage(&mut self, root: &Path) -> Option<Usage> {
+        let metadata = root.symlink_metadata().ok()?;
+        self.device = Some(metadata.dev());
+        self.entry(root.to_path_buf(), &metadata)
+    }
+
+    fn entry(&mut self, path: PathBuf, metadata: &fs::Metadata) -> Option<Usage> {
+        if self.cancel.is_cancelled() {
+            return None;
+        }
+        let counted = metadata.is_dir()
+            || metadata.nlink() <= 1
+            || self.linked.insert((metadata.dev(), metadata.ino()));
+        let own = if counted { metadata.blocks() * 512 } else { 0 };
+        self.entries += 1;
+        self.bytes += own;
+        if self.last_report.elapsed() >= BATCH_FLUSH_INTERVAL {
+            (self.report)(self.entries, self.bytes);
+            self.last_report = Instant::now();
+        }
+
+        let mut usage = Usage {
+            path,
+            bytes: own,
+            is_directory: metadata.is_dir(),
+            children: Vec::new(),
+        };
+        // A mount point below the root is shown, but not entered.
+        if !usage.is_directory || Some(metadata.dev()) != self.device {
+            return Some(usage);
+        }
+        // A directory that cannot be listed counts as what it takes itself.
+        let Ok(entries) = fs::read_dir(&usage.path) else {
+            return Some(usage);
+        };
+        for entry in entries.flatten() {
+            // `DirEntry::metadata` does not follow symlinks.
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let child = self.entry(entry.path(), &metadata)?;
+            usage.bytes += child.bytes;
+            usage.children.push(child);
+        }
+        sort_largest_first(&mut usage.children);
+        Some(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::mpsc::channel};
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn scan(root: &Path, cancel: &CancellationToken) -> Option<Usage> {
+        Scan {
+            cancel,
+            device: None,
+            entries: 0,
+            bytes: 0,
+            linked: HashSet::new(),
+            last_report: Instant::now(),
+            report: |_, _| {},
+        }
+        .usage(root)
+    }
+
+    fn blocks(path: &Path) -> u64 {
+        path.symlink_metadata().unwrap().blocks() * 512
+    }
+
+    /// A tree whose files take whole blocks however the filesystem rounds.
+    fn tree(fx: &TempDir) -> PathBuf {
+        let root = fx.join("tree");
+        fs::create_dir_all(root.join("small")).unwrap();
+        fs::create_dir_all(root.join("large")).unwrap();
+        fs::write(root.join("small/a"), vec![1; 10_000]).unwrap();
+        fs::write(root.join("large/b"), vec![1; 100_000]).unwrap();
+        fs::write(root.join("large/c"), vec![1; 50_000]).unwrap();
+        root
+    }
+
+    #[test]
+    fn a_tree_is_totalled_and_listed_largest_first() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+
+        let usage = scan(&root, &CancellationToken::new()).unwrap();
+
+        let names: Vec<_> = usage.children.iter().map(Usage::name).collect();
+        assert_eq!(vec!["large", "small"], names);
+        let large = usage.find(&root.join("large")).unwrap();
+        assert_eq!(
+            blocks(&root.join("large"))
+                + blocks(&root.join("large/b"))
+                + blocks(&root.join("large/c")),
+            large.bytes
+        );
+        assert_eq!(
+            usage.bytes,
+            blocks(&root) + large.bytes + usage.find(&root.join("small")).unwrap().bytes
+        );
+    }
+
+    #[test]
+    fn a_hard_linked_file_counts_once_and_a_symlink_is_not_followed() {
+        let fx = TempDir::new("disk_usage");
+        let root = fx.join("tree");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a"), vec![1; 100_000]).unwrap();
+        fs::hard_link(root.join("a"), root.join("b")).unwrap();
+        fs::write(fx.join("outside"), vec![1; 100_000]).unwrap();
+        std::os::unix::fs::symlink(fx.join("outside"), root.join("link")).unwrap();
+
+        let usage = scan(&root, &CancellationToken::new()).unwrap();
+
+        assert_eq!(
+            blocks(&root) + blocks(&root.join("a")) + blocks(&root.join("link")),
+            usage.bytes
+        );
+    }
+
+    #[test]
+    fn a_cancelled_scan_has_no_tree() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        assert_eq!(None, scan(&root, &cancel));
+    }
+
+    #[test]
+    fn progress_is_reported_while_scanning() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+        let reported = Cell::new(None);
+        let cancel = CancellationToken::new();
+        let mut scan = Scan {
+            cancel: &cancel,
+            device: None,
+            entries: 0,
+            bytes: 0,
+            linked: HashSet::new(),
+            // Long enough ago that the first entry reports.
+            last_report: Instant::now().checked_sub(BATCH_FLUSH_INTERVAL).unwrap(),
+            report: |entries, bytes| reported.set(Some((entries, bytes))),
+        };
+
+        scan.usage(&root).unwrap();
+
+        assert_eq!(Some((1, blocks(&root))), reported.get());
+    }
+
+    #[test]
+    fn removing_an_entry_takes_it_out_of_every_total() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+        let mut usage = scan(&root, &CancellationToken::new()).unwrap();
+        let (total, large) = (usage.bytes, usage.children[0].bytes);
+        let b = blocks(&root.join("large/b"));
+
+        assert_eq!(Some(b), usage.remove(&root.join("large/b")));
+
+        assert_eq!(total - b, usage.bytes);
+        assert_eq!(large - b, usage.find(&root.join("large")).unwrap().bytes);
+        assert_eq!(None, usage.find(&root.join("large/b")));
+        assert_eq!(None, usage.remove(&root.join("missing")));
+    }
+
+    #[test]
+    fn a_rescanned_entry_moves_the_totals_by_the_difference() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+        let mut usage = scan(&root, &CancellationToken::new()).unwrap();
+        let total = usage.bytes;
+        let small = usage.find(&root.join("small")).unwrap().bytes;
+        fs::write(root.join("small/d"), vec![1; 500_000]).unwrap();
+        let rescanned = scan(&root.join("small"), &CancellationToken::new()).unwrap();
+        let grown = rescanned.bytes;
+
+        assert_eq!(Some(small), usage.replace(rescanned));
+
+        assert_eq!(total - small + grown, usage.bytes);
+        // Now the larger of the two, so it comes first.
+        assert_eq!(root.join("small"), usage.children[0].path);
+    }
+
+    #[test]
+    fn a_scan_ends_with_the_tree() {
+        let fx = TempDir::new("disk_usage");
+        let root = tree(&fx);
+        let (tx, rx) = channel();
+
+        run_disk_usage(tx, CancellationToken::new(), root.clone());
+
+        let scanned = rx
+            .iter()
+            .find_map(|command| match command {
+                Command::DiskUsageScanned(usage) => Some(usage),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(root, scanned.path);
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::Read,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::{Instant, SystemTime},
+};
+
+use anyhow::{Result, anyhow};
+
+use super::{path_info::compact, stream::BATCH_FLUSH_INTERVAL};
+use crate::command::{Command, progress::CancellationToken};
+
+/// How much of each file the first hashing pass reads. Files of the same size
+/// usually differ in their first few kilobytes, so most candidates are ruled
+/// out without reading them whole.
+const SAMPLE_BYTES: u64 = 4096;
+/// The read size of the full hashing pass, and how often it checks for a
+/// cancel.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// One name of a set of identical files, as it was when the scan read it.
+/// Replacing it is refused once it has changed since.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DuplicateFile {
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+}
+
+/// Files with the same contents. Oldest first.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DuplicateSet {
+    /// The size of each one.
+    pub bytes: u64,
+    pub files: Vec<DuplicateFile>,
+}
+
+impl DuplicateSet {
+    /// What keeping a single copy would free.
+    pub fn reclaimable(&self) -> u64 {
+        self.bytes * (self.files.len().saturating_sub(1) as u64)
+    }
+}
+
+/// How far a scan has got, by stage.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DuplicateProgress {
+    #[default]
+    Starting,
+    /// Files found so far.
+    Walking { files: u64 },
+    /// Files of a shared size whose first few kilobytes have been hashed.
+    Sampling { files: u64, total: u64 },
+    /// Bytes hashed of the files whose samples matched.
+    Hashing { bytes: u64, total: u64 },
+}
+
+/// Spawns a background scan for identical files in the tree at `root`,
+/// reporting how far it has got as `Command::DuplicatesProgress` at most every
+/// `BATCH_FLUSH_INTERVAL`, then the sets it found as
+/// `Command::DuplicatesFound`. Nothing is sent once `cancel` is set, so a view
+/// closed mid-scan sees no late results.
+pub(super) fn run_duplicates(tx: Sender<Command>, cancel: CancellationToken, root: PathBuf) {
+    thread::spawn(move || {
+        let mut scan = Scan {
+            cancel: &cancel,
+            last_report: Instant::now(),
+            report: |progress| {
+                if !cancel.is_cancelled() {
+                    let _ = tx.send(Command::DuplicatesProgress(progress));
+                }
+            },
+        };
+        if let Some(sets) = scan.duplicates(&root)
+            && !cancel.is_cancelled()
+        {
+            let _ = tx.send(Command::DuplicatesFound(sets));
+        }
+    });
+}
+
+/// Replaces `duplicate` with a hard link to `keep`, so the two names share
+/// one copy of the contents. The link is made beside `duplicate` and renamed
+/// over it, so a failure part way leaves `duplicate` as it was. Refused when
+/// either file has changed since the scan compared them, or when they are on
+/// different filesystems, which a hard link cannot span.
+pub(super) fn replace_with_hard_link(
+    keep: &DuplicateFile,
+    duplicate: &DuplicateFile,
+) -> Result<()> {
+    let kept = unchanged(keep)?;
+    let replaced = unchanged(duplicate)?;
+    if kept.dev() != replaced.dev() {
+        return Err(anyhow!(
+            "{} and {} are on different filesystems",
+            compact(&keep.path),
+            compact(&duplicate.path)
+        ));
+    }
+    if kept.ino() == replaced.ino() {
+        return Ok(());
+    }
+    let name = duplicate
+        .path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no name", compact(&duplicate.path)))?;
+    let mut temporary = name.to_os_string();
+    temporary.push(format!(".filectrl-link-{}", std::process::id()));
+    let temporary = duplicate.path.with_file_name(temporary);
+    fs::hard_link(&keep.path, &temporary)?;
+    fs::rename(&temporary, &duplicate.path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })?;
+    Ok(())
+}
+
+/// `file`'s metadata, provided it is still the file the scan read.
+fn unchanged(file: &DuplicateFile) -> Result<fs::Metadata> {
+    let metadata = file.path.symlink_metadata()?;
+    if !metadata.is_file() || metadata.modified().ok() != file.modified {
+        return Err(anyhow!(
+            "{} has changed since it was compared",
+            compact(&file.path)
+        ));
+    }
+    Ok(metadata)
+}
+
+enum Hashed {
+    Hash(blake3::Hash),
+    Unreadable,
+}
+
+/// One scan's running state.
+struct Scan<'a, F: Fn(DuplicateProgress)> {
+    cancel: &'a CancellationToken,
+    last_report: Instant,
+    report: F,
+}
+
+impl<F: Fn(DuplicateProgress)> Scan<'_, F> {
+    /// The sets of identical files below `root`, the most space to reclaim
+    /// first. `None` when cancelled.
+    ///
+    /// Files are grouped by size, then by a hash of their first
+    /// `SAMPLE_BYTES`, then by a hash of everything, each pass reading only
+    /// the files the one before could not tell apart. Symlinks are not
+    /// followed, empty files are left out, and a file with several hard links
+    /// is one file, already sharing its contents. A file that cannot be read
+    /// is left out of every set.
+    fn duplicates(&mut self, root: &Path) -> Option<Vec<DuplicateSet>> {
+        let by_size = self.walk(root)?;
+        let candidates: Vec<_> = by_size
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .collect();
+
+        let total = candidates.iter().map(|(_, files)| files.len() as u64).sum();
+        let mut sampled = 0;
+        let mut by_sample = HashMap::new();
+        for (bytes, files) in candidates {
+            for file in files {
+                if let Hashed::Hash(hash) = self.hash(&file.path, bytes.min(SAMPLE_BYTES))? {
+                    by_sample
+                        .entry((bytes, hash))
+                        .or_insert_with(Vec::new)
+                        .push(file);
+                }
+                sampled += 1;
+                self.maybe_report(DuplicateProgress::Sampling {
+                    files: sampled,
+                    total,
+                });
+            }
+        }
+
+        let mut sets = Vec::new();
+        let mut to_hash = Vec::new();
+        for ((bytes, _), files) in by_sample {
+            if files.len() < 2 {
+                continue;
+            }
+            // The sample was the whole file.
+            if bytes <= SAMPLE_BYTES {
+                sets.push(DuplicateSet { bytes, files });
+            } else {
+                to_hash.push((bytes, files));
+            }
+        }
+
+        let total = to_hash
+            .iter()
+            .map(|(bytes, files)| bytes * files.len() as u64)
+            .sum();
+        let mut hashed = 0;
+        for (bytes, files) in to_hash {
+            let mut by_hash = HashMap::new();
+            for file in files {
+                if let Hashed::Hash(hash) = self.hash(&file.path, bytes)? {
+                    by_hash.entry(hash).or_insert_with(Vec::new).push(file);
+                }
+                hashed += bytes;
+                self.maybe_report(DuplicateProgress::Hashing {
+                    bytes: hashed,
+                    total,
+                });
+            }
+            sets.extend(
+                by_hash
+                    .into_values()
+                    .filter(|files| files.len() > 1)
+                    .map(|files| DuplicateSet { bytes, files }),
+            );
+        }
+
+        for set in &mut sets {
+            set.files.sort_by(|a, b| {
+                a.modified
+                    .cmp(&b.modified)
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+        }
+        sets.sort_by(|a, b| {
+            b.reclaimable()
+                .cmp(&a.reclaimable())
+                .then_with(|| a.files[0].path.cmp(&b.files[0].path))
+        });
+        Some(sets)
+    }
+
+    /// The regular files below `root`, by size.
+    fn walk(&mut self, root: &Path) -> Option<HashMap<u64, Vec<DuplicateFile>>> {
+        let mut by_size: HashMap<u64, Vec<DuplicateFile>> = HashMap::new();
+        let mut inodes = HashSet::new();
+        let mut files = 0;
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(directory) = stack.pop() {
+            let Ok(entries) = fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if self.cancel.is_cancelled() {
+                    return None;
+                }
+                // `DirEntry::metadata` does not follow symlinks.
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    stack.push(entry.path());
+                    continue;
+                }
+                if !metadata.is_file()
+                    || metadata.len() == 0
+                    || !inodes.insert((metadata.dev(), metadata.ino()))
+                {
+                    continue;
+                }
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push(DuplicateFile {
+                        path: entry.path(),
+                        modified: metadata.modified().ok(),
+                    });
+                files += 1;
+                self.maybe_report(DuplicateProgress::Walking { files });
+            }
+        }
+        Some(by_size)
+    }
+
+    /// A hash of the first `limit` bytes of the file at `path`. `None` when
+    /// cancelled.
+    fn hash(&self, path: &Path, limit: u64) -> Option<Hashed> {
+        let Ok(file) = File::open(path) else {
+            return Some(Hashed::Unreadable);
+        };
+        let mut reader = file.take(limit);
+        let mut hasher = blake3::Hasher::new();
+        let mut buffer = vec![0; CHUNK_BYTES];
+        loop {
+            if self.cancel.is_cancelled() {
+                return None;
+            }
+            match reader.read(&mut buffer) {
+                Ok(0) => return Some(Hashed::Hash(hasher.finalize())),
+                Ok(read) => {
+                    hasher.update(&buffer[..read]);
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => return Some(Hashed::Unreadable),
+            }
+        }
+    }
+
+    fn maybe_report(&mut self, progress: DuplicateProgress) {
+        if self.last_report.elapsed() >= BATCH_FLUSH_INTERVAL {
+            (self.report)(progress);
+            self.last_report = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::mpsc::channel};
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn scan(root: &Path) -> Vec<DuplicateSet> {
+        Scan {
+            cancel: &CancellationToken::new(),
+            last_report: Instant::now(),
+            report: |_| {},
+        }
+        .duplicates(root)
+        .unwrap()
+    }
+
+    fn names(set: &DuplicateSet) -> Vec<&str> {
+        set.files
+            .iter()
+            .map(|file| file.path.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    fn file(path: PathBuf) -> DuplicateFile {
+        let modified = path.symlink_metadata().unwrap().modified().ok();
+        DuplicateFile { path, modified }
+    }
+
+    #[test]
+    fn identical_files_are_grouped_and_lookalikes_are_not() {
+        let fx = TempDir::new("duplicates");
+        fs::create_dir_all(fx.join("sub")).unwrap();
+        fs::write(fx.join("a"), b"same").unwrap();
+        fs::write(fx.join("sub/b"), b"same").unwrap();
+        // The same size, but different contents.
+        fs::write(fx.join("c"), b"diff").unwrap();
+        // The same first SAMPLE_BYTES, different after.
+        let mut long = vec![7; 10_000];
+        fs::write(fx.join("d"), &long).unwrap();
+        long[9_999] = 8;
+        fs::write(fx.join("e"), &long).unwrap();
+        fs::write(fx.join("empty1"), b"").unwrap();
+        fs::write(fx.join("empty2"), b"").unwrap();
+
+        let sets = scan(fx.path());
+
+        assert_eq!(1, sets.len());
+        let mut found = names(&sets[0]);
+        found.sort_unstable();
+        assert_eq!(vec!["a", "b"], found);
+        assert_eq!(4, sets[0].bytes);
+    }
+
+    #[test]
+    fn hard_links_and_symlinks_are_not_duplicates() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), b"contents").unwrap();
+        fs::hard_link(fx.join("a"), fx.join("b")).unwrap();
+        std::os::unix::fs::symlink(fx.join("a"), fx.join("c")).unwrap();
+
+        assert_eq!(Vec::<DuplicateSet>::new(), scan(fx.path()));
+    }
+
+    #[test]
+    fn the_most_space_to_reclaim_comes_first() {
+        let fx = TempDir::new("duplicates");
+        for name in ["small1", "small2", "small3"] {
+            fs::write(fx.join(name), b"x").unwrap();
+        }
+        for name in ["large1", "large2"] {
+            fs::write(fx.join(name), vec![1; 100_000]).unwrap();
+        }
+
+        let sets = scan(fx.path());
+
+        assert_eq!(2, sets.len());
+        assert_eq!(100_000, sets[0].reclaimable());
+        assert_eq!(2, sets[1].reclaimable());
+    }
+
+    #[test]
+    fn progress_is_reported_by_stage() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), vec![1; 10_000]).unwrap();
+        fs::write(fx.join("b"), vec![1; 10_000]).unwrap();
+        let reported = Cell::new(DuplicateProgress::Starting);
+        let cancel = CancellationToken::new();
+        let mut scan = Scan {
+            cancel: &cancel,
+            // Long enough ago that the first step reports.
+            last_report: Instant::now().checked_sub(BATCH_FLUSH_INTERVAL).unwrap(),
+            report: |progress| reported.set(progress),
+        };
+
+        scan.walk(fx.path()).unwrap();
+
+        assert_eq!(DuplicateProgress::Walking { files: 1 }, reported.get());
+    }
+
+    #[test]
+    fn a_cancelled_scan_finds_nothing() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), b"same").unwrap();
+        fs::write(fx.join("b"), b"same").unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let mut scan = Scan {
+            cancel: &cancel,
+            last_report: Instant::now(),
+            report: |_| {},
+        };
+
+        assert_eq!(None, scan.duplicates(fx.path()));
+    }
+
+    #[test]
+    fn a_duplicate_is_replaced_with_a_hard_link() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), b"same").unwrap();
+        fs::write(fx.join("b"), b"same").unwrap();
+
+        replace_with_hard_link(&file(fx.join("a")), &file(fx.join("b"))).unwrap();
+
+        let (a, b) = (
+            fx.join("a").metadata().unwrap(),
+            fx.join("b").metadata().unwrap(),
+        );
+        assert_eq!(a.ino(), b.ino());
+        assert_eq!(b"same", fs::read(fx.join("b")).unwrap().as_slice());
+        assert_eq!(2, fs::read_dir(fx.path()).unwrap().count());
+    }
+
+    #[test]
+    fn a_file_changed_since_the_scan_is_not_replaced() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), b"same").unwrap();
+        fs::write(fx.join("b"), b"same").unwrap();
+        let mut stale = file(fx.join("b"));
+        stale.modified = Some(SystemTime::UNIX_EPOCH);
+
+        assert!(replace_with_hard_link(&file(fx.join("a")), &stale).is_err());
+        assert_ne!(
+            fx.join("a").metadata().unwrap().ino(),
+            fx.join("b").metadata().unwrap().ino()
+        );
+    }
+
+    #[test]
+    fn a_scan_ends_with_the_sets() {
+        let fx = TempDir::new("duplicates");
+        fs::write(fx.join("a"), b"same").unwrap();
+        fs::write(fx.join("b"), b"same").unwrap();
+        let (tx, rx) = channel();
+
+        run_duplicates(tx, CancellationToken::new(), fx.path().to_path_buf());
+
+        let sets = rx
+            .iter()
+            .find_map(|command| match command {
+                Command::DuplicatesFound(sets) => Some(sets),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(1, sets.len());
+    }
+}
@@ -32,23 +32,8 @@ impl CommandHandler for FileSystem {
                 }
                 Err(message) => Command::AlertError(message).into(),
             },
-            Command::Chmod { paths, mode } => self.chmod(paths, mode),
-            Command::CreateDirectory(name) => self.create_directory(name),
-            Command::Copy { srcs, dest } => self.start_paste(false, srcs, dest),
-            Command::Move { srcs, dest } => self.start_paste(true, srcs, dest),
-            Command::ResolveConflict(choice) => self.resolve_conflict(*choice),
-            // Dismissing the conflict prompt abandons the rest of the paste.
-            // A no-op for every other prompt, which leaves nothing pending.
-            Command::CancelPrompt => self.cancel_paste(),
-            Command::Delete(paths) => {
-                let mut commands = Vec::new();
-                for path in paths {
-                    let (_, task_commands) = self.run_task(TaskCommand::Delete(path.clone()), None);
-                    commands.extend(task_commands);
-                }
-                commands.into()
-            }
             Command::Open(path) => self.open(path),
+            Command::OpenRemote(address) => self.open_remote(address),
             Command::OpenCurrentDirectory => self.open_current_directory(),
             Command::OpenNewWindow => self.open_new_window(),
             Command::OpenWith {
@@ -58,15 +43,141 @@ impl CommandHandler for FileSystem {
             } => self.open_with(working_dir.as_deref(), label, argv),
             Command::Progress(task) => self.check_progress_for_error(task),
             Command::RefreshDirectory => self.refresh(),
+            Command::SwitchedPane {
+                directory,
+                previous_directory,
+                ..
+            } => self.switch_pane(directory, previous_directory.as_ref()),
             Command::DirectoryListingComplete { generation } => {
                 self.on_listing_complete(*generation)
             }
-            Command::Rename { path, name } => self.rename(path, name),
+            Command::SizeDirectories {
+                directories,
+                generation,
+            } => self.size_directories(directories, *generation),
             Command::ExitedSearch { generation } => {
                 self.on_search_exited(*generation);
                 CommandResult::NotHandled
             }
             Command::StartSearch(query) => self.search(query),
+            _ => match self.handle_operation_command(command) {
+                CommandResult::NotHandled => self.handle_inspection_command(command),
+                result => result,
+            },
+        }
+    }
+}
+
+impl FileSystem {
+    /// The commands that change what is on disk.
+    fn handle_operation_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::Chmod {
+                paths,
+                mode,
+                recursive,
+            } => self.chmod(paths, mode, *recursive),
+            Command::Touch {
+                paths,
+                time,
+                recursive,
+            } => self.touch(paths, time, *recursive),
+            Command::Chown {
+                paths,
+                owner,
+                recursive,
+            } => self.chown(paths, owner, *recursive),
+            Command::Compress { paths, name } => self.compress(paths, name),
+            Command::Extract { archive, here } => self.extract(archive, *here),
+            Command::ArchiveSurveyed {
+                archive,
+                destination,
+                names,
+            } => self.on_archive_surveyed(archive, destination, names),
+            Command::CreateDirectory(name) => self.create_directory(name),
+            Command::CreateFile(name) => self.create_file(name),
+            Command::CreateFromTemplate { template, name } => {
+                self.create_from_template(template, name)
+            }
+            Command::Copy { srcs, dest } => self.start_paste(false, srcs, dest),
+            Command::Move { srcs, dest } => self.start_paste(true, srcs, dest),
+            Command::ResolveConflict(choice) => self.resolve_conflict(*choice),
+            // Dismissing the conflict prompt abandons the rest of the paste, or
+            // the extract waiting on it. A no-op for every other prompt, which
+            // leaves nothing pending.
+            Command::CancelPrompt => {
+                self.cancel_delete_summary();
+                self.cancel_paste()
+            }
+            Command::SummarizeDelete(paths) => self.summarize_delete(paths),
+            Command::ConfirmDelete => {
+                self.cancel_delete_summary();
+                CommandResult::NotHandled
+            }
+            Command::Delete(paths) => {
+                let mut commands = Vec::new();
+                for path in paths {
+                    let (_, task_commands) = self.run_task(TaskCommand::Delete(path.clone()), None);
+                    commands.extend(task_commands);
+                }
+                commands.into()
+            }
+            Command::Rename { path, name } => self.rename(path, name),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    /// The commands that read what is on disk for a view of its own: the
+    /// scans, comparisons, checksums and previews.
+    fn handle_inspection_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::ScanDiskUsage(root) => self.scan_disk_usage(root),
+            Command::CancelDiskUsage => self.cancel_disk_usage(),
+            Command::FindDuplicates(root) => self.find_duplicates(root),
+            Command::CancelDuplicates => self.cancel_duplicates(),
+            Command::HardLinkDuplicates(pairs) => self.hard_link_duplicates(pairs),
+            Command::CompareDirectories {
+                left,
+                right,
+                contents,
+            } => self.compare_directories(left, right, *contents),
+            Command::CancelCompare => self.cancel_compare(),
+            Command::CopyCompared {
+                left,
+                right,
+                copies,
+            } => self.copy_compared(left, right, copies),
+            Command::MirrorCompared {
+                source,
+                destination,
+                copies,
+                removals,
+            } => self.mirror_compared(source, destination, copies, removals),
+            Command::ComputeChecksums {
+                paths,
+                directory,
+                algorithm,
+            } => self.compute_checksums(paths, directory, *algorithm),
+            Command::VerifyChecksums(sums) => self.verify_checksums(sums),
+            Command::DiffFiles { left, right } => self.diff_files(left, right),
+            Command::Peek { path, column } => self.peek(path, *column),
+            Command::PreviewFile(path) => self.preview_file(path),
+            // Shown by a view that may be gone by the time they arrive, with
+            // nothing left to claim them: DiskUsageView, DuplicatesView and
+            // CompareView once closed mid-scan, MillerView and PreviewView
+            // once their columns are hidden, and PromptView, which is only reached while a prompt
+            // is open, for a count the walk sent just before its prompt was
+            // answered.
+            Command::DiskUsageProgress { .. }
+            | Command::DiskUsageScanned(_)
+            | Command::DuplicatesProgress(_)
+            | Command::DuplicatesFound(_)
+            | Command::DuplicatesLinked(_)
+            | Command::CompareProgress { .. }
+            | Command::Compared(_)
+            | Command::Peeked { .. }
+            | Command::Previewed { .. }
+            | Command::DeleteSummary(_) => CommandResult::Handled,
             _ => CommandResult::NotHandled,
         }
     }
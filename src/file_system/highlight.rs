@@ -0,0 +1,752 @@
+use std::path::Path;
+
+/// What a piece of a highlighted line is, which picks its style from the
+/// `[theme.preview]` section.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+/// A piece of a highlighted line, styled as one.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// A quoted string as a language writes it.
+struct Quote {
+    open: &'static str,
+    close: &'static str,
+    /// Whether a backslash escapes the character after it.
+    escapes: bool,
+    /// Whether it runs on past the end of its line until it is closed. One
+    /// that does not is closed by the end of its line, so that a stray quote
+    /// colours no more than the rest of that line.
+    multiline: bool,
+    /// Whether it holds a single character or escape, as a Rust or C
+    /// character does. A quote not closed right after one, such as a Rust
+    /// lifetime's, is no string at all.
+    is_char: bool,
+}
+
+const fn quote(open: &'static str, escapes: bool, multiline: bool) -> Quote {
+    Quote {
+        open,
+        close: open,
+        escapes,
+        multiline,
+        is_char: false,
+    }
+}
+
+const CHAR: Quote = Quote {
+    open: "'",
+    close: "'",
+    escapes: true,
+    multiline: false,
+    is_char: true,
+};
+
+/// What the highlighter knows of a language: how to tell its files, and how
+/// its keywords, strings and comments are written. It is a lexer, not a
+/// parser, and colours what can be told from the text alone.
+struct Language {
+    extensions: &'static [&'static str],
+    /// Names of files that have no extension to go by, such as `.bashrc`.
+    file_names: &'static [&'static str],
+    /// The interpreters a `#!` line names, without their versions.
+    interpreters: &'static [&'static str],
+    /// Sets of keywords, so that a language can share another's.
+    keywords: &'static [&'static [&'static str]],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Tried in order, so a longer opening goes before its prefix.
+    quotes: &'static [Quote],
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "NULL", "auto", "bool", "break", "case", "char", "const", "continue", "default", "do",
+    "double", "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int",
+    "long", "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct",
+    "switch", "true", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "catch",
+    "class",
+    "constexpr",
+    "delete",
+    "explicit",
+    "friend",
+    "namespace",
+    "new",
+    "noexcept",
+    "nullptr",
+    "operator",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typename",
+    "using",
+    "virtual",
+];
+
+const JS_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "null",
+    "of",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const TS_KEYWORDS: &[&str] = &[
+    "abstract",
+    "any",
+    "as",
+    "boolean",
+    "declare",
+    "enum",
+    "implements",
+    "interface",
+    "namespace",
+    "number",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "string",
+    "type",
+];
+
+const JS_QUOTES: &[Quote] = &[
+    quote("\"", true, false),
+    quote("'", true, false),
+    quote("`", true, true),
+];
+
+const LANGUAGES: &[Language] = &[
+    // Rust
+    Language {
+        extensions: &["rs"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[&[
+            "Self", "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+            "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+            "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ]],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[quote("\"", true, true), CHAR],
+    },
+    // C
+    Language {
+        extensions: &["c", "h"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[C_KEYWORDS],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[quote("\"", true, false), CHAR],
+    },
+    // C++
+    Language {
+        extensions: &["cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[C_KEYWORDS, CPP_KEYWORDS],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[quote("\"", true, false), CHAR],
+    },
+    // Go
+    Language {
+        extensions: &["go"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[&[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "fallthrough",
+            "false",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ]],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[quote("\"", true, false), quote("`", false, true), CHAR],
+    },
+    // Java
+    Language {
+        extensions: &["java"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[&[
+            "abstract",
+            "boolean",
+            "break",
+            "byte",
+            "case",
+            "catch",
+            "char",
+            "class",
+            "continue",
+            "default",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "float",
+            "for",
+            "if",
+            "implements",
+            "import",
+            "instanceof",
+            "int",
+            "interface",
+            "long",
+            "new",
+            "null",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "short",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "throws",
+            "true",
+            "try",
+            "var",
+            "void",
+            "while",
+        ]],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[quote("\"", true, false), CHAR],
+    },
+    // JavaScript
+    Language {
+        extensions: &["cjs", "js", "jsx", "mjs"],
+        file_names: &[],
+        interpreters: &["node"],
+        keywords: &[JS_KEYWORDS],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: JS_QUOTES,
+    },
+    // TypeScript
+    Language {
+        extensions: &["mts", "ts", "tsx"],
+        file_names: &[],
+        interpreters: &["deno", "ts-node"],
+        keywords: &[JS_KEYWORDS, TS_KEYWORDS],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: JS_QUOTES,
+    },
+    // Python
+    Language {
+        extensions: &["py", "pyi", "pyw"],
+        file_names: &[],
+        interpreters: &["python"],
+        keywords: &[&[
+            "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+            "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+            "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+            "return", "self", "try", "while", "with", "yield",
+        ]],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &[
+            quote("\"\"\"", true, true),
+            quote("'''", true, true),
+            quote("\"", true, false),
+            quote("'", true, false),
+        ],
+    },
+    // Shell
+    Language {
+        extensions: &["bash", "ksh", "sh", "zsh"],
+        file_names: &[
+            ".bash_logout",
+            ".bash_profile",
+            ".bashrc",
+            ".profile",
+            ".zprofile",
+            ".zshenv",
+            ".zshrc",
+        ],
+        interpreters: &["ash", "bash", "dash", "ksh", "sh", "zsh"],
+        keywords: &[&[
+            "break", "case", "continue", "declare", "do", "done", "elif", "else", "esac", "exit",
+            "export", "fi", "for", "function", "if", "in", "local", "readonly", "return", "select",
+            "shift", "then", "until", "unset", "while",
+        ]],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &[quote("\"", true, true), quote("'", false, true)],
+    },
+    // Ruby
+    Language {
+        extensions: &["rb"],
+        file_names: &["Gemfile", "Rakefile"],
+        interpreters: &["ruby"],
+        keywords: &[&[
+            "BEGIN", "END", "alias", "and", "begin", "break", "case", "class", "def", "do", "else",
+            "elsif", "end", "ensure", "false", "for", "if", "in", "module", "next", "nil", "not",
+            "or", "redo", "rescue", "retry", "return", "self", "super", "then", "true", "undef",
+            "unless", "until", "when", "while", "yield",
+        ]],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &[quote("\"", true, true), quote("'", true, true)],
+    },
+    // Lua
+    Language {
+        extensions: &["lua"],
+        file_names: &[],
+        interpreters: &["lua", "luajit"],
+        keywords: &[&[
+            "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto",
+            "if", "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until",
+            "while",
+        ]],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+        quotes: &[quote("\"", true, false), quote("'", true, false)],
+    },
+    // TOML
+    Language {
+        extensions: &["toml"],
+        file_names: &["Cargo.lock"],
+        interpreters: &[],
+        keywords: &[&["false", "true"]],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &[
+            quote("\"\"\"", true, true),
+            quote("'''", false, true),
+            quote("\"", true, false),
+            quote("'", false, false),
+        ],
+    },
+    // YAML, whose single quotes are left out: they are far more often an
+    // apostrophe in an unquoted string than a quoted string
+    Language {
+        extensions: &["yaml", "yml"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[&["false", "no", "null", "off", "on", "true", "yes"]],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &[quote("\"", true, false)],
+    },
+    // JSON
+    Language {
+        extensions: &["json"],
+        file_names: &[],
+        interpreters: &[],
+        keywords: &[&["false", "null", "true"]],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &[quote("\"", true, false)],
+    },
+];
+
+/// The largest file that is highlighted. Only its start is previewed, but a
+/// file this large is most often generated, minified or data, which colours
+/// help little with, and whose lines can run to the whole of what is read.
+pub(super) const MAX_HIGHLIGHT_BYTES: u64 = 256 * 1024;
+
+/// `lines`, the start of the file at `path`, split into the tokens they are
+/// styled by, or `None` when the file is in no language known here. The
+/// language is told by the file's extension or name, and failing those by
+/// the interpreter its `#!` line names.
+pub(super) fn highlight(path: &Path, lines: &[String]) -> Option<Vec<Vec<Token>>> {
+    let language = language_for(path, lines.first().map(String::as_str))?;
+    let mut open = None;
+    Some(
+        lines
+            .iter()
+            .map(|line| highlight_line(language, line, &mut open))
+            .collect(),
+    )
+}
+
+fn language_for(path: &Path, first_line: Option<&str>) -> Option<&'static Language> {
+    let name = path.file_name().and_then(|name| name.to_str());
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    LANGUAGES
+        .iter()
+        .find(|language| {
+            name.is_some_and(|name| language.file_names.contains(&name))
+                || extension
+                    .as_deref()
+                    .is_some_and(|extension| language.extensions.contains(&extension))
+        })
+        .or_else(|| {
+            let interpreter = interpreter(first_line?)?;
+            LANGUAGES
+                .iter()
+                .find(|language| language.interpreters.contains(&interpreter))
+        })
+}
+
+/// The interpreter a `#!` line runs, through `env` if need be, without its
+/// version: `python` for `#!/usr/bin/env python3.12`.
+fn interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Past its options and any variables it sets.
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    Some(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
+
+/// A comment or a string still open at the end of a line, which the next
+/// line carries on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Open {
+    Comment,
+    /// The index of its quote in the language's.
+    Quote(usize),
+}
+
+/// The tokens of `line`, with `open` carried in from the line before and
+/// out to the next.
+fn highlight_line(language: &Language, line: &str, open: &mut Option<Open>) -> Vec<Token> {
+    let mut tokens = Tokens::default();
+    let mut pos = 0;
+    while pos < line.len() {
+        let rest = &line[pos..];
+        match open.take() {
+            Some(Open::Comment) => {
+                let (_, close) = language.block_comment.unwrap_or_default();
+                let end = rest.find(close).map(|end| end + close.len());
+                *open = end.is_none().then_some(Open::Comment);
+                pos += tokens.push(TokenKind::Comment, rest, end);
+                continue;
+            }
+            Some(Open::Quote(index)) => {
+                let quote = &language.quotes[index];
+                let end = closing(quote, rest);
+                *open = (end.is_none() && quote.multiline).then_some(Open::Quote(index));
+                pos += tokens.push(TokenKind::String, rest, end);
+                continue;
+            }
+            None => {}
+        }
+        if let Some((opening, _)) = language.block_comment
+            && rest.starts_with(opening)
+        {
+            *open = Some(Open::Comment);
+            pos += tokens.push(TokenKind::Comment, rest, Some(opening.len()));
+        } else if language.line_comments.iter().any(|comment| {
+            // A `#` within a word, as in the shell's `${#list}`, starts no
+            // comment.
+            rest.starts_with(comment)
+                && (*comment != "#" || pos == 0 || line[..pos].ends_with(char::is_whitespace))
+        }) {
+            pos += tokens.push(TokenKind::Comment, rest, None);
+        } else if let Some((index, quote)) = language
+            .quotes
+            .iter()
+            .enumerate()
+            .find(|(_, quote)| rest.starts_with(quote.open))
+        {
+            if quote.is_char {
+                let end = char_literal(rest);
+                let kind = if end.is_some() {
+                    TokenKind::String
+                } else {
+                    TokenKind::Plain
+                };
+                pos += tokens.push(kind, rest, Some(end.unwrap_or(quote.open.len())));
+            } else {
+                *open = Some(Open::Quote(index));
+                pos += tokens.push(TokenKind::String, rest, Some(quote.open.len()));
+            }
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            pos += tokens.push(TokenKind::Number, rest, Some(number_len(rest)));
+        } else if rest.starts_with(is_word_char) {
+            let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            let kind = if language
+                .keywords
+                .iter()
+                .any(|keywords| keywords.contains(&&rest[..len]))
+            {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Plain
+            };
+            pos += tokens.push(kind, rest, Some(len));
+        } else {
+            let len = rest.chars().next().map_or(1, char::len_utf8);
+            pos += tokens.push(TokenKind::Plain, rest, Some(len));
+        }
+    }
+    // A quote that cannot span lines, opened as the line's last character
+    // or left open since, is closed by its end all the same.
+    if let Some(Open::Quote(index)) = *open
+        && !language.quotes[index].multiline
+    {
+        *open = None;
+    }
+    tokens.0
+}
+
+/// The tokens of a line, with neighbours of the same kind run together.
+#[derive(Default)]
+struct Tokens(Vec<Token>);
+
+impl Tokens {
+    /// Adds the first `len` bytes of `rest`, or all of it, as `kind`, and
+    /// returns how many bytes that took.
+    fn push(&mut self, kind: TokenKind, rest: &str, len: Option<usize>) -> usize {
+        let text = &rest[..len.unwrap_or(rest.len())];
+        match self.0.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(text),
+            _ => self.0.push(Token {
+                kind,
+                text: text.to_string(),
+            }),
+        }
+        text.len()
+    }
+}
+
+/// Where in `rest`, a string's text after its opening quote, the string
+/// ends, just past its closing quote.
+fn closing(quote: &Quote, rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if quote.escapes && c == '\\' {
+            chars.next();
+        } else if rest[index..].starts_with(quote.close) {
+            return Some(index + quote.close.len());
+        }
+    }
+    None
+}
+
+/// How long the character literal `rest` starts with is: a quote, one
+/// character or a backslash escape, and a quote.
+fn char_literal(rest: &str) -> Option<usize> {
+    let inner = &rest[1..];
+    let len = if inner.starts_with('\\') {
+        // Long enough for `\u{10FFFF}`.
+        inner
+            .char_indices()
+            .skip(2)
+            .take(10)
+            .find(|&(_, c)| c == '\'')?
+            .0
+    } else {
+        inner.chars().next().filter(|&c| c != '\'')?.len_utf8()
+    };
+    inner[len..].starts_with('\'').then_some(len + 2)
+}
+
+/// How long the number `rest` starts with is, suffixes and all: `0x1F`,
+/// `1.5e3` and `10u32` are numbers, while the dot of `1..2` is not part of
+/// one.
+fn number_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut len = 0;
+    while len < bytes.len() {
+        let byte = bytes[len];
+        let is_decimal_point = byte == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if !(byte.is_ascii_alphanumeric() || byte == b'_' || is_decimal_point) {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    /// `lines` as `(kind, text)` pairs, with the plain ones left out.
+    fn styled(path: &str, lines: &[&str]) -> Vec<(TokenKind, String)> {
+        let lines: Vec<_> = lines.iter().map(|line| (*line).to_string()).collect();
+        highlight(Path::new(path), &lines)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .filter(|token| token.kind != TokenKind::Plain)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    fn tokens(pairs: &[(TokenKind, &str)]) -> Vec<(TokenKind, String)> {
+        pairs
+            .iter()
+            .map(|(kind, text)| (*kind, (*text).to_string()))
+            .collect()
+    }
+
+    #[test_case("main.rs", "" => Some(&["rs"][..]) ; "by extension")]
+    #[test_case("MAIN.RS", "" => Some(&["rs"][..]) ; "by upper case extension")]
+    #[test_case(".bashrc", "" => Some(&["bash", "ksh", "sh", "zsh"][..]) ; "by file name")]
+    #[test_case("run", "#!/usr/bin/env python3.12" => Some(&["py", "pyi", "pyw"][..]) ; "by env shebang")]
+    #[test_case("run", "#!/usr/bin/env -S node --flag" => Some(&["cjs", "js", "jsx", "mjs"][..]) ; "by env shebang with options")]
+    #[test_case("run", "#!/bin/bash -e" => Some(&["bash", "ksh", "sh", "zsh"][..]) ; "by shebang")]
+    #[test_case("notes.txt", "#!/bin/sh" => Some(&["bash", "ksh", "sh", "zsh"][..]) ; "shebang over an unknown extension")]
+    #[test_case("notes.txt", "" => None ; "unknown")]
+    fn the_language_is_told_by_name_and_shebang(
+        path: &str,
+        first_line: &str,
+    ) -> Option<&'static [&'static str]> {
+        language_for(Path::new(path), Some(first_line)).map(|language| language.extensions)
+    }
+
+    #[test]
+    fn a_line_is_split_into_keywords_strings_numbers_and_comments() {
+        assert_eq!(
+            tokens(&[
+                (TokenKind::Keyword, "let"),
+                (TokenKind::String, "\"a \\\" b\""),
+                (TokenKind::Number, "0x1F"),
+                (TokenKind::Number, "1.5"),
+                (TokenKind::Number, "1"),
+                (TokenKind::Number, "2"),
+                (TokenKind::Comment, "// done"),
+            ]),
+            styled(
+                "a.rs",
+                &["let s = (\"a \\\" b\", 0x1F, 1.5, 1..2); // done"]
+            )
+        );
+    }
+
+    #[test]
+    fn comments_and_strings_run_on_across_lines() {
+        assert_eq!(
+            tokens(&[
+                (TokenKind::Comment, "/* a"),
+                (TokenKind::Comment, "b */"),
+                (TokenKind::Keyword, "fn"),
+            ]),
+            styled("a.rs", &["/* a", "b */ fn f"])
+        );
+        assert_eq!(
+            tokens(&[
+                (TokenKind::String, "\"\"\"a"),
+                (TokenKind::String, "b\"\"\""),
+                (TokenKind::Keyword, "pass"),
+            ]),
+            styled("a.py", &["x = \"\"\"a", "b\"\"\"", "pass"])
+        );
+        // A string that cannot span lines ends with its own.
+        assert_eq!(
+            tokens(&[(TokenKind::String, "\"a"), (TokenKind::Keyword, "true")]),
+            styled("a.go", &["\"a", "true"])
+        );
+        // Even when it opens as the last character of its line.
+        assert_eq!(
+            tokens(&[(TokenKind::String, "\""), (TokenKind::Keyword, "pass")]),
+            styled("a.py", &["x = \"", "pass"])
+        );
+    }
+
+    #[test_case("a.rs", "fn f<'a>(c: &'a str) -> char { 'x' }" => tokens(&[(TokenKind::Keyword, "fn"), (TokenKind::String, "'x'")]) ; "a lifetime is no string")]
+    #[test_case("a.rs", "let c = '\\u{1F600}';" => tokens(&[(TokenKind::Keyword, "let"), (TokenKind::String, "'\\u{1F600}'")]) ; "an escaped character")]
+    #[test_case("a.sh", "echo ${#list} # count" => tokens(&[(TokenKind::Comment, "# count")]) ; "a hash within a word")]
+    #[test_case("a.cpp", "if (x) return nullptr;" => tokens(&[(TokenKind::Keyword, "if"), (TokenKind::Keyword, "return"), (TokenKind::Keyword, "nullptr")]) ; "a language with another's keywords")]
+    fn what_looks_like_a_string_or_comment_may_not_be(
+        path: &str,
+        line: &str,
+    ) -> Vec<(TokenKind, String)> {
+        styled(path, &[line])
+    }
+}
@@ -0,0 +1,319 @@
+use std::{fs, sync::OnceLock};
+
+use anyhow::{Result, anyhow};
+
+/// The bits a mode may set: permissions plus setuid, setgid and sticky.
+const MODE_BITS: u32 = 0o7777;
+
+/// A chmod mode as typed into the prompt: octal (`644`) or symbolic
+/// (`u+x,go-w`, `a=rX`). A symbolic mode is relative, so it is resolved against
+/// each entry's current mode by `apply` rather than once up front.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Octal(u32),
+    Symbolic(Vec<Clause>),
+}
+
+/// One comma-separated clause of a symbolic mode: who it applies to and the
+/// operations, applied left to right (`u+r-w` is two).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Clause {
+    /// The bits `u`, `g` and `o` select, or `None` when no class was named.
+    who: Option<u32>,
+    operations: Vec<(Operator, Permissions)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Operator {
+    Add,
+    Remove,
+    Set,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Permissions {
+    /// Any of `rwxst`, plus whether `X` was given, which depends on the entry.
+    Bits {
+        bits: u32,
+        conditional_execute: bool,
+    },
+    /// `u`, `g` or `o` on the right of the operator: that class's current
+    /// `rwx`, copied. The shift is the class's position in the mode.
+    Copy(u32),
+}
+
+impl Mode {
+    pub fn parse(mode: &str) -> Result<Self> {
+        let invalid =
+            || anyhow!("Invalid mode {mode:?}: expected octal like 644 or symbolic like u+x,go-w");
+        if mode.starts_with(|c: char| c.is_ascii_digit()) {
+            return match u32::from_str_radix(mode, 8) {
+                Ok(bits) if bits <= MODE_BITS => Ok(Self::Octal(bits)),
+                _ => Err(invalid()),
+            };
+        }
+        mode.split(',')
+            .map(Clause::parse)
+            .collect::<Option<_>>()
+            .map(Self::Symbolic)
+            .ok_or_else(invalid)
+    }
+
+    /// The mode an entry whose mode is now `current` ends up with. `X` sets
+    /// execute only on a directory or on an entry someone can already
+    /// execute, which is what makes `a+rX` safe across a mixed tree.
+    pub fn apply(&self, current: u32, is_directory: bool) -> u32 {
+        match self {
+            Self::Octal(bits) => *bits,
+            Self::Symbolic(clauses) => {
+                let executable = is_directory || current & 0o111 != 0;
+                clauses.iter().fold(current & MODE_BITS, |mode, clause| {
+                    clause.apply(mode, executable)
+                })
+            }
+        }
+    }
+}
+
+impl Clause {
+    fn parse(clause: &str) -> Option<Self> {
+        let operators_at = clause.find(['+', '-', '='])?;
+        let (who, mut rest) = clause.split_at(operators_at);
+        let who = if who.is_empty() {
+            None
+        } else {
+            Some(who.chars().try_fold(0, |bits, class| {
+                Some(
+                    bits | match class {
+                        'u' => 0o4700,
+                        'g' => 0o2070,
+                        'o' => 0o1007,
+                        'a' => MODE_BITS,
+                        _ => return None,
+                    },
+                )
+            })?)
+        };
+        let mut operations = Vec::new();
+        while let Some(operator) = rest.chars().next() {
+            let operator = match operator {
+                '+' => Operator::Add,
+                '-' => Operator::Remove,
+                '=' => Operator::Set,
+                _ => return None,
+            };
+            rest = &rest[1..];
+            let end = rest.find(['+', '-', '=']).unwrap_or(rest.len());
+            operations.push((operator, Permissions::parse(&rest[..end])?));
+            rest = &rest[end..];
+        }
+        Some(Self { who, operations })
+    }
+
+    fn apply(&self, mut mode: u32, executable: bool) -> u32 {
+        // With no class named, chmod(1) applies the clause to everyone but
+        // leaves out what the umask would withhold from a new file, so `+w`
+        // does not make a file world-writable.
+        let (who, mask) = match self.who {
+            Some(who) => (who, MODE_BITS),
+            None => (MODE_BITS, !umask()),
+        };
+        for &(operator, permissions) in &self.operations {
+            let bits = permissions.bits(mode, executable) & who & mask;
+            mode = match operator {
+                Operator::Add => mode | bits,
+                Operator::Remove => mode & !bits,
+                Operator::Set => (mode & !who) | bits,
+            };
+        }
+        mode
+    }
+}
+
+impl Permissions {
+    fn parse(permissions: &str) -> Option<Self> {
+        match permissions {
+            "u" => return Some(Self::Copy(6)),
+            "g" => return Some(Self::Copy(3)),
+            "o" => return Some(Self::Copy(0)),
+            _ => {}
+        }
+        let mut bits = 0;
+        let mut conditional_execute = false;
+        for permission in permissions.chars() {
+            match permission {
+                'r' => bits |= 0o444,
+                'w' => bits |= 0o222,
+                'x' => bits |= 0o111,
+                'X' => conditional_execute = true,
+                's' => bits |= 0o6000,
+                't' => bits |= 0o1000,
+                _ => return None,
+            }
+        }
+        Some(Self::Bits {
+            bits,
+            conditional_execute,
+        })
+    }
+
+    /// The bits for every class; the clause narrows them to its own.
+    fn bits(self, mode: u32, executable: bool) -> u32 {
+        match self {
+            Self::Bits {
+                bits,
+                conditional_execute,
+            } => {
+                if conditional_execute && executable {
+                    bits | 0o111
+                } else {
+                    bits
+                }
+            }
+            Self::Copy(shift) => ((mode >> shift) & 0o7) * 0o111,
+        }
+    }
+}
+
+/// The modes a recursive chmod applies: one for directories and one for
+/// everything else, since the `755`/`644` a source tree wants are not one mode.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TreeModes {
+    pub directories: Mode,
+    pub files: Mode,
+}
+
+impl TreeModes {
+    /// `<mode>` for both, or `<directory mode> <file mode>`.
+    pub fn parse(modes: &str) -> Result<Self> {
+        match modes.split_whitespace().collect::<Vec<_>>()[..] {
+            [mode] => {
+                let mode = Mode::parse(mode)?;
+                Ok(Self {
+                    directories: mode.clone(),
+                    files: mode,
+                })
+            }
+            [directories, files] => Ok(Self {
+                directories: Mode::parse(directories)?,
+                files: Mode::parse(files)?,
+            }),
+            _ => Err(anyhow!(
+                "Invalid modes {modes:?}: expected one mode, or a directory mode and a file mode"
+            )),
+        }
+    }
+
+    pub fn for_entry(&self, is_directory: bool) -> &Mode {
+        if is_directory {
+            &self.directories
+        } else {
+            &self.files
+        }
+    }
+}
+
+/// The process umask. Read rather than set and restored: `umask(2)` has no
+/// read-only form, and a worker creating files while it was briefly changed
+/// would get the wrong permissions. Where `/proc` does not report it, the
+/// usual `022` is assumed.
+fn umask() -> u32 {
+    static UMASK: OnceLock<u32> = OnceLock::new();
+    *UMASK.get_or_init(|| {
+        fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status.lines().find_map(|line| {
+                    let value = line.strip_prefix("Umask:")?;
+                    u32::from_str_radix(value.trim(), 8).ok()
+                })
+            })
+            .unwrap_or(0o022)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn apply(mode: &str, current: u32, is_directory: bool) -> u32 {
+        Mode::parse(mode).unwrap().apply(current, is_directory)
+    }
+
+    #[test_case("644" => Some(0o644) ; "three digits")]
+    #[test_case("0" => Some(0o0) ; "a single zero")]
+    #[test_case("7777" => Some(0o7777) ; "the largest accepted value")]
+    #[test_case("4755" => Some(0o4755) ; "a setuid bit")]
+    #[test_case("10000" => None ; "one past the largest")]
+    #[test_case("888" => None ; "digits outside octal")]
+    #[test_case("0o644" => None ; "a rust literal prefix")]
+    #[test_case("-1" => None ; "negative")]
+    fn an_octal_mode_is_absolute(mode: &str) -> Option<u32> {
+        // The current mode must not show through: octal replaces it whole.
+        Mode::parse(mode).ok().map(|mode| mode.apply(0o7777, false))
+    }
+
+    #[test_case("u+x", 0o644, false => 0o744 ; "add for one class")]
+    #[test_case("go-w", 0o666, false => 0o644 ; "remove for two classes")]
+    #[test_case("a=r", 0o755, false => 0o444 ; "set replaces the classes' bits")]
+    #[test_case("u+x,go-w", 0o666, false => 0o744 ; "clauses apply in order")]
+    #[test_case("u=rw,go=", 0o777, false => 0o600 ; "set to nothing clears")]
+    #[test_case("u+r-w", 0o200, false => 0o400 ; "several operations in one clause")]
+    #[test_case("g=u", 0o740, false => 0o770 ; "copy another class's bits")]
+    #[test_case("u+s", 0o755, false => 0o4755 ; "setuid belongs to the user")]
+    #[test_case("g+s", 0o755, true => 0o2755 ; "setgid belongs to the group")]
+    #[test_case("o+t", 0o777, true => 0o1777 ; "sticky")]
+    #[test_case("u+t", 0o755, true => 0o755 ; "sticky is not the user's")]
+    fn a_symbolic_mode_edits_the_current_one(mode: &str, current: u32, is_directory: bool) -> u32 {
+        apply(mode, current, is_directory)
+    }
+
+    /// `X` is what lets one mode fix a whole source tree: directories stay
+    /// enterable and scripts runnable without every text file becoming
+    /// executable.
+    #[test_case(0o600, true => 0o755 ; "a directory")]
+    #[test_case(0o700, false => 0o755 ; "a file someone can execute")]
+    #[test_case(0o600, false => 0o644 ; "a file nobody can execute")]
+    fn capital_x_executes_only_what_was_executable(current: u32, is_directory: bool) -> u32 {
+        apply("a=rX,u+w", current, is_directory)
+    }
+
+    #[test]
+    fn no_class_named_respects_the_umask() {
+        let expected = 0o777 & !umask();
+
+        assert_eq!(expected, apply("+rwx", 0, false));
+    }
+
+    #[test_case("u+q" ; "unknown permission")]
+    #[test_case("z+x" ; "unknown class")]
+    #[test_case("ux" ; "no operator")]
+    #[test_case("u+x," ; "trailing comma")]
+    #[test_case("" ; "empty")]
+    fn a_malformed_symbolic_mode_is_rejected(mode: &str) {
+        let error = Mode::parse(mode).unwrap_err().to_string();
+        assert!(error.contains(&format!("{mode:?}")), "{error}");
+    }
+
+    #[test]
+    fn one_tree_mode_applies_to_both_kinds() {
+        let modes = TreeModes::parse("a+rX").unwrap();
+
+        assert_eq!(modes.directories, modes.files);
+    }
+
+    #[test]
+    fn two_tree_modes_are_directories_then_files() {
+        let modes = TreeModes::parse("755 644").unwrap();
+
+        assert_eq!(&Mode::Octal(0o755), modes.for_entry(true));
+        assert_eq!(&Mode::Octal(0o644), modes.for_entry(false));
+    }
+
+    #[test]
+    fn three_tree_modes_are_rejected() {
+        assert!(TreeModes::parse("755 644 600").is_err());
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     fs,
     path::{Path, PathBuf},
     process::{Child, Stdio},
-    sync::mpsc::Sender,
+    sync::{Arc, mpsc::Sender},
     thread,
     time::Duration,
 };
@@ -12,6 +12,9 @@ use anyhow::{Result, anyhow};
 use log::{info, warn};
 
 use super::{
+    archive::{self, ArchiveFormat},
+    backend::{Backend, Local},
+    braces,
     path_info::{PathInfo, compact},
     shell,
     stream::{BATCH_FLUSH_INTERVAL, Batcher, batch_sender},
@@ -26,6 +29,7 @@ const CD_BATCH_SIZE: usize = 256;
 /// can be ignored, and `cancel` stops the walk when that happens. Off the UI
 /// thread, so navigating into a very large directory stays responsive.
 pub(super) fn stream_cd(
+    backend: Arc<dyn Backend>,
     directory: PathInfo,
     generation: u64,
     tx: Sender<Command>,
@@ -33,7 +37,12 @@ pub(super) fn stream_cd(
 ) {
     info!("Streaming directory {directory:?}");
     thread::spawn(move || {
-        let entries = match fs::read_dir(&directory.path) {
+        if let Some(archive) = directory.archive() {
+            let archive = archive.to_path_buf();
+            stream_archive_directory(&directory, &archive, generation, &tx, &cancel);
+            return;
+        }
+        let entries = match backend.list(&directory.path) {
             Ok(entries) => entries,
             Err(error) => {
                 let _ = tx.send(Command::AlertWarn(format!(
@@ -56,7 +65,7 @@ pub(super) fn stream_cd(
                 return;
             }
             let path = match entry {
-                Ok(entry) => entry.path(),
+                Ok(path) => path,
                 Err(error) => {
                     warn!(
                         "Failed to read an entry in {}: {error}",
@@ -66,7 +75,11 @@ pub(super) fn stream_cd(
                     continue;
                 }
             };
-            match PathInfo::try_from(&path) {
+            let info = match directory.remote() {
+                Some(remote) => PathInfo::read_remote(remote, &path),
+                None => PathInfo::read(&*backend, &path),
+            };
+            match info {
                 Ok(info) => {
                     if !batcher.push(info, &send) {
                         return; // channel closed
@@ -92,16 +105,84 @@ pub(super) fn stream_cd(
     });
 }
 
+/// `stream_cd` for a directory inside an archive: its entries come from the
+/// archive's index rather than `read_dir`, built in one pass the first time
+/// since an archive (a compressed tar above all) can only be read front to
+/// back.
+fn stream_archive_directory(
+    directory: &PathInfo,
+    archive: &Path,
+    generation: u64,
+    tx: &Sender<Command>,
+    cancel: &CancellationToken,
+) {
+    let inner = directory.path_in_archive().unwrap_or(Path::new(""));
+    let children = archive
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no name", compact(archive)))
+        .and_then(|name| ArchiveFormat::from_name(&name.to_string_lossy()))
+        .and_then(|format| {
+            Ok(archive::children(archive, format, inner, &|| {
+                cancel.is_cancelled()
+            })?)
+        });
+    if cancel.is_cancelled() {
+        return;
+    }
+    match children {
+        Ok(children) => {
+            let send = batch_sender(tx, generation);
+            let mut batcher = Batcher::new(CD_BATCH_SIZE, BATCH_FLUSH_INTERVAL);
+            for child in &children {
+                if !batcher.push(PathInfo::in_archive(directory, child), &send) {
+                    return; // channel closed
+                }
+            }
+            if !batcher.flush(&send) {
+                return;
+            }
+        }
+        Err(error) => {
+            let _ = tx.send(Command::AlertWarn(format!(
+                "Failed to read directory {}: {error}",
+                compact(&directory.path)
+            )));
+        }
+    }
+    let _ = tx.send(Command::DirectoryListingComplete { generation });
+}
+
 pub(super) fn open_in(path: &PathInfo, template: &str, command_tx: Sender<Command>) -> Result<()> {
     info!("Opening \"{path:?}\" using template: \"{template}\"");
     if template.is_empty() {
         return Ok(());
     }
     let command = shell::template(template, &shell::quote(path.path.as_os_str()));
+    run_in_shell(&command, command_tx)
+}
+
+/// The command line that diffs `left` and `right` with the `openers.diff`
+/// template, whose `%s`s are the two paths in turn. Run in the foreground
+/// rather than detached: a diff tool is typically a terminal program, which
+/// needs the terminal this one is drawing on.
+pub(super) fn diff_command(left: &Path, right: &Path, template: &str) -> OsString {
+    info!(
+        "Diffing \"{}\" and \"{}\" using template: \"{template}\"",
+        left.display(),
+        right.display()
+    );
+    let (left, right) = (
+        shell::quote(left.as_os_str()),
+        shell::quote(right.as_os_str()),
+    );
+    shell::template_each(template, &[&left, &right])
+}
+
+fn run_in_shell(command: &OsStr, command_tx: Sender<Command>) -> Result<()> {
     // Only for the message: the command line itself is passed to the shell as
     // raw bytes, so a path that is not valid UTF-8 still reaches the program.
     let label = format!("Command {:?}", command.to_string_lossy());
-    let child = spawn_detached("sh", [OsStr::new("-c"), command.as_os_str()])
+    let child = spawn_detached("sh", [OsStr::new("-c"), command])
         .map_err(|error| anyhow!("Failed to run {label}: {error}"))?;
     watch_for_immediate_failure(child, label, command_tx);
     Ok(())
@@ -197,11 +278,131 @@ pub(super) fn add_bookmark(dir: &Path, target: &PathInfo, name: &str) -> Result<
     Ok(())
 }
 
-pub(super) fn create_directory(parent: &PathInfo, name: &str) -> Result<()> {
-    validate_basename("Directory name", name)?;
+/// Creates every directory `pattern` names below `parent`, as `mkdir -p`
+/// would: `pattern` may span several components (`a/b/c`) and expand braces
+/// (`{2024,2025}/{q1,q2}`), and the directories leading to each are created as
+/// needed. Returns the directories named, in the order the pattern gives them.
+///
+/// Every name is checked before any is created, so a pattern that is invalid
+/// or names something already there creates nothing at all. That the named
+/// directory itself must be new is the one way this is stricter than `mkdir
+/// -p`: an existing entry is an error rather than silently adopted.
+pub(super) fn create_directory(
+    backend: &dyn Backend,
+    parent: &PathInfo,
+    pattern: &str,
+) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for name in braces::expand(pattern)? {
+        let name = name.trim_end_matches(std::path::MAIN_SEPARATOR);
+        validate_relative_path("Directory name", name)?;
+        let path = parent.as_path().join(name);
+        if backend.stat(&path).is_ok() {
+            return Err(anyhow!("{} already exists", compact(&path)));
+        }
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    for path in &paths {
+        info!("Creating directory {}", path.display());
+        backend.create_dir_all(path)?;
+    }
+    Ok(paths)
+}
+
+/// Creates an empty file at `name` below `parent`, creating the directories
+/// between them as needed. An entry already at `name` is an error rather than
+/// being truncated. Returns the file's path.
+pub(super) fn create_file(backend: &dyn Backend, parent: &PathInfo, name: &str) -> Result<PathBuf> {
+    let path = new_entry_path(backend, parent, "File name", name)?;
+    info!("Creating file {}", path.display());
+    backend.create(&path)?;
+    Ok(path)
+}
+
+/// Copies `template` to `name` below `parent`, creating the directories
+/// between them as needed. The copy takes the template's permissions, so an
+/// executable script template stays executable. With `overwrite`, a file
+/// already at `name` is replaced; a directory never is. Returns the copy's
+/// path.
+pub(super) fn create_from_template(
+    parent: &PathInfo,
+    template: &Path,
+    name: &str,
+    overwrite: bool,
+) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = new_entry_path(&Local, parent, "File name", name)?;
+    info!(
+        "Creating {} from template {}",
+        path.display(),
+        template.display()
+    );
+    let mut source = fs::File::open(template)?;
+    let mode = source.metadata()?.permissions().mode();
+    if overwrite {
+        match path.symlink_metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                return Err(anyhow!("{} is a directory", compact(&path)));
+            }
+            // Removed rather than written through: `create_new` below then
+            // cannot follow a symlink that is in the way to somewhere else.
+            Ok(_) => fs::remove_file(&path)?,
+            Err(_) => {}
+        }
+    }
+    let mut destination = fs::File::create_new(&path)?;
+    std::io::copy(&mut source, &mut destination)?;
+    destination.set_permissions(fs::Permissions::from_mode(mode & 0o7777))?;
+    Ok(path)
+}
+
+/// The path `name` denotes below `parent`, with the directories leading to it
+/// created. `name` may span several components (`src/lib/mod.rs`) but may not
+/// leave `parent`.
+fn new_entry_path(
+    backend: &dyn Backend,
+    parent: &PathInfo,
+    kind: &str,
+    name: &str,
+) -> Result<PathBuf> {
+    validate_relative_path(kind, name)?;
     let path = parent.as_path().join(name);
-    info!("Creating directory {}", path.display());
-    fs::create_dir(&path)?;
+    if let Some(directory) = path.parent() {
+        backend.create_dir_all(directory)?;
+    }
+    Ok(path)
+}
+
+/// Rejects a name that cannot denote an entry below the directory it is joined
+/// to: the relative-path counterpart of `validate_basename`. A `..` anywhere is
+/// refused even where it would stay inside, since it would have to climb out
+/// of a directory that may not exist yet to get there.
+pub(super) fn validate_relative_path(kind: &str, name: &str) -> Result<()> {
+    use std::path::Component;
+
+    if name.is_empty() {
+        return Err(anyhow!("{kind} cannot be empty"));
+    }
+    if name.ends_with(std::path::MAIN_SEPARATOR) {
+        return Err(anyhow!(
+            "{kind} cannot end with {:?}",
+            std::path::MAIN_SEPARATOR
+        ));
+    }
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(_) => {}
+            Component::CurDir | Component::ParentDir => {
+                return Err(anyhow!("{kind} cannot contain \".\" or \"..\""));
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(anyhow!("{kind} must be relative to the current directory"));
+            }
+        }
+    }
     Ok(())
 }
 
@@ -209,7 +410,7 @@ pub(super) fn create_directory(parent: &PathInfo, name: &str) -> Result<()> {
 /// joined to. `Path::join` discards the base when handed an absolute path, so
 /// without this a prompt value can create or rename an entry anywhere on the
 /// filesystem rather than in the directory the user is looking at.
-fn validate_basename(kind: &str, name: &str) -> Result<()> {
+pub(super) fn validate_basename(kind: &str, name: &str) -> Result<()> {
     if name.is_empty() {
         return Err(anyhow!("{kind} cannot be empty"));
     }
@@ -225,7 +426,7 @@ fn validate_basename(kind: &str, name: &str) -> Result<()> {
     Ok(())
 }
 
-pub(super) fn rename(path: &PathInfo, new_basename: &str) -> Result<()> {
+pub(super) fn rename(backend: &dyn Backend, path: &PathInfo, new_basename: &str) -> Result<()> {
     validate_basename("New name", new_basename)?;
     let old_path = path.as_path();
     let new_path = join_parent(old_path, new_basename);
@@ -235,7 +436,7 @@ pub(super) fn rename(path: &PathInfo, new_basename: &str) -> Result<()> {
         // destination would be misreported as the problem. Only NotFound
         // means vanished; other errors (e.g. permission denied) must not
         // claim the file is gone.
-        if let Err(error) = old_path.symlink_metadata() {
+        if let Err(error) = backend.stat(old_path) {
             return Err(if error.kind() == std::io::ErrorKind::NotFound {
                 anyhow!("{} no longer exists", compact(old_path))
             } else {
@@ -244,8 +445,8 @@ pub(super) fn rename(path: &PathInfo, new_basename: &str) -> Result<()> {
         }
         // Refuse to overwrite: `fs::rename` would silently replace an
         // existing destination.
-        if new_path.symlink_metadata().is_ok() {
-            if !is_same_file(old_path, &new_path) {
+        if backend.stat(&new_path).is_ok() {
+            if !is_same_file(backend, old_path, &new_path) {
                 return Err(anyhow!("{} already exists", compact(&new_path)));
             }
             // Same underlying file. A case-only change is a real rename on a
@@ -260,7 +461,7 @@ pub(super) fn rename(path: &PathInfo, new_basename: &str) -> Result<()> {
                 ));
             }
         }
-        fs::rename(old_path, new_path)?;
+        backend.rename(old_path, &new_path)?;
     }
     Ok(())
 }
@@ -278,10 +479,9 @@ fn is_case_only_change(a: &Path, b: &Path) -> bool {
 /// True when both paths resolve to the same underlying file (device and
 /// inode). Links are not followed, so a symlink is compared as the link
 /// itself.
-fn is_same_file(a: &Path, b: &Path) -> bool {
-    use std::os::unix::fs::MetadataExt;
-    match (a.symlink_metadata(), b.symlink_metadata()) {
-        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+fn is_same_file(backend: &dyn Backend, a: &Path, b: &Path) -> bool {
+    match (backend.stat(a), backend.stat(b)) {
+        (Ok(a), Ok(b)) => a.device == b.device && a.inode == b.inode,
         _ => false,
     }
 }
@@ -326,7 +526,7 @@ mod tests {
     use test_case::test_case;
 
     use super::*;
-    use crate::test_support::TempDir;
+    use crate::{file_system::backend::Memory, test_support::TempDir};
 
     #[test_case("/b", "/a", "b"; "/a to b relative")]
     #[test_case("/b", "/a", "/b"; "/a to /b absolute")]
@@ -351,12 +551,13 @@ mod tests {
     #[test_case("" ; "empty")]
     #[test_case("." ; "current directory")]
     #[test_case(".." ; "parent directory")]
-    #[test_case("nested/name" ; "relative path")]
-    fn create_directory_rejects_a_name_that_is_not_a_basename(name: &str) {
+    #[test_case("nested/../name" ; "a parent component")]
+    #[test_case("{ok,..}" ; "an expansion that climbs out")]
+    fn create_directory_rejects_a_name_that_leaves_the_parent(name: &str) {
         let dir = TempDir::new("ops_create");
         let parent = PathInfo::try_from(dir.path()).unwrap();
 
-        assert!(create_directory(&parent, name).is_err());
+        assert!(create_directory(&Local, &parent, name).is_err());
         assert!(is_empty(&dir));
     }
 
@@ -370,11 +571,51 @@ mod tests {
         // call's doing and not another process's.
         let escape = TempDir::reserved("ops_create_escape");
 
-        assert!(create_directory(&parent, escape.path().to_str().unwrap()).is_err());
+        assert!(create_directory(&Local, &parent, escape.path().to_str().unwrap()).is_err());
         assert!(!escape.path().exists());
         assert!(is_empty(&dir));
     }
 
+    #[test]
+    fn a_directory_streams_from_its_backend() {
+        let memory = Memory::default();
+        memory.file("/dir/a.txt", b"a").file("/dir/sub/b.txt", b"b");
+        let directory = PathInfo::read(&memory, Path::new("/dir")).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        stream_cd(Arc::new(memory), directory, 7, tx, CancellationToken::new());
+
+        let mut names = Vec::new();
+        for command in rx {
+            match command {
+                Command::ListingBatch { items, generation } => {
+                    assert_eq!(7, generation);
+                    names.extend(items.into_iter().map(|item| item.name().into_owned()));
+                }
+                Command::DirectoryListingComplete { .. } => break,
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        names.sort();
+        assert_eq!(vec!["a.txt", "sub/"], names);
+    }
+
+    #[test]
+    fn create_and_rename_work_through_the_backend() {
+        let memory = Memory::default();
+        memory.create_dir(Path::new("/dir")).unwrap();
+        let parent = PathInfo::read(&memory, Path::new("/dir")).unwrap();
+
+        let path = create_file(&memory, &parent, "src/a.txt").unwrap();
+        let created = PathInfo::read(&memory, &path).unwrap();
+        rename(&memory, &created, "b.txt").unwrap();
+
+        assert!(memory.exists(Path::new("/dir/src/b.txt")).unwrap());
+        assert!(!memory.exists(Path::new("/dir/src/a.txt")).unwrap());
+        // Nothing here touched the disk.
+        assert!(!Path::new("/dir/src").exists());
+    }
+
     #[test]
     fn rename_refuses_existing_destination() {
         let dir = TempDir::new("ops_rename");
@@ -384,11 +625,11 @@ mod tests {
         fs::write(&b, b"b").unwrap();
 
         let info = PathInfo::try_from(a.as_path()).unwrap();
-        assert!(rename(&info, "b.txt").is_err());
+        assert!(rename(&Local, &info, "b.txt").is_err());
         // The existing destination must be untouched.
         assert_eq!(b"b".to_vec(), fs::read(&b).unwrap());
 
-        assert!(rename(&info, "c.txt").is_ok());
+        assert!(rename(&Local, &info, "c.txt").is_ok());
         assert!(dir.join("c.txt").exists());
     }
 
@@ -403,7 +644,7 @@ mod tests {
         let info = PathInfo::try_from(a.as_path()).unwrap();
         // Renaming onto another hard link of the same inode would be a POSIX
         // no-op; report it rather than silently succeeding.
-        let error = rename(&info, "b.txt").unwrap_err().to_string();
+        let error = rename(&Local, &info, "b.txt").unwrap_err().to_string();
         assert!(error.contains("same file"), "unexpected error: {error}");
         assert!(a.exists());
         assert!(b.exists());
@@ -425,7 +666,7 @@ mod tests {
         fs::hard_link(&a, &upper).unwrap();
 
         let info = PathInfo::try_from(a.as_path()).unwrap();
-        assert!(rename(&info, "A.TXT").is_ok());
+        assert!(rename(&Local, &info, "A.TXT").is_ok());
         assert!(upper.exists());
     }
 
@@ -472,23 +713,147 @@ mod tests {
         let dir = TempDir::new("ops_mkdir");
         let parent = PathInfo::try_from(dir.path()).unwrap();
 
-        create_directory(&parent, "brand_new").unwrap();
+        create_directory(&Local, &parent, "brand_new").unwrap();
 
         assert!(dir.join("brand_new").is_dir());
     }
 
+    #[test]
+    fn create_directory_creates_nested_and_expanded_paths() {
+        let dir = TempDir::new("ops_mkdir_p");
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        let created = create_directory(&Local, &parent, "{2024,2025}/{q1,q2}/").unwrap();
+
+        assert_eq!(
+            vec![
+                dir.join("2024/q1"),
+                dir.join("2024/q2"),
+                dir.join("2025/q1"),
+                dir.join("2025/q2"),
+            ],
+            created
+        );
+        assert!(created.iter().all(|path| path.is_dir()));
+    }
+
+    #[test]
+    fn create_directory_creates_nothing_when_one_name_is_taken() {
+        let dir = TempDir::new("ops_mkdir_p_taken");
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+
+        // Checked up front, so `a` is not left behind by a submission that
+        // reported failure.
+        assert!(create_directory(&Local, &parent, "{a,b}").is_err());
+        assert!(!dir.join("a").exists());
+    }
+
     #[test]
     fn create_directory_refuses_a_name_already_taken() {
         let dir = TempDir::new("ops_mkdir_exists");
         let parent = PathInfo::try_from(dir.path()).unwrap();
         fs::write(dir.join("taken"), b"x").unwrap();
 
-        // `create_dir` rather than `create_dir_all`, so an existing entry is
-        // an error instead of silently adopting whatever is already there.
-        assert!(create_directory(&parent, "taken").is_err());
+        // Checked before `create_dir_all`, which would otherwise silently
+        // adopt a directory already there.
+        assert!(create_directory(&Local, &parent, "taken").is_err());
         assert!(dir.join("taken").is_file());
     }
 
+    // ── create_file / create_from_template ──────────────────────────────────
+
+    #[test]
+    fn create_file_makes_the_directories_leading_to_it() {
+        let dir = TempDir::new("ops_touch");
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        create_file(&Local, &parent, "src/bin/main.rs").unwrap();
+
+        assert_eq!(0, fs::metadata(dir.join("src/bin/main.rs")).unwrap().len());
+    }
+
+    #[test]
+    fn create_file_refuses_a_name_already_taken() {
+        let dir = TempDir::new("ops_touch_exists");
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+        fs::write(dir.join("taken"), b"kept").unwrap();
+
+        // A new file must never truncate the one already there.
+        assert!(create_file(&Local, &parent, "taken").is_err());
+        assert_eq!(b"kept".to_vec(), fs::read(dir.join("taken")).unwrap());
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("../escaped" ; "climbing out")]
+    #[test_case("a/../../escaped" ; "climbing out from below")]
+    #[test_case("./a" ; "a current directory component")]
+    #[test_case("a/" ; "a trailing separator")]
+    #[test_case("/tmp/escaped" ; "absolute")]
+    fn create_file_rejects_a_path_that_leaves_the_directory(name: &str) {
+        let dir = TempDir::new("ops_touch_invalid");
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        assert!(create_file(&Local, &parent, name).is_err());
+        assert!(is_empty(&dir));
+    }
+
+    #[test]
+    fn a_template_copy_keeps_the_templates_content_and_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("ops_template");
+        let template = dir.join("script.sh");
+        fs::write(&template, b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(&template, fs::Permissions::from_mode(0o755)).unwrap();
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        create_from_template(&parent, &template, "bin/run.sh", false).unwrap();
+
+        let copy = dir.join("bin/run.sh");
+        assert_eq!(b"#!/bin/sh\n".to_vec(), fs::read(&copy).unwrap());
+        assert_eq!(
+            0o755,
+            fs::metadata(&copy).unwrap().permissions().mode() & 0o7777
+        );
+    }
+
+    #[test]
+    fn a_template_copy_replaces_only_when_asked() {
+        let dir = TempDir::new("ops_template_overwrite");
+        let template = dir.join("template.txt");
+        fs::write(&template, b"template").unwrap();
+        fs::write(dir.join("outside.txt"), b"outside").unwrap();
+        std::os::unix::fs::symlink(dir.join("outside.txt"), dir.join("taken.txt")).unwrap();
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        assert!(create_from_template(&parent, &template, "taken.txt", false).is_err());
+        create_from_template(&parent, &template, "taken.txt", true).unwrap();
+
+        // The link in the way is replaced, not written through.
+        assert_eq!(
+            b"template".to_vec(),
+            fs::read(dir.join("taken.txt")).unwrap()
+        );
+        assert!(!dir.join("taken.txt").is_symlink());
+        assert_eq!(
+            b"outside".to_vec(),
+            fs::read(dir.join("outside.txt")).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_template_copy_never_replaces_a_directory() {
+        let dir = TempDir::new("ops_template_directory");
+        let template = dir.join("template.txt");
+        fs::write(&template, b"template").unwrap();
+        fs::create_dir_all(dir.join("taken/inner")).unwrap();
+        let parent = PathInfo::try_from(dir.path()).unwrap();
+
+        assert!(create_from_template(&parent, &template, "taken", true).is_err());
+        assert!(dir.join("taken/inner").is_dir());
+    }
+
     // ── add_bookmark ────────────────────────────────────────────────────────
 
     #[test]
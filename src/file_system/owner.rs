@@ -0,0 +1,182 @@
+use std::{fs, io, os::unix::fs as unix_fs, path::Path};
+
+use anyhow::{Result, anyhow};
+use nix::unistd::{Group, Uid, User};
+
+/// A chown target as typed into the prompt, resolved to ids once up front so
+/// every entry of a recursive change gets the same answer. Either half may be
+/// absent, in which case that id is left as it is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Owner {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl Owner {
+    /// `user`, `user:group`, `:group`, or `user:` for the user and their login
+    /// group, as chown(1) reads them. Each half is a name or a numeric id; a
+    /// name wins over an id that looks the same, as it does for chown.
+    pub fn parse(owner: &str) -> Result<Self> {
+        let (user_name, group) = match owner.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (owner, None),
+        };
+        if user_name.is_empty() && group.is_none_or(str::is_empty) {
+            return Err(anyhow!(
+                "Invalid owner {owner:?}: expected user, user:group or :group"
+            ));
+        }
+        let user = if user_name.is_empty() {
+            None
+        } else {
+            Some(resolve_user(user_name)?)
+        };
+        let gid = match group {
+            Some("") => match &user {
+                Some(ResolvedUser { gid: Some(gid), .. }) => Some(*gid),
+                _ => {
+                    return Err(anyhow!(
+                        "Invalid owner {owner:?}: {user_name:?} has no login group"
+                    ));
+                }
+            },
+            Some(group) => Some(resolve_group(group)?),
+            None => None,
+        };
+        Ok(Self {
+            uid: user.map(|user| user.uid),
+            gid,
+        })
+    }
+
+    /// Changes the owner of `path`, or of what it points at when `follow` and
+    /// it is a symlink.
+    pub fn apply(self, path: &Path, follow: bool) -> io::Result<()> {
+        if follow {
+            unix_fs::chown(path, self.uid, self.gid)
+        } else {
+            unix_fs::lchown(path, self.uid, self.gid)
+        }
+    }
+
+    /// Whether an entry now owned by `uid` and `gid` would be left unchanged.
+    pub fn is_satisfied_by(self, uid: u32, gid: u32) -> bool {
+        self.uid.is_none_or(|wanted| wanted == uid) && self.gid.is_none_or(|wanted| wanted == gid)
+    }
+}
+
+/// A resolved user: their id, and the login group `user:` asks for, which a
+/// bare uid with no passwd entry does not have.
+struct ResolvedUser {
+    uid: u32,
+    gid: Option<u32>,
+}
+
+fn resolve_user(user: &str) -> Result<ResolvedUser> {
+    let found = User::from_name(user)
+        .ok()
+        .flatten()
+        .or_else(|| {
+            let uid = Uid::from_raw(user.parse().ok()?);
+            User::from_uid(uid).ok().flatten()
+        })
+        .map(|found| ResolvedUser {
+            uid: found.uid.as_raw(),
+            gid: Some(found.gid.as_raw()),
+        });
+    match (found, user.parse()) {
+        (Some(found), _) => Ok(found),
+        // An id with no passwd entry is still a valid owner.
+        (None, Ok(uid)) => Ok(ResolvedUser { uid, gid: None }),
+        (None, Err(_)) => Err(anyhow!("Unknown user {user:?}")),
+    }
+}
+
+fn resolve_group(group: &str) -> Result<u32> {
+    match Group::from_name(group).ok().flatten() {
+        Some(found) => Ok(found.gid.as_raw()),
+        None => group
+            .parse()
+            .map_err(|_| anyhow!("Unknown group {group:?}")),
+    }
+}
+
+/// The names in the passwd database, sorted, for the chown prompt to complete.
+pub fn user_names() -> Vec<String> {
+    database_names("/etc/passwd")
+}
+
+/// The names in the group database, sorted, for the chown prompt to complete.
+pub fn group_names() -> Vec<String> {
+    database_names("/etc/group")
+}
+
+/// The first field of each entry of a colon-separated database. nix resolves a
+/// name through NSS but has no way to list them, so completion reads the local
+/// files: a directory-service account is still accepted when typed in full,
+/// it is just not offered.
+fn database_names(path: &str) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut names = parse_database_names(&contents);
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn parse_database_names(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split(':').next())
+        // `+` and `-` lines are NIS compat markers, not names.
+        .filter(|name| !name.is_empty() && !name.starts_with(['+', '-']))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("0" => Some((Some(0), None)) ; "a uid alone")]
+    #[test_case("0:0" => Some((Some(0), Some(0))) ; "a uid and gid")]
+    #[test_case(":0" => Some((None, Some(0))) ; "a gid alone")]
+    #[test_case("root" => Some((Some(0), None)) ; "a user name")]
+    #[test_case("root:root" => Some((Some(0), Some(0))) ; "a user and group name")]
+    #[test_case("root:" => Some((Some(0), Some(0))) ; "a trailing colon adds the login group")]
+    #[test_case("" => None ; "empty")]
+    #[test_case(":" => None ; "a bare colon")]
+    #[test_case("no-such-user-here" => None ; "an unknown user")]
+    #[test_case("4000000:" => None ; "a login group for a uid with no entry")]
+    #[test_case(":no-such-group-here" => None ; "an unknown group")]
+    fn parse_cases(owner: &str) -> Option<(Option<u32>, Option<u32>)> {
+        Owner::parse(owner).ok().map(|owner| (owner.uid, owner.gid))
+    }
+
+    #[test]
+    fn an_unknown_name_is_named_in_the_error() {
+        let error = Owner::parse("root:no-such-group-here")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("\"no-such-group-here\""), "{error}");
+    }
+
+    #[test_case(Some(1), None => true ; "only the named half is compared")]
+    #[test_case(Some(1), Some(2) => false ; "a differing group")]
+    #[test_case(None, None => true ; "nothing asked for")]
+    fn is_satisfied_by_an_entry_owned_by_1_and_5(uid: Option<u32>, gid: Option<u32>) -> bool {
+        Owner { uid, gid }.is_satisfied_by(1, 5)
+    }
+
+    #[test]
+    fn database_names_skip_comments_and_compat_markers() {
+        let contents = "# comment\nroot:x:0:0::/root:/bin/sh\n+::::::\n-nobody\n\nalice:x:1000:\n";
+
+        assert_eq!(vec!["root", "alice"], parse_database_names(contents));
+    }
+}
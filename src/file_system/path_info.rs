@@ -3,8 +3,8 @@ use std::{
     cmp, env,
     fmt::{self, Display},
     io,
-    os::unix::prelude::{MetadataExt, PermissionsExt},
     path::{MAIN_SEPARATOR, MAIN_SEPARATOR_STR, Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
 };
 
@@ -12,9 +12,26 @@ use anyhow::{Error, Result};
 use chrono::{DateTime, Datelike, Local};
 use nix::unistd::{Gid, Group, Uid, User};
 
+use super::{
+    archive::{self, ArchiveFormat, Child, EntryKind},
+    backend::{Backend, Local as LocalDisk},
+    sftp::{self, Address, Sftp},
+};
+
 const FACTOR: u64 = 1024;
 const UNITS: [&str; 6] = ["", "K", "M", "G", "T", "P"];
 
+/// File type bits of `st_mode`, for entries of an archive, which have no
+/// `stat` to read them from.
+const TYPE_DIRECTORY: u32 = 0o040_000;
+const TYPE_FILE: u32 = 0o100_000;
+const TYPE_SYMLINK: u32 = 0o120_000;
+/// Permissions shown for an archive entry that records none: what `umask 022`
+/// leaves a new directory, file or symlink.
+const DEFAULT_DIRECTORY_PERMISSIONS: u32 = 0o755;
+const DEFAULT_FILE_PERMISSIONS: u32 = 0o644;
+const DEFAULT_SYMLINK_PERMISSIONS: u32 = 0o777;
+
 fn display_name(path: &Path) -> String {
     path.file_name()
         .map_or(String::new(), |n| n.to_string_lossy().into_owned())
@@ -107,9 +124,186 @@ pub struct PathInfo {
     symlink_broken: bool,
     accessed: Option<DateTime<Local>>,
     created: Option<DateTime<Local>>,
+    /// The archive this entry is inside, for an entry of an archive being
+    /// browsed rather than one on disk. `path` is then the archive's path
+    /// joined with the entry's name inside it: what the breadcrumbs show, and a
+    /// path nothing on disk answers to.
+    archive: Option<PathBuf>,
+    /// The session to the host this entry is on, for an entry read over SFTP
+    /// rather than from this machine's disk. `path` is then the host's path.
+    remote: Option<Sftp>,
 }
 
 impl PathInfo {
+    /// `archive`, as the directory its top level is browsed through. It keeps
+    /// the archive's owner and times, and its read bits gain the matching
+    /// search bits so the permissions column reads as a directory's would.
+    pub fn archive_root(archive: &PathInfo) -> Self {
+        let permissions = archive.mode & 0o7777;
+        let mut root = archive.clone();
+        root.mode = TYPE_DIRECTORY | permissions | ((permissions & 0o444) >> 2);
+        root.symlink_broken = false;
+        root.archive = Some(archive.path.clone());
+        root.remote = None;
+        root
+    }
+
+    /// `child`, listed inside `directory`, an archive directory. Its device and
+    /// inode are left at zero: it has neither, and `is_same_inode` compares
+    /// archive entries by path instead.
+    pub fn in_archive(directory: &PathInfo, child: &Child) -> Self {
+        let (kind, permissions) = match child.kind {
+            EntryKind::Directory => (TYPE_DIRECTORY, DEFAULT_DIRECTORY_PERMISSIONS),
+            EntryKind::Symlink(_) => (TYPE_SYMLINK, DEFAULT_SYMLINK_PERMISSIONS),
+            // A hard link is stored as a second name for an earlier file,
+            // which is what it extracts to.
+            EntryKind::File | EntryKind::HardLink(_) => (TYPE_FILE, DEFAULT_FILE_PERMISSIONS),
+            EntryKind::Other => (0, 0),
+        };
+        let path = directory.path.join(&child.name);
+        Self {
+            accessed: None,
+            archive: directory.archive.clone(),
+            created: None,
+            device: 0,
+            display_name: display_name(&path),
+            gid: directory.gid,
+            inode: 0,
+            mode: kind | child.mode.map_or(permissions, |mode| mode & 0o7777),
+            modified: child.modified.map(DateTime::<Local>::from),
+            path,
+            remote: None,
+            size: child.size,
+            // Whether a link resolves depends on where it is extracted to.
+            symlink_broken: false,
+            uid: directory.uid,
+        }
+    }
+
+    /// The entry at `path` in `backend`. `TryFrom<&Path>` is this for the
+    /// local disk.
+    pub fn read(backend: &dyn Backend, path: &Path) -> Result<Self> {
+        let stat = backend.stat(path)?;
+        let time = |time: Option<SystemTime>| time.map(DateTime::<Local>::from);
+        Ok(Self {
+            accessed: time(stat.accessed),
+            archive: None,
+            created: time(stat.created),
+            device: stat.device,
+            display_name: display_name(path),
+            gid: stat.gid,
+            inode: stat.inode,
+            mode: stat.mode,
+            modified: time(stat.modified),
+            path: path.to_path_buf(),
+            remote: None,
+            size: stat.size,
+            // Only a symlink can be broken, so nothing else pays for the
+            // second look at the path. `exists` follows the link, so a
+            // permission error on the target (or on a parent component) is not
+            // misreported as broken; only a confirmed "does not exist" counts.
+            symlink_broken: unix_mode::is_symlink(stat.mode)
+                && matches!(backend.exists(path), Ok(false)),
+            uid: stat.uid,
+        })
+    }
+
+    /// The entry at `path` on the host `remote` reaches.
+    pub fn read_remote(remote: &Sftp, path: &Path) -> Result<Self> {
+        let mut info = Self::read(remote, path)?;
+        info.remote = Some(remote.clone());
+        Ok(info)
+    }
+
+    /// The remote entry at `location`, an `sftp://` URL as `location` gives
+    /// it. `None` when it is not one. Only a host already connected to is
+    /// read: connecting blocks, and whatever asks is on the UI thread.
+    pub fn remote_at(location: &str) -> Option<Result<Self>> {
+        if !location.starts_with(sftp::SCHEME) {
+            return None;
+        }
+        let address = Address::parse(location)?;
+        let authority = address.authority();
+        Some(match sftp::session(&authority) {
+            Some(remote) => Self::read_remote(&remote, Path::new(&address.path)),
+            None => Err(anyhow::anyhow!(
+                "Not connected to {authority}: go to {address} first"
+            )),
+        })
+    }
+
+    /// The archive entry at `path`, named as its listing names it: the
+    /// archive's path joined with the entry's path inside it. Reads the
+    /// archive to learn what the entry is. `None` when no directory above
+    /// `path` is an archive on disk, for a path that is not inside one.
+    pub fn in_archive_at(path: &Path) -> Option<Result<Self>> {
+        let (archive, format) = path.ancestors().skip(1).find_map(|ancestor| {
+            let format = ArchiveFormat::from_name(&ancestor.file_name()?.to_string_lossy()).ok()?;
+            ancestor.is_file().then_some((ancestor, format))
+        })?;
+        let inner = path.strip_prefix(archive).ok()?;
+        Some(Self::try_from(archive).and_then(|archive_info| {
+            let mut directory = Self::archive_root(&archive_info);
+            let parent = inner.parent().unwrap_or(Path::new(""));
+            for name in parent.components() {
+                directory = Self::in_archive(
+                    &directory,
+                    &Child {
+                        name: name.as_os_str().to_owned(),
+                        kind: EntryKind::Directory,
+                        size: 0,
+                        mode: None,
+                        modified: None,
+                    },
+                );
+            }
+            let name = inner.file_name().unwrap_or_default();
+            archive::children(archive, format, parent, &|| false)?
+                .iter()
+                .find(|child| child.name == name)
+                .map(|child| Self::in_archive(&directory, child))
+                .ok_or_else(|| anyhow::anyhow!("{} holds no {}", compact(archive), inner.display()))
+        }))
+    }
+
+    /// The archive this entry is inside, if it is an archive entry.
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    /// This entry's path inside its archive, empty for the archive's top
+    /// level, or `None` for an entry on disk.
+    pub fn path_in_archive(&self) -> Option<&Path> {
+        let archive = self.archive.as_deref()?;
+        Some(self.path.strip_prefix(archive).unwrap_or(Path::new("")))
+    }
+
+    /// The host this entry is on, if it is a remote entry.
+    pub fn remote(&self) -> Option<&Sftp> {
+        self.remote.as_ref()
+    }
+
+    /// What this entry is listed, changed and copied through.
+    pub fn backend(&self) -> Arc<dyn Backend> {
+        match &self.remote {
+            Some(remote) => Arc::new(remote.clone()),
+            None => Arc::new(LocalDisk),
+        }
+    }
+
+    /// Where this entry is, as Goto and the clipboard take it: the path, or
+    /// for a remote entry an `sftp://` URL naming its host.
+    pub fn location(&self) -> String {
+        match &self.remote {
+            Some(remote) => remote.location(&self.path),
+            None => self.path.to_string_lossy().into_owned(),
+        }
+    }
+
+    fn authority(&self) -> Option<&str> {
+        self.remote.as_ref().map(Sftp::authority)
+    }
+
     /// An entry whose type and permission bits are set directly, for tests of
     /// code that dispatches on them. Some cannot be created on disk at all (a
     /// block device needs root, a door needs Solaris), and for the rest the
@@ -134,7 +328,12 @@ impl PathInfo {
     }
 
     pub fn breadcrumbs(&self) -> Vec<String> {
-        breadcrumbs(&self.path)
+        let mut breadcrumbs = breadcrumbs(&self.path);
+        // The root reads as `host:/`, as scp writes a remote path.
+        if let (Some(authority), Some(root)) = (self.authority(), breadcrumbs.first_mut()) {
+            *root = format!("{authority}:");
+        }
+        breadcrumbs
     }
 
     pub fn accessed(&self, relative_to: DateTime<Local>) -> Option<String> {
@@ -157,6 +356,17 @@ impl PathInfo {
         maybe_time_to_string(self.modified.as_ref(), relative_to)
     }
 
+    /// Which version of which entry this is: its device and inode, and when
+    /// it last changed. What something worked out from the entry's contents is
+    /// cached by.
+    pub fn version(&self) -> (u64, u64, Option<i64>) {
+        (
+            self.device,
+            self.inode,
+            self.modified.and_then(|dt| dt.timestamp_nanos_opt()),
+        )
+    }
+
     pub fn modified_comparator(&self) -> i64 {
         self.modified.map_or(0, |dt| dt.timestamp())
     }
@@ -174,6 +384,11 @@ impl PathInfo {
     }
 
     pub fn group(&self) -> Option<String> {
+        // Another host's ids name its own users, which this one's database
+        // does not know, or knows as someone else.
+        if self.remote.is_some() {
+            return Some(self.gid.to_string());
+        }
         Group::from_gid(Gid::from_raw(self.gid))
             .ok()
             .flatten()
@@ -181,6 +396,9 @@ impl PathInfo {
     }
 
     pub fn owner(&self) -> Option<String> {
+        if self.remote.is_some() {
+            return Some(self.uid.to_string());
+        }
         User::from_uid(Uid::from_raw(self.uid))
             .ok()
             .flatten()
@@ -188,9 +406,27 @@ impl PathInfo {
     }
 
     pub fn parent(&self) -> Option<PathInfo> {
-        self.path
-            .parent()
-            .and_then(|parent| PathInfo::try_from(parent).ok())
+        let parent = self.path.parent()?;
+        if let Some(remote) = &self.remote {
+            return PathInfo::read_remote(remote, parent).ok();
+        }
+        match &self.archive {
+            // Above an archive's top level is the directory holding it.
+            Some(archive) if self.path == *archive => PathInfo::try_from(parent).ok(),
+            Some(archive) if parent == archive => PathInfo::try_from(archive.as_path())
+                .ok()
+                .map(|archive| PathInfo::archive_root(&archive)),
+            Some(_) => {
+                let mut directory = self.clone();
+                directory.display_name = display_name(parent);
+                directory.path = parent.to_path_buf();
+                directory.mode = TYPE_DIRECTORY | DEFAULT_DIRECTORY_PERMISSIONS;
+                directory.modified = None;
+                directory.size = 0;
+                Some(directory)
+            }
+            None => PathInfo::try_from(parent).ok(),
+        }
     }
 
     pub fn size(&self) -> String {
@@ -245,6 +481,14 @@ impl PathInfo {
     }
 
     pub fn is_same_inode(&self, other: &Self) -> bool {
+        // Archive and remote entries have no inode, so the path (and the
+        // host) is all that names them.
+        if self.archive.is_some() || other.archive.is_some() {
+            return self.path == other.path;
+        }
+        if self.remote.is_some() || other.remote.is_some() {
+            return self == other;
+        }
         // Inode numbers are only unique within one filesystem; entries from
         // different mounts (e.g. two mount points in one listing) can share
         // an inode number, so the device must match too.
@@ -310,7 +554,7 @@ impl Display for PathInfo {
 
 impl PartialEq for PathInfo {
     fn eq(&self, other: &Self) -> bool {
-        self.path == other.path
+        self.path == other.path && self.authority() == other.authority()
     }
 }
 
@@ -324,27 +568,7 @@ impl TryFrom<&Path> for PathInfo {
     type Error = Error;
 
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        let metadata = path.symlink_metadata()?;
-        let mode = metadata.permissions().mode();
-
-        Ok(Self {
-            accessed: maybe_time(metadata.accessed()),
-            created: maybe_time(metadata.created()),
-            device: metadata.dev(),
-            display_name: display_name(path),
-            gid: metadata.gid(),
-            inode: metadata.ino(),
-            mode,
-            modified: maybe_time(metadata.modified()),
-            path: path.to_path_buf(),
-            size: metadata.len(),
-            // Only a symlink can be broken, so nothing else pays for the
-            // second look at the path. `try_exists` follows the link, so a
-            // permission error on the target (or on a parent component) is not
-            // misreported as broken; only a confirmed "does not exist" counts.
-            symlink_broken: unix_mode::is_symlink(mode) && matches!(path.try_exists(), Ok(false)),
-            uid: metadata.uid(),
-        })
+        Self::read(&LocalDisk, path)
     }
 }
 
@@ -374,6 +598,11 @@ impl TryFrom<String> for PathInfo {
     }
 }
 
+/// `bytes` in the Size column's units, for a total that is not one entry's.
+pub fn humanize_size(bytes: u64) -> String {
+    humanize_bytes(bytes, unit_index(bytes))
+}
+
 // Display-only scaling. f64 carries 53 bits of integer precision, so a size
 // would have to exceed 8 exabytes before the rendered figure moved, and the
 // unit index is bounded by UNITS.
@@ -398,7 +627,8 @@ fn humanize_bytes(bytes: u64, unit_index: usize) -> String {
     format!("{}{}", formatted_value, UNITS[unit_index])
 }
 
-fn unit_index(bytes: u64) -> usize {
+/// Which of the Size column's units `bytes` is shown in.
+pub fn unit_index(bytes: u64) -> usize {
     // Below one KiB there is no fractional rendering, so keep these values in
     // the byte unit; otherwise 1000..=1023 would be mislabelled as "1.0K".
     if bytes < FACTOR {
@@ -478,10 +708,6 @@ fn humanize_datetime(datetime: DateTime<Local>, relative_to: DateTime<Local>) ->
     datetime
 }
 
-fn maybe_time(result: io::Result<SystemTime>) -> Option<DateTime<Local>> {
-    result.ok().map(Into::into)
-}
-
 fn maybe_time_to_string(
     time: Option<&DateTime<Local>>,
     relative_to: DateTime<Local>,
@@ -661,4 +887,42 @@ mod tests {
         assert!(!info.is_symlink_broken());
         assert!(PathInfo::try_from(&link).unwrap().is_symlink_broken());
     }
+
+    #[test]
+    fn an_archive_entry_climbs_back_out_through_its_archive() {
+        use crate::test_support::TempDir;
+
+        let fx = TempDir::new("path_info_archive");
+        let archive = fx.join("a.zip");
+        std::fs::write(&archive, b"").unwrap();
+        let root = PathInfo::archive_root(&PathInfo::try_from(&archive).unwrap());
+        let child = |directory: &PathInfo, name: &str| {
+            PathInfo::in_archive(
+                directory,
+                &Child {
+                    name: name.into(),
+                    kind: EntryKind::Directory,
+                    size: 0,
+                    mode: None,
+                    modified: None,
+                },
+            )
+        };
+        let inner = child(&child(&root, "inner"), "dir");
+
+        assert!(inner.is_directory());
+        assert_eq!(Some(Path::new("inner/dir")), inner.path_in_archive());
+        assert_eq!(
+            ["a.zip", "inner", "dir"],
+            inner.breadcrumbs()[inner.breadcrumbs().len() - 3..]
+        );
+        let up = inner.parent().unwrap();
+        assert_eq!(Some(Path::new("inner")), up.path_in_archive());
+        let up = up.parent().unwrap();
+        assert_eq!(Some(Path::new("")), up.path_in_archive());
+        // Above the top level is the directory on disk holding the archive.
+        let up = up.parent().unwrap();
+        assert_eq!(None, up.archive());
+        assert_eq!(fx.path(), up.path.as_path());
+    }
 }
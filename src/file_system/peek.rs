@@ -0,0 +1,133 @@
+use std::{
+    sync::{Arc, mpsc::Sender},
+    thread,
+};
+
+use log::warn;
+
+use super::{
+    CURSOR_SETTLE_DELAY,
+    backend::Backend,
+    path_info::{PathInfo, compact},
+};
+use crate::command::{Command, progress::CancellationToken};
+
+/// The most entries a Miller column is filled with. A column is a glance at
+/// a directory, not a listing of it, and a huge one would otherwise be read
+/// in full on every move of the cursor onto it.
+const MAX_PEEK_ENTRIES: usize = 10_000;
+
+/// Which of the Miller columns beside the listing a peek fills.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PeekColumn {
+    /// The entries of the listed directory's parent, the directory among them.
+    Parent,
+    /// The entries of the selected directory.
+    Preview,
+}
+
+/// Lists, off the UI thread, the directory `column` shows for `path`, and
+/// sends it as `Command::Peeked`. It first waits for the cursor to settle,
+/// and lists nothing once `cancel` says the column has been asked for
+/// another. A listing nobody wants by the time it arrives is dropped by the
+/// view it was for.
+pub(super) fn run_peek(
+    tx: Sender<Command>,
+    backend: Arc<dyn Backend>,
+    path: PathInfo,
+    column: PeekColumn,
+    cancel: CancellationToken,
+) {
+    thread::spawn(move || {
+        thread::sleep(CURSOR_SETTLE_DELAY);
+        if cancel.is_cancelled() {
+            return;
+        }
+        let entries = match column {
+            PeekColumn::Parent => match path.parent() {
+                Some(parent) => peek(&*backend, &parent),
+                None => Ok(Vec::new()),
+            },
+            PeekColumn::Preview => peek(&*backend, &path),
+        };
+        let _ = tx.send(Command::Peeked {
+            path: path.path,
+            column,
+            entries,
+        });
+    });
+}
+
+fn peek(backend: &dyn Backend, directory: &PathInfo) -> Result<Vec<PathInfo>, String> {
+    // The archive is read front to back to list any directory in it, which
+    // is too slow to do for a glance.
+    if directory.archive().is_some() {
+        return Err("Not shown inside an archive".into());
+    }
+    let entries = backend.list(&directory.path).map_err(|error| {
+        format!(
+            "Failed to read directory {}: {error}",
+            compact(&directory.path)
+        )
+    })?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .take(MAX_PEEK_ENTRIES)
+        .filter_map(|path| {
+            let info = match directory.remote() {
+                Some(remote) => PathInfo::read_remote(remote, &path),
+                None => PathInfo::read(backend, &path),
+            };
+            info.map_err(|error| warn!("Failed to read metadata for {}: {error}", path.display()))
+                .ok()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::mpsc};
+
+    use super::*;
+    use crate::{file_system::backend::Local, test_support::TempDir};
+
+    fn peeked(path: &PathInfo, column: PeekColumn) -> Result<Vec<String>, String> {
+        let (tx, rx) = mpsc::channel();
+        run_peek(
+            tx,
+            Arc::new(Local),
+            path.clone(),
+            column,
+            CancellationToken::new(),
+        );
+        let Ok(Command::Peeked { entries, .. }) = rx.recv() else {
+            panic!("expected Peeked");
+        };
+        entries.map(|entries| {
+            let mut names: Vec<_> = entries
+                .into_iter()
+                .map(|entry| entry.display_name)
+                .collect();
+            names.sort();
+            names
+        })
+    }
+
+    #[test]
+    fn a_peek_lists_the_directory_or_its_parent() {
+        let fx = TempDir::new("peek");
+        fs::create_dir(fx.join("sub")).unwrap();
+        fs::write(fx.join("sub/a"), b"").unwrap();
+        fs::write(fx.join("b"), b"").unwrap();
+        let sub = PathInfo::try_from(fx.join("sub").as_path()).unwrap();
+
+        assert_eq!(Ok(vec!["a".into()]), peeked(&sub, PeekColumn::Preview));
+        assert_eq!(
+            Ok(vec!["b".into(), "sub".into()]),
+            peeked(&sub, PeekColumn::Parent)
+        );
+
+        fs::remove_dir_all(fx.join("sub")).unwrap();
+        assert!(peeked(&sub, PeekColumn::Preview).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+    sync::{Arc, mpsc::Sender},
+    thread,
+};
+
+use super::{
+    CURSOR_SETTLE_DELAY,
+    backend::Backend,
+    diff::is_binary,
+    highlight::{MAX_HIGHLIGHT_BYTES, Token, highlight},
+    path_info::{PathInfo, compact},
+};
+use crate::command::{Command, progress::CancellationToken};
+
+/// The most of a file read to preview it. A preview is the start of the file
+/// and is read again on every move of the cursor onto it, so a large file
+/// costs no more than a small one.
+const MAX_PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// The most lines a preview keeps, more than any terminal shows at once.
+const MAX_PREVIEW_LINES: usize = 500;
+
+/// The start of a file, as the preview shows it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FilePreview {
+    /// Its first lines, without their line endings. Tabs and control
+    /// characters are kept, for the view to show.
+    Text(Vec<String>),
+    /// Its first lines, split into tokens styled by their kind, when it is
+    /// in a language the highlighter knows.
+    Highlighted(Vec<Vec<Token>>),
+    /// A file that is not text, which is not shown.
+    Binary,
+}
+
+/// Reads, off the UI thread, the start of the file at `path`, and sends it
+/// as `Command::Previewed`. As with a peek, it first waits for the cursor to
+/// settle, and reads nothing once `cancel` says the cursor has moved on. One
+/// that has already been read is dropped by its view.
+pub(super) fn run_preview(
+    tx: Sender<Command>,
+    backend: Arc<dyn Backend>,
+    path: PathInfo,
+    cancel: CancellationToken,
+) {
+    thread::spawn(move || {
+        thread::sleep(CURSOR_SETTLE_DELAY);
+        if cancel.is_cancelled() {
+            return;
+        }
+        let preview = if path.archive().is_some() {
+            Err("Not shown inside an archive".into())
+        } else {
+            read_preview(&*backend, &path)
+                .map_err(|error| format!("Failed to read {}: {error}", compact(&path.path)))
+        };
+        let _ = tx.send(Command::Previewed {
+            path: path.path,
+            preview,
+        });
+    });
+}
+
+fn read_preview(backend: &dyn Backend, path: &PathInfo) -> io::Result<FilePreview> {
+    let mut bytes = Vec::new();
+    backend
+        .read(&path.path)?
+        .take(MAX_PREVIEW_BYTES)
+        .read_to_end(&mut bytes)?;
+    let is_cut_short = bytes.len() as u64 == MAX_PREVIEW_BYTES;
+    Ok(highlighted(
+        &path.path,
+        path.size,
+        preview_of(&bytes, is_cut_short),
+    ))
+}
+
+/// `preview` highlighted, when it is text in a known language and its file,
+/// of `size` bytes, is small enough to be worth it.
+fn highlighted(path: &Path, size: u64, preview: FilePreview) -> FilePreview {
+    match preview {
+        FilePreview::Text(lines) if size <= MAX_HIGHLIGHT_BYTES => {
+            highlight(path, &lines).map_or(FilePreview::Text(lines), FilePreview::Highlighted)
+        }
+        preview => preview,
+    }
+}
+
+/// What `bytes`, the start of a file, preview as. `is_cut_short` when there
+/// is more of the file after them.
+fn preview_of(bytes: &[u8], is_cut_short: bool) -> FilePreview {
+    if is_binary(bytes) {
+        return FilePreview::Binary;
+    }
+    let bytes = if is_cut_short {
+        without_partial_char(bytes)
+    } else {
+        bytes
+    };
+    // Not all text is UTF-8, and a stray byte should not hide the rest of
+    // the file, so it is shown as the replacement character.
+    let text = String::from_utf8_lossy(bytes);
+    FilePreview::Text(
+        text.lines()
+            .take(MAX_PREVIEW_LINES)
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// `bytes` without a character cut short at the end, as reading at most
+/// `MAX_PREVIEW_BYTES` can leave one, so that it is not shown as invalid.
+fn without_partial_char(bytes: &[u8]) -> &[u8] {
+    // A UTF-8 character is a lead byte and at most three continuation bytes.
+    for (back, &byte) in bytes.iter().rev().take(4).enumerate() {
+        let len = match byte {
+            0x00..=0x7F => 1,
+            0x80..=0xBF => continue,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _ => 4,
+        };
+        return if len > back + 1 {
+            &bytes[..bytes.len() - back - 1]
+        } else {
+            bytes
+        };
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn text(lines: &[&str]) -> FilePreview {
+        FilePreview::Text(lines.iter().map(|line| (*line).to_string()).collect())
+    }
+
+    #[test_case(b"a\r\nb\n", false, &text(&["a", "b"]) ; "line endings")]
+    #[test_case(b"\ta\x1bb", false, &text(&["\ta\x1bb"]) ; "tabs and control characters")]
+    #[test_case(b"caf\xe9", false, &text(&["caf\u{fffd}"]) ; "not UTF-8")]
+    #[test_case(b"caf\xc3", true, &text(&["caf"]) ; "a character cut short")]
+    #[test_case("café 日本".as_bytes(), true, &text(&["café 日本"]) ; "UTF-8")]
+    #[test_case(b"a\0b", false, &FilePreview::Binary ; "binary")]
+    fn a_preview_is_the_text_of_the_file(bytes: &[u8], is_cut_short: bool, expected: &FilePreview) {
+        assert_eq!(*expected, preview_of(bytes, is_cut_short));
+    }
+
+    #[test]
+    fn only_a_known_language_not_too_large_is_highlighted() {
+        let lines = || FilePreview::Text(vec!["fn main() {}".into()]);
+
+        assert!(matches!(
+            highlighted(Path::new("main.rs"), 12, lines()),
+            FilePreview::Highlighted(_)
+        ));
+        assert_eq!(lines(), highlighted(Path::new("main.txt"), 12, lines()));
+        assert_eq!(
+            lines(),
+            highlighted(Path::new("main.rs"), MAX_HIGHLIGHT_BYTES + 1, lines())
+        );
+    }
+
+    #[test]
+    fn only_the_start_of_a_file_is_previewed() {
+        let lines = "line\n".repeat(MAX_PREVIEW_LINES * 2);
+
+        let FilePreview::Text(preview) = preview_of(lines.as_bytes(), false) else {
+            panic!("expected text");
+        };
+        assert_eq!(MAX_PREVIEW_LINES, preview.len());
+    }
+}
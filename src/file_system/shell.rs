@@ -43,11 +43,25 @@ pub(crate) fn quote(value: &OsStr) -> OsString {
 /// Substitutes every `%s` in a shell template with `replacement`, which the
 /// caller has already quoted if it needs to be.
 pub(crate) fn template(template: &str, replacement: &OsStr) -> OsString {
-    let mut expanded = OsString::with_capacity(template.len() + replacement.len());
+    template_each(template, &[replacement])
+}
+
+/// Substitutes the `%s`s in a shell template with `replacements` in turn: the
+/// first with the first, and so on, and any past the last with the last.
+pub(crate) fn template_each(template: &str, replacements: &[&OsStr]) -> OsString {
+    let mut expanded = OsString::with_capacity(template.len());
     let mut rest = template;
+    let mut replacements = replacements.iter().peekable();
     while let Some(index) = rest.find("%s") {
         expanded.push(&rest[..index]);
-        expanded.push(replacement);
+        let replacement = if replacements.len() > 1 {
+            replacements.next()
+        } else {
+            replacements.peek().copied()
+        };
+        if let Some(replacement) = replacement {
+            expanded.push(replacement);
+        }
         rest = &rest[index + 2..];
     }
     expanded.push(rest);
@@ -113,6 +127,18 @@ mod tests {
             .into_owned()
     }
 
+    #[test_case("nvim -d %s %s", &["a", "b c"] => "nvim -d a 'b c'" ; "each placeholder takes the next")]
+    #[test_case("%s %s %s", &["a", "b"] => "a b b" ; "placeholders past the last repeat it")]
+    #[test_case("meld %s", &["a", "b"] => "meld a" ; "replacements past the last placeholder are dropped")]
+    fn template_each_produces(text: &str, replacements: &[&str]) -> String {
+        let quoted: Vec<_> = replacements
+            .iter()
+            .map(|replacement| quote(OsStr::new(replacement)))
+            .collect();
+        let quoted: Vec<_> = quoted.iter().map(OsString::as_os_str).collect();
+        template_each(text, &quoted).to_string_lossy().into_owned()
+    }
+
     #[test]
     fn template_preserves_bytes_that_are_not_utf8() {
         let expanded = template("cp %s /dest", &quote(invalid_utf8()));
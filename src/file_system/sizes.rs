@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc::Sender},
+    thread,
+    time::Instant,
+};
+
+use super::{TICK_INTERVAL, path_info::PathInfo, stream::BATCH_FLUSH_INTERVAL};
+use crate::command::{Command, progress::CancellationToken};
+
+/// What a directory's size is cached by: `PathInfo::version`. A change deep
+/// inside the directory leaves its own modification time alone, so the cache
+/// can answer for a tree that has since grown; turning the sizes off forgets
+/// them all.
+pub(super) type SizeCache = Arc<Mutex<HashMap<(u64, u64, Option<i64>), u64>>>;
+
+/// Answers what `cache` already knows about `directories` as one
+/// `Command::DirectorySizes`, then spawns a walk over the rest that streams
+/// theirs at most every `BATCH_FLUSH_INTERVAL`, each directory once it is
+/// counted. Nothing is sent once `cancel` is set, so a listing that has moved
+/// on sees no late sizes.
+pub(super) fn run_sizes(
+    tx: Sender<Command>,
+    cancel: CancellationToken,
+    cache: SizeCache,
+    directories: &[PathInfo],
+    generation: u64,
+) -> Option<Command> {
+    let mut known = Vec::new();
+    let mut unknown = Vec::new();
+    {
+        let cache = cache.lock().unwrap();
+        for directory in directories {
+            match cache.get(&directory.version()) {
+                Some(&bytes) => known.push((directory.path.clone(), bytes)),
+                None => unknown.push((directory.path.clone(), directory.version())),
+            }
+        }
+    }
+    if !unknown.is_empty() {
+        // Wakes the event loop so the spinners of the directories still being
+        // counted keep turning while nothing else arrives. Stops with the walk.
+        let done = CancellationToken::new();
+        let (tick_done, tick_cancel, tick_tx) = (done.clone(), cancel.clone(), tx.clone());
+        thread::spawn(move || {
+            loop {
+                thread::sleep(TICK_INTERVAL);
+                if tick_done.is_cancelled()
+                    || tick_cancel.is_cancelled()
+                    || tick_tx.send(Command::Tick).is_err()
+                {
+                    break;
+                }
+            }
+        });
+        thread::spawn(move || {
+            // Stops the ticker however the walk ends.
+            let _done = DoneOnDrop(done);
+            let mut sizes = Vec::new();
+            let mut last_report = Instant::now();
+            for (path, version) in unknown {
+                let Some(bytes) = tree_bytes(&path, &cancel) else {
+                    return;
+                };
+                cache.lock().unwrap().insert(version, bytes);
+                sizes.push((path, bytes));
+                if last_report.elapsed() >= BATCH_FLUSH_INTERVAL {
+                    send_unless_cancelled(&tx, &cancel, std::mem::take(&mut sizes), generation);
+                    last_report = Instant::now();
+                }
+            }
+            if !sizes.is_empty() {
+                send_unless_cancelled(&tx, &cancel, sizes, generation);
+            }
+        });
+    }
+    (!known.is_empty()).then_some(Command::DirectorySizes {
+        sizes: known,
+        generation,
+    })
+}
+
+struct DoneOnDrop(CancellationToken);
+
+impl Drop for DoneOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+fn send_unless_cancelled(
+    tx: &Sender<Command>,
+    cancel: &CancellationToken,
+    sizes: Vec<(PathBuf, u64)>,
+    generation: u64,
+) {
+    if !cancel.is_cancelled() {
+        let _ = tx.send(Command::DirectorySizes { sizes, generation });
+    }
+}
+
+/// The bytes of the regular files below `root`, as `du --apparent-size`
+/// counts them: symlinks are not followed, and a file with several names
+/// inside the tree counts once. A directory that cannot be listed adds
+/// nothing. Returns `None` when cancelled.
+fn tree_bytes(root: &Path, cancel: &CancellationToken) -> Option<u64> {
+    let mut bytes = 0;
+    let mut linked = HashSet::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(directory) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else if metadata.is_file()
+                && (metadata.nlink() == 1 || linked.insert((metadata.dev(), metadata.ino())))
+            {
+                bytes += metadata.len();
+            }
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn a_tree_counts_its_files_once_and_not_what_links_point_at() {
+        let fx = TempDir::new("sizes");
+        let root = fx.join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), b"abc").unwrap();
+        fs::write(root.join("sub/b.txt"), b"de").unwrap();
+        fs::hard_link(root.join("a.txt"), root.join("sub/a-again.txt")).unwrap();
+        fs::write(fx.join("outside.bin"), vec![0; 100]).unwrap();
+        std::os::unix::fs::symlink(fx.join("outside.bin"), root.join("link")).unwrap();
+
+        assert_eq!(Some(5), tree_bytes(&root, &CancellationToken::new()));
+    }
+
+    #[test]
+    fn a_cancelled_walk_counts_nothing() {
+        let fx = TempDir::new("sizes");
+        fs::create_dir_all(fx.join("tree")).unwrap();
+        fs::write(fx.join("tree/a.txt"), b"x").unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        assert_eq!(None, tree_bytes(&fx.join("tree"), &cancel));
+    }
+
+    #[test]
+    fn a_directory_counted_before_is_answered_from_the_cache() {
+        let fx = TempDir::new("sizes");
+        fs::create_dir_all(fx.join("tree")).unwrap();
+        fs::write(fx.join("tree/a.txt"), b"abc").unwrap();
+        let tree = PathInfo::try_from(fx.join("tree").as_path()).unwrap();
+        let cache = SizeCache::default();
+        let (tx, rx) = channel();
+
+        let first = run_sizes(
+            tx.clone(),
+            CancellationToken::new(),
+            Arc::clone(&cache),
+            std::slice::from_ref(&tree),
+            1,
+        );
+        assert_eq!(None, first);
+        // The walk's ticks may arrive first.
+        let streamed = rx
+            .iter()
+            .find(|command| matches!(command, Command::DirectorySizes { .. }))
+            .unwrap();
+
+        // Counted once, then known without another walk.
+        let again = run_sizes(tx, CancellationToken::new(), cache, &[tree], 2);
+        assert_eq!(
+            Some(Command::DirectorySizes {
+                sizes: vec![(fx.join("tree"), 3)],
+                generation: 2,
+            }),
+            again
+        );
+        assert_eq!(
+            Command::DirectorySizes {
+                sizes: vec![(fx.join("tree"), 3)],
+                generation: 1,
+            },
+            streamed
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+    time::Instant,
+};
+
+use nix::unistd::Uid;
+
+use super::{path_info::PathInfo, stream::BATCH_FLUSH_INTERVAL};
+use crate::command::{Command, progress::CancellationToken};
+
+/// What deleting a set of entries would remove, counted the way the delete
+/// walks them: a symlink is one entry however much it points at.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TreeSummary {
+    /// Everything that is not a directory: files, symlinks, special files.
+    pub files: u64,
+    pub directories: u64,
+    /// The bytes of the regular files among `files`.
+    pub bytes: u64,
+    /// Symlinks pointing at a directory. The delete only unlinks them, but a
+    /// user who sees a familiar tree's name among the entries may expect its
+    /// contents to be counted, so they are called out.
+    pub symlinked_directories: u64,
+    /// Entries owned by a user other than the one running FileCTRL, which in a
+    /// directory of one's own usually means something unexpected is there.
+    pub foreign_owned: u64,
+    /// Directories that could not be listed, so whatever they hold is missing
+    /// from the counts.
+    pub unreadable: u64,
+    /// False while the walk is still running: the counts so far are a lower
+    /// bound.
+    pub is_complete: bool,
+}
+
+impl TreeSummary {
+    fn add(&mut self, path: &Path, metadata: &fs::Metadata, euid: u32) {
+        if metadata.is_dir() {
+            self.directories += 1;
+        } else {
+            self.files += 1;
+            if metadata.is_file() {
+                self.bytes += metadata.len();
+            }
+        }
+        // `metadata` follows no link, so only a second look says what one
+        // points at.
+        if metadata.is_symlink() && fs::metadata(path).is_ok_and(|target| target.is_dir()) {
+            self.symlinked_directories += 1;
+        }
+        if metadata.uid() != euid {
+            self.foreign_owned += 1;
+        }
+    }
+}
+
+/// Spawns a background walk over `paths` that reports what deleting them would
+/// remove, as `Command::DeleteSummary`: partial counts at most every
+/// `BATCH_FLUSH_INTERVAL` while it runs, then the complete ones. Nothing is sent
+/// once `cancel` is set, so a prompt answered before the walk finishes sees no
+/// late summary.
+pub(super) fn run_summary(tx: Sender<Command>, cancel: CancellationToken, paths: Vec<PathInfo>) {
+    thread::spawn(move || {
+        let roots: Vec<PathBuf> = paths.into_iter().map(|path| path.path).collect();
+        if let Some(summary) = summarize(&roots, &cancel, |partial| {
+            send_unless_cancelled(&tx, &cancel, partial);
+        }) {
+            send_unless_cancelled(&tx, &cancel, summary);
+        }
+    });
+}
+
+fn send_unless_cancelled(tx: &Sender<Command>, cancel: &CancellationToken, summary: TreeSummary) {
+    if !cancel.is_cancelled() {
+        let _ = tx.send(Command::DeleteSummary(summary));
+    }
+}
+
+/// The walk itself, run on the caller's thread. `report` receives the partial
+/// counts as they accumulate. Returns `None` when cancelled.
+fn summarize(
+    roots: &[PathBuf],
+    cancel: &CancellationToken,
+    report: impl Fn(TreeSummary),
+) -> Option<TreeSummary> {
+    let euid = Uid::effective().as_raw();
+    let mut summary = TreeSummary::default();
+    let mut stack: Vec<PathBuf> = Vec::new();
+    let mut last_report = Instant::now();
+    for root in roots {
+        // A root that vanished is reported by the delete itself; counting it
+        // here would promise a removal that cannot happen.
+        let Ok(metadata) = root.symlink_metadata() else {
+            continue;
+        };
+        summary.add(root, &metadata, euid);
+        if metadata.is_dir() {
+            stack.push(root.clone());
+        }
+    }
+    while let Some(directory) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            summary.unreadable += 1;
+            continue;
+        };
+        for entry in entries.flatten() {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            // `DirEntry::metadata` does not follow symlinks, matching the
+            // delete walk: a link to a directory is unlinked, not descended.
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            summary.add(&path, &metadata, euid);
+            if metadata.is_dir() {
+                stack.push(path);
+            }
+            if last_report.elapsed() >= BATCH_FLUSH_INTERVAL {
+                report(summary);
+                last_report = Instant::now();
+            }
+        }
+    }
+    summary.is_complete = true;
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn summarize_all(roots: &[PathBuf]) -> TreeSummary {
+        summarize(roots, &CancellationToken::new(), |_| {}).unwrap()
+    }
+
+    #[test]
+    fn a_tree_is_counted_by_files_directories_and_bytes() {
+        let fx = TempDir::new("summary");
+        let root = fx.join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), b"abc").unwrap();
+        fs::write(root.join("sub/b.txt"), b"de").unwrap();
+
+        let summary = summarize_all(&[root]);
+
+        // `tree` and `sub` are both removed, so both count.
+        assert_eq!(2, summary.directories);
+        assert_eq!(2, summary.files);
+        assert_eq!(5, summary.bytes);
+        assert!(summary.is_complete);
+    }
+
+    #[test]
+    fn a_symlinked_directory_is_flagged_and_not_descended_into() {
+        let fx = TempDir::new("summary");
+        let target = fx.join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("big.bin"), vec![0; 100]).unwrap();
+        let root = fx.join("root");
+        fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(&target, root.join("link")).unwrap();
+
+        let summary = summarize_all(&[root]);
+
+        // The link is one entry and none of the target's bytes: the delete
+        // unlinks it and leaves `target` alone.
+        assert_eq!(1, summary.files);
+        assert_eq!(0, summary.bytes);
+        assert_eq!(1, summary.symlinked_directories);
+    }
+
+    #[test]
+    fn a_vanished_root_counts_for_nothing() {
+        let fx = TempDir::new("summary");
+
+        let summary = summarize_all(&[fx.join("gone")]);
+
+        assert_eq!(
+            TreeSummary {
+                is_complete: true,
+                ..TreeSummary::default()
+            },
+            summary
+        );
+    }
+
+    #[test]
+    fn own_entries_are_not_flagged_as_foreign() {
+        let fx = TempDir::new("summary");
+        fs::write(fx.join("mine.txt"), b"x").unwrap();
+
+        assert_eq!(0, summarize_all(&[fx.join("mine.txt")]).foreign_owned);
+    }
+
+    #[test]
+    fn a_cancelled_walk_reports_nothing() {
+        let fx = TempDir::new("summary");
+        fs::create_dir_all(fx.join("tree")).unwrap();
+        fs::write(fx.join("tree/a.txt"), b"x").unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        // A prompt already answered has no use for a late count.
+        assert_eq!(None, summarize(&[fx.join("tree")], &cancel, |_| {}));
+    }
+}
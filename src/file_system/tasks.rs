@@ -1,15 +1,18 @@
 use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashSet},
+    ffi::OsString,
     fs::{self, File},
-    io::{ErrorKind, Read, Write},
-    os::unix::fs::PermissionsExt,
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    rc::Rc,
     sync::{
         Arc, OnceLock,
         atomic::AtomicBool,
         mpsc::{self, Sender},
     },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{Result, anyhow};
@@ -17,11 +20,18 @@ use log::{error, info, warn};
 
 use super::{
     Occupant, PasteStep,
+    archive::{self, ArchiveEntry, ArchiveFormat, ArchiveWriter, EntryKind},
+    backend::{Backend, Local, Stat},
+    checksums::{self, ChecksumAlgorithm, ChecksumEntry, ChecksumReport},
     conflicts::Conflicts,
+    mode::TreeModes,
+    owner::Owner,
     path_info::{PathInfo, compact},
     step,
+    times::Stamp,
 };
 use crate::{
+    app::config::protected_paths::ProtectedPaths,
     command::{
         Command,
         progress::{ActiveTask, CancellationToken, Task, TaskKind, Transfer},
@@ -34,6 +44,8 @@ use crate::{
 /// entry of the tree, so that adding another does not lengthen every signature
 /// in between.
 struct CopyContext<'a> {
+    /// What the tree is read from and written to.
+    backend: &'a dyn Backend,
     /// One read buffer for the whole tree; see `copy_with_progress`.
     buffer: &'a mut [u8],
     /// The paste's standing `*All` answer, which settles a name already taken
@@ -68,6 +80,14 @@ const PROGRESS_MIN_INTERVAL: Duration = Duration::from_millis(100);
 /// buffer reads `Ok(0)` at once and writes an empty destination. 8 KiB matches
 /// std's default I/O buffer size.
 const MIN_COPY_BUFFER_BYTES: usize = 8 * 1024;
+/// The buffer an extract writes each file's contents through. Fixed rather
+/// than sized like a copy's: a file's size is only known once the archive
+/// reaches it, and a decompressor hands out far less than this at a time.
+const EXTRACT_BUFFER_BYTES: usize = 64 * 1024;
+/// The mode bits an extract restores, as `tar` restores them for anyone but
+/// root: no setuid, setgid or sticky bit from an archive that came from
+/// anywhere.
+const EXTRACTED_MODE_BITS: u32 = 0o777;
 
 type Job = Box<dyn FnOnce() + Send>;
 
@@ -149,52 +169,130 @@ impl TaskRunResult {
 /// the tree while the copy runs is settled by the paste's standing answer.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TaskCommand {
+    /// A recursive chmod of the entry and everything below it.
+    Chmod(PathInfo, TreeModes),
+    /// A recursive chown of the entry and everything below it.
+    Chown(PathInfo, Owner),
+    /// A recursive touch of the entry and everything below it.
+    Touch(PathInfo, Stamp),
+    /// Packs the entries, and everything below them, into a new archive at
+    /// the path, in the format.
+    Compress(Vec<PathInfo>, PathBuf, ArchiveFormat),
     Copy(PathInfo, PathInfo, bool),
+    /// A copy where the source or the directory is on another host, through
+    /// their backends rather than `std::fs`.
+    CopyAcross(PathInfo, PathInfo, bool),
     Delete(PathInfo),
+    /// Unpacks an archive into a directory.
+    Extract(Extraction),
     Move(PathInfo, PathInfo, bool),
+    /// Copies entries between two trees, each over what is at its
+    /// destination.
+    Sync(SyncPlan),
+    /// Makes the plan's right tree match its left: deletes its removals, then
+    /// copies each source over what is at its destination.
+    Mirror(SyncPlan),
+    /// Computes the checksums of files, or checks them against those a
+    /// checksum file lists.
+    Checksum(ChecksumJob),
 }
 
 impl TaskCommand {
     /// `conflicts` is the paste's standing `*All` answer, which a copy consults
     /// when it finds a name already taken inside the tree it is writing.
-    /// `None` for a delete, which never collides.
+    /// `None` for a delete, chmod, chown, touch, compress or extract, which
+    /// never collides: an extract's collisions are all answered before it
+    /// starts.
+    ///
+    /// `protected` is checked before anything is queued, so a refused entry
+    /// produces an alert and no progress notice.
     pub fn run(
         self,
         tx: Sender<Command>,
         conflicts: Option<&Conflicts>,
+        protected: &ProtectedPaths,
         buffer_min_bytes: u64,
         buffer_max_bytes: u64,
     ) -> TaskRunResult {
         match self {
+            TaskCommand::Chmod(path, modes) => run_chmod_task(tx, &path, modes, protected),
+            TaskCommand::Chown(path, owner) => run_chown_task(tx, &path, owner, protected),
+            TaskCommand::Touch(path, stamp) => run_touch_task(tx, &path, stamp, protected),
+            TaskCommand::Compress(paths, archive, format) => {
+                run_compress_task(tx, &paths, archive, format)
+            }
             TaskCommand::Copy(path, dir, overwrite) => run_copy_task(
                 tx,
                 &path,
                 &dir,
                 overwrite,
                 conflicts,
+                protected,
+                buffer_min_bytes,
+                buffer_max_bytes,
+            ),
+            TaskCommand::CopyAcross(path, dir, overwrite) => run_copy_across_task(
+                tx,
+                &path,
+                &dir,
+                overwrite,
+                protected,
                 buffer_min_bytes,
                 buffer_max_bytes,
             ),
-            TaskCommand::Delete(path) => run_delete_task(tx, &path),
+            TaskCommand::Delete(path) => run_delete_task(tx, &path, protected),
+            TaskCommand::Extract(extraction) => run_extract_task(tx, extraction, protected),
             TaskCommand::Move(path, dir, overwrite) => run_move_task(
                 tx,
                 &path,
                 &dir,
                 overwrite,
                 conflicts,
+                protected,
                 buffer_min_bytes,
                 buffer_max_bytes,
             ),
+            TaskCommand::Sync(plan) => {
+                let kind = TaskKind::Sync {
+                    path: TaskKind::sync_name(&plan.left, &plan.right),
+                };
+                run_sync_task(
+                    tx,
+                    plan,
+                    kind,
+                    protected,
+                    buffer_min_bytes,
+                    buffer_max_bytes,
+                )
+            }
+            TaskCommand::Mirror(plan) => {
+                let kind = TaskKind::Mirror {
+                    path: TaskKind::mirror_name(&plan.left, &plan.right),
+                };
+                run_sync_task(
+                    tx,
+                    plan,
+                    kind,
+                    protected,
+                    buffer_min_bytes,
+                    buffer_max_bytes,
+                )
+            }
+            TaskCommand::Checksum(job) => {
+                run_checksum_task(tx, job, buffer_min_bytes, buffer_max_bytes)
+            }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_copy_task(
     tx: Sender<Command>,
     path: &PathInfo,
     dir: &PathInfo,
     overwrite: bool,
     conflicts: Option<&Conflicts>,
+    protected: &ProtectedPaths,
     buffer_min_bytes: u64,
     buffer_max_bytes: u64,
 ) -> TaskRunResult {
@@ -205,7 +303,7 @@ fn run_copy_task(
         Ok(fresh) => fresh,
         Err(result) => return TaskRunResult::failed(result),
     };
-    let (old_path, new_path) = match validate_paths(&path, dir, "copy", overwrite) {
+    let (old_path, new_path) = match validate_paths(&path, dir, "copy", overwrite, protected) {
         Ok(paths) => paths,
         Err(result) => return TaskRunResult::failed(result),
     };
@@ -216,12 +314,13 @@ fn run_copy_task(
         destination: display_path(&new_path),
     });
 
+    let backend = path.backend();
     let is_directory = path.is_directory();
     // Fail before the task is registered, so an unreadable directory creates no
     // progress notice. The recursive size walk still runs off the UI thread.
     // A symlink has `is_directory == false` even when it points at a directory,
     // so it skips this and is recreated as a link by `copy_symlink`.
-    if is_directory && let Err(error) = fs::read_dir(&old_path) {
+    if is_directory && let Err(error) = backend.list(&old_path) {
         return TaskRunResult::failed(
             Command::AlertError(format!(
                 "Failed to read directory {}: {error}",
@@ -240,12 +339,13 @@ fn run_copy_task(
     let uncancellable = active.uncancellable_handle();
 
     queue_operation(move || {
-        let Some(active) = check_cancelled(active)
-            .and_then(|active| clear_destination(active, &old_path, &new_path, overwrite))
-        else {
+        let Some(active) = check_cancelled(active).and_then(|active| {
+            clear_destination(&*backend, active, &old_path, &new_path, overwrite)
+        }) else {
             return;
         };
         if let Some((active, outcome)) = copy_with_progress(
+            &*backend,
             &old_path,
             &new_path,
             active,
@@ -261,19 +361,108 @@ fn run_copy_task(
             // A skipped entry needs no mention here: nothing is left behind by
             // a copy that did not make it, and the standing "skip all" that
             // settled it was the user's own answer.
-            finalize_copy(active, outcome.errors);
+            finalize_with_errors(active, outcome.errors);
+        }
+    });
+
+    TaskRunResult::started(&initial, token, uncancellable)
+}
+
+/// Copies `path` into `dir` when one of them is on another host. Plainer than
+/// a local copy: only directories and files cross, with their permissions but
+/// not their times, and a name another process takes inside the tree while it
+/// runs is recorded rather than settled from the paste's answer, which would
+/// cost a round trip to the host for every entry.
+fn run_copy_across_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    dir: &PathInfo,
+    overwrite: bool,
+    protected: &ProtectedPaths,
+    buffer_min_bytes: u64,
+    buffer_max_bytes: u64,
+) -> TaskRunResult {
+    let from = path.backend();
+    let to = dir.backend();
+    let failed = |message: String| TaskRunResult::failed(Command::AlertError(message).into());
+    // Re-stat, as a local copy does: the clipboard's metadata may be stale.
+    let stat = match from.stat(&path.path) {
+        Ok(stat) => stat,
+        Err(error) => return failed(format!("Failed to copy {}: {error}", path.location())),
+    };
+    let Some(name) = path.path.file_name() else {
+        return failed(format!("Cannot copy {}: it has no name", path.location()));
+    };
+    let old_path = path.path.clone();
+    let new_path = dir.path.join(name);
+    if path.remote() == dir.remote() && new_path.starts_with(&old_path) {
+        return failed(format!("Cannot copy {} into itself", path.location()));
+    }
+    if overwrite
+        && dir.remote().is_none()
+        && let Err(result) = protected.check("overwrite", &new_path, false)
+    {
+        return TaskRunResult::failed(result.into());
+    }
+    let destination = match dir.remote() {
+        Some(remote) => remote.location(&new_path),
+        None => display_path(&new_path),
+    };
+
+    info!("Copying {} to {destination}", path.location());
+    let kind = TaskKind::Copy(Transfer {
+        source: path.location(),
+        destination,
+    });
+    let (active, initial, token) = ActiveTask::new(tx, kind, stat.size);
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
+
+    queue_operation(move || {
+        let Some(mut active) = check_cancelled(active) else {
+            return;
+        };
+        // Only a non-directory is ever offered for replacing.
+        if overwrite && let Err(error) = to.remove(&new_path) {
+            active.error(format!("Failed to replace {}: {error}", compact(&new_path)));
+            return;
+        }
+        let total = if unix_mode::is_dir(stat.mode) {
+            let Some(total) = dir_total_size(&*from, &active, &old_path) else {
+                active.cancelled();
+                return;
+            };
+            active.set_total(total);
+            total
+        } else {
+            stat.size
+        };
+        let mut buffer = vec![0; copy_buffer_bytes(total, buffer_min_bytes, buffer_max_bytes)];
+        let mut errors = Vec::new();
+        let mut across = Across {
+            from: &*from,
+            to: &*to,
+            buffer: &mut buffer,
+            errors: &mut errors,
+        };
+        if !across.copy(&old_path, &new_path, stat, &mut active) {
+            active.cancelled();
+            return;
         }
+        finalize_with_errors(active, errors);
     });
 
     TaskRunResult::started(&initial, token, uncancellable)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_move_task(
     tx: Sender<Command>,
     path: &PathInfo,
     dir: &PathInfo,
     overwrite: bool,
     conflicts: Option<&Conflicts>,
+    protected: &ProtectedPaths,
     buffer_min_bytes: u64,
     buffer_max_bytes: u64,
 ) -> TaskRunResult {
@@ -282,7 +471,7 @@ fn run_move_task(
         Ok(fresh) => fresh,
         Err(result) => return TaskRunResult::failed(result),
     };
-    let (old_path, new_path) = match validate_paths(&path, dir, "move", overwrite) {
+    let (old_path, new_path) = match validate_paths(&path, dir, "move", overwrite, protected) {
         Ok(paths) => paths,
         Err(result) => return TaskRunResult::failed(result),
     };
@@ -293,6 +482,7 @@ fn run_move_task(
         destination: display_path(&new_path),
     });
     let (active, initial, token) = ActiveTask::new(tx, kind, path.size);
+    let backend = path.backend();
     let size = path.size;
     let source_mode = path.mode();
     let is_directory = path.is_directory();
@@ -303,7 +493,7 @@ fn run_move_task(
         let Some(mut active) = check_cancelled(active) else {
             return;
         };
-        match rename_for_move(&old_path, &new_path, overwrite) {
+        match rename_for_move(&*backend, &old_path, &new_path, overwrite) {
             Ok(()) => {
                 active.increment(size);
                 active.done();
@@ -314,11 +504,13 @@ fn run_move_task(
                     // There is no rename to replace the destination here, and
                     // the copy below opens it with `create_new`, so a granted
                     // overwrite has to clear it first.
-                    let Some(active) = clear_destination(active, &old_path, &new_path, overwrite)
+                    let Some(active) =
+                        clear_destination(&*backend, active, &old_path, &new_path, overwrite)
                     else {
                         return;
                     };
                     let Some((active, outcome)) = copy_with_progress(
+                        &*backend,
                         &old_path,
                         &new_path,
                         active,
@@ -336,7 +528,7 @@ fn run_move_task(
                     ) else {
                         return;
                     };
-                    finish_cross_device_move(active, outcome, &old_path, is_directory);
+                    finish_cross_device_move(&*backend, active, outcome, &old_path, is_directory);
                 }
                 _ => active.error(format!(
                     "Failed to move {} to {}: {error}",
@@ -350,7 +542,11 @@ fn run_move_task(
     TaskRunResult::started(&initial, token, uncancellable)
 }
 
-fn run_delete_task(tx: Sender<Command>, path: &PathInfo) -> TaskRunResult {
+fn run_delete_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    protected: &ProtectedPaths,
+) -> TaskRunResult {
     // Same staleness class as copy/move: the selection-time metadata may be
     // outdated. A directory replaced by a symlink must be unlinked as a
     // link, not followed into its target.
@@ -358,6 +554,11 @@ fn run_delete_task(tx: Sender<Command>, path: &PathInfo) -> TaskRunResult {
         Ok(fresh) => fresh,
         Err(result) => return TaskRunResult::failed(result),
     };
+    // After the re-stat, which decides whether the delete reaches below the
+    // entry: a symlink is only unlinked, whatever it points at.
+    if let Err(error) = protected.check("delete", &path.path, path.is_directory()) {
+        return TaskRunResult::failed(error.into());
+    }
     let kind = TaskKind::Delete {
         path: display_path(&path.path),
     };
@@ -366,6 +567,7 @@ fn run_delete_task(tx: Sender<Command>, path: &PathInfo) -> TaskRunResult {
     // non-directory delete removes; a directory's real total is scanned in the
     // worker, off the UI thread, and applied via `active.set_total`.
     let (mut active, initial, token) = ActiveTask::new(tx, kind, 1);
+    let backend = path.backend();
     let is_directory = path.is_directory();
     let path = path.path.clone();
     info!("Deleting {}", path.display());
@@ -374,13 +576,13 @@ fn run_delete_task(tx: Sender<Command>, path: &PathInfo) -> TaskRunResult {
 
     queue_operation(move || {
         if is_directory {
-            let Some(total) = dir_total_entries(&active, &path) else {
+            let Some(total) = dir_total_entries(&*backend, &active, &path) else {
                 active.cancelled();
                 return;
             };
             active.set_total(total);
         }
-        if let Some(active) = remove_path(&path, is_directory, active) {
+        if let Some(active) = remove_path(&*backend, &path, is_directory, active) {
             active.done();
         }
     });
@@ -388,1522 +590,3516 @@ fn run_delete_task(tx: Sender<Command>, path: &PathInfo) -> TaskRunResult {
     TaskRunResult::started(&initial, token, uncancellable)
 }
 
-fn buffer_bytes(len: u64, buffer_min_bytes: u64, buffer_max_bytes: u64) -> usize {
-    let bytes = if len <= buffer_min_bytes {
-        len
-    } else if len >= (buffer_max_bytes * BUFFER_SIZE_DIVISOR) {
-        buffer_max_bytes
-    } else {
-        std::cmp::max(buffer_min_bytes, len / BUFFER_SIZE_DIVISOR)
-    };
-    // The sizes are u64 from the config and from a scanned total, so on a
-    // 32-bit target one can exceed what a buffer could be allocated at.
-    // Saturating asks for the largest buffer the address space can express.
-    usize::try_from(bytes).unwrap_or(usize::MAX)
+fn run_chmod_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    modes: TreeModes,
+    protected: &ProtectedPaths,
+) -> TaskRunResult {
+    run_tree_task(
+        tx,
+        path,
+        protected,
+        "chmod",
+        |path| TaskKind::Chmod { path },
+        move |backend, root, protected, active| {
+            chmod_tree(backend, root, &modes, protected, active)
+        },
+    )
 }
 
-/// The read-buffer size for a copy: `buffer_bytes` floored at
-/// `MIN_COPY_BUFFER_BYTES` so a tiny or zero scanned total never yields a
-/// buffer too small to make progress. The floor itself is capped at
-/// `buffer_max_bytes`, which a user may configure below the floor.
-fn copy_buffer_bytes(len: u64, buffer_min_bytes: u64, buffer_max_bytes: u64) -> usize {
-    buffer_bytes(len, buffer_min_bytes, buffer_max_bytes)
-        .max(MIN_COPY_BUFFER_BYTES.min(usize::try_from(buffer_max_bytes).unwrap_or(usize::MAX)))
+fn run_chown_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    owner: Owner,
+    protected: &ProtectedPaths,
+) -> TaskRunResult {
+    run_tree_task(
+        tx,
+        path,
+        protected,
+        "chown",
+        |path| TaskKind::Chown { path },
+        move |backend, root, protected, active| chown_tree(backend, root, owner, protected, active),
+    )
 }
 
-/// The byte-copy stage shared by copy and cross-device move: for a directory
-/// source, scans the real transfer total (a directory entry's own size is not
-/// the transfer size) and applies it via `set_total`, sizes the read buffer,
-/// and copies the tree.
-///
-/// Returns `None` when the task was cancelled, in which case it has already
-/// been finalized via `active.cancelled()`. Otherwise returns the task and
-/// what the walk left behind, for the caller to finalize.
-#[allow(clippy::too_many_arguments)]
-fn copy_with_progress(
-    old_path: &Path,
-    new_path: &Path,
-    mut active: ActiveTask,
-    entry_size: u64,
-    is_directory: bool,
-    source_mode: u32,
-    preserve_times: bool,
-    conflicts: Option<&Conflicts>,
-    buffer_min_bytes: u64,
-    buffer_max_bytes: u64,
-) -> Option<(ActiveTask, CopyOutcome)> {
-    let total_size = if is_directory {
-        let Some(size) = dir_total_size(&active, old_path) else {
-            active.cancelled();
-            return None;
-        };
-        active.set_total(size);
-        size
-    } else {
-        entry_size
-    };
-    // One buffer for the whole tree. It is sized from the tree's total, so
-    // allocating it per file would hand every small file in a large directory
-    // its own multi-megabyte allocation.
-    let mut buffer = vec![0; copy_buffer_bytes(total_size, buffer_min_bytes, buffer_max_bytes)];
-    let mut context = CopyContext {
-        buffer: &mut buffer,
-        conflicts,
-        preserve_times,
-        skipped: 0,
+fn run_touch_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    stamp: Stamp,
+    protected: &ProtectedPaths,
+) -> TaskRunResult {
+    run_tree_task(
+        tx,
+        path,
+        protected,
+        "touch",
+        |path| TaskKind::Touch { path },
+        move |backend, root, protected, active| touch_tree(backend, root, stamp, protected, active),
+    )
+}
+
+/// Queues `walk` over the tree at `path`, for a recursive chmod, chown or
+/// touch.
+fn run_tree_task(
+    tx: Sender<Command>,
+    path: &PathInfo,
+    protected: &ProtectedPaths,
+    operation: &'static str,
+    kind: fn(String) -> TaskKind,
+    walk: impl FnOnce(
+        &dyn Backend,
+        &Path,
+        &ProtectedPaths,
+        ActiveTask,
+    ) -> Option<(ActiveTask, Vec<String>)>
+    + Send
+    + 'static,
+) -> TaskRunResult {
+    let path = match restat_source(operation, &path.path) {
+        Ok(fresh) => fresh,
+        Err(result) => return TaskRunResult::failed(result),
     };
-    let mut errors = Vec::new();
-    if !copy_path(
-        old_path,
-        new_path,
-        &mut active,
-        &mut errors,
-        &mut context,
-        is_directory,
-        source_mode,
-    ) {
-        active.cancelled();
-        return None;
+    // Like `chmod -R` and `chown -R`, the walk never follows a symlink, and a
+    // root that is one would leave nothing to walk: only its target would
+    // change, which the non-recursive operation already does.
+    if path.is_symlink() {
+        return TaskRunResult::failed(
+            anyhow!(
+                "Cannot {operation} {} recursively: it is a symbolic link",
+                compact(&path.path)
+            )
+            .into(),
+        );
     }
-    Some((
+    if let Err(error) = protected.check(operation, &path.path, path.is_directory()) {
+        return TaskRunResult::failed(error.into());
+    }
+    let kind = kind(display_path(&path.path));
+    info!("{} recursively", kind.message());
+    // Progress counts entries, as a delete's does. The total is scanned in the
+    // worker, off the UI thread.
+    let (mut active, initial, token) = ActiveTask::new(tx, kind, 1);
+    let backend = path.backend();
+    let is_directory = path.is_directory();
+    let path = path.path.clone();
+    let protected = protected.clone();
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
+
+    queue_operation(move || {
+        if is_directory {
+            let Some(total) = dir_total_entries(&*backend, &active, &path) else {
+                active.cancelled();
+                return;
+            };
+            active.set_total(total);
+        }
+        if let Some((active, errors)) = walk(&*backend, &path, &protected, active) {
+            finalize_with_errors(active, errors);
+        }
+    });
+
+    TaskRunResult::started(&initial, token, uncancellable)
+}
+
+/// Applies `modes` to `root` and everything below it. Symlinks are left alone:
+/// a link's own mode means nothing, and changing its target's would reach
+/// outside the tree.
+fn chmod_tree(
+    backend: &dyn Backend,
+    root: &Path,
+    modes: &TreeModes,
+    protected: &ProtectedPaths,
+    active: ActiveTask,
+) -> Option<(ActiveTask, Vec<String>)> {
+    change_tree(
+        backend,
+        root,
+        "chmod",
+        protected,
+        false,
         active,
-        CopyOutcome {
-            errors,
-            skipped: context.skipped,
+        |path, stat| {
+            let is_directory = unix_mode::is_dir(stat.mode);
+            let current = stat.mode & 0o7777;
+            let mode = modes.for_entry(is_directory).apply(current, is_directory);
+            if mode == current {
+                return None;
+            }
+            backend
+                .set_mode(path, mode)
+                .err()
+                .map(|error| format!("Failed to chmod {} to {mode:o}: {error}", compact(path)))
         },
-    ))
+    )
 }
 
-/// Best-effort recursive size for the progress total. Entries that cannot be
-/// read are skipped here; the copy itself reports them as errors.
-///
-/// Returns `None` when the task was cancelled. The walk runs before any bytes
-/// are copied and takes as long as the tree is large, so it observes the token
-/// itself rather than leaving a cancel acknowledged but still running.
-fn dir_total_size(active: &ActiveTask, root: &Path) -> Option<u64> {
-    let mut total = 0;
-    let mut stack = vec![root.to_path_buf()];
-    while let Some(path) = stack.pop() {
-        if active.is_cancelled() {
-            return None;
-        }
-        let Ok(entries) = fs::read_dir(&path) else {
-            continue;
-        };
-        for entry in entries.flatten() {
-            if active.is_cancelled() {
+/// Gives `root` and everything below it to `owner`. A symlink's own owner is
+/// changed rather than its target's, as `chown -R` does, so the walk cannot
+/// reach outside the tree.
+fn chown_tree(
+    backend: &dyn Backend,
+    root: &Path,
+    owner: Owner,
+    protected: &ProtectedPaths,
+    active: ActiveTask,
+) -> Option<(ActiveTask, Vec<String>)> {
+    change_tree(
+        backend,
+        root,
+        "chown",
+        protected,
+        true,
+        active,
+        |path, stat| {
+            if owner.is_satisfied_by(stat.uid, stat.gid) {
                 return None;
             }
-            // `DirEntry::metadata` does not follow symlinks and avoids a
-            // second path lookup per entry.
-            let Ok(metadata) = entry.metadata() else {
-                continue;
-            };
-            if metadata.is_dir() {
-                stack.push(entry.path());
-            } else if !metadata.is_symlink() {
-                total += metadata.len();
+            backend
+                .set_owner(path, owner.uid, owner.gid)
+                .err()
+                .map(|error| format!("Failed to chown {}: {error}", compact(path)))
+        },
+    )
+}
+
+/// Sets the times of `root` and everything below it to `stamp`. A symlink's
+/// own times are set rather than its target's, as with chown, so the walk
+/// cannot reach outside the tree.
+fn touch_tree(
+    backend: &dyn Backend,
+    root: &Path,
+    stamp: Stamp,
+    protected: &ProtectedPaths,
+    active: ActiveTask,
+) -> Option<(ActiveTask, Vec<String>)> {
+    let mut directories = Vec::new();
+    let (active, mut errors) = change_tree(
+        backend,
+        root,
+        "touch",
+        protected,
+        true,
+        active,
+        |path, stat| match stamp.apply_through(backend, path) {
+            Ok(()) if unix_mode::is_dir(stat.mode) => {
+                directories.push(path.to_path_buf());
+                None
             }
+            Ok(()) => None,
+            Err(error) => Some(format!("Failed to touch {}: {error}", compact(path))),
+        },
+    )?;
+    // The walk lists each directory after touching it, which moves its access
+    // time again. Touching the directories once more, now that nothing will
+    // read them, leaves them with the times asked for. Only those set the
+    // first time: a failure is already recorded.
+    for directory in directories {
+        if let Err(error) = stamp.apply_through(backend, &directory) {
+            errors.push(format!("Failed to touch {}: {error}", compact(&directory)));
         }
     }
-    Some(total)
+    Some((active, errors))
 }
 
-/// Best-effort recursive entry count for the delete progress total, including
-/// `root` itself. Reads directories only, with no per-entry stat, so it is
-/// cheaper than `dir_total_size`; a directory that cannot be listed counts as
-/// the single entry it is. Returns `None` when the task was cancelled.
-fn dir_total_entries(active: &ActiveTask, root: &Path) -> Option<u64> {
-    let mut total = 1; // `root` itself, which appears in no listing.
+/// Calls `change` on `root` and everything below it, parents before their
+/// entries as `chmod -R` does, so a change that makes a directory readable
+/// takes effect before it is listed. An entry that cannot be changed or listed
+/// is recorded and the walk goes on. Symlinks are never followed, and are
+/// passed to `change` only with `include_symlinks`. A protected entry below
+/// `root` is left alone, with everything under it.
+///
+/// Returns `None` when cancelled, having finalized the task; otherwise the
+/// task and the errors, for the caller to finalize.
+fn change_tree(
+    backend: &dyn Backend,
+    root: &Path,
+    operation: &str,
+    protected: &ProtectedPaths,
+    include_symlinks: bool,
+    mut active: ActiveTask,
+    mut change: impl FnMut(&Path, &Stat) -> Option<String>,
+) -> Option<(ActiveTask, Vec<String>)> {
+    let mut errors = Vec::new();
     let mut stack = vec![root.to_path_buf()];
+    let mut debouncer = debounce::ProgressDebouncer::new(
+        PROGRESS_DEBOUNCE_PERCENTAGE,
+        PROGRESS_MIN_INTERVAL,
+        active.total_size(),
+    );
     while let Some(path) = stack.pop() {
         if active.is_cancelled() {
+            active.cancelled();
             return None;
         }
-        let Ok(entries) = fs::read_dir(&path) else {
-            continue;
-        };
-        for entry in entries.flatten() {
-            if active.is_cancelled() {
-                return None;
+        active.increment(1);
+        if debouncer.should_trigger(Instant::now(), 1) {
+            active.send_progress();
+        }
+        let stat = match backend.stat(&path) {
+            Ok(stat) => stat,
+            Err(error) => {
+                errors.push(format!("Failed to {operation} {}: {error}", compact(&path)));
+                continue;
             }
-            total += 1;
-            // `file_type` does not follow symlinks, matching the delete walk:
-            // a link to a directory is unlinked rather than descended into.
-            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
-                stack.push(entry.path());
+        };
+        if unix_mode::is_symlink(stat.mode) && !include_symlinks {
+            continue;
+        }
+        // The root was checked before the task was queued. Below it, only a
+        // wildcard pattern can match, which the up-front check cannot see.
+        if path != root
+            && let Err(error) = protected.check(operation, &path, false)
+        {
+            errors.push(error.to_string());
+            continue;
+        }
+        errors.extend(change(&path, &stat));
+        if unix_mode::is_dir(stat.mode) {
+            match backend.list(&path) {
+                Ok(entries) => stack.extend(entries.flatten()),
+                Err(error) => errors.push(format!("Failed to list {}: {error}", compact(&path))),
             }
         }
     }
-    Some(total)
+    Some((active, errors))
 }
 
-/// Unwraps a `Result`, or finalizes `$active` with `"{$ctx}: {error}"` and
-/// returns `None` from the enclosing function. `$ctx` must not reference an
-/// `error` binding of its own (macro hygiene binds the error here).
-macro_rules! try_or_abort {
-    ($active:expr, $result:expr, $ctx:expr) => {
-        match $result {
-            Ok(value) => value,
-            Err(error) => {
-                $active.error(format!("{}: {error}", $ctx));
-                return None;
-            }
-        }
+/// Packs `paths` into a new archive at `archive`. Progress counts the bytes of
+/// the files read, as a copy's counts those written.
+fn run_compress_task(
+    tx: Sender<Command>,
+    paths: &[PathInfo],
+    archive: PathBuf,
+    format: ArchiveFormat,
+) -> TaskRunResult {
+    // The worker creates the archive with `create_new`, which would refuse a
+    // taken name too, but only after a progress notice had been shown for it.
+    if fs::symlink_metadata(&archive).is_ok() {
+        return TaskRunResult::failed(anyhow!("{} already exists", compact(&archive)).into());
+    }
+    let kind = TaskKind::Compress {
+        path: display_path(&archive),
     };
-}
+    info!("{}", kind.message());
+    // Seeded with a single unit, as a delete is; the real total is scanned in
+    // the worker, off the UI thread, and applied via `active.set_total`.
+    let (mut active, initial, token) = ActiveTask::new(tx, kind, 1);
+    let sources: Vec<PathBuf> = paths.iter().map(|path| path.path.clone()).collect();
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
 
-/// Lists `$dir` for `remove_path`: unwraps the collected entries, finalizes
-/// `$active` as cancelled and returns `None` if the drain was cancelled, or
-/// as an error and returns `None` if the read failed.
-macro_rules! list_or_abort {
-    ($active:expr, $dir:expr) => {{
-        let dir = $dir;
-        match list_entries(&$active, dir) {
-            Ok(Some(entries)) => entries,
-            Ok(None) => {
-                $active.cancelled();
-                return None;
-            }
-            Err(error) => {
-                $active.error(format!(
-                    "Failed to read directory {}: {error}",
-                    compact(dir)
-                ));
-                return None;
+    queue_operation(move || {
+        // The sources are read where the archive is written: on this disk, in
+        // or below the current directory.
+        let Some(total) = sources_total_size(&Local, &active, &sources) else {
+            active.cancelled();
+            return;
+        };
+        active.set_total(total);
+        if let Some((active, errors)) = pack(&Local, &archive, format, &sources, active) {
+            finalize_with_errors(active, errors);
+        }
+    });
+
+    TaskRunResult::started(&initial, token, uncancellable)
+}
+
+/// The bytes a compress reads: those of the files among `sources` and below
+/// them. Returns `None` when the task was cancelled.
+fn sources_total_size(
+    backend: &dyn Backend,
+    active: &ActiveTask,
+    sources: &[PathBuf],
+) -> Option<u64> {
+    let mut total = 0;
+    for source in sources {
+        match backend.stat(source) {
+            Ok(stat) if unix_mode::is_dir(stat.mode) => {
+                total += dir_total_size(backend, active, source)?;
             }
+            Ok(stat) if unix_mode::is_file(stat.mode) => total += stat.size,
+            _ => {}
         }
-    }};
+    }
+    Some(total)
 }
 
-/// Re-stats the source at task start: selection- and yank-time metadata may
-/// be stale, and the path may have changed since (e.g. replaced by a FIFO,
-/// which a byte-wise copy would block on forever, or by a symlink, which a
-/// delete must unlink rather than follow).
-fn restat_source(operation: &str, old_path: &Path) -> Result<PathInfo, CommandResult> {
-    PathInfo::try_from(old_path)
-        .map_err(|error| anyhow!("Failed to {operation} {}: {error}", compact(old_path)).into())
+/// Why a compress stopped before its archive was complete.
+enum PackAbort {
+    Cancelled,
+    Failed(String),
 }
 
-/// Finishes a cross-device move once the copy stage is done, removing the
-/// source only when the destination holds every entry of it, like `mv`.
+/// Writes `sources`, read through `backend`, and everything below them, into
+/// a new archive at `archive`.
 ///
-/// A failed entry keeps the whole source, including the entries that copied,
-/// and the partial destination. A skipped one does the same: it is no more at
-/// the destination than a failed one, so removing the source would delete what
-/// nothing else holds.
-fn finish_cross_device_move(
-    active: ActiveTask,
-    outcome: CopyOutcome,
-    old_path: &Path,
-    is_directory: bool,
-) {
-    if !outcome.errors.is_empty() {
-        finalize_copy(active, outcome.errors);
-        return;
-    }
-    if outcome.skipped > 0 {
-        let skipped = outcome.skipped;
-        let entries = if skipped == 1 { "entry" } else { "entries" };
-        active.error(format!(
-            "Skipped {skipped} {entries}, so the original {} was kept",
-            compact(old_path)
-        ));
-        return;
-    }
-    // Not cancellable: the copy is complete, so removing the source outright is
-    // the only way to finish the move. Mark it so a cancel keypress during this
-    // stage does not claim to have cancelled anything.
-    active.set_uncancellable();
-    let removed = if is_directory {
-        fs::remove_dir_all(old_path)
-    } else {
-        fs::remove_file(old_path)
+/// An entry that cannot be read, or that no archive format here can hold (a
+/// FIFO, socket or device), is recorded and left out, as `tar` leaves it out,
+/// and the archive is kept. A failure writing the archive, or reading a file
+/// part way through, stops the task, as does a cancel; either way the partial
+/// archive is removed, since unlike a partial copy it holds nothing usable.
+/// Returns `None` then, having finalized the task; otherwise the task and the
+/// errors, for the caller to finalize.
+fn pack(
+    backend: &dyn Backend,
+    archive: &Path,
+    format: ArchiveFormat,
+    sources: &[PathBuf],
+    mut active: ActiveTask,
+) -> Option<(ActiveTask, Vec<String>)> {
+    let mut writer = match ArchiveWriter::create(archive, format) {
+        Ok(writer) => writer,
+        Err(error) => {
+            active.error(format!("Failed to create {}: {error}", compact(archive)));
+            return None;
+        }
     };
-    match removed {
-        Ok(()) => active.done(),
-        Err(error) => active.error(format!(
-            "Copy succeeded, but failed to delete original {}: {error}",
-            compact(old_path)
-        )),
-    }
-}
-
-/// Finalizes a copy/move task the way coreutils does: success when no per-entry
-/// error was recorded, otherwise one alert summarizing them. Skipped entries are
-/// not failures and do not appear. Every error is also logged.
-fn finalize_copy(active: ActiveTask, errors: Vec<String>) {
-    if errors.is_empty() {
-        active.done();
-        return;
+    let mut errors = Vec::new();
+    let written = pack_entries(
+        backend,
+        &mut writer,
+        archive,
+        sources,
+        &mut active,
+        &mut errors,
+    )
+    .and_then(|()| {
+        writer.finish().map_err(|error| {
+            PackAbort::Failed(format!("Failed to write {}: {error}", compact(archive)))
+        })
+    });
+    let Err(abort) = written else {
+        return Some((active, errors));
+    };
+    // The archive is a local file whatever the sources are: see
+    // `ArchiveWriter::create`.
+    if let Err(error) = fs::remove_file(archive) {
+        warn!(
+            "Failed to remove the partial archive {}: {error}",
+            compact(archive)
+        );
     }
-    for error in &errors {
-        warn!("{error}");
+    match abort {
+        PackAbort::Cancelled => active.cancelled(),
+        PackAbort::Failed(message) => active.error(message),
     }
-    let summary = if errors.len() == 1 {
-        errors.into_iter().next().expect("errors is non-empty")
-    } else {
-        format!("{} (and {} more)", errors[0], errors.len() - 1)
-    };
-    active.error(summary);
+    None
 }
 
-/// The copy functions below follow coreutils `cp -R`/`mv` semantics: an entry
-/// that cannot be copied is recorded in `errors` and the copy continues with
-/// the remaining entries. Each returns `false` only when the task was
-/// cancelled, in which case the caller must finalize with
-/// `active.cancelled()`; otherwise the caller finalizes via `finalize_copy`.
-/// A cancelled copy leaves the partially copied destination in place, like an
-/// interrupted `cp`; the destination is not removed.
-fn copy_directory(
-    old_path: &Path,
-    new_path: &Path,
+/// Adds `sources` and everything below them to `writer`, parents before their
+/// entries and each directory's entries in name order, so the same tree always
+/// packs the same way. Entries are named by their path relative to the
+/// archive's directory, which a search result below it keeps. Symlinks are
+/// stored as links and never followed.
+fn pack_entries(
+    backend: &dyn Backend,
+    writer: &mut ArchiveWriter,
+    archive: &Path,
+    sources: &[PathBuf],
     active: &mut ActiveTask,
     errors: &mut Vec<String>,
-    context: &mut CopyContext<'_>,
-) -> bool {
-    match fs::create_dir(new_path) {
-        Ok(()) => {}
-        // Something took this name while the copy was running: the destination
-        // was free when the task started, so this is another process writing
-        // into the tree. A directory is never replaced, so only a standing
-        // "skip all" settles this one, and skipping drops the subtree.
-        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
-            if !resolve_nested(context, errors, new_path) {
-                return true;
+) -> Result<(), PackAbort> {
+    let base = archive.parent().unwrap_or(Path::new(""));
+    let mut debouncer = debounce::ProgressDebouncer::new(
+        PROGRESS_DEBOUNCE_PERCENTAGE,
+        PROGRESS_MIN_INTERVAL,
+        active.total_size(),
+    );
+    let mut stack: Vec<(PathBuf, PathBuf)> = sources
+        .iter()
+        .rev()
+        .map(|source| {
+            // Every source is in or below the current directory, where the
+            // archive goes; the fallback is for one that no longer is.
+            let name = match source.strip_prefix(base) {
+                Ok(name) => name.to_path_buf(),
+                Err(_) => PathBuf::from(source.file_name().unwrap_or_default()),
+            };
+            (source.clone(), name)
+        })
+        .collect();
+    while let Some((path, name)) = stack.pop() {
+        if active.is_cancelled() {
+            return Err(PackAbort::Cancelled);
+        }
+        let stat = match backend.stat(&path) {
+            Ok(stat) => stat,
+            Err(error) => {
+                errors.push(format!("Failed to read {}: {error}", compact(&path)));
+                continue;
             }
-            if let Err(error) = remove_existing(new_path).and_then(|()| fs::create_dir(new_path)) {
-                errors.push(format!(
-                    "Failed to create directory {}: {error}",
-                    compact(new_path)
-                ));
-                return true;
+        };
+        let written = if unix_mode::is_dir(stat.mode) {
+            writer.add_directory(&name, &stat)
+        } else if unix_mode::is_symlink(stat.mode) {
+            match backend.read_link(&path) {
+                Ok(target) => writer.add_symlink(&name, &target, &stat),
+                Err(error) => {
+                    errors.push(format!(
+                        "Failed to read symlink {}: {error}",
+                        compact(&path)
+                    ));
+                    continue;
+                }
             }
-        }
-        Err(error) => {
-            // The subtree cannot be copied at all; skip it and continue with
-            // the siblings.
+        } else if unix_mode::is_file(stat.mode) {
+            let file = match backend.read(&path) {
+                Ok(file) => file,
+                Err(error) => {
+                    errors.push(format!("Failed to read {}: {error}", compact(&path)));
+                    continue;
+                }
+            };
+            let mut contents = ProgressReader {
+                file,
+                remaining: stat.size,
+                active: &mut *active,
+                debouncer: &mut debouncer,
+            };
+            writer.add_file(&name, &stat, &mut contents)
+        } else {
             errors.push(format!(
-                "Failed to create directory {}: {error}",
-                compact(new_path)
+                "Cannot compress {}: it is not a file, directory or symbolic link",
+                compact(&path)
             ));
-            return true;
+            continue;
+        };
+        match written {
+            Ok(()) => {}
+            Err(_) if active.is_cancelled() => return Err(PackAbort::Cancelled),
+            // A name zip cannot hold is refused before anything is written, so
+            // the archive is still whole without it.
+            Err(error) if error.kind() == ErrorKind::InvalidFilename => {
+                errors.push(format!("Cannot compress {}: {error}", compact(&path)));
+                continue;
+            }
+            Err(error) => {
+                return Err(PackAbort::Failed(format!(
+                    "Failed to add {} to {}: {error}",
+                    compact(&path),
+                    compact(archive)
+                )));
+            }
+        }
+        if unix_mode::is_dir(stat.mode) {
+            match backend.list(&path) {
+                Ok(entries) => {
+                    let mut children: Vec<_> = entries
+                        .flatten()
+                        .filter_map(|entry| entry.file_name().map(ToOwned::to_owned))
+                        .collect();
+                    children.sort();
+                    stack.extend(
+                        children
+                            .into_iter()
+                            .rev()
+                            .map(|child| (path.join(&child), name.join(&child))),
+                    );
+                }
+                Err(error) => errors.push(format!("Failed to list {}: {error}", compact(&path))),
+            }
         }
     }
+    Ok(())
+}
 
-    // Applied on every non-cancel exit, after the contents: a source mode
-    // without owner-write (e.g. 0o555) would otherwise stop us creating this
-    // directory's own children. Matches `cp`. Read from the source's metadata
-    // rather than its entries, so it applies even when the read below fails.
-    let preserve_times = context.preserve_times;
-    let source_mode = fs::symlink_metadata(old_path)
-        .ok()
-        .map(|metadata| metadata.permissions().mode());
-    let apply_source_mode = || {
-        // After the contents, for the same reason the mode is: writing the
-        // children is what moved the directory's own modification time.
-        if preserve_times {
-            apply_times_to_path(old_path, new_path);
+/// Reads a file into an archive, counting its bytes toward the task's
+/// progress. It fails once the task is cancelled, so a large file does not
+/// hold a cancel up until its end, and when the file comes up short of the
+/// size the tar header already recorded, which would otherwise leave the rest
+/// of the archive misaligned.
+struct ProgressReader<'a> {
+    file: Box<dyn Read + Send>,
+    remaining: u64,
+    active: &'a mut ActiveTask,
+    debouncer: &'a mut debounce::ProgressDebouncer,
+}
+
+impl Read for ProgressReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.active.is_cancelled() {
+            return Err(std::io::Error::other("cancelled"));
         }
-        if let Some(mode) = source_mode {
-            apply_permissions(mode, new_path);
+        if self.remaining == 0 {
+            // A file that grew since it was measured is cut at its old size.
+            return Ok(0);
         }
-    };
-
-    let entries = match fs::read_dir(old_path) {
-        Ok(entries) => entries,
-        Err(error) => {
-            errors.push(format!(
-                "Failed to read directory {}: {error}",
-                compact(old_path)
+        let limit = usize::try_from(self.remaining)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let read = self.file.read(&mut buf[..limit])?;
+        if read == 0 {
+            return Err(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "it shrank while being read",
             ));
-            apply_source_mode();
-            return true;
         }
-    };
+        self.remaining -= read as u64;
+        self.active.increment(read as u64);
+        if self.debouncer.should_trigger(Instant::now(), read as u64) {
+            self.active.send_progress();
+        }
+        Ok(read)
+    }
+}
 
-    for entry in entries {
-        if active.is_cancelled() {
-            // Like interrupted `cp`: leave the partially copied destination in
-            // place rather than removing it.
-            return false;
+/// What one sync or mirror does between two directories: entries found
+/// missing or older below one of them, each copied from where it is newer or
+/// only exists, and for a mirror, from the left onto the right, what only the
+/// right has, deleted.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyncPlan {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// Each source and the path it is copied to, below the other directory.
+    pub copies: Vec<(PathBuf, PathBuf)>,
+    /// Entries deleted before anything is copied, which frees the room a
+    /// nearly full backup drive needs for the copies. Empty for a sync.
+    pub removals: Vec<PathBuf>,
+}
+
+/// Deletes the plan's removals, then copies its entries in one task,
+/// replacing what is at each destination and creating the directories above
+/// it. Modification times are kept, so that comparing the two directories
+/// again finds the copies identical rather than newer. Progress counts the
+/// bytes copied, as a copy's does; the deletes are not counted.
+fn run_sync_task(
+    tx: Sender<Command>,
+    plan: SyncPlan,
+    kind: TaskKind,
+    protected: &ProtectedPaths,
+    buffer_min_bytes: u64,
+    buffer_max_bytes: u64,
+) -> TaskRunResult {
+    // A mirror replaces a directory with a file of the same name, taking
+    // everything below it, so a directory is checked as one.
+    let checks = plan
+        .copies
+        .iter()
+        .map(|(_, destination)| ("overwrite", destination))
+        .chain(plan.removals.iter().map(|path| ("delete", path)));
+    for (operation, path) in checks {
+        if let Ok(metadata) = path.symlink_metadata()
+            && let Err(error) = protected.check(operation, path, metadata.is_dir())
+        {
+            return TaskRunResult::failed(error.into());
         }
+    }
+    info!("{}", kind.message());
+    // The real total is scanned in the worker, off the UI thread.
+    let (active, initial, token) = ActiveTask::new(tx, kind, 0);
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
 
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(error) => {
-                errors.push(format!(
-                    "Failed to read entry in {}: {error}",
-                    compact(old_path)
-                ));
-                continue;
-            }
+    queue_operation(move || {
+        let Some(mut active) = check_cancelled(active) else {
+            return;
         };
-
-        let src = entry.path();
-        let dst = new_path.join(entry.file_name());
-        let metadata = match fs::symlink_metadata(&src) {
-            Ok(metadata) => metadata,
-            Err(error) => {
-                errors.push(format!(
-                    "Failed to read metadata for {}: {error}",
-                    compact(&src)
-                ));
-                continue;
+        // A plan holds paths on this disk: the compare that made it read them
+        // there.
+        let backend = &Local;
+        let mut errors = Vec::new();
+        for path in &plan.removals {
+            if active.is_cancelled() {
+                active.cancelled();
+                return;
+            }
+            if let Err(error) = remove_entry(backend, path) {
+                errors.push(format!("Failed to delete {}: {error}", compact(path)));
+            }
+        }
+        let mut total = 0;
+        for (source, _) in &plan.copies {
+            match backend.stat(source) {
+                Ok(stat) if unix_mode::is_dir(stat.mode) => {
+                    let Some(size) = dir_total_size(backend, &active, source) else {
+                        active.cancelled();
+                        return;
+                    };
+                    total += size;
+                }
+                Ok(stat) if unix_mode::is_file(stat.mode) => total += stat.size,
+                _ => {}
             }
+        }
+        active.set_total(total);
+        let mut buffer = vec![0; copy_buffer_bytes(total, buffer_min_bytes, buffer_max_bytes)];
+        let mut context = CopyContext {
+            backend,
+            buffer: &mut buffer,
+            conflicts: None,
+            preserve_times: true,
+            skipped: 0,
         };
-
-        // `symlink_metadata`, so the mode carries `S_IFLNK` for a symlink and
-        // `copy_path` dispatches links, directories, and files off it alike.
-        if !copy_path(
-            &src,
-            &dst,
-            active,
-            errors,
-            context,
-            metadata.is_dir(),
-            metadata.permissions().mode(),
-        ) {
-            return false;
+        for (source, destination) in &plan.copies {
+            if !sync_entry(source, destination, &mut active, &mut errors, &mut context) {
+                active.cancelled();
+                return;
+            }
         }
-    }
+        finalize_with_errors(active, errors);
+    });
 
-    apply_source_mode();
-    true
+    TaskRunResult::started(&initial, token, uncancellable)
 }
 
-/// Recreates the symlink at `old_path` at `new_path`, pointing at the same
-/// (possibly relative, possibly dangling) target. The target is never followed,
-/// so no bytes are transferred and no permissions are applied:
-/// `fs::set_permissions` would chmod the target rather than the link.
-fn copy_symlink(
-    old_path: &Path,
-    new_path: &Path,
+/// Removes the entry at `path`, a directory with everything below it, without
+/// following a symlink. One that is already gone is not an error.
+///
+/// A directory goes in one call, which a cancel cannot stop partway; a
+/// mirror's removals are what the source no longer has, seldom large trees.
+fn remove_entry(backend: &dyn Backend, path: &Path) -> std::io::Result<()> {
+    match backend.stat(path) {
+        Ok(stat) if unix_mode::is_dir(stat.mode) => backend.remove_dir_all(path),
+        Ok(_) => remove_existing(backend, path),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Copies `source` to `destination`, replacing what is there. Failures are
+/// recorded in `errors`; returns `false` only when cancelled.
+fn sync_entry(
+    source: &Path,
+    destination: &Path,
+    active: &mut ActiveTask,
     errors: &mut Vec<String>,
     context: &mut CopyContext<'_>,
-) {
-    let target = match fs::read_link(old_path) {
-        Ok(target) => target,
+) -> bool {
+    let backend = context.backend;
+    let stat = match backend.stat(source) {
+        Ok(stat) => stat,
         Err(error) => {
-            errors.push(format!(
-                "Failed to read symlink {}: {error}",
-                compact(old_path)
-            ));
-            return;
+            errors.push(format!("Failed to read {}: {error}", compact(source)));
+            return true;
         }
     };
-    match std::os::unix::fs::symlink(&target, new_path) {
-        Ok(()) => {}
-        // Raced; settled from the standing answer, or recorded.
-        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
-            if !resolve_nested(context, errors, new_path) {
+    let is_directory = unix_mode::is_dir(stat.mode);
+    if let Some(parent) = destination.parent()
+        && let Err(error) = backend.create_dir_all(parent)
+    {
+        errors.push(format!(
+            "Failed to create directory {}: {error}",
+            compact(parent)
+        ));
+        return true;
+    }
+    // A directory is only ever copied to where there was nothing or a
+    // non-directory, so a directory found there now is recorded by the copy
+    // rather than replaced. A file, though, replaces a directory in a mirror.
+    let replaced = if is_directory {
+        remove_existing(backend, destination)
+    } else {
+        remove_entry(backend, destination)
+    };
+    if let Err(error) = replaced
+        && error.kind() != ErrorKind::IsADirectory
+    {
+        errors.push(format!(
+            "Failed to replace {}: {error}",
+            compact(destination)
+        ));
+        return true;
+    }
+    copy_path(
+        source,
+        destination,
+        active,
+        errors,
+        context,
+        is_directory,
+        stat.mode,
+    )
+}
+
+/// What one checksum task reads: files, each with the digest a checksum file
+/// expects of it when verifying.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChecksumJob {
+    pub algorithm: ChecksumAlgorithm,
+    /// What the files are shown relative to.
+    pub directory: PathBuf,
+    /// The checksum file, when verifying.
+    pub sums: Option<PathBuf>,
+    pub files: Vec<(PathBuf, Option<String>)>,
+}
+
+/// Reads each of the job's files through to its digest, in one task, and
+/// sends them all as `Command::Checksummed` once it is done. A file that
+/// cannot be read is reported there, beside the others, rather than failing
+/// the task: when verifying, a file that is missing is one of the answers.
+/// Progress counts bytes read. Nothing is sent when cancelled.
+fn run_checksum_task(
+    tx: Sender<Command>,
+    job: ChecksumJob,
+    buffer_min_bytes: u64,
+    buffer_max_bytes: u64,
+) -> TaskRunResult {
+    let kind = match (&job.sums, job.files.as_slice()) {
+        (Some(sums), _) => TaskKind::Verify {
+            path: display_path(sums),
+        },
+        (None, [(path, _)]) => TaskKind::Checksum {
+            path: display_path(path),
+        },
+        (None, files) => TaskKind::Checksum {
+            path: format!("{} files in {}", files.len(), display_path(&job.directory)),
+        },
+    };
+    info!("{}", kind.message());
+    // The real total is read in the worker, off the UI thread.
+    let (active, initial, token) = ActiveTask::new(tx.clone(), kind, 0);
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
+
+    queue_operation(move || {
+        let Some(mut active) = check_cancelled(active) else {
+            return;
+        };
+        let total = job
+            .files
+            .iter()
+            .filter_map(|(path, _)| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        active.set_total(total);
+        let mut buffer = vec![0; copy_buffer_bytes(total, buffer_min_bytes, buffer_max_bytes)];
+        let mut debouncer = debounce::ProgressDebouncer::new(
+            PROGRESS_DEBOUNCE_PERCENTAGE,
+            PROGRESS_MIN_INTERVAL,
+            total,
+        );
+        let mut entries = Vec::with_capacity(job.files.len());
+        for (path, expected) in job.files {
+            let Some(digest) = digest_file(
+                &path,
+                job.algorithm,
+                &mut active,
+                &mut buffer,
+                &mut debouncer,
+            ) else {
+                active.cancelled();
                 return;
-            }
-            if let Err(error) = remove_existing(new_path)
-                .and_then(|()| std::os::unix::fs::symlink(&target, new_path))
-            {
-                errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
-            }
+            };
+            entries.push(ChecksumEntry {
+                path,
+                digest,
+                expected,
+            });
         }
-        Err(error) => errors.push(format!(
-            "Failed to create symlink {}: {error}",
-            compact(new_path)
-        )),
-    }
+        let _ = tx.send(Command::Checksummed(ChecksumReport {
+            algorithm: job.algorithm,
+            directory: job.directory,
+            sums: job.sums,
+            entries,
+        }));
+        active.done();
+    });
+
+    TaskRunResult::started(&initial, token, uncancellable)
 }
 
-/// Copies a file chunk-by-chunk, sending debounced progress updates via
-/// `active` and applying `source_mode`'s permissions on success. Failures are
-/// recorded in `errors`; returns `false` only when cancelled.
-fn copy_file(
-    old_path: &Path,
-    new_path: &Path,
+/// The digest of the file at `path`, symlinks followed as `sha256sum` follows
+/// them, or why it could not be read. `None` when cancelled, which is checked
+/// between reads so that a large file does not hold a cancel up.
+fn digest_file(
+    path: &Path,
+    algorithm: ChecksumAlgorithm,
     active: &mut ActiveTask,
-    errors: &mut Vec<String>,
-    context: &mut CopyContext<'_>,
-    source_mode: u32,
-) -> bool {
-    let total_size = active.total_size();
-    let (mut old_file, mut new_file) = match open_files(old_path, new_path) {
-        Ok(files) => files,
-        // A name already taken inside the tree being copied. The top-level
-        // collision was answered before the task started, so this one is a
-        // race, settled from the paste's standing answer or recorded.
-        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
-            if !resolve_nested(context, errors, new_path) {
-                return true;
-            }
-            match remove_existing(new_path).and_then(|()| open_files(old_path, new_path)) {
-                Ok(files) => files,
-                Err(error) => {
-                    errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
-                    return true;
-                }
-            }
+    buffer: &mut [u8],
+    debouncer: &mut debounce::ProgressDebouncer,
+) -> Option<Result<String, String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => return Some(Err(error.to_string())),
+    };
+    let mut hasher = checksums::Hasher::new(algorithm);
+    loop {
+        if active.is_cancelled() {
+            return None;
+        }
+        let read = match file.read(buffer) {
+            Ok(0) => return Some(Ok(hasher.finish())),
+            Ok(read) => read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Some(Err(error.to_string())),
+        };
+        hasher.update(&buffer[..read]);
+        active.increment(read as u64);
+        if debouncer.should_trigger(Instant::now(), read as u64) {
+            active.send_progress();
+        }
+    }
+}
+
+/// What one extract does: the archive, where its entries go, and what the
+/// conflict prompts settled about the names already there.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Extraction {
+    pub archive: PathBuf,
+    pub format: ArchiveFormat,
+    /// The entry inside the archive to extract, with everything below it, or
+    /// empty for the whole archive. It lands in the destination under its own
+    /// name, as a copy out of a directory would.
+    pub root: PathBuf,
+    /// The directory the entries land in, created when it does not exist.
+    pub destination: PathBuf,
+    /// Existing non-directories the user agreed to replace, removed before
+    /// anything is extracted.
+    pub replace: Vec<PathBuf>,
+    /// Top-level names of the archive the user chose to skip: nothing at or
+    /// below them is extracted.
+    pub skip: BTreeSet<OsString>,
+}
+
+/// Unpacks an archive. Progress counts the bytes of the archive read, the one
+/// total known before it has all been read.
+fn run_extract_task(
+    tx: Sender<Command>,
+    extraction: Extraction,
+    protected: &ProtectedPaths,
+) -> TaskRunResult {
+    // Only a non-directory is ever replaced, so nothing below it is at stake.
+    for path in &extraction.replace {
+        if let Err(error) = protected.check("overwrite", path, false) {
+            return TaskRunResult::failed(error.into());
         }
+    }
+    let total = match fs::metadata(&extraction.archive) {
+        Ok(metadata) => metadata.len(),
         Err(error) => {
-            errors.push(format!(
-                "Failed to copy {} to {}: {error}",
-                compact(old_path),
-                compact(new_path)
+            return TaskRunResult::failed(
+                anyhow!("Failed to read {}: {error}", compact(&extraction.archive)).into(),
+            );
+        }
+    };
+    let kind = TaskKind::Extract(Transfer {
+        source: display_path(&extraction.archive),
+        destination: display_path(&extraction.destination),
+    });
+    info!("{}", kind.message());
+    let (active, initial, token) = ActiveTask::new(tx, kind, total);
+    active.send_progress();
+    let uncancellable = active.uncancellable_handle();
+
+    queue_operation(move || {
+        let Some(active) = check_cancelled(active) else {
+            return;
+        };
+        // Into a directory on this disk, where the archive is read from.
+        if let Some((active, errors)) = unpack(&Local, &extraction, active) {
+            finalize_with_errors(active, errors);
+        }
+    });
+
+    TaskRunResult::started(&initial, token, uncancellable)
+}
+
+/// Extracts the archive's entries into the destination, writing them through
+/// `backend`. The archive itself is read as a local file, which a zip must
+/// seek in.
+///
+/// An entry that cannot be written, or that would land outside the
+/// destination, is recorded and left out, and the rest carry on, as `tar`
+/// carries on. A failure reading the archive stops the task, since nothing
+/// past a corrupt block can be trusted, as does a cancel. What was extracted
+/// by then is kept: unlike a partial archive, it is usable as it is. Returns
+/// `None` when the task was finalized here; otherwise the task and the errors,
+/// for the caller to finalize.
+fn unpack(
+    backend: &dyn Backend,
+    extraction: &Extraction,
+    mut active: ActiveTask,
+) -> Option<(ActiveTask, Vec<String>)> {
+    for path in &extraction.replace {
+        if let Err(error) = remove_existing(backend, path) {
+            active.error(format!("Failed to replace {}: {error}", compact(path)));
+            return None;
+        }
+    }
+    if let Err(error) = backend.create_dir_all(&extraction.destination) {
+        active.error(format!(
+            "Failed to create {}: {error}",
+            compact(&extraction.destination)
+        ));
+        return None;
+    }
+    let file = match File::open(&extraction.archive) {
+        Ok(file) => file,
+        Err(error) => {
+            active.error(format!(
+                "Failed to read {}: {error}",
+                compact(&extraction.archive)
             ));
-            return true;
+            return None;
         }
     };
+    let read = Rc::new(Cell::new(0));
+    let source = CountingReader {
+        file,
+        read: Rc::clone(&read),
+    };
+    let mut unpacker = Unpacker {
+        backend,
+        root: &extraction.root,
+        destination: &extraction.destination,
+        skip: &extraction.skip,
+        debouncer: debounce::ProgressDebouncer::new(
+            PROGRESS_DEBOUNCE_PERCENTAGE,
+            PROGRESS_MIN_INTERVAL,
+            active.total_size(),
+        ),
+        active: &mut active,
+        read,
+        reported: 0,
+        buffer: vec![0; EXTRACT_BUFFER_BYTES],
+        created: HashSet::new(),
+        directories: Vec::new(),
+        errors: Vec::new(),
+    };
+    let result = archive::read_entries(source, extraction.format, &mut |entry| {
+        unpacker.entry(entry)
+    });
+    unpacker.finish_directories();
+    let errors = unpacker.errors;
+    match result {
+        Ok(()) if active.is_cancelled() => {
+            active.cancelled();
+            None
+        }
+        Ok(()) => Some((active, errors)),
+        Err(error) => {
+            active.error(format!(
+                "Failed to read {}: {error}",
+                compact(&extraction.archive)
+            ));
+            None
+        }
+    }
+}
 
-    let mut debouncer = debounce::ProgressDebouncer::new(
-        PROGRESS_DEBOUNCE_PERCENTAGE,
-        PROGRESS_MIN_INTERVAL,
-        total_size,
-    );
+/// Counts the bytes read from an archive toward the task's progress. The count
+/// is shared through a `Cell`, since the reader itself is handed over to the
+/// decompressor and the archive reader.
+struct CountingReader {
+    file: File,
+    read: Rc<Cell<u64>>,
+}
 
-    loop {
-        if active.is_cancelled() {
-            // Like interrupted `cp`: leave the partially written destination
-            // file in place rather than removing it.
-            return false;
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.read.set(self.read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        self.file.seek(position)
+    }
+}
+
+/// The state of one extract, carried from entry to entry.
+struct Unpacker<'a> {
+    backend: &'a dyn Backend,
+    root: &'a Path,
+    destination: &'a Path,
+    skip: &'a BTreeSet<OsString>,
+    active: &'a mut ActiveTask,
+    debouncer: debounce::ProgressDebouncer,
+    /// The bytes of the archive read so far, and how many of them have been
+    /// counted toward the task's progress.
+    read: Rc<Cell<u64>>,
+    reported: u64,
+    buffer: Vec<u8>,
+    /// Everything this extract has created. Only these are ever replaced or
+    /// written into: an entry the archive names twice is replaced by the later
+    /// one, as `tar` replaces it, but a name that was taken by anything else
+    /// is recorded and left alone, whatever the archive says.
+    created: HashSet<PathBuf>,
+    /// Directories with the mode and time their entries recorded, applied once
+    /// everything is extracted: a read-only directory would refuse its own
+    /// entries, and writing them would move its time.
+    directories: Vec<(PathBuf, Option<u32>, Option<SystemTime>)>,
+    errors: Vec<String>,
+}
+
+impl Unpacker<'_> {
+    /// Extracts one entry, recording it when it cannot be. Returns whether to
+    /// carry on, which is only `false` once the task is cancelled; an `Err` is
+    /// a failure reading the archive itself.
+    fn entry(&mut self, mut entry: ArchiveEntry<'_>) -> std::io::Result<bool> {
+        self.report_progress();
+        if self.active.is_cancelled() {
+            return Ok(false);
+        }
+        let Some(relative) = archive::relative_path(&entry.name) else {
+            // Nowhere near the entry being copied out.
+            if !self.root.as_os_str().is_empty() {
+                return Ok(true);
+            }
+            self.errors.push(format!(
+                "Refused to extract {}: it would land outside {}",
+                entry.name.display(),
+                compact(self.destination)
+            ));
+            return Ok(true);
+        };
+        let Some(relative) = self.placed(&relative) else {
+            return Ok(true);
+        };
+        // A name of nothing but `.` is the destination itself.
+        let Some(top) = relative.components().next() else {
+            return Ok(true);
+        };
+        if self.skip.contains(top.as_os_str()) {
+            return Ok(true);
+        }
+        let path = self.destination.join(&relative);
+        if let Err(error) = self.parents(&relative) {
+            self.errors
+                .push(format!("Failed to extract {}: {error}", compact(&path)));
+            return Ok(true);
+        }
+        let written = match entry.kind {
+            EntryKind::Directory => self.directory(&path, entry.mode, entry.modified),
+            EntryKind::File => return self.file(&path, &mut entry),
+            EntryKind::Symlink(ref target) => self
+                .clear(&path)
+                .and_then(|()| self.backend.symlink(target, &path)),
+            EntryKind::HardLink(ref target) => self.hard_link(target, &path),
+            EntryKind::Other => Err(std::io::Error::other("it is not a file, directory or link")),
+        };
+        match written {
+            // A directory records itself, only when it is new.
+            Ok(()) if entry.kind != EntryKind::Directory => {
+                self.created.insert(path);
+            }
+            Ok(()) => {}
+            Err(error) => self
+                .errors
+                .push(format!("Failed to extract {}: {error}", compact(&path))),
+        }
+        Ok(true)
+    }
+
+    /// Where in the destination the archive's `relative` lands: below it with
+    /// the root's parent stripped, or `None` when it is not at or below the
+    /// root.
+    fn placed(&self, relative: &Path) -> Option<PathBuf> {
+        if !relative.starts_with(self.root) {
+            return None;
         }
+        let parent = self.root.parent().unwrap_or(Path::new(""));
+        relative.strip_prefix(parent).ok().map(Path::to_path_buf)
+    }
 
-        match old_file.read(context.buffer) {
-            Ok(0) => {
-                // Before the permissions, and before `new_file` is dropped:
-                // writing is what moves the modification time, so it has to be
-                // restored once the last byte is written.
-                if context.preserve_times {
-                    apply_times(old_path, &new_file);
+    /// Creates the directories above `relative` that are not there yet. Every
+    /// one that is must be a real directory: a symlink among them, which an
+    /// earlier entry may have planted, would carry the entry anywhere it
+    /// points.
+    fn parents(&mut self, relative: &Path) -> std::io::Result<()> {
+        let mut path = self.destination.to_path_buf();
+        let Some(parent) = relative.parent() else {
+            return Ok(());
+        };
+        for component in parent.components() {
+            path.push(component);
+            match self.backend.stat(&path) {
+                Ok(stat) if unix_mode::is_dir(stat.mode) => {}
+                Ok(_) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::NotADirectory,
+                        format!("{} is not a directory", compact(&path)),
+                    ));
                 }
-                apply_permissions(source_mode, new_path);
-                return true;
-            }
-            Ok(bytes) => match new_file.write_all(&context.buffer[..bytes]) {
-                Ok(()) => {
-                    active.increment(bytes as u64);
-                    if debouncer.should_trigger(Instant::now(), bytes as u64) {
-                        active.send_progress();
-                    }
+                Err(error) if error.kind() == ErrorKind::NotFound => {
+                    self.backend.create_dir(&path)?;
+                    self.created.insert(path.clone());
                 }
-                Err(error) => {
-                    errors.push(format!("Failed to write {}: {error}", compact(new_path)));
-                    return true;
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    fn directory(
+        &mut self,
+        path: &Path,
+        mode: Option<u32>,
+        modified: Option<SystemTime>,
+    ) -> std::io::Result<()> {
+        match self.backend.stat(path) {
+            // One the archive named before, or made as a parent: its mode and
+            // time are the archive's to set. One that was already there is
+            // extracted into and otherwise left as it is.
+            Ok(stat) if unix_mode::is_dir(stat.mode) => {
+                if !self.created.contains(path) {
+                    return Ok(());
                 }
-            },
+            }
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    "something that is not a directory is already there",
+                ));
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                self.backend.create_dir(path)?;
+                self.created.insert(path.to_path_buf());
+            }
+            Err(error) => return Err(error),
+        }
+        self.directories.push((path.to_path_buf(), mode, modified));
+        Ok(())
+    }
+
+    /// Writes a file's contents. Returns as `entry` does: a failure writing
+    /// the file is recorded, a failure reading the archive is returned.
+    fn file(&mut self, path: &Path, entry: &mut ArchiveEntry<'_>) -> std::io::Result<bool> {
+        let created = self.clear(path).and_then(|()| self.backend.create(path));
+        let mut file = match created {
+            Ok(file) => file,
             Err(error) => {
-                errors.push(format!("Failed to read {}: {error}", compact(old_path)));
-                return true;
+                self.errors
+                    .push(format!("Failed to extract {}: {error}", compact(path)));
+                return Ok(true);
+            }
+        };
+        self.created.insert(path.to_path_buf());
+        loop {
+            if self.active.is_cancelled() {
+                // Half a file is not something the archive held.
+                drop(file);
+                remove_partial(self.backend, path);
+                return Ok(false);
+            }
+            let read = entry.contents.read(&mut self.buffer)?;
+            if read == 0 {
+                break;
+            }
+            if let Err(error) = file.write_all(&self.buffer[..read]) {
+                drop(file);
+                remove_partial(self.backend, path);
+                self.errors
+                    .push(format!("Failed to extract {}: {error}", compact(path)));
+                return Ok(true);
+            }
+            self.report_progress();
+        }
+        // A backend's writer is only known to have written everything once
+        // flushed, and the time is set once nothing more will be written.
+        let flushed = file.flush();
+        drop(file);
+        if let Err(error) = flushed {
+            remove_partial(self.backend, path);
+            self.errors
+                .push(format!("Failed to extract {}: {error}", compact(path)));
+            return Ok(true);
+        }
+        if let Some(mode) = entry.mode {
+            apply_permissions(self.backend, mode & EXTRACTED_MODE_BITS, path);
+        }
+        if let Some(modified) = entry.modified
+            && let Err(error) = self.backend.set_times(path, None, Some(modified))
+        {
+            warn!("Failed to set times on {}: {error}", compact(path));
+        }
+        Ok(true)
+    }
+
+    /// Links `path` to the earlier entry `target` names. Only to one this
+    /// extract created: a tar link names any path it likes, and one reaching
+    /// through a symlink or to something already there would hand the archive
+    /// a second name for a file it never held.
+    fn hard_link(&mut self, target: &Path, path: &Path) -> std::io::Result<()> {
+        let target = archive::relative_path(target)
+            .and_then(|relative| self.placed(&relative))
+            .map(|relative| self.destination.join(relative))
+            .filter(|target| self.created.contains(target))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("it links to {}, which it did not extract", target.display()),
+                )
+            })?;
+        self.clear(path)?;
+        self.backend.hard_link(&target, path)
+    }
+
+    /// Makes way for an entry at `path`. Something already there is replaced
+    /// only when this extract put it there and it is not a directory.
+    fn clear(&self, path: &Path) -> std::io::Result<()> {
+        match self.backend.stat(path) {
+            Ok(stat) if unix_mode::is_dir(stat.mode) => Err(std::io::Error::new(
+                ErrorKind::AlreadyExists,
+                "a directory of that name is already there",
+            )),
+            Ok(_) if self.created.contains(path) => self.backend.remove(path),
+            Ok(_) => Err(std::io::Error::new(
+                ErrorKind::AlreadyExists,
+                "it already exists",
+            )),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn report_progress(&mut self) {
+        let read = self.read.get();
+        let additional = read.saturating_sub(self.reported);
+        self.reported = read;
+        self.active.increment(additional);
+        if self.debouncer.should_trigger(Instant::now(), additional) {
+            self.active.send_progress();
+        }
+    }
+
+    /// Applies the directories' modes and times, deepest first, so that a
+    /// read-only parent does not refuse the child that comes after it.
+    fn finish_directories(&mut self) {
+        for (path, mode, modified) in self.directories.drain(..).rev() {
+            if let Some(modified) = modified
+                && let Err(error) = self.backend.set_times(&path, None, Some(modified))
+            {
+                warn!("Failed to set times on {}: {error}", compact(&path));
+            }
+            if let Some(mode) = mode {
+                apply_permissions(self.backend, mode & EXTRACTED_MODE_BITS, &path);
             }
         }
     }
 }
 
-/// Copies a directory, file, symlink, or special file, dispatching on
-/// `is_directory` and `source_mode`. Per-entry failures accumulate in
-/// `errors`; returns `false` only when the task was cancelled.
-fn copy_path(
-    old_path: &Path,
-    new_path: &Path,
-    active: &mut ActiveTask,
-    errors: &mut Vec<String>,
-    context: &mut CopyContext<'_>,
-    is_directory: bool,
-    source_mode: u32,
-) -> bool {
-    // Check symlink first: `source_mode` comes from `symlink_metadata`, so a
-    // symlink (even one pointing at a directory) is recreated as a link rather
-    // than followed. `is_directory` is already false for any symlink.
-    if unix_mode::is_symlink(source_mode) {
-        copy_symlink(old_path, new_path, errors, context);
-        true
-    } else if is_directory {
-        copy_directory(old_path, new_path, active, errors, context)
-    } else if unix_mode::is_file(source_mode) {
-        copy_file(old_path, new_path, active, errors, context, source_mode)
-    } else {
-        copy_special(old_path, new_path, errors, context, source_mode);
-        true
+fn remove_partial(backend: &dyn Backend, path: &Path) {
+    if let Err(error) = backend.remove(path) {
+        warn!("Failed to remove the partial {}: {error}", compact(path));
     }
 }
 
-/// Recreates a special file (FIFO, socket, or device node) as a fresh node at
-/// `new_path` with the source's permission bits, like `cp -R` does. No bytes
-/// are transferred: reading a FIFO would block until a writer appears. FIFOs
-/// and sockets need no privileges; device nodes require root, so as a normal
-/// user they record a "not permitted" error here, exactly as `cp` reports.
-fn copy_special(
-    old_path: &Path,
-    new_path: &Path,
-    errors: &mut Vec<String>,
-    context: &mut CopyContext<'_>,
-    source_mode: u32,
-) {
-    use nix::sys::stat::{Mode, SFlag, mknod};
-
-    let kind = if unix_mode::is_fifo(source_mode) {
-        SFlag::S_IFIFO
-    } else if unix_mode::is_socket(source_mode) {
-        SFlag::S_IFSOCK
-    } else if unix_mode::is_block_device(source_mode) {
-        SFlag::S_IFBLK
-    } else if unix_mode::is_char_device(source_mode) {
-        SFlag::S_IFCHR
-    } else {
-        errors.push(format!(
-            "Cannot copy {}: unsupported file type",
-            compact(old_path)
-        ));
-        return;
-    };
-
-    // Device nodes need the source's device numbers; zero for the rest.
-    let rdev = if matches!(kind, SFlag::S_IFBLK | SFlag::S_IFCHR) {
-        use std::os::unix::fs::MetadataExt;
-        match fs::symlink_metadata(old_path) {
-            Ok(metadata) => metadata.rdev() as nix::libc::dev_t,
-            Err(error) => {
-                errors.push(format!(
-                    "Failed to read metadata for {}: {error}",
-                    compact(old_path)
-                ));
-                return;
-            }
-        }
+fn buffer_bytes(len: u64, buffer_min_bytes: u64, buffer_max_bytes: u64) -> usize {
+    let bytes = if len <= buffer_min_bytes {
+        len
+    } else if len >= (buffer_max_bytes * BUFFER_SIZE_DIVISOR) {
+        buffer_max_bytes
     } else {
-        0
+        std::cmp::max(buffer_min_bytes, len / BUFFER_SIZE_DIVISOR)
     };
+    // The sizes are u64 from the config and from a scanned total, so on a
+    // 32-bit target one can exceed what a buffer could be allocated at.
+    // Saturating asks for the largest buffer the address space can express.
+    usize::try_from(bytes).unwrap_or(usize::MAX)
+}
 
-    // `mode_t` is u32 on Linux but u16 on macOS, so cast rather than assume.
-    let permissions = Mode::from_bits_truncate(source_mode as nix::libc::mode_t);
-    match mknod(new_path, kind, permissions, rdev) {
-        Ok(()) => {}
-        // Raced; settled from the standing answer, or recorded.
-        Err(nix::errno::Errno::EEXIST) => {
-            if !resolve_nested(context, errors, new_path) {
-                return;
-            }
-            if let Err(error) = remove_existing(new_path)
-                .map_err(|error| error.to_string())
-                .and_then(|()| {
-                    mknod(new_path, kind, permissions, rdev).map_err(|error| error.to_string())
-                })
-            {
-                errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
-            }
-        }
-        Err(error) => errors.push(format!(
-            "Failed to create special file {}: {error}",
-            compact(new_path)
-        )),
-    }
+/// The read-buffer size for a copy: `buffer_bytes` floored at
+/// `MIN_COPY_BUFFER_BYTES` so a tiny or zero scanned total never yields a
+/// buffer too small to make progress. The floor itself is capped at
+/// `buffer_max_bytes`, which a user may configure below the floor.
+fn copy_buffer_bytes(len: u64, buffer_min_bytes: u64, buffer_max_bytes: u64) -> usize {
+    buffer_bytes(len, buffer_min_bytes, buffer_max_bytes)
+        .max(MIN_COPY_BUFFER_BYTES.min(usize::try_from(buffer_max_bytes).unwrap_or(usize::MAX)))
 }
 
-/// Removes a file or directory tree, checking for cancellation between
-/// entries. Cancelling mid-delete leaves whatever has not been removed yet.
-/// Iterative (explicit stack), so directory depth cannot overflow the thread
-/// stack.
+/// The byte-copy stage shared by copy and cross-device move: for a directory
+/// source, scans the real transfer total (a directory entry's own size is not
+/// the transfer size) and applies it via `set_total`, sizes the read buffer,
+/// and copies the tree.
 ///
-/// Returns `Some(active)` on success, leaving finalization to the caller.
-/// Returns `None` when cancelled or on error, in which case the task has
-/// already been finalized via `active.cancelled()` / `active.error()`.
-fn remove_path(path: &Path, is_directory: bool, mut active: ActiveTask) -> Option<ActiveTask> {
-    if active.is_cancelled() {
+/// Returns `None` when the task was cancelled, in which case it has already
+/// been finalized via `active.cancelled()`. Otherwise returns the task and
+/// what the walk left behind, for the caller to finalize.
+#[allow(clippy::too_many_arguments)]
+fn copy_with_progress(
+    backend: &dyn Backend,
+    old_path: &Path,
+    new_path: &Path,
+    mut active: ActiveTask,
+    entry_size: u64,
+    is_directory: bool,
+    source_mode: u32,
+    preserve_times: bool,
+    conflicts: Option<&Conflicts>,
+    buffer_min_bytes: u64,
+    buffer_max_bytes: u64,
+) -> Option<(ActiveTask, CopyOutcome)> {
+    let total_size = if is_directory {
+        let Some(size) = dir_total_size(backend, &active, old_path) else {
+            active.cancelled();
+            return None;
+        };
+        active.set_total(size);
+        size
+    } else {
+        entry_size
+    };
+    // One buffer for the whole tree. It is sized from the tree's total, so
+    // allocating it per file would hand every small file in a large directory
+    // its own multi-megabyte allocation.
+    let mut buffer = vec![0; copy_buffer_bytes(total_size, buffer_min_bytes, buffer_max_bytes)];
+    let mut context = CopyContext {
+        backend,
+        buffer: &mut buffer,
+        conflicts,
+        preserve_times,
+        skipped: 0,
+    };
+    let mut errors = Vec::new();
+    if !copy_path(
+        old_path,
+        new_path,
+        &mut active,
+        &mut errors,
+        &mut context,
+        is_directory,
+        source_mode,
+    ) {
         active.cancelled();
         return None;
     }
-    if !is_directory {
-        // Symlinks are removed as links (never followed): `is_directory` comes
-        // from `symlink_metadata`, so a link to a directory takes this branch.
-        try_or_abort!(
-            active,
-            fs::remove_file(path),
-            format!("Failed to delete {}", compact(path))
-        );
-        active.increment(1);
-        return Some(active);
-    }
+    Some((
+        active,
+        CopyOutcome {
+            errors,
+            skipped: context.skipped,
+        },
+    ))
+}
 
-    // Post-order walk, each directory drained into a Vec before anything is
-    // deleted: the fd closes at once (depth is not bounded by the fd limit) and
-    // nothing is unlinked while a ReadDir stream is open, which on some
-    // filesystems (NFS) can skip entries. The trade is peak memory, which is
-    // proportional to the widest root-to-leaf path rather than O(1) per level.
-    // A directory is removed once its entries are done, so a cancelled or
-    // failed delete leaves each subtree either fully removed or intact.
-    let root = path.to_path_buf();
-    let entries = list_or_abort!(active, &root);
-    let mut stack = vec![(root, entries.into_iter())];
-    // One unit of progress per entry removed, against the total counted by
-    // `dir_total_entries` before the walk. Debounced so a wide tree does not
-    // put one progress command per entry ahead of terminal input.
-    let mut debouncer = debounce::ProgressDebouncer::new(
-        PROGRESS_DEBOUNCE_PERCENTAGE,
-        PROGRESS_MIN_INTERVAL,
-        active.total_size(),
-    );
-    while let Some(top) = stack.last_mut() {
+/// Best-effort recursive size for the progress total: the bytes of the files
+/// below `root`. Entries that cannot be read are skipped here; the copy itself
+/// reports them as errors.
+///
+/// Returns `None` when the task was cancelled. The walk runs before any bytes
+/// are copied and takes as long as the tree is large, so it observes the token
+/// itself rather than leaving a cancel acknowledged but still running.
+fn dir_total_size(backend: &dyn Backend, active: &ActiveTask, root: &Path) -> Option<u64> {
+    let mut total = 0;
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(path) = stack.pop() {
         if active.is_cancelled() {
-            active.cancelled();
             return None;
         }
-        match top.1.next() {
-            // This directory's entries are done; remove it.
-            None => {
-                let (directory, _) = stack.pop().expect("stack is non-empty");
-                try_or_abort!(
-                    active,
-                    fs::remove_dir(&directory),
-                    format!("Failed to delete {}", compact(&directory))
-                );
+        let Ok(entries) = backend.list(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if active.is_cancelled() {
+                return None;
             }
-            Some((entry_path, true)) => {
-                let entries = list_or_abort!(active, &entry_path);
-                stack.push((entry_path, entries.into_iter()));
-                // Descending is not a removal, so it advances no progress.
+            // `stat` does not follow symlinks, so a link is never descended
+            // into nor counted.
+            let Ok(stat) = backend.stat(&entry) else {
                 continue;
+            };
+            if unix_mode::is_dir(stat.mode) {
+                stack.push(entry);
+            } else if unix_mode::is_file(stat.mode) {
+                total += stat.size;
             }
-            Some((entry_path, false)) => {
-                try_or_abort!(
-                    active,
-                    fs::remove_file(&entry_path),
-                    format!("Failed to delete {}", compact(&entry_path))
-                );
-            }
-        }
-        active.increment(1);
-        if debouncer.should_trigger(Instant::now(), 1) {
-            active.send_progress();
         }
     }
-    Some(active)
+    Some(total)
 }
 
-/// Collects `(path, is_directory)` for each entry of `directory`, closing the
-/// directory's fd before the caller deletes anything. `file_type()` does not
-/// follow symlinks, so a link to a directory reports `false` and is unlinked
-/// rather than descended into. Checked for cancellation once per entry, so a
-/// huge directory does not delay a cancel; returns `Ok(None)` when cancelled.
-fn list_entries(
-    active: &ActiveTask,
-    directory: &Path,
-) -> std::io::Result<Option<Vec<(PathBuf, bool)>>> {
-    let mut entries = Vec::new();
-    for entry in fs::read_dir(directory)? {
+/// Best-effort recursive entry count for the delete progress total, including
+/// `root` itself. A directory that cannot be listed counts as the single entry
+/// it is. Returns `None` when the task was cancelled.
+fn dir_total_entries(backend: &dyn Backend, active: &ActiveTask, root: &Path) -> Option<u64> {
+    let mut total = 1; // `root` itself, which appears in no listing.
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(path) = stack.pop() {
         if active.is_cancelled() {
-            return Ok(None);
+            return None;
+        }
+        let Ok(entries) = backend.list(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if active.is_cancelled() {
+                return None;
+            }
+            total += 1;
+            // `stat` does not follow symlinks, matching the delete walk: a
+            // link to a directory is unlinked rather than descended into.
+            if backend
+                .stat(&entry)
+                .is_ok_and(|stat| unix_mode::is_dir(stat.mode))
+            {
+                stack.push(entry);
+            }
         }
-        let entry = entry?;
-        let file_type = entry.file_type()?;
-        entries.push((entry.path(), file_type.is_dir()));
     }
-    Ok(Some(entries))
+    Some(total)
 }
 
-/// Copies `source`'s access and modification times onto an already-open
-/// `target`. Best effort: a filesystem that cannot record them is not a reason
-/// to fail the operation.
-fn apply_times(source: &Path, target: &File) {
-    let Ok(metadata) = fs::metadata(source) else {
-        return;
-    };
-    let mut times = fs::FileTimes::new();
-    if let Ok(accessed) = metadata.accessed() {
-        times = times.set_accessed(accessed);
-    }
-    if let Ok(modified) = metadata.modified() {
-        times = times.set_modified(modified);
-    }
-    if let Err(error) = target.set_times(times) {
-        warn!("Failed to set times on {}: {error}", compact(source));
-    }
+/// A tree being copied between hosts: where it comes from and goes to, and
+/// what the copy functions below share, as `CopyContext` is for a local copy.
+struct Across<'a> {
+    from: &'a dyn Backend,
+    to: &'a dyn Backend,
+    buffer: &'a mut [u8],
+    errors: &'a mut Vec<String>,
 }
 
-/// The same, for a path that is not already open. Opening a directory
-/// read-only is enough to set its times when the caller owns it.
-fn apply_times_to_path(source: &Path, target: &Path) {
-    if let Ok(file) = File::open(target) {
-        apply_times(source, &file);
+impl Across<'_> {
+    /// Copies the entry at `old_path`, which `stat` describes, to `new_path`.
+    /// Failures are recorded and the copy goes on, as `cp -R` does; returns
+    /// `false` only when cancelled.
+    fn copy(
+        &mut self,
+        old_path: &Path,
+        new_path: &Path,
+        stat: Stat,
+        active: &mut ActiveTask,
+    ) -> bool {
+        if unix_mode::is_dir(stat.mode) {
+            self.copy_directory(old_path, new_path, stat, active)
+        } else if unix_mode::is_file(stat.mode) {
+            self.copy_file(old_path, new_path, stat, active)
+        } else {
+            self.errors.push(format!(
+                "Skipped {}: only files and directories are copied between hosts",
+                compact(old_path)
+            ));
+            true
+        }
     }
-}
 
-fn apply_permissions(mode: u32, path: &Path) {
-    if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
-        warn!("Failed to set permissions on {}: {e}", path.display());
+    fn copy_directory(
+        &mut self,
+        old_path: &Path,
+        new_path: &Path,
+        stat: Stat,
+        active: &mut ActiveTask,
+    ) -> bool {
+        if let Err(error) = self.to.create_dir(new_path) {
+            self.errors.push(format!(
+                "Failed to create directory {}: {error}",
+                compact(new_path)
+            ));
+            return true;
+        }
+        match self.from.list(old_path) {
+            Ok(entries) => {
+                for entry in entries {
+                    if active.is_cancelled() {
+                        return false;
+                    }
+                    let child = match entry.and_then(|entry| {
+                        let stat = self.from.stat(&entry)?;
+                        Ok((entry, stat))
+                    }) {
+                        Ok(child) => child,
+                        Err(error) => {
+                            self.errors.push(format!(
+                                "Failed to read an entry in {}: {error}",
+                                compact(old_path)
+                            ));
+                            continue;
+                        }
+                    };
+                    let (entry, stat) = child;
+                    let name = entry.file_name().unwrap_or_default();
+                    if !self.copy(&entry, &new_path.join(name), stat, active) {
+                        return false;
+                    }
+                }
+            }
+            Err(error) => self.errors.push(format!(
+                "Failed to read directory {}: {error}",
+                compact(old_path)
+            )),
+        }
+        // After the contents, for the reason a local copy applies it last.
+        self.apply_mode(new_path, stat);
+        true
     }
-}
 
-/// Renames `old_path` to `new_path`, failing atomically with `AlreadyExists` if
-/// `new_path` is taken, unlike `fs::rename`, which replaces it. `validate_paths`
-/// rejects an existing destination already, but on the UI thread before the
-/// worker starts; folding the check into the rename closes the window where one
-/// appears in between.
-///
-/// Linux uses `renameat2(RENAME_NOREPLACE)` and macOS `renameatx_np`, both
-/// through rustix's safe wrapper, since `unsafe` is denied crate-wide. Other
-/// targets fall back to `fs::rename` and keep the narrow race.
-#[cfg(any(target_os = "linux", target_os = "macos"))]
-fn rename_no_replace(old_path: &Path, new_path: &Path) -> std::io::Result<()> {
-    use rustix::{
-        fs::{CWD, RenameFlags, renameat_with},
-        io::Errno,
-    };
-
-    // `CWD` as both dirfds: absolute paths ignore it and relative paths resolve
-    // against the current directory, matching `fs::rename`.
-    match renameat_with(CWD, old_path, CWD, new_path, RenameFlags::NOREPLACE) {
-        Ok(()) => Ok(()),
-        // Some filesystems reject the no-replace flag; fall back to a plain
-        // rename (validate_paths already guarded the destination, so only the
-        // narrow race reopens here).
-        Err(Errno::NOSYS | Errno::INVAL | Errno::NOTSUP) => fs::rename(old_path, new_path),
-        // Preserve the errno (e.g. XDEV -> CrossesDevices, EXIST ->
-        // AlreadyExists) so callers can dispatch on `error.kind()`.
-        Err(errno) => Err(errno.into()),
-    }
-}
-
-#[cfg(not(any(target_os = "linux", target_os = "macos")))]
-fn rename_no_replace(old_path: &Path, new_path: &Path) -> std::io::Result<()> {
-    fs::rename(old_path, new_path)
-}
-
-fn open_files(source: &Path, target: &Path) -> std::io::Result<(File, File)> {
-    let source = File::open(source)?;
-    // `create_new` (O_EXCL|O_CREAT) fails atomically if the target exists,
-    // closing the same window as `rename_no_replace`; without it a file that
-    // appeared since `validate_paths` ran would be truncated.
-    let target = fs::OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(target)?;
-    Ok((source, target))
-}
-
-/// An absolute, display-friendly rendering of `path` for the operations
-/// notice. Lexical only (no filesystem access), so it works for destination
-/// paths that do not exist yet; falls back to the original path if it cannot
-/// be absolutized.
-fn display_path(path: &Path) -> String {
-    std::path::absolute(path)
-        .unwrap_or_else(|_| path.to_path_buf())
-        .display()
-        .to_string()
-}
-
-/// The path with symlinks and `..` components resolved. Falls back to a lexical
-/// absolutize-and-normalize when the path cannot be resolved, which is the
-/// normal case for a destination that does not exist yet.
-fn resolve(path: &Path) -> PathBuf {
-    path.canonicalize().unwrap_or_else(|_| {
-        lexical_normalize(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
-    })
-}
-
-/// Collapses `.` and `..` components purely lexically (no filesystem access,
-/// so it works for destinations that do not exist yet). `..` pops the previous
-/// component; at the root it is a no-op.
-fn lexical_normalize(path: &Path) -> PathBuf {
-    use std::path::Component;
-    let mut out = PathBuf::new();
-    for component in path.components() {
-        match component {
-            Component::ParentDir => {
-                out.pop();
+    fn copy_file(
+        &mut self,
+        old_path: &Path,
+        new_path: &Path,
+        stat: Stat,
+        active: &mut ActiveTask,
+    ) -> bool {
+        let files =
+            self.from
+                .read(old_path)
+                .map_err(|error| format!("Failed to read {}: {error}", compact(old_path)))
+                .and_then(|reader| {
+                    let writer = self.to.create(new_path).map_err(|error| {
+                        format!("Failed to create {}: {error}", compact(new_path))
+                    })?;
+                    Ok((reader, writer))
+                });
+        let (mut reader, mut writer) = match files {
+            Ok(files) => files,
+            Err(error) => {
+                self.errors.push(error);
+                return true;
+            }
+        };
+        let mut debouncer = debounce::ProgressDebouncer::new(
+            PROGRESS_DEBOUNCE_PERCENTAGE,
+            PROGRESS_MIN_INTERVAL,
+            active.total_size(),
+        );
+        loop {
+            if active.is_cancelled() {
+                return false;
+            }
+            match reader.read(self.buffer) {
+                Ok(0) => break,
+                Ok(bytes) => {
+                    if let Err(error) = writer.write_all(&self.buffer[..bytes]) {
+                        self.errors
+                            .push(format!("Failed to write {}: {error}", compact(new_path)));
+                        return true;
+                    }
+                    active.increment(bytes as u64);
+                    if debouncer.should_trigger(Instant::now(), bytes as u64) {
+                        active.send_progress();
+                    }
+                }
+                Err(error) => {
+                    self.errors
+                        .push(format!("Failed to read {}: {error}", compact(old_path)));
+                    return true;
+                }
             }
-            Component::CurDir => {}
-            other => out.push(other.as_os_str()),
         }
-    }
-    out
-}
-
-fn validate_paths(
-    source: &PathInfo,
-    destination_directory: &PathInfo,
-    operation: &str,
-    overwrite: bool,
-) -> Result<(PathBuf, PathBuf), CommandResult> {
-    let old_path = source.path.clone();
-    // Join the source's raw `OsStr` file name rather than its display name:
-    // the display name is lossy UTF-8, which would silently mangle a non-UTF8
-    // name at the destination.
-    let Some(file_name) = source.path.file_name() else {
-        return Err(anyhow!(
-            "Cannot {operation} {}: path has no file name",
-            compact(&old_path)
-        )
-        .into());
-    };
-    let new_path = destination_directory.path.join(file_name);
-
-    // Compare resolved paths so that neither a parent-dir segment (e.g.
-    // `/a/c/../b`) nor a destination directory that is a symlink to the
-    // source's own directory can disguise one path as another. The
-    // destination's own name does not exist yet, so its parent is resolved and
-    // the name rejoined.
-    let abs_old = resolve(&old_path);
-    let abs_new = resolve(&destination_directory.path).join(file_name);
-
-    // The two paths name the same entry. This has to be caught here whatever
-    // the source's type: a granted overwrite clears the destination before
-    // copying, so letting an aliased path through would unlink the source and
-    // leave nothing to copy.
-    if abs_old == abs_new {
-        // Equal resolved paths mean the destination directory is the source's
-        // own, so the message names the entry once.
-        return Err(anyhow!(
-            "Cannot {operation} {} into its own directory",
-            compact(&old_path)
-        )
-        .into());
-    }
-
-    // Without this a copy creates the destination under the source and recurses
-    // into it forever, filling the disk. Only a real directory can: `copy_path`
-    // recreates a symlink as a link and copies a file as bytes, so neither
-    // descends into what it is creating. Restricting the check to directories
-    // also keeps it from refusing a symlink copied into the directory it points
-    // at.
-    if source.is_directory() && abs_new.starts_with(&abs_old) {
-        return Err(anyhow!(
-            "Cannot {operation} {} into its own subdirectory {}",
-            compact(&old_path),
-            compact(&new_path)
-        )
-        .into());
+        // A remote write is only known to have landed once flushed.
+        if let Err(error) = writer.flush() {
+            self.errors
+                .push(format!("Failed to write {}: {error}", compact(new_path)));
+            return true;
+        }
+        drop(writer);
+        self.apply_mode(new_path, stat);
+        true
     }
 
-    // Refuse to replace an existing destination unless the paste asked for it:
-    // `File::create`/`fs::rename` would otherwise do so silently. An existing
-    // directory is never replaced whatever was asked, because removing it would
-    // take its contents with it and merging into it is not supported.
-    match new_path.symlink_metadata() {
-        // Both messages name the destination directory rather than the full
-        // destination path: it differs from the source only in its directory,
-        // so repeating the file name says nothing.
-        Ok(metadata) if metadata.is_dir() => Err(anyhow!(
-            "Cannot {operation} {} into {}: a directory of that name is already there",
-            compact(&old_path),
-            compact(&destination_directory.path)
-        )
-        .into()),
-        Ok(_) if !overwrite => Err(anyhow!(
-            "Cannot {operation} {} into {}: it already exists there",
-            compact(&old_path),
-            compact(&destination_directory.path)
-        )
-        .into()),
-        _ => Ok((old_path, new_path)),
+    fn apply_mode(&self, path: &Path, stat: Stat) {
+        if let Err(error) = self.to.set_mode(path, stat.mode & 0o7777) {
+            warn!("Failed to set permissions on {}: {error}", path.display());
+        }
     }
 }
 
-/// What to do about `new_path`, a name another process took at a destination
-/// inside the tree being copied: the destination was free when the task
-/// started. Returns whether to replace it, having counted or recorded it
-/// otherwise.
-///
-/// The same decision the queue makes for a top-level collision, minus the one
-/// outcome a worker cannot produce: it never asks, so a collision the paste's
-/// standing answer does not settle is recorded like any other entry that could
-/// not be written.
-fn resolve_nested(
-    context: &mut CopyContext<'_>,
-    errors: &mut Vec<String>,
-    new_path: &Path,
-) -> bool {
-    // A directory is never replaced, so only the skip choices apply to one,
-    // exactly as at the top level.
-    let occupant = if new_path.symlink_metadata().is_ok_and(|it| it.is_dir()) {
-        Occupant::Directory
-    } else {
-        Occupant::Replaceable
-    };
-    let standing = context.conflicts.and_then(Conflicts::standing);
-    match step(standing, Some(occupant)) {
-        PasteStep::Run { overwrite } => overwrite,
-        PasteStep::Skip => {
-            context.skipped += 1;
-            false
-        }
-        PasteStep::Ask { .. } => {
-            errors.push(format!("{} already exists", compact(new_path)));
-            false
+/// Unwraps a `Result`, or finalizes `$active` with `"{$ctx}: {error}"` and
+/// returns `None` from the enclosing function. `$ctx` must not reference an
+/// `error` binding of its own (macro hygiene binds the error here).
+macro_rules! try_or_abort {
+    ($active:expr, $result:expr, $ctx:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(error) => {
+                $active.error(format!("{}: {error}", $ctx));
+                return None;
+            }
         }
-    }
+    };
 }
 
-/// Removes an existing non-directory destination, treating an already-absent
-/// path as success: the entry the user agreed to replace may have been removed
-/// by something else in the meantime, which is not a reason to fail the paste.
-fn remove_existing(path: &Path) -> std::io::Result<()> {
-    match fs::remove_file(path) {
-        Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
-        result => result,
-    }
+/// Lists `$dir` through `$backend` for `remove_path`: unwraps the collected
+/// entries, finalizes `$active` as cancelled and returns `None` if the drain
+/// was cancelled, or as an error and returns `None` if the read failed.
+macro_rules! list_or_abort {
+    ($active:expr, $backend:expr, $dir:expr) => {{
+        let dir = $dir;
+        match list_entries($backend, &$active, dir) {
+            Ok(Some(entries)) => entries,
+            Ok(None) => {
+                $active.cancelled();
+                return None;
+            }
+            Err(error) => {
+                $active.error(format!(
+                    "Failed to read directory {}: {error}",
+                    compact(dir)
+                ));
+                return None;
+            }
+        }
+    }};
 }
 
-/// Honors a cancel that landed while the task sat in the queue, which can be a
-/// long time: a single worker runs every operation in turn. Returns `None` when
-/// the task was finalized here and must not continue.
-fn check_cancelled(active: ActiveTask) -> Option<ActiveTask> {
-    if active.is_cancelled() {
-        active.cancelled();
-        return None;
-    }
-    Some(active)
+/// Re-stats the source at task start: selection- and yank-time metadata may
+/// be stale, and the path may have changed since (e.g. replaced by a FIFO,
+/// which a byte-wise copy would block on forever, or by a symlink, which a
+/// delete must unlink rather than follow).
+fn restat_source(operation: &str, old_path: &Path) -> Result<PathInfo, CommandResult> {
+    PathInfo::try_from(old_path)
+        .map_err(|error| anyhow!("Failed to {operation} {}: {error}", compact(old_path)).into())
 }
 
-/// Clears a destination the paste granted permission to replace. Returns `None`
-/// when the task was finalized here and must not continue.
-///
-/// Runs in the worker, once the operation is about to write. The caller queues
-/// every source of an "overwrite all" paste in one pass, so clearing there would
-/// delete every destination up front and leave a hole wherever a later task is
-/// cancelled or fails.
+/// Finishes a cross-device move once the copy stage is done, removing the
+/// source only when the destination holds every entry of it, like `mv`.
 ///
-/// The source is re-checked first for the same reason: removing the destination
-/// for a copy that then finds nothing to read would leave neither entry. It
-/// narrows the window rather than closing it.
-fn clear_destination(
+/// A failed entry keeps the whole source, including the entries that copied,
+/// and the partial destination. A skipped one does the same: it is no more at
+/// the destination than a failed one, so removing the source would delete what
+/// nothing else holds.
+fn finish_cross_device_move(
+    backend: &dyn Backend,
     active: ActiveTask,
+    outcome: CopyOutcome,
     old_path: &Path,
-    new_path: &Path,
-    overwrite: bool,
-) -> Option<ActiveTask> {
-    if !overwrite {
-        return Some(active);
+    is_directory: bool,
+) {
+    if !outcome.errors.is_empty() {
+        finalize_with_errors(active, outcome.errors);
+        return;
     }
-    if let Err(error) = old_path.symlink_metadata() {
+    if outcome.skipped > 0 {
+        let skipped = outcome.skipped;
+        let entries = if skipped == 1 { "entry" } else { "entries" };
         active.error(format!(
-            "Failed to replace {}: failed to read {}: {error}",
-            compact(new_path),
+            "Skipped {skipped} {entries}, so the original {} was kept",
             compact(old_path)
         ));
-        return None;
-    }
-    if let Err(error) = remove_existing(new_path) {
-        active.error(format!("Failed to replace {}: {error}", compact(new_path)));
-        return None;
+        return;
+    }
+    // Not cancellable: the copy is complete, so removing the source outright is
+    // the only way to finish the move. Mark it so a cancel keypress during this
+    // stage does not claim to have cancelled anything.
+    active.set_uncancellable();
+    let removed = if is_directory {
+        backend.remove_dir_all(old_path)
+    } else {
+        backend.remove(old_path)
+    };
+    match removed {
+        Ok(()) => active.done(),
+        Err(error) => active.error(format!(
+            "Copy succeeded, but failed to delete original {}: {error}",
+            compact(old_path)
+        )),
     }
-    Some(active)
 }
 
-/// Renames `old_path` onto `new_path`, replacing an existing destination when
-/// `overwrite`.
-///
-/// Prefers the kernel's atomic replace: no window with the destination missing,
-/// and a rename that fails for an unrelated reason (a vanished source, a
-/// permission error) leaves it untouched rather than destroyed for nothing. The
-/// destination is cleared only for the case the kernel refuses outright,
-/// replacing a non-directory with a directory.
-fn rename_for_move(old_path: &Path, new_path: &Path, overwrite: bool) -> std::io::Result<()> {
-    if !overwrite {
-        return rename_no_replace(old_path, new_path);
+/// Finalizes a copy, move, or recursive chmod, chown or touch the way coreutils does: success
+/// when no per-entry error was recorded, otherwise one alert summarizing them.
+/// Skipped entries are not failures and do not appear. Every error is also
+/// logged.
+fn finalize_with_errors(active: ActiveTask, errors: Vec<String>) {
+    if errors.is_empty() {
+        active.done();
+        return;
     }
-    match fs::rename(old_path, new_path) {
-        Err(error) if error.kind() == ErrorKind::NotADirectory => {
-            remove_existing(new_path)?;
-            fs::rename(old_path, new_path)
+    for error in &errors {
+        warn!("{error}");
+    }
+    let summary = if errors.len() == 1 {
+        errors.into_iter().next().expect("errors is non-empty")
+    } else {
+        format!("{} (and {} more)", errors[0], errors.len() - 1)
+    };
+    active.error(summary);
+}
+
+/// The copy functions below follow coreutils `cp -R`/`mv` semantics: an entry
+/// that cannot be copied is recorded in `errors` and the copy continues with
+/// the remaining entries. Each returns `false` only when the task was
+/// cancelled, in which case the caller must finalize with
+/// `active.cancelled()`; otherwise the caller finalizes via `finalize_with_errors`.
+/// A cancelled copy leaves the partially copied destination in place, like an
+/// interrupted `cp`; the destination is not removed.
+fn copy_directory(
+    old_path: &Path,
+    new_path: &Path,
+    active: &mut ActiveTask,
+    errors: &mut Vec<String>,
+    context: &mut CopyContext<'_>,
+) -> bool {
+    let backend = context.backend;
+    match backend.create_dir(new_path) {
+        Ok(()) => {}
+        // Something took this name while the copy was running: the destination
+        // was free when the task started, so this is another process writing
+        // into the tree. A directory is never replaced, so only a standing
+        // "skip all" settles this one, and skipping drops the subtree.
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+            if !resolve_nested(context, errors, new_path) {
+                return true;
+            }
+            if let Err(error) =
+                remove_existing(backend, new_path).and_then(|()| backend.create_dir(new_path))
+            {
+                errors.push(format!(
+                    "Failed to create directory {}: {error}",
+                    compact(new_path)
+                ));
+                return true;
+            }
+        }
+        Err(error) => {
+            // The subtree cannot be copied at all; skip it and continue with
+            // the siblings.
+            errors.push(format!(
+                "Failed to create directory {}: {error}",
+                compact(new_path)
+            ));
+            return true;
+        }
+    }
+
+    // Applied on every non-cancel exit, after the contents: a source mode
+    // without owner-write (e.g. 0o555) would otherwise stop us creating this
+    // directory's own children. Matches `cp`. Read from the source's own stat
+    // rather than its entries, so it applies even when the read below fails.
+    let preserve_times = context.preserve_times;
+    let source = backend.stat(old_path).ok();
+    let apply_source_mode = || {
+        let Some(source) = source else {
+            return;
+        };
+        // After the contents, for the same reason the mode is: writing the
+        // children is what moved the directory's own modification time.
+        if preserve_times {
+            apply_times(backend, &source, new_path);
+        }
+        apply_permissions(backend, source.mode, new_path);
+    };
+
+    let entries = match backend.list(old_path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            errors.push(format!(
+                "Failed to read directory {}: {error}",
+                compact(old_path)
+            ));
+            apply_source_mode();
+            return true;
+        }
+    };
+
+    for entry in entries {
+        if active.is_cancelled() {
+            // Like interrupted `cp`: leave the partially copied destination in
+            // place rather than removing it.
+            return false;
+        }
+
+        let src = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                errors.push(format!(
+                    "Failed to read entry in {}: {error}",
+                    compact(old_path)
+                ));
+                continue;
+            }
+        };
+        let dst = new_path.join(src.file_name().unwrap_or_default());
+        let stat = match backend.stat(&src) {
+            Ok(stat) => stat,
+            Err(error) => {
+                errors.push(format!(
+                    "Failed to read metadata for {}: {error}",
+                    compact(&src)
+                ));
+                continue;
+            }
+        };
+
+        // `stat` does not follow symlinks, so the mode carries `S_IFLNK` for a
+        // symlink and `copy_path` dispatches links, directories, and files off
+        // it alike.
+        if !copy_path(
+            &src,
+            &dst,
+            active,
+            errors,
+            context,
+            unix_mode::is_dir(stat.mode),
+            stat.mode,
+        ) {
+            return false;
         }
-        result => result,
     }
+
+    apply_source_mode();
+    true
 }
 
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
+/// Recreates the symlink at `old_path` at `new_path`, pointing at the same
+/// (possibly relative, possibly dangling) target. The target is never followed,
+/// so no bytes are transferred and no permissions are applied: `set_mode`
+/// would chmod the target rather than the link.
+fn copy_symlink(
+    old_path: &Path,
+    new_path: &Path,
+    errors: &mut Vec<String>,
+    context: &mut CopyContext<'_>,
+) {
+    let backend = context.backend;
+    let target = match backend.read_link(old_path) {
+        Ok(target) => target,
+        Err(error) => {
+            errors.push(format!(
+                "Failed to read symlink {}: {error}",
+                compact(old_path)
+            ));
+            return;
+        }
+    };
+    match backend.symlink(&target, new_path) {
+        Ok(()) => {}
+        // Raced; settled from the standing answer, or recorded.
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+            if !resolve_nested(context, errors, new_path) {
+                return;
+            }
+            if let Err(error) =
+                remove_existing(backend, new_path).and_then(|()| backend.symlink(&target, new_path))
+            {
+                errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
+            }
+        }
+        Err(error) => errors.push(format!(
+            "Failed to create symlink {}: {error}",
+            compact(new_path)
+        )),
+    }
+}
 
-    use test_case::test_case;
+/// Copies a file chunk-by-chunk, sending debounced progress updates via
+/// `active` and applying `source_mode`'s permissions on success. Failures are
+/// recorded in `errors`; returns `false` only when cancelled.
+fn copy_file(
+    old_path: &Path,
+    new_path: &Path,
+    active: &mut ActiveTask,
+    errors: &mut Vec<String>,
+    context: &mut CopyContext<'_>,
+    source_mode: u32,
+) -> bool {
+    let backend = context.backend;
+    let total_size = active.total_size();
+    let (mut old_file, mut new_file) = match open_files(backend, old_path, new_path) {
+        Ok(files) => files,
+        // A name already taken inside the tree being copied. The top-level
+        // collision was answered before the task started, so this one is a
+        // race, settled from the paste's standing answer or recorded.
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+            if !resolve_nested(context, errors, new_path) {
+                return true;
+            }
+            match remove_existing(backend, new_path)
+                .and_then(|()| open_files(backend, old_path, new_path))
+            {
+                Ok(files) => files,
+                Err(error) => {
+                    errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
+                    return true;
+                }
+            }
+        }
+        Err(error) => {
+            errors.push(format!(
+                "Failed to copy {} to {}: {error}",
+                compact(old_path),
+                compact(new_path)
+            ));
+            return true;
+        }
+    };
+
+    let mut debouncer = debounce::ProgressDebouncer::new(
+        PROGRESS_DEBOUNCE_PERCENTAGE,
+        PROGRESS_MIN_INTERVAL,
+        total_size,
+    );
+
+    loop {
+        if active.is_cancelled() {
+            // Like interrupted `cp`: leave the partially written destination
+            // file in place rather than removing it.
+            return false;
+        }
+
+        match old_file.read(context.buffer) {
+            Ok(0) => {
+                // A backend's writer is only known to have written everything
+                // once flushed.
+                let flushed = new_file.flush();
+                drop(new_file);
+                if let Err(error) = flushed {
+                    errors.push(format!("Failed to write {}: {error}", compact(new_path)));
+                    return true;
+                }
+                // Before the permissions, and once the last byte is written:
+                // writing is what moves the modification time.
+                if context.preserve_times
+                    && let Ok(source) = backend.stat(old_path)
+                {
+                    apply_times(backend, &source, new_path);
+                }
+                apply_permissions(backend, source_mode, new_path);
+                return true;
+            }
+            Ok(bytes) => match new_file.write_all(&context.buffer[..bytes]) {
+                Ok(()) => {
+                    active.increment(bytes as u64);
+                    if debouncer.should_trigger(Instant::now(), bytes as u64) {
+                        active.send_progress();
+                    }
+                }
+                Err(error) => {
+                    errors.push(format!("Failed to write {}: {error}", compact(new_path)));
+                    return true;
+                }
+            },
+            Err(error) => {
+                errors.push(format!("Failed to read {}: {error}", compact(old_path)));
+                return true;
+            }
+        }
+    }
+}
+
+/// Copies a directory, file, symlink, or special file, dispatching on
+/// `is_directory` and `source_mode`. Per-entry failures accumulate in
+/// `errors`; returns `false` only when the task was cancelled.
+fn copy_path(
+    old_path: &Path,
+    new_path: &Path,
+    active: &mut ActiveTask,
+    errors: &mut Vec<String>,
+    context: &mut CopyContext<'_>,
+    is_directory: bool,
+    source_mode: u32,
+) -> bool {
+    // Check symlink first: `source_mode` comes from `symlink_metadata`, so a
+    // symlink (even one pointing at a directory) is recreated as a link rather
+    // than followed. `is_directory` is already false for any symlink.
+    if unix_mode::is_symlink(source_mode) {
+        copy_symlink(old_path, new_path, errors, context);
+        true
+    } else if is_directory {
+        copy_directory(old_path, new_path, active, errors, context)
+    } else if unix_mode::is_file(source_mode) {
+        copy_file(old_path, new_path, active, errors, context, source_mode)
+    } else {
+        copy_special(old_path, new_path, errors, context, source_mode);
+        true
+    }
+}
+
+/// Recreates a special file (FIFO, socket, or device node) as a fresh node at
+/// `new_path` with the source's permission bits, like `cp -R` does. No bytes
+/// are transferred: reading a FIFO would block until a writer appears. FIFOs
+/// and sockets need no privileges; device nodes require root, so as a normal
+/// user they record a "not permitted" error here, exactly as `cp` reports.
+///
+/// Straight to the local disk rather than through the context's backend: no
+/// backend makes nodes, and a copy between hosts skips them.
+fn copy_special(
+    old_path: &Path,
+    new_path: &Path,
+    errors: &mut Vec<String>,
+    context: &mut CopyContext<'_>,
+    source_mode: u32,
+) {
+    use nix::sys::stat::{Mode, SFlag, mknod};
+
+    let kind = if unix_mode::is_fifo(source_mode) {
+        SFlag::S_IFIFO
+    } else if unix_mode::is_socket(source_mode) {
+        SFlag::S_IFSOCK
+    } else if unix_mode::is_block_device(source_mode) {
+        SFlag::S_IFBLK
+    } else if unix_mode::is_char_device(source_mode) {
+        SFlag::S_IFCHR
+    } else {
+        errors.push(format!(
+            "Cannot copy {}: unsupported file type",
+            compact(old_path)
+        ));
+        return;
+    };
+
+    // Device nodes need the source's device numbers; zero for the rest.
+    let rdev = if matches!(kind, SFlag::S_IFBLK | SFlag::S_IFCHR) {
+        use std::os::unix::fs::MetadataExt;
+        match fs::symlink_metadata(old_path) {
+            Ok(metadata) => metadata.rdev() as nix::libc::dev_t,
+            Err(error) => {
+                errors.push(format!(
+                    "Failed to read metadata for {}: {error}",
+                    compact(old_path)
+                ));
+                return;
+            }
+        }
+    } else {
+        0
+    };
+
+    // `mode_t` is u32 on Linux but u16 on macOS, so cast rather than assume.
+    let permissions = Mode::from_bits_truncate(source_mode as nix::libc::mode_t);
+    match mknod(new_path, kind, permissions, rdev) {
+        Ok(()) => {}
+        // Raced; settled from the standing answer, or recorded.
+        Err(nix::errno::Errno::EEXIST) => {
+            if !resolve_nested(context, errors, new_path) {
+                return;
+            }
+            if let Err(error) = remove_existing(context.backend, new_path)
+                .map_err(|error| error.to_string())
+                .and_then(|()| {
+                    mknod(new_path, kind, permissions, rdev).map_err(|error| error.to_string())
+                })
+            {
+                errors.push(format!("Failed to replace {}: {error}", compact(new_path)));
+            }
+        }
+        Err(error) => errors.push(format!(
+            "Failed to create special file {}: {error}",
+            compact(new_path)
+        )),
+    }
+}
+
+/// Removes a file or directory tree, checking for cancellation between
+/// entries. Cancelling mid-delete leaves whatever has not been removed yet.
+/// Iterative (explicit stack), so directory depth cannot overflow the thread
+/// stack.
+///
+/// Returns `Some(active)` on success, leaving finalization to the caller.
+/// Returns `None` when cancelled or on error, in which case the task has
+/// already been finalized via `active.cancelled()` / `active.error()`.
+fn remove_path(
+    backend: &dyn Backend,
+    path: &Path,
+    is_directory: bool,
+    mut active: ActiveTask,
+) -> Option<ActiveTask> {
+    if active.is_cancelled() {
+        active.cancelled();
+        return None;
+    }
+    if !is_directory {
+        // Symlinks are removed as links (never followed): `is_directory` comes
+        // from `symlink_metadata`, so a link to a directory takes this branch.
+        try_or_abort!(
+            active,
+            backend.remove(path),
+            format!("Failed to delete {}", compact(path))
+        );
+        active.increment(1);
+        return Some(active);
+    }
+
+    // Post-order walk, each directory drained into a Vec before anything is
+    // deleted: the fd closes at once (depth is not bounded by the fd limit) and
+    // nothing is unlinked while a ReadDir stream is open, which on some
+    // filesystems (NFS) can skip entries. The trade is peak memory, which is
+    // proportional to the widest root-to-leaf path rather than O(1) per level.
+    // A directory is removed once its entries are done, so a cancelled or
+    // failed delete leaves each subtree either fully removed or intact.
+    let root = path.to_path_buf();
+    let entries = list_or_abort!(active, backend, &root);
+    let mut stack = vec![(root, entries.into_iter())];
+    // One unit of progress per entry removed, against the total counted by
+    // `dir_total_entries` before the walk. Debounced so a wide tree does not
+    // put one progress command per entry ahead of terminal input.
+    let mut debouncer = debounce::ProgressDebouncer::new(
+        PROGRESS_DEBOUNCE_PERCENTAGE,
+        PROGRESS_MIN_INTERVAL,
+        active.total_size(),
+    );
+    while let Some(top) = stack.last_mut() {
+        if active.is_cancelled() {
+            active.cancelled();
+            return None;
+        }
+        match top.1.next() {
+            // This directory's entries are done; remove it.
+            None => {
+                let (directory, _) = stack.pop().expect("stack is non-empty");
+                try_or_abort!(
+                    active,
+                    backend.remove_dir(&directory),
+                    format!("Failed to delete {}", compact(&directory))
+                );
+            }
+            Some((entry_path, true)) => {
+                let entries = list_or_abort!(active, backend, &entry_path);
+                stack.push((entry_path, entries.into_iter()));
+                // Descending is not a removal, so it advances no progress.
+                continue;
+            }
+            Some((entry_path, false)) => {
+                try_or_abort!(
+                    active,
+                    backend.remove(&entry_path),
+                    format!("Failed to delete {}", compact(&entry_path))
+                );
+            }
+        }
+        active.increment(1);
+        if debouncer.should_trigger(Instant::now(), 1) {
+            active.send_progress();
+        }
+    }
+    Some(active)
+}
+
+/// Collects `(path, is_directory)` for each entry of `directory`, the whole
+/// listing before the caller deletes anything. `stat` does not follow
+/// symlinks, so a link to a directory reports `false` and is unlinked rather
+/// than descended into. Checked for cancellation once per entry, so a huge
+/// directory does not delay a cancel; returns `Ok(None)` when cancelled.
+fn list_entries(
+    backend: &dyn Backend,
+    active: &ActiveTask,
+    directory: &Path,
+) -> std::io::Result<Option<Vec<(PathBuf, bool)>>> {
+    let mut entries = Vec::new();
+    for entry in backend.list(directory)? {
+        if active.is_cancelled() {
+            return Ok(None);
+        }
+        let entry = entry?;
+        let is_directory = unix_mode::is_dir(backend.stat(&entry)?.mode);
+        entries.push((entry, is_directory));
+    }
+    Ok(Some(entries))
+}
+
+/// Copies the access and modification times `source` records onto `target`.
+/// Best effort: a filesystem that cannot record them is not a reason to fail
+/// the operation.
+fn apply_times(backend: &dyn Backend, source: &Stat, target: &Path) {
+    if let Err(error) = backend.set_times(target, source.accessed, source.modified) {
+        warn!("Failed to set times on {}: {error}", compact(target));
+    }
+}
+
+fn apply_permissions(backend: &dyn Backend, mode: u32, path: &Path) {
+    if let Err(e) = backend.set_mode(path, mode) {
+        warn!("Failed to set permissions on {}: {e}", path.display());
+    }
+}
+
+type OpenFiles = (Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+fn open_files(backend: &dyn Backend, source: &Path, target: &Path) -> std::io::Result<OpenFiles> {
+    let source = backend.read(source)?;
+    // `create` fails atomically if the target exists (`O_EXCL|O_CREAT` on the
+    // disk), closing the same window as `rename_no_replace`; without it a file
+    // that appeared since `validate_paths` ran would be truncated.
+    let target = backend.create(target)?;
+    Ok((source, target))
+}
+
+/// An absolute, display-friendly rendering of `path` for the operations
+/// notice. Lexical only (no filesystem access), so it works for destination
+/// paths that do not exist yet; falls back to the original path if it cannot
+/// be absolutized.
+fn display_path(path: &Path) -> String {
+    std::path::absolute(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// The path with symlinks and `..` components resolved. Falls back to a lexical
+/// absolutize-and-normalize when the path cannot be resolved, which is the
+/// normal case for a destination that does not exist yet.
+fn resolve(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        lexical_normalize(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+    })
+}
+
+/// Collapses `.` and `..` components purely lexically (no filesystem access,
+/// so it works for destinations that do not exist yet). `..` pops the previous
+/// component; at the root it is a no-op.
+fn lexical_normalize(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+fn validate_paths(
+    source: &PathInfo,
+    destination_directory: &PathInfo,
+    operation: &str,
+    overwrite: bool,
+    protected: &ProtectedPaths,
+) -> Result<(PathBuf, PathBuf), CommandResult> {
+    let old_path = source.path.clone();
+    // Join the source's raw `OsStr` file name rather than its display name:
+    // the display name is lossy UTF-8, which would silently mangle a non-UTF8
+    // name at the destination.
+    let Some(file_name) = source.path.file_name() else {
+        return Err(anyhow!(
+            "Cannot {operation} {}: path has no file name",
+            compact(&old_path)
+        )
+        .into());
+    };
+    let new_path = destination_directory.path.join(file_name);
+
+    // Compare resolved paths so that neither a parent-dir segment (e.g.
+    // `/a/c/../b`) nor a destination directory that is a symlink to the
+    // source's own directory can disguise one path as another. The
+    // destination's own name does not exist yet, so its parent is resolved and
+    // the name rejoined.
+    let abs_old = resolve(&old_path);
+    let abs_new = resolve(&destination_directory.path).join(file_name);
+
+    // The two paths name the same entry. This has to be caught here whatever
+    // the source's type: a granted overwrite clears the destination before
+    // copying, so letting an aliased path through would unlink the source and
+    // leave nothing to copy.
+    if abs_old == abs_new {
+        // Equal resolved paths mean the destination directory is the source's
+        // own, so the message names the entry once.
+        return Err(anyhow!(
+            "Cannot {operation} {} into its own directory",
+            compact(&old_path)
+        )
+        .into());
+    }
+
+    // Without this a copy creates the destination under the source and recurses
+    // into it forever, filling the disk. Only a real directory can: `copy_path`
+    // recreates a symlink as a link and copies a file as bytes, so neither
+    // descends into what it is creating. Restricting the check to directories
+    // also keeps it from refusing a symlink copied into the directory it points
+    // at.
+    if source.is_directory() && abs_new.starts_with(&abs_old) {
+        return Err(anyhow!(
+            "Cannot {operation} {} into its own subdirectory {}",
+            compact(&old_path),
+            compact(&new_path)
+        )
+        .into());
+    }
+
+    // A move takes the source away from where it was, so it is refused like a
+    // delete would be. A copy leaves the source in place.
+    if operation == "move" {
+        protected
+            .check(operation, &old_path, source.is_directory())
+            .map_err(CommandResult::from)?;
+    }
+
+    // Refuse to replace an existing destination unless the paste asked for it:
+    // `File::create`/`fs::rename` would otherwise do so silently. An existing
+    // directory is never replaced whatever was asked, because removing it would
+    // take its contents with it and merging into it is not supported.
+    match new_path.symlink_metadata() {
+        // Both messages name the destination directory rather than the full
+        // destination path: it differs from the source only in its directory,
+        // so repeating the file name says nothing.
+        Ok(metadata) if metadata.is_dir() => Err(anyhow!(
+            "Cannot {operation} {} into {}: a directory of that name is already there",
+            compact(&old_path),
+            compact(&destination_directory.path)
+        )
+        .into()),
+        Ok(_) if !overwrite => Err(anyhow!(
+            "Cannot {operation} {} into {}: it already exists there",
+            compact(&old_path),
+            compact(&destination_directory.path)
+        )
+        .into()),
+        // Only a non-directory is ever replaced, so nothing below it is at
+        // stake.
+        Ok(_) => match protected.check("overwrite", &new_path, false) {
+            Ok(()) => Ok((old_path, new_path)),
+            Err(error) => Err(error.into()),
+        },
+        Err(_) => Ok((old_path, new_path)),
+    }
+}
+
+/// What to do about `new_path`, a name another process took at a destination
+/// inside the tree being copied: the destination was free when the task
+/// started. Returns whether to replace it, having counted or recorded it
+/// otherwise.
+///
+/// The same decision the queue makes for a top-level collision, minus the one
+/// outcome a worker cannot produce: it never asks, so a collision the paste's
+/// standing answer does not settle is recorded like any other entry that could
+/// not be written.
+fn resolve_nested(
+    context: &mut CopyContext<'_>,
+    errors: &mut Vec<String>,
+    new_path: &Path,
+) -> bool {
+    // A directory is never replaced, so only the skip choices apply to one,
+    // exactly as at the top level.
+    let occupant = if context
+        .backend
+        .stat(new_path)
+        .is_ok_and(|stat| unix_mode::is_dir(stat.mode))
+    {
+        Occupant::Directory
+    } else {
+        Occupant::Replaceable
+    };
+    let standing = context.conflicts.and_then(Conflicts::standing);
+    match step(standing, Some(occupant)) {
+        PasteStep::Run { overwrite } => overwrite,
+        PasteStep::Skip => {
+            context.skipped += 1;
+            false
+        }
+        PasteStep::Ask { .. } => {
+            errors.push(format!("{} already exists", compact(new_path)));
+            false
+        }
+    }
+}
+
+/// Removes an existing non-directory destination, treating an already-absent
+/// path as success: the entry the user agreed to replace may have been removed
+/// by something else in the meantime, which is not a reason to fail the paste.
+/// A directory is refused with `IsADirectory`, as `unlink` refuses it.
+fn remove_existing(backend: &dyn Backend, path: &Path) -> std::io::Result<()> {
+    match backend.stat(path) {
+        Ok(stat) if unix_mode::is_dir(stat.mode) => Err(std::io::Error::new(
+            ErrorKind::IsADirectory,
+            format!("{} is a directory", compact(path)),
+        )),
+        Ok(_) => match backend.remove(path) {
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Honors a cancel that landed while the task sat in the queue, which can be a
+/// long time: a single worker runs every operation in turn. Returns `None` when
+/// the task was finalized here and must not continue.
+fn check_cancelled(active: ActiveTask) -> Option<ActiveTask> {
+    if active.is_cancelled() {
+        active.cancelled();
+        return None;
+    }
+    Some(active)
+}
+
+/// Clears a destination the paste granted permission to replace. Returns `None`
+/// when the task was finalized here and must not continue.
+///
+/// Runs in the worker, once the operation is about to write. The caller queues
+/// every source of an "overwrite all" paste in one pass, so clearing there would
+/// delete every destination up front and leave a hole wherever a later task is
+/// cancelled or fails.
+///
+/// The source is re-checked first for the same reason: removing the destination
+/// for a copy that then finds nothing to read would leave neither entry. It
+/// narrows the window rather than closing it.
+fn clear_destination(
+    backend: &dyn Backend,
+    active: ActiveTask,
+    old_path: &Path,
+    new_path: &Path,
+    overwrite: bool,
+) -> Option<ActiveTask> {
+    if !overwrite {
+        return Some(active);
+    }
+    if let Err(error) = backend.stat(old_path) {
+        active.error(format!(
+            "Failed to replace {}: failed to read {}: {error}",
+            compact(new_path),
+            compact(old_path)
+        ));
+        return None;
+    }
+    if let Err(error) = remove_existing(backend, new_path) {
+        active.error(format!("Failed to replace {}: {error}", compact(new_path)));
+        return None;
+    }
+    Some(active)
+}
+
+/// Renames `old_path` onto `new_path`, replacing an existing destination when
+/// `overwrite`.
+///
+/// Prefers the kernel's atomic replace: no window with the destination missing,
+/// and a rename that fails for an unrelated reason (a vanished source, a
+/// permission error) leaves it untouched rather than destroyed for nothing. The
+/// destination is cleared only for the case the kernel refuses outright,
+/// replacing a non-directory with a directory.
+fn rename_for_move(
+    backend: &dyn Backend,
+    old_path: &Path,
+    new_path: &Path,
+    overwrite: bool,
+) -> std::io::Result<()> {
+    if !overwrite {
+        // Fails with `AlreadyExists` in the same step as the move: the
+        // destination was checked on the UI thread, and one may have appeared
+        // since.
+        return backend.rename_no_replace(old_path, new_path);
+    }
+    match backend.rename(old_path, new_path) {
+        Err(error) if error.kind() == ErrorKind::NotADirectory => {
+            remove_existing(backend, new_path)?;
+            backend.rename(old_path, new_path)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, time::Duration};
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{
+        command::{ConflictChoice, progress::Progress},
+        file_system::backend::Memory,
+        test_support::TempDir,
+    };
+
+    // (len, min, max) -> expected buffer size. With BUFFER_SIZE_DIVISOR == 20.
+    #[test_case(0, 10, 100 => 0 ; "zero length")]
+    #[test_case(5, 10, 100 => 5 ; "below min uses len")]
+    #[test_case(10, 10, 100 => 10 ; "equal to min uses len")]
+    #[test_case(2000, 10, 100 => 100 ; "at max*divisor uses max")]
+    #[test_case(5000, 10, 100 => 100 ; "above max*divisor uses max")]
+    #[test_case(400, 10, 100 => 20 ; "mid range uses len/divisor")]
+    #[test_case(30, 10, 100 => 10 ; "mid range floored at min")]
+    fn buffer_bytes_is_correct(len: u64, min: u64, max: u64) -> usize {
+        buffer_bytes(len, min, max)
+    }
+
+    #[test]
+    fn copy_buffer_bytes_floors_at_min_copy_buffer() {
+        // A zero scanned total would map to a 0-length buffer; the floor keeps
+        // it at MIN_COPY_BUFFER_BYTES so a file read into it makes progress.
+        assert_eq!(
+            MIN_COPY_BUFFER_BYTES,
+            copy_buffer_bytes(0, 64_000, 64_000_000)
+        );
+    }
+
+    #[test]
+    fn copy_buffer_bytes_never_exceeds_max() {
+        // The floor must not override a user-configured buffer_max_bytes set
+        // below MIN_COPY_BUFFER_BYTES.
+        let max = 4_000;
+        assert!(u64::try_from(MIN_COPY_BUFFER_BYTES).unwrap() > max);
+        assert_eq!(
+            usize::try_from(max).unwrap(),
+            copy_buffer_bytes(0, max, max)
+        );
+    }
+
+    /// A source built from `/`, so it reports as a directory: the subdirectory
+    /// check below only applies to one, and every test using this helper is
+    /// about a path rule rather than about the source's type.
+    fn path_info(path: &str, basename: &str) -> PathInfo {
+        let mut info = PathInfo::try_from(Path::new("/")).unwrap();
+        info.path = PathBuf::from(path);
+        info.display_name = basename.to_string();
+        info
+    }
+
+    /// The message a refusal carried. `validate_paths` has several separate
+    /// reasons to refuse, so `is_err` alone cannot tell whether a fixture
+    /// reached the rule it was built for.
+    fn rejection(result: Result<(PathBuf, PathBuf), CommandResult>) -> String {
+        let refusal = result.expect_err("the paths should have been refused");
+        match Command::try_from(refusal) {
+            Ok(Command::AlertError(message)) => message,
+            other => panic!("expected an AlertError, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_paths_rejects_identical_source_and_destination() {
+        let src = path_info("/a/b", "b");
+        let dest = path_info("/a", "a");
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.ends_with("into its own directory"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_rejects_destination_inside_source() {
+        let src = path_info("/a/b", "b");
+        let dest = path_info("/a/b/c", "c");
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.contains("into its own subdirectory"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_rejects_destination_inside_source_via_parent_dir() {
+        // "/a/c/../b/d" resolves to "/a/b/d", inside the source, which a raw
+        // component-wise prefix check on the path as written would not catch.
+        let src = path_info("/a/b", "b");
+        let dest = path_info("/a/c/../b/d", "d");
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.contains("into its own subdirectory"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_rejects_a_destination_that_symlinks_into_the_source() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("src");
+        std::fs::create_dir_all(src.join("inner")).unwrap();
+        // A lexical prefix check cannot see that `link` resolves inside `src`.
+        // Copying through it would create the destination under the source and
+        // then recurse into what it is creating, filling the disk.
+        let link = fx.join("link");
+        std::os::unix::fs::symlink(src.join("inner"), &link).unwrap();
+
+        let source = path_info(src.to_str().unwrap(), "src");
+        let dest = path_info(link.to_str().unwrap(), "link");
+        let message = rejection(validate_paths(
+            &source,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.contains("into its own subdirectory"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_allows_a_symlink_copied_into_the_directory_it_points_at() {
+        let fx = TempDir::new("tasks");
+        let target = fx.join("target");
+        std::fs::create_dir_all(&target).unwrap();
+        let link = fx.join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        // The source resolves to the destination, but copying a symlink
+        // recreates a link rather than descending into anything, so there is
+        // no subtree to recurse into and nothing to reject.
+        let source = PathInfo::try_from(link.as_path()).unwrap();
+        let dest = PathInfo::try_from(target.as_path()).unwrap();
+        assert!(validate_paths(&source, &dest, "copy", false, &ProtectedPaths::default()).is_ok());
+    }
+
+    #[test_case(true  ; "overwrite granted")]
+    #[test_case(false ; "overwrite not granted")]
+    fn validate_paths_rejects_a_destination_that_aliases_the_source(overwrite: bool) {
+        let fx = TempDir::new("tasks");
+        let real = fx.join("real");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("f.txt"), b"precious").unwrap();
+        // A second path to the same directory, which the clipboard can easily
+        // carry: it holds whatever absolute path the other window was showing.
+        std::os::unix::fs::symlink(&real, fx.join("link")).unwrap();
+
+        let src = PathInfo::try_from(real.join("f.txt").as_path()).unwrap();
+        let dest = PathInfo::try_from(fx.join("link").as_path()).unwrap();
+
+        // The two paths name one file. A granted overwrite clears the
+        // destination before copying, so letting this through would unlink the
+        // source and leave nothing to copy from.
+        assert!(
+            validate_paths(&src, &dest, "copy", overwrite, &ProtectedPaths::default()).is_err()
+        );
+        assert!(real.join("f.txt").exists());
+    }
+
+    #[test]
+    fn validate_paths_allows_sibling_destination() {
+        let src = path_info("/a/b", "b");
+        let dest = path_info("/x", "x");
+        let (old_path, new_path) =
+            validate_paths(&src, &dest, "copy", false, &ProtectedPaths::default())
+                .expect("should be allowed");
+        assert_eq!(PathBuf::from("/a/b"), old_path);
+        assert_eq!(PathBuf::from("/x/b"), new_path);
+    }
+
+    #[test]
+    fn validate_paths_allows_destination_with_shared_prefix_but_different_component() {
+        // "/a/bb" must not be treated as inside "/a/b".
+        let src = path_info("/a/b", "b");
+        let dest = path_info("/a/bb", "bb");
+        assert!(validate_paths(&src, &dest, "copy", false, &ProtectedPaths::default()).is_ok());
+    }
+
+    #[test]
+    fn validate_paths_preserves_non_utf8_source_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        // The display name is lossy UTF-8; the destination must be built from
+        // the raw file name so the bytes survive.
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        let mut src = path_info("/a/placeholder", "placeholder");
+        src.path = PathBuf::from("/a").join(name);
+        let dest = path_info("/x", "x");
+        let (_, new_path) = validate_paths(&src, &dest, "copy", false, &ProtectedPaths::default())
+            .expect("should be allowed");
+        assert_eq!(PathBuf::from("/x").join(name), new_path);
+    }
+
+    #[test]
+    fn validate_paths_rejects_source_without_file_name() {
+        let src = path_info("/", "");
+        let dest = path_info("/x", "x");
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.ends_with("path has no file name"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_rejects_existing_destination() {
+        let fx = TempDir::new("tasks");
+        std::fs::write(fx.join("existing.txt"), b"x").unwrap();
+        let src = path_info("/elsewhere/existing.txt", "existing.txt");
+        let dest = path_info(fx.path().to_str().unwrap(), "dir");
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.ends_with("it already exists there"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_rejects_a_destination_directory_of_the_same_name() {
+        let fx = TempDir::new("tasks");
+        std::fs::create_dir_all(fx.join("existing.txt")).unwrap();
+        let src = path_info("/elsewhere/existing.txt", "existing.txt");
+        let dest = path_info(fx.path().to_str().unwrap(), "dir");
+
+        // A directory is refused whatever the answer: removing it would take
+        // its contents with it, and merging is not supported.
+        for overwrite in [false, true] {
+            let message = rejection(validate_paths(
+                &src,
+                &dest,
+                "copy",
+                overwrite,
+                &ProtectedPaths::default(),
+            ));
+            assert!(
+                message.ends_with("a directory of that name is already there"),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_paths_rejects_existing_broken_symlink_destination() {
+        let fx = TempDir::new("tasks");
+        let link = fx.join("existing.txt");
+        std::os::unix::fs::symlink(fx.join("missing"), &link).unwrap();
+        let src = path_info("/elsewhere/existing.txt", "existing.txt");
+        let dest = path_info(fx.path().to_str().unwrap(), "dir");
+
+        // `symlink_metadata` rather than `exists`, which follows the link and
+        // reports a dangling one as absent, silently overwriting it.
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            false,
+            &ProtectedPaths::default(),
+        ));
+        assert!(message.ends_with("it already exists there"), "{message}");
+    }
+
+    /// Protects `names` inside `fx`. The directory is canonicalized, as the
+    /// check resolves an entry's parents, so the pattern has to name the
+    /// directory the way resolving it does.
+    fn protecting(fx: &TempDir, names: &[&str]) -> ProtectedPaths {
+        let root = fx.path().canonicalize().unwrap();
+        let globs: Vec<String> = names
+            .iter()
+            .map(|name| root.join(name).to_string_lossy().into_owned())
+            .collect();
+        ProtectedPaths::new(&globs, None).unwrap()
+    }
+
+    #[test]
+    fn validate_paths_refuses_to_overwrite_a_protected_destination() {
+        let fx = TempDir::new("tasks");
+        std::fs::write(fx.join("keep.txt"), b"x").unwrap();
+        let src = path_info("/elsewhere/keep.txt", "keep.txt");
+        let dest = path_info(fx.path().to_str().unwrap(), "dir");
+
+        let message = rejection(validate_paths(
+            &src,
+            &dest,
+            "copy",
+            true,
+            &protecting(&fx, &["*.txt"]),
+        ));
+        assert!(message.contains("protected"), "{message}");
+    }
+
+    #[test]
+    fn validate_paths_refuses_to_move_a_protected_source_but_copies_it() {
+        let fx = TempDir::new("tasks");
+        std::fs::create_dir_all(fx.join("src/.ssh")).unwrap();
+        std::fs::create_dir_all(fx.join("dest")).unwrap();
+        let src = PathInfo::try_from(fx.join("src").as_path()).unwrap();
+        let dest = PathInfo::try_from(fx.join("dest").as_path()).unwrap();
+        let protected = protecting(&fx, &["src/.ssh"]);
+
+        // Moving `src` takes the protected entry inside it along; copying it
+        // leaves both where they were.
+        let message = rejection(validate_paths(&src, &dest, "move", false, &protected));
+        assert!(message.contains("protected"), "{message}");
+        assert!(validate_paths(&src, &dest, "copy", false, &protected).is_ok());
+    }
+
+    #[test]
+    fn a_protected_entry_is_refused_before_a_delete_is_queued() {
+        let fx = TempDir::new("tasks");
+        std::fs::create_dir_all(fx.join("project/.git")).unwrap();
+        let project = PathInfo::try_from(fx.join("project").as_path()).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let result = run_delete_task(tx, &project, &protecting(&fx, &["project/.git"]));
+
+        // No task was registered, so nothing reported progress, and the alert
+        // is all the user sees.
+        assert!(result.cancel_info.is_none());
+        assert!(rx.try_recv().is_err());
+        assert!(matches!(
+            Command::try_from(result.command_result),
+            Ok(Command::AlertError(_))
+        ));
+        assert!(fx.join("project/.git").exists());
+    }
+
+    /// A copy context for a test: no paste behind it, so a nested collision
+    /// records an error rather than asking.
+    fn context(preserve_times: bool, buffer: &mut [u8]) -> CopyContext<'_> {
+        CopyContext {
+            backend: &Local,
+            buffer,
+            conflicts: None,
+            preserve_times,
+            skipped: 0,
+        }
+    }
+
+    fn copy_task(tx: std::sync::mpsc::Sender<Command>) -> ActiveTask {
+        let (active, _, _) = ActiveTask::new(
+            tx,
+            TaskKind::Copy(Transfer {
+                source: String::new(),
+                destination: String::new(),
+            }),
+            1,
+        );
+        active
+    }
+
+    // Linux only: recreating a socket goes through mknod, which macOS refuses
+    // to an unprivileged process with EPERM.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn copy_path_recreates_socket() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&src).unwrap();
+        let dst = fx.join("sock_copy");
+        let mode = std::fs::symlink_metadata(&src)
+            .unwrap()
+            .permissions()
+            .mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(false, &mut [0u8; 64]),
+            false,
+            mode,
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        let dst_mode = std::fs::symlink_metadata(&dst)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert!(unix_mode::is_socket(dst_mode));
+        assert!(src.exists());
+        active.done();
+    }
+
+    #[test]
+    fn copy_path_recreates_fifo() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("fifo");
+        nix::unistd::mkfifo(&src, nix::sys::stat::Mode::from_bits_truncate(0o644)).unwrap();
+        let dst = fx.join("fifo_copy");
+        let mode = std::fs::symlink_metadata(&src)
+            .unwrap()
+            .permissions()
+            .mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(false, &mut [0u8; 64]),
+            false,
+            mode,
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        let dst_mode = std::fs::symlink_metadata(&dst)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert!(unix_mode::is_fifo(dst_mode));
+        assert_eq!(0o644, dst_mode & 0o7777);
+        active.done();
+    }
+
+    #[test]
+    fn copy_path_continues_past_unreadable_entries() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.txt"), b"a").unwrap();
+        std::fs::write(src.join("bad"), b"x").unwrap();
+        std::fs::write(src.join("c.txt"), b"c").unwrap();
+        let mut permissions = std::fs::metadata(src.join("bad")).unwrap().permissions();
+        permissions.set_mode(0o000);
+        std::fs::set_permissions(src.join("bad"), permissions).unwrap();
+        // A chmod-000 file is still readable by root (CAP_DAC_OVERRIDE) and on
+        // mounts that ignore permissions. Probe what this filesystem actually
+        // does rather than inspecting the euid, so the assertions below match
+        // the environment instead of being skipped in it.
+        let is_unreadable = std::fs::File::open(src.join("bad")).is_err();
+
+        let dst = fx.join("dst");
+        let mode = std::fs::symlink_metadata(&src)
+            .unwrap()
+            .permissions()
+            .mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
 
-    use super::*;
-    use crate::{
-        command::{ConflictChoice, progress::Progress},
-        test_support::TempDir,
-    };
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(false, &mut [0u8; 64]),
+            true,
+            mode,
+        ));
 
-    // (len, min, max) -> expected buffer size. With BUFFER_SIZE_DIVISOR == 20.
-    #[test_case(0, 10, 100 => 0 ; "zero length")]
-    #[test_case(5, 10, 100 => 5 ; "below min uses len")]
-    #[test_case(10, 10, 100 => 10 ; "equal to min uses len")]
-    #[test_case(2000, 10, 100 => 100 ; "at max*divisor uses max")]
-    #[test_case(5000, 10, 100 => 100 ; "above max*divisor uses max")]
-    #[test_case(400, 10, 100 => 20 ; "mid range uses len/divisor")]
-    #[test_case(30, 10, 100 => 10 ; "mid range floored at min")]
-    fn buffer_bytes_is_correct(len: u64, min: u64, max: u64) -> usize {
-        buffer_bytes(len, min, max)
+        if is_unreadable {
+            // Like cp -R: the unreadable entry is recorded, not fatal.
+            assert_eq!(1, errors.len(), "expected one error: {errors:?}");
+            assert!(errors[0].contains("bad"), "unexpected error: {}", errors[0]);
+            assert!(!dst.join("bad").exists());
+        } else {
+            // Nothing was unreadable here, so this is a plain full copy.
+            assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+            assert!(dst.join("bad").exists());
+        }
+        // The walk must reach the entries on both sides of "bad" either way: a
+        // failed entry must not abort the siblings.
+        assert!(dst.join("a.txt").exists());
+        assert!(dst.join("c.txt").exists());
+        active.done();
     }
 
     #[test]
-    fn copy_buffer_bytes_floors_at_min_copy_buffer() {
-        // A zero scanned total would map to a 0-length buffer; the floor keeps
-        // it at MIN_COPY_BUFFER_BYTES so a file read into it makes progress.
+    fn copy_path_reuses_one_buffer_without_leaking_bytes_between_files() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a_long.txt"), b"aaaaaaaaaaaaaaaa").unwrap();
+        std::fs::write(src.join("b_short.txt"), b"b").unwrap();
+        let dst = fx.join("dst");
+        let mode = std::fs::symlink_metadata(&src)
+            .unwrap()
+            .permissions()
+            .mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+
+        // One buffer serves the whole tree, so a short file copied after a
+        // longer one must not pick up the previous file's trailing bytes.
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(false, &mut [0u8; 64]),
+            true,
+            mode,
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
+
         assert_eq!(
-            MIN_COPY_BUFFER_BYTES,
-            copy_buffer_bytes(0, 64_000, 64_000_000)
+            b"aaaaaaaaaaaaaaaa".to_vec(),
+            std::fs::read(dst.join("a_long.txt")).unwrap()
         );
-    }
-
-    #[test]
-    fn copy_buffer_bytes_never_exceeds_max() {
-        // The floor must not override a user-configured buffer_max_bytes set
-        // below MIN_COPY_BUFFER_BYTES.
-        let max = 4_000;
-        assert!(u64::try_from(MIN_COPY_BUFFER_BYTES).unwrap() > max);
         assert_eq!(
-            usize::try_from(max).unwrap(),
-            copy_buffer_bytes(0, max, max)
+            b"b".to_vec(),
+            std::fs::read(dst.join("b_short.txt")).unwrap()
         );
     }
 
-    /// A source built from `/`, so it reports as a directory: the subdirectory
-    /// check below only applies to one, and every test using this helper is
-    /// about a path rule rather than about the source's type.
-    fn path_info(path: &str, basename: &str) -> PathInfo {
-        let mut info = PathInfo::try_from(Path::new("/")).unwrap();
-        info.path = PathBuf::from(path);
-        info.display_name = basename.to_string();
-        info
-    }
-
-    /// The message a refusal carried. `validate_paths` has five separate
-    /// reasons to refuse, so `is_err` alone cannot tell whether a fixture
-    /// reached the rule it was built for.
-    fn rejection(result: Result<(PathBuf, PathBuf), CommandResult>) -> String {
-        let refusal = result.expect_err("the paths should have been refused");
-        match Command::try_from(refusal) {
-            Ok(Command::AlertError(message)) => message,
-            other => panic!("expected an AlertError, got {other:?}"),
+    /// The modification times of `path` and everything under it, by relative
+    /// name, so a tree can be compared against its copy.
+    fn modified_times(root: &Path) -> Vec<(PathBuf, std::time::SystemTime)> {
+        let mut times = vec![(
+            PathBuf::from("."),
+            fs::metadata(root).unwrap().modified().unwrap(),
+        )];
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir).unwrap().flatten() {
+                let path = entry.path();
+                let metadata = fs::metadata(&path).unwrap();
+                if metadata.is_dir() {
+                    stack.push(path.clone());
+                }
+                times.push((
+                    path.strip_prefix(root).unwrap().to_path_buf(),
+                    metadata.modified().unwrap(),
+                ));
+            }
         }
+        times.sort();
+        times
     }
 
     #[test]
-    fn validate_paths_rejects_identical_source_and_destination() {
-        let src = path_info("/a/b", "b");
-        let dest = path_info("/a", "a");
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.ends_with("into its own directory"), "{message}");
+    fn a_preserving_copy_keeps_the_modification_times_of_the_whole_tree() {
+        let fx = TempDir::new("tasks_times");
+        let src = fx.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), b"a").unwrap();
+        fs::write(src.join("sub").join("b.txt"), b"b").unwrap();
+        // Backdate everything, deepest first so writing a child does not move
+        // the parent's time again.
+        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for relative in ["sub/b.txt", "sub", "a.txt", "."] {
+            let file = File::options().read(true).open(src.join(relative)).unwrap();
+            file.set_times(fs::FileTimes::new().set_modified(old))
+                .unwrap();
+        }
+        let before = modified_times(&src);
+        let dst = fx.join("dst");
+        let mode = fs::symlink_metadata(&src).unwrap().permissions().mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+
+        // A same-device move is a rename, which keeps the timestamps. The
+        // cross-device fallback copies, so it has to put them back or the
+        // result depends on which mount the destination is on.
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(true, &mut [0u8; 64]),
+            true,
+            mode,
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
+
+        assert_eq!(before, modified_times(&dst));
     }
 
     #[test]
-    fn validate_paths_rejects_destination_inside_source() {
-        let src = path_info("/a/b", "b");
-        let dest = path_info("/a/b/c", "c");
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.contains("into its own subdirectory"), "{message}");
+    fn a_plain_copy_does_not_keep_the_modification_times() {
+        let fx = TempDir::new("tasks_times_off");
+        let src = fx.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"a").unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for relative in ["a.txt", "."] {
+            let file = File::options().read(true).open(src.join(relative)).unwrap();
+            file.set_times(fs::FileTimes::new().set_modified(old))
+                .unwrap();
+        }
+        let dst = fx.join("dst");
+        let mode = fs::symlink_metadata(&src).unwrap().permissions().mode();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+
+        // `cp` does not preserve timestamps without `-p`, so a copy must not
+        // start doing it just because the move path needs to.
+        assert!(copy_path(
+            &src,
+            &dst,
+            &mut active,
+            &mut errors,
+            &mut context(false, &mut [0u8; 64]),
+            true,
+            mode,
+        ));
+        active.done();
+
+        assert_ne!(
+            old,
+            fs::metadata(dst.join("a.txt")).unwrap().modified().unwrap()
+        );
     }
 
-    #[test]
-    fn validate_paths_rejects_destination_inside_source_via_parent_dir() {
-        // "/a/c/../b/d" resolves to "/a/b/d", inside the source, which a raw
-        // component-wise prefix check on the path as written would not catch.
-        let src = path_info("/a/b", "b");
-        let dest = path_info("/a/c/../b/d", "d");
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.contains("into its own subdirectory"), "{message}");
+    /// A copy context whose collisions are settled by a standing choice, which
+    /// is the only kind of answer that reaches a worker.
+    fn answered_context<'a>(
+        standing: ConflictChoice,
+        buffer: &'a mut [u8],
+        conflicts: &'a Conflicts,
+    ) -> CopyContext<'a> {
+        conflicts.answer(standing);
+        CopyContext {
+            backend: &Local,
+            buffer,
+            conflicts: Some(conflicts),
+            preserve_times: false,
+            skipped: 0,
+        }
     }
 
-    #[test]
-    fn validate_paths_rejects_a_destination_that_symlinks_into_the_source() {
-        let fx = TempDir::new("tasks");
+    /// A source entry and a destination path that another process took while
+    /// the copy was already running. A copy's destination is free when it
+    /// starts, so a race part way through is the only way a collision appears
+    /// underneath it, and each entry is reached individually.
+    fn raced(label: &str) -> (TempDir, PathBuf, PathBuf) {
+        let fx = TempDir::new(label);
         let src = fx.join("src");
-        std::fs::create_dir_all(src.join("inner")).unwrap();
-        // A lexical prefix check cannot see that `link` resolves inside `src`.
-        // Copying through it would create the destination under the source and
-        // then recurse into what it is creating, filling the disk.
-        let link = fx.join("link");
-        std::os::unix::fs::symlink(src.join("inner"), &link).unwrap();
+        let dst = fx.join("dst");
+        fs::create_dir_all(&src).unwrap();
+        // What the copy had created before the other process interfered.
+        fs::create_dir_all(&dst).unwrap();
+        (fx, src, dst)
+    }
 
-        let source = path_info(src.to_str().unwrap(), "src");
-        let dest = path_info(link.to_str().unwrap(), "link");
-        let message = rejection(validate_paths(&source, &dest, "copy", false));
-        assert!(message.contains("into its own subdirectory"), "{message}");
+    /// The three pieces every raced-entry test needs. The receiver is leaked
+    /// rather than returned as a fourth: nothing here reads it, and it only has
+    /// to outlive the task, whose sends are best-effort anyway.
+    fn raced_parts() -> (ActiveTask, Vec<String>, Conflicts) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::mem::forget(rx);
+        (copy_task(tx), Vec::new(), Conflicts::default())
     }
 
-    #[test]
-    fn validate_paths_allows_a_symlink_copied_into_the_directory_it_points_at() {
-        let fx = TempDir::new("tasks");
-        let target = fx.join("target");
-        std::fs::create_dir_all(&target).unwrap();
-        let link = fx.join("link");
-        std::os::unix::fs::symlink(&target, &link).unwrap();
-
-        // The source resolves to the destination, but copying a symlink
-        // recreates a link rather than descending into anything, so there is
-        // no subtree to recurse into and nothing to reject.
-        let source = PathInfo::try_from(link.as_path()).unwrap();
-        let dest = PathInfo::try_from(target.as_path()).unwrap();
-        assert!(validate_paths(&source, &dest, "copy", false).is_ok());
+    fn mode_of(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().permissions().mode()
     }
 
-    #[test_case(true  ; "overwrite granted")]
-    #[test_case(false ; "overwrite not granted")]
-    fn validate_paths_rejects_a_destination_that_aliases_the_source(overwrite: bool) {
-        let fx = TempDir::new("tasks");
-        let real = fx.join("real");
-        std::fs::create_dir_all(&real).unwrap();
-        std::fs::write(real.join("f.txt"), b"precious").unwrap();
-        // A second path to the same directory, which the clipboard can easily
-        // carry: it holds whatever absolute path the other window was showing.
-        std::os::unix::fs::symlink(&real, fx.join("link")).unwrap();
+    #[test]
+    fn a_raced_file_is_replaced_when_that_is_the_answer() {
+        let (_fx, src, dst) = raced("tasks_raced_overwrite");
+        fs::write(src.join("a.txt"), b"src").unwrap();
+        fs::write(dst.join("a.txt"), b"raced").unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
 
-        let src = PathInfo::try_from(real.join("f.txt").as_path()).unwrap();
-        let dest = PathInfo::try_from(fx.join("link").as_path()).unwrap();
+        assert!(copy_path(
+            &src.join("a.txt"),
+            &dst.join("a.txt"),
+            &mut active,
+            &mut errors,
+            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
+            false,
+            mode_of(&src.join("a.txt")),
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
 
-        // The two paths name one file. A granted overwrite clears the
-        // destination before copying, so letting this through would unlink the
-        // source and leave nothing to copy from.
-        assert!(validate_paths(&src, &dest, "copy", overwrite).is_err());
-        assert!(real.join("f.txt").exists());
+        assert_eq!(b"src".to_vec(), fs::read(dst.join("a.txt")).unwrap());
     }
 
     #[test]
-    fn validate_paths_allows_sibling_destination() {
-        let src = path_info("/a/b", "b");
-        let dest = path_info("/x", "x");
-        let (old_path, new_path) =
-            validate_paths(&src, &dest, "copy", false).expect("should be allowed");
-        assert_eq!(PathBuf::from("/a/b"), old_path);
-        assert_eq!(PathBuf::from("/x/b"), new_path);
-    }
+    fn a_raced_file_is_left_alone_when_that_is_the_answer() {
+        let (_fx, src, dst) = raced("tasks_raced_skip");
+        fs::write(src.join("a.txt"), b"src").unwrap();
+        fs::write(dst.join("a.txt"), b"raced").unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
+        let mut context = answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts);
 
-    #[test]
-    fn validate_paths_allows_destination_with_shared_prefix_but_different_component() {
-        // "/a/bb" must not be treated as inside "/a/b".
-        let src = path_info("/a/b", "b");
-        let dest = path_info("/a/bb", "bb");
-        assert!(validate_paths(&src, &dest, "copy", false).is_ok());
-    }
+        assert!(copy_path(
+            &src.join("a.txt"),
+            &dst.join("a.txt"),
+            &mut active,
+            &mut errors,
+            &mut context,
+            false,
+            mode_of(&src.join("a.txt")),
+        ));
+        // A skipped name is a choice, not a failure, so nothing is reported.
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        // It is still counted: a move must not remove a source whose entries
+        // are not all at the destination.
+        assert_eq!(1, context.skipped);
+        active.done();
 
-    #[test]
-    fn validate_paths_preserves_non_utf8_source_names() {
-        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
-        // The display name is lossy UTF-8; the destination must be built from
-        // the raw file name so the bytes survive.
-        let name = OsStr::from_bytes(b"caf\xe9.txt");
-        let mut src = path_info("/a/placeholder", "placeholder");
-        src.path = PathBuf::from("/a").join(name);
-        let dest = path_info("/x", "x");
-        let (_, new_path) = validate_paths(&src, &dest, "copy", false).expect("should be allowed");
-        assert_eq!(PathBuf::from("/x").join(name), new_path);
+        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
     }
 
     #[test]
-    fn validate_paths_rejects_source_without_file_name() {
-        let src = path_info("/", "");
-        let dest = path_info("/x", "x");
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.ends_with("path has no file name"), "{message}");
-    }
+    fn a_raced_name_with_no_standing_answer_is_recorded_rather_than_asked_about() {
+        let (_fx, src, dst) = raced("tasks_raced_unanswered");
+        fs::write(src.join("a.txt"), b"src").unwrap();
+        fs::write(dst.join("a.txt"), b"raced").unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
+        let mut context = CopyContext {
+            backend: &Local,
+            buffer: &mut buffer,
+            conflicts: Some(&conflicts),
+            preserve_times: false,
+            skipped: 0,
+        };
 
-    #[test]
-    fn validate_paths_rejects_existing_destination() {
-        let fx = TempDir::new("tasks");
-        std::fs::write(fx.join("existing.txt"), b"x").unwrap();
-        let src = path_info("/elsewhere/existing.txt", "existing.txt");
-        let dest = path_info(fx.path().to_str().unwrap(), "dir");
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.ends_with("it already exists there"), "{message}");
+        // A worker never asks: the queue that could have prompted is gone by
+        // the time it runs.
+        assert!(copy_path(
+            &src.join("a.txt"),
+            &dst.join("a.txt"),
+            &mut active,
+            &mut errors,
+            &mut context,
+            false,
+            mode_of(&src.join("a.txt")),
+        ));
+        active.done();
+
+        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
+        assert!(errors[0].contains("already exists"), "{}", errors[0]);
+        // Recorded, not skipped: the entry was left behind by a failure to
+        // settle it, so a move must report it rather than call it a choice.
+        assert_eq!(0, context.skipped);
+        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
     }
 
     #[test]
-    fn validate_paths_rejects_a_destination_directory_of_the_same_name() {
-        let fx = TempDir::new("tasks");
-        std::fs::create_dir_all(fx.join("existing.txt")).unwrap();
-        let src = path_info("/elsewhere/existing.txt", "existing.txt");
-        let dest = path_info(fx.path().to_str().unwrap(), "dir");
+    fn a_raced_directory_is_recorded_when_only_overwrite_all_stands() {
+        let (_fx, src, dst) = raced("tasks_raced_directory_overwrite");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::create_dir_all(dst.join("sub")).unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
 
-        // A directory is refused whatever the answer: removing it would take
-        // its contents with it, and merging is not supported.
-        for overwrite in [false, true] {
-            let message = rejection(validate_paths(&src, &dest, "copy", overwrite));
-            assert!(
-                message.ends_with("a directory of that name is already there"),
-                "{message}"
-            );
-        }
+        // A directory is never replaced, so "overwrite all" cannot settle this
+        // one and there is nobody to ask.
+        assert!(copy_path(
+            &src.join("sub"),
+            &dst.join("sub"),
+            &mut active,
+            &mut errors,
+            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
+            true,
+            mode_of(&src.join("sub")),
+        ));
+        active.done();
+
+        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
+        assert!(errors[0].contains("already exists"), "{}", errors[0]);
     }
 
     #[test]
-    fn validate_paths_rejects_existing_broken_symlink_destination() {
-        let fx = TempDir::new("tasks");
-        let link = fx.join("existing.txt");
-        std::os::unix::fs::symlink(fx.join("missing"), &link).unwrap();
-        let src = path_info("/elsewhere/existing.txt", "existing.txt");
-        let dest = path_info(fx.path().to_str().unwrap(), "dir");
-
-        // `symlink_metadata` rather than `exists`, which follows the link and
-        // reports a dangling one as absent, silently overwriting it.
-        let message = rejection(validate_paths(&src, &dest, "copy", false));
-        assert!(message.ends_with("it already exists there"), "{message}");
-    }
+    fn a_raced_symlink_is_replaced_when_that_is_the_answer() {
+        let (_fx, src, dst) = raced("tasks_raced_symlink");
+        std::os::unix::fs::symlink("target", src.join("link")).unwrap();
+        std::os::unix::fs::symlink("raced", dst.join("link")).unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
 
-    /// A copy context for a test: no paste behind it, so a nested collision
-    /// records an error rather than asking.
-    fn context(preserve_times: bool, buffer: &mut [u8]) -> CopyContext<'_> {
-        CopyContext {
-            buffer,
-            conflicts: None,
-            preserve_times,
-            skipped: 0,
-        }
-    }
+        assert!(copy_path(
+            &src.join("link"),
+            &dst.join("link"),
+            &mut active,
+            &mut errors,
+            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
+            false,
+            mode_of(&src.join("link")),
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
 
-    fn copy_task(tx: std::sync::mpsc::Sender<Command>) -> ActiveTask {
-        let (active, _, _) = ActiveTask::new(
-            tx,
-            TaskKind::Copy(Transfer {
-                source: String::new(),
-                destination: String::new(),
-            }),
-            1,
+        assert_eq!(
+            PathBuf::from("target"),
+            fs::read_link(dst.join("link")).unwrap()
         );
-        active
     }
 
-    // Linux only: recreating a socket goes through mknod, which macOS refuses
-    // to an unprivileged process with EPERM.
-    #[cfg(target_os = "linux")]
     #[test]
-    fn copy_path_recreates_socket() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("sock");
-        let _listener = std::os::unix::net::UnixListener::bind(&src).unwrap();
-        let dst = fx.join("sock_copy");
-        let mode = std::fs::symlink_metadata(&src)
-            .unwrap()
-            .permissions()
-            .mode();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
+    fn a_raced_symlink_with_no_standing_answer_is_recorded() {
+        let (_fx, src, dst) = raced("tasks_raced_symlink_unanswered");
+        std::os::unix::fs::symlink("target", src.join("link")).unwrap();
+        std::os::unix::fs::symlink("raced", dst.join("link")).unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
+        let mut context = CopyContext {
+            backend: &Local,
+            buffer: &mut buffer,
+            conflicts: Some(&conflicts),
+            preserve_times: false,
+            skipped: 0,
+        };
 
         assert!(copy_path(
-            &src,
-            &dst,
+            &src.join("link"),
+            &dst.join("link"),
             &mut active,
             &mut errors,
-            &mut context(false, &mut [0u8; 64]),
+            &mut context,
             false,
-            mode,
+            mode_of(&src.join("link")),
         ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        let dst_mode = std::fs::symlink_metadata(&dst)
-            .unwrap()
-            .permissions()
-            .mode();
-        assert!(unix_mode::is_socket(dst_mode));
-        assert!(src.exists());
         active.done();
+
+        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
+        assert!(errors[0].contains("already exists"), "{}", errors[0]);
+        assert_eq!(
+            PathBuf::from("raced"),
+            fs::read_link(dst.join("link")).unwrap()
+        );
     }
 
     #[test]
-    fn copy_path_recreates_fifo() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("fifo");
-        nix::unistd::mkfifo(&src, nix::sys::stat::Mode::from_bits_truncate(0o644)).unwrap();
-        let dst = fx.join("fifo_copy");
-        let mode = std::fs::symlink_metadata(&src)
-            .unwrap()
-            .permissions()
-            .mode();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
+    fn a_raced_special_file_is_left_alone_when_that_is_the_answer() {
+        use nix::sys::stat::Mode;
+
+        let (_fx, src, dst) = raced("tasks_raced_fifo");
+        nix::unistd::mkfifo(&src.join("pipe"), Mode::from_bits_truncate(0o644)).unwrap();
+        nix::unistd::mkfifo(&dst.join("pipe"), Mode::from_bits_truncate(0o600)).unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
+        let mut context = answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts);
 
         assert!(copy_path(
-            &src,
-            &dst,
+            &src.join("pipe"),
+            &dst.join("pipe"),
             &mut active,
             &mut errors,
-            &mut context(false, &mut [0u8; 64]),
+            &mut context,
             false,
-            mode,
+            mode_of(&src.join("pipe")),
         ));
         assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        let dst_mode = std::fs::symlink_metadata(&dst)
-            .unwrap()
-            .permissions()
-            .mode();
-        assert!(unix_mode::is_fifo(dst_mode));
-        assert_eq!(0o644, dst_mode & 0o7777);
+        assert_eq!(1, context.skipped);
         active.done();
+
+        // Left exactly as the other process made it.
+        assert_eq!(0o600, mode_of(&dst.join("pipe")) & 0o7777);
     }
 
     #[test]
-    fn copy_path_continues_past_unreadable_entries() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("src");
-        std::fs::create_dir_all(&src).unwrap();
-        std::fs::write(src.join("a.txt"), b"a").unwrap();
-        std::fs::write(src.join("bad"), b"x").unwrap();
-        std::fs::write(src.join("c.txt"), b"c").unwrap();
-        let mut permissions = std::fs::metadata(src.join("bad")).unwrap().permissions();
-        permissions.set_mode(0o000);
-        std::fs::set_permissions(src.join("bad"), permissions).unwrap();
-        // A chmod-000 file is still readable by root (CAP_DAC_OVERRIDE) and on
-        // mounts that ignore permissions. Probe what this filesystem actually
-        // does rather than inspecting the euid, so the assertions below match
-        // the environment instead of being skipped in it.
-        let is_unreadable = std::fs::File::open(src.join("bad")).is_err();
-
-        let dst = fx.join("dst");
-        let mode = std::fs::symlink_metadata(&src)
-            .unwrap()
-            .permissions()
-            .mode();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
+    fn a_raced_directory_is_never_replaced_so_its_subtree_is_dropped() {
+        let (_fx, src, dst) = raced("tasks_raced_directory");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("sub").join("deep.txt"), b"src").unwrap();
+        fs::create_dir_all(dst.join("sub")).unwrap();
+        let (mut active, mut errors, conflicts) = raced_parts();
+        let mut buffer = [0u8; 64];
 
+        // Only the skip choices apply to a directory, so "skip all" is what can
+        // answer this one; "overwrite all" would still have to ask.
         assert!(copy_path(
-            &src,
-            &dst,
+            &src.join("sub"),
+            &dst.join("sub"),
             &mut active,
             &mut errors,
-            &mut context(false, &mut [0u8; 64]),
+            &mut answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts),
             true,
-            mode,
+            mode_of(&src.join("sub")),
         ));
-
-        if is_unreadable {
-            // Like cp -R: the unreadable entry is recorded, not fatal.
-            assert_eq!(1, errors.len(), "expected one error: {errors:?}");
-            assert!(errors[0].contains("bad"), "unexpected error: {}", errors[0]);
-            assert!(!dst.join("bad").exists());
-        } else {
-            // Nothing was unreadable here, so this is a plain full copy.
-            assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-            assert!(dst.join("bad").exists());
-        }
-        // The walk must reach the entries on both sides of "bad" either way: a
-        // failed entry must not abort the siblings.
-        assert!(dst.join("a.txt").exists());
-        assert!(dst.join("c.txt").exists());
         active.done();
+
+        // Dropped, not merged into: a directory is never replaced, and the copy
+        // does not descend into one it did not create.
+        assert!(!dst.join("sub").join("deep.txt").exists());
     }
 
     #[test]
-    fn copy_path_reuses_one_buffer_without_leaking_bytes_between_files() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("src");
-        std::fs::create_dir_all(&src).unwrap();
-        std::fs::write(src.join("a_long.txt"), b"aaaaaaaaaaaaaaaa").unwrap();
-        std::fs::write(src.join("b_short.txt"), b"b").unwrap();
-        let dst = fx.join("dst");
-        let mode = std::fs::symlink_metadata(&src)
-            .unwrap()
-            .permissions()
-            .mode();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
+    fn a_raced_name_with_no_paste_behind_it_is_recorded_rather_than_asked_about() {
+        let (_fx, src, dst) = raced("tasks_raced_no_paste");
+        fs::write(src.join("a.txt"), b"src").unwrap();
+        fs::write(dst.join("a.txt"), b"raced").unwrap();
+        let (mut active, mut errors, _conflicts) = raced_parts();
 
-        // One buffer serves the whole tree, so a short file copied after a
-        // longer one must not pick up the previous file's trailing bytes.
+        // No paste at all (an operation that is not one), so there is not even
+        // a standing answer to consult: the entry is left alone and reported.
         assert!(copy_path(
-            &src,
-            &dst,
+            &src.join("a.txt"),
+            &dst.join("a.txt"),
             &mut active,
             &mut errors,
             &mut context(false, &mut [0u8; 64]),
-            true,
-            mode,
+            false,
+            mode_of(&src.join("a.txt")),
         ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
         active.done();
 
-        assert_eq!(
-            b"aaaaaaaaaaaaaaaa".to_vec(),
-            std::fs::read(dst.join("a_long.txt")).unwrap()
-        );
-        assert_eq!(
-            b"b".to_vec(),
-            std::fs::read(dst.join("b_short.txt")).unwrap()
-        );
+        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
+        assert!(errors[0].contains("already exists"), "{}", errors[0]);
+        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
     }
 
-    /// The modification times of `path` and everything under it, by relative
-    /// name, so a tree can be compared against its copy.
-    fn modified_times(root: &Path) -> Vec<(PathBuf, std::time::SystemTime)> {
-        let mut times = vec![(
-            PathBuf::from("."),
-            fs::metadata(root).unwrap().modified().unwrap(),
-        )];
-        let mut stack = vec![root.to_path_buf()];
-        while let Some(dir) = stack.pop() {
-            for entry in fs::read_dir(&dir).unwrap().flatten() {
-                let path = entry.path();
-                let metadata = fs::metadata(&path).unwrap();
-                if metadata.is_dir() {
-                    stack.push(path.clone());
-                }
-                times.push((
-                    path.strip_prefix(root).unwrap().to_path_buf(),
-                    metadata.modified().unwrap(),
-                ));
+    // ── finishing a cross-device move, which is the only path that deletes ───
+
+    /// A source tree, and the channel the finished task reports on.
+    fn moved(label: &str) -> (TempDir, PathBuf, mpsc::Receiver<Command>, ActiveTask) {
+        let fx = TempDir::new(label);
+        let src = fx.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"src").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let active = copy_task(tx);
+        (fx, src, rx, active)
+    }
+
+    /// The last task the operation sent, which carries how it finished.
+    fn finished_task(rx: &mpsc::Receiver<Command>) -> Task {
+        let mut last = None;
+        while let Ok(command) = rx.try_recv() {
+            if let Command::Progress(task) = command {
+                last = Some(task);
             }
         }
-        times.sort();
-        times
+        last.expect("the task sent no progress")
     }
 
     #[test]
-    fn a_preserving_copy_keeps_the_modification_times_of_the_whole_tree() {
-        let fx = TempDir::new("tasks_times");
-        let src = fx.join("src");
-        fs::create_dir_all(src.join("sub")).unwrap();
-        fs::write(src.join("a.txt"), b"a").unwrap();
-        fs::write(src.join("sub").join("b.txt"), b"b").unwrap();
-        // Backdate everything, deepest first so writing a child does not move
-        // the parent's time again.
-        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
-        for relative in ["sub/b.txt", "sub", "a.txt", "."] {
-            let file = File::options().read(true).open(src.join(relative)).unwrap();
-            file.set_times(fs::FileTimes::new().set_modified(old))
-                .unwrap();
-        }
-        let before = modified_times(&src);
-        let dst = fx.join("dst");
-        let mode = fs::symlink_metadata(&src).unwrap().permissions().mode();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
+    fn a_cross_device_move_removes_its_source_once_every_entry_arrived() {
+        let (_fx, src, rx, active) = moved("tasks_move_complete");
+
+        finish_cross_device_move(&Local, active, CopyOutcome::default(), &src, true);
+
+        assert!(!src.exists());
+        assert_eq!(None, finished_task(&rx).error_message());
+    }
+
+    #[test]
+    fn a_cross_device_move_keeps_its_source_when_an_entry_was_skipped() {
+        let (_fx, src, rx, active) = moved("tasks_move_skipped");
+
+        finish_cross_device_move(
+            &Local,
+            active,
+            CopyOutcome {
+                errors: Vec::new(),
+                skipped: 1,
+            },
+            &src,
+            true,
+        );
+
+        // The skipped entry never reached the destination, so removing the
+        // source would delete the only copy of it. A skip is not an error, so
+        // the emptiness of `errors` must not be what decides this.
+        assert!(src.join("a.txt").exists());
+        let message = finished_task(&rx)
+            .error_message()
+            .expect("the kept source must be reported");
+        assert!(message.contains("Skipped 1 entry"), "{message}");
+    }
+
+    #[test]
+    fn a_cross_device_move_keeps_its_source_when_an_entry_failed() {
+        let (_fx, src, rx, active) = moved("tasks_move_failed");
 
-        // A same-device move is a rename, which keeps the timestamps. The
-        // cross-device fallback copies, so it has to put them back or the
-        // result depends on which mount the destination is on.
-        assert!(copy_path(
+        finish_cross_device_move(
+            &Local,
+            active,
+            CopyOutcome {
+                errors: vec!["a.txt already exists".to_string()],
+                skipped: 0,
+            },
             &src,
-            &dst,
-            &mut active,
-            &mut errors,
-            &mut context(true, &mut [0u8; 64]),
             true,
-            mode,
-        ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        active.done();
+        );
 
-        assert_eq!(before, modified_times(&dst));
+        assert!(src.join("a.txt").exists());
+        assert_eq!(
+            Some("a.txt already exists".to_string()),
+            finished_task(&rx).error_message()
+        );
     }
 
+    /// `copy_path_continues_past_unreadable_entries` degrades to a plain full
+    /// copy under root, so the error-recording path is pinned here instead,
+    /// with a failure the kernel enforces for every user.
     #[test]
-    fn a_plain_copy_does_not_keep_the_modification_times() {
-        let fx = TempDir::new("tasks_times_off");
+    fn copy_path_records_a_directory_it_cannot_create() {
+        let fx = TempDir::new("tasks");
         let src = fx.join("src");
-        fs::create_dir_all(&src).unwrap();
-        fs::write(src.join("a.txt"), b"a").unwrap();
-        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
-        for relative in ["a.txt", "."] {
-            let file = File::options().read(true).open(src.join(relative)).unwrap();
-            file.set_times(fs::FileTimes::new().set_modified(old))
-                .unwrap();
-        }
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.txt"), b"a").unwrap();
+        // A regular file already occupies the destination, so creating the
+        // directory fails with EEXIST regardless of privileges.
         let dst = fx.join("dst");
-        let mode = fs::symlink_metadata(&src).unwrap().permissions().mode();
+        std::fs::write(&dst, b"in the way").unwrap();
+        let mode = std::fs::symlink_metadata(&src)
+            .unwrap()
+            .permissions()
+            .mode();
         let (tx, _rx) = std::sync::mpsc::channel();
         let mut active = copy_task(tx);
         let mut errors = Vec::new();
 
-        // `cp` does not preserve timestamps without `-p`, so a copy must not
-        // start doing it just because the move path needs to.
+        // The subtree is skipped rather than failing the whole task, so a
+        // multi-source paste still copies its other sources.
         assert!(copy_path(
             &src,
             &dst,
@@ -1913,748 +4109,885 @@ mod tests {
             true,
             mode,
         ));
-        active.done();
 
-        assert_ne!(
-            old,
-            fs::metadata(dst.join("a.txt")).unwrap().modified().unwrap()
-        );
+        assert_eq!(1, errors.len(), "expected one error: {errors:?}");
+        assert!(errors[0].contains("dst"), "unexpected error: {}", errors[0]);
+        // The occupying file must be left exactly as it was.
+        assert_eq!(b"in the way".to_vec(), std::fs::read(&dst).unwrap());
+        active.done();
     }
 
-    /// A copy context whose collisions are settled by a standing choice, which
-    /// is the only kind of answer that reaches a worker.
-    fn answered_context<'a>(
-        standing: ConflictChoice,
-        buffer: &'a mut [u8],
-        conflicts: &'a Conflicts,
-    ) -> CopyContext<'a> {
-        conflicts.answer(standing);
-        CopyContext {
-            buffer,
-            conflicts: Some(conflicts),
-            preserve_times: false,
-            skipped: 0,
-        }
-    }
+    // ── prepare_destination: the ordering every queued operation depends on ──
 
-    /// A source entry and a destination path that another process took while
-    /// the copy was already running. A copy's destination is free when it
-    /// starts, so a race part way through is the only way a collision appears
-    /// underneath it, and each entry is reached individually.
-    fn raced(label: &str) -> (TempDir, PathBuf, PathBuf) {
+    /// A source file holding "src", a destination file holding "dest", and an
+    /// `ActiveTask` for the operation that would replace one with the other.
+    /// The receiver is leaked for the same reason as in `raced_parts`.
+    fn destination(label: &str) -> (TempDir, PathBuf, PathBuf, ActiveTask, CancellationToken) {
         let fx = TempDir::new(label);
-        let src = fx.join("src");
-        let dst = fx.join("dst");
-        fs::create_dir_all(&src).unwrap();
-        // What the copy had created before the other process interfered.
-        fs::create_dir_all(&dst).unwrap();
-        (fx, src, dst)
-    }
-
-    /// The three pieces every raced-entry test needs. The receiver is leaked
-    /// rather than returned as a fourth: nothing here reads it, and it only has
-    /// to outlive the task, whose sends are best-effort anyway.
-    fn raced_parts() -> (ActiveTask, Vec<String>, Conflicts) {
+        let src = fx.join("src.txt");
+        let dst = fx.join("dest.txt");
+        std::fs::write(&src, b"src").unwrap();
+        std::fs::write(&dst, b"dest").unwrap();
         let (tx, rx) = std::sync::mpsc::channel();
         std::mem::forget(rx);
-        (copy_task(tx), Vec::new(), Conflicts::default())
+        let (active, _, token) = ActiveTask::new(
+            tx,
+            TaskKind::Copy(Transfer {
+                source: String::new(),
+                destination: String::new(),
+            }),
+            1,
+        );
+        (fx, src, dst, active, token)
     }
 
-    fn mode_of(path: &Path) -> u32 {
-        fs::symlink_metadata(path).unwrap().permissions().mode()
+    #[test]
+    fn a_cancel_that_lands_while_queued_stops_the_task() {
+        let (_fx, _src, dst, active, token) = destination("tasks_prepare_cancelled");
+        token.cancel();
+
+        // The queue can hold a task for as long as the operations ahead of it
+        // take, so a cancel has to be honored before anything is written, and
+        // before the destination it would have replaced is cleared.
+        assert!(check_cancelled(active).is_none());
+        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
     }
 
     #[test]
-    fn a_raced_file_is_replaced_when_that_is_the_answer() {
-        let (_fx, src, dst) = raced("tasks_raced_overwrite");
-        fs::write(src.join("a.txt"), b"src").unwrap();
-        fs::write(dst.join("a.txt"), b"raced").unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
+    fn a_granted_overwrite_clears_the_destination() {
+        let (_fx, src, dst, active, _token) = destination("tasks_prepare_overwrite");
 
-        assert!(copy_path(
-            &src.join("a.txt"),
-            &dst.join("a.txt"),
-            &mut active,
-            &mut errors,
-            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
-            false,
-            mode_of(&src.join("a.txt")),
-        ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        active.done();
+        let active =
+            clear_destination(&Local, active, &src, &dst, true).expect("the task should continue");
 
-        assert_eq!(b"src".to_vec(), fs::read(dst.join("a.txt")).unwrap());
+        assert!(!dst.exists());
+        active.done();
     }
 
     #[test]
-    fn a_raced_file_is_left_alone_when_that_is_the_answer() {
-        let (_fx, src, dst) = raced("tasks_raced_skip");
-        fs::write(src.join("a.txt"), b"src").unwrap();
-        fs::write(dst.join("a.txt"), b"raced").unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
-        let mut context = answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts);
+    fn a_destination_survives_when_overwrite_was_not_granted() {
+        let (_fx, src, dst, active, _token) = destination("tasks_prepare_no_overwrite");
 
-        assert!(copy_path(
-            &src.join("a.txt"),
-            &dst.join("a.txt"),
-            &mut active,
-            &mut errors,
-            &mut context,
-            false,
-            mode_of(&src.join("a.txt")),
-        ));
-        // A skipped name is a choice, not a failure, so nothing is reported.
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        // It is still counted: a move must not remove a source whose entries
-        // are not all at the destination.
-        assert_eq!(1, context.skipped);
+        let active =
+            clear_destination(&Local, active, &src, &dst, false).expect("the task should continue");
+
+        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
         active.done();
+    }
 
-        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
+    #[test]
+    fn a_destination_that_already_vanished_is_not_an_error() {
+        let (_fx, src, dst, active, _token) = destination("tasks_prepare_vanished");
+        std::fs::remove_file(&dst).unwrap();
+
+        // Something else removed it between the prompt and the worker, which
+        // is the outcome the user asked for anyway.
+        assert!(clear_destination(&Local, active, &src, &dst, true).is_some());
     }
 
     #[test]
-    fn a_raced_name_with_no_standing_answer_is_recorded_rather_than_asked_about() {
-        let (_fx, src, dst) = raced("tasks_raced_unanswered");
-        fs::write(src.join("a.txt"), b"src").unwrap();
-        fs::write(dst.join("a.txt"), b"raced").unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
-        let mut context = CopyContext {
-            buffer: &mut buffer,
-            conflicts: Some(&conflicts),
-            preserve_times: false,
-            skipped: 0,
-        };
+    fn a_cleared_destination_can_be_opened_by_the_cross_device_move_fallback() {
+        let (_fx, src, dst, active, _token) = destination("tasks_prepare_reopen");
 
-        // A worker never asks: the queue that could have prompted is gone by
-        // the time it runs.
-        assert!(copy_path(
-            &src.join("a.txt"),
-            &dst.join("a.txt"),
-            &mut active,
-            &mut errors,
-            &mut context,
-            false,
-            mode_of(&src.join("a.txt")),
-        ));
-        active.done();
+        let active =
+            clear_destination(&Local, active, &src, &dst, true).expect("the task should continue");
 
-        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
-        assert!(errors[0].contains("already exists"), "{}", errors[0]);
-        // Recorded, not skipped: the entry was left behind by a failure to
-        // settle it, so a move must report it rather than call it a choice.
-        assert_eq!(0, context.skipped);
-        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
+        // A move across filesystems cannot rename, so it falls back to a byte
+        // copy that opens the destination with `create_new`. That open fails
+        // unless the destination was cleared first, which is why clearing is
+        // not skippable just because a rename would have replaced it.
+        assert!(open_files(&Local, &src, &dst).is_ok());
+        active.done();
     }
 
     #[test]
-    fn a_raced_directory_is_recorded_when_only_overwrite_all_stands() {
-        let (_fx, src, dst) = raced("tasks_raced_directory_overwrite");
-        fs::create_dir_all(src.join("sub")).unwrap();
-        fs::create_dir_all(dst.join("sub")).unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
+    fn a_vanished_source_leaves_the_destination_it_would_have_replaced() {
+        let (_fx, src, dst, active, _token) = destination("tasks_prepare_source_gone");
+        std::fs::remove_file(&src).unwrap();
 
-        // A directory is never replaced, so "overwrite all" cannot settle this
-        // one and there is nobody to ask.
-        assert!(copy_path(
-            &src.join("sub"),
-            &dst.join("sub"),
-            &mut active,
-            &mut errors,
-            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
-            true,
-            mode_of(&src.join("sub")),
-        ));
-        active.done();
+        // A task can wait behind a long operation on the shared worker, so the
+        // source it was going to copy may be gone by the time it runs. Clearing
+        // the destination for a copy that can no longer happen would leave the
+        // user with neither entry.
+        assert!(clear_destination(&Local, active, &src, &dst, true).is_none());
+        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+    }
+
+    #[test]
+    fn a_failed_overwriting_move_leaves_the_destination_in_place() {
+        let fx = TempDir::new("tasks_move_failure");
+        let src = fx.join("gone.txt");
+        let dst = fx.join("dest.txt");
+        std::fs::write(&dst, b"dest").unwrap();
 
-        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
-        assert!(errors[0].contains("already exists"), "{}", errors[0]);
+        // The source vanished while the task sat in the queue, which a single
+        // worker running a long copy makes easy. Clearing the destination up
+        // front would lose it for a move that then cannot happen, leaving the
+        // user with neither file.
+        assert!(rename_for_move(&Local, &src, &dst, true).is_err());
+        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
     }
 
     #[test]
-    fn a_raced_symlink_is_replaced_when_that_is_the_answer() {
-        let (_fx, src, dst) = raced("tasks_raced_symlink");
-        std::os::unix::fs::symlink("target", src.join("link")).unwrap();
-        std::os::unix::fs::symlink("raced", dst.join("link")).unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
+    fn an_overwriting_move_replaces_a_file_without_clearing_it_first() {
+        let fx = TempDir::new("tasks_move_atomic");
+        let src = fx.join("src.txt");
+        let dst = fx.join("dest.txt");
+        std::fs::write(&src, b"src").unwrap();
+        std::fs::write(&dst, b"dest").unwrap();
 
-        assert!(copy_path(
-            &src.join("link"),
-            &dst.join("link"),
-            &mut active,
-            &mut errors,
-            &mut answered_context(ConflictChoice::OverwriteAll, &mut buffer, &conflicts),
-            false,
-            mode_of(&src.join("link")),
-        ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        active.done();
+        // The kernel replaces atomically here, so there is no moment in which
+        // the destination is missing.
+        rename_for_move(&Local, &src, &dst, true).unwrap();
+        assert_eq!(b"src".to_vec(), std::fs::read(&dst).unwrap());
+        assert!(!src.exists());
+    }
+
+    #[test]
+    fn an_overwriting_move_clears_a_file_that_a_directory_must_replace() {
+        let fx = TempDir::new("tasks_move_dir_over_file");
+        let src = fx.join("srcdir");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("inner.txt"), b"src").unwrap();
+        let dst = fx.join("dest");
+        std::fs::write(&dst, b"dest").unwrap();
 
+        // `rename` refuses to replace a non-directory with a directory, so
+        // this is the one case that has to clear the destination itself.
+        rename_for_move(&Local, &src, &dst, true).unwrap();
         assert_eq!(
-            PathBuf::from("target"),
-            fs::read_link(dst.join("link")).unwrap()
+            b"src".to_vec(),
+            std::fs::read(dst.join("inner.txt")).unwrap()
         );
     }
 
     #[test]
-    fn a_raced_symlink_with_no_standing_answer_is_recorded() {
-        let (_fx, src, dst) = raced("tasks_raced_symlink_unanswered");
-        std::os::unix::fs::symlink("target", src.join("link")).unwrap();
-        std::os::unix::fs::symlink("raced", dst.join("link")).unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
-        let mut context = CopyContext {
-            buffer: &mut buffer,
-            conflicts: Some(&conflicts),
-            preserve_times: false,
-            skipped: 0,
-        };
+    fn a_move_without_overwrite_refuses_an_existing_destination() {
+        let fx = TempDir::new("tasks_move_no_overwrite");
+        let src = fx.join("src.txt");
+        let dst = fx.join("dest.txt");
+        std::fs::write(&src, b"src").unwrap();
+        std::fs::write(&dst, b"dest").unwrap();
 
-        assert!(copy_path(
-            &src.join("link"),
-            &dst.join("link"),
-            &mut active,
-            &mut errors,
-            &mut context,
-            false,
-            mode_of(&src.join("link")),
-        ));
-        active.done();
+        // Without a granted overwrite the destination is never touched, even
+        // though the same function would replace it with one.
+        assert!(rename_for_move(&Local, &src, &dst, false).is_err());
+        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+        assert!(src.exists());
+    }
 
-        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
-        assert!(errors[0].contains("already exists"), "{}", errors[0]);
-        assert_eq!(
-            PathBuf::from("raced"),
-            fs::read_link(dst.join("link")).unwrap()
+    #[test]
+    fn a_panicking_job_does_not_stop_the_queue() {
+        // The worker is shared by every file operation. If a panic took it
+        // down, every later operation would register a task, announce itself
+        // at 0%, and never run or finish.
+        queue_operation(|| panic!("a file operation panicked on purpose"));
+        let (tx, rx) = std::sync::mpsc::channel();
+        queue_operation(move || {
+            let _ = tx.send(());
+        });
+
+        assert!(
+            rx.recv_timeout(Duration::from_secs(5)).is_ok(),
+            "the job queued after a panicking one never ran"
         );
     }
 
     #[test]
-    fn a_raced_special_file_is_left_alone_when_that_is_the_answer() {
-        use nix::sys::stat::Mode;
+    fn rename_no_replace_moves_to_new_destination() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("a.txt");
+        std::fs::write(&src, b"x").unwrap();
+        let dst = fx.join("b.txt");
 
-        let (_fx, src, dst) = raced("tasks_raced_fifo");
-        nix::unistd::mkfifo(&src.join("pipe"), Mode::from_bits_truncate(0o644)).unwrap();
-        nix::unistd::mkfifo(&dst.join("pipe"), Mode::from_bits_truncate(0o600)).unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
-        let mut context = answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts);
+        Local.rename_no_replace(&src, &dst).unwrap();
+        assert!(!src.exists());
+        assert_eq!(b"x".to_vec(), std::fs::read(&dst).unwrap());
+    }
 
-        assert!(copy_path(
-            &src.join("pipe"),
-            &dst.join("pipe"),
-            &mut active,
-            &mut errors,
-            &mut context,
-            false,
-            mode_of(&src.join("pipe")),
-        ));
-        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
-        assert_eq!(1, context.skipped);
-        active.done();
+    #[test]
+    fn rename_no_replace_refuses_existing_destination() {
+        let fx = TempDir::new("tasks");
+        let src = fx.join("a.txt");
+        let dst = fx.join("b.txt");
+        std::fs::write(&src, b"src").unwrap();
+        std::fs::write(&dst, b"dst").unwrap();
 
-        // Left exactly as the other process made it.
-        assert_eq!(0o600, mode_of(&dst.join("pipe")) & 0o7777);
+        if let Err(error) = Local.rename_no_replace(&src, &dst) {
+            assert_eq!(std::io::ErrorKind::AlreadyExists, error.kind());
+            // Both files must be untouched.
+            assert_eq!(b"src".to_vec(), std::fs::read(&src).unwrap());
+            assert_eq!(b"dst".to_vec(), std::fs::read(&dst).unwrap());
+        } else {
+            // The filesystem lacks an atomic no-replace rename, so the call
+            // fell back to fs::rename. Assert the fallback's semantics rather
+            // than passing vacuously, so this test cannot rot into a no-op.
+            assert!(!src.exists());
+            assert_eq!(b"src".to_vec(), std::fs::read(&dst).unwrap());
+        }
     }
 
     #[test]
-    fn a_raced_directory_is_never_replaced_so_its_subtree_is_dropped() {
-        let (_fx, src, dst) = raced("tasks_raced_directory");
-        fs::create_dir_all(src.join("sub")).unwrap();
-        fs::write(src.join("sub").join("deep.txt"), b"src").unwrap();
-        fs::create_dir_all(dst.join("sub")).unwrap();
-        let (mut active, mut errors, conflicts) = raced_parts();
-        let mut buffer = [0u8; 64];
-
-        // Only the skip choices apply to a directory, so "skip all" is what can
-        // answer this one; "overwrite all" would still have to ask.
-        assert!(copy_path(
-            &src.join("sub"),
-            &dst.join("sub"),
-            &mut active,
-            &mut errors,
-            &mut answered_context(ConflictChoice::SkipAll, &mut buffer, &conflicts),
-            true,
-            mode_of(&src.join("sub")),
-        ));
-        active.done();
+    fn remove_path_deletes_directory_tree() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("doomed");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (active, _, _) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: String::new(),
+            },
+            1,
+        );
 
-        // Dropped, not merged into: a directory is never replaced, and the copy
-        // does not descend into one it did not create.
-        assert!(!dst.join("sub").join("deep.txt").exists());
+        assert!(remove_path(&Local, &root, true, active).is_some());
+        assert!(!root.exists());
     }
 
     #[test]
-    fn a_raced_name_with_no_paste_behind_it_is_recorded_rather_than_asked_about() {
-        let (_fx, src, dst) = raced("tasks_raced_no_paste");
-        fs::write(src.join("a.txt"), b"src").unwrap();
-        fs::write(dst.join("a.txt"), b"raced").unwrap();
-        let (mut active, mut errors, _conflicts) = raced_parts();
+    fn dir_total_entries_counts_the_root_and_every_descendant() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("tree");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.txt"), b"x").unwrap();
+        std::fs::write(root.join("sub").join("b.txt"), b"x").unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let active = copy_task(tx);
 
-        // No paste at all (an operation that is not one), so there is not even
-        // a standing answer to consult: the entry is left alone and reported.
-        assert!(copy_path(
-            &src.join("a.txt"),
-            &dst.join("a.txt"),
-            &mut active,
-            &mut errors,
-            &mut context(false, &mut [0u8; 64]),
-            false,
-            mode_of(&src.join("a.txt")),
-        ));
+        // root, a.txt, sub, sub/b.txt: one unit per removal the delete walk
+        // makes, so the bar reaches exactly 100% when the tree is gone.
+        assert_eq!(Some(4), dir_total_entries(&Local, &active, &root));
         active.done();
-
-        assert_eq!(1, errors.len(), "expected the collision: {errors:?}");
-        assert!(errors[0].contains("already exists"), "{}", errors[0]);
-        assert_eq!(b"raced".to_vec(), fs::read(dst.join("a.txt")).unwrap());
     }
 
-    // ── finishing a cross-device move, which is the only path that deletes ───
-
-    /// A source tree, and the channel the finished task reports on.
-    fn moved(label: &str) -> (TempDir, PathBuf, mpsc::Receiver<Command>, ActiveTask) {
-        let fx = TempDir::new(label);
-        let src = fx.join("src");
-        fs::create_dir_all(&src).unwrap();
-        fs::write(src.join("a.txt"), b"src").unwrap();
-        let (tx, rx) = mpsc::channel();
-        let active = copy_task(tx);
-        (fx, src, rx, active)
-    }
+    #[test]
+    fn the_pre_scans_stop_when_the_task_is_cancelled() {
+        let fx = TempDir::new("tasks");
+        std::fs::write(fx.join("a.txt"), b"x").unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (active, _, token) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: String::new(),
+            },
+            1,
+        );
+        token.cancel();
 
-    /// The last task the operation sent, which carries how it finished.
-    fn finished_task(rx: &mpsc::Receiver<Command>) -> Task {
-        let mut last = None;
-        while let Ok(command) = rx.try_recv() {
-            if let Command::Progress(task) = command {
-                last = Some(task);
-            }
-        }
-        last.expect("the task sent no progress")
+        // Both scans run before any file is touched and take as long as the
+        // tree is large, so a cancel must not have to wait one out.
+        assert_eq!(None, dir_total_size(&Local, &active, fx.path()));
+        assert_eq!(None, dir_total_entries(&Local, &active, fx.path()));
+        active.done();
     }
 
     #[test]
-    fn a_cross_device_move_removes_its_source_once_every_entry_arrived() {
-        let (_fx, src, rx, active) = moved("tasks_move_complete");
+    fn remove_path_advances_progress_from_the_removals() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("doomed");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        // The total `dir_total_entries` would report: root, sub, sub/f.txt.
+        let (active, _, _) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: String::new(),
+            },
+            3,
+        );
 
-        finish_cross_device_move(active, CopyOutcome::default(), &src, true);
+        let active = remove_path(&Local, &root, true, active).expect("the tree should be removed");
+        let completed: Vec<u64> = rx
+            .try_iter()
+            .filter_map(|command| match command {
+                Command::Progress(task) => {
+                    Some(task.combine_progress(&Progress::default()).completed)
+                }
+                _ => None,
+            })
+            .collect();
+        active.done();
 
-        assert!(!src.exists());
-        assert_eq!(None, finished_task(&rx).error_message());
+        // The bar must advance from the removals themselves rather than from
+        // `done` filling it in at the end, so a long delete shows motion
+        // instead of sitting at 0%.
+        assert_eq!(Some(&1), completed.first());
+        // How many of the three arrive depends on how much of
+        // `PROGRESS_MIN_INTERVAL` this tree takes to remove, which on any
+        // machine fast enough is none of it. What must hold either way is that
+        // each update reports more than the last and none overshoots the total.
+        assert!(completed.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(completed.iter().all(|completed| *completed <= 3));
     }
 
     #[test]
-    fn a_cross_device_move_keeps_its_source_when_an_entry_was_skipped() {
-        let (_fx, src, rx, active) = moved("tasks_move_skipped");
-
-        finish_cross_device_move(
-            active,
-            CopyOutcome {
-                errors: Vec::new(),
-                skipped: 1,
+    fn remove_path_stops_when_already_cancelled() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("kept");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (active, _, token) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: String::new(),
             },
-            &src,
-            true,
+            1,
         );
+        token.cancel();
 
-        // The skipped entry never reached the destination, so removing the
-        // source would delete the only copy of it. A skip is not an error, so
-        // the emptiness of `errors` must not be what decides this.
-        assert!(src.join("a.txt").exists());
-        let message = finished_task(&rx)
-            .error_message()
-            .expect("the kept source must be reported");
-        assert!(message.contains("Skipped 1 entry"), "{message}");
+        assert!(remove_path(&Local, &root, true, active).is_none());
+        assert!(root.join("sub").join("f.txt").exists());
     }
 
-    #[test]
-    fn a_cross_device_move_keeps_its_source_when_an_entry_failed() {
-        let (_fx, src, rx, active) = moved("tasks_move_failed");
-
-        finish_cross_device_move(
-            active,
-            CopyOutcome {
-                errors: vec!["a.txt already exists".to_string()],
-                skipped: 0,
+    fn chmod_task(tx: std::sync::mpsc::Sender<Command>, total: u64) -> ActiveTask {
+        let (active, _, _) = ActiveTask::new(
+            tx,
+            TaskKind::Chmod {
+                path: String::new(),
             },
-            &src,
-            true,
-        );
-
-        assert!(src.join("a.txt").exists());
-        assert_eq!(
-            Some("a.txt already exists".to_string()),
-            finished_task(&rx).error_message()
+            total,
         );
+        active
     }
 
-    /// `copy_path_continues_past_unreadable_entries` degrades to a plain full
-    /// copy under root, so the error-recording path is pinned here instead,
-    /// with a failure the kernel enforces for every user.
     #[test]
-    fn copy_path_records_a_directory_it_cannot_create() {
+    fn chmod_tree_gives_directories_and_files_their_own_modes() {
         let fx = TempDir::new("tasks");
-        let src = fx.join("src");
-        std::fs::create_dir_all(&src).unwrap();
-        std::fs::write(src.join("a.txt"), b"a").unwrap();
-        // A regular file already occupies the destination, so creating the
-        // directory fails with EEXIST regardless of privileges.
-        let dst = fx.join("dst");
-        std::fs::write(&dst, b"in the way").unwrap();
-        let mode = std::fs::symlink_metadata(&src)
-            .unwrap()
-            .permissions()
-            .mode();
+        let root = fx.join("src");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("a.c"), b"x").unwrap();
+        std::fs::set_permissions(root.join("sub"), fs::Permissions::from_mode(0o700)).unwrap();
+        std::fs::set_permissions(root.join("sub/a.c"), fs::Permissions::from_mode(0o600)).unwrap();
         let (tx, _rx) = std::sync::mpsc::channel();
-        let mut active = copy_task(tx);
-        let mut errors = Vec::new();
-
-        // The subtree is skipped rather than failing the whole task, so a
-        // multi-source paste still copies its other sources.
-        assert!(copy_path(
-            &src,
-            &dst,
-            &mut active,
-            &mut errors,
-            &mut context(false, &mut [0u8; 64]),
-            true,
-            mode,
-        ));
-
-        assert_eq!(1, errors.len(), "expected one error: {errors:?}");
-        assert!(errors[0].contains("dst"), "unexpected error: {}", errors[0]);
-        // The occupying file must be left exactly as it was.
-        assert_eq!(b"in the way".to_vec(), std::fs::read(&dst).unwrap());
+        let modes = TreeModes::parse("755 644").unwrap();
+
+        let (active, errors) = chmod_tree(
+            &Local,
+            &root,
+            &modes,
+            &ProtectedPaths::default(),
+            chmod_task(tx, 3),
+        )
+        .unwrap();
         active.done();
-    }
-
-    // ── prepare_destination: the ordering every queued operation depends on ──
 
-    /// A source file holding "src", a destination file holding "dest", and an
-    /// `ActiveTask` for the operation that would replace one with the other.
-    /// The receiver is leaked for the same reason as in `raced_parts`.
-    fn destination(label: &str) -> (TempDir, PathBuf, PathBuf, ActiveTask, CancellationToken) {
-        let fx = TempDir::new(label);
-        let src = fx.join("src.txt");
-        let dst = fx.join("dest.txt");
-        std::fs::write(&src, b"src").unwrap();
-        std::fs::write(&dst, b"dest").unwrap();
-        let (tx, rx) = std::sync::mpsc::channel();
-        std::mem::forget(rx);
-        let (active, _, token) = ActiveTask::new(
-            tx,
-            TaskKind::Copy(Transfer {
-                source: String::new(),
-                destination: String::new(),
-            }),
-            1,
-        );
-        (fx, src, dst, active, token)
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(0o755, mode_of(&root) & 0o7777);
+        assert_eq!(0o755, mode_of(&root.join("sub")) & 0o7777);
+        assert_eq!(0o644, mode_of(&root.join("sub/a.c")) & 0o7777);
     }
 
     #[test]
-    fn a_cancel_that_lands_while_queued_stops_the_task() {
-        let (_fx, _src, dst, active, token) = destination("tasks_prepare_cancelled");
-        token.cancel();
+    fn chmod_tree_makes_a_directory_readable_before_listing_it() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("locked");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.txt"), b"x").unwrap();
+        std::fs::set_permissions(root.join("a.txt"), fs::Permissions::from_mode(0o600)).unwrap();
+        std::fs::set_permissions(&root, fs::Permissions::from_mode(0o000)).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let modes = TreeModes::parse("u+rwX,go+rX").unwrap();
+
+        let (active, errors) = chmod_tree(
+            &Local,
+            &root,
+            &modes,
+            &ProtectedPaths::default(),
+            chmod_task(tx, 2),
+        )
+        .unwrap();
+        active.done();
 
-        // The queue can hold a task for as long as the operations ahead of it
-        // take, so a cancel has to be honored before anything is written, and
-        // before the destination it would have replaced is cleared.
-        assert!(check_cancelled(active).is_none());
-        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+        // Fixing an unreadable tree is the point: listing it first would fail
+        // on the very directory the mode was meant to open up.
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(0o755, mode_of(&root) & 0o7777);
+        assert_eq!(0o644, mode_of(&root.join("a.txt")) & 0o7777);
     }
 
     #[test]
-    fn a_granted_overwrite_clears_the_destination() {
-        let (_fx, src, dst, active, _token) = destination("tasks_prepare_overwrite");
-
-        let active = clear_destination(active, &src, &dst, true).expect("the task should continue");
-
-        assert!(!dst.exists());
+    fn chmod_tree_leaves_symlinks_and_their_targets_alone() {
+        let fx = TempDir::new("tasks");
+        let outside = fx.join("outside.txt");
+        std::fs::write(&outside, b"x").unwrap();
+        std::fs::set_permissions(&outside, fs::Permissions::from_mode(0o600)).unwrap();
+        let root = fx.join("tree");
+        std::fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let modes = TreeModes::parse("755 644").unwrap();
+
+        let (active, errors) = chmod_tree(
+            &Local,
+            &root,
+            &modes,
+            &ProtectedPaths::default(),
+            chmod_task(tx, 2),
+        )
+        .unwrap();
         active.done();
+
+        // `set_permissions` follows a link, so changing one would reach a
+        // file outside the tree.
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(0o600, mode_of(&outside) & 0o7777);
     }
 
     #[test]
-    fn a_destination_survives_when_overwrite_was_not_granted() {
-        let (_fx, src, dst, active, _token) = destination("tasks_prepare_no_overwrite");
-
-        let active =
-            clear_destination(active, &src, &dst, false).expect("the task should continue");
+    fn chmod_tree_skips_a_protected_entry_and_says_so() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("tree");
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::set_permissions(root.join(".git"), fs::Permissions::from_mode(0o700)).unwrap();
+        let protected = protecting(&fx, &["**/.git"]);
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let modes = TreeModes::parse("755").unwrap();
 
-        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+        let (active, errors) =
+            chmod_tree(&Local, &root, &modes, &protected, chmod_task(tx, 2)).unwrap();
         active.done();
+
+        assert_eq!(0o700, mode_of(&root.join(".git")) & 0o7777);
+        assert_eq!(1, errors.len(), "{errors:?}");
+        assert!(errors[0].contains("protected"), "{errors:?}");
     }
 
     #[test]
-    fn a_destination_that_already_vanished_is_not_an_error() {
-        let (_fx, src, dst, active, _token) = destination("tasks_prepare_vanished");
-        std::fs::remove_file(&dst).unwrap();
+    fn chmod_tree_stops_when_already_cancelled() {
+        let fx = TempDir::new("tasks");
+        let root = fx.join("tree");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::set_permissions(&root, fs::Permissions::from_mode(0o700)).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (active, _, token) = ActiveTask::new(
+            tx,
+            TaskKind::Chmod {
+                path: String::new(),
+            },
+            1,
+        );
+        token.cancel();
 
-        // Something else removed it between the prompt and the worker, which
-        // is the outcome the user asked for anyway.
-        assert!(clear_destination(active, &src, &dst, true).is_some());
+        let modes = TreeModes::parse("755").unwrap();
+        assert!(chmod_tree(&Local, &root, &modes, &ProtectedPaths::default(), active).is_none());
+        assert_eq!(0o700, mode_of(&root) & 0o7777);
     }
 
     #[test]
-    fn a_cleared_destination_can_be_opened_by_the_cross_device_move_fallback() {
-        let (_fx, src, dst, active, _token) = destination("tasks_prepare_reopen");
+    fn a_recursive_chmod_of_a_symlink_is_refused_before_queueing() {
+        let fx = TempDir::new("tasks");
+        std::fs::create_dir_all(fx.join("dir")).unwrap();
+        std::os::unix::fs::symlink(fx.join("dir"), fx.join("link")).unwrap();
+        let link = PathInfo::try_from(fx.join("link").as_path()).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
 
-        let active = clear_destination(active, &src, &dst, true).expect("the task should continue");
+        let result = TaskCommand::Chmod(link, TreeModes::parse("755").unwrap()).run(
+            tx,
+            None,
+            &ProtectedPaths::default(),
+            1,
+            1,
+        );
 
-        // A move across filesystems cannot rename, so it falls back to a byte
-        // copy that opens the destination with `create_new`. That open fails
-        // unless the destination was cleared first, which is why clearing is
-        // not skippable just because a rename would have replaced it.
-        assert!(open_files(&src, &dst).is_ok());
-        active.done();
+        assert!(result.cancel_info.is_none());
     }
 
-    #[test]
-    fn a_vanished_source_leaves_the_destination_it_would_have_replaced() {
-        let (_fx, src, dst, active, _token) = destination("tasks_prepare_source_gone");
-        std::fs::remove_file(&src).unwrap();
-
-        // A task can wait behind a long operation on the shared worker, so the
-        // source it was going to copy may be gone by the time it runs. Clearing
-        // the destination for a copy that can no longer happen would leave the
-        // user with neither entry.
-        assert!(clear_destination(active, &src, &dst, true).is_none());
-        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+    fn compress_task(total: u64) -> (ActiveTask, CancellationToken) {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let (active, _, token) = ActiveTask::new(
+            tx,
+            TaskKind::Compress {
+                path: String::new(),
+            },
+            total,
+        );
+        (active, token)
     }
 
     #[test]
-    fn a_failed_overwriting_move_leaves_the_destination_in_place() {
-        let fx = TempDir::new("tasks_move_failure");
-        let src = fx.join("gone.txt");
-        let dst = fx.join("dest.txt");
-        std::fs::write(&dst, b"dest").unwrap();
+    fn pack_names_entries_below_the_archive_and_leaves_out_special_files() {
+        let fx = TempDir::new("tasks_pack");
+        std::fs::create_dir_all(fx.join("dir/sub")).unwrap();
+        std::fs::write(fx.join("dir/sub/b.txt"), b"bb").unwrap();
+        std::fs::write(fx.join("dir/a.txt"), b"a").unwrap();
+        std::fs::write(fx.join("c.txt"), b"ccc").unwrap();
+        nix::unistd::mkfifo(
+            &fx.join("dir/fifo"),
+            nix::sys::stat::Mode::from_bits_truncate(0o644),
+        )
+        .unwrap();
+        let archive = fx.join("out.tar");
+        let (active, _token) = compress_task(6);
+
+        let (active, errors) = pack(
+            &Local,
+            &archive,
+            ArchiveFormat::Tar,
+            &[fx.join("dir"), fx.join("c.txt")],
+            active,
+        )
+        .unwrap();
 
-        // The source vanished while the task sat in the queue, which a single
-        // worker running a long copy makes easy. Clearing the destination up
-        // front would lose it for a move that then cannot happen, leaving the
-        // user with neither file.
-        assert!(rename_for_move(&src, &dst, true).is_err());
-        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
+        active.done();
+        // The FIFO, which would block a read forever, is reported rather than
+        // packed.
+        assert_eq!(1, errors.len(), "{errors:?}");
+        assert!(errors[0].contains("fifo"), "{errors:?}");
+        let mut tar = tar::Archive::new(File::open(&archive).unwrap());
+        let names: Vec<String> = tar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            vec!["dir", "dir/a.txt", "dir/sub", "dir/sub/b.txt", "c.txt"],
+            names
+        );
     }
 
     #[test]
-    fn an_overwriting_move_replaces_a_file_without_clearing_it_first() {
-        let fx = TempDir::new("tasks_move_atomic");
-        let src = fx.join("src.txt");
-        let dst = fx.join("dest.txt");
-        std::fs::write(&src, b"src").unwrap();
-        std::fs::write(&dst, b"dest").unwrap();
+    fn pack_removes_the_partial_archive_when_cancelled() {
+        let fx = TempDir::new("tasks_pack_cancelled");
+        std::fs::write(fx.join("a.txt"), b"a").unwrap();
+        let archive = fx.join("out.zip");
+        let (active, token) = compress_task(1);
+        token.cancel();
 
-        // The kernel replaces atomically here, so there is no moment in which
-        // the destination is missing.
-        rename_for_move(&src, &dst, true).unwrap();
-        assert_eq!(b"src".to_vec(), std::fs::read(&dst).unwrap());
-        assert!(!src.exists());
+        assert!(
+            pack(
+                &Local,
+                &archive,
+                ArchiveFormat::Zip,
+                &[fx.join("a.txt")],
+                active
+            )
+            .is_none()
+        );
+        assert!(!archive.exists());
     }
 
     #[test]
-    fn an_overwriting_move_clears_a_file_that_a_directory_must_replace() {
-        let fx = TempDir::new("tasks_move_dir_over_file");
-        let src = fx.join("srcdir");
-        std::fs::create_dir_all(&src).unwrap();
-        std::fs::write(src.join("inner.txt"), b"src").unwrap();
-        let dst = fx.join("dest");
-        std::fs::write(&dst, b"dest").unwrap();
+    fn a_compress_into_an_existing_name_is_refused_before_queueing() {
+        let fx = TempDir::new("tasks_compress_exists");
+        std::fs::write(fx.join("a.txt"), b"a").unwrap();
+        std::fs::write(fx.join("a.tar"), b"keep").unwrap();
+        let path = PathInfo::try_from(fx.join("a.txt").as_path()).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
 
-        // `rename` refuses to replace a non-directory with a directory, so
-        // this is the one case that has to clear the destination itself.
-        rename_for_move(&src, &dst, true).unwrap();
-        assert_eq!(
-            b"src".to_vec(),
-            std::fs::read(dst.join("inner.txt")).unwrap()
+        let result = TaskCommand::Compress(vec![path], fx.join("a.tar"), ArchiveFormat::Tar).run(
+            tx,
+            None,
+            &ProtectedPaths::default(),
+            1,
+            1,
         );
+
+        assert!(result.cancel_info.is_none());
+        assert_eq!(b"keep".to_vec(), std::fs::read(fx.join("a.tar")).unwrap());
     }
 
-    #[test]
-    fn a_move_without_overwrite_refuses_an_existing_destination() {
-        let fx = TempDir::new("tasks_move_no_overwrite");
-        let src = fx.join("src.txt");
-        let dst = fx.join("dest.txt");
-        std::fs::write(&src, b"src").unwrap();
-        std::fs::write(&dst, b"dest").unwrap();
+    fn extract_task() -> ActiveTask {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let kind = TaskKind::Extract(Transfer {
+            source: String::new(),
+            destination: String::new(),
+        });
+        ActiveTask::new(tx, kind, 1).0
+    }
 
-        // Without a granted overwrite the destination is never touched, even
-        // though the same function would replace it with one.
-        assert!(rename_for_move(&src, &dst, false).is_err());
-        assert_eq!(b"dest".to_vec(), std::fs::read(&dst).unwrap());
-        assert!(src.exists());
+    /// A tar at `a.tar` in `fx`, with the entries `build` appends.
+    fn tar_with(fx: &TempDir, build: impl FnOnce(&mut tar::Builder<File>)) -> PathBuf {
+        let path = fx.join("a.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+        build(&mut builder);
+        builder.finish().unwrap();
+        path
+    }
+
+    fn tar_file(builder: &mut tar::Builder<File>, name: &str, mode: u32, contents: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(mode);
+        header.set_size(contents.len() as u64);
+        builder.append_data(&mut header, name, contents).unwrap();
+    }
+
+    fn tar_link(
+        builder: &mut tar::Builder<File>,
+        kind: tar::EntryType,
+        name: &str,
+        target: impl AsRef<Path>,
+    ) {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(kind);
+        header.set_mode(0o777);
+        header.set_size(0);
+        builder.append_link(&mut header, name, target).unwrap();
+    }
+
+    fn extraction(archive: PathBuf, destination: PathBuf) -> Extraction {
+        Extraction {
+            archive,
+            format: ArchiveFormat::Tar,
+            root: PathBuf::new(),
+            destination,
+            replace: Vec::new(),
+            skip: BTreeSet::new(),
+        }
     }
 
     #[test]
-    fn a_panicking_job_does_not_stop_the_queue() {
-        // The worker is shared by every file operation. If a panic took it
-        // down, every later operation would register a task, announce itself
-        // at 0%, and never run or finish.
-        queue_operation(|| panic!("a file operation panicked on purpose"));
-        let (tx, rx) = std::sync::mpsc::channel();
-        queue_operation(move || {
-            let _ = tx.send(());
+    fn unpack_writes_files_directories_and_links() {
+        let fx = TempDir::new("tasks_unpack");
+        let archive = tar_with(&fx, |builder| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Directory);
+            header.set_mode(0o750);
+            header.set_size(0);
+            builder
+                .append_data(&mut header, "dir", std::io::empty())
+                .unwrap();
+            tar_file(builder, "dir/a.txt", 0o4640, b"hello");
+            tar_link(builder, tar::EntryType::Symlink, "dir/link", "a.txt");
+            tar_link(builder, tar::EntryType::Link, "dir/hard", "dir/a.txt");
         });
+        let destination = fx.join("a");
 
-        assert!(
-            rx.recv_timeout(Duration::from_secs(5)).is_ok(),
-            "the job queued after a panicking one never ran"
+        let (active, errors) = unpack(
+            &Local,
+            &extraction(archive, destination.clone()),
+            extract_task(),
+        )
+        .unwrap();
+
+        active.done();
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            b"hello".to_vec(),
+            fs::read(destination.join("dir/a.txt")).unwrap()
+        );
+        assert_eq!(
+            b"hello".to_vec(),
+            fs::read(destination.join("dir/hard")).unwrap()
+        );
+        assert_eq!(
+            Path::new("a.txt"),
+            fs::read_link(destination.join("dir/link")).unwrap()
         );
+        // The setuid bit an archive from anywhere carries is not restored.
+        let mode = |path: &str| {
+            fs::metadata(destination.join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o7777
+        };
+        assert_eq!(0o640, mode("dir/a.txt"));
+        assert_eq!(0o750, mode("dir"));
     }
 
     #[test]
-    fn rename_no_replace_moves_to_new_destination() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("a.txt");
-        std::fs::write(&src, b"x").unwrap();
-        let dst = fx.join("b.txt");
+    fn unpack_refuses_entries_that_would_land_outside_the_destination() {
+        let fx = TempDir::new("tasks_unpack_outside");
+        let outside = fx.join("outside");
+        fs::create_dir(&outside).unwrap();
+        let archive = tar_with(&fx, |builder| {
+            // The tar crate refuses to write a `..` name, so it is set by hand.
+            let mut header = tar::Header::new_gnu();
+            let name = b"../evil.txt";
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_mode(0o644);
+            header.set_size(1);
+            header.set_cksum();
+            builder.append(&header, &b"x"[..]).unwrap();
+            // A symlink out, then a file through it.
+            tar_link(builder, tar::EntryType::Symlink, "link", &outside);
+            tar_file(builder, "link/through.txt", 0o644, b"x");
+            // A hard link to something the archive never held.
+            tar_link(builder, tar::EntryType::Link, "hard", outside.join("x"));
+            tar_file(builder, "ok.txt", 0o644, b"ok");
+        });
+        let destination = fx.join("a");
 
-        rename_no_replace(&src, &dst).unwrap();
-        assert!(!src.exists());
-        assert_eq!(b"x".to_vec(), std::fs::read(&dst).unwrap());
+        let (active, errors) = unpack(
+            &Local,
+            &extraction(archive, destination.clone()),
+            extract_task(),
+        )
+        .unwrap();
+
+        active.done();
+        assert_eq!(3, errors.len(), "{errors:?}");
+        assert!(!fx.join("evil.txt").exists());
+        assert!(!outside.join("through.txt").exists());
+        assert!(!destination.join("hard").exists());
+        assert_eq!(
+            b"ok".to_vec(),
+            fs::read(destination.join("ok.txt")).unwrap()
+        );
     }
 
     #[test]
-    fn rename_no_replace_refuses_existing_destination() {
-        let fx = TempDir::new("tasks");
-        let src = fx.join("a.txt");
-        let dst = fx.join("b.txt");
-        std::fs::write(&src, b"src").unwrap();
-        std::fs::write(&dst, b"dst").unwrap();
-
-        if let Err(error) = rename_no_replace(&src, &dst) {
-            assert_eq!(std::io::ErrorKind::AlreadyExists, error.kind());
-            // Both files must be untouched.
-            assert_eq!(b"src".to_vec(), std::fs::read(&src).unwrap());
-            assert_eq!(b"dst".to_vec(), std::fs::read(&dst).unwrap());
-        } else {
-            // The filesystem lacks an atomic no-replace rename, so the call
-            // fell back to fs::rename. Assert the fallback's semantics rather
-            // than passing vacuously, so this test cannot rot into a no-op.
-            assert!(!src.exists());
-            assert_eq!(b"src".to_vec(), std::fs::read(&dst).unwrap());
+    fn unpack_replaces_and_skips_only_what_it_was_told_to() {
+        let fx = TempDir::new("tasks_unpack_conflicts");
+        let destination = fx.join("dest");
+        fs::create_dir(&destination).unwrap();
+        for name in ["replaced.txt", "skipped.txt", "unasked.txt"] {
+            fs::write(destination.join(name), b"old").unwrap();
         }
-    }
+        let archive = tar_with(&fx, |builder| {
+            for name in ["replaced.txt", "skipped.txt", "unasked.txt"] {
+                tar_file(builder, name, 0o644, b"new");
+            }
+            // A name an archive holds twice ends up as the later one, as
+            // `tar` leaves it.
+            tar_file(builder, "twice.txt", 0o644, b"first");
+            tar_file(builder, "twice.txt", 0o644, b"second");
+        });
+        let mut extraction = extraction(archive, destination.clone());
+        extraction.replace.push(destination.join("replaced.txt"));
+        extraction.skip.insert("skipped.txt".into());
 
-    #[test]
-    fn remove_path_deletes_directory_tree() {
-        let fx = TempDir::new("tasks");
-        let root = fx.join("doomed");
-        std::fs::create_dir_all(root.join("sub")).unwrap();
-        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let (active, _, _) = ActiveTask::new(
-            tx,
-            TaskKind::Delete {
-                path: String::new(),
-            },
-            1,
-        );
+        let (active, errors) = unpack(&Local, &extraction, extract_task()).unwrap();
 
-        assert!(remove_path(&root, true, active).is_some());
-        assert!(!root.exists());
+        active.done();
+        let read = |name: &str| fs::read(destination.join(name)).unwrap();
+        assert_eq!(b"new".to_vec(), read("replaced.txt"));
+        assert_eq!(b"old".to_vec(), read("skipped.txt"));
+        // A name taken since the prompts were answered is not replaced.
+        assert_eq!(b"old".to_vec(), read("unasked.txt"));
+        assert_eq!(1, errors.len(), "{errors:?}");
+        assert_eq!(b"second".to_vec(), read("twice.txt"));
     }
 
     #[test]
-    fn dir_total_entries_counts_the_root_and_every_descendant() {
-        let fx = TempDir::new("tasks");
-        let root = fx.join("tree");
-        std::fs::create_dir_all(root.join("sub")).unwrap();
-        std::fs::write(root.join("a.txt"), b"x").unwrap();
-        std::fs::write(root.join("sub").join("b.txt"), b"x").unwrap();
-        let (tx, _rx) = std::sync::mpsc::channel();
-        let active = copy_task(tx);
+    fn unpack_of_a_root_extracts_it_alone_under_its_own_name() {
+        let fx = TempDir::new("tasks_unpack_root");
+        let archive = tar_with(&fx, |builder| {
+            tar_file(builder, "a/b/c.txt", 0o644, b"c");
+            tar_link(builder, tar::EntryType::Link, "a/b/hard", "a/b/c.txt");
+            tar_file(builder, "a/bb.txt", 0o644, b"bb");
+            tar_file(builder, "other.txt", 0o644, b"other");
+        });
+        let destination = fx.join("dest");
+        let mut extraction = extraction(archive, destination.clone());
+        extraction.root = PathBuf::from("a/b");
+
+        let (active, errors) = unpack(&Local, &extraction, extract_task()).unwrap();
 
-        // root, a.txt, sub, sub/b.txt: one unit per removal the delete walk
-        // makes, so the bar reaches exactly 100% when the tree is gone.
-        assert_eq!(Some(4), dir_total_entries(&active, &root));
         active.done();
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            b"c".to_vec(),
+            fs::read(destination.join("b/c.txt")).unwrap()
+        );
+        assert_eq!(b"c".to_vec(), fs::read(destination.join("b/hard")).unwrap());
+        // A sibling whose name merely starts with the root's is not below it.
+        assert!(!destination.join("bb.txt").exists());
+        assert!(!destination.join("other.txt").exists());
     }
 
     #[test]
-    fn the_pre_scans_stop_when_the_task_is_cancelled() {
-        let fx = TempDir::new("tasks");
-        std::fs::write(fx.join("a.txt"), b"x").unwrap();
+    fn a_cancelled_unpack_stops_before_the_next_entry() {
+        let fx = TempDir::new("tasks_unpack_cancelled");
+        let archive = tar_with(&fx, |builder| tar_file(builder, "a.txt", 0o644, b"a"));
         let (tx, _rx) = std::sync::mpsc::channel();
-        let (active, _, token) = ActiveTask::new(
-            tx,
-            TaskKind::Delete {
-                path: String::new(),
-            },
-            1,
-        );
+        let kind = TaskKind::Extract(Transfer {
+            source: String::new(),
+            destination: String::new(),
+        });
+        let (active, _, token) = ActiveTask::new(tx, kind, 1);
         token.cancel();
 
-        // Both scans run before any file is touched and take as long as the
-        // tree is large, so a cancel must not have to wait one out.
-        assert_eq!(None, dir_total_size(&active, fx.path()));
-        assert_eq!(None, dir_total_entries(&active, fx.path()));
-        active.done();
+        assert!(unpack(&Local, &extraction(archive, fx.join("a")), active).is_none());
+        assert!(!fx.join("a/a.txt").exists());
     }
 
     #[test]
-    fn remove_path_advances_progress_from_the_removals() {
+    fn touch_tree_sets_every_entry_but_not_a_symlinks_target() {
         let fx = TempDir::new("tasks");
-        let root = fx.join("doomed");
+        let outside = fx.join("outside.txt");
+        std::fs::write(&outside, b"x").unwrap();
+        let root = fx.join("tree");
         std::fs::create_dir_all(root.join("sub")).unwrap();
-        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
-        let (tx, rx) = std::sync::mpsc::channel();
-        // The total `dir_total_entries` would report: root, sub, sub/f.txt.
+        std::fs::write(root.join("sub/a.txt"), b"x").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let before = Stamp::of(&outside).unwrap();
+        let stamp = Stamp::parse("2001-02-03 04:05:06", chrono::Local::now()).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
         let (active, _, _) = ActiveTask::new(
             tx,
-            TaskKind::Delete {
+            TaskKind::Touch {
                 path: String::new(),
             },
-            3,
+            4,
         );
 
-        let active = remove_path(&root, true, active).expect("the tree should be removed");
-        let completed: Vec<u64> = rx
-            .try_iter()
-            .filter_map(|command| match command {
-                Command::Progress(task) => {
-                    Some(task.combine_progress(&Progress::default()).completed)
-                }
-                _ => None,
-            })
-            .collect();
+        let (active, errors) =
+            touch_tree(&Local, &root, stamp, &ProtectedPaths::default(), active).unwrap();
         active.done();
 
-        // The bar must advance from the removals themselves rather than from
-        // `done` filling it in at the end, so a long delete shows motion
-        // instead of sitting at 0%.
-        assert_eq!(Some(&1), completed.first());
-        // How many of the three arrive depends on how much of
-        // `PROGRESS_MIN_INTERVAL` this tree takes to remove, which on any
-        // machine fast enough is none of it. What must hold either way is that
-        // each update reports more than the last and none overshoots the total.
-        assert!(completed.windows(2).all(|pair| pair[0] < pair[1]));
-        assert!(completed.iter().all(|completed| *completed <= 3));
+        assert!(errors.is_empty(), "{errors:?}");
+        for path in [&root, &root.join("sub"), &root.join("sub/a.txt")] {
+            assert_eq!(stamp, Stamp::of(path).unwrap(), "{}", path.display());
+        }
+        assert_eq!(before, Stamp::of(&outside).unwrap());
     }
 
     #[test]
-    fn remove_path_stops_when_already_cancelled() {
+    fn chown_tree_changes_a_symlink_rather_than_its_target() {
+        use std::os::unix::fs::MetadataExt;
+
         let fx = TempDir::new("tasks");
-        let root = fx.join("kept");
-        std::fs::create_dir_all(root.join("sub")).unwrap();
-        std::fs::write(root.join("sub").join("f.txt"), b"x").unwrap();
+        let outside = fx.join("outside.txt");
+        std::fs::write(&outside, b"x").unwrap();
+        let root = fx.join("tree");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.txt"), b"x").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        let gid = std::fs::metadata(&outside).unwrap().gid();
+        let owner = Owner {
+            uid: None,
+            gid: Some(gid + 1),
+        };
+        // Giving a file away takes privilege. Probe whether this environment
+        // has it rather than inspecting the euid, so the assertions below
+        // match the environment instead of being skipped in it.
+        let probe = fx.join("probe");
+        std::fs::write(&probe, b"x").unwrap();
+        let can_chown = owner.apply(&probe, false).is_ok();
         let (tx, _rx) = std::sync::mpsc::channel();
-        let (active, _, token) = ActiveTask::new(
+        let (active, _, _) = ActiveTask::new(
             tx,
-            TaskKind::Delete {
+            TaskKind::Chown {
                 path: String::new(),
             },
-            1,
+            3,
         );
-        token.cancel();
 
-        assert!(remove_path(&root, true, active).is_none());
-        assert!(root.join("sub").join("f.txt").exists());
+        let (active, errors) =
+            chown_tree(&Local, &root, owner, &ProtectedPaths::default(), active).unwrap();
+        active.done();
+
+        // `chown` follows a link, so changing one that way would reach a file
+        // outside the tree.
+        assert_eq!(gid, std::fs::metadata(&outside).unwrap().gid());
+        if can_chown {
+            assert!(errors.is_empty(), "{errors:?}");
+            let link = std::fs::symlink_metadata(root.join("link")).unwrap();
+            assert_eq!(gid + 1, link.gid());
+            assert_eq!(
+                gid + 1,
+                std::fs::metadata(root.join("a.txt")).unwrap().gid()
+            );
+        } else {
+            assert_eq!(3, errors.len(), "{errors:?}");
+        }
     }
 
     #[test]
@@ -2673,7 +5006,7 @@ mod tests {
 
         // A cancel observed while listing yields Ok(None), so the caller
         // aborts instead of deleting a directory it never finished reading.
-        assert!(list_entries(&active, fx.path()).unwrap().is_none());
+        assert!(list_entries(&Local, &active, fx.path()).unwrap().is_none());
     }
 
     #[test]
@@ -2719,4 +5052,217 @@ mod tests {
         assert_eq!(target_mode, 0o600, "copy must not chmod the symlink target");
         assert_eq!(std::fs::read(&target).unwrap(), b"hello");
     }
+
+    #[test]
+    fn a_copy_reads_and_writes_through_its_backend() {
+        let memory = Memory::default();
+        memory
+            .file("/src/a.txt", b"a")
+            .file("/src/sub/b.txt", b"b")
+            .link("/src/link", "a.txt");
+        let modified = std::time::UNIX_EPOCH + Duration::from_secs(1_000);
+        memory
+            .set_times(Path::new("/src/a.txt"), None, Some(modified))
+            .unwrap();
+        memory.set_mode(Path::new("/src/a.txt"), 0o600).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+        let mut buffer = [0u8; 64];
+        let mut context = CopyContext {
+            backend: &memory,
+            ..context(true, &mut buffer)
+        };
+
+        let mode = memory.stat(Path::new("/src")).unwrap().mode;
+        assert!(copy_path(
+            Path::new("/src"),
+            Path::new("/dst"),
+            &mut active,
+            &mut errors,
+            &mut context,
+            true,
+            mode,
+        ));
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
+
+        let copied = memory.stat(Path::new("/dst/a.txt")).unwrap();
+        assert_eq!(Some(modified), copied.modified);
+        assert_eq!(0o600, copied.mode & 0o7777);
+        let mut contents = String::new();
+        memory
+            .read(Path::new("/dst/sub/b.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!("b", contents);
+        assert_eq!(
+            PathBuf::from("a.txt"),
+            memory.read_link(Path::new("/dst/link")).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_delete_lists_and_removes_through_its_backend() {
+        let memory = Memory::default();
+        memory
+            .file("/doomed/a.txt", b"a")
+            .file("/doomed/sub/b.txt", b"b")
+            .link("/doomed/link", "/elsewhere");
+        memory.create_dir(Path::new("/elsewhere")).unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let active = copy_task(tx);
+
+        // doomed, a.txt, sub, sub/b.txt, link: the link is not descended into.
+        assert_eq!(
+            Some(5),
+            dir_total_entries(&memory, &active, Path::new("/doomed"))
+        );
+        let active = remove_path(&memory, Path::new("/doomed"), true, active)
+            .expect("the tree should be removed");
+        active.done();
+
+        assert!(!memory.exists(Path::new("/doomed")).unwrap());
+        assert!(memory.exists(Path::new("/elsewhere")).unwrap());
+    }
+
+    #[test]
+    fn a_synced_entry_replaces_its_destination_and_keeps_its_time() {
+        let fx = TempDir::new("tasks_sync");
+        fs::create_dir_all(fx.join("left")).unwrap();
+        fs::create_dir_all(fx.join("right/sub")).unwrap();
+        fs::write(fx.join("left/a.txt"), b"newer").unwrap();
+        fs::write(fx.join("right/a.txt"), b"old").unwrap();
+        fs::write(fx.join("right/sub/b.txt"), b"b").unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(fx.join("right/sub/b.txt"))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        let (tx, _rx) = std::sync::mpsc::channel();
+        let mut active = copy_task(tx);
+        let mut errors = Vec::new();
+        let mut buffer = [0u8; 64];
+        let mut context = context(true, &mut buffer);
+
+        for (source, destination) in [
+            (fx.join("left/a.txt"), fx.join("right/a.txt")),
+            // Into a directory the left side does not have yet.
+            (fx.join("right/sub/b.txt"), fx.join("left/sub/b.txt")),
+        ] {
+            assert!(sync_entry(
+                &source,
+                &destination,
+                &mut active,
+                &mut errors,
+                &mut context
+            ));
+        }
+        assert!(errors.is_empty(), "unexpected errors: {errors:?}");
+        active.done();
+
+        assert_eq!(b"newer".to_vec(), fs::read(fx.join("right/a.txt")).unwrap());
+        assert_eq!(b"b".to_vec(), fs::read(fx.join("left/sub/b.txt")).unwrap());
+        assert_eq!(
+            old,
+            fx.join("left/sub/b.txt")
+                .metadata()
+                .unwrap()
+                .modified()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn a_sync_over_a_protected_entry_is_refused_before_queueing() {
+        let fx = TempDir::new("tasks_sync");
+        let root = fx.path().canonicalize().unwrap();
+        fs::write(root.join("a"), b"a").unwrap();
+        fs::write(root.join("b"), b"keep").unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let result = TaskCommand::Sync(SyncPlan {
+            left: root.clone(),
+            right: root.clone(),
+            copies: vec![(root.join("a"), root.join("b"))],
+            removals: Vec::new(),
+        })
+        .run(tx, None, &protecting(&fx, &["b"]), 1, 1);
+
+        assert!(result.cancel_info.is_none());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(b"keep".to_vec(), fs::read(root.join("b")).unwrap());
+    }
+    #[test]
+    fn a_mirror_deletes_what_the_source_lacks_and_replaces_a_directory_with_a_file() {
+        let fx = TempDir::new("tasks_mirror");
+        let root = fx.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("left")).unwrap();
+        fs::create_dir_all(root.join("right/kind/inside")).unwrap();
+        fs::create_dir_all(root.join("right/extra_dir")).unwrap();
+        fs::write(root.join("left/kind"), b"file").unwrap();
+        fs::write(root.join("right/kind/inside/a"), b"a").unwrap();
+        fs::write(root.join("right/extra_dir/b"), b"b").unwrap();
+        fs::write(root.join("right/extra"), b"extra").unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let result = TaskCommand::Mirror(SyncPlan {
+            left: root.join("left"),
+            right: root.join("right"),
+            copies: vec![(root.join("left/kind"), root.join("right/kind"))],
+            removals: vec![root.join("right/extra"), root.join("right/extra_dir")],
+        })
+        .run(tx, None, &protecting(&fx, &[]), 1, 64);
+
+        assert!(result.cancel_info.is_some());
+        let task = loop {
+            let Ok(Command::Progress(task)) = rx.recv_timeout(Duration::from_secs(10)) else {
+                panic!("the mirror should have reported");
+            };
+            if task.is_terminal() {
+                break task;
+            }
+        };
+        assert_eq!(None, task.error_message());
+        assert!(!task.is_cancelled());
+        assert_eq!(b"file".to_vec(), fs::read(root.join("right/kind")).unwrap());
+        assert!(!root.join("right/extra").exists());
+        assert!(!root.join("right/extra_dir").exists());
+    }
+
+    #[test]
+    fn a_verification_reports_each_listed_file_against_its_digest() {
+        let fx = TempDir::new("tasks_checksum");
+        fs::write(fx.join("empty"), b"").unwrap();
+        fs::write(fx.join("changed"), b"changed").unwrap();
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        TaskCommand::Checksum(ChecksumJob {
+            algorithm: ChecksumAlgorithm::Sha256,
+            directory: fx.path().to_path_buf(),
+            sums: Some(fx.join("SHA256SUMS")),
+            files: vec![
+                (fx.join("empty"), Some(empty.to_string())),
+                (fx.join("changed"), Some(empty.to_string())),
+                (fx.join("missing"), Some(empty.to_string())),
+            ],
+        })
+        .run(tx, None, &protecting(&fx, &[]), 1, 64);
+
+        let report = loop {
+            let command = rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("the verification should have reported");
+            if let Command::Checksummed(report) = command {
+                break report;
+            }
+        };
+        let verified: Vec<_> = report.entries.iter().map(ChecksumEntry::verified).collect();
+        assert_eq!(vec![Some(true), Some(false), Some(false)], verified);
+        assert!(report.entries[2].digest.is_err());
+    }
 }
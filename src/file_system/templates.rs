@@ -0,0 +1,67 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use super::path_info::PathInfo;
+
+/// The directory "new from template" lists: `$XDG_TEMPLATES_DIR` when set, then
+/// the one `user-dirs.dirs` names, then `~/Templates`, which is where both
+/// GNOME and KDE put it when nothing says otherwise.
+pub fn templates_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_TEMPLATES_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let dirs = directories::UserDirs::new()?;
+    Some(
+        dirs.template_dir()
+            .map_or_else(|| dirs.home_dir().join("Templates"), Path::to_path_buf),
+    )
+}
+
+/// The templates in `dir`, sorted by name: every file directly inside it,
+/// including one reached through a symlink. Hidden files are left out, as file
+/// managers leave them out of their "new document" menus, and so are
+/// subdirectories, whose contents a single copy would not take along.
+pub fn templates_in(dir: &Path) -> Vec<PathInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<PathInfo> = entries
+        .flatten()
+        .filter_map(|entry| PathInfo::try_from(entry.path().as_path()).ok())
+        .filter(|info| !info.is_hidden() && info.path.is_file())
+        .collect();
+    templates.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+    templates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn templates_are_the_visible_files_sorted_by_name() {
+        let fx = TempDir::new("templates");
+        fs::write(fx.join("Text.txt"), b"").unwrap();
+        fs::write(fx.join("Script.sh"), b"#!/bin/sh\n").unwrap();
+        fs::write(fx.join(".hidden"), b"").unwrap();
+        fs::create_dir_all(fx.join("Office")).unwrap();
+        std::os::unix::fs::symlink(fx.join("Text.txt"), fx.join("Linked.txt")).unwrap();
+
+        let names: Vec<String> = templates_in(fx.path())
+            .into_iter()
+            .map(|info| info.display_name)
+            .collect();
+
+        assert_eq!(vec!["Linked.txt", "Script.sh", "Text.txt"], names);
+    }
+
+    #[test]
+    fn a_missing_directory_has_no_templates() {
+        let fx = TempDir::reserved("templates_missing");
+
+        assert!(templates_in(fx.path()).is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{io, path::Path, time::SystemTime};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rustix::fs::{AtFlags, CWD, Timespec, Timestamps, UTIME_OMIT, utimensat};
+
+use super::backend::Backend;
+
+/// The date-and-time forms the touch prompt accepts, tried in order. All are
+/// local time, as `touch -d` reads them.
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+const TIME_FORMATS: [&str; 2] = ["%H:%M:%S", "%H:%M"];
+
+/// The access and modification times a touch sets, resolved once up front so
+/// every entry of a recursive touch gets the same ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Stamp {
+    accessed: SystemTime,
+    modified: SystemTime,
+}
+
+impl Stamp {
+    /// `now`, a date (`2024-05-01`, at midnight), a date and time
+    /// (`2024-05-01 13:45`, seconds optional), or a time today (`13:45`).
+    /// Both times are set to the one given, as `touch -d` sets them.
+    pub fn parse(input: &str, now: DateTime<Local>) -> Result<Self> {
+        let input = input.trim();
+        let at = if input.is_empty() || input.eq_ignore_ascii_case("now") {
+            now
+        } else {
+            let naive = parse_naive(input, now.date_naive()).ok_or_else(|| {
+                anyhow!(
+                    "Invalid time {input:?}: expected now, YYYY-MM-DD, YYYY-MM-DD HH:MM[:SS] or HH:MM[:SS]"
+                )
+            })?;
+            // `earliest` picks the first of an hour repeated when the clocks go
+            // back; an hour skipped when they go forward has no answer.
+            Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .ok_or_else(|| anyhow!("Invalid time {input:?}: it does not exist locally"))?
+        };
+        let at = SystemTime::from(at);
+        Ok(Self {
+            accessed: at,
+            modified: at,
+        })
+    }
+
+    /// The times of `reference`, or of what it points at, as `touch -r` takes
+    /// them.
+    pub fn of(reference: &Path) -> io::Result<Self> {
+        let metadata = reference.metadata()?;
+        Ok(Self {
+            accessed: metadata.accessed()?,
+            modified: metadata.modified()?,
+        })
+    }
+
+    /// Sets the times of `path`, or of what it points at when `follow` and it
+    /// is a symlink. Through `utimensat` rather than an open file, so an entry
+    /// the user can change but not read is still touched.
+    pub fn apply(self, path: &Path, follow: bool) -> io::Result<()> {
+        let flags = if follow {
+            AtFlags::empty()
+        } else {
+            AtFlags::SYMLINK_NOFOLLOW
+        };
+        let times = Timestamps {
+            last_access: timespec(self.accessed),
+            last_modification: timespec(self.modified),
+        };
+        Ok(utimensat(CWD, path, &times, flags)?)
+    }
+
+    /// Sets the times of `path` through `backend`, not following a final
+    /// symlink, as a recursive touch sets them.
+    pub fn apply_through(self, backend: &dyn Backend, path: &Path) -> io::Result<()> {
+        backend.set_times(path, Some(self.accessed), Some(self.modified))
+    }
+}
+
+fn parse_naive(input: &str, today: NaiveDate) -> Option<NaiveDateTime> {
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .or_else(|| {
+            TIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(input, format).ok())
+                .map(|time| today.and_time(time))
+        })
+}
+
+/// `time`, or when there is none, the kernel's marker for leaving a time as it
+/// is.
+pub(super) fn timespec_or_omit(time: Option<SystemTime>) -> Timespec {
+    time.map_or(
+        Timespec {
+            tv_sec: 0,
+            tv_nsec: UTIME_OMIT,
+        },
+        timespec,
+    )
+}
+
+/// Through chrono, which splits a time before the epoch into whole seconds
+/// and a positive remainder the way the kernel expects.
+fn timespec(time: SystemTime) -> Timespec {
+    let utc = DateTime::<Utc>::from(time);
+    Timespec {
+        tv_sec: utc.timestamp(),
+        tv_nsec: utc.timestamp_subsec_nanos().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap()
+    }
+
+    fn local(input: &str) -> Option<String> {
+        let stamp = Stamp::parse(input, now()).ok()?;
+        assert_eq!(stamp.accessed, stamp.modified);
+        Some(
+            DateTime::<Local>::from(stamp.modified)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        )
+    }
+
+    #[test_case("now" => Some("2025-03-14 15:09:26".to_string()) ; "now")]
+    #[test_case("" => Some("2025-03-14 15:09:26".to_string()) ; "empty is now")]
+    #[test_case("2024-05-01" => Some("2024-05-01 00:00:00".to_string()) ; "a date is at midnight")]
+    #[test_case("2024-05-01 13:45" => Some("2024-05-01 13:45:00".to_string()) ; "a date and time")]
+    #[test_case("2024-05-01T13:45:30" => Some("2024-05-01 13:45:30".to_string()) ; "iso 8601")]
+    #[test_case("08:30" => Some("2025-03-14 08:30:00".to_string()) ; "a time is today")]
+    #[test_case("yesterday" => None ; "a word")]
+    #[test_case("2024-13-01" => None ; "no such month")]
+    fn parse_cases(input: &str) -> Option<String> {
+        local(input)
+    }
+
+    #[test]
+    fn apply_sets_both_times_and_of_reads_them_back() {
+        let fx = TempDir::new("times");
+        fs::write(fx.join("a.txt"), b"x").unwrap();
+        let stamp = Stamp::parse("2001-02-03 04:05:06", now()).unwrap();
+
+        stamp.apply(&fx.join("a.txt"), true).unwrap();
+
+        assert_eq!(stamp, Stamp::of(&fx.join("a.txt")).unwrap());
+    }
+
+    #[test]
+    fn apply_without_follow_leaves_a_symlinks_target_alone() {
+        let fx = TempDir::new("times_symlink");
+        fs::write(fx.join("target.txt"), b"x").unwrap();
+        std::os::unix::fs::symlink(fx.join("target.txt"), fx.join("link")).unwrap();
+        let before = Stamp::of(&fx.join("target.txt")).unwrap();
+
+        Stamp::parse("2001-02-03", now())
+            .unwrap()
+            .apply(&fx.join("link"), false)
+            .unwrap();
+
+        assert_eq!(before, Stamp::of(&fx.join("target.txt")).unwrap());
+    }
+}
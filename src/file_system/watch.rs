@@ -9,44 +9,47 @@ use std::{
 };
 
 use anyhow::Result;
-use log::{debug, error, warn};
-use notify::{Event, RecommendedWatcher, Watcher, recommended_watcher};
+use log::{debug, error};
 
+use super::backend::{Backend, Change, Watch};
 use crate::{command::Command, file_system::debounce};
 
 pub struct DirectoryWatcher {
+    backend: Arc<dyn Backend>,
     debounce_threshold: Duration,
     handles: Vec<thread::JoinHandle<()>>,
-    notify_rx: Option<Receiver<std::result::Result<Event, notify::Error>>>,
+    /// What every watch reports its changes into. Option so `Drop` can
+    /// `.take()` it: once it and the watch holding its clone are gone, the
+    /// threads waiting on the receiver see the channel close and exit.
+    changes_tx: Option<Sender<Change>>,
+    changes_rx: Option<Receiver<Change>>,
     watched_directory: Option<PathBuf>,
-    /// Option so `Drop` can `.take()` it before joining the watcher threads.
-    /// Dropping the watcher closes the notify channel sender, which unblocks
-    /// the threads waiting on the receiver so they can exit.
-    watcher: Option<RecommendedWatcher>,
+    watch: Option<Watch>,
 }
 
 impl DirectoryWatcher {
-    pub fn try_new(debounce_ms: u64) -> Result<Self> {
-        let (notify_tx, notify_rx) = channel();
-        let watcher = recommended_watcher(notify_tx)?;
-        Ok(Self {
+    pub fn new(backend: Arc<dyn Backend>, debounce_ms: u64) -> Self {
+        let (changes_tx, changes_rx) = channel();
+        Self {
+            backend,
             debounce_threshold: Duration::from_millis(debounce_ms),
             handles: Vec::new(),
-            notify_rx: Some(notify_rx),
-            watcher: Some(watcher),
+            changes_tx: Some(changes_tx),
+            changes_rx: Some(changes_rx),
             watched_directory: None,
-        })
+            watch: None,
+        }
     }
 
     pub fn run_once(&mut self, command_tx: &Sender<Command>) {
         // Already running, do nothing
-        let Some(notify_rx) = self.notify_rx.take() else {
+        let Some(changes_rx) = self.changes_rx.take() else {
             return;
         };
 
         let (delayed_tx, delayed_rx) = channel();
         let command_tx_for_delayed = command_tx.clone();
-        let command_tx_for_notify = command_tx.clone();
+        let command_tx_for_changes = command_tx.clone();
         // Shared between both threads so a dispatched delayed refresh counts
         // as a trigger (clearing the delayed flag and resetting the window).
         let debouncer = Arc::new(Mutex::new(debounce::TimeDebouncer::new(
@@ -61,77 +64,77 @@ impl DirectoryWatcher {
             );
         }));
         self.handles.push(thread::spawn(move || {
-            watch_for_notify_events(&command_tx_for_notify, &delayed_tx, notify_rx, &debouncer);
+            watch_for_changes(&command_tx_for_changes, &delayed_tx, changes_rx, &debouncer);
         }));
     }
 
     pub(super) fn watch_directory(&mut self, path: PathBuf) -> Result<()> {
-        let Some(watcher) = &mut self.watcher else {
+        let Some(changes_tx) = &self.changes_tx else {
             return Ok(());
         };
         // Rewatch even when the path is unchanged: an external delete and
         // recreate invalidates the watch on the old inode, and a refresh has to
         // re-register on the new one. The bookkeeping is cleared before
-        // unwatching and set only after a successful watch, so
+        // the old watch is dropped and set only after a successful watch, so
         // `watched_directory` never names a path without an active watch.
-        if let Some(old_path) = self.watched_directory.take()
-            && let Err(e) = watcher.unwatch(old_path.as_path())
-        {
-            warn!("Failed to unwatch directory: {e}");
-        }
-
-        watcher.watch(path.as_path(), notify::RecursiveMode::NonRecursive)?;
+        self.watched_directory = None;
+        self.watch = None;
+        self.watch = Some(self.backend.watch(&path, changes_tx.clone())?);
         self.watched_directory = Some(path);
         Ok(())
     }
+
+    /// Drops the current watch, for a directory nothing can watch (one on
+    /// another host), so changes to the one left behind stop refreshing it.
+    pub(super) fn unwatch(&mut self) {
+        self.watched_directory = None;
+        self.watch = None;
+    }
 }
 
 impl Drop for DirectoryWatcher {
     fn drop(&mut self) {
-        // Drop the watcher first: it owns the notify channel sender. Dropping it
-        // causes notify_rx.recv() to return Err, which exits watch_for_notify_events,
-        // which in turn drops delayed_tx, exiting watch_for_delayed_commands.
-        // Without this, handle.join() below would block forever.
-        self.watcher.take();
+        // Drop the senders first: the watch holds one and this holds the
+        // other. Dropping both causes changes_rx.recv() to return Err, which
+        // exits watch_for_changes, which in turn drops delayed_tx, exiting
+        // watch_for_delayed_commands. Without this, handle.join() below would
+        // block forever.
+        self.watch.take();
+        self.changes_tx.take();
         for handle in self.handles.drain(..) {
             let _ = handle.join();
         }
     }
 }
 
-/// Debounces file system events into refreshes, on a background thread. An event
+/// Debounces changes into refreshes, on a background thread. A change
 /// arriving after the debounce window refreshes at once; one arriving inside it
 /// schedules a single delayed refresh, so a burst produces one refresh at the
-/// front and one at the end rather than one per event.
-fn watch_for_notify_events(
+/// front and one at the end rather than one per change.
+fn watch_for_changes(
     command_tx: &Sender<Command>,
     delayed_tx: &Sender<Duration>,
-    notify_rx: Receiver<std::result::Result<Event, notify::Error>>,
+    changes_rx: Receiver<Change>,
     debouncer: &Arc<Mutex<debounce::TimeDebouncer>>,
 ) {
-    for result in notify_rx {
-        match result {
-            Ok(event) => match event.kind {
-                notify::EventKind::Create(_)
-                | notify::EventKind::Modify(_)
-                | notify::EventKind::Remove(_) => {
-                    let mut debouncer = debouncer.lock().unwrap();
-                    if debouncer.should_trigger(Instant::now()) {
-                        if let Err(e) = command_tx.send(Command::RefreshDirectory) {
-                            error!("Failed to send refresh command: {e}");
-                        }
-                    } else if !debouncer.has_delayed_event() {
-                        let delay = debouncer.remaining(Instant::now());
-                        if let Err(e) = delayed_tx.send(delay) {
-                            error!("Failed to schedule delayed refresh: {e}");
-                        } else {
-                            debouncer.set_delayed_event();
-                        }
+    for change in changes_rx {
+        match change {
+            Change::Changed => {
+                let mut debouncer = debouncer.lock().unwrap();
+                if debouncer.should_trigger(Instant::now()) {
+                    if let Err(e) = command_tx.send(Command::RefreshDirectory) {
+                        error!("Failed to send refresh command: {e}");
+                    }
+                } else if !debouncer.has_delayed_event() {
+                    let delay = debouncer.remaining(Instant::now());
+                    if let Err(e) = delayed_tx.send(delay) {
+                        error!("Failed to schedule delayed refresh: {e}");
+                    } else {
+                        debouncer.set_delayed_event();
                     }
                 }
-                _ => (),
-            },
-            Err(e) => {
+            }
+            Change::Failed(e) => {
                 error!("File system watcher error: {e}");
                 let error_command = Command::AlertError(format!(
                     "Failed to run the directory watcher in the background: {e}"
@@ -175,13 +178,14 @@ fn watch_for_delayed_commands(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file_system::backend::Local;
     use crate::test_support::TempDir;
 
     #[test]
     fn watch_directory_tracks_only_successful_watches() {
         let temp = TempDir::new("watch");
         let dir = temp.path().to_path_buf();
-        let mut watcher = DirectoryWatcher::try_new(100).unwrap();
+        let mut watcher = DirectoryWatcher::new(Arc::new(Local), 100);
 
         watcher.watch_directory(dir.clone()).unwrap();
         assert_eq!(Some(&dir), watcher.watched_directory.as_ref());
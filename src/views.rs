@@ -1,13 +1,24 @@
 mod alerts;
 mod breadcrumbs;
+mod checksums;
+mod compare;
+mod diff;
+mod disk_usage;
+mod duplicates;
 mod help;
+mod miller;
 mod notices;
 mod open_with;
+mod panes;
+mod permissions;
+mod preview;
 mod prompt;
 pub mod root;
 mod scrollbar;
 mod status;
 mod table;
+mod tabs;
+mod templates;
 mod unicode;
 
 pub use help::keybindings_help_text;
@@ -76,7 +87,11 @@ impl ListingMode {
     /// not change the mode.
     pub(super) fn transition(command: &Command) -> Option<Self> {
         match command {
-            Command::NavigatedDirectory { .. } | Command::ResetView => Some(Self::Normal),
+            // A pane or tab brought into focus is a directory listing, until
+            // what it showed is run again: see `PanesView::switched_to`.
+            Command::NavigatedDirectory { .. }
+            | Command::ResetView
+            | Command::SwitchedPane { .. } => Some(Self::Normal),
             // The table rejects an empty query, so it must not enter search mode.
             Command::StartSearch(query) if !query.is_empty() => Some(Self::Search),
             Command::Bookmarks { .. } => Some(Self::Bookmarks),
@@ -13,6 +13,10 @@ use crate::{command::result::CommandResult, file_system::path_info::PathInfo};
 #[derive(Default)]
 pub(super) struct BreadcrumbsView {
     breadcrumbs: Vec<String>,
+    /// The directory the breadcrumbs spell, when they spell one rather than
+    /// the bookmarks directory. A click walks up from it, since a crumb inside
+    /// an archive or on another host names nothing on this machine's disk.
+    directory: Option<PathInfo>,
     /// Which listing the header describes; transitions come solely from
     /// `ListingMode::transition`.
     mode: ListingMode,
@@ -58,10 +62,15 @@ impl BreadcrumbsView {
 
     fn set_directory(&mut self, directory: &PathInfo) -> CommandResult {
         self.breadcrumbs = directory.breadcrumbs();
+        self.directory = Some(directory.clone());
         CommandResult::Handled
     }
 
     fn to_path(&self, end_index: usize) -> Option<PathInfo> {
+        if let Some(directory) = &self.directory {
+            let steps = self.breadcrumbs.len().checked_sub(end_index + 1)?;
+            return (0..steps).try_fold(directory.clone(), |path, _| path.parent());
+        }
         if let Some(components) = self.breadcrumbs.get(0..=end_index) {
             let path = if components.len() == 1 {
                 // Clicked on the root element, which is empty string
@@ -129,6 +138,24 @@ mod tests {
         assert_eq!(None, view.to_path(2).map(|info| info.path));
     }
 
+    /// A crumb on another host names nothing on this disk, so the click walks
+    /// up from the directory over its session instead.
+    #[test]
+    fn clicking_a_remote_breadcrumb_stays_on_its_host() {
+        let memory = std::sync::Arc::new(crate::file_system::backend::Memory::default());
+        memory.file("/srv/www/index.html", b"");
+        let remote = crate::file_system::sftp::stand_in("crumbs.test", memory);
+        let mut view = BreadcrumbsView::default();
+        view.set_directory(
+            &PathInfo::read_remote(&remote, std::path::Path::new("/srv/www")).unwrap(),
+        );
+
+        assert_eq!("crumbs.test:", view.breadcrumbs[0]);
+        let parent = view.to_path(1).unwrap();
+        assert_eq!(std::path::PathBuf::from("/srv"), parent.path);
+        assert_eq!(Some(&remote), parent.remote());
+    }
+
     #[test]
     fn search_after_bookmarks_shows_the_search_tag() {
         Config::init_test();
@@ -42,6 +42,7 @@ impl CommandHandler for BreadcrumbsView {
             Command::Bookmarks { .. } => {
                 let dir = Config::global().bookmarks_dir();
                 self.breadcrumbs = path_breadcrumbs(&dir);
+                self.directory = None;
                 self.positions.clear();
                 CommandResult::Handled
             }
@@ -0,0 +1,248 @@
+mod handler;
+mod view;
+mod widget;
+
+use ratatui::layout::Rect;
+
+use super::{
+    ScrollbarView,
+    open_with::{clamp_scroll, clamp_selection},
+};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, result::CommandResult},
+    file_system::{
+        checksums::{ChecksumEntry, ChecksumReport},
+        path_info::compact,
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+
+/// Lists the checksums a task computed, one line per file, or when a checksum
+/// file was verified, whether each file it lists matched. The selected
+/// file's digest can be copied, to paste beside a published one. Shown in
+/// place of the table once the task is done, like the pickers, and over a
+/// scanning view without closing it.
+pub(super) struct ChecksumView {
+    area: Rect,
+    /// The rows' area, for hit testing a click on a row.
+    content_area: Rect,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    inner_height: usize,
+    is_visible: bool,
+    report: Option<ChecksumReport>,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+    selected: usize,
+}
+
+impl ChecksumView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            content_area: Rect::default(),
+            hint: format!(
+                "(Press {} to copy, {} to close)",
+                kb.hint_for(&[Action::Copy]),
+                kb.hint_for(&[Action::ResetView])
+            ),
+            inner_height: 0,
+            is_visible: false,
+            report: None,
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+            selected: 0,
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Show `report`, in place of any shown before it.
+    pub(super) fn show(&mut self, report: &ChecksumReport) {
+        self.inner_height = 0;
+        self.is_visible = true;
+        self.report = Some(report.clone());
+        self.scroll_offset = 0;
+        self.selected = 0;
+    }
+
+    pub(super) fn hide(&mut self) {
+        self.is_visible = false;
+        self.report = None;
+    }
+
+    fn entries(&self) -> &[ChecksumEntry] {
+        self.report
+            .as_ref()
+            .map_or(&[], |report| report.entries.as_slice())
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.entries().len().saturating_sub(self.inner_height)
+    }
+
+    fn select(&mut self, index: usize) -> CommandResult {
+        self.selected = index.min(self.entries().len().saturating_sub(1));
+        self.scroll_offset = clamp_scroll(self.inner_height, self.selected, self.scroll_offset);
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let page = self.inner_height.max(1);
+        match action {
+            Action::SelectNext => self.select(self.selected.saturating_add(1)),
+            Action::SelectPrevious => self.select(self.selected.saturating_sub(1)),
+            Action::PageDown => self.select(self.selected.saturating_add(page)),
+            Action::PageUp => self.select(self.selected.saturating_sub(page)),
+            Action::SelectFirst => self.select(0),
+            Action::SelectLast => self.select(usize::MAX),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.scroll_offset = offset;
+        self.selected = clamp_selection(
+            self.inner_height,
+            self.entries().len(),
+            offset,
+            self.selected,
+        );
+    }
+
+    /// The path of `entry` as shown: below the report's directory, or in
+    /// full when it is elsewhere, as a search result can be.
+    fn name(&self, entry: &ChecksumEntry) -> String {
+        self.report
+            .as_ref()
+            .and_then(|report| entry.path.strip_prefix(&report.directory).ok())
+            .map_or_else(
+                || compact(&entry.path).to_string(),
+                |relative| relative.display().to_string(),
+            )
+    }
+
+    /// Puts the selected file's digest on the clipboard, on its own, as it
+    /// is compared against a published one.
+    fn copy_selected(&self) -> CommandResult {
+        let (Some(report), Some(entry)) = (&self.report, self.entries().get(self.selected)) else {
+            return CommandResult::Handled;
+        };
+        match &entry.digest {
+            Ok(digest) => vec![
+                Command::SetClipboardText(digest.clone()),
+                Command::AlertInfo(format!(
+                    "Copied the {} of {}",
+                    report.algorithm.label(),
+                    self.name(entry)
+                )),
+            ]
+            .into(),
+            Err(error) => {
+                Command::AlertWarn(format!("No checksum of {}: {error}", self.name(entry))).into()
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        let Some(report) = &self.report else {
+            return String::new();
+        };
+        let algorithm = report.algorithm.label();
+        let Some(sums) = &report.sums else {
+            return match report.entries.as_slice() {
+                [entry] => format!("{algorithm} of {}", self.name(entry)),
+                entries => format!("{algorithm} of {} files", entries.len()),
+            };
+        };
+        let verified = |wanted: bool| {
+            report
+                .entries
+                .iter()
+                .filter(|entry| entry.verified() == Some(wanted))
+                .count()
+        };
+        let (matched, failed) = (verified(true), verified(false));
+        let sums = sums.file_name().map_or_else(
+            || compact(sums).to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        if failed == 0 {
+            format!("Verify {sums} ({algorithm}): all {matched} OK")
+        } else {
+            format!("Verify {sums} ({algorithm}): {failed} FAILED, {matched} OK")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChecksumView;
+    use crate::{
+        app::config::Config,
+        command::Command,
+        file_system::checksums::{ChecksumAlgorithm, ChecksumEntry, ChecksumReport},
+    };
+
+    fn entry(name: &str, digest: Result<&str, &str>, expected: Option<&str>) -> ChecksumEntry {
+        ChecksumEntry {
+            path: format!("/data/{name}").into(),
+            digest: digest.map(Into::into).map_err(Into::into),
+            expected: expected.map(Into::into),
+        }
+    }
+
+    fn shown(sums: Option<&str>, entries: Vec<ChecksumEntry>) -> ChecksumView {
+        Config::init_test();
+        let mut view = ChecksumView::new();
+        view.show(&ChecksumReport {
+            algorithm: ChecksumAlgorithm::Sha256,
+            directory: "/data".into(),
+            sums: sums.map(Into::into),
+            entries,
+        });
+        view
+    }
+
+    #[test]
+    fn the_selected_digest_is_copied_on_its_own() {
+        let mut view = shown(
+            None,
+            vec![entry("a", Ok("aa"), None), entry("b", Err("gone"), None)],
+        );
+        assert_eq!("SHA-256 of 2 files", view.title());
+
+        let commands = view.copy_selected().into_commands();
+        assert_eq!(Command::SetClipboardText("aa".into()), commands[0]);
+
+        view.select(1);
+        assert!(matches!(
+            Command::try_from(view.copy_selected()),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
+
+    #[test]
+    fn a_verification_counts_what_failed() {
+        let view = shown(
+            Some("/data/SHA256SUMS"),
+            vec![
+                entry("a", Ok("aa"), Some("aa")),
+                entry("b", Ok("bb"), Some("00")),
+                entry("c", Err("gone"), Some("cc")),
+            ],
+        );
+        assert_eq!("Verify SHA256SUMS (SHA-256): 2 FAILED, 1 OK", view.title());
+
+        let view = shown(
+            Some("/data/SHA256SUMS"),
+            vec![entry("a", Ok("aa"), Some("AA"))],
+        );
+        assert_eq!("Verify SHA256SUMS (SHA-256): all 1 OK", view.title());
+    }
+}
@@ -0,0 +1,73 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::ChecksumView;
+use crate::{
+    app::config::{
+        Config,
+        keybindings::{Action, hardcoded_normal_action},
+    },
+    command::{handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for ChecksumView {
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        let action = hardcoded_normal_action(code, modifiers)
+            .or_else(|| Config::global().keybindings.normal_action(code, modifiers));
+        match action {
+            Some(Action::Copy) => self.copy_selected(),
+            // The keys that asked for the checksums close them, as the keys
+            // that open the pickers do.
+            Some(Action::Checksum | Action::ChecksumBlake3 | Action::VerifyChecksums) => {
+                self.hide();
+                CommandResult::Handled
+            }
+            Some(action) => self.handle_scroll_action(action),
+            None => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> CommandResult {
+        match event.kind {
+            MouseEventKind::ScrollDown => self.handle_scroll_action(Action::SelectNext),
+            MouseEventKind::ScrollUp => self.handle_scroll_action(Action::SelectPrevious),
+            MouseEventKind::Down(MouseButton::Left)
+            | MouseEventKind::Up(MouseButton::Left)
+            | MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(offset) = self.scrollbar_view.handle_mouse(event, self.max_scroll()) {
+                    self.scroll_to(offset);
+                } else if matches!(event.kind, MouseEventKind::Down(MouseButton::Left))
+                    && self.content_area.contains(Position {
+                        x: event.column,
+                        y: event.row,
+                    })
+                {
+                    let row = event.row.saturating_sub(self.content_area.y) as usize;
+                    let index = self.scroll_offset + row;
+                    // A click on the blank space below the last row leaves the
+                    // selection be.
+                    if index < self.entries().len() {
+                        self.select(index);
+                    }
+                }
+                CommandResult::Handled
+            }
+            // Claim everything else so a stray event cannot leak to the views
+            // this one is covering.
+            _ => CommandResult::Handled,
+        }
+    }
+
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        matches!(
+            event.kind,
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) || self.scrollbar_view.is_dragging()
+            || self.area.contains(Position {
+                x: event.column,
+                y: event.row,
+            })
+    }
+}
@@ -0,0 +1,89 @@
+use ratatui::{
+    buffer::CellWidth,
+    text::{Line, Span},
+};
+
+use super::ChecksumView;
+use crate::{app::config::Config, file_system::checksums::ChecksumEntry};
+
+/// A line per file: its digest, or when verifying, whether it matched, then
+/// its path. A file that could not be read says why in place of its digest.
+pub(super) fn build_rows(view: &ChecksumView, width: u16) -> Vec<Line<'static>> {
+    view.entries()
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| entry_row(view, index == view.selected, width, entry))
+        .collect()
+}
+
+/// What the first column says of `entry`, and whether that calls for
+/// attention.
+fn outcome(entry: &ChecksumEntry) -> (String, bool) {
+    match (entry.verified(), &entry.digest) {
+        (Some(true), _) => ("OK".into(), false),
+        (Some(false), Ok(_)) => ("FAILED".into(), true),
+        (Some(false), Err(error)) => (format!("FAILED: {error}"), true),
+        (None, Ok(digest)) => (digest.clone(), false),
+        (None, Err(error)) => (error.clone(), true),
+    }
+}
+
+fn entry_row(
+    view: &ChecksumView,
+    is_selected: bool,
+    width: u16,
+    entry: &ChecksumEntry,
+) -> Line<'static> {
+    let theme = Config::global().theme();
+    let (outcome, is_notable) = outcome(entry);
+    // Digests line up in a column of their own; a verdict is short.
+    let column = if entry.expected.is_some() { 6 } else { 64 };
+    let outcome = format!("{outcome:column$}");
+    let name = view.name(entry);
+    let used = 3 + outcome.cell_width() as usize + 2 + name.cell_width() as usize;
+    let padding = " ".repeat((width as usize).saturating_sub(used));
+
+    if is_selected {
+        // One span inheriting the line style, as in the pickers.
+        return Line::styled(
+            format!("   {outcome}  {name}{padding}"),
+            theme.open_with.selected(),
+        );
+    }
+    let outcome_style = if is_notable {
+        theme.open_with.shortcut()
+    } else {
+        theme.open_with.detail()
+    };
+    Line::from(vec![
+        Span::raw("   "),
+        Span::styled(outcome, outcome_style),
+        Span::raw("  "),
+        Span::raw(name),
+        Span::raw(padding),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::outcome;
+    use crate::file_system::checksums::ChecksumEntry;
+
+    #[test_case(Ok("aa"), None => ("aa".to_string(), false) ; "computed")]
+    #[test_case(Err("gone"), None => ("gone".to_string(), true) ; "unreadable")]
+    #[test_case(Ok("aa"), Some("aa") => ("OK".to_string(), false) ; "matched")]
+    #[test_case(Ok("aa"), Some("bb") => ("FAILED".to_string(), true) ; "mismatched")]
+    #[test_case(Err("gone"), Some("aa") => ("FAILED: gone".to_string(), true) ; "missing")]
+    fn a_row_leads_with_the_digest_or_the_verdict(
+        digest: Result<&str, &str>,
+        expected: Option<&str>,
+    ) -> (String, bool) {
+        outcome(&ChecksumEntry {
+            path: "a".into(),
+            digest: digest.map(Into::into).map_err(Into::into),
+            expected: expected.map(Into::into),
+        })
+    }
+}
@@ -0,0 +1,675 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::{collections::HashSet, path::PathBuf};
+
+use ratatui::layout::Rect;
+
+use super::{
+    ScrollbarView,
+    open_with::{clamp_scroll, clamp_selection},
+};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{
+        Command,
+        progress::{Task, TaskKind},
+        result::CommandResult,
+    },
+    file_system::{
+        compare::{CompareEntry, CompareStatus, MirrorStep},
+        path_info::{PathInfo, compact},
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+
+/// A mirror of one directory onto the other, previewed before anything is
+/// written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Mirror {
+    /// From the left onto the right, or the other way.
+    to_right: bool,
+    /// Whether what only the destination has is deleted, or kept.
+    delete: bool,
+}
+
+/// Lists two directory trees side by side, one line per path below either,
+/// saying which side has it, whether they match, and which is newer. What is
+/// missing or older on one side can then be copied across from the other in
+/// one task. Entries are marked here rather than in the table, to copy only
+/// those. Shown in place of the table, like the disk usage view, whose
+/// scrolling and styles it shares.
+///
+/// One side can instead be made to mirror the other, as `rsync -a --delete`
+/// would, for a backup. The mirror is previewed first: the view then lists
+/// only what it would copy, replace or delete, and nothing is written until
+/// it is confirmed.
+pub(super) struct CompareView {
+    area: Rect,
+    /// The rows' area, for hit testing a click on a row.
+    content_area: Rect,
+    /// Whether files of the same size are compared byte for byte, rather than
+    /// by modification time.
+    contents: bool,
+    /// The merged listing, once the comparison completes.
+    entries: Option<Vec<CompareEntry>>,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    inner_height: usize,
+    is_visible: bool,
+    left: PathBuf,
+    /// Relative paths.
+    marked: HashSet<PathBuf>,
+    /// The mirror being previewed, if any.
+    mirror: Option<Mirror>,
+    /// Bordered header hint while previewing a mirror, cached at
+    /// construction.
+    mirror_hint: String,
+    /// Entries compared so far.
+    progress: u64,
+    right: PathBuf,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+    selected: usize,
+    /// The progress notice's name for a copy started from here that has not
+    /// finished, to compare again once it has.
+    syncing: Option<String>,
+}
+
+impl CompareView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            content_area: Rect::default(),
+            contents: false,
+            entries: None,
+            hint: format!(
+                "(Press {} to close)",
+                kb.hint_for(&[Action::CompareDirectories, Action::ResetView])
+            ),
+            inner_height: 0,
+            is_visible: false,
+            left: PathBuf::new(),
+            marked: HashSet::new(),
+            mirror: None,
+            mirror_hint: format!(
+                "(Press {} to mirror, {} to delete extras or keep them, {} to go back)",
+                kb.hint_for(&[Action::Open]),
+                kb.hint_for(&[Action::Delete]),
+                kb.hint_for(&[Action::ResetView])
+            ),
+            progress: 0,
+            right: PathBuf::new(),
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+            selected: 0,
+            syncing: None,
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Show the view for `left` and `right` and ask for their comparison, by
+    /// modification time.
+    pub(super) fn show(&mut self, left: &PathInfo, right: &PathInfo) -> CommandResult {
+        self.is_visible = true;
+        self.contents = false;
+        self.left.clone_from(&left.path);
+        self.right.clone_from(&right.path);
+        self.recompare()
+    }
+
+    /// Hide the view, stopping a comparison still running. `None` when it was
+    /// not showing.
+    pub(super) fn hide(&mut self) -> Option<Command> {
+        if !self.is_visible {
+            return None;
+        }
+        self.is_visible = false;
+        self.entries = None;
+        self.marked.clear();
+        self.mirror = None;
+        self.syncing = None;
+        Some(Command::CancelCompare)
+    }
+
+    /// Forget the entries and compare the directories again. A mirror being
+    /// previewed stays, to preview against the new entries.
+    fn recompare(&mut self) -> CommandResult {
+        self.entries = None;
+        self.inner_height = 0;
+        self.marked.clear();
+        self.progress = 0;
+        self.scroll_offset = 0;
+        self.selected = 0;
+        vec![
+            Command::CancelCompare,
+            Command::CompareDirectories {
+                left: self.left.clone(),
+                right: self.right.clone(),
+                contents: self.contents,
+            },
+        ]
+        .into()
+    }
+
+    /// Switches between comparing by modification time and byte for byte,
+    /// and compares again.
+    fn toggle_contents(&mut self) -> CommandResult {
+        self.contents = !self.contents;
+        self.recompare()
+    }
+
+    /// The entries shown, each with what the mirror being previewed would do
+    /// to it: every entry, or while previewing, only those it would change.
+    fn listed(&self) -> Vec<(&CompareEntry, Option<MirrorStep>)> {
+        let Some(entries) = &self.entries else {
+            return Vec::new();
+        };
+        match self.mirror {
+            None => entries.iter().map(|entry| (entry, None)).collect(),
+            Some(Mirror { to_right, delete }) => entries
+                .iter()
+                .filter_map(|entry| {
+                    let step = entry.status.mirror_step(to_right, delete)?;
+                    Some((entry, Some(step)))
+                })
+                .collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.listed().len()
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.len().saturating_sub(self.inner_height)
+    }
+
+    fn select(&mut self, index: usize) -> CommandResult {
+        self.selected = index.min(self.len().saturating_sub(1));
+        self.scroll_offset = clamp_scroll(self.inner_height, self.selected, self.scroll_offset);
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let page = self.inner_height.max(1);
+        match action {
+            Action::SelectNext => self.select(self.selected.saturating_add(1)),
+            Action::SelectPrevious => self.select(self.selected.saturating_sub(1)),
+            Action::PageDown => self.select(self.selected.saturating_add(page)),
+            Action::PageUp => self.select(self.selected.saturating_sub(page)),
+            Action::SelectFirst => self.select(0),
+            Action::SelectLast => self.select(usize::MAX),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.scroll_offset = offset;
+        self.selected = clamp_selection(self.inner_height, self.len(), offset, self.selected);
+    }
+
+    /// Previews mirroring the left directory onto the right when `to_right`,
+    /// or the right onto the left, keeping whether extras are deleted from a
+    /// preview the other way.
+    fn preview_mirror(&mut self, to_right: bool) -> CommandResult {
+        if self.entries.is_none() {
+            return CommandResult::Handled;
+        }
+        let delete = self.mirror.is_some_and(|mirror| mirror.delete);
+        self.mirror = Some(Mirror { to_right, delete });
+        self.select(0)
+    }
+
+    /// Back from a mirror's preview to the comparison.
+    fn leave_preview(&mut self) -> CommandResult {
+        self.mirror = None;
+        self.select(0)
+    }
+
+    /// Switches between deleting and keeping what only the destination of
+    /// the mirror being previewed has.
+    fn toggle_deletions(&mut self) -> CommandResult {
+        if let Some(mirror) = &mut self.mirror {
+            mirror.delete = !mirror.delete;
+        }
+        self.select(self.selected)
+    }
+
+    /// Runs the mirror being previewed, in one task, and goes back to the
+    /// comparison, which compares again once the task has finished.
+    fn mirror_across(&mut self) -> CommandResult {
+        let Some(Mirror { to_right, .. }) = self.mirror else {
+            return CommandResult::Handled;
+        };
+        let (source, destination) = if to_right {
+            (&self.left, &self.right)
+        } else {
+            (&self.right, &self.left)
+        };
+        let mut copies = Vec::new();
+        let mut removals = Vec::new();
+        let mut skipped = 0;
+        for (entry, step) in self.listed() {
+            match step {
+                Some(MirrorStep::Copy | MirrorStep::Replace) => copies.push((
+                    source.join(&entry.relative),
+                    destination.join(&entry.relative),
+                )),
+                Some(MirrorStep::Delete) => removals.push(destination.join(&entry.relative)),
+                Some(MirrorStep::Skip) => skipped += 1,
+                None => {}
+            }
+        }
+        if copies.is_empty() && removals.is_empty() && skipped > 0 {
+            return Command::AlertWarn(format!(
+                "Nothing to mirror: {skipped} unreadable {} skipped",
+                if skipped == 1 {
+                    "directory is"
+                } else {
+                    "directories are"
+                }
+            ))
+            .into();
+        }
+        if copies.is_empty() && removals.is_empty() {
+            return Command::AlertWarn(format!(
+                "Nothing to mirror: {} already matches {}",
+                compact(destination),
+                compact(source)
+            ))
+            .into();
+        }
+        let command = Command::MirrorCompared {
+            source: source.clone(),
+            destination: destination.clone(),
+            copies,
+            removals,
+        };
+        self.syncing = Some(TaskKind::mirror_name(source, destination));
+        self.mirror = None;
+        self.select(0);
+        command.into()
+    }
+
+    fn toggle_mark(&mut self) -> CommandResult {
+        if let Some(entry) = self.entries.as_ref().and_then(|e| e.get(self.selected))
+            && !self.marked.remove(&entry.relative)
+        {
+            self.marked.insert(entry.relative.clone());
+        }
+        self.select(self.selected.saturating_add(1))
+    }
+
+    /// Copies each entry that is missing or older on one side from the other,
+    /// the marked ones if there are any, in one task. Entries that merely
+    /// differ are left alone: nothing says which of the two to keep.
+    fn copy_across(&mut self) -> CommandResult {
+        let Some(entries) = &self.entries else {
+            return CommandResult::Handled;
+        };
+        let copies: Vec<_> = entries
+            .iter()
+            .filter(|entry| self.marked.is_empty() || self.marked.contains(&entry.relative))
+            .filter_map(|entry| {
+                let (left, right) = (
+                    self.left.join(&entry.relative),
+                    self.right.join(&entry.relative),
+                );
+                if entry.status.copies_to_right()? {
+                    Some((left, right))
+                } else {
+                    Some((right, left))
+                }
+            })
+            .collect();
+        if copies.is_empty() {
+            return Command::AlertWarn(if self.marked.is_empty() {
+                "Nothing to copy: no entry is missing or newer on either side".into()
+            } else {
+                "Nothing to copy: no marked entry is missing or newer on either side".into()
+            })
+            .into();
+        }
+        self.syncing = Some(TaskKind::sync_name(&self.left, &self.right));
+        Command::CopyCompared {
+            left: self.left.clone(),
+            right: self.right.clone(),
+            copies,
+        }
+        .into()
+    }
+
+    fn progressed(&mut self, entries: u64) -> CommandResult {
+        if self.entries.is_none() {
+            self.progress = entries;
+        }
+        CommandResult::Handled
+    }
+
+    fn compared(&mut self, entries: &[CompareEntry]) -> CommandResult {
+        // A comparison for a view since closed.
+        if !self.is_visible || self.entries.is_some() {
+            return CommandResult::Handled;
+        }
+        self.entries = Some(entries.to_vec());
+        self.select(0)
+    }
+
+    /// Once the copy or mirror started from here finishes, whether or not all
+    /// of it made it, compares again to show what it left.
+    fn task_progressed(&mut self, task: &Task) -> CommandResult {
+        let (TaskKind::Sync { path } | TaskKind::Mirror { path }) = task.kind() else {
+            return CommandResult::NotHandled;
+        };
+        if !task.is_terminal() || self.syncing.as_ref() != Some(path) {
+            return CommandResult::NotHandled;
+        }
+        self.syncing = None;
+        // Claimed by the notices as well; the comparison is an addition.
+        self.recompare()
+    }
+
+    fn hint(&self) -> &str {
+        if self.mirror.is_some() {
+            &self.mirror_hint
+        } else {
+            &self.hint
+        }
+    }
+
+    fn title(&self) -> String {
+        if let Some(mirror) = self.mirror {
+            return self.mirror_title(mirror);
+        }
+        let by = if self.contents { " by contents" } else { "" };
+        let directories = format!(
+            "Compare {} with {}{by}",
+            compact(&self.left),
+            compact(&self.right)
+        );
+        let Some(entries) = &self.entries else {
+            return directories;
+        };
+        let to_copy = entries
+            .iter()
+            .filter(|entry| entry.status.copies_to_right().is_some())
+            .count();
+        let count = |wanted: CompareStatus| {
+            entries
+                .iter()
+                .filter(|entry| entry.status == wanted)
+                .count()
+        };
+        let counts: Vec<_> = [
+            (to_copy, "to copy"),
+            (count(CompareStatus::Differs), "differ"),
+            (count(CompareStatus::Unreadable), "unreadable"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{count} {what}"))
+        .collect();
+        if counts.is_empty() {
+            format!("{directories}: identical")
+        } else {
+            format!("{directories}: {}", counts.join(", "))
+        }
+    }
+
+    /// What the mirror being previewed would do, step by step, and what it
+    /// leaves of the destination's extras when they are kept.
+    fn mirror_title(&self, mirror: Mirror) -> String {
+        let (source, destination) = if mirror.to_right {
+            (&self.left, &self.right)
+        } else {
+            (&self.right, &self.left)
+        };
+        let by = if self.contents { " by contents" } else { "" };
+        let directories = format!(
+            "Mirror {} onto {}{by}",
+            compact(source),
+            compact(destination)
+        );
+        let Some(entries) = &self.entries else {
+            return directories;
+        };
+        let count = |wanted: MirrorStep| {
+            entries
+                .iter()
+                .filter(|entry| entry.status.mirror_step(mirror.to_right, true) == Some(wanted))
+                .count()
+        };
+        let (extras, verb) = (
+            count(MirrorStep::Delete),
+            if mirror.delete { "delete" } else { "keep" },
+        );
+        let steps: Vec<_> = [
+            (count(MirrorStep::Copy), "copy"),
+            (count(MirrorStep::Replace), "replace"),
+            (extras, verb),
+            (count(MirrorStep::Skip), "skip as unreadable"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, verb)| format!("{count} to {verb}"))
+        .collect();
+        if steps.is_empty() {
+            format!("{directories}: nothing to do")
+        } else {
+            format!("{directories}: {}", steps.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::SystemTime};
+
+    use super::CompareView;
+    use crate::{
+        app::config::Config,
+        command::{
+            Command,
+            progress::{ActiveTask, TaskKind},
+        },
+        file_system::{
+            compare::{CompareEntry, CompareSide, CompareStatus},
+            path_info::PathInfo,
+        },
+        test_support::TempDir,
+    };
+
+    fn entry(name: &str, status: CompareStatus) -> CompareEntry {
+        let side = Some(CompareSide {
+            size: 1,
+            modified: Some(SystemTime::UNIX_EPOCH),
+            is_directory: false,
+        });
+        CompareEntry {
+            relative: name.into(),
+            left: (status != CompareStatus::OnlyRight)
+                .then_some(side)
+                .flatten(),
+            right: (status != CompareStatus::OnlyLeft)
+                .then_some(side)
+                .flatten(),
+            status,
+        }
+    }
+
+    /// A view over `left` and `right` below `fx` that compared one entry of
+    /// each status.
+    fn compared(fx: &TempDir) -> CompareView {
+        Config::init_test();
+        fs::create_dir_all(fx.join("left")).unwrap();
+        fs::create_dir_all(fx.join("right")).unwrap();
+        let mut view = CompareView::new();
+        view.show(
+            &PathInfo::try_from(fx.join("left").as_path()).unwrap(),
+            &PathInfo::try_from(fx.join("right").as_path()).unwrap(),
+        );
+        view.compared(&[
+            entry("a", CompareStatus::OnlyLeft),
+            entry("b", CompareStatus::OnlyRight),
+            entry("c", CompareStatus::Identical),
+            entry("d", CompareStatus::NewerLeft),
+            entry("e", CompareStatus::NewerRight),
+            entry("f", CompareStatus::Differs),
+        ]);
+        view
+    }
+
+    fn copies(view: &mut CompareView) -> Vec<(String, String)> {
+        let Some(Command::CopyCompared { copies, .. }) =
+            view.copy_across().into_commands().into_iter().next()
+        else {
+            panic!("expected a CopyCompared");
+        };
+        let fx_relative = |path: &std::path::Path| {
+            let mut parts = path.components().rev();
+            let name = parts.next().unwrap().as_os_str().to_string_lossy();
+            let side = parts.next().unwrap().as_os_str().to_string_lossy();
+            format!("{side}/{name}")
+        };
+        copies
+            .iter()
+            .map(|(from, to)| (fx_relative(from), fx_relative(to)))
+            .collect()
+    }
+
+    #[test]
+    fn opening_asks_for_a_comparison_and_contents_asks_again() {
+        Config::init_test();
+        let fx = TempDir::new("compare_view");
+        let mut view = compared(&fx);
+
+        let commands = view.toggle_contents().into_commands();
+
+        assert!(commands.contains(&Command::CompareDirectories {
+            left: fx.join("left"),
+            right: fx.join("right"),
+            contents: true,
+        }));
+        assert!(view.entries.is_none());
+        assert_eq!(Some(Command::CancelCompare), view.hide());
+    }
+
+    #[test]
+    fn what_is_missing_or_newer_is_copied_toward_the_side_without_it() {
+        let fx = TempDir::new("compare_view");
+        let mut view = compared(&fx);
+
+        assert_eq!(
+            vec![
+                ("left/a".to_string(), "right/a".to_string()),
+                ("right/b".to_string(), "left/b".to_string()),
+                ("left/d".to_string(), "right/d".to_string()),
+                ("right/e".to_string(), "left/e".to_string()),
+            ],
+            copies(&mut view)
+        );
+    }
+
+    #[test]
+    fn only_the_marked_entries_are_copied_when_there_are_any() {
+        let fx = TempDir::new("compare_view");
+        let mut view = compared(&fx);
+        view.select(4);
+        view.toggle_mark();
+        // Identical, so there is nothing to copy for it.
+        view.select(2);
+        view.toggle_mark();
+
+        assert_eq!(
+            vec![("right/e".to_string(), "left/e".to_string())],
+            copies(&mut view)
+        );
+
+        view.marked.retain(|path| path.as_os_str() == "c");
+        assert!(matches!(
+            Command::try_from(view.copy_across()),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
+
+    #[test]
+    fn the_finished_copy_is_followed_by_another_comparison() {
+        let fx = TempDir::new("compare_view");
+        let mut view = compared(&fx);
+        copies(&mut view);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (active, _running, _token) = ActiveTask::new(
+            tx,
+            TaskKind::Sync {
+                path: TaskKind::sync_name(&fx.join("left"), &fx.join("right")),
+            },
+            1,
+        );
+        active.done();
+        let Ok(Command::Progress(task)) = rx.recv() else {
+            panic!("the task should have reported")
+        };
+
+        let commands = view.task_progressed(&task).into_commands();
+
+        assert!(commands.contains(&Command::CompareDirectories {
+            left: fx.join("left"),
+            right: fx.join("right"),
+            contents: false,
+        }));
+        assert!(view.syncing.is_none());
+    }
+    #[test]
+    fn a_previewed_mirror_copies_what_differs_and_deletes_extras_once_asked() {
+        let fx = TempDir::new("compare_view");
+        let mut view = compared(&fx);
+
+        view.preview_mirror(true);
+        // Everything but the identical entry and, kept for now, the extra.
+        assert_eq!(4, view.len());
+        assert!(
+            view.title()
+                .ends_with(": 1 to copy, 3 to replace, 1 to keep")
+        );
+        view.toggle_deletions();
+        assert_eq!(5, view.len());
+
+        let Some(Command::MirrorCompared {
+            source,
+            destination,
+            copies,
+            removals,
+        }) = view.mirror_across().into_commands().into_iter().next()
+        else {
+            panic!("expected a MirrorCompared");
+        };
+
+        assert_eq!((fx.join("left"), fx.join("right")), (source, destination));
+        assert_eq!(
+            vec!["a", "d", "e", "f"],
+            copies
+                .iter()
+                .map(|(from, to)| {
+                    assert_eq!(
+                        from.strip_prefix(fx.join("left")),
+                        to.strip_prefix(fx.join("right"))
+                    );
+                    from.file_name().unwrap().to_str().unwrap()
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![fx.join("right/b")], removals);
+        // Back to the comparison, to compare again once the mirror is done.
+        assert!(view.mirror.is_none());
+        assert_eq!(6, view.len());
+        assert!(view.syncing.is_some());
+    }
+}
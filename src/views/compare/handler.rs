@@ -0,0 +1,95 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::CompareView;
+use crate::{
+    app::config::{
+        Config,
+        keybindings::{Action, hardcoded_normal_action},
+    },
+    command::{Command, handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for CompareView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::CompareProgress { entries } => self.progressed(*entries),
+            Command::Compared(entries) => self.compared(entries),
+            Command::Progress(task) => self.task_progressed(task),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        let action = hardcoded_normal_action(code, modifiers)
+            .or_else(|| Config::global().keybindings.normal_action(code, modifiers));
+        // While a mirror is previewed, marks and copies wait for the
+        // comparison, and Esc goes back to it rather than closing the view.
+        if self.mirror.is_some() {
+            match action {
+                Some(Action::Open) => return self.mirror_across(),
+                Some(Action::Delete) => return self.toggle_deletions(),
+                Some(Action::ResetView) => return self.leave_preview(),
+                Some(Action::ToggleMark | Action::CopyCompared) => return CommandResult::Handled,
+                _ => {}
+            }
+        }
+        match action {
+            Some(Action::MirrorRight) => self.preview_mirror(true),
+            Some(Action::MirrorLeft) => self.preview_mirror(false),
+            Some(Action::ToggleMark) => self.toggle_mark(),
+            Some(Action::CopyCompared) => self.copy_across(),
+            Some(Action::CompareContents) => self.toggle_contents(),
+            Some(Action::Refresh) => self.recompare(),
+            Some(Action::CompareDirectories) => {
+                self.hide().map_or(CommandResult::Handled, Into::into)
+            }
+            Some(action) => self.handle_scroll_action(action),
+            None => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> CommandResult {
+        match event.kind {
+            MouseEventKind::ScrollDown => self.handle_scroll_action(Action::SelectNext),
+            MouseEventKind::ScrollUp => self.handle_scroll_action(Action::SelectPrevious),
+            MouseEventKind::Down(MouseButton::Left)
+            | MouseEventKind::Up(MouseButton::Left)
+            | MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(offset) = self.scrollbar_view.handle_mouse(event, self.max_scroll()) {
+                    self.scroll_to(offset);
+                } else if matches!(event.kind, MouseEventKind::Down(MouseButton::Left))
+                    && self.content_area.contains(Position {
+                        x: event.column,
+                        y: event.row,
+                    })
+                {
+                    let row = event.row.saturating_sub(self.content_area.y) as usize;
+                    let index = self.scroll_offset + row;
+                    // A click on the blank space below the last row leaves the
+                    // selection be.
+                    if index < self.len() {
+                        self.select(index);
+                    }
+                }
+                CommandResult::Handled
+            }
+            // Claim everything else so a stray event cannot leak to the views
+            // this one is covering.
+            _ => CommandResult::Handled,
+        }
+    }
+
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        matches!(
+            event.kind,
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) || self.scrollbar_view.is_dragging()
+            || self.area.contains(Position {
+                x: event.column,
+                y: event.row,
+            })
+    }
+}
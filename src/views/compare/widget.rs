@@ -0,0 +1,193 @@
+use ratatui::{
+    buffer::CellWidth,
+    text::{Line, Span},
+};
+
+use super::CompareView;
+use crate::{
+    app::config::Config,
+    file_system::{
+        compare::{CompareEntry, CompareSide, CompareStatus, MirrorStep},
+        path_info::humanize_size,
+    },
+};
+
+/// A line per entry: whether it is marked, how the two sides compare, the
+/// size on each side, and its path below both directories. While a mirror is
+/// previewed, what it would do replaces the mark and the status. Before the
+/// comparison completes, a line saying how far it has got.
+pub(super) fn build_rows(view: &CompareView, width: u16) -> Vec<Line<'static>> {
+    let theme = &Config::global().theme().open_with;
+    let Some(entries) = &view.entries else {
+        return vec![Line::styled(progress_text(view.progress), theme.detail())];
+    };
+    if entries.is_empty() {
+        return vec![Line::styled(" Both directories are empty", theme.detail())];
+    }
+    let listed = view.listed();
+    if listed.is_empty() {
+        return vec![Line::styled(
+            " Nothing to do: the directories already match",
+            theme.detail(),
+        )];
+    }
+    listed
+        .into_iter()
+        .enumerate()
+        .map(|(index, (entry, step))| entry_row(view, index == view.selected, width, entry, step))
+        .collect()
+}
+
+fn progress_text(entries: u64) -> String {
+    match entries {
+        0 => " Comparing".into(),
+        entries => format!(" Comparing: {entries} entries"),
+    }
+}
+
+fn size(side: Option<&CompareSide>) -> String {
+    match side {
+        None => "-".into(),
+        Some(side) if side.is_directory => "dir".into(),
+        Some(side) => humanize_size(side.size),
+    }
+}
+
+fn entry_row(
+    view: &CompareView,
+    is_selected: bool,
+    width: u16,
+    entry: &CompareEntry,
+    step: Option<MirrorStep>,
+) -> Line<'static> {
+    let theme = Config::global().theme();
+    let is_marked = view.marked.contains(&entry.relative);
+    let mark = match step {
+        Some(_) => "   ",
+        None if is_marked => "[x]",
+        None => "[ ]",
+    };
+    let status = format!(
+        "{:11}",
+        step.map_or(entry.status.label(), MirrorStep::label)
+    );
+    let sizes = format!(
+        "{:>6}  {:>6}",
+        size(entry.left.as_ref()),
+        size(entry.right.as_ref())
+    );
+    let is_directory = entry
+        .left
+        .or(entry.right)
+        .is_some_and(|side| side.is_directory);
+    let separator = if is_directory { "/" } else { "" };
+    // The directories themselves, when one of them cannot be read.
+    let path = if entry.relative.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        format!("{}{separator}", entry.relative.display())
+    };
+    let used = 3 + mark.len() + 1 + status.len() + 2 + sizes.len() + 2 + path.cell_width() as usize;
+    let padding = " ".repeat((width as usize).saturating_sub(used));
+
+    if is_selected {
+        // One span inheriting the line style, as in the pickers.
+        return Line::styled(
+            format!("   {mark} {status}  {sizes}  {path}{padding}"),
+            theme.open_with.selected(),
+        );
+    }
+    let mark_style = if is_marked {
+        theme.table.marked()
+    } else {
+        theme.open_with.detail()
+    };
+    // What there is nothing to do about recedes.
+    let status_style = if entry.status == CompareStatus::Identical {
+        theme.open_with.detail()
+    } else {
+        theme.open_with.shortcut()
+    };
+    Line::from(vec![
+        Span::raw("   "),
+        Span::styled(mark, mark_style),
+        Span::raw(" "),
+        Span::styled(status, status_style),
+        Span::raw("  "),
+        Span::styled(sizes, theme.open_with.detail()),
+        Span::raw("  "),
+        Span::raw(path),
+        Span::raw(padding),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use test_case::test_case;
+
+    use super::{build_rows, progress_text};
+    use crate::{
+        app::config::Config,
+        file_system::{
+            compare::{CompareEntry, CompareSide, CompareStatus},
+            path_info::PathInfo,
+        },
+        test_support::TempDir,
+        views::compare::CompareView,
+    };
+
+    fn text(line: &ratatui::text::Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test_case(0, " Comparing" ; "starting")]
+    #[test_case(12, " Comparing: 12 entries" ; "running")]
+    fn progress_says_how_far_the_comparison_has_got(entries: u64, expected: &str) {
+        assert_eq!(expected, progress_text(entries));
+    }
+
+    #[test]
+    fn an_entry_shows_its_status_and_the_size_on_each_side() {
+        Config::init_test();
+        let fx = TempDir::new("compare_widget");
+        let directory = PathInfo::try_from(fx.path()).unwrap();
+        let mut view = CompareView::new();
+        view.show(&directory, &directory);
+        let side = |size, is_directory| CompareSide {
+            size,
+            modified: Some(SystemTime::UNIX_EPOCH),
+            is_directory,
+        };
+        view.compared(&[
+            CompareEntry {
+                relative: "a.txt".into(),
+                left: Some(side(2000, false)),
+                right: Some(side(10, false)),
+                status: CompareStatus::NewerLeft,
+            },
+            CompareEntry {
+                relative: "sub".into(),
+                left: None,
+                right: Some(side(4096, true)),
+                status: CompareStatus::OnlyRight,
+            },
+        ]);
+        view.toggle_mark();
+
+        let rows = build_rows(&view, 60);
+
+        assert_eq!(
+            "   [x] newer left     2.0K      10  a.txt",
+            text(&rows[0]).trim_end()
+        );
+        assert_eq!(
+            "   [ ] only right        -     dir  sub/",
+            text(&rows[1]).trim_end()
+        );
+    }
+}
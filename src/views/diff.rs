@@ -0,0 +1,231 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::path::Path;
+
+use ratatui::layout::Rect;
+
+use super::ScrollbarView;
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::result::CommandResult,
+    file_system::{
+        diff::{DiffLine, FileDiff},
+        path_info::compact,
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+
+/// One row as shown: a hunk's `@@` header, or one of its lines.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Row {
+    Header(String),
+    Line(DiffLine),
+}
+
+/// Shows the unified diff of two files, a hunk at a time or scrolled through
+/// like a pager. Shown in place of the table, like the pickers, once the
+/// files are read.
+pub(super) struct DiffView {
+    area: Rect,
+    /// Lines added and removed, for the title.
+    counts: (usize, usize),
+    /// The hunk last moved to, or the one scrolled into, for the title.
+    current_hunk: usize,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    /// Where each hunk's header is among `rows`.
+    hunk_starts: Vec<usize>,
+    inner_height: usize,
+    is_visible: bool,
+    /// What is diffed, for the title.
+    names: (String, String),
+    /// Every hunk flattened, so scrolling is by row.
+    rows: Vec<Row>,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+}
+
+impl DiffView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            counts: (0, 0),
+            current_hunk: 0,
+            hint: format!(
+                "(Press {} or {} for the next or previous hunk, {} to close)",
+                kb.hint_for(&[Action::NextHunk]),
+                kb.hint_for(&[Action::PreviousHunk]),
+                kb.hint_for(&[Action::Diff, Action::ResetView])
+            ),
+            hunk_starts: Vec::new(),
+            inner_height: 0,
+            is_visible: false,
+            names: (String::new(), String::new()),
+            rows: Vec::new(),
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Show `diff` from its first hunk, in place of any shown before it.
+    pub(super) fn show(&mut self, diff: &FileDiff) {
+        self.counts = diff.counts();
+        self.current_hunk = 0;
+        self.hunk_starts.clear();
+        self.inner_height = 0;
+        self.is_visible = true;
+        self.names = names(&diff.left, &diff.right);
+        self.rows.clear();
+        for hunk in &diff.hunks {
+            self.hunk_starts.push(self.rows.len());
+            self.rows.push(Row::Header(hunk.header.clone()));
+            self.rows.extend(hunk.lines.iter().cloned().map(Row::Line));
+        }
+        self.scroll_offset = 0;
+    }
+
+    pub(super) fn hide(&mut self) {
+        self.is_visible = false;
+        self.hunk_starts = Vec::new();
+        self.rows = Vec::new();
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.rows.len().saturating_sub(self.inner_height)
+    }
+
+    /// Scrolls to `offset`, taking the hunk it lands in as the current one.
+    fn scroll_to(&mut self, offset: usize) -> CommandResult {
+        self.scroll_offset = offset.min(self.max_scroll());
+        self.current_hunk = self
+            .hunk_starts
+            .partition_point(|&start| start <= self.scroll_offset)
+            .saturating_sub(1);
+        CommandResult::Handled
+    }
+
+    /// Scrolls the current hunk's header to the top, or as near to it as the
+    /// end of the diff allows. The hunk stays the current one even then, so
+    /// that the title counts it.
+    fn go_to_hunk(&mut self, hunk: usize) -> CommandResult {
+        let Some(&start) = self.hunk_starts.get(hunk) else {
+            return CommandResult::Handled;
+        };
+        self.scroll_offset = start.min(self.max_scroll());
+        self.current_hunk = hunk;
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let page = self.inner_height.max(1);
+        match action {
+            Action::NextHunk => self.go_to_hunk(self.current_hunk.saturating_add(1)),
+            Action::PreviousHunk => self.go_to_hunk(self.current_hunk.saturating_sub(1)),
+            Action::SelectNext => self.scroll_to(self.scroll_offset.saturating_add(1)),
+            Action::SelectPrevious => self.scroll_to(self.scroll_offset.saturating_sub(1)),
+            Action::PageDown => self.scroll_to(self.scroll_offset.saturating_add(page)),
+            Action::PageUp => self.scroll_to(self.scroll_offset.saturating_sub(page)),
+            Action::SelectFirst => self.scroll_to(0),
+            Action::SelectLast => self.scroll_to(usize::MAX),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn title(&self) -> String {
+        let (left, right) = &self.names;
+        let (added, removed) = self.counts;
+        format!(
+            "Diff {left} and {right}: hunk {} of {}, +{added} -{removed}",
+            self.current_hunk + 1,
+            self.hunk_starts.len()
+        )
+    }
+}
+
+/// The two files as the title names them: by name alone when they are side
+/// by side, as marked files are, and in full when that would not tell them
+/// apart.
+fn names(left: &Path, right: &Path) -> (String, String) {
+    match (left.file_name(), right.file_name()) {
+        (Some(l), Some(r)) if left.parent() == right.parent() => (
+            l.to_string_lossy().into_owned(),
+            r.to_string_lossy().into_owned(),
+        ),
+        _ => (compact(left).to_string(), compact(right).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiffView;
+    use crate::{
+        app::config::{Config, keybindings::Action},
+        file_system::diff::{DiffHunk, DiffLine, DiffLineKind, FileDiff},
+    };
+
+    fn hunk(header: &str, lines: usize) -> DiffHunk {
+        DiffHunk {
+            header: header.into(),
+            lines: (0..lines)
+                .map(|n| DiffLine {
+                    kind: if n == 0 {
+                        DiffLineKind::Added
+                    } else {
+                        DiffLineKind::Context
+                    },
+                    text: n.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn shown() -> DiffView {
+        Config::init_test();
+        let mut view = DiffView::new();
+        view.show(&FileDiff {
+            left: "/data/a.txt".into(),
+            right: "/data/b.txt".into(),
+            hunks: vec![hunk("@@ -1 +1 @@", 4), hunk("@@ -9 +9 @@", 4)],
+        });
+        view.inner_height = 4;
+        view
+    }
+
+    #[test]
+    fn hunks_are_stepped_through_in_turn() {
+        let mut view = shown();
+        assert_eq!("Diff a.txt and b.txt: hunk 1 of 2, +2 -0", view.title());
+
+        view.handle_scroll_action(Action::NextHunk);
+        assert_eq!(5, view.scroll_offset);
+        assert_eq!("Diff a.txt and b.txt: hunk 2 of 2, +2 -0", view.title());
+
+        // There is no hunk past the last.
+        view.handle_scroll_action(Action::NextHunk);
+        assert_eq!(1, view.current_hunk);
+
+        view.handle_scroll_action(Action::PreviousHunk);
+        assert_eq!((0, 0), (view.scroll_offset, view.current_hunk));
+    }
+
+    #[test]
+    fn scrolling_into_a_hunk_makes_it_the_current_one() {
+        let mut view = shown();
+
+        view.handle_scroll_action(Action::PageDown);
+        assert_eq!((4, 0), (view.scroll_offset, view.current_hunk));
+        view.handle_scroll_action(Action::SelectNext);
+        assert_eq!((5, 1), (view.scroll_offset, view.current_hunk));
+        // Scrolling stops with the last row at the bottom.
+        view.handle_scroll_action(Action::SelectLast);
+        assert_eq!(6, view.scroll_offset);
+    }
+}
@@ -0,0 +1,89 @@
+use ratatui::{style::Style, text::Line};
+
+use super::{DiffView, Row};
+use crate::{
+    app::config::Config,
+    file_system::diff::{DiffLine, DiffLineKind},
+};
+
+/// How many columns a tab is shown as. A terminal cell holds one character,
+/// so tabs are expanded rather than left for the terminal to interpret.
+const TAB_WIDTH: usize = 4;
+
+/// The `height` rows from the scroll offset down: each hunk's header, then
+/// its lines marked as `diff -u` marks them.
+pub(super) fn build_rows(view: &DiffView, height: usize) -> Vec<Line<'static>> {
+    view.rows
+        .iter()
+        .skip(view.scroll_offset)
+        .take(height)
+        .map(row)
+        .collect()
+}
+
+fn row(row: &Row) -> Line<'static> {
+    let theme = Config::global().theme();
+    match row {
+        Row::Header(header) => Line::styled(header.clone(), theme.diff.hunk()),
+        Row::Line(line) => {
+            let (marker, style) = match line.kind {
+                DiffLineKind::Context => (' ', Style::default()),
+                DiffLineKind::Removed => ('-', theme.diff.removed()),
+                DiffLineKind::Added => ('+', theme.diff.added()),
+                DiffLineKind::NoNewline => ('\\', theme.diff.hunk()),
+            };
+            Line::styled(text(marker, line), style)
+        }
+    }
+}
+
+/// A line as `diff -u` prints it, after its marker, with tabs expanded to the
+/// next tab stop and any other control character shown as `?`.
+fn text(marker: char, line: &DiffLine) -> String {
+    let mut text = String::with_capacity(line.text.len() + 2);
+    text.push(marker);
+    if line.kind == DiffLineKind::NoNewline {
+        text.push(' ');
+    }
+    let mut column = 0;
+    for c in line.text.chars() {
+        match c {
+            '\t' => {
+                let width = TAB_WIDTH - column % TAB_WIDTH;
+                text.extend(std::iter::repeat_n(' ', width));
+                column += width;
+            }
+            c if c.is_control() => {
+                text.push('?');
+                column += 1;
+            }
+            c => {
+                text.push(c);
+                column += 1;
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::text;
+    use crate::file_system::diff::{DiffLine, DiffLineKind};
+
+    #[test_case('+', DiffLineKind::Added, "a" => "+a" ; "added")]
+    #[test_case(' ', DiffLineKind::Context, "\tab\tc" => "     ab  c" ; "tabs to the next stop")]
+    #[test_case('-', DiffLineKind::Removed, "a\u{1b}[0m" => "-a?[0m" ; "control character")]
+    #[test_case('\\', DiffLineKind::NoNewline, "No newline at end of file" => "\\ No newline at end of file" ; "no newline")]
+    fn a_line_is_marked_as_diff_marks_it(marker: char, kind: DiffLineKind, line: &str) -> String {
+        text(
+            marker,
+            &DiffLine {
+                kind,
+                text: line.into(),
+            },
+        )
+    }
+}
@@ -0,0 +1,441 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::path::PathBuf;
+
+use ratatui::layout::Rect;
+
+use super::{
+    ScrollbarView,
+    open_with::{clamp_scroll, clamp_selection},
+};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{
+        Command, PromptAction,
+        progress::{Task, TaskKind},
+        result::CommandResult,
+    },
+    file_system::{
+        disk_usage::Usage,
+        path_info::{PathInfo, compact, humanize_size},
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+
+/// Scans a directory tree once and lists it largest first, one directory at a
+/// time, to find what is filling a disk. Shown in place of the table, like the
+/// pickers, whose scrolling and styles it shares. Deleting from here takes the
+/// entry out of every total once the delete finishes, without a rescan.
+pub(super) struct DiskUsageView {
+    area: Rect,
+    /// The rows' area, for hit testing a click on a row.
+    content_area: Rect,
+    /// The directory whose entries are listed: the scanned one, or one below.
+    current: PathBuf,
+    /// Entries deleted from here whose delete has not finished, so their
+    /// totals still stand.
+    deleting: Vec<PathBuf>,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    inner_height: usize,
+    is_visible: bool,
+    /// The entry the open delete prompt is asking about.
+    pending_delete: Option<PathInfo>,
+    /// The entries and bytes counted so far, until the scan completes.
+    progress: (u64, u64),
+    root: PathBuf,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+    selected: usize,
+    /// The scanned tree, once the scan completes.
+    usage: Option<Usage>,
+}
+
+impl DiskUsageView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            content_area: Rect::default(),
+            current: PathBuf::new(),
+            deleting: Vec::new(),
+            hint: format!(
+                "(Press {} to close)",
+                kb.hint_for(&[Action::DiskUsage, Action::ResetView])
+            ),
+            inner_height: 0,
+            is_visible: false,
+            pending_delete: None,
+            progress: (0, 0),
+            root: PathBuf::new(),
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+            selected: 0,
+            usage: None,
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Show the view for `directory` and ask for its scan.
+    pub(super) fn show(&mut self, directory: &PathInfo) -> CommandResult {
+        self.is_visible = true;
+        self.root.clone_from(&directory.path);
+        self.rescan()
+    }
+
+    /// Hide the view, stopping whatever it is still scanning. `None` when it
+    /// was not showing.
+    pub(super) fn hide(&mut self) -> Option<Command> {
+        if !self.is_visible {
+            return None;
+        }
+        self.is_visible = false;
+        self.usage = None;
+        self.deleting.clear();
+        self.pending_delete = None;
+        Some(Command::CancelDiskUsage)
+    }
+
+    /// Forget the tree and scan it again from the top.
+    fn rescan(&mut self) -> CommandResult {
+        self.current = self.root.clone();
+        self.deleting.clear();
+        self.inner_height = 0;
+        self.progress = (0, 0);
+        self.scroll_offset = 0;
+        self.selected = 0;
+        self.usage = None;
+        vec![
+            Command::CancelDiskUsage,
+            Command::ScanDiskUsage(self.root.clone()),
+        ]
+        .into()
+    }
+
+    /// The directory being listed, once the scan completes.
+    fn directory(&self) -> Option<&Usage> {
+        self.usage.as_ref()?.find(&self.current)
+    }
+
+    fn entries(&self) -> &[Usage] {
+        self.directory()
+            .map_or(&[], |directory| &directory.children)
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.entries().len().saturating_sub(self.inner_height)
+    }
+
+    fn select(&mut self, index: usize) -> CommandResult {
+        let count = self.entries().len();
+        if count == 0 {
+            return CommandResult::Handled;
+        }
+        self.selected = index.min(count - 1);
+        self.scroll_offset = clamp_scroll(self.inner_height, self.selected, self.scroll_offset);
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let last = self.entries().len().saturating_sub(1);
+        let page = self.inner_height.max(1);
+        match action {
+            Action::SelectNext => self.select(self.selected.saturating_add(1)),
+            Action::SelectPrevious => self.select(self.selected.saturating_sub(1)),
+            Action::PageDown => self.select(self.selected.saturating_add(page)),
+            Action::PageUp => self.select(self.selected.saturating_sub(page)),
+            Action::SelectFirst => self.select(0),
+            Action::SelectLast => self.select(last),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.scroll_offset = offset;
+        self.selected = clamp_selection(
+            self.inner_height,
+            self.entries().len(),
+            offset,
+            self.selected,
+        );
+    }
+
+    /// Drill into the selected directory.
+    fn open_selected(&mut self) -> CommandResult {
+        let Some(entry) = self.entries().get(self.selected) else {
+            return CommandResult::Handled;
+        };
+        if !entry.is_directory {
+            return CommandResult::Handled;
+        }
+        self.current = entry.path.clone();
+        self.scroll_offset = 0;
+        self.selected = 0;
+        CommandResult::Handled
+    }
+
+    /// Back out to the directory above, with the one just left selected. Not
+    /// past the scanned directory, which is all there is.
+    fn go_to_parent(&mut self) -> CommandResult {
+        if self.current == self.root {
+            return CommandResult::Handled;
+        }
+        let left = self.current.clone();
+        let Some(parent) = left.parent() else {
+            return CommandResult::Handled;
+        };
+        self.current = parent.to_path_buf();
+        let index = self
+            .entries()
+            .iter()
+            .position(|entry| entry.path == left)
+            .unwrap_or(0);
+        self.select(index)
+    }
+
+    /// Ask whether to delete the selected entry, as the table does.
+    fn delete_selected(&mut self) -> CommandResult {
+        let Some(entry) = self.entries().get(self.selected) else {
+            return CommandResult::Handled;
+        };
+        match PathInfo::try_from(entry.path.as_path()) {
+            Ok(path) => {
+                self.pending_delete = Some(path.clone());
+                vec![
+                    Command::OpenPrompt(PromptAction::Delete(1)),
+                    Command::SummarizeDelete(vec![path]),
+                ]
+                .into()
+            }
+            Err(error) => Command::AlertError(format!("{error:#}")).into(),
+        }
+    }
+
+    fn confirm_delete(&mut self) -> CommandResult {
+        let Some(path) = self.pending_delete.take() else {
+            return CommandResult::NotHandled;
+        };
+        self.deleting.push(path.path.clone());
+        Command::Delete(vec![path]).into()
+    }
+
+    fn progressed(&mut self, entries: u64, bytes: u64) -> CommandResult {
+        if self.usage.is_none() {
+            self.progress = (entries, bytes);
+        }
+        CommandResult::Handled
+    }
+
+    /// Takes the scanned tree, or a rescan of an entry a delete left behind.
+    fn scanned(&mut self, usage: &Usage) -> CommandResult {
+        match &mut self.usage {
+            None if usage.path == self.root => self.usage = Some(usage.clone()),
+            Some(tree) if usage.path != self.root => {
+                tree.replace(usage.clone());
+            }
+            // A scan for a view since closed or reopened elsewhere.
+            _ => return CommandResult::Handled,
+        }
+        self.select(self.selected)
+    }
+
+    /// Once a delete from here finishes, takes what it removed out of the
+    /// totals: the whole entry when it is gone, otherwise whatever a rescan of
+    /// it finds missing.
+    fn task_progressed(&mut self, task: &Task) -> CommandResult {
+        let TaskKind::Delete { path } = task.kind() else {
+            return CommandResult::NotHandled;
+        };
+        if !task.is_terminal() {
+            return CommandResult::NotHandled;
+        }
+        // The task names its entry by absolute path, as the tree does.
+        let Some(index) = self
+            .deleting
+            .iter()
+            .position(|deleting| deleting.display().to_string() == *path)
+        else {
+            return CommandResult::NotHandled;
+        };
+        let deleted = self.deleting.remove(index);
+        if deleted.symlink_metadata().is_ok() {
+            return Command::ScanDiskUsage(deleted).into();
+        }
+        if let Some(tree) = &mut self.usage {
+            tree.remove(&deleted);
+        }
+        self.select(self.selected);
+        // Claimed by the notices and the file system as well.
+        CommandResult::NotHandled
+    }
+
+    fn title(&self) -> String {
+        let current = compact(&self.current);
+        match self.directory() {
+            Some(directory) => format!(
+                "Disk usage of {current}: {}",
+                humanize_size(directory.bytes)
+            ),
+            None => format!("Disk usage of {current}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::DiskUsageView;
+    use crate::{
+        app::config::Config,
+        command::{
+            Command, PromptAction,
+            progress::{ActiveTask, Task, TaskKind},
+            result::CommandResult,
+        },
+        file_system::{disk_usage::Usage, path_info::PathInfo},
+        test_support::TempDir,
+    };
+
+    /// The terminal update of a delete task, taken from the channel an
+    /// `ActiveTask` reports on rather than built by hand.
+    fn finished_delete(path: &Path) -> Task {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (active, _running, _token) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: path.display().to_string(),
+            },
+            1,
+        );
+        active.done();
+        let Ok(Command::Progress(finished)) = rx.recv() else {
+            panic!("the task should have reported")
+        };
+        finished
+    }
+
+    fn usage(path: PathBuf, bytes: u64, children: Vec<Usage>) -> Usage {
+        Usage {
+            path,
+            bytes,
+            is_directory: !children.is_empty(),
+            children,
+        }
+    }
+
+    /// A view over `root`, scanned as: root (300) > large (200) > file (200),
+    /// and root > small (100).
+    fn scanned(root: &TempDir) -> DiskUsageView {
+        Config::init_test();
+        let mut view = DiskUsageView::new();
+        view.show(&PathInfo::try_from(root.path()).unwrap());
+        let tree = usage(
+            root.path().to_path_buf(),
+            300,
+            vec![
+                usage(
+                    root.join("large"),
+                    200,
+                    vec![usage(root.join("large/file"), 200, Vec::new())],
+                ),
+                usage(root.join("small"), 100, Vec::new()),
+            ],
+        );
+        view.scanned(&tree);
+        view
+    }
+
+    #[test]
+    fn opening_asks_for_a_scan_and_shows_progress_until_it_completes() {
+        Config::init_test();
+        let fx = TempDir::new("disk_usage_view");
+        let mut view = DiskUsageView::new();
+
+        let result = view.show(&PathInfo::try_from(fx.path()).unwrap());
+
+        assert!(
+            result
+                .into_commands()
+                .contains(&Command::ScanDiskUsage(fx.path().to_path_buf()))
+        );
+        view.progressed(12, 4096);
+        assert_eq!((12, 4096), view.progress);
+        assert!(view.entries().is_empty());
+    }
+
+    #[test]
+    fn drilling_down_and_back_up_keeps_the_place() {
+        let fx = TempDir::new("disk_usage_view");
+        let mut view = scanned(&fx);
+
+        view.open_selected();
+        assert_eq!(fx.join("large"), view.current);
+        assert_eq!(1, view.entries().len());
+
+        view.go_to_parent();
+        assert_eq!(fx.path(), view.current);
+        assert_eq!(0, view.selected);
+
+        // Not past the scanned directory.
+        view.go_to_parent();
+        assert_eq!(fx.path(), view.current);
+    }
+
+    #[test]
+    fn a_finished_delete_takes_the_entry_out_of_the_totals() {
+        let fx = TempDir::new("disk_usage_view");
+        fs::create_dir_all(fx.join("large")).unwrap();
+        let mut view = scanned(&fx);
+
+        let result = view.delete_selected();
+        assert!(
+            result
+                .into_commands()
+                .contains(&Command::OpenPrompt(PromptAction::Delete(1)))
+        );
+        let Ok(Command::Delete(paths)) = Command::try_from(view.confirm_delete()) else {
+            panic!("expected a Delete");
+        };
+        assert_eq!(fx.join("large"), paths[0].path);
+
+        // Gone from disk by the time its task ends.
+        fs::remove_dir(fx.join("large")).unwrap();
+        let task = finished_delete(&fx.join("large"));
+        assert_eq!(CommandResult::NotHandled, view.task_progressed(&task));
+
+        let tree = view.usage.as_ref().unwrap();
+        assert_eq!(100, tree.bytes);
+        assert_eq!(None, tree.find(&fx.join("large")));
+    }
+
+    #[test]
+    fn an_entry_a_delete_left_behind_is_rescanned() {
+        let fx = TempDir::new("disk_usage_view");
+        fs::create_dir_all(fx.join("large")).unwrap();
+        let mut view = scanned(&fx);
+        view.delete_selected();
+        view.confirm_delete();
+
+        // Still on disk: part of it could not be deleted.
+        let task = finished_delete(&fx.join("large"));
+        assert_eq!(
+            Command::ScanDiskUsage(fx.join("large")),
+            Command::try_from(view.task_progressed(&task)).unwrap()
+        );
+
+        view.scanned(&usage(fx.join("large"), 50, Vec::new()));
+        assert_eq!(150, view.usage.as_ref().unwrap().bytes);
+        // Now the smaller of the two.
+        assert_eq!(fx.join("small"), view.entries()[0].path);
+    }
+}
@@ -0,0 +1,87 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::DiskUsageView;
+use crate::{
+    app::config::{
+        Config,
+        keybindings::{Action, hardcoded_normal_action},
+    },
+    command::{Command, handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for DiskUsageView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::DiskUsageProgress { entries, bytes } => self.progressed(*entries, *bytes),
+            Command::DiskUsageScanned(usage) => self.scanned(usage),
+            Command::ConfirmDelete => self.confirm_delete(),
+            // Also answered by the table, whose own pending delete this
+            // clears, so it is not claimed here.
+            Command::CancelPrompt => {
+                self.pending_delete = None;
+                CommandResult::NotHandled
+            }
+            Command::Progress(task) => self.task_progressed(task),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        let action = hardcoded_normal_action(code, modifiers)
+            .or_else(|| Config::global().keybindings.normal_action(code, modifiers));
+        match action {
+            Some(Action::Open) => self.open_selected(),
+            Some(Action::GoToParentDirectory) => self.go_to_parent(),
+            Some(Action::Delete) => self.delete_selected(),
+            Some(Action::Refresh) => self.rescan(),
+            Some(Action::DiskUsage) => self.hide().map_or(CommandResult::Handled, Into::into),
+            Some(action) => self.handle_scroll_action(action),
+            None => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> CommandResult {
+        match event.kind {
+            MouseEventKind::ScrollDown => self.handle_scroll_action(Action::SelectNext),
+            MouseEventKind::ScrollUp => self.handle_scroll_action(Action::SelectPrevious),
+            MouseEventKind::Down(MouseButton::Left)
+            | MouseEventKind::Up(MouseButton::Left)
+            | MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(offset) = self.scrollbar_view.handle_mouse(event, self.max_scroll()) {
+                    self.scroll_to(offset);
+                } else if matches!(event.kind, MouseEventKind::Down(MouseButton::Left))
+                    && self.content_area.contains(Position {
+                        x: event.column,
+                        y: event.row,
+                    })
+                {
+                    let row = event.row.saturating_sub(self.content_area.y) as usize;
+                    let index = self.scroll_offset + row;
+                    // Ignore a click on the blank space below the last row,
+                    // which would otherwise silently move the selection.
+                    if index < self.entries().len() {
+                        self.select(index);
+                    }
+                }
+                CommandResult::Handled
+            }
+            // Claim everything else so a stray event cannot leak to the views
+            // this one is covering.
+            _ => CommandResult::Handled,
+        }
+    }
+
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        matches!(
+            event.kind,
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) || self.scrollbar_view.is_dragging()
+            || self.area.contains(Position {
+                x: event.column,
+                y: event.row,
+            })
+    }
+}
@@ -0,0 +1,175 @@
+use ratatui::{
+    buffer::CellWidth,
+    text::{Line, Span},
+};
+
+use crate::{
+    app::config::theme::OpenWith,
+    file_system::{disk_usage::Usage, path_info::humanize_size},
+};
+
+/// Cells in each row's bar.
+const BAR_WIDTH: u64 = 10;
+
+/// One line per entry of `directory`: its size, a bar and a percentage of the
+/// directory's total, and its name. Before the scan completes, a line saying
+/// how far it has got.
+pub(super) fn build_rows(
+    theme: &OpenWith,
+    selected: usize,
+    width: u16,
+    directory: Option<&Usage>,
+    (entries, bytes): (u64, u64),
+) -> Vec<Line<'static>> {
+    let Some(directory) = directory else {
+        return vec![Line::styled(
+            format!(" Scanning: {entries} entries, {}", humanize_size(bytes)),
+            theme.detail(),
+        )];
+    };
+    if directory.children.is_empty() {
+        return vec![Line::styled(" Empty", theme.detail())];
+    }
+    directory
+        .children
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| build_row(theme, index == selected, width, directory.bytes, entry))
+        .collect()
+}
+
+fn build_row(
+    theme: &OpenWith,
+    is_selected: bool,
+    width: u16,
+    total: u64,
+    entry: &Usage,
+) -> Line<'static> {
+    let size = format!("{:>6}", humanize_size(entry.bytes));
+    let filled = share(entry.bytes, total, BAR_WIDTH);
+    let bar = format!(
+        "[{}{}]",
+        "#".repeat(usize::try_from(filled).unwrap_or(0)),
+        " ".repeat(usize::try_from(BAR_WIDTH - filled).unwrap_or(0)),
+    );
+    let tenths = share(entry.bytes, total, 1000);
+    let percent = format!("{:>3}.{}%", tenths / 10, tenths % 10);
+    let mut name = entry.name().into_owned();
+    if entry.is_directory {
+        name.push('/');
+    }
+    let used = 1
+        + size.cell_width() as usize
+        + 1
+        + bar.cell_width() as usize
+        + 1
+        + percent.cell_width() as usize
+        + 2
+        + name.cell_width() as usize;
+    let padding = " ".repeat((width as usize).saturating_sub(used));
+
+    if is_selected {
+        // One span inheriting the line style, as in the pickers.
+        return Line::styled(
+            format!(" {size} {bar} {percent}  {name}{padding}"),
+            theme.selected(),
+        );
+    }
+    Line::from(vec![
+        Span::raw(" "),
+        Span::styled(size, theme.detail()),
+        Span::raw(" "),
+        Span::styled(bar, theme.shortcut()),
+        Span::raw(" "),
+        Span::styled(percent, theme.detail()),
+        Span::raw("  "),
+        Span::raw(name),
+        Span::raw(padding),
+    ])
+}
+
+/// `part` of `total` in `scale`ths, rounded to the nearest. Integer arithmetic,
+/// so a total of exabytes neither overflows nor loses its low digits.
+fn share(part: u64, total: u64, scale: u64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+    let scaled = (u128::from(part) * u128::from(scale) + u128::from(total) / 2) / u128::from(total);
+    u64::try_from(scaled).unwrap_or(scale).min(scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use test_case::test_case;
+
+    use super::{build_rows, share};
+    use crate::{
+        app::config::{Config, theme::OpenWith},
+        file_system::disk_usage::Usage,
+    };
+
+    fn theme() -> &'static OpenWith {
+        Config::init_test();
+        &Config::global().theme().open_with
+    }
+
+    fn text(line: &ratatui::text::Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test_case(0, 100, 10, 0 ; "nothing")]
+    #[test_case(100, 100, 10, 10 ; "everything")]
+    #[test_case(1, 3, 1000, 333 ; "a third in tenths of a percent")]
+    #[test_case(2, 3, 1000, 667 ; "rounds to the nearest")]
+    #[test_case(5, 0, 10, 0 ; "an empty total")]
+    #[test_case(u64::MAX, u64::MAX, 1000, 1000 ; "no overflow")]
+    fn share_scales_a_part_of_the_total(part: u64, total: u64, scale: u64, expected: u64) {
+        assert_eq!(expected, share(part, total, scale));
+    }
+
+    #[test]
+    fn a_row_shows_the_size_share_and_name() {
+        let directory = Usage {
+            path: PathBuf::from("/d"),
+            bytes: 4000,
+            is_directory: true,
+            children: vec![
+                Usage {
+                    path: PathBuf::from("/d/sub"),
+                    bytes: 3000,
+                    is_directory: true,
+                    children: Vec::new(),
+                },
+                Usage {
+                    path: PathBuf::from("/d/file"),
+                    bytes: 1000,
+                    is_directory: false,
+                    children: Vec::new(),
+                },
+            ],
+        };
+
+        let rows = build_rows(theme(), 0, 60, Some(&directory), (0, 0));
+
+        assert_eq!(
+            "   2.9K [########  ]  75.0%  sub/",
+            text(&rows[0]).trim_end()
+        );
+        assert_eq!(
+            "   1000 [###       ]  25.0%  file",
+            text(&rows[1]).trim_end()
+        );
+    }
+
+    #[test]
+    fn a_scan_in_progress_says_how_far_it_has_got() {
+        let rows = build_rows(theme(), 0, 60, None, (12, 0));
+
+        assert_eq!(" Scanning: 12 entries, 0", text(&rows[0]));
+    }
+}
@@ -0,0 +1,581 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use ratatui::layout::Rect;
+
+use super::{
+    ScrollbarView,
+    open_with::{clamp_scroll, clamp_selection},
+    unicode::pluralize,
+};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{
+        Command, PromptAction,
+        progress::{Task, TaskKind},
+        result::CommandResult,
+    },
+    file_system::{
+        duplicates::{DuplicateFile, DuplicateProgress, DuplicateSet},
+        path_info::{PathInfo, compact, humanize_size},
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+
+/// A line of the listing: a set's heading, or one of its files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Row {
+    Set(usize),
+    File(usize, usize),
+}
+
+/// Looks for identical files in a directory tree and lists them one set at a
+/// time, the most space to reclaim first. Files are marked here rather than in
+/// the table, and the marked ones deleted or replaced with hard links to a
+/// copy that is kept. Shown in place of the table, like the disk usage view,
+/// whose scrolling and styles it shares.
+pub(super) struct DuplicatesView {
+    area: Rect,
+    /// The rows' area, for hit testing a click on a row.
+    content_area: Rect,
+    /// Files deleted from here whose delete has not finished.
+    deleting: Vec<PathBuf>,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    inner_height: usize,
+    is_visible: bool,
+    marked: HashSet<PathBuf>,
+    /// The files the open delete prompt is asking about.
+    pending_delete: Vec<PathInfo>,
+    progress: DuplicateProgress,
+    root: PathBuf,
+    /// `sets` flattened into lines, rebuilt whenever they change.
+    rows: Vec<Row>,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+    /// A row index, always that of a file while there are any.
+    selected: usize,
+    /// The sets found, once the scan completes.
+    sets: Option<Vec<DuplicateSet>>,
+}
+
+impl DuplicatesView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            content_area: Rect::default(),
+            deleting: Vec::new(),
+            hint: format!(
+                "(Press {} to close)",
+                kb.hint_for(&[Action::FindDuplicates, Action::ResetView])
+            ),
+            inner_height: 0,
+            is_visible: false,
+            marked: HashSet::new(),
+            pending_delete: Vec::new(),
+            progress: DuplicateProgress::default(),
+            root: PathBuf::new(),
+            rows: Vec::new(),
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+            selected: 0,
+            sets: None,
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Show the view for `directory` and ask for its scan.
+    pub(super) fn show(&mut self, directory: &PathInfo) -> CommandResult {
+        self.is_visible = true;
+        self.root.clone_from(&directory.path);
+        self.rescan()
+    }
+
+    /// Hide the view, stopping a scan still running. `None` when it was not
+    /// showing.
+    pub(super) fn hide(&mut self) -> Option<Command> {
+        if !self.is_visible {
+            return None;
+        }
+        self.is_visible = false;
+        self.sets = None;
+        self.rows.clear();
+        self.marked.clear();
+        self.deleting.clear();
+        self.pending_delete.clear();
+        Some(Command::CancelDuplicates)
+    }
+
+    /// Forget the sets and look for them again.
+    fn rescan(&mut self) -> CommandResult {
+        self.deleting.clear();
+        self.inner_height = 0;
+        self.marked.clear();
+        self.progress = DuplicateProgress::default();
+        self.rows.clear();
+        self.scroll_offset = 0;
+        self.selected = 0;
+        self.sets = None;
+        vec![
+            Command::CancelDuplicates,
+            Command::FindDuplicates(self.root.clone()),
+        ]
+        .into()
+    }
+
+    fn file(&self, row: usize) -> Option<&DuplicateFile> {
+        let Row::File(set, file) = *self.rows.get(row)? else {
+            return None;
+        };
+        self.sets.as_ref()?.get(set)?.files.get(file)
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.rows.len().saturating_sub(self.inner_height)
+    }
+
+    /// Select the file at `row`, or the nearest one past it in the direction
+    /// of travel, or failing that the nearest one back. A heading is never
+    /// selected.
+    fn select(&mut self, row: usize, forward: bool) -> CommandResult {
+        if self.rows.is_empty() {
+            return CommandResult::Handled;
+        }
+        let row = row.min(self.rows.len() - 1);
+        let is_file = |index: &usize| matches!(self.rows[*index], Row::File(..));
+        let ahead = (row..self.rows.len()).find(is_file);
+        let behind = (0..=row).rev().find(is_file);
+        let found = if forward {
+            ahead.or(behind)
+        } else {
+            behind.or(ahead)
+        };
+        if let Some(found) = found {
+            self.selected = found;
+            self.scroll_offset = clamp_scroll(self.inner_height, found, self.scroll_offset);
+        }
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let page = self.inner_height.max(1);
+        match action {
+            Action::SelectNext => self.select(self.selected.saturating_add(1), true),
+            Action::SelectPrevious => match self.selected.checked_sub(1) {
+                Some(row) => self.select(row, false),
+                None => CommandResult::Handled,
+            },
+            Action::PageDown => self.select(self.selected.saturating_add(page), true),
+            Action::PageUp => self.select(self.selected.saturating_sub(page), false),
+            Action::SelectFirst => self.select(0, true),
+            Action::SelectLast => self.select(usize::MAX, false),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.scroll_offset = offset;
+        let row = clamp_selection(self.inner_height, self.rows.len(), offset, self.selected);
+        let forward = row > self.selected;
+        self.select(row, forward);
+        // Snapping past a heading must not move the viewport back.
+        self.scroll_offset = offset;
+    }
+
+    fn toggle_mark(&mut self) -> CommandResult {
+        if let Some(path) = self.file(self.selected).map(|file| file.path.clone())
+            && !self.marked.remove(&path)
+        {
+            self.marked.insert(path);
+        }
+        CommandResult::Handled
+    }
+
+    /// Marks every file of every set but one: the most recently modified, or
+    /// the least. What was marked before is unmarked.
+    fn mark_all_but(&mut self, newest: bool) -> CommandResult {
+        let Some(sets) = &self.sets else {
+            return CommandResult::Handled;
+        };
+        self.marked = sets
+            .iter()
+            .flat_map(|set| {
+                // Oldest first, so the one kept is at one end.
+                let kept = if newest { set.files.len() - 1 } else { 0 };
+                set.files
+                    .iter()
+                    .enumerate()
+                    .filter(move |(index, _)| *index != kept)
+                    .map(|(_, file)| file.path.clone())
+            })
+            .collect();
+        CommandResult::Handled
+    }
+
+    /// The marked files, or else the selected one.
+    fn targets(&self) -> Vec<&DuplicateFile> {
+        let Some(sets) = &self.sets else {
+            return Vec::new();
+        };
+        if self.marked.is_empty() {
+            return self.file(self.selected).into_iter().collect();
+        }
+        sets.iter()
+            .flat_map(|set| &set.files)
+            .filter(|file| self.marked.contains(&file.path))
+            .collect()
+    }
+
+    /// Ask whether to delete the targets, as the table does.
+    fn delete_targets(&mut self) -> CommandResult {
+        let paths: Result<Vec<_>, _> = self
+            .targets()
+            .into_iter()
+            .map(|file| PathInfo::try_from(file.path.as_path()))
+            .collect();
+        match paths {
+            Ok(paths) if paths.is_empty() => CommandResult::Handled,
+            Ok(paths) => {
+                self.pending_delete.clone_from(&paths);
+                vec![
+                    Command::OpenPrompt(PromptAction::Delete(paths.len())),
+                    Command::SummarizeDelete(paths),
+                ]
+                .into()
+            }
+            Err(error) => Command::AlertError(format!("{error:#}")).into(),
+        }
+    }
+
+    fn confirm_delete(&mut self) -> CommandResult {
+        if self.pending_delete.is_empty() {
+            return CommandResult::NotHandled;
+        }
+        let paths = std::mem::take(&mut self.pending_delete);
+        self.deleting
+            .extend(paths.iter().map(|path| path.path.clone()));
+        Command::Delete(paths).into()
+    }
+
+    /// Replaces each marked file with a hard link to the oldest file of its
+    /// set that is not marked. A set with every file marked has nothing to
+    /// keep, so it is left alone.
+    fn hard_link_marked(&mut self) -> CommandResult {
+        let Some(sets) = &self.sets else {
+            return CommandResult::Handled;
+        };
+        if self.marked.is_empty() {
+            let kb = &Config::global().keybindings;
+            return Command::AlertWarn(format!(
+                "Mark the duplicates to replace first ({} marks all but the newest)",
+                kb.hint_for(&[Action::MarkAllButNewest])
+            ))
+            .into();
+        }
+        let mut pairs = Vec::new();
+        let mut unkept = 0;
+        for set in sets {
+            let (marked, unmarked): (Vec<_>, Vec<_>) = set
+                .files
+                .iter()
+                .partition(|file| self.marked.contains(&file.path));
+            match unmarked.first() {
+                Some(keep) => pairs.extend(
+                    marked
+                        .into_iter()
+                        .map(|duplicate| ((*keep).clone(), duplicate.clone())),
+                ),
+                None => unkept += 1,
+            }
+        }
+        let mut commands = Vec::new();
+        if unkept > 0 {
+            commands.push(Command::AlertWarn(format!(
+                "Skipped {} with every copy marked: leave one unmarked to link to",
+                pluralize(unkept, "set", "sets")
+            )));
+        }
+        if !pairs.is_empty() {
+            commands.push(Command::HardLinkDuplicates(pairs));
+        }
+        commands.into()
+    }
+
+    fn progressed(&mut self, progress: DuplicateProgress) -> CommandResult {
+        if self.sets.is_none() {
+            self.progress = progress;
+        }
+        CommandResult::Handled
+    }
+
+    fn found(&mut self, sets: &[DuplicateSet]) -> CommandResult {
+        // A scan for a view since closed.
+        if !self.is_visible || self.sets.is_some() {
+            return CommandResult::Handled;
+        }
+        self.sets = Some(sets.to_vec());
+        self.rebuild_rows();
+        self.select(0, true)
+    }
+
+    /// Takes `paths` out of their sets, dropping a set left with a single
+    /// file, which no longer has a duplicate.
+    fn forget(&mut self, paths: &[PathBuf]) {
+        let Some(sets) = &mut self.sets else {
+            return;
+        };
+        for set in sets.iter_mut() {
+            set.files.retain(|file| !paths.contains(&file.path));
+        }
+        sets.retain(|set| set.files.len() > 1);
+        for path in paths {
+            self.marked.remove(path);
+        }
+        let selected = self.selected;
+        self.rebuild_rows();
+        self.select(selected, true);
+    }
+
+    fn rebuild_rows(&mut self) {
+        self.rows =
+            self.sets
+                .iter()
+                .flatten()
+                .enumerate()
+                .fold(Vec::new(), |mut rows, (index, set)| {
+                    rows.push(Row::Set(index));
+                    rows.extend((0..set.files.len()).map(|file| Row::File(index, file)));
+                    rows
+                });
+    }
+
+    /// The files a hard link now stands in for take no more space.
+    fn linked(&mut self, paths: &[PathBuf]) -> CommandResult {
+        self.forget(paths);
+        CommandResult::Handled
+    }
+
+    /// Once a delete from here finishes, takes the file out of its set, unless
+    /// it is somehow still there.
+    fn task_progressed(&mut self, task: &Task) -> CommandResult {
+        let TaskKind::Delete { path } = task.kind() else {
+            return CommandResult::NotHandled;
+        };
+        if !task.is_terminal() {
+            return CommandResult::NotHandled;
+        }
+        // The task names its file by absolute path, as the sets do.
+        let Some(index) = self
+            .deleting
+            .iter()
+            .position(|deleting| deleting.display().to_string() == *path)
+        else {
+            return CommandResult::NotHandled;
+        };
+        let deleted = self.deleting.remove(index);
+        if deleted.symlink_metadata().is_err() {
+            self.forget(&[deleted]);
+        }
+        // Claimed by the notices and the file system as well.
+        CommandResult::NotHandled
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    fn title(&self) -> String {
+        let root = compact(&self.root);
+        match &self.sets {
+            Some(sets) => format!(
+                "Duplicates in {root}: {} reclaimable",
+                humanize_size(sets.iter().map(DuplicateSet::reclaimable).sum())
+            ),
+            None => format!("Duplicates in {root}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, time::SystemTime};
+
+    use super::DuplicatesView;
+    use crate::{
+        app::config::Config,
+        command::{
+            Command, PromptAction,
+            progress::{ActiveTask, TaskKind},
+        },
+        file_system::{
+            duplicates::{DuplicateFile, DuplicateSet},
+            path_info::PathInfo,
+        },
+        test_support::TempDir,
+    };
+
+    fn file(path: &Path, age: u64) -> DuplicateFile {
+        DuplicateFile {
+            path: path.to_path_buf(),
+            modified: SystemTime::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(age)),
+        }
+    }
+
+    /// A view over `root` that found: (a, b, c) and (d, e), oldest first.
+    fn found(root: &TempDir) -> DuplicatesView {
+        Config::init_test();
+        for name in ["a", "b", "c", "d", "e"] {
+            fs::write(root.join(name), b"x").unwrap();
+        }
+        let mut view = DuplicatesView::new();
+        view.show(&PathInfo::try_from(root.path()).unwrap());
+        view.found(&[
+            DuplicateSet {
+                bytes: 10,
+                files: vec![
+                    file(&root.join("a"), 1),
+                    file(&root.join("b"), 2),
+                    file(&root.join("c"), 3),
+                ],
+            },
+            DuplicateSet {
+                bytes: 5,
+                files: vec![file(&root.join("d"), 1), file(&root.join("e"), 2)],
+            },
+        ]);
+        view
+    }
+
+    fn marked(view: &DuplicatesView) -> Vec<String> {
+        let mut names: Vec<_> = view
+            .marked
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn opening_asks_for_a_scan() {
+        Config::init_test();
+        let fx = TempDir::new("duplicates_view");
+        let mut view = DuplicatesView::new();
+
+        let result = view.show(&PathInfo::try_from(fx.path()).unwrap());
+
+        assert!(
+            result
+                .into_commands()
+                .contains(&Command::FindDuplicates(fx.path().to_path_buf()))
+        );
+        assert_eq!(Some(Command::CancelDuplicates), view.hide());
+    }
+
+    #[test]
+    fn the_selection_skips_the_headings() {
+        let fx = TempDir::new("duplicates_view");
+        let mut view = found(&fx);
+        assert_eq!(1, view.selected);
+
+        for _ in 0..3 {
+            view.handle_scroll_action(crate::app::config::keybindings::Action::SelectNext);
+        }
+        // Past the second heading, onto "d".
+        assert_eq!(fx.join("d"), view.file(view.selected).unwrap().path);
+
+        view.handle_scroll_action(crate::app::config::keybindings::Action::SelectPrevious);
+        assert_eq!(fx.join("c"), view.file(view.selected).unwrap().path);
+    }
+
+    #[test]
+    fn all_but_the_newest_or_the_oldest_are_marked() {
+        let fx = TempDir::new("duplicates_view");
+        let mut view = found(&fx);
+
+        view.mark_all_but(true);
+        assert_eq!(vec!["a", "b", "d"], marked(&view));
+
+        view.mark_all_but(false);
+        assert_eq!(vec!["b", "c", "e"], marked(&view));
+    }
+
+    #[test]
+    fn marked_files_are_linked_to_the_oldest_unmarked_one() {
+        let fx = TempDir::new("duplicates_view");
+        let mut view = found(&fx);
+        view.mark_all_but(false);
+
+        let Ok(Command::HardLinkDuplicates(pairs)) = Command::try_from(view.hard_link_marked())
+        else {
+            panic!("expected a HardLinkDuplicates");
+        };
+
+        let pairs: Vec<_> = pairs
+            .iter()
+            .map(|(keep, duplicate)| (keep.path.clone(), duplicate.path.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (fx.join("a"), fx.join("b")),
+                (fx.join("a"), fx.join("c")),
+                (fx.join("d"), fx.join("e")),
+            ],
+            pairs
+        );
+
+        // Once linked, a set down to one file is no longer listed.
+        view.linked(&[fx.join("b"), fx.join("c"), fx.join("e")]);
+        assert_eq!(Some(0), view.sets.as_ref().map(Vec::len));
+        assert!(view.marked.is_empty());
+    }
+
+    #[test]
+    fn a_finished_delete_takes_the_file_out_of_its_set() {
+        let fx = TempDir::new("duplicates_view");
+        let mut view = found(&fx);
+        view.mark_all_but(true);
+
+        let result = view.delete_targets();
+        assert!(
+            result
+                .into_commands()
+                .contains(&Command::OpenPrompt(PromptAction::Delete(3)))
+        );
+        let Ok(Command::Delete(paths)) = Command::try_from(view.confirm_delete()) else {
+            panic!("expected a Delete");
+        };
+        assert_eq!(3, paths.len());
+
+        // Gone from disk by the time its task ends.
+        fs::remove_file(fx.join("d")).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (active, _running, _token) = ActiveTask::new(
+            tx,
+            TaskKind::Delete {
+                path: fx.join("d").display().to_string(),
+            },
+            1,
+        );
+        active.done();
+        let Ok(Command::Progress(task)) = rx.recv() else {
+            panic!("the task should have reported")
+        };
+        view.task_progressed(&task);
+
+        let sets = view.sets.as_ref().unwrap();
+        assert_eq!(1, sets.len());
+        assert!(!view.marked.contains(&fx.join("d")));
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    buffer::CellWidth,
+    text::{Line, Span},
+};
+
+use super::{DuplicatesView, Row};
+use crate::{
+    app::config::Config,
+    file_system::{
+        duplicates::{DuplicateFile, DuplicateProgress, DuplicateSet},
+        path_info::humanize_size,
+    },
+};
+
+/// A set's heading, then a line per file: whether it is marked, when it was
+/// last modified, and its path below the scanned directory. Before the scan
+/// completes, a line saying how far it has got.
+pub(super) fn build_rows(view: &DuplicatesView, width: u16) -> Vec<Line<'static>> {
+    let theme = &Config::global().theme().open_with;
+    let Some(sets) = &view.sets else {
+        return vec![Line::styled(progress_text(view.progress), theme.detail())];
+    };
+    if sets.is_empty() {
+        return vec![Line::styled(" No duplicates", theme.detail())];
+    }
+    view.rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| match *row {
+            Row::Set(set) => Line::styled(heading(&sets[set]), theme.detail()),
+            Row::File(set, file) => {
+                file_row(view, index == view.selected, width, &sets[set].files[file])
+            }
+        })
+        .collect()
+}
+
+fn progress_text(progress: DuplicateProgress) -> String {
+    match progress {
+        DuplicateProgress::Starting => " Scanning".into(),
+        DuplicateProgress::Walking { files } => format!(" Scanning: {files} files"),
+        DuplicateProgress::Sampling { files, total } => {
+            format!(" Comparing the start of files of the same size: {files} of {total}")
+        }
+        DuplicateProgress::Hashing { bytes, total } => format!(
+            " Comparing whole files: {} of {}",
+            humanize_size(bytes),
+            humanize_size(total)
+        ),
+    }
+}
+
+fn heading(set: &DuplicateSet) -> String {
+    format!(
+        " {} copies of {}, {} reclaimable",
+        set.files.len(),
+        humanize_size(set.bytes),
+        humanize_size(set.reclaimable())
+    )
+}
+
+fn file_row(
+    view: &DuplicatesView,
+    is_selected: bool,
+    width: u16,
+    file: &DuplicateFile,
+) -> Line<'static> {
+    let theme = Config::global().theme();
+    let is_marked = view.marked.contains(&file.path);
+    let mark = if is_marked { "[x]" } else { "[ ]" };
+    let modified = file.modified.map_or_else(
+        || format!("{:16}", "-"),
+        |modified| {
+            DateTime::<Local>::from(modified)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        },
+    );
+    let path = view.relative(&file.path).display().to_string();
+    let used = 3 + mark.len() + 1 + modified.cell_width() as usize + 2 + path.cell_width() as usize;
+    let padding = " ".repeat((width as usize).saturating_sub(used));
+
+    if is_selected {
+        // One span inheriting the line style, as in the pickers.
+        return Line::styled(
+            format!("   {mark} {modified}  {path}{padding}"),
+            theme.open_with.selected(),
+        );
+    }
+    let mark_style = if is_marked {
+        theme.table.marked()
+    } else {
+        theme.open_with.detail()
+    };
+    Line::from(vec![
+        Span::raw("   "),
+        Span::styled(mark, mark_style),
+        Span::raw(" "),
+        Span::styled(modified, theme.open_with.detail()),
+        Span::raw("  "),
+        Span::raw(path),
+        Span::raw(padding),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{build_rows, progress_text};
+    use crate::{
+        app::config::Config,
+        file_system::{
+            duplicates::{DuplicateFile, DuplicateProgress, DuplicateSet},
+            path_info::PathInfo,
+        },
+        test_support::TempDir,
+        views::duplicates::DuplicatesView,
+    };
+
+    fn text(line: &ratatui::text::Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test_case(DuplicateProgress::Starting, " Scanning" ; "starting")]
+    #[test_case(DuplicateProgress::Walking { files: 12 }, " Scanning: 12 files" ; "walking")]
+    #[test_case(
+        DuplicateProgress::Sampling { files: 3, total: 8 },
+        " Comparing the start of files of the same size: 3 of 8" ;
+        "sampling"
+    )]
+    #[test_case(
+        DuplicateProgress::Hashing { bytes: 500, total: 2000 },
+        " Comparing whole files: 500 of 2.0K" ;
+        "hashing"
+    )]
+    fn progress_says_how_far_the_scan_has_got(progress: DuplicateProgress, expected: &str) {
+        assert_eq!(expected, progress_text(progress));
+    }
+
+    #[test]
+    fn a_set_is_a_heading_and_a_line_per_file() {
+        Config::init_test();
+        let fx = TempDir::new("duplicates_widget");
+        let mut view = DuplicatesView::new();
+        view.show(&PathInfo::try_from(fx.path()).unwrap());
+        let file = |name: &str| DuplicateFile {
+            path: fx.join(name),
+            modified: None,
+        };
+        view.found(&[DuplicateSet {
+            bytes: 2000,
+            files: vec![file("a"), file("sub/b")],
+        }]);
+        view.toggle_mark();
+        // Off the row being checked, which then carries no selection style.
+        view.select(2, true);
+
+        let rows = build_rows(&view, 60);
+
+        assert_eq!(" 2 copies of 2.0K, 2.0K reclaimable", text(&rows[0]));
+        assert_eq!("   [x] -                 a", text(&rows[1]).trim_end());
+        assert_eq!("   [ ] -                 sub/b", text(&rows[2]).trim_end());
+    }
+}
@@ -115,6 +115,16 @@ fn kb_entry(label: &str, keys: String) -> (String, String) {
 
 /// Build normal mode keybinding display strings from KeyBindings.
 pub(super) fn build_normal_keybindings(kb: &KeyBindings) -> Vec<(String, String)> {
+    [
+        navigation_keybindings(kb),
+        operation_keybindings(kb),
+        view_keybindings(kb),
+    ]
+    .concat()
+}
+
+/// Moving around, the layout, opening, and marking.
+fn navigation_keybindings(kb: &KeyBindings) -> Vec<(String, String)> {
     let d = |a: Action| annotate_uppercase(kb.display_for(a));
     let s = |a| d(a);
     let p = |a, b| format!("{}, {}", d(a), d(b));
@@ -147,6 +157,17 @@ pub(super) fn build_normal_keybindings(kb: &KeyBindings) -> Vec<(String, String)
         kb_entry("Go to previous dir", s(Action::GoToPreviousDirectory)),
         kb_entry("Go to home dir", s(Action::GoHome)),
         kb_entry("Go to path", s(Action::Goto)),
+        // Layout
+        kb_entry("Toggle Miller columns", s(Action::ToggleMillerColumns)),
+        kb_entry("Toggle preview", s(Action::TogglePreview)),
+        kb_entry("Toggle dual pane", s(Action::ToggleDualPane)),
+        kb_entry("Switch pane", s(Action::SwitchPane)),
+        kb_entry("New tab, close tab", p(Action::NewTab, Action::CloseTab)),
+        kb_entry(
+            "Next tab, previous tab",
+            p(Action::NextTab, Action::PreviousTab),
+        ),
+        kb_entry("Go to tab 1-9", Action::GO_TO_TAB.map(d).join(", ")),
         // Opening
         kb_entry("Open", s(Action::Open)),
         kb_entry("Open current directory", s(Action::OpenCurrentDirectory)),
@@ -155,15 +176,58 @@ pub(super) fn build_normal_keybindings(kb: &KeyBindings) -> Vec<(String, String)
         // Marking
         kb_entry("Mark/unmark item", s(Action::ToggleMark)),
         kb_entry("Range mark", s(Action::RangeMark)),
+    ]
+}
+
+/// The file operations.
+fn operation_keybindings(kb: &KeyBindings) -> Vec<(String, String)> {
+    let d = |a: Action| annotate_uppercase(kb.display_for(a));
+    let s = |a| d(a);
+    let p = |a, b| format!("{}, {}", d(a), d(b));
+    let t = |a, b, c| format!("{}, {}, {}", d(a), d(b), d(c));
+
+    vec![
         // File operations
         kb_entry(
             "Copy, Cut, Paste",
             t(Action::Copy, Action::Cut, Action::Paste),
         ),
+        kb_entry(
+            "Copy, move to other pane",
+            p(Action::CopyToOtherPane, Action::MoveToOtherPane),
+        ),
         kb_entry("Rename", s(Action::Rename)),
         kb_entry("Chmod", s(Action::Chmod)),
+        kb_entry("Chmod recursively", s(Action::ChmodRecursive)),
+        kb_entry("Chown", s(Action::Chown)),
+        kb_entry("Chown recursively", s(Action::ChownRecursive)),
+        kb_entry("Edit permissions", s(Action::EditPermissions)),
+        kb_entry("Touch", s(Action::Touch)),
+        kb_entry("Touch recursively", s(Action::TouchRecursive)),
+        kb_entry("Touch like clipboard", s(Action::TouchFromClipboard)),
+        kb_entry("Compress", s(Action::Compress)),
+        kb_entry("Extract into a new directory", s(Action::Extract)),
+        kb_entry("Extract here", s(Action::ExtractHere)),
         kb_entry("Create directory", s(Action::CreateDirectory)),
+        kb_entry("Create file", s(Action::CreateFile)),
+        kb_entry("New from template", s(Action::NewFromTemplate)),
         kb_entry("Delete", s(Action::Delete)),
+        kb_entry(
+            "Checksum: SHA-256, BLAKE3",
+            p(Action::Checksum, Action::ChecksumBlake3),
+        ),
+        kb_entry("Verify checksum file", s(Action::VerifyChecksums)),
+    ]
+}
+
+/// The listing, the tools that open a view of their own, and the application.
+fn view_keybindings(kb: &KeyBindings) -> Vec<(String, String)> {
+    let d = |a: Action| annotate_uppercase(kb.display_for(a));
+    let s = |a| d(a);
+    let p = |a, b| format!("{}, {}", d(a), d(b));
+    let t = |a, b, c| format!("{}, {}, {}", d(a), d(b), d(c));
+
+    vec![
         // View
         kb_entry("Filter", s(Action::Filter)),
         kb_entry("Search", s(Action::Search)),
@@ -179,6 +243,27 @@ pub(super) fn build_normal_keybindings(kb: &KeyBindings) -> Vec<(String, String)
             ),
         ),
         kb_entry("Toggle show hidden files", s(Action::ToggleShowHidden)),
+        kb_entry("Toggle directory sizes", s(Action::ToggleDirectorySizes)),
+        kb_entry("Disk usage", s(Action::DiskUsage)),
+        kb_entry("Find duplicates", s(Action::FindDuplicates)),
+        kb_entry(
+            "Duplicates: mark all but newest, oldest",
+            p(Action::MarkAllButNewest, Action::MarkAllButOldest),
+        ),
+        kb_entry(
+            "Duplicates: replace marked with hard links",
+            s(Action::HardLinkDuplicates),
+        ),
+        kb_entry("Compare directories", s(Action::CompareDirectories)),
+        kb_entry("Compare: by contents", s(Action::CompareContents)),
+        kb_entry("Compare: copy missing and newer", s(Action::CopyCompared)),
+        kb_entry("Compare: mirror left onto right", s(Action::MirrorRight)),
+        kb_entry("Compare: mirror right onto left", s(Action::MirrorLeft)),
+        kb_entry("Diff two marked files", s(Action::Diff)),
+        kb_entry(
+            "Diff: next, previous hunk",
+            p(Action::NextHunk, Action::PreviousHunk),
+        ),
         // Application
         kb_entry("Cancel file or search operations", s(Action::CancelTask)),
         kb_entry(
@@ -0,0 +1,110 @@
+mod handler;
+
+use std::path::Path;
+
+use ratatui::{Frame, layout::Rect};
+
+use super::preview::{self, PreviewView};
+use crate::{
+    command::{Command, result::CommandResult},
+    file_system::{path_info::PathInfo, peek::PeekColumn},
+};
+
+/// What the parent column holds: nothing yet, the entries it lists, or why
+/// they could not be listed.
+#[derive(Debug, Default)]
+enum Column {
+    #[default]
+    Empty,
+    Entries(Vec<PathInfo>),
+    Failed(String),
+}
+
+/// The Miller columns beside the listing: on its left the parent directory,
+/// with the listed directory among its entries, and on its right a preview
+/// of the selected entry. They follow the listing and its cursor from the
+/// broadcasts the table sends, and FileSystem reads what they show, so
+/// neither column is ever read on the UI thread.
+#[derive(Debug, Default)]
+pub(super) struct MillerView {
+    /// The listed directory, whose parent the left column shows.
+    directory: Option<PathInfo>,
+    parent: Column,
+    preview: PreviewView,
+}
+
+impl MillerView {
+    /// Fills both columns afresh for `directory` and `selected`, as when the
+    /// columns are shown again after being hidden.
+    pub(super) fn show(
+        &mut self,
+        directory: Option<&PathInfo>,
+        selected: Option<&PathInfo>,
+    ) -> CommandResult {
+        *self = Self::default();
+        let commands: Vec<_> = directory
+            .and_then(|directory| self.set_directory(directory))
+            .into_iter()
+            .chain(self.preview.set_selected(selected))
+            .collect();
+        commands.into()
+    }
+
+    /// Takes up a newly listed directory, asking for its parent's entries.
+    /// A reload of the same one keeps them.
+    fn set_directory(&mut self, directory: &PathInfo) -> Option<Command> {
+        if self
+            .directory
+            .as_ref()
+            .is_some_and(|current| current.path == directory.path)
+        {
+            return None;
+        }
+        self.directory = Some(directory.clone());
+        self.parent = Column::Empty;
+        Some(Command::Peek {
+            path: directory.clone(),
+            column: PeekColumn::Parent,
+        })
+    }
+
+    /// Fills the parent column, unless the listing has since moved on from
+    /// the directory it was asked about.
+    fn peeked(&mut self, path: &Path, entries: &Result<Vec<PathInfo>, String>) {
+        if self
+            .directory
+            .as_ref()
+            .is_none_or(|directory| directory.path != path)
+        {
+            return;
+        }
+        self.parent = match entries {
+            Ok(entries) => Column::Entries(preview::sorted(entries)),
+            Err(error) => Column::Failed(error.clone()),
+        };
+    }
+
+    /// Draws the parent column in `parent_area` and the preview in
+    /// `preview_area`, leaving out dotfiles when the listing does.
+    pub(super) fn render(
+        &self,
+        parent_area: Rect,
+        preview_area: Rect,
+        show_hidden: bool,
+        frame: &mut Frame<'_>,
+    ) {
+        let buf = frame.buffer_mut();
+        match &self.parent {
+            Column::Empty => {}
+            Column::Entries(entries) => preview::render_entries(
+                entries,
+                self.directory.as_ref(),
+                show_hidden,
+                parent_area,
+                buf,
+            ),
+            Column::Failed(error) => preview::render_message(error, parent_area, buf),
+        }
+        self.preview.render(preview_area, show_hidden, frame);
+    }
+}
@@ -0,0 +1,31 @@
+use super::MillerView;
+use crate::{
+    command::{Command, handler::CommandHandler, result::CommandResult},
+    file_system::peek::PeekColumn,
+};
+
+impl CommandHandler for MillerView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        // The listing's own commands are the table's too, so they are passed
+        // on whatever is asked for here.
+        match command {
+            Command::NavigatedDirectory { directory, .. }
+            | Command::RefreshedDirectory { directory, .. } => self
+                .set_directory(directory)
+                .map_or(CommandResult::NotHandled, Into::into),
+            Command::Peeked {
+                path,
+                column: PeekColumn::Parent,
+                entries,
+            } => {
+                self.peeked(path, entries);
+                CommandResult::Handled
+            }
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn visit_command_handlers(&mut self, visitor: &mut dyn FnMut(&mut dyn CommandHandler)) {
+        visitor(&mut self.preview);
+    }
+}
@@ -71,10 +71,11 @@ impl CommandHandler for NoticesView {
                 CommandResult::Handled
             }
             // The loading indicator's position is a function of elapsed time,
-            // read live in render, so a tick changes no state at all: it exists
-            // only to wake the event loop for the next frame while a search is
-            // running and nothing else is arriving.
-            Command::SearchTick => return CommandResult::Handled,
+            // read live in render as the table's size spinners are, so a tick
+            // changes no state at all: it exists
+            // only to wake the event loop for the next frame while a search or
+            // a size count is running and nothing else is arriving.
+            Command::Tick => return CommandResult::Handled,
             Command::Progress(task) => self.update_tasks(task.clone()),
             Command::ResetView => {
                 self.clipboard_entry = None;
@@ -90,6 +91,15 @@ impl CommandHandler for NoticesView {
                 self.filter.clone_from(filter);
                 CommandResult::NotHandled
             }
+            // Taken as the pane has them rather than from its next snapshot,
+            // which would clear a clipboard the marks were not made after.
+            Command::SwitchedPane {
+                filter, mark_count, ..
+            } => {
+                self.filter.clone_from(filter);
+                self.mark_count = *mark_count;
+                CommandResult::NotHandled
+            }
             // Opening bookmarks cancels any in-flight search; the transition
             // hook above clears the notice immediately (the walker's eventual
             // ExitedSearch can lag and is then a no-op).
@@ -127,7 +127,12 @@ impl OpenWithView {
 /// Move `selected` into the viewport starting at `scroll`, moving as little as
 /// possible. A scrollbar drag sets the offset directly, and the next render
 /// would pull it straight back if the selection were left off screen.
-fn clamp_selection(inner_height: usize, count: usize, scroll: usize, selected: usize) -> usize {
+pub(super) fn clamp_selection(
+    inner_height: usize,
+    count: usize,
+    scroll: usize,
+    selected: usize,
+) -> usize {
     if count == 0 {
         return 0;
     }
@@ -137,7 +142,7 @@ fn clamp_selection(inner_height: usize, count: usize, scroll: usize, selected: u
 
 /// The scroll offset that keeps `selected` inside the viewport, moving as
 /// little as possible.
-fn clamp_scroll(inner_height: usize, selected: usize, scroll: usize) -> usize {
+pub(super) fn clamp_scroll(inner_height: usize, selected: usize, scroll: usize) -> usize {
     if inner_height == 0 {
         return 0;
     }
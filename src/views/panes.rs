@@ -0,0 +1,323 @@
+mod handler;
+mod view;
+
+use std::mem;
+
+use ratatui::layout::Rect;
+
+use super::{ListingMode, miller::MillerView, preview::PreviewView, table::TableView};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, handler::CommandHandler, result::CommandResult},
+    file_system::path_info::PathInfo,
+};
+
+/// The listing: one table, or two side by side in the dual-pane layout, each
+/// with its own directory, sort, filter and marks. FileSystem lists and
+/// watches one directory at a time, so only the focused pane takes keys and
+/// is fed listings. The other is left as it was, and read again when focus
+/// comes back to it.
+#[derive(Default)]
+pub(super) struct PanesView {
+    focused: TableView,
+    /// The pane out of focus, while there are two.
+    other: Option<TableView>,
+    /// Whether the focused pane is the right one. Switching moves the focus,
+    /// not the panes, so each stays on its side.
+    is_focus_right: bool,
+    /// Where the pane out of focus is drawn, for a click that focuses it.
+    other_area: Rect,
+    /// The Miller columns, while that layout is on. They are drawn, and
+    /// follow the listing, only while there is one pane: two panes leave no
+    /// room for them.
+    miller: Option<MillerView>,
+    /// The preview beside the listing, while it is shown. Like the Miller
+    /// columns, it is drawn only while there is one pane, and never with
+    /// them, whose right column is a preview already.
+    preview: Option<PreviewView>,
+}
+
+/// What `TabsView` reads and sets on the tabs it keeps.
+impl PanesView {
+    /// One pane, in the layout the config starts the listing in.
+    pub(super) fn new() -> Self {
+        let ui = &Config::global().ui;
+        Self::with_side_columns(ui.miller_columns, ui.show_preview)
+    }
+
+    /// One pane, for a tab opened in the layout of this one. Its first
+    /// listing fills the columns beside it.
+    pub(super) fn opened_from(&self) -> Self {
+        Self::with_side_columns(self.miller.is_some(), self.preview.is_some())
+    }
+
+    fn with_side_columns(miller_columns: bool, show_preview: bool) -> Self {
+        Self {
+            miller: miller_columns.then(MillerView::default),
+            preview: (show_preview && !miller_columns).then(PreviewView::default),
+            ..Self::default()
+        }
+    }
+
+    pub(super) fn directory(&self) -> Option<&PathInfo> {
+        self.focused.directory()
+    }
+
+    /// What FileSystem and the notices are told when this tab, or its other
+    /// pane, is brought into focus: its directory, and then the search it
+    /// showed, run again, or the bookmarks, read again. Nothing for a pane
+    /// whose first listing has not arrived, which there is nothing to bring
+    /// back.
+    pub(super) fn switched_to(&self) -> CommandResult {
+        let Some(directory) = self.focused.directory() else {
+            return CommandResult::Handled;
+        };
+        let mut commands = vec![Command::SwitchedPane {
+            directory: directory.clone(),
+            filter: self.focused.filter().to_string(),
+            mark_count: self.focused.mark_count(),
+            previous_directory: self.focused.previous_directory().cloned(),
+        }];
+        match self.focused.mode() {
+            ListingMode::Normal => {}
+            ListingMode::Search => commands.extend(
+                self.focused
+                    .search_query()
+                    .map(|query| Command::StartSearch(query.to_string())),
+            ),
+            ListingMode::Bookmarks => commands.push(Command::GetBookmarks),
+        }
+        commands.into()
+    }
+
+    /// Hands a clipboard change to both panes of a tab out of view, which
+    /// dispatch does not reach.
+    pub(super) fn share_clipboard(&mut self, command: &Command) {
+        self.focused.handle_command(command);
+        if let Some(other) = &mut self.other {
+            other.handle_command(command);
+        }
+    }
+}
+
+impl PanesView {
+    /// Shows the listing between its parent directory and a preview of the
+    /// selected entry, or only the listing again.
+    /// The preview is replaced by the columns, whose right one previews.
+    fn toggle_miller_columns(&mut self) -> CommandResult {
+        if self.miller.take().is_some() {
+            return CommandResult::Handled;
+        }
+        self.preview = None;
+        self.miller = Some(MillerView::default());
+        self.show_side_columns()
+    }
+
+    /// Shows a preview of the selected entry beside the listing, or only the
+    /// listing again.
+    fn toggle_preview(&mut self) -> CommandResult {
+        if self.miller.is_some() {
+            return Command::AlertInfo(
+                "The Miller columns already preview the selected entry".into(),
+            )
+            .into();
+        }
+        if self.preview.take().is_some() {
+            return CommandResult::Handled;
+        }
+        self.preview = Some(PreviewView::default());
+        self.show_side_columns()
+    }
+
+    /// Fills the Miller columns or the preview, whichever is on, for what
+    /// the focused pane lists. They are left as they were while there were
+    /// two panes.
+    fn show_side_columns(&mut self) -> CommandResult {
+        if self.other.is_some() {
+            return CommandResult::Handled;
+        }
+        if let Some(miller) = &mut self.miller {
+            miller.show(self.focused.directory(), self.focused.selected())
+        } else if let Some(preview) = &mut self.preview {
+            preview.show(self.focused.selected())
+        } else {
+            CommandResult::Handled
+        }
+    }
+
+    /// Opens a second pane on the focused one's directory and focuses it, or
+    /// closes the pane out of focus.
+    fn toggle_dual_pane(&mut self) -> CommandResult {
+        if self.other.take().is_some() {
+            self.is_focus_right = false;
+            self.other_area = Rect::default();
+            self.update_panes();
+            return self.show_side_columns();
+        }
+        if let Some(refusal) = self.refuse_switch() {
+            return refusal;
+        }
+        let Some(directory) = self.focused.directory().cloned() else {
+            return CommandResult::Handled;
+        };
+        self.other = Some(mem::take(&mut self.focused));
+        self.is_focus_right = true;
+        self.update_panes();
+        Command::SwitchedPane {
+            directory,
+            filter: String::new(),
+            mark_count: 0,
+            previous_directory: None,
+        }
+        .into()
+    }
+
+    fn switch_pane(&mut self) -> CommandResult {
+        if self.other.is_none() {
+            return Command::AlertWarn(format!(
+                "No other pane: press {} to open one",
+                Config::global()
+                    .keybindings
+                    .hint_for(&[Action::ToggleDualPane])
+            ))
+            .into();
+        }
+        if let Some(refusal) = self.refuse_switch() {
+            return refusal;
+        }
+        // A pane just opened has no directory until its first listing
+        // arrives, and nothing to switch back to.
+        let Some(other) = self
+            .other
+            .as_mut()
+            .filter(|other| other.directory().is_some())
+        else {
+            return CommandResult::Handled;
+        };
+        mem::swap(&mut self.focused, other);
+        self.is_focus_right = !self.is_focus_right;
+        self.update_panes();
+        self.switched_to()
+    }
+
+    /// Refuses to move the focus away from search results or the bookmarks.
+    /// The notices and the breadcrumbs follow the focused pane, and a pane
+    /// out of focus is only ever a directory listing, which the refresh on
+    /// switching back to it reads again.
+    fn refuse_switch(&self) -> Option<CommandResult> {
+        (self.focused.mode() != ListingMode::Normal).then(|| {
+            Command::AlertWarn("Leave the search or the bookmarks to switch panes".into()).into()
+        })
+    }
+
+    /// Tells each pane whether it is focused and where the other one is.
+    fn update_panes(&mut self) {
+        let other_directory = self
+            .other
+            .as_ref()
+            .and_then(|other| other.directory().cloned());
+        self.focused.set_focused(true);
+        self.focused.set_other_pane(other_directory);
+        if let Some(other) = &mut self.other {
+            other.set_focused(false);
+            other.set_other_pane(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A single pane listing `fx`, as FileSystem would have it after startup.
+    fn panes(fx: &TempDir) -> PanesView {
+        Config::init_test();
+        let mut panes = PanesView::default();
+        panes.focused.handle_command(&Command::NavigatedDirectory {
+            directory: PathInfo::try_from(fx.path()).unwrap(),
+            generation: 1,
+        });
+        panes
+    }
+
+    #[test]
+    fn a_second_pane_opens_on_the_same_directory_and_takes_the_focus() {
+        let fx = TempDir::new("panes");
+        let mut panes = panes(&fx);
+
+        let result = panes.toggle_dual_pane();
+
+        assert_eq!(
+            Some(Command::SwitchedPane {
+                directory: PathInfo::try_from(fx.path()).unwrap(),
+                filter: String::new(),
+                mark_count: 0,
+                previous_directory: None,
+            }),
+            Command::try_from(result).ok()
+        );
+        assert!(panes.is_focus_right);
+        assert!(panes.other.is_some());
+
+        // Closing it keeps the focused pane, whose directory FileSystem holds.
+        panes.toggle_dual_pane();
+        assert!(panes.other.is_none());
+        assert!(!panes.is_focus_right);
+    }
+
+    #[test]
+    fn switching_brings_back_the_other_panes_directory_and_filter() {
+        let fx = TempDir::new("panes");
+        std::fs::create_dir(fx.join("sub")).unwrap();
+        let mut panes = panes(&fx);
+        panes
+            .focused
+            .handle_command(&Command::FilterChanged("a".into()));
+        panes.toggle_dual_pane();
+        let sub = PathInfo::try_from(fx.join("sub").as_path()).unwrap();
+        panes.focused.handle_command(&Command::NavigatedDirectory {
+            directory: sub.clone(),
+            generation: 2,
+        });
+        panes.update_panes();
+
+        let result = panes.switch_pane();
+
+        assert_eq!(
+            Some(Command::SwitchedPane {
+                directory: PathInfo::try_from(fx.path()).unwrap(),
+                filter: "a".into(),
+                mark_count: 0,
+                previous_directory: None,
+            }),
+            Command::try_from(result).ok()
+        );
+        assert!(!panes.is_focus_right);
+        // The pane just left stays where it was.
+        assert_eq!(
+            Some(sub.path),
+            panes
+                .other
+                .as_ref()
+                .and_then(|other| other.directory())
+                .map(|directory| directory.path.clone())
+        );
+    }
+
+    #[test]
+    fn the_focus_stays_on_search_results() {
+        let fx = TempDir::new("panes");
+        let mut panes = panes(&fx);
+        panes.toggle_dual_pane();
+        panes
+            .focused
+            .handle_command(&Command::StartSearch("a".into()));
+
+        assert!(matches!(
+            Command::try_from(panes.switch_pane()),
+            Ok(Command::AlertWarn(_))
+        ));
+        assert!(panes.is_focus_right);
+    }
+}
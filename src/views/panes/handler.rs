@@ -0,0 +1,61 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::PanesView;
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for PanesView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        // The clipboard is shared, and the pane out of focus shows what is on
+        // it and pastes it once focused again, so it must not miss a change.
+        if let Command::SetClipboardEntry(_) = command
+            && let Some(other) = &mut self.other
+        {
+            return other.handle_command(command);
+        }
+        CommandResult::NotHandled
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        match Config::global().keybindings.normal_action(code, modifiers) {
+            Some(Action::ToggleMillerColumns) => self.toggle_miller_columns(),
+            Some(Action::TogglePreview) => self.toggle_preview(),
+            Some(Action::ToggleDualPane) => self.toggle_dual_pane(),
+            Some(Action::SwitchPane) => self.switch_pane(),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, _: MouseEvent) -> CommandResult {
+        self.switch_pane()
+    }
+
+    /// A click on the pane out of focus focuses it. Everything else is for the
+    /// focused pane, which is visited next.
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        matches!(event.kind, MouseEventKind::Down(MouseButton::Left))
+            && self.other.is_some()
+            && self.other_area.contains(Position {
+                x: event.column,
+                y: event.row,
+            })
+    }
+
+    fn visit_command_handlers(&mut self, visitor: &mut dyn FnMut(&mut dyn CommandHandler)) {
+        visitor(&mut self.focused);
+        if self.other.is_some() {
+            return;
+        }
+        if let Some(miller) = &mut self.miller {
+            visitor(miller);
+        }
+        if let Some(preview) = &mut self.preview {
+            visitor(preview);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+};
+
+use super::PanesView;
+use crate::views::View;
+
+impl View for PanesView {
+    fn constraint(&self, area: Rect) -> Constraint {
+        self.focused.constraint(area)
+    }
+
+    fn render(&mut self, area: Rect, frame: &mut Frame<'_>) {
+        let Some(other) = &mut self.other else {
+            if let Some(preview) = &self.preview {
+                let [table_area, _, preview_area] = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Ratio(1, 2),
+                        Constraint::Length(1),
+                        Constraint::Ratio(1, 2),
+                    ])
+                    .areas(area);
+                preview.render(preview_area, self.focused.shows_hidden(), frame);
+                self.focused.render(table_area, frame);
+                return;
+            }
+            let Some(miller) = &self.miller else {
+                self.focused.render(area, frame);
+                return;
+            };
+            // A column of its own each, with a blank one between them so
+            // that a name does not run into the next column's.
+            let [parent_area, _, table_area, _, preview_area] = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(1),
+                    Constraint::Fill(2),
+                    Constraint::Length(1),
+                    Constraint::Fill(2),
+                ])
+                .areas(area);
+            miller.render(
+                parent_area,
+                preview_area,
+                self.focused.shows_hidden(),
+                frame,
+            );
+            self.focused.render(table_area, frame);
+            return;
+        };
+        let [left_area, right_area] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .areas(area);
+        let (focused_area, other_area) = if self.is_focus_right {
+            (right_area, left_area)
+        } else {
+            (left_area, right_area)
+        };
+        self.focused.render(focused_area, frame);
+        other.render(other_area, frame);
+        self.other_area = other_area;
+    }
+}
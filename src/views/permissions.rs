@@ -0,0 +1,407 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::{fs, os::unix::fs::MetadataExt};
+
+use ratatui::layout::Rect;
+
+use super::unicode::pluralize_items;
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, result::CommandResult},
+    file_system::path_info::PathInfo,
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+/// The user, group and other rows, then the row of special bits.
+const ROWS: usize = 4;
+const COLUMNS: usize = 3;
+
+/// Whether a bit is set, across every entry being edited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BitState {
+    On,
+    Off,
+    /// Set on some entries and not others.
+    Mixed,
+}
+
+/// Edits the permission bits of the selected or marked entries as a grid of
+/// toggles, and resolves into a symbolic `Command::Chmod` naming only the bits
+/// that were changed, so a mixed selection keeps whatever it was not asked to
+/// lose. Shown in place of the table, like the "open with" picker.
+pub(super) struct PermissionsView {
+    area: Rect,
+    /// Bits set on at least one entry.
+    any_set: u32,
+    /// Bits set on every entry.
+    all_set: u32,
+    /// The grid's area, for hit testing a click on a toggle.
+    content_area: Rect,
+    /// The chosen value of each bit in `touched`.
+    chosen: u32,
+    /// `(row, column)` of the toggle under the cursor.
+    cursor: (usize, usize),
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    is_visible: bool,
+    paths: Vec<PathInfo>,
+    /// Bordered header title, rebuilt each time the editor is shown.
+    title: String,
+    /// The bits the user changed from what the entries have.
+    touched: u32,
+}
+
+impl PermissionsView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            any_set: 0,
+            all_set: 0,
+            content_area: Rect::default(),
+            chosen: 0,
+            cursor: (0, 0),
+            hint: format!(
+                "(Press {} to close)",
+                kb.hint_for(&[Action::EditPermissions, Action::ResetView])
+            ),
+            is_visible: false,
+            paths: Vec::new(),
+            title: String::new(),
+            touched: 0,
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    pub(super) fn show(&mut self, paths: &[PathInfo]) {
+        let modes: Vec<u32> = paths.iter().map(effective_mode).collect();
+        self.any_set = modes.iter().fold(0, |bits, mode| bits | mode);
+        self.all_set = modes.iter().fold(0o7777, |bits, mode| bits & mode);
+        self.chosen = 0;
+        self.cursor = (0, 0);
+        self.is_visible = true;
+        self.paths = paths.to_vec();
+        self.title = match paths {
+            [path] => format!("Permissions of {}", path.name()),
+            _ => format!("Permissions of {}", pluralize_items(paths.len())),
+        };
+        self.touched = 0;
+    }
+
+    pub(super) fn hide(&mut self) {
+        self.is_visible = false;
+        self.paths = Vec::new();
+        self.title = String::new();
+    }
+
+    fn original(&self, bit: u32) -> BitState {
+        if self.all_set & bit != 0 {
+            BitState::On
+        } else if self.any_set & bit != 0 {
+            BitState::Mixed
+        } else {
+            BitState::Off
+        }
+    }
+
+    fn state(&self, bit: u32) -> BitState {
+        match (self.touched & bit != 0, self.chosen & bit != 0) {
+            (true, true) => BitState::On,
+            (true, false) => BitState::Off,
+            (false, _) => self.original(bit),
+        }
+    }
+
+    /// On and off alternate. A bit that started mixed also passes back
+    /// through mixed, which is how a user takes back a change to it: anything
+    /// else would have to set or clear it on every entry.
+    fn toggle(&mut self, bit: u32) {
+        let original = self.original(bit);
+        let next = match (self.state(bit), original) {
+            (BitState::On, _) => BitState::Off,
+            (BitState::Off, BitState::Mixed) => BitState::Mixed,
+            (BitState::Off | BitState::Mixed, _) => BitState::On,
+        };
+        if next == original {
+            self.touched &= !bit;
+        } else {
+            self.touched |= bit;
+        }
+        if next == BitState::On {
+            self.chosen |= bit;
+        } else {
+            self.chosen &= !bit;
+        }
+    }
+
+    fn toggle_cursor(&mut self) -> CommandResult {
+        self.toggle(bit_at(self.cursor.0, self.cursor.1));
+        CommandResult::Handled
+    }
+
+    fn move_cursor(&mut self, rows: isize, columns: isize) -> CommandResult {
+        let (row, column) = self.cursor;
+        self.cursor = (
+            row.saturating_add_signed(rows).min(ROWS - 1),
+            column.saturating_add_signed(columns).min(COLUMNS - 1),
+        );
+        CommandResult::Handled
+    }
+
+    /// The touched bits as a symbolic mode, class by class: `u+x-w,o-r`.
+    /// Empty when nothing was changed.
+    fn symbolic_changes(&self) -> String {
+        ["u", "g", "o"]
+            .iter()
+            .enumerate()
+            .filter_map(|(row, class)| {
+                let bits: Vec<(char, u32)> = ['r', 'w', 'x']
+                    .into_iter()
+                    .enumerate()
+                    .map(|(column, name)| (name, bit_at(row, column)))
+                    .chain(std::iter::once((
+                        if row == 2 { 't' } else { 's' },
+                        bit_at(ROWS - 1, row),
+                    )))
+                    .filter(|(_, bit)| self.touched & bit != 0)
+                    .collect();
+                let names = |on: bool| -> String {
+                    bits.iter()
+                        .filter(|(_, bit)| (self.chosen & bit != 0) == on)
+                        .map(|(name, _)| *name)
+                        .collect()
+                };
+                let (added, removed) = (names(true), names(false));
+                if added.is_empty() && removed.is_empty() {
+                    return None;
+                }
+                let mut clause = (*class).to_string();
+                if !added.is_empty() {
+                    clause.push('+');
+                    clause.push_str(&added);
+                }
+                if !removed.is_empty() {
+                    clause.push('-');
+                    clause.push_str(&removed);
+                }
+                Some(clause)
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The result as four octal digits, with `?` for a digit any mixed bit
+    /// leaves unknown.
+    fn octal_preview(&self) -> String {
+        (0..4)
+            .rev()
+            .map(|digit| {
+                let bits = [4, 2, 1].map(|weight| weight << (digit * 3));
+                if bits.iter().any(|&bit| self.state(bit) == BitState::Mixed) {
+                    '?'
+                } else {
+                    let value = bits
+                        .iter()
+                        .filter(|&&bit| self.state(bit) == BitState::On)
+                        .fold(0, |value, bit| value | (bit >> (digit * 3)));
+                    char::from_digit(value, 8).expect("an octal digit")
+                }
+            })
+            .collect()
+    }
+
+    /// The result as `ls -l` writes it, with `?` for a mixed position.
+    fn symbolic_preview(&self) -> String {
+        (0..3)
+            .flat_map(|row| {
+                let special = bit_at(ROWS - 1, row);
+                (0..COLUMNS).map(move |column| (row, column, special))
+            })
+            .map(|(row, column, special)| {
+                let bit = bit_at(row, column);
+                let state = self.state(bit);
+                if column < 2 {
+                    return match state {
+                        BitState::On => ['r', 'w'][column],
+                        BitState::Off => '-',
+                        BitState::Mixed => '?',
+                    };
+                }
+                // The execute position also carries the special bit, as `s`
+                // or `t` when both are set and capitalized without execute.
+                let (set, unset) = if row == 2 { ('t', 'T') } else { ('s', 'S') };
+                match (state, self.state(special)) {
+                    (BitState::Mixed, _) | (_, BitState::Mixed) => '?',
+                    (BitState::On, BitState::On) => set,
+                    (BitState::Off, BitState::On) => unset,
+                    (BitState::On, BitState::Off) => 'x',
+                    (BitState::Off, BitState::Off) => '-',
+                }
+            })
+            .collect()
+    }
+
+    /// Closes the editor, with a chmod of the touched bits unless nothing was
+    /// changed.
+    fn apply(&mut self) -> CommandResult {
+        let mode = self.symbolic_changes();
+        let paths = std::mem::take(&mut self.paths);
+        self.hide();
+        if mode.is_empty() {
+            return CommandResult::Handled;
+        }
+        Command::Chmod {
+            paths,
+            mode,
+            recursive: false,
+        }
+        .into()
+    }
+}
+
+/// The toggle at `(row, column)`: read, write and execute for user, group and
+/// other, then setuid, setgid and sticky.
+fn bit_at(row: usize, column: usize) -> u32 {
+    if row == ROWS - 1 {
+        0o4000 >> column
+    } else {
+        0o400 >> (row * 3 + column)
+    }
+}
+
+/// The mode a chmod of `path` would change: for a symlink, its target's, since
+/// the link's own mode is fixed and `chmod` follows it.
+fn effective_mode(path: &PathInfo) -> u32 {
+    let mode = if path.is_symlink() {
+        fs::metadata(&path.path).map_or(path.mode(), |metadata| metadata.mode())
+    } else {
+        path.mode()
+    };
+    mode & 0o7777
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn editor(modes: &[u32]) -> (TempDir, PermissionsView) {
+        Config::init_test();
+        let fx = TempDir::new("permissions");
+        let paths: Vec<PathInfo> = modes
+            .iter()
+            .enumerate()
+            .map(|(index, mode)| {
+                let path = fx.join(format!("{index}.txt"));
+                fs::write(&path, b"x").unwrap();
+                fs::set_permissions(&path, fs::Permissions::from_mode(*mode)).unwrap();
+                PathInfo::try_from(path.as_path()).unwrap()
+            })
+            .collect();
+        let mut view = PermissionsView::new();
+        view.show(&paths);
+        (fx, view)
+    }
+
+    fn applied(view: &mut PermissionsView) -> Option<String> {
+        match Command::try_from(view.apply()) {
+            Ok(Command::Chmod { mode, .. }) => Some(mode),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn the_grid_starts_from_the_entrys_mode() {
+        let (_fx, view) = editor(&[0o4750]);
+
+        assert_eq!("4750", view.octal_preview());
+        assert_eq!("rwsr-x---", view.symbolic_preview());
+    }
+
+    #[test]
+    fn a_bit_the_entries_disagree_on_is_mixed() {
+        let (_fx, view) = editor(&[0o644, 0o664]);
+
+        assert_eq!(BitState::Mixed, view.state(0o020));
+        assert_eq!(BitState::On, view.state(0o040));
+        assert_eq!(BitState::Off, view.state(0o010));
+        assert_eq!("06?4", view.octal_preview());
+        assert_eq!("rw-r?-r--", view.symbolic_preview());
+    }
+
+    #[test]
+    fn only_touched_bits_are_applied() {
+        let (_fx, mut view) = editor(&[0o644, 0o600]);
+        view.toggle(0o100);
+        view.toggle(0o002);
+
+        // The group and other read bits differ between the entries and were
+        // left alone, so the chmod must not name them.
+        assert_eq!(Some("u+x,o+w".to_string()), applied(&mut view));
+    }
+
+    #[test_case(0o644, &[BitState::Off, BitState::On] ; "a set bit alternates")]
+    #[test_case(0o600, &[BitState::On, BitState::Off] ; "a clear bit alternates")]
+    fn a_uniform_bit_toggles_between_on_and_off(mode: u32, expected: &[BitState]) {
+        let (_fx, mut view) = editor(&[mode]);
+
+        for state in expected {
+            view.toggle(0o040);
+            assert_eq!(*state, view.state(0o040));
+        }
+        // Back where it started, so there is nothing to apply.
+        assert_eq!(None, applied(&mut view));
+    }
+
+    #[test]
+    fn a_mixed_bit_cycles_back_to_untouched() {
+        let (_fx, mut view) = editor(&[0o644, 0o664]);
+
+        view.toggle(0o020);
+        assert_eq!(BitState::On, view.state(0o020));
+        view.toggle(0o020);
+        assert_eq!(BitState::Off, view.state(0o020));
+        view.toggle(0o020);
+        // Without this step a mixed bit, once touched, could only be forced
+        // one way or the other on every entry.
+        assert_eq!(BitState::Mixed, view.state(0o020));
+        assert_eq!(0, view.touched);
+    }
+
+    #[test]
+    fn the_special_bits_apply_to_their_own_classes() {
+        let (_fx, mut view) = editor(&[0o755]);
+        view.toggle(bit_at(3, 1));
+        view.toggle(bit_at(3, 2));
+
+        assert_eq!("3755", view.octal_preview());
+        assert_eq!(Some("g+s,o+t".to_string()), applied(&mut view));
+    }
+
+    #[test]
+    fn applying_nothing_only_closes() {
+        let (_fx, mut view) = editor(&[0o644]);
+
+        assert_eq!(CommandResult::Handled, view.apply());
+        assert!(!view.is_visible());
+    }
+
+    #[test]
+    fn the_cursor_stays_on_the_grid() {
+        let (_fx, mut view) = editor(&[0o644]);
+
+        view.move_cursor(-1, -1);
+        assert_eq!((0, 0), view.cursor);
+        view.move_cursor(10, 10);
+        assert_eq!((ROWS - 1, COLUMNS - 1), view.cursor);
+    }
+}
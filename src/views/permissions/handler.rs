@@ -0,0 +1,73 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::{
+    COLUMNS, PermissionsView,
+    widget::{CELL_WIDTH, LABEL_WIDTH, grid_row},
+};
+use crate::{
+    app::config::{
+        Config,
+        keybindings::{Action, hardcoded_normal_action},
+    },
+    command::{handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for PermissionsView {
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        // Enter is also bound to Open, which moves the cursor right here, so
+        // it is checked before the bindings are.
+        if code == KeyCode::Enter && modifiers == KeyModifiers::NONE {
+            return self.apply();
+        }
+        let action = hardcoded_normal_action(code, modifiers)
+            .or_else(|| Config::global().keybindings.normal_action(code, modifiers));
+        match action {
+            Some(Action::EditPermissions) => {
+                self.hide();
+                CommandResult::Handled
+            }
+            Some(Action::ToggleMark) => self.toggle_cursor(),
+            Some(Action::SelectNext) => self.move_cursor(1, 0),
+            Some(Action::SelectPrevious) => self.move_cursor(-1, 0),
+            // Left and right, with their bound letters, move between columns
+            // instead of directories.
+            Some(Action::GoToParentDirectory) => self.move_cursor(0, -1),
+            Some(Action::Open) => self.move_cursor(0, 1),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> CommandResult {
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind
+            && self.content_area.contains(Position {
+                x: event.column,
+                y: event.row,
+            })
+            && let Some(row) = grid_row(event.row - self.content_area.y)
+        {
+            let x = event.column - self.content_area.x;
+            // A click on a label or past the last column toggles nothing.
+            if let Some(column) = x
+                .checked_sub(LABEL_WIDTH)
+                .map(|x| (x / CELL_WIDTH) as usize)
+                && column < COLUMNS
+            {
+                self.cursor = (row, column);
+                return self.toggle_cursor();
+            }
+        }
+        // Claim everything else so a stray event cannot leak to the views
+        // the editor is covering.
+        CommandResult::Handled
+    }
+
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        self.area.contains(Position {
+            x: event.column,
+            y: event.row,
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+};
+
+use super::{MIN_HEIGHT, PermissionsView, widget::build_lines};
+use crate::{
+    app::config::Config,
+    views::{View, bordered, render_lines},
+};
+
+impl View for PermissionsView {
+    /// The same constraint as `TableView`, so the editor lands in exactly the
+    /// table's slot and nothing above or below it moves.
+    fn constraint(&self, _: Rect) -> Constraint {
+        Constraint::Min(MIN_HEIGHT)
+    }
+
+    fn render(&mut self, area: Rect, frame: &mut Frame<'_>) {
+        if area.height < MIN_HEIGHT {
+            // Zero-size areas clear both hit test regions, so a click on the
+            // sliver that is left cannot be tested against a stale layout.
+            self.area = Rect::default();
+            self.content_area = Rect::default();
+            return;
+        }
+        self.area = area;
+
+        let theme = &Config::global().theme().permissions;
+        let style = theme.base();
+        let content_area = bordered(area, frame.buffer_mut(), style, &self.title, &self.hint);
+        self.content_area = content_area;
+        let lines = build_lines(theme, self);
+        render_lines(&lines, content_area, frame.buffer_mut(), style, 0);
+    }
+}
@@ -0,0 +1,138 @@
+use ratatui::text::{Line, Span};
+
+use super::{BitState, COLUMNS, PermissionsView, ROWS, bit_at};
+use crate::app::config::{Config, keybindings::Action, theme::Permissions};
+
+/// Columns before the first toggle: the row label.
+pub(super) const LABEL_WIDTH: u16 = 10;
+/// Columns per toggle, including the gap after it.
+pub(super) const CELL_WIDTH: u16 = 8;
+
+const HEADERS: [&str; 2] = ["read    write   execute", "setuid  setgid  sticky"];
+const LABELS: [&str; ROWS] = ["user", "group", "other", "special"];
+
+/// The grid row drawn on content line `line`, if that line holds toggles. The
+/// special bits get a header of their own, so their row is one line further
+/// down than the others' pattern would put it.
+pub(super) fn grid_row(line: u16) -> Option<usize> {
+    match line {
+        1..=3 => Some(line as usize - 1),
+        5 => Some(ROWS - 1),
+        _ => None,
+    }
+}
+
+pub(super) fn build_lines(theme: &Permissions, view: &PermissionsView) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for row in 0..ROWS {
+        if row == 0 || row == ROWS - 1 {
+            let header = HEADERS[usize::from(row != 0)];
+            lines.push(Line::styled(
+                format!("{}{header}", " ".repeat(LABEL_WIDTH as usize)),
+                theme.header(),
+            ));
+        }
+        let mut spans = vec![Span::raw(format!(
+            "  {:<width$}",
+            LABELS[row],
+            width = LABEL_WIDTH as usize - 2
+        ))];
+        for column in 0..COLUMNS {
+            let bit = bit_at(row, column);
+            let toggle = match view.state(bit) {
+                BitState::On => "[x]",
+                BitState::Off => "[ ]",
+                BitState::Mixed => "[-]",
+            };
+            let style = if view.cursor == (row, column) {
+                theme.selected()
+            } else if view.touched & bit != 0 {
+                theme.changed()
+            } else {
+                theme.base()
+            };
+            spans.push(Span::styled(toggle, style));
+            spans.push(Span::raw(" ".repeat(CELL_WIDTH as usize - toggle.len())));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::raw(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Result  ", theme.header()),
+        Span::raw(format!(
+            "{}  {}",
+            view.octal_preview(),
+            view.symbolic_preview()
+        )),
+    ]));
+    let changes = view.symbolic_changes();
+    lines.push(Line::from(vec![
+        Span::styled("  Change  ", theme.header()),
+        if changes.is_empty() {
+            Span::raw("(nothing yet)")
+        } else {
+            Span::styled(changes, theme.changed())
+        },
+    ]));
+    lines.push(Line::raw(""));
+    let kb = &Config::global().keybindings;
+    lines.push(Line::styled(
+        format!(
+            "  {} toggles, Enter applies",
+            kb.hint_for(&[Action::ToggleMark])
+        ),
+        theme.header(),
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_system::path_info::PathInfo, test_support::TempDir};
+
+    fn text(line: &Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    fn lines() -> (TempDir, Vec<Line<'static>>) {
+        Config::init_test();
+        let fx = TempDir::new("permissions_widget");
+        std::fs::write(fx.join("a.txt"), b"x").unwrap();
+        let mut view = PermissionsView::new();
+        view.show(&[PathInfo::try_from(fx.join("a.txt").as_path()).unwrap()]);
+        let lines = build_lines(&Config::global().theme().permissions, &view);
+        (fx, lines)
+    }
+
+    #[test]
+    fn grid_row_names_exactly_the_lines_that_hold_toggles() {
+        let (_fx, lines) = lines();
+
+        // A click is mapped back to a toggle through `grid_row`, so it has to
+        // agree with where `build_lines` actually draws them.
+        for (index, line) in lines.iter().enumerate() {
+            let index = u16::try_from(index).unwrap();
+            assert_eq!(
+                text(line).contains('['),
+                grid_row(index).is_some(),
+                "line {index}: {:?}",
+                text(line)
+            );
+        }
+    }
+
+    #[test]
+    fn each_toggle_starts_where_a_click_would_find_it() {
+        let (_fx, lines) = lines();
+
+        let row = text(&lines[1]);
+        for column in 0..COLUMNS {
+            let at = LABEL_WIDTH as usize + CELL_WIDTH as usize * column;
+            assert_eq!(Some('['), row.chars().nth(at), "{row:?}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+mod handler;
+mod widget;
+
+use std::{cmp::Ordering, path::Path};
+
+use ratatui::{Frame, layout::Rect};
+
+pub(super) use self::widget::{render_entries, render_message};
+use crate::{
+    app::config::Config,
+    command::{Command, result::CommandResult},
+    file_system::{
+        path_info::{PathInfo, name_comparator},
+        peek::PeekColumn,
+        preview::FilePreview,
+    },
+};
+
+/// What the preview holds of the selected entry: nothing yet, a directory's
+/// entries, the start of a file, or why neither could be read.
+#[derive(Debug, Default)]
+enum Content {
+    #[default]
+    Empty,
+    Entries(Vec<PathInfo>),
+    File(FilePreview),
+    Failed(String),
+}
+
+/// A preview of the entry under the cursor: a directory's entries, the first
+/// lines of a file, or the details of anything else. It follows the cursor
+/// from the selection the table broadcasts, and FileSystem reads what it
+/// shows, so nothing is read on the UI thread, and only once the cursor
+/// lands on it. Shown beside the listing, or as the right of the Miller
+/// columns.
+#[derive(Debug, Default)]
+pub(super) struct PreviewView {
+    selected: Option<PathInfo>,
+    content: Content,
+}
+
+impl PreviewView {
+    /// Previews `selected` afresh, as when the preview is shown again after
+    /// being hidden.
+    pub(super) fn show(&mut self, selected: Option<&PathInfo>) -> CommandResult {
+        *self = Self::default();
+        self.set_selected(selected)
+            .map_or(CommandResult::Handled, Into::into)
+    }
+
+    /// Takes up the entry under the cursor, asking for what it shows of it.
+    pub(super) fn set_selected(&mut self, selected: Option<&PathInfo>) -> Option<Command> {
+        if self.selected.as_ref().map(|entry| &entry.path) == selected.map(|entry| &entry.path) {
+            return None;
+        }
+        self.selected = selected.cloned();
+        self.content = Content::Empty;
+        let selected = selected?;
+        if selected.is_directory() {
+            Some(Command::Peek {
+                path: selected.clone(),
+                column: PeekColumn::Preview,
+            })
+        } else if selected.is_file() {
+            Some(Command::PreviewFile(selected.clone()))
+        } else {
+            // Reading a FIFO or a device could block, and following a
+            // symlink could land on one, so they are shown by their details.
+            None
+        }
+    }
+
+    /// Whether `path` is still the entry under the cursor, for a peek or a
+    /// preview that arrives after the cursor may have moved on.
+    fn is_selected(&self, path: &Path) -> bool {
+        self.selected
+            .as_ref()
+            .is_some_and(|selected| selected.path == path)
+    }
+
+    fn peeked(&mut self, path: &Path, entries: &Result<Vec<PathInfo>, String>) {
+        if !self.is_selected(path) {
+            return;
+        }
+        self.content = match entries {
+            Ok(entries) => Content::Entries(sorted(entries)),
+            Err(error) => Content::Failed(error.clone()),
+        };
+    }
+
+    fn previewed(&mut self, path: &Path, preview: &Result<FilePreview, String>) {
+        if !self.is_selected(path) {
+            return;
+        }
+        self.content = match preview {
+            Ok(preview) => Content::File(preview.clone()),
+            Err(error) => Content::Failed(error.clone()),
+        };
+    }
+
+    /// Draws the preview in `area`, leaving dotfiles out of a directory's
+    /// entries when the listing does.
+    pub(super) fn render(&self, area: Rect, show_hidden: bool, frame: &mut Frame<'_>) {
+        let buf = frame.buffer_mut();
+        let Some(selected) = &self.selected else {
+            return;
+        };
+        match &self.content {
+            Content::Entries(entries) => {
+                widget::render_entries(entries, None, show_hidden, area, buf);
+            }
+            Content::File(FilePreview::Text(lines)) => widget::render_text(lines, area, buf),
+            Content::File(FilePreview::Highlighted(lines)) => {
+                widget::render_highlighted(lines, area, buf);
+            }
+            Content::File(FilePreview::Binary) => {
+                widget::render_details(selected, Some("Binary file"), area, buf);
+            }
+            Content::Failed(error) => widget::render_message(error, area, buf),
+            // Still being read.
+            Content::Empty if selected.is_directory() || selected.is_file() => {}
+            Content::Empty => widget::render_details(selected, None, area, buf),
+        }
+    }
+}
+
+/// `entries` in the order the table sorts by name in: directories first
+/// when configured to be, then by name.
+pub(super) fn sorted(entries: &[PathInfo]) -> Vec<PathInfo> {
+    let mut entries = entries.to_vec();
+    let directories_first = Config::global().ui.sort_directories_first;
+    entries.sort_by(|a, b| {
+        let order = if directories_first {
+            b.is_directory().cmp(&a.is_directory())
+        } else {
+            Ordering::Equal
+        };
+        order.then_with(|| name_comparator(&a.display_name).cmp(&name_comparator(&b.display_name)))
+    });
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn info(path: &Path) -> PathInfo {
+        PathInfo::try_from(path).unwrap()
+    }
+
+    #[test]
+    fn a_directory_is_peeked_into_and_a_file_previewed() {
+        Config::init_test();
+        let fx = TempDir::new("preview");
+        fs::create_dir(fx.join("sub")).unwrap();
+        fs::write(fx.join("file"), b"text").unwrap();
+        std::os::unix::fs::symlink("file", fx.join("link")).unwrap();
+        let mut view = PreviewView::default();
+
+        assert_eq!(
+            Some(Command::Peek {
+                path: info(&fx.join("sub")),
+                column: PeekColumn::Preview,
+            }),
+            view.set_selected(Some(&info(&fx.join("sub"))))
+        );
+        assert_eq!(
+            Some(Command::PreviewFile(info(&fx.join("file")))),
+            view.set_selected(Some(&info(&fx.join("file"))))
+        );
+        // The same entry again needs nothing read.
+        assert_eq!(None, view.set_selected(Some(&info(&fx.join("file")))));
+        assert_eq!(None, view.set_selected(Some(&info(&fx.join("link")))));
+    }
+
+    #[test]
+    fn a_preview_the_cursor_has_moved_on_from_is_dropped() {
+        Config::init_test();
+        let fx = TempDir::new("preview");
+        fs::write(fx.join("a"), b"a").unwrap();
+        fs::write(fx.join("b"), b"b").unwrap();
+        let mut view = PreviewView::default();
+        view.set_selected(Some(&info(&fx.join("a"))));
+        view.set_selected(Some(&info(&fx.join("b"))));
+
+        view.previewed(&fx.join("a"), &Ok(FilePreview::Binary));
+        assert!(matches!(view.content, Content::Empty));
+
+        view.previewed(&fx.join("b"), &Ok(FilePreview::Text(vec!["b".into()])));
+        assert!(matches!(view.content, Content::File(FilePreview::Text(_))));
+    }
+}
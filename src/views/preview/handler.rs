@@ -0,0 +1,29 @@
+use super::PreviewView;
+use crate::{
+    command::{Command, handler::CommandHandler, result::CommandResult},
+    file_system::peek::PeekColumn,
+};
+
+impl CommandHandler for PreviewView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            // The table's own broadcast, which the views beside it read too.
+            Command::SelectionChanged { selected, .. } => self
+                .set_selected(selected.as_ref())
+                .map_or(CommandResult::NotHandled, Into::into),
+            Command::Peeked {
+                path,
+                column: PeekColumn::Preview,
+                entries,
+            } => {
+                self.peeked(path, entries);
+                CommandResult::Handled
+            }
+            Command::Previewed { path, preview } => {
+                self.previewed(path, preview);
+                CommandResult::Handled
+            }
+            _ => CommandResult::NotHandled,
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use chrono::Local;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::{
+    app::config::{Config, theme::Preview},
+    file_system::{
+        highlight::{Token, TokenKind},
+        path_info::PathInfo,
+    },
+    views::{render_lines, table::name_style},
+};
+
+/// Columns between tab stops, as a terminal sets them.
+const TAB_WIDTH: usize = 8;
+
+/// Draws `entries` one name per line, leaving out dotfiles unless
+/// `show_hidden`. `highlight` is the entry drawn as selected, kept in view
+/// when there are more than fit.
+pub(crate) fn render_entries(
+    entries: &[PathInfo],
+    highlight: Option<&PathInfo>,
+    show_hidden: bool,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let theme = Config::global().theme();
+    let shown: Vec<_> = entries
+        .iter()
+        .filter(|entry| show_hidden || !entry.is_hidden())
+        .collect();
+    if shown.is_empty() {
+        render_message("Empty", area, buf);
+        return;
+    }
+    let highlighted =
+        highlight.and_then(|highlight| shown.iter().position(|entry| entry.path == highlight.path));
+    let lines: Vec<_> = shown
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let line = Line::from(format!(" {}", entry.name()));
+            if highlighted == Some(index) {
+                line.style(theme.table.selected())
+            } else {
+                line.style(name_style(&theme.file_type, entry))
+            }
+        })
+        .collect();
+    render_lines(
+        &lines,
+        area,
+        buf,
+        theme.table.body(),
+        scroll_to(highlighted, lines.len(), area.height),
+    );
+}
+
+/// Draws `message` where a column's entries would be.
+pub(crate) fn render_message(message: &str, area: Rect, buf: &mut Buffer) {
+    let theme = Config::global().theme();
+    render_lines(
+        &[Line::from(format!(" {message}"))],
+        area,
+        buf,
+        theme.table.body(),
+        0,
+    );
+}
+
+/// Draws what the table does not show of an entry that is not previewed:
+/// its details, one per line, under `note` when there is one.
+pub(super) fn render_details(entry: &PathInfo, note: Option<&str>, area: Rect, buf: &mut Buffer) {
+    let theme = Config::global().theme();
+    let label_style = theme.table.body().add_modifier(Modifier::BOLD);
+    let mut fields = vec![(" Size", entry.size()), (" Mode", entry.unix_mode())];
+    if let Some(modified) = entry.modified(Local::now()) {
+        fields.push((" Modified", modified));
+    }
+    let mut lines: Vec<_> = note
+        .map(|note| Line::from(format!(" {note}")))
+        .into_iter()
+        .collect();
+    lines.extend(fields.into_iter().map(|(label, value)| {
+        Line::from(vec![
+            Span::styled(format!("{label:<10}"), label_style),
+            Span::raw(value),
+        ])
+    }));
+    render_lines(&lines, area, buf, theme.table.body(), 0);
+}
+
+/// Draws the first lines of a text file, as many as fit.
+pub(super) fn render_text(lines: &[String], area: Rect, buf: &mut Buffer) {
+    let style = Config::global().theme().preview.base();
+    render_pieces(
+        lines.iter().map(|line| vec![(line.as_str(), style)]),
+        area,
+        buf,
+    );
+}
+
+/// Draws the first lines of a text file highlighted, as many as fit.
+pub(super) fn render_highlighted(lines: &[Vec<Token>], area: Rect, buf: &mut Buffer) {
+    let preview = &Config::global().theme().preview;
+    render_pieces(
+        lines.iter().map(|line| {
+            line.iter()
+                .map(|token| (token.text.as_str(), token_style(preview, token.kind)))
+                .collect()
+        }),
+        area,
+        buf,
+    );
+}
+
+fn token_style(preview: &Preview, kind: TokenKind) -> Style {
+    match kind {
+        TokenKind::Plain => preview.base(),
+        TokenKind::Keyword => preview.keyword(),
+        TokenKind::String => preview.string(),
+        TokenKind::Number => preview.number(),
+        TokenKind::Comment => preview.comment(),
+    }
+}
+
+/// Draws lines made of pieces of text in the style of each.
+fn render_pieces<'a>(
+    lines: impl Iterator<Item = Vec<(&'a str, Style)>>,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let theme = Config::global().theme();
+    let lines: Vec<_> = lines
+        .take(usize::from(area.height))
+        .map(|pieces| visible_line(&pieces, theme.preview.marker()))
+        .collect();
+    render_lines(&lines, area, buf, theme.preview.base(), 0);
+}
+
+/// `pieces`, a line's text in the style of each piece, as a line in which a
+/// tab and a control character can be seen: a tab as an arrow padded out to
+/// the next tab stop, and a control character as its symbol from the
+/// Control Pictures block, both in `marker_style`.
+fn visible_line(pieces: &[(&str, Style)], marker_style: Style) -> Line<'static> {
+    let mut spans = Vec::new();
+    // Where the next character lands, for the width of a tab.
+    let mut column = 0;
+    for &(text, style) in pieces {
+        let mut plain = String::new();
+        for c in text.chars() {
+            let marker = match c {
+                '\t' => {
+                    let width =
+                        TAB_WIDTH - (column + Span::raw(plain.as_str()).width()) % TAB_WIDTH;
+                    format!("→{}", " ".repeat(width - 1))
+                }
+                '\0'..='\x1f' => char::from_u32(0x2400 + u32::from(c))
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+                    .to_string(),
+                '\x7f' => "␡".to_string(),
+                c => {
+                    plain.push(c);
+                    continue;
+                }
+            };
+            if !plain.is_empty() {
+                let span = Span::styled(std::mem::take(&mut plain), style);
+                column += span.width();
+                spans.push(span);
+            }
+            let span = Span::styled(marker, marker_style);
+            column += span.width();
+            spans.push(span);
+        }
+        if !plain.is_empty() {
+            let span = Span::styled(plain, style);
+            column += span.width();
+            spans.push(span);
+        }
+    }
+    Line::from(spans)
+}
+
+/// How far down a column of `len` lines to start for `highlighted` to sit in
+/// the middle of the `height` shown, as near as the ends allow.
+fn scroll_to(highlighted: Option<usize>, len: usize, height: u16) -> u16 {
+    let height = usize::from(height);
+    let Some(highlighted) = highlighted else {
+        return 0;
+    };
+    let top = highlighted
+        .saturating_sub(height / 2)
+        .min(len.saturating_sub(height));
+    u16::try_from(top).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Style};
+    use test_case::test_case;
+
+    use super::{scroll_to, visible_line};
+
+    #[test_case(None, 100 => 0 ; "nothing highlighted")]
+    #[test_case(Some(2), 100 => 0 ; "near the top")]
+    #[test_case(Some(50), 100 => 45 ; "in the middle")]
+    #[test_case(Some(98), 100 => 90 ; "near the end")]
+    #[test_case(Some(3), 5 => 0 ; "all in view")]
+    fn the_highlighted_entry_is_kept_in_view(highlighted: Option<usize>, len: usize) -> u16 {
+        scroll_to(highlighted, len, 10)
+    }
+
+    #[test_case("a\tb" => "a→      b" ; "a tab pads to the next stop")]
+    #[test_case("日本\tb" => "日本→   b" ; "wide characters count twice")]
+    #[test_case("a\x1b[0m\x7f" => "a␛[0m␡" ; "control characters")]
+    #[test_case("plain" => "plain" ; "nothing to show")]
+    fn tabs_and_control_characters_are_shown(text: &str) -> String {
+        visible_line(&[(text, Style::default())], Color::Red.into()).to_string()
+    }
+
+    #[test]
+    fn only_the_markers_take_the_marker_style() {
+        let blue = Style::from(Color::Blue);
+        let line = visible_line(
+            &[("a\t", blue), ("b\tc", Style::default())],
+            Color::Red.into(),
+        );
+
+        let styled: Vec<_> = line.spans.iter().map(|span| span.style.fg).collect();
+        assert_eq!(
+            vec![
+                Some(Color::Blue),
+                Some(Color::Red),
+                None,
+                Some(Color::Red),
+                None
+            ],
+            styled
+        );
+        // A tab stop is found across pieces.
+        assert_eq!("a→      b→      c", line.to_string());
+    }
+}
@@ -8,10 +8,19 @@ use ratatui::buffer::CellWidth;
 use ratatui::layout::Rect;
 use ratatui_textarea::{CursorMove, TextArea};
 
-use super::{View, as_dimension, unicode::pluralize_items};
+use super::{
+    View, as_dimension,
+    unicode::{pluralize, pluralize_items},
+};
 use crate::{
-    command::{Command, PromptAction, result::CommandResult},
-    file_system::path_info::PathInfo,
+    command::{Command, PromptAction, TouchTime, result::CommandResult},
+    file_system::{
+        braces::{self, MAX_EXPANSIONS},
+        owner,
+        path_info::{PathInfo, humanize_size},
+        sftp::{self, Address},
+        summary::TreeSummary,
+    },
 };
 
 #[derive(Default)]
@@ -22,34 +31,104 @@ pub(super) struct PromptView {
     render_area: Rect,
     /// Horizontal scroll offset (in display columns), mirroring tui-textarea's internal viewport.
     scroll_col: u16,
-    /// Goto: the directory that relative input is resolved against.
+    /// Goto and CopyTo: the directory that relative input is resolved
+    /// against.
     basedir: String,
-    /// Goto: prefix-matching entries `(name, is_dir)`, sorted ascending.
+    /// Goto, CopyTo and Chown: prefix-matching entries `(name, is_dir)`, sorted
+    /// ascending. A Chown suggestion is never a directory.
     suggestions: Vec<(String, bool)>,
-    /// Goto: index of the currently shown suggestion.
+    /// Goto, CopyTo and Chown: index of the currently shown suggestion.
     suggestion_index: usize,
     /// Goto: the directory `cached_entries` was read from. Avoids re-reading
     /// the filesystem on every keystroke while the directory prefix is unchanged.
     cached_dir: Option<PathBuf>,
     /// Goto: every entry of `cached_dir` as `(name, is_dir)`, sorted ascending.
     cached_entries: Vec<(String, bool)>,
+    /// Chown: the user names completed before the `:`, read when the prompt
+    /// opens.
+    user_names: Vec<String>,
+    /// Chown: the group names completed after the `:`.
+    group_names: Vec<String>,
+    /// Delete: the latest count of what the delete would remove, streamed in
+    /// while the prompt is open. `None` until the walk first reports.
+    delete_summary: Option<TreeSummary>,
 }
 
 impl PromptView {
     fn label(&self) -> String {
         match &self.actions {
-            PromptAction::Chmod { paths, .. } => {
-                format!(" Chmod {} (octal) ", pluralize_items(paths.len()))
-            }
+            PromptAction::Chmod {
+                paths,
+                recursive: false,
+                ..
+            } => format!(" Chmod {} (644, u+x) ", pluralize_items(paths.len())),
+            PromptAction::Chmod {
+                paths,
+                recursive: true,
+                ..
+            } => format!(
+                " Chmod {} recursively (a+rX, or directories then files: 755 644) ",
+                pluralize_items(paths.len())
+            ),
+            PromptAction::Chown {
+                paths,
+                recursive: false,
+                ..
+            } => format!(" Chown {} (user:group) ", pluralize_items(paths.len())),
+            PromptAction::Chown {
+                paths,
+                recursive: true,
+                ..
+            } => format!(
+                " Chown {} recursively (user:group) ",
+                pluralize_items(paths.len())
+            ),
+            PromptAction::Touch {
+                paths,
+                recursive: false,
+            } => format!(
+                " Touch {} (now, date or time) ",
+                pluralize_items(paths.len())
+            ),
+            PromptAction::Touch {
+                paths,
+                recursive: true,
+            } => format!(
+                " Touch {} recursively (now, date or time) ",
+                pluralize_items(paths.len())
+            ),
+            PromptAction::Compress { paths, .. } => format!(
+                " Compress {} (.tar, .tar.gz, .tar.xz, .tar.zst or .zip) ",
+                pluralize_items(paths.len())
+            ),
             PromptAction::AddBookmark { .. } => " Add bookmark ".to_string(),
-            PromptAction::CreateDirectory => " New directory ".to_string(),
-            PromptAction::Delete(count) => {
-                format!(" Delete {}? (y/n) ", pluralize_items(*count))
+            PromptAction::CreateDirectory => self.create_directory_label(),
+            PromptAction::CreateFile => " New file ".to_string(),
+            PromptAction::CreateFromTemplate { template, .. } => {
+                format!(" New from {} ", template.display_name)
             }
+            PromptAction::Delete(count) => match &self.delete_summary {
+                None => format!(" Delete {}? (y/n) ", pluralize_items(*count)),
+                Some(summary) => format!(
+                    " Delete {} ({})? (y/n) ",
+                    pluralize_items(*count),
+                    describe_summary(summary)
+                ),
+            },
             PromptAction::Filter(_) => " Filter ".to_string(),
             PromptAction::Goto { .. } => " Go to ".to_string(),
             PromptAction::Rename { .. } => " Rename ".to_string(),
             PromptAction::Search(_) => " Search ".to_string(),
+            PromptAction::CopyTo {
+                paths,
+                is_move: false,
+                ..
+            } => format!(" Copy {} to ", pluralize_items(paths.len())),
+            PromptAction::CopyTo {
+                paths,
+                is_move: true,
+                ..
+            } => format!(" Move {} to ", pluralize_items(paths.len())),
             PromptAction::Conflict {
                 name,
                 can_overwrite: true,
@@ -61,23 +140,51 @@ impl PromptView {
         }
     }
 
+    /// Previews how many directories the input names once its braces expand,
+    /// so a pattern that multiplies out further than meant shows it before it
+    /// is submitted. The directories leading to each are not counted: they are
+    /// what the user typed on the way, not what they asked for.
+    fn create_directory_label(&self) -> String {
+        let input = self.text_area.lines().join("");
+        match braces::expand(&input) {
+            Ok(names) if names.len() > 1 => format!(
+                " New directory ({}) ",
+                pluralize(names.len() as u64, "directory", "directories")
+            ),
+            Ok(_) => " New directory ".to_string(),
+            Err(_) => format!(" New directory (over {MAX_EXPANSIONS}) "),
+        }
+    }
+
     fn open(&mut self, kind: &PromptAction) -> CommandResult {
         let text = match kind {
             PromptAction::Chmod { mode, .. } => mode.clone(),
+            PromptAction::Chown { owner, .. } => owner.clone(),
+            PromptAction::Touch { .. } => "now".to_string(),
+            PromptAction::CopyTo { destination, .. } => destination.location(),
             PromptAction::Conflict { .. }
             | PromptAction::CreateDirectory
+            | PromptAction::CreateFile
             | PromptAction::Delete(_)
             | PromptAction::Goto { .. } => String::new(),
             PromptAction::AddBookmark { name: text, .. }
+            | PromptAction::Compress { name: text, .. }
+            | PromptAction::CreateFromTemplate { name: text, .. }
             | PromptAction::Filter(text)
             | PromptAction::Rename { name: text, .. }
             | PromptAction::Search(text) => text.clone(),
         };
         self.actions = kind.clone();
+        self.delete_summary = None;
         self.initial_text.clone_from(&text);
         self.reset_text(&text);
-        if let PromptAction::Goto { directory } = kind {
-            self.basedir.clone_from(directory);
+        let basedir = match kind {
+            PromptAction::Goto { directory } => Some(directory.clone()),
+            PromptAction::CopyTo { destination, .. } => Some(destination.location()),
+            _ => None,
+        };
+        if let Some(basedir) = basedir {
+            self.basedir = basedir;
             self.suggestion_index = 0;
             // Drop any cache from a previous prompt so on-disk changes since
             // it was last open are picked up.
@@ -85,9 +192,23 @@ impl PromptView {
             self.cached_entries.clear();
             self.refresh_suggestions();
         }
+        if matches!(kind, PromptAction::Chown { .. }) {
+            self.user_names = owner::user_names();
+            self.group_names = owner::group_names();
+            self.suggestion_index = 0;
+            self.refresh_suggestions();
+        }
         CommandResult::Handled
     }
 
+    /// Whether this prompt offers type-ahead suggestions.
+    fn completes(&self) -> bool {
+        matches!(
+            self.actions,
+            PromptAction::Goto { .. } | PromptAction::CopyTo { .. } | PromptAction::Chown { .. }
+        )
+    }
+
     fn reset_text(&mut self, text: &str) {
         let mut text_area = TextArea::from([text]);
         text_area.move_cursor(CursorMove::End);
@@ -128,15 +249,39 @@ impl PromptView {
     fn submit(&mut self) -> CommandResult {
         let value = self.text_area.lines().join("");
         match &self.actions {
-            PromptAction::Chmod { paths, .. } => Command::Chmod {
+            PromptAction::Chmod {
+                paths, recursive, ..
+            } => Command::Chmod {
                 paths: paths.clone(),
                 mode: value,
+                recursive: *recursive,
+            },
+            PromptAction::Chown {
+                paths, recursive, ..
+            } => Command::Chown {
+                paths: paths.clone(),
+                owner: value,
+                recursive: *recursive,
+            },
+            PromptAction::Touch { paths, recursive } => Command::Touch {
+                paths: paths.clone(),
+                time: TouchTime::Typed(value),
+                recursive: *recursive,
+            },
+            PromptAction::Compress { paths, .. } => Command::Compress {
+                paths: paths.clone(),
+                name: value,
             },
             PromptAction::AddBookmark { directory, .. } => Command::AddBookmark {
                 directory: directory.clone(),
                 name: value,
             },
             PromptAction::CreateDirectory => Command::CreateDirectory(value),
+            PromptAction::CreateFile => Command::CreateFile(value),
+            PromptAction::CreateFromTemplate { template, .. } => Command::CreateFromTemplate {
+                template: template.clone(),
+                name: value,
+            },
             // The confirmation prompts resolve in `handle_key` on a single
             // keypress, so submit never reaches them; treat it as a cancel
             // rather than guessing an answer on the user's behalf.
@@ -153,10 +298,27 @@ impl PromptView {
                             path.display()
                         )),
                     }
+                } else if let Some(address) = self.remote_address(&value) {
+                    Command::OpenRemote(address)
                 } else {
                     Command::AlertWarn(format!("Path does not exist: {}", path.display()))
                 }
             }
+            PromptAction::CopyTo {
+                paths,
+                destination,
+                is_move,
+            } => match self.copy_destination(&value, destination) {
+                Ok(dest) if *is_move => Command::Move {
+                    srcs: paths.clone(),
+                    dest,
+                },
+                Ok(dest) => Command::Copy {
+                    srcs: paths.clone(),
+                    dest,
+                },
+                Err(message) => Command::AlertWarn(message),
+            },
             PromptAction::Rename { path, .. } => Command::Rename {
                 path: path.clone(),
                 name: value,
@@ -190,6 +352,35 @@ impl PromptView {
         }
     }
 
+    /// The directory a CopyTo `input` names: the other pane's as pre-filled,
+    /// or one typed in its place, here or on a host already connected to.
+    fn copy_destination(&self, input: &str, destination: &PathInfo) -> Result<PathInfo, String> {
+        if input == self.initial_text {
+            return Ok(destination.clone());
+        }
+        let info = PathInfo::remote_at(input)
+            .unwrap_or_else(|| PathInfo::try_from(&self.resolve_path(input)))
+            .map_err(|error| format!("Failed to access {input}: {error}"))?;
+        if info.is_directory() {
+            Ok(info)
+        } else {
+            Err(format!("Not a directory: {input}"))
+        }
+    }
+
+    /// The directory on another host that Goto `input` names: an address
+    /// typed out, or relative input in a remote directory. Asked only once no
+    /// local entry answers, so a local name holding a colon still wins.
+    fn remote_address(&self, input: &str) -> Option<Address> {
+        if let Some(address) = Address::parse(input) {
+            return Some(address);
+        }
+        if input.starts_with(['/', '~']) || !self.basedir.starts_with(sftp::SCHEME) {
+            return None;
+        }
+        Address::parse(&self.basedir).map(|base| base.join(input))
+    }
+
     /// Splits the current input into `(dir_prefix, partial)` at the last `/`.
     /// `dir_prefix` includes the trailing `/`; `partial` is the basename being typed.
     fn split_input(input: &str) -> (&str, &str) {
@@ -199,22 +390,63 @@ impl PromptView {
         }
     }
 
-    /// Re-reads the resolved directory and rebuilds the prefix-matching
-    /// (case-sensitive), alphabetically sorted suggestion list.
+    /// Splits a Chown input into `(user_prefix, partial)` at the `:`, so the
+    /// user is completed before it and the group after it. `user_prefix`
+    /// includes the `:`.
+    fn split_owner(input: &str) -> (&str, &str) {
+        match input.find(':') {
+            Some(i) => (&input[..=i], &input[i + 1..]),
+            None => ("", input),
+        }
+    }
+
+    /// `split_input` or `split_owner`, whichever this prompt completes with.
+    fn split_for_completion<'a>(&self, input: &'a str) -> (&'a str, &'a str) {
+        if matches!(self.actions, PromptAction::Chown { .. }) {
+            Self::split_owner(input)
+        } else {
+            Self::split_input(input)
+        }
+    }
+
+    /// Rebuilds the prefix-matching (case-sensitive), alphabetically sorted
+    /// suggestion list: from the resolved directory for Goto, which is re-read
+    /// only when it changes, and from the user or group names for Chown.
     fn refresh_suggestions(&mut self) {
         self.suggestions.clear();
-        if !matches!(self.actions, PromptAction::Goto { .. }) {
+        if !self.completes() {
             return;
         }
         let input = self.text_area.lines().join("");
-        let (dir_prefix, partial) = Self::split_input(&input);
-        // Only suggest once a basename character has been typed; an empty
-        // partial would otherwise dump the entire directory listing.
+        let (prefix, partial) = self.split_for_completion(&input);
+        // Only suggest once a character has been typed; an empty partial would
+        // otherwise dump the entire directory listing or database.
         if partial.is_empty() {
             self.suggestion_index = 0;
             return;
         }
-        let dir = self.resolve_path(dir_prefix);
+        if matches!(self.actions, PromptAction::Chown { .. }) {
+            let names = if prefix.is_empty() {
+                &self.user_names
+            } else {
+                &self.group_names
+            };
+            self.suggestions = names
+                .iter()
+                .filter(|name| name.starts_with(partial))
+                .map(|name| (name.clone(), false))
+                .collect();
+            if self.suggestion_index >= self.suggestions.len() {
+                self.suggestion_index = 0;
+            }
+            return;
+        }
+        // Nothing is listed on another host per keystroke: each would be a
+        // round trip, on the UI thread.
+        if input.starts_with(sftp::SCHEME) || self.basedir.starts_with(sftp::SCHEME) {
+            return;
+        }
+        let dir = self.resolve_path(prefix);
         // Only hit the filesystem when the resolved directory changes; typing
         // within the same directory just re-filters the cached listing.
         if self.cached_dir.as_deref() != Some(dir.as_path()) {
@@ -251,7 +483,7 @@ impl PromptView {
             return None;
         }
         let input = self.text_area.lines().join("");
-        let (_, partial) = Self::split_input(&input);
+        let (_, partial) = self.split_for_completion(&input);
         let (name, is_dir) = &self.suggestions[self.suggestion_index];
         // The suggestions can lag the input; only slice while the typed
         // partial is still a prefix of the suggestion.
@@ -275,8 +507,8 @@ impl PromptView {
             return;
         };
         let input = self.text_area.lines().join("");
-        let (dir_prefix, _) = Self::split_input(&input);
-        let mut new_text = format!("{dir_prefix}{name}");
+        let (prefix, _) = self.split_for_completion(&input);
+        let mut new_text = format!("{prefix}{name}");
         if is_dir {
             new_text.push('/');
         }
@@ -317,6 +549,57 @@ impl PromptView {
     }
 }
 
+/// The delete prompt's account of a tree summary: what goes, then anything
+/// unusual about it. The totals come first because they are what the answer
+/// weighs; a running walk says so, since its counts can only grow.
+fn describe_summary(summary: &TreeSummary) -> String {
+    let mut text = format!(
+        "{}{}, {}, {}",
+        if summary.is_complete {
+            ""
+        } else {
+            "counting: "
+        },
+        pluralize(summary.files, "file", "files"),
+        pluralize(summary.directories, "directory", "directories"),
+        describe_bytes(summary.bytes)
+    );
+    let flags = [
+        (
+            summary.foreign_owned,
+            "owned by another user",
+            "owned by other users",
+        ),
+        (
+            summary.symlinked_directories,
+            "symlinked directory",
+            "symlinked directories",
+        ),
+        (
+            summary.unreadable,
+            "unreadable directory",
+            "unreadable directories",
+        ),
+    ];
+    for (count, singular, plural) in flags {
+        if count > 0 {
+            text.push_str("; ");
+            text.push_str(&pluralize(count, singular, plural));
+        }
+    }
+    text
+}
+
+/// The Size column's units, except that a count too small to carry one says
+/// what it counts: a bare "5" among the entry counts would read as a fourth.
+fn describe_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        pluralize(bytes, "byte", "bytes")
+    } else {
+        humanize_size(bytes)
+    }
+}
+
 /// Replicates tui-textarea's `next_scroll_top` to keep our scroll offset in sync.
 fn next_scroll_top(prev_top: u16, cursor: u16, len: u16) -> u16 {
     if cursor < prev_top {
@@ -352,6 +635,58 @@ mod tests {
         view
     }
 
+    // ── delete prompt ────────────────────────────────────────────────────────
+
+    fn summary(files: u64, directories: u64, bytes: u64, is_complete: bool) -> TreeSummary {
+        TreeSummary {
+            files,
+            directories,
+            bytes,
+            is_complete,
+            ..TreeSummary::default()
+        }
+    }
+
+    #[test]
+    fn the_delete_prompt_asks_with_the_count_until_the_walk_reports() {
+        let view = prompt_with_action(PromptAction::Delete(2));
+
+        assert_eq!(" Delete 2 items? (y/n) ", view.label());
+    }
+
+    #[test_case(summary(3, 1, 2048, false) => " Delete 2 items (counting: 3 files, 1 directory, 2K)? (y/n) " ; "a running walk says so")]
+    #[test_case(summary(1, 0, 5, true) => " Delete 2 items (1 file, 0 directories, 5 bytes)? (y/n) " ; "a finished walk")]
+    #[test_case(TreeSummary { foreign_owned: 2, symlinked_directories: 1, ..summary(4, 2, 0, true) }
+        => " Delete 2 items (4 files, 2 directories, 0 bytes; 2 owned by other users; 1 symlinked directory)? (y/n) " ; "unusual entries are flagged")]
+    fn the_delete_prompt_describes_the_latest_summary(summary: TreeSummary) -> String {
+        let mut view = prompt_with_action(PromptAction::Delete(2));
+
+        view.handle_command(&Command::DeleteSummary(summary));
+
+        view.label()
+    }
+
+    #[test]
+    fn a_summary_does_not_outlive_its_prompt() {
+        let mut view = prompt_with_action(PromptAction::Delete(2));
+        view.handle_command(&Command::DeleteSummary(summary(9, 9, 9, true)));
+
+        // The next delete asks about other entries, so the last one's counts
+        // would describe a delete that is not the one on offer.
+        view.handle_command(&Command::OpenPrompt(PromptAction::Delete(1)));
+        assert_eq!(" Delete 1 item? (y/n) ", view.label());
+    }
+
+    #[test]
+    fn a_summary_is_ignored_by_other_prompts() {
+        let mut view = prompt_with_action(PromptAction::Filter(String::new()));
+
+        assert_eq!(
+            CommandResult::NotHandled,
+            view.handle_command(&Command::DeleteSummary(summary(1, 1, 1, true)))
+        );
+    }
+
     // ── conflict prompt ──────────────────────────────────────────────────────
 
     fn conflict_chord(can_overwrite: bool, key: char, modifiers: KeyModifiers) -> Option<Command> {
@@ -427,6 +762,16 @@ mod tests {
         conflict_chord(true, key, KeyModifiers::SHIFT)
     }
 
+    #[test_case("a" => " New directory " ; "one")]
+    #[test_case("a/b/c" => " New directory " ; "a nested path is still one")]
+    #[test_case("{2024,2025}/{q1,q2}" => " New directory (4 directories) " ; "an expansion")]
+    fn the_create_directory_prompt_counts_the_expansion(input: &str) -> String {
+        let mut view = prompt_with_action(PromptAction::CreateDirectory);
+        view.reset_text(input);
+
+        view.label()
+    }
+
     #[test]
     fn a_conflict_prompt_renders_as_a_confirmation() {
         // No text is collected, so it takes the full-width label path rather
@@ -714,6 +1059,25 @@ mod tests {
         ));
     }
 
+    #[test_case("host.test:notes", None, "sftp://host.test/~/notes" ; "scp form")]
+    #[test_case("sftp://host.test:2222/srv", None, "sftp://host.test:2222/srv" ; "url form")]
+    #[test_case("www", Some("sftp://host.test/srv"), "sftp://host.test/srv/www" ; "relative to a remote directory")]
+    fn goto_submit_naming_another_host_opens_it(input: &str, basedir: Option<&str>, address: &str) {
+        let fixture = GotoFixture::new();
+        let mut view = match basedir {
+            Some(basedir) => prompt_with_action(PromptAction::Goto {
+                directory: basedir.to_string(),
+            }),
+            None => goto_prompt(fixture.dir.path()),
+        };
+        type_str(&mut view, input);
+        let result = view.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(
+            CommandResult::from(Command::OpenRemote(Address::parse(address).unwrap())),
+            result
+        );
+    }
+
     #[test]
     fn current_suggestion_is_none_when_suggestions_are_stale() {
         let fixture = GotoFixture::new();
@@ -740,6 +1104,61 @@ mod tests {
         assert_eq!(view.current_suggestion(), None);
     }
 
+    // ── Chown type-ahead ─────────────────────────────────────────────────────
+
+    /// A chown prompt completing from fixed names rather than the host's
+    /// databases, whose contents the tests cannot know.
+    fn chown_prompt() -> PromptView {
+        let mut view = prompt_with_action(PromptAction::Chown {
+            paths: vec![test_path()],
+            owner: String::new(),
+            recursive: false,
+        });
+        view.user_names = vec!["alice".to_string(), "alex".to_string()];
+        view.group_names = vec!["staff".to_string(), "wheel".to_string()];
+        view
+    }
+
+    #[test]
+    fn chown_completes_a_user_before_the_colon_and_a_group_after_it() {
+        let mut view = chown_prompt();
+
+        type_str(&mut view, "al");
+        view.handle_key(KeyCode::Down, KeyModifiers::NONE);
+        view.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(view.text_area.lines()[0], "alex");
+
+        // The group half draws on the other database: a user name here would
+        // complete to a group nobody has.
+        type_str(&mut view, ":w");
+        view.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(view.text_area.lines()[0], "alex:wheel");
+    }
+
+    #[test]
+    fn chown_enter_accepts_the_suggestion_then_submits() {
+        let mut view = chown_prompt();
+        type_str(&mut view, "alice:st");
+
+        let result = view.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+
+        assert_eq!(
+            Command::Chown {
+                paths: vec![test_path()],
+                owner: "alice:staff".to_string(),
+                recursive: false,
+            },
+            Command::try_from(result).unwrap()
+        );
+    }
+
+    #[test_case("alice" => ("", "alice") ; "no colon: the user")]
+    #[test_case("alice:wh" => ("alice:", "wh") ; "after the colon: the group")]
+    #[test_case(":wh" => (":", "wh") ; "a group alone")]
+    fn split_owner_cases(input: &str) -> (&str, &str) {
+        PromptView::split_owner(input)
+    }
+
     // ── split_input ──────────────────────────────────────────────────────────
 
     #[test_case("",          "",      ""    ; "empty input")]
@@ -783,4 +1202,38 @@ mod tests {
         assert_eq!(view.resolve_path("~"), home);
         assert_eq!(view.resolve_path("~/Documents"), home.join("Documents"));
     }
+
+    // ── CopyTo ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn copy_to_goes_to_the_other_pane_or_the_directory_typed_instead() {
+        let fixture = GotoFixture::new();
+        let other = PathInfo::try_from(fixture.dir.path()).unwrap();
+        let srcs = vec![test_path()];
+        let mut view = prompt_with_action(PromptAction::CopyTo {
+            paths: srcs.clone(),
+            destination: other.clone(),
+            is_move: true,
+        });
+
+        assert_eq!(
+            Some(Command::Move {
+                srcs: srcs.clone(),
+                dest: other,
+            }),
+            Command::try_from(view.submit()).ok()
+        );
+
+        view.reset_text("Apple");
+        match Command::try_from(view.submit()) {
+            Ok(Command::Move { dest, .. }) => assert_eq!(fixture.dir.join("Apple"), dest.path),
+            other => panic!("expected a move into Apple, got {other:?}"),
+        }
+
+        view.reset_text("Banana");
+        assert!(matches!(
+            Command::try_from(view.submit()),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
 }
@@ -17,6 +17,12 @@ impl CommandHandler for PromptView {
     fn handle_command(&mut self, command: &Command) -> CommandResult {
         match command {
             Command::OpenPrompt(kind) => self.open(kind),
+            // Only while the delete prompt is up: a late count for one already
+            // answered has nothing to describe.
+            Command::DeleteSummary(summary) if matches!(self.actions, PromptAction::Delete(_)) => {
+                self.delete_summary = Some(*summary);
+                CommandResult::Handled
+            }
             Command::ClipboardText(text) => {
                 self.text_area.set_yank_text(text);
                 self.text_area.paste();
@@ -74,9 +80,9 @@ impl CommandHandler for PromptView {
         // Rebindable prompt keys (lookup once, reuse after textarea input)
         let action = Config::global().keybindings.prompt_action(code, modifiers);
 
-        // Goto type-ahead: Tab accepts, Enter accepts then submits,
+        // Goto and Chown type-ahead: Tab accepts, Enter accepts then submits,
         // Down/Up cycle through matches
-        if matches!(self.actions, PromptAction::Goto { .. }) {
+        if self.completes() {
             match action {
                 Some(Action::PromptAcceptSuggestion) => {
                     self.accept_suggestion();
@@ -118,7 +124,7 @@ impl CommandHandler for PromptView {
         self.text_area
             .input(Input::from(KeyEvent::new(code, modifiers)));
 
-        if matches!(self.actions, PromptAction::Goto { .. }) {
+        if self.completes() {
             self.refresh_suggestions();
         }
 
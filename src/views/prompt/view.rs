@@ -8,7 +8,6 @@ use ratatui::{
 use super::widget::{confirmation_label_widget, label_widget, suggestion_overlay_text};
 use super::{PromptView, View};
 use crate::app::config::Config;
-use crate::command::PromptAction;
 
 impl View for PromptView {
     fn constraint(&self, _: Rect) -> Constraint {
@@ -37,10 +36,11 @@ impl View for PromptView {
         frame.render_widget(&self.text_area, input_area);
         self.update_scroll_col(input_area.width);
 
-        // Goto type-ahead: paint the muted completion suffix + match counter
-        // as an overlay after the typed text, only while the cursor is at the
-        // end of the input (otherwise it would misalign with an interior cursor).
-        if matches!(self.actions, PromptAction::Goto { .. })
+        // Goto and Chown type-ahead: paint the muted completion suffix + match
+        // counter as an overlay after the typed text, only while the cursor is
+        // at the end of the input (otherwise it would misalign with an
+        // interior cursor).
+        if self.completes()
             && self.cursor_at_end()
             && let Some((suffix, idx, total)) = self.current_suggestion()
         {
@@ -7,8 +7,11 @@ use ratatui::{
 };
 
 use super::{
-    View, alerts::AlertsView, breadcrumbs::BreadcrumbsView, help::HelpView, notices::NoticesView,
-    open_with::OpenWithView, prompt::PromptView, status::StatusView, table::TableView,
+    View, alerts::AlertsView, breadcrumbs::BreadcrumbsView, checksums::ChecksumView,
+    compare::CompareView, diff::DiffView, disk_usage::DiskUsageView, duplicates::DuplicatesView,
+    help::HelpView, notices::NoticesView, open_with::OpenWithView, panes::PanesView,
+    permissions::PermissionsView, prompt::PromptView, status::StatusView, tabs::TabsView,
+    templates::TemplatesView,
 };
 use crate::{
     app::config::{Config, keybindings::Action},
@@ -19,9 +22,11 @@ const MIN_WIDTH: u16 = 14;
 const MIN_HEIGHT: u16 = 5;
 const RESIZE_WINDOW: &str = "Resize window";
 
-/// Forwards broadcast commands to a view covered by an overlay (help or the
-/// "open with" picker) while declining key and mouse dispatch, which must
-/// reach only the overlay.
+/// Forwards broadcast commands to a view covered by an overlay (help, the
+/// "open with" picker, the permission editor, the template picker, the
+/// checksums, the diff viewer, the disk usage view, the duplicate finder or the directory
+/// comparison) while declining key and mouse dispatch, which must reach only
+/// the overlay.
 struct CommandOnly<'a>(&'a mut dyn CommandHandler);
 
 impl CommandHandler for CommandOnly<'_> {
@@ -44,14 +49,24 @@ impl CommandHandler for CommandOnly<'_> {
 pub struct RootView {
     alerts: AlertsView,
     breadcrumbs: BreadcrumbsView,
+    checksums: ChecksumView,
+    compare: CompareView,
+    diff: DiffView,
+    disk_usage: DiskUsageView,
+    duplicates: DuplicatesView,
     help: HelpView,
     is_help_visible: bool,
     mode: InputMode,
     notices: NoticesView,
     open_with: OpenWithView,
+    panes: PanesView,
+    permissions: PermissionsView,
     prompt: PromptView,
     status: StatusView,
-    table: TableView,
+    /// The tab bar, which keeps the tabs out of view. The active tab is
+    /// `panes`.
+    tabs: TabsView,
+    templates: TemplatesView,
 }
 
 impl RootView {
@@ -59,14 +74,22 @@ impl RootView {
         Self {
             alerts: AlertsView::new(),
             breadcrumbs: BreadcrumbsView::default(),
+            checksums: ChecksumView::new(),
+            compare: CompareView::new(),
+            diff: DiffView::new(),
+            disk_usage: DiskUsageView::new(),
+            duplicates: DuplicatesView::new(),
             help: HelpView::new(),
             is_help_visible: false,
             mode: InputMode::default(),
             notices: NoticesView::new(),
             open_with: OpenWithView::new(),
+            panes: PanesView::new(),
+            permissions: PermissionsView::new(),
             prompt: PromptView::default(),
             status: StatusView::default(),
-            table: TableView::default(),
+            tabs: TabsView::new(),
+            templates: TemplatesView::new(),
         }
     }
 
@@ -81,13 +104,36 @@ impl RootView {
         }
         // Read before the mutable borrows below.
         let is_open_with_visible = self.open_with.is_visible();
-        let mut views: Vec<&mut dyn View> = vec![&mut self.alerts, &mut self.breadcrumbs];
-        // The picker takes the table's slot, and has the same constraint, so
-        // what is above and below it stays exactly where it was.
+        let is_permissions_visible = self.permissions.is_visible();
+        let is_templates_visible = self.templates.is_visible();
+        let is_checksums_visible = self.checksums.is_visible();
+        let is_diff_visible = self.diff.is_visible();
+        let is_disk_usage_visible = self.disk_usage.is_visible();
+        let is_duplicates_visible = self.duplicates.is_visible();
+        let is_compare_visible = self.compare.is_visible();
+        let mut views: Vec<&mut dyn View> =
+            vec![&mut self.alerts, &mut self.tabs, &mut self.breadcrumbs];
+        // The pickers and the editor take the table's slot, and have the same
+        // constraint, so what is above and below them stays exactly where it
+        // was.
         if is_open_with_visible {
             views.push(&mut self.open_with);
+        } else if is_permissions_visible {
+            views.push(&mut self.permissions);
+        } else if is_templates_visible {
+            views.push(&mut self.templates);
+        } else if is_checksums_visible {
+            views.push(&mut self.checksums);
+        } else if is_diff_visible {
+            views.push(&mut self.diff);
+        } else if is_disk_usage_visible {
+            views.push(&mut self.disk_usage);
+        } else if is_duplicates_visible {
+            views.push(&mut self.duplicates);
+        } else if is_compare_visible {
+            views.push(&mut self.compare);
         } else {
-            views.push(&mut self.table);
+            views.push(&mut self.panes);
         }
         views.push(&mut self.notices);
         if matches!(self.mode, InputMode::Prompt) {
@@ -113,6 +159,97 @@ impl RootView {
         self.mode = InputMode::Normal;
         was_open.then_some(Command::CancelPrompt)
     }
+
+    /// Opens the overlay `command` asks for, over or in place of the others,
+    /// or closes them all.
+    fn handle_overlay_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::OpenWithPrompt(path) => {
+                // RootView owns the picker, so showing it is a direct call
+                // rather than a broadcast.
+                self.hide_pickers();
+                self.open_with.show(path);
+                CommandResult::Handled
+            }
+            Command::OpenPermissionEditor(paths) => {
+                self.hide_pickers();
+                self.permissions.show(paths);
+                CommandResult::Handled
+            }
+            Command::OpenTemplatePicker => {
+                self.hide_pickers();
+                self.templates.show();
+                CommandResult::Handled
+            }
+            // The checksums cover a scanning view rather than closing it,
+            // since they arrive whenever their task is done rather than when
+            // asked for.
+            Command::Checksummed(report) => {
+                self.hide_pickers();
+                self.checksums.show(report);
+                CommandResult::Handled
+            }
+            Command::Diffed(diff) => {
+                self.hide_pickers();
+                self.diff.show(diff);
+                CommandResult::Handled
+            }
+            // The scanning views replace each other, and the one closed stops
+            // its scan.
+            Command::OpenDiskUsage(directory) => {
+                let mut commands: Vec<_> = self
+                    .duplicates
+                    .hide()
+                    .into_iter()
+                    .chain(self.compare.hide())
+                    .collect();
+                commands.extend(self.disk_usage.show(directory).into_commands());
+                commands.into()
+            }
+            Command::OpenDuplicates(directory) => {
+                let mut commands: Vec<_> = self
+                    .disk_usage
+                    .hide()
+                    .into_iter()
+                    .chain(self.compare.hide())
+                    .collect();
+                commands.extend(self.duplicates.show(directory).into_commands());
+                commands.into()
+            }
+            Command::OpenCompare { left, right } => {
+                let mut commands: Vec<_> = self
+                    .disk_usage
+                    .hide()
+                    .into_iter()
+                    .chain(self.duplicates.hide())
+                    .collect();
+                commands.extend(self.compare.show(left, right).into_commands());
+                commands.into()
+            }
+            Command::ResetView => {
+                self.is_help_visible = false;
+                self.hide_pickers();
+                self.checksums.hide();
+                self.diff.hide();
+                let commands: Vec<_> = self
+                    .disk_usage
+                    .hide()
+                    .into_iter()
+                    .chain(self.duplicates.hide())
+                    .chain(self.compare.hide())
+                    .chain(self.close_prompt())
+                    .collect();
+                commands.into()
+            }
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn hide_pickers(&mut self) {
+        self.open_with.hide();
+        self.permissions.hide();
+        self.templates.hide();
+    }
 }
 
 impl CommandHandler for RootView {
@@ -120,8 +257,13 @@ impl CommandHandler for RootView {
         match command {
             Command::AddBookmark { .. }
             | Command::Chmod { .. }
+            | Command::Chown { .. }
+            | Command::Touch { .. }
+            | Command::Compress { .. }
             | Command::ConfirmDelete
             | Command::CreateDirectory(_)
+            | Command::CreateFile(_)
+            | Command::CreateFromTemplate { .. }
             | Command::Open(_)
             | Command::Rename { .. }
             | Command::FilterChanged(_)
@@ -130,8 +272,10 @@ impl CommandHandler for RootView {
                 .map_or(CommandResult::NotHandled, Into::into),
             // The conflict prompt's own answer. The paste is waiting on it and
             // may reopen the prompt for the next collision, so this must not be
-            // announced as the prompt being abandoned.
-            Command::ResolveConflict(_) => {
+            // announced as the prompt being abandoned. The same goes for the
+            // copy to the other pane's directory, whose paste is about to
+            // start.
+            Command::ResolveConflict(_) | Command::Copy { .. } | Command::Move { .. } => {
                 self.mode = InputMode::Normal;
                 CommandResult::NotHandled
             }
@@ -143,19 +287,12 @@ impl CommandHandler for RootView {
                 self.mode = InputMode::Prompt;
                 CommandResult::Handled
             }
-            Command::OpenWithPrompt(path) => {
-                // RootView owns the picker, so showing it is a direct call
-                // rather than a broadcast.
-                self.open_with.show(path);
-                CommandResult::Handled
-            }
-            Command::ResetView => {
-                self.is_help_visible = false;
-                self.open_with.hide();
-                self.close_prompt()
-                    .map_or(CommandResult::Handled, Into::into)
-            }
-            _ => CommandResult::NotHandled,
+            // RootView has the active tab, which the tab bar swaps for the one
+            // brought in.
+            Command::NewTab => self.tabs.open(&mut self.panes),
+            Command::CloseTab => self.tabs.close(&mut self.panes),
+            Command::SwitchTab(index) => self.tabs.switch_to(*index, &mut self.panes),
+            _ => self.handle_overlay_command(command),
         }
     }
 
@@ -176,7 +313,16 @@ impl CommandHandler for RootView {
     }
 
     fn visit_command_handlers(&mut self, visitor: &mut dyn FnMut(&mut dyn CommandHandler)) {
-        if !self.is_help_visible && !self.open_with.is_visible() {
+        if !self.is_help_visible
+            && !self.open_with.is_visible()
+            && !self.permissions.is_visible()
+            && !self.templates.is_visible()
+            && !self.checksums.is_visible()
+            && !self.diff.is_visible()
+            && !self.disk_usage.is_visible()
+            && !self.duplicates.is_visible()
+            && !self.compare.is_visible()
+        {
             for view in self.views() {
                 visitor(view);
             }
@@ -185,23 +331,64 @@ impl CommandHandler for RootView {
         // An overlay is the only key and mouse handler while it is shown, but
         // async commands (task progress, watcher refreshes, streamed listings)
         // keep arriving, so every view it covers must still receive them.
+        let is_picker_visible = self.is_help_visible
+            || self.open_with.is_visible()
+            || self.permissions.is_visible()
+            || self.templates.is_visible();
         let overlay: &mut dyn CommandHandler = if self.is_help_visible {
             &mut self.help
-        } else {
+        } else if self.open_with.is_visible() {
             &mut self.open_with
+        } else if self.permissions.is_visible() {
+            &mut self.permissions
+        } else if self.templates.is_visible() {
+            &mut self.templates
+        } else if self.checksums.is_visible() {
+            &mut self.checksums
+        } else if self.diff.is_visible() {
+            &mut self.diff
+        } else if self.disk_usage.is_visible() {
+            &mut self.disk_usage
+        } else if self.duplicates.is_visible() {
+            &mut self.duplicates
+        } else {
+            &mut self.compare
         };
         visitor(overlay);
+        // The checksums open over a scanning view without closing it, so
+        // that view's scan and task results must still reach it.
+        if self.checksums.is_visible() && !is_picker_visible {
+            let scanning: [&mut dyn CommandHandler; 3] = [
+                &mut self.disk_usage,
+                &mut self.duplicates,
+                &mut self.compare,
+            ];
+            for view in scanning {
+                visitor(&mut CommandOnly(view));
+            }
+        }
         let covered: [&mut dyn CommandHandler; 6] = [
             &mut self.alerts,
+            &mut self.tabs,
             &mut self.breadcrumbs,
             &mut self.notices,
-            &mut self.prompt,
             &mut self.status,
-            &mut self.table,
+            &mut self.panes,
         ];
         for view in covered {
             visitor(&mut CommandOnly(view));
         }
+        // The disk usage view and the duplicate finder are the overlays that
+        // open a prompt, to confirm a delete, so the prompt keeps its keys
+        // under them.
+        if (self.disk_usage.is_visible() || self.duplicates.is_visible())
+            && !self.is_help_visible
+            && !self.checksums.is_visible()
+        {
+            visitor(&mut self.prompt);
+        } else {
+            visitor(&mut CommandOnly(&mut self.prompt));
+        }
     }
 }
 
@@ -449,6 +636,148 @@ mod tests {
         assert_eq!(without_picker, root.views().len());
     }
 
+    fn showing_permissions() -> RootView {
+        let mut root = view();
+        root.handle_command(&Command::OpenPermissionEditor(vec![
+            PathInfo::try_from("/tmp").unwrap(),
+        ]));
+        assert!(root.permissions.is_visible());
+        root
+    }
+
+    #[test]
+    fn keys_reach_only_the_permission_editor_while_it_is_visible() {
+        let mut root = showing_permissions();
+
+        // The delete key: the editor declines it, where the table beneath
+        // would open the delete prompt.
+        assert_eq!(
+            vec![CommandResult::NotHandled],
+            press(
+                &mut root,
+                InputMode::Normal,
+                KeyCode::Char('d'),
+                KeyModifiers::NONE
+            )
+        );
+    }
+
+    #[test]
+    fn the_permission_editor_and_the_picker_replace_each_other() {
+        let mut root = showing_permissions();
+
+        // Only one overlay can hold the table's slot and take the keys.
+        root.handle_command(&Command::OpenWithPrompt(
+            PathInfo::try_from("/tmp").unwrap(),
+        ));
+
+        assert!(!root.permissions.is_visible());
+        assert!(root.open_with.is_visible());
+    }
+
+    #[test]
+    fn reset_view_closes_the_permission_editor() {
+        let mut root = showing_permissions();
+
+        root.handle_command(&Command::ResetView);
+
+        assert!(!root.permissions.is_visible());
+    }
+
+    #[test]
+    fn the_template_picker_replaces_the_other_overlays() {
+        let mut root = showing_permissions();
+
+        root.handle_command(&Command::OpenTemplatePicker);
+
+        assert!(!root.permissions.is_visible());
+        assert!(root.templates.is_visible());
+        root.handle_command(&Command::ResetView);
+        assert!(!root.templates.is_visible());
+    }
+
+    fn showing_disk_usage(fx: &crate::test_support::TempDir) -> RootView {
+        let mut root = view();
+        let result = root.handle_command(&Command::OpenDiskUsage(
+            PathInfo::try_from(fx.path()).unwrap(),
+        ));
+        assert!(
+            result
+                .into_commands()
+                .contains(&Command::ScanDiskUsage(fx.path().to_path_buf()))
+        );
+        root
+    }
+
+    #[test]
+    fn the_prompt_keeps_its_keys_over_the_disk_usage_view() {
+        let fx = crate::test_support::TempDir::new("root_disk_usage");
+        let mut root = showing_disk_usage(&fx);
+        // Deleting from the view opens the delete prompt over it.
+        root.mode = InputMode::Prompt;
+
+        assert_eq!(
+            vec![CommandResult::from(Command::CancelPrompt)],
+            press(
+                &mut root,
+                InputMode::Prompt,
+                KeyCode::Esc,
+                KeyModifiers::NONE
+            )
+        );
+    }
+
+    #[test]
+    fn reset_view_closes_the_disk_usage_view_and_stops_its_scan() {
+        let fx = crate::test_support::TempDir::new("root_disk_usage");
+        let mut root = showing_disk_usage(&fx);
+
+        assert_eq!(
+            CommandResult::from(Command::CancelDiskUsage),
+            root.handle_command(&Command::ResetView)
+        );
+        assert!(!root.disk_usage.is_visible());
+    }
+
+    #[test]
+    fn the_duplicate_finder_replaces_the_disk_usage_view_and_stops_its_scan() {
+        let fx = crate::test_support::TempDir::new("root_disk_usage");
+        let mut root = showing_disk_usage(&fx);
+
+        let commands = root
+            .handle_command(&Command::OpenDuplicates(
+                PathInfo::try_from(fx.path()).unwrap(),
+            ))
+            .into_commands();
+
+        assert!(commands.contains(&Command::CancelDiskUsage));
+        assert!(commands.contains(&Command::FindDuplicates(fx.path().to_path_buf())));
+        assert!(!root.disk_usage.is_visible());
+        assert!(root.duplicates.is_visible());
+    }
+
+    #[test]
+    fn a_comparison_replaces_the_disk_usage_view_and_reset_view_closes_it() {
+        let fx = crate::test_support::TempDir::new("root_disk_usage");
+        let mut root = showing_disk_usage(&fx);
+        let directory = PathInfo::try_from(fx.path()).unwrap();
+
+        let commands = root
+            .handle_command(&Command::OpenCompare {
+                left: directory.clone(),
+                right: directory,
+            })
+            .into_commands();
+
+        assert!(commands.contains(&Command::CancelDiskUsage));
+        assert!(!root.disk_usage.is_visible());
+        assert!(root.compare.is_visible());
+
+        let commands = root.handle_command(&Command::ResetView).into_commands();
+        assert!(commands.contains(&Command::CancelCompare));
+        assert!(!root.compare.is_visible());
+    }
+
     #[test]
     fn reset_view_closes_the_open_with_picker() {
         let mut root = showing_open_with();
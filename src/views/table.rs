@@ -14,13 +14,16 @@ mod style;
 mod view;
 mod widget;
 
+use std::path::PathBuf;
+
 use ratatui::{layout::Rect, widgets::TableState};
 
+pub(super) use self::style::name_style;
 use self::{
     columns::Columns, content::DirectoryContent, double_click::DoubleClick, marks::Marks,
     navigation::PendingLoad, row_map::LineItemMap,
 };
-use super::ScrollbarView;
+use super::{ListingMode, ScrollbarView};
 use crate::{app::clipboard::ClipboardEntry, file_system::path_info::PathInfo};
 
 #[derive(Default)]
@@ -29,6 +32,16 @@ pub(super) struct TableView {
     content: DirectoryContent,
     marks: Marks,
     pending_delete: Vec<PathInfo>,
+    /// The other pane's directory in the dual-pane layout, which copying and
+    /// moving to the other pane default to.
+    other_pane: Option<PathInfo>,
+    /// Set on the pane out of focus, which shows no cursor, so the one that
+    /// takes the keys is the one with a cursor.
+    is_out_of_focus: bool,
+    /// The directory this pane last navigated away from. FileSystem keeps
+    /// only the focused pane's, so it is handed back on switching to this
+    /// pane, or to its tab, for going back to.
+    previous_directory: Option<PathInfo>,
 
     table_area: Rect,
     table_state: TableState,
@@ -47,6 +60,10 @@ pub(super) struct TableView {
     /// Selection state captured at the start of a streamed load, applied once it
     /// completes (see `begin_directory`/`finish_directory`).
     pending_load: PendingLoad,
+    /// An entry just created, to be selected once a listing holds it. Kept
+    /// across loads until one does: the refresh after a create may be folded
+    /// into a load already streaming, which can finish without it.
+    reveal: Option<PathBuf>,
 
     columns: Columns,
     double_click: DoubleClick,
@@ -60,6 +77,50 @@ pub(super) struct TableView {
     scrollbar_view: ScrollbarView,
 }
 
+/// What `PanesView` reads and sets on the panes it lays out.
+impl TableView {
+    pub(super) fn directory(&self) -> Option<&PathInfo> {
+        self.content.directory()
+    }
+
+    pub(super) fn filter(&self) -> &str {
+        self.content.filter()
+    }
+
+    pub(super) fn mode(&self) -> ListingMode {
+        self.content.mode()
+    }
+
+    /// What the search being shown was searched for, while one is.
+    pub(super) fn search_query(&self) -> Option<&str> {
+        self.content.search_query()
+    }
+
+    pub(super) fn mark_count(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub(super) fn previous_directory(&self) -> Option<&PathInfo> {
+        self.previous_directory.as_ref()
+    }
+
+    pub(super) fn selected(&self) -> Option<&PathInfo> {
+        self.selected_path()
+    }
+
+    pub(super) fn shows_hidden(&self) -> bool {
+        self.content.show_hidden()
+    }
+
+    pub(super) fn set_focused(&mut self, is_focused: bool) {
+        self.is_out_of_focus = !is_focused;
+    }
+
+    pub(super) fn set_other_pane(&mut self, directory: Option<PathInfo>) {
+        self.other_pane = directory;
+    }
+}
+
 /// A listing of `a`, `b` and `c`, with `a` and `b` marked and the cursor left
 /// on `c`, so an action reading the marks and one reading the cursor cannot
 /// produce the same answer. Shared by the sibling modules whose tests are about
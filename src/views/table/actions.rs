@@ -1,10 +1,106 @@
 use super::TableView;
 use crate::{
-    command::{Command, PromptAction, result::CommandResult},
-    file_system::path_info::PathInfo,
+    app::{clipboard::ClipboardEntry, config::keybindings::Action},
+    command::{Command, PromptAction, TouchTime, result::CommandResult},
+    file_system::{
+        checksums::ChecksumAlgorithm,
+        path_info::{PathInfo, compact},
+    },
 };
 
 impl TableView {
+    /// Refuses an action that would change the archive being browsed, or hand
+    /// one of its entries to something that expects it on disk, before any
+    /// prompt for it opens. Copying out is what is left, and the paste that
+    /// follows extracts.
+    pub(super) fn refuse_in_archive(&self, action: Action) -> Option<CommandResult> {
+        let archive = self.content.directory()?.archive()?;
+        match action {
+            Action::AddBookmark
+            | Action::Chmod
+            | Action::ChmodRecursive
+            | Action::Chown
+            | Action::ChownRecursive
+            | Action::Checksum
+            | Action::ChecksumBlake3
+            | Action::Compress
+            | Action::CreateDirectory
+            | Action::CreateFile
+            | Action::Cut
+            | Action::Delete
+            | Action::DiskUsage
+            | Action::EditPermissions
+            | Action::Extract
+            | Action::ExtractHere
+            | Action::CompareDirectories
+            | Action::Diff
+            | Action::FindDuplicates
+            | Action::MoveToOtherPane
+            | Action::NewFromTemplate
+            | Action::OpenCurrentDirectory
+            | Action::OpenNewWindow
+            | Action::OpenWith
+            | Action::Paste
+            | Action::Rename
+            | Action::Search
+            | Action::Touch
+            | Action::TouchFromClipboard
+            | Action::TouchRecursive
+            | Action::VerifyChecksums => Some(
+                Command::AlertWarn(format!(
+                    "{} is read-only: copy entries out of it instead",
+                    compact(archive)
+                ))
+                .into(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Refuses, in a directory on another host, an action that reaches past
+    /// what the session offers: reading, copying, creating and renaming.
+    /// Everything else would need a shell on the host, or hands an entry to
+    /// an application that expects it on this machine's disk.
+    pub(super) fn refuse_on_remote(&self, action: Action) -> Option<CommandResult> {
+        let remote = self.content.directory()?.remote()?;
+        match action {
+            Action::AddBookmark
+            | Action::Chmod
+            | Action::ChmodRecursive
+            | Action::Chown
+            | Action::ChownRecursive
+            | Action::Checksum
+            | Action::ChecksumBlake3
+            | Action::Compress
+            | Action::Cut
+            | Action::Delete
+            | Action::DiskUsage
+            | Action::EditPermissions
+            | Action::Extract
+            | Action::ExtractHere
+            | Action::CompareDirectories
+            | Action::Diff
+            | Action::FindDuplicates
+            | Action::MoveToOtherPane
+            | Action::NewFromTemplate
+            | Action::OpenCurrentDirectory
+            | Action::OpenNewWindow
+            | Action::OpenWith
+            | Action::Search
+            | Action::Touch
+            | Action::TouchFromClipboard
+            | Action::TouchRecursive
+            | Action::VerifyChecksums => Some(
+                Command::AlertWarn(format!(
+                    "Not available on {}: only reading, copying, creating and renaming are",
+                    remote.authority()
+                ))
+                .into(),
+            ),
+            _ => None,
+        }
+    }
+
     pub(super) fn delete(&mut self) -> CommandResult {
         let paths = if self.has_marks() {
             self.marked_paths()
@@ -15,8 +111,9 @@ impl TableView {
             }
         };
         let count = paths.len();
+        let summarize = Command::SummarizeDelete(paths.clone());
         self.pending_delete = paths;
-        Command::OpenPrompt(PromptAction::Delete(count)).into()
+        vec![Command::OpenPrompt(PromptAction::Delete(count)), summarize].into()
     }
 
     pub(super) fn navigate_to_home_directory() -> CommandResult {
@@ -37,16 +134,19 @@ impl TableView {
         let directory = self
             .content
             .directory()
-            .map(|d| d.path.to_string_lossy().into_owned())
+            .map(PathInfo::location)
             .unwrap_or_default();
         Command::OpenPrompt(PromptAction::Goto { directory }).into()
     }
 
-    pub(super) fn open_chmod_prompt(&self) -> CommandResult {
+    /// A recursive chmod starts blank: the selected entry's own mode says
+    /// nothing about what the tree below it should have.
+    pub(super) fn open_chmod_prompt(&self, recursive: bool) -> CommandResult {
         let (paths, initial_mode) = if self.has_marks() {
             (self.marked_paths(), String::new())
         } else {
             match self.selected_path() {
+                Some(path) if recursive => (vec![path.clone()], String::new()),
                 Some(path) => {
                     let mode = format!("{:o}", path.mode() & 0o7777);
                     (vec![path.clone()], mode)
@@ -57,14 +157,260 @@ impl TableView {
         Command::OpenPrompt(PromptAction::Chmod {
             paths,
             mode: initial_mode,
+            recursive,
+        })
+        .into()
+    }
+
+    /// Pre-filled with a single entry's `user:group`. Unlike a recursive
+    /// chmod, a recursive chown keeps it: handing a tree to whoever owns its
+    /// top is the usual reason to run one.
+    pub(super) fn open_chown_prompt(&self, recursive: bool) -> CommandResult {
+        let (paths, owner) = if self.has_marks() {
+            (self.marked_paths(), String::new())
+        } else {
+            match self.selected_path() {
+                Some(path) => {
+                    let owner = match (path.owner(), path.group()) {
+                        (Some(user), Some(group)) => format!("{user}:{group}"),
+                        _ => String::new(),
+                    };
+                    (vec![path.clone()], owner)
+                }
+                None => return Command::AlertWarn("No file(s) selected".into()).into(),
+            }
+        };
+        Command::OpenPrompt(PromptAction::Chown {
+            paths,
+            owner,
+            recursive,
+        })
+        .into()
+    }
+
+    /// Starts at `now`, the usual reason to touch anything.
+    pub(super) fn open_touch_prompt(&self) -> CommandResult {
+        self.open_touch_prompt_with(false)
+    }
+
+    pub(super) fn open_touch_recursive_prompt(&self) -> CommandResult {
+        self.open_touch_prompt_with(true)
+    }
+
+    fn open_touch_prompt_with(&self, recursive: bool) -> CommandResult {
+        let paths = if self.has_marks() {
+            self.marked_paths()
+        } else {
+            match self.selected_path() {
+                Some(path) => vec![path.clone()],
+                None => return Command::AlertWarn("No file(s) selected".into()).into(),
+            }
+        };
+        Command::OpenPrompt(PromptAction::Touch { paths, recursive }).into()
+    }
+
+    /// Gives the marked entries, or the selected one, the times of the single
+    /// entry on the clipboard, as `touch -r` does. Immediate: there is nothing
+    /// to type.
+    pub(super) fn touch_like_clipboard(&self) -> CommandResult {
+        let reference = match self.clipboard_entry.as_ref().map(ClipboardEntry::paths) {
+            Some([reference]) => reference.clone(),
+            _ => {
+                return Command::AlertWarn("Copy or cut one entry to use its times".into()).into();
+            }
+        };
+        let paths = if self.has_marks() {
+            self.marked_paths()
+        } else {
+            match self.selected_path() {
+                Some(path) => vec![path.clone()],
+                None => return Command::AlertWarn("No file(s) selected".into()).into(),
+            }
+        };
+        Command::Touch {
+            paths,
+            time: TouchTime::Like(reference),
+            recursive: false,
+        }
+        .into()
+    }
+
+    /// Pre-filled with a `.tar.gz` named after the one entry being packed, or
+    /// after the directory the entries are in, the names `tar` users reach
+    /// for.
+    pub(super) fn open_compress_prompt(&self) -> CommandResult {
+        let (paths, stem) = if self.has_marks() {
+            let stem = self
+                .content
+                .directory()
+                .and_then(|directory| directory.path.file_name())
+                .map_or_else(
+                    || "archive".to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+            (self.marked_paths(), stem)
+        } else {
+            match self.selected_path() {
+                Some(path) => (vec![path.clone()], path.name().into_owned()),
+                None => return Command::AlertWarn("No file(s) selected".into()).into(),
+            }
+        };
+        Command::OpenPrompt(PromptAction::Compress {
+            paths,
+            name: format!("{stem}.tar.gz"),
         })
         .into()
     }
 
+    /// Extracts the selected archive. Marks do not apply: every archive asks
+    /// about its own taken names, and one at a time keeps those prompts from
+    /// interleaving.
+    pub(super) fn extract(&self, here: bool) -> CommandResult {
+        match self.selected_path() {
+            Some(path) => Command::Extract {
+                archive: path.clone(),
+                here,
+            }
+            .into(),
+            None => Command::AlertWarn("No file selected".into()).into(),
+        }
+    }
+
+    pub(super) fn open_permission_editor(&self) -> CommandResult {
+        let paths = if self.has_marks() {
+            self.marked_paths()
+        } else {
+            match self.selected_path() {
+                Some(path) => vec![path.clone()],
+                None => return Command::AlertWarn("No file(s) selected".into()).into(),
+            }
+        };
+        Command::OpenPermissionEditor(paths).into()
+    }
+
     pub(super) fn open_create_directory_prompt() -> CommandResult {
         Command::OpenPrompt(PromptAction::CreateDirectory).into()
     }
 
+    pub(super) fn open_create_file_prompt() -> CommandResult {
+        Command::OpenPrompt(PromptAction::CreateFile).into()
+    }
+
+    pub(super) fn open_template_picker() -> CommandResult {
+        Command::OpenTemplatePicker.into()
+    }
+
+    /// Scans the directory being listed, not the selection: what is filling a
+    /// disk is rarely the entry the cursor happens to be on.
+    pub(super) fn open_disk_usage(&self) -> CommandResult {
+        match self.content.directory() {
+            Some(directory) => Command::OpenDiskUsage(directory.clone()).into(),
+            None => CommandResult::Handled,
+        }
+    }
+
+    /// Looks through the directory being listed, for the same reason.
+    pub(super) fn open_duplicates(&self) -> CommandResult {
+        match self.content.directory() {
+            Some(directory) => Command::OpenDuplicates(directory.clone()).into(),
+            None => CommandResult::Handled,
+        }
+    }
+
+    /// Compares the two marked directories, in listing order, or else the
+    /// directory being listed with the one directory on the clipboard.
+    pub(super) fn open_compare(&self) -> CommandResult {
+        let (left, right) = if self.has_marks() {
+            match self.marked_paths().as_slice() {
+                [left, right] if left.is_directory() && right.is_directory() => {
+                    (left.clone(), right.clone())
+                }
+                _ => return Command::AlertWarn("Mark two directories to compare".into()).into(),
+            }
+        } else {
+            let Some(directory) = self.content.directory() else {
+                return CommandResult::Handled;
+            };
+            match self.clipboard_entry.as_ref().map(ClipboardEntry::paths) {
+                Some([other]) if other.is_directory() && other.path != directory.path => {
+                    (directory.clone(), other.clone())
+                }
+                _ => {
+                    return Command::AlertWarn(
+                        "Copy a directory to compare with this one, or mark two".into(),
+                    )
+                    .into();
+                }
+            }
+        };
+        // Both sides are walked on this machine's disk, which the directory
+        // being listed is checked for before this runs.
+        if let Some(other) = [&left, &right]
+            .into_iter()
+            .find(|path| path.archive().is_some() || path.remote().is_some())
+        {
+            return Command::AlertWarn(format!(
+                "Cannot compare {}: only local directories can be compared",
+                other.location()
+            ))
+            .into();
+        }
+        Command::OpenCompare { left, right }.into()
+    }
+
+    /// Checksums the marked files, or the selected one. Anything else among
+    /// the marks is passed over: a directory has no checksum of its own, and
+    /// reading a pipe or a device would never end.
+    pub(super) fn checksum(&self, algorithm: ChecksumAlgorithm) -> CommandResult {
+        let Some(directory) = self.content.directory() else {
+            return CommandResult::Handled;
+        };
+        let candidates = if self.has_marks() {
+            self.marked_paths()
+        } else {
+            self.selected_path().cloned().into_iter().collect()
+        };
+        let paths: Vec<_> = candidates
+            .into_iter()
+            .filter(|path| path.is_file() || (path.is_symlink() && !path.is_symlink_broken()))
+            .map(|path| path.path)
+            .collect();
+        if paths.is_empty() {
+            return Command::AlertWarn("Mark or select a file to checksum".into()).into();
+        }
+        Command::ComputeChecksums {
+            paths,
+            directory: directory.path.clone(),
+            algorithm,
+        }
+        .into()
+    }
+
+    /// Checks the files the selected checksum file lists against it. Marks
+    /// do not apply, as for an extract.
+    pub(super) fn verify_checksums(&self) -> CommandResult {
+        match self.selected_path() {
+            Some(path) if !path.is_directory() => {
+                Command::VerifyChecksums(path.path.clone()).into()
+            }
+            _ => Command::AlertWarn("Select a checksum file to verify".into()).into(),
+        }
+    }
+
+    /// Diffs the two marked files, the one listed first being the old side.
+    /// The selection does not count as either, as for a comparison, since
+    /// the two are told apart only by being marked.
+    pub(super) fn diff(&self) -> CommandResult {
+        match self.marked_paths().as_slice() {
+            [left, right] if !left.is_directory() && !right.is_directory() => Command::DiffFiles {
+                left: left.path.clone(),
+                right: right.path.clone(),
+            }
+            .into(),
+            _ => Command::AlertWarn("Mark two files to diff".into()).into(),
+        }
+    }
+
     pub(super) fn open_filter_prompt(&self) -> CommandResult {
         Command::OpenPrompt(PromptAction::Filter(self.content.filter().to_string())).into()
     }
@@ -131,8 +477,11 @@ impl TableView {
 /// pinned per action rather than left to the reader.
 #[cfg(test)]
 mod tests {
+    use std::{path::Path, sync::Arc};
+
     use super::super::{display_names as names, marked_table, navigation::Reselect};
     use super::*;
+    use crate::file_system::{backend::Memory, sftp};
 
     fn prompt(result: CommandResult) -> PromptAction {
         match Command::try_from(result) {
@@ -141,6 +490,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn inside_an_archive_only_what_reads_it_is_allowed() {
+        let (dir, mut table) = marked_table();
+        let archive = PathInfo::try_from(dir.join("a").as_path()).unwrap();
+        table.begin_directory(PathInfo::archive_root(&archive), Reselect::Top);
+
+        assert!(table.refuse_in_archive(Action::Paste).is_some());
+        assert!(table.refuse_in_archive(Action::Rename).is_some());
+        // Copying is how entries come out of it.
+        assert!(table.refuse_in_archive(Action::Copy).is_none());
+        assert!(table.refuse_in_archive(Action::Open).is_none());
+    }
+
+    #[test]
+    fn on_another_host_only_what_the_session_offers_is_allowed() {
+        let memory = Arc::new(Memory::default());
+        memory.file("/srv/a.txt", b"a");
+        let remote = sftp::stand_in("table.test", memory);
+        let (_dir, mut table) = marked_table();
+        table.begin_directory(
+            PathInfo::read_remote(&remote, Path::new("/srv")).unwrap(),
+            Reselect::Top,
+        );
+
+        assert!(table.refuse_on_remote(Action::Delete).is_some());
+        assert!(table.refuse_on_remote(Action::OpenWith).is_some());
+        assert!(table.refuse_on_remote(Action::Paste).is_none());
+        assert!(table.refuse_on_remote(Action::Rename).is_none());
+    }
+
     #[test]
     fn open_with_offers_the_selection_and_ignores_the_marks() {
         let (_dir, mut table) = marked_table();
@@ -168,15 +547,109 @@ mod tests {
         assert_eq!("c", name);
     }
 
+    #[test]
+    fn a_compress_of_marks_is_named_after_their_directory() {
+        let (dir, mut table) = marked_table();
+
+        let PromptAction::Compress { paths, name } = prompt(table.open_compress_prompt()) else {
+            panic!("expected a Compress prompt");
+        };
+        assert_eq!(vec!["a", "b"], names(&paths));
+        let directory = dir.path().file_name().unwrap().to_string_lossy();
+        assert_eq!(format!("{directory}.tar.gz"), name);
+
+        // Without marks, the one entry names it.
+        table.clear_marks_notifying();
+        let PromptAction::Compress { name, .. } = prompt(table.open_compress_prompt()) else {
+            panic!("expected a Compress prompt");
+        };
+        assert_eq!("c.tar.gz", name);
+    }
+
+    #[test]
+    fn a_comparison_is_of_two_marked_directories_or_of_this_one_and_the_clipboards() {
+        let (dir, mut table) = marked_table();
+        // Marked, but files.
+        assert!(matches!(
+            Command::try_from(table.open_compare()),
+            Ok(Command::AlertWarn(_))
+        ));
+
+        table.clear_marks();
+        std::fs::create_dir(dir.join("other")).unwrap();
+        let other = PathInfo::try_from(dir.join("other").as_path()).unwrap();
+        table.clipboard_entry = Some(ClipboardEntry::Copy(vec![other.clone()]));
+        let Ok(Command::OpenCompare { left, right }) = Command::try_from(table.open_compare())
+        else {
+            panic!("expected an OpenCompare");
+        };
+        assert_eq!(dir.path(), left.path);
+        assert_eq!(other, right);
+    }
+
+    #[test]
+    fn a_diff_is_of_exactly_two_marked_files() {
+        let (dir, mut table) = marked_table();
+
+        let Ok(Command::DiffFiles { left, right }) = Command::try_from(table.diff()) else {
+            panic!("expected a DiffFiles");
+        };
+        assert_eq!((dir.join("a"), dir.join("b")), (left, right));
+
+        table.clear_marks();
+        assert!(matches!(
+            Command::try_from(table.diff()),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
+
+    #[test]
+    fn touch_like_clipboard_needs_exactly_one_reference() {
+        let (dir, mut table) = marked_table();
+        let reference = PathInfo::try_from(dir.join("c").as_path()).unwrap();
+
+        // Several entries on the clipboard have several sets of times, and
+        // nothing says whose to take.
+        table.clipboard_entry = Some(ClipboardEntry::Copy(vec![reference.clone(); 2]));
+        assert!(matches!(
+            Command::try_from(table.touch_like_clipboard()),
+            Ok(Command::AlertWarn(_))
+        ));
+
+        table.clipboard_entry = Some(ClipboardEntry::Move(vec![reference.clone()]));
+        let Ok(Command::Touch {
+            paths,
+            time,
+            recursive: false,
+        }) = Command::try_from(table.touch_like_clipboard())
+        else {
+            panic!("expected a Touch");
+        };
+        assert_eq!(vec!["a", "b"], names(&paths));
+        assert_eq!(TouchTime::Like(reference), time);
+    }
+
+    /// The delete prompt, and the paths its summary is counted for.
+    fn delete_prompt(result: CommandResult) -> (PromptAction, Vec<PathInfo>) {
+        match result.into_commands().as_slice() {
+            [Command::OpenPrompt(action), Command::SummarizeDelete(paths)] => {
+                (action.clone(), paths.clone())
+            }
+            other => panic!("expected a delete prompt and its summary, got {other:?}"),
+        }
+    }
+
     #[test]
     fn delete_takes_the_marks_when_there_are_any() {
         let (_dir, mut table) = marked_table();
 
-        let action = prompt(table.delete());
+        let (action, summarized) = delete_prompt(table.delete());
 
-        // The count in the prompt and the paths held for the confirmation have
-        // to agree, or the message names a number the delete does not act on.
+        // The count in the prompt, the paths summarized for it and the paths
+        // held for the confirmation have to agree, or the message describes a
+        // delete that is not the one performed.
         assert_eq!(PromptAction::Delete(2), action);
+        assert_eq!(vec!["a", "b"], names(&summarized));
         assert_eq!(vec!["a", "b"], names(&table.pending_delete));
     }
 
@@ -185,9 +658,10 @@ mod tests {
         let (_dir, mut table) = marked_table();
         table.clear_marks();
 
-        let action = prompt(table.delete());
+        let (action, summarized) = delete_prompt(table.delete());
 
         assert_eq!(PromptAction::Delete(1), action);
+        assert_eq!(vec!["c"], names(&summarized));
         assert_eq!(vec!["c"], names(&table.pending_delete));
     }
 
@@ -207,11 +681,43 @@ mod tests {
             .append(&[PathInfo::try_from(dir.join("c").as_path()).unwrap()]);
         table.finish_directory();
 
-        let PromptAction::Chmod { paths, mode } = prompt(table.open_chmod_prompt()) else {
+        let PromptAction::Chmod { paths, mode, .. } = prompt(table.open_chmod_prompt(false)) else {
             panic!("expected a Chmod prompt");
         };
         assert_eq!(vec!["c"], names(&paths));
         assert_eq!("640", mode);
+
+        let PromptAction::Chmod {
+            mode, recursive, ..
+        } = prompt(table.open_chmod_prompt(true))
+        else {
+            panic!("expected a Chmod prompt");
+        };
+        // The entry's own mode is no guide to what its tree should get.
+        assert!(recursive);
+        assert_eq!("", mode);
+    }
+
+    #[test]
+    fn chown_prefills_the_owner_of_a_single_entry_even_recursively() {
+        let (_dir, mut table) = marked_table();
+        table.clear_marks();
+        let selected = table.selected_path().unwrap().clone();
+        let expected = format!(
+            "{}:{}",
+            selected.owner().unwrap(),
+            selected.group().unwrap()
+        );
+
+        for recursive in [false, true] {
+            let PromptAction::Chown { paths, owner, .. } =
+                prompt(table.open_chown_prompt(recursive))
+            else {
+                panic!("expected a Chown prompt");
+            };
+            assert_eq!(vec![selected.clone()], paths);
+            assert_eq!(expected, owner);
+        }
     }
 
     #[test]
@@ -220,7 +726,7 @@ mod tests {
 
         // The marked entries need not share a mode, so prefilling either one
         // would offer to apply it to the rest.
-        let PromptAction::Chmod { paths, mode } = prompt(table.open_chmod_prompt()) else {
+        let PromptAction::Chmod { paths, mode, .. } = prompt(table.open_chmod_prompt(false)) else {
             panic!("expected a Chmod prompt");
         };
         assert_eq!(vec!["a", "b"], names(&paths));
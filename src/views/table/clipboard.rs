@@ -1,7 +1,10 @@
 use super::TableView;
 use crate::{
-    app::clipboard::ClipboardEntry,
-    command::{Command, result::CommandResult},
+    app::{
+        clipboard::ClipboardEntry,
+        config::{Config, keybindings::Action},
+    },
+    command::{Command, PromptAction, result::CommandResult},
     file_system::path_info::PathInfo,
 };
 
@@ -15,15 +18,41 @@ impl TableView {
     }
 
     fn set_clipboard(&mut self, make_entry: fn(Vec<PathInfo>) -> ClipboardEntry) -> CommandResult {
+        match self.marked_or_selected() {
+            None => Command::AlertWarn("No file selected".into()).into(),
+            Some(paths) => Command::SetClipboardEntry(Some(make_entry(paths))).into(),
+        }
+    }
+
+    /// Opens the prompt to copy or move the marked entries, or the selected
+    /// one, to a directory, pre-filled with the other pane's. The clipboard is
+    /// left alone until the paste this becomes is done.
+    pub(super) fn copy_to_other_pane(&self, is_move: bool) -> CommandResult {
+        let Some(destination) = &self.other_pane else {
+            return Command::AlertWarn(format!(
+                "No other pane: press {} to open one",
+                Config::global()
+                    .keybindings
+                    .hint_for(&[Action::ToggleDualPane])
+            ))
+            .into();
+        };
+        match self.marked_or_selected() {
+            None => Command::AlertWarn("No file selected".into()).into(),
+            Some(paths) => Command::OpenPrompt(PromptAction::CopyTo {
+                paths,
+                destination: destination.clone(),
+                is_move,
+            })
+            .into(),
+        }
+    }
+
+    fn marked_or_selected(&self) -> Option<Vec<PathInfo>> {
         if self.has_marks() {
-            Command::SetClipboardEntry(Some(make_entry(self.marked_paths()))).into()
+            Some(self.marked_paths())
         } else {
-            match self.selected_path() {
-                None => Command::AlertWarn("No file selected".into()).into(),
-                Some(path) => {
-                    Command::SetClipboardEntry(Some(make_entry(vec![path.clone()]))).into()
-                }
-            }
+            self.selected_path().map(|path| vec![path.clone()])
         }
     }
 
@@ -76,6 +105,37 @@ mod tests {
         assert_eq!(vec!["c"], display_names(entry.paths()));
     }
 
+    #[test_case(false ; "a copy")]
+    #[test_case(true ; "a move")]
+    fn copying_to_the_other_pane_prompts_with_its_directory(is_move: bool) {
+        let (_dir, mut table) = marked_table();
+        let other = PathInfo::try_from("/tmp").unwrap();
+        table.set_other_pane(Some(other.clone()));
+
+        match Command::try_from(table.copy_to_other_pane(is_move)) {
+            Ok(Command::OpenPrompt(PromptAction::CopyTo {
+                paths,
+                destination,
+                is_move: prompted_move,
+            })) => {
+                assert_eq!(vec!["a", "b"], display_names(&paths));
+                assert_eq!(other, destination);
+                assert_eq!(is_move, prompted_move);
+            }
+            other => panic!("expected the CopyTo prompt, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn copying_to_the_other_pane_needs_one() {
+        let (_dir, table) = marked_table();
+
+        assert!(matches!(
+            Command::try_from(table.copy_to_other_pane(false)),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
+
     #[test]
     fn an_empty_listing_warns_rather_than_setting_an_empty_clipboard() {
         let (_dir, mut table) = marked_table();
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 use std::cmp::Reverse;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{MAIN_SEPARATOR, Path, PathBuf};
 
 use super::columns::{SortColumn, SortDirection};
@@ -18,6 +18,9 @@ pub(super) struct DirectoryContent {
     /// the bookmark items are per-mode data.
     mode: ListingMode,
     search_root: Option<PathBuf>,
+    /// What the search listed was searched for, so a tab brought back can run
+    /// it again.
+    search_query: Option<String>,
     /// True while a directory's entries are still streaming in.
     loading: bool,
     /// Bumped whenever `items_sorted` or display-affecting state (search root,
@@ -27,6 +30,20 @@ pub(super) struct DirectoryContent {
     /// Runtime override for showing hidden (dotfile) entries. `None` defers to
     /// the `ui.show_hidden_files` config value.
     show_hidden: Option<bool>,
+    /// Runtime override for showing directories' recursive sizes. `None`
+    /// defers to the `ui.show_directory_sizes` config value.
+    directory_sizes: Option<bool>,
+    /// The recursive sizes asked for this listing, by path: `None` while the
+    /// directory is still being counted.
+    sizes: HashMap<PathBuf, Option<u64>>,
+}
+
+/// What the Size column shows for an entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum ShownSize {
+    /// A directory whose recursive size is still being counted.
+    Counting,
+    Bytes(u64),
 }
 
 impl DirectoryContent {
@@ -68,6 +85,7 @@ impl DirectoryContent {
         self.directory = Some(directory);
         self.items.clear();
         self.items_sorted.clear();
+        self.sizes.clear();
         self.loading = true;
         self.revision += 1;
     }
@@ -98,6 +116,7 @@ impl DirectoryContent {
         self.mode = mode;
         if mode != ListingMode::Search {
             self.search_root = None;
+            self.search_query = None;
         }
         // A load cancelled mid-stream never finalizes, so leaving the
         // plain-listing flow must clear the loading flag; a stale value
@@ -152,7 +171,7 @@ impl DirectoryContent {
         self.filter.clear();
     }
 
-    fn show_hidden(&self) -> bool {
+    pub(super) fn show_hidden(&self) -> bool {
         self.show_hidden
             .unwrap_or(Config::global().ui.show_hidden_files)
     }
@@ -161,6 +180,65 @@ impl DirectoryContent {
         self.show_hidden = Some(!self.show_hidden());
     }
 
+    pub(super) fn shows_directory_sizes(&self) -> bool {
+        self.directory_sizes
+            .unwrap_or(Config::global().ui.show_directory_sizes)
+    }
+
+    /// Flips the setting and forgets the sizes, so turning it back on counts
+    /// afresh.
+    pub(super) fn toggle_directory_sizes(&mut self) {
+        self.directory_sizes = Some(!self.shows_directory_sizes());
+        self.sizes.clear();
+        self.revision += 1;
+    }
+
+    /// The directories of the current listing to count, now marked as being
+    /// counted. Empty unless sizes are on and the listing is a local
+    /// directory: a search or the bookmarks are not one directory's contents,
+    /// and walking a whole tree over a remote session or out of an archive
+    /// would cost far more than the column is worth. A symlink is left as it
+    /// is, as the walk below it does not follow one either.
+    pub(super) fn directories_to_size(&mut self) -> Vec<PathInfo> {
+        let is_local = self
+            .directory
+            .as_ref()
+            .is_some_and(|directory| directory.remote().is_none() && directory.archive().is_none());
+        if !self.shows_directory_sizes() || self.mode != ListingMode::Normal || !is_local {
+            return Vec::new();
+        }
+        let directories: Vec<_> = self
+            .items
+            .iter()
+            .filter(|item| item.is_directory() && !item.is_symlink())
+            .cloned()
+            .collect();
+        for directory in &directories {
+            self.sizes.insert(directory.path.clone(), None);
+        }
+        self.revision += 1;
+        directories
+    }
+
+    /// Records counted sizes. Ignores a path the listing no longer asks for,
+    /// so a count that crossed a toggle cannot come back.
+    pub(super) fn set_directory_sizes(&mut self, sizes: &[(PathBuf, u64)]) {
+        for (path, bytes) in sizes {
+            if let Some(size) = self.sizes.get_mut(path) {
+                *size = Some(*bytes);
+            }
+        }
+        self.revision += 1;
+    }
+
+    pub(super) fn shown_size(&self, item: &PathInfo) -> ShownSize {
+        match self.sizes.get(&item.path) {
+            Some(None) => ShownSize::Counting,
+            Some(Some(bytes)) => ShownSize::Bytes(*bytes),
+            None => ShownSize::Bytes(item.size),
+        }
+    }
+
     /// Sort and filter items into `items_sorted`. Visibility is re-derived
     /// from the unfiltered `items`, so a toggled show-hidden setting or filter
     /// change takes effect on the next sort.
@@ -200,6 +278,14 @@ impl DirectoryContent {
         // than reversing the sorted listing, so that entries sharing a key keep
         // the order they arrived in whichever way the column points. Every sort
         // here is stable, as the directories-first pass below requires.
+        let sizes = &self.sizes;
+        let sizes_key = |item: &PathInfo| {
+            sizes
+                .get(&item.path)
+                .copied()
+                .flatten()
+                .unwrap_or(item.size)
+        };
         let descending = sort_direction == SortDirection::Descending;
         match (sort_column, descending) {
             (SortColumn::Name, false) => self.items_sorted.sort_by_cached_key(name_key),
@@ -212,8 +298,12 @@ impl DirectoryContent {
             (SortColumn::Modified, true) => self
                 .items_sorted
                 .sort_by_key(|item| Reverse(item.modified_comparator())),
-            (SortColumn::Size, false) => self.items_sorted.sort_by_key(|item| item.size),
-            (SortColumn::Size, true) => self.items_sorted.sort_by_key(|item| Reverse(item.size)),
+            // A directory still being counted sorts by its own size until its
+            // count arrives and re-sorts it.
+            (SortColumn::Size, false) => self.items_sorted.sort_by_key(|item| sizes_key(item)),
+            (SortColumn::Size, true) => self
+                .items_sorted
+                .sort_by_key(|item| Reverse(sizes_key(item))),
         }
 
         if sort_column == SortColumn::Name && Config::global().ui.sort_directories_first {
@@ -221,9 +311,10 @@ impl DirectoryContent {
         }
     }
 
-    pub(super) fn start_search(&mut self) {
+    pub(super) fn start_search(&mut self, query: &str) {
         self.set_mode(ListingMode::Search);
         self.search_root = self.directory.as_ref().map(|d| PathBuf::from(&d.path));
+        self.search_query = Some(query.to_string());
         self.items.clear();
         self.items_sorted.clear();
         self.filter.clear();
@@ -243,6 +334,10 @@ impl DirectoryContent {
         self.search_root.as_deref()
     }
 
+    pub(super) fn search_query(&self) -> Option<&str> {
+        self.search_query.as_deref()
+    }
+
     /// Replace the listing with the given bookmarks (one synchronous batch,
     /// unlike streamed search results). The current `directory` is left
     /// untouched so breadcrumbs/CWD restore cleanly when the view is dismissed.
@@ -515,6 +610,54 @@ mod tests {
         assert_eq!(names(&content), vec!["large", "medium", "small"]);
     }
 
+    #[test]
+    fn counted_directory_sizes_are_shown_and_sorted_by() {
+        Config::init_test();
+        let fx = Fixture::new();
+        let items = vec![
+            fx.dir_entry("big"),
+            fx.file_entry("medium", 5000),
+            fx.dir_entry("empty"),
+        ];
+        let mut content = DirectoryContent::default();
+        content.set_items(fx.directory(), items);
+        // Default config has show_directory_sizes = false.
+        assert!(content.directories_to_size().is_empty());
+
+        content.toggle_directory_sizes();
+        let mut asked: Vec<_> = content
+            .directories_to_size()
+            .into_iter()
+            .map(|directory| directory.display_name)
+            .collect();
+        asked.sort();
+        assert_eq!(asked, vec!["big", "empty"]);
+        let big = fx.dir_entry("big");
+        assert_eq!(ShownSize::Counting, content.shown_size(&big));
+
+        // A path the listing did not ask for is not recorded.
+        content.set_directory_sizes(&[
+            (fx.dir.join("big"), 1_000_000),
+            (fx.dir.join("empty"), 0),
+            (fx.dir.join("elsewhere"), 7),
+        ]);
+        assert_eq!(ShownSize::Bytes(1_000_000), content.shown_size(&big));
+        assert_eq!(
+            ShownSize::Bytes(5000),
+            content.shown_size(&fx.file_entry("medium", 5000))
+        );
+        assert!(!content.sizes.contains_key(&fx.dir.join("elsewhere")));
+
+        content.sort(SortColumn::Size, SortDirection::Ascending);
+        assert_eq!(names(&content), vec!["empty", "medium", "big"]);
+
+        // Off again, directories sort by their own size, below the file.
+        content.toggle_directory_sizes();
+        assert_eq!(ShownSize::Bytes(big.size), content.shown_size(&big));
+        content.sort(SortColumn::Size, SortDirection::Descending);
+        assert_eq!(names(&content)[0], "medium");
+    }
+
     #[test]
     fn filter_retains_case_insensitive_substring_matches() {
         Config::init_test();
@@ -680,7 +823,7 @@ mod tests {
         content.set_items(fx.directory(), vec![]);
         // Default config has show_hidden_files = true; toggle it off.
         content.toggle_show_hidden();
-        content.start_search();
+        content.start_search("a");
 
         // A search explicitly matched these names, so hidden results are kept.
         content.append(&[fx.file_entry(".hidden", 1), fx.file_entry("visible", 1)]);
@@ -816,7 +959,7 @@ mod tests {
         ];
         let mut content = DirectoryContent::default();
         content.set_items(fx.directory(), vec![]);
-        content.start_search();
+        content.start_search("a");
         content.append(&items);
 
         // "reports/" reaches the directory itself through its trailing
@@ -10,7 +10,7 @@ use crate::{
         keybindings::{Action, hardcoded_normal_action},
     },
     command::{Command, handler::CommandHandler, result::CommandResult},
-    file_system::path_info::PathInfo,
+    file_system::{checksums::ChecksumAlgorithm, path_info::PathInfo},
     views::ListingMode,
 };
 
@@ -27,6 +27,9 @@ impl CommandHandler for TableView {
             Command::Copy { .. }
             | Command::Move { .. }
             | Command::Chmod { .. }
+            | Command::Chown { .. }
+            | Command::Touch { .. }
+            | Command::Compress { .. }
             | Command::Delete(_) => {
                 // The operation consumes the marks; the FileSystem handler clears
                 // the clipboard for these same commands. Reset the mark-count
@@ -54,7 +57,14 @@ impl CommandHandler for TableView {
                 generation,
             } => {
                 // Different directory: nothing from the old listing carries over.
+                // Tracked as FileSystem tracks it, for going back.
+                if let Some(current) = self.content.directory()
+                    && current != directory
+                {
+                    self.previous_directory = Some(current.clone());
+                }
                 self.content.clear_filter();
+                self.reveal = None;
                 self.stream_generation = *generation;
                 self.begin_directory(directory.clone(), Reselect::Top);
                 CommandResult::Handled
@@ -64,6 +74,7 @@ impl CommandHandler for TableView {
                 generation,
             } => self.refreshed_directory(directory, *generation),
             Command::ListingBatch { items, generation } => self.listing_batch(items, *generation),
+            Command::Created(path) => self.reveal(path),
             Command::DirectoryListingComplete { generation } => {
                 // A cancelled load that had already drained the directory still
                 // reports completion, and the bookmarks view does not bump the
@@ -75,12 +86,15 @@ impl CommandHandler for TableView {
                 }
                 self.finish_directory()
             }
+            Command::DirectorySizes { sizes, generation } => {
+                self.directory_sizes(sizes, *generation)
+            }
             Command::ResetView => self.reset_view(previous_mode),
             Command::StartSearch(query) => {
                 if query.is_empty() {
                     return CommandResult::Handled;
                 }
-                self.content.start_search();
+                self.content.start_search(query);
                 self.table_state.select(None);
                 self.clear_marks_notifying()
             }
@@ -125,12 +139,20 @@ impl CommandHandler for TableView {
         // Hardcoded bindings take precedence, then config bindings.
         let action = hardcoded_normal_action(code, modifiers)
             .or_else(|| Config::global().keybindings.normal_action(code, modifiers));
+        if let Some(refusal) = action.and_then(|action| {
+            self.refuse_in_archive(action)
+                .or_else(|| self.refuse_on_remote(action))
+        }) {
+            return refusal;
+        }
 
         match action {
             // Clipboard
             Some(Action::Copy) => self.copy_to_clipboard(),
             Some(Action::Cut) => self.cut_to_clipboard(),
             Some(Action::Paste) => self.paste_from_clipboard(),
+            Some(Action::CopyToOtherPane) => self.copy_to_other_pane(false),
+            Some(Action::MoveToOtherPane) => self.copy_to_other_pane(true),
             // Navigation (page)
             Some(Action::PageUp) => self.previous_page(),
             Some(Action::PageDown) => self.next_page(),
@@ -142,6 +164,10 @@ impl CommandHandler for TableView {
             Some(Action::OpenCurrentDirectory) => Command::OpenCurrentDirectory.into(),
             Some(Action::OpenNewWindow) => Command::OpenNewWindow.into(),
             Some(Action::OpenWith) => self.open_with(),
+            Some(Action::DiskUsage) => self.open_disk_usage(),
+            Some(Action::FindDuplicates) => self.open_duplicates(),
+            Some(Action::CompareDirectories) => self.open_compare(),
+            Some(Action::Diff) => self.diff(),
             Some(Action::GoHome) => Self::navigate_to_home_directory(),
             Some(Action::Goto) => self.open_goto_prompt(),
             // Selection
@@ -159,9 +185,24 @@ impl CommandHandler for TableView {
             // File operations
             Some(Action::AddBookmark) => self.open_add_bookmark_prompt(),
             Some(Action::GetBookmarks) => Self::get_bookmarks(),
-            Some(Action::Chmod) => self.open_chmod_prompt(),
+            Some(Action::Chmod) => self.open_chmod_prompt(false),
+            Some(Action::ChmodRecursive) => self.open_chmod_prompt(true),
+            Some(Action::Chown) => self.open_chown_prompt(false),
+            Some(Action::ChownRecursive) => self.open_chown_prompt(true),
+            Some(Action::EditPermissions) => self.open_permission_editor(),
+            Some(Action::Touch) => self.open_touch_prompt(),
+            Some(Action::TouchRecursive) => self.open_touch_recursive_prompt(),
+            Some(Action::TouchFromClipboard) => self.touch_like_clipboard(),
+            Some(Action::Compress) => self.open_compress_prompt(),
+            Some(Action::Extract) => self.extract(false),
+            Some(Action::ExtractHere) => self.extract(true),
             Some(Action::CreateDirectory) => Self::open_create_directory_prompt(),
+            Some(Action::CreateFile) => Self::open_create_file_prompt(),
+            Some(Action::NewFromTemplate) => Self::open_template_picker(),
             Some(Action::Delete) => self.delete(),
+            Some(Action::Checksum) => self.checksum(ChecksumAlgorithm::Sha256),
+            Some(Action::ChecksumBlake3) => self.checksum(ChecksumAlgorithm::Blake3),
+            Some(Action::VerifyChecksums) => self.verify_checksums(),
             Some(Action::Rename) => self.open_rename_prompt(),
             Some(Action::Filter) => self.open_filter_prompt(),
             Some(Action::Search) => Self::open_search_prompt(),
@@ -170,6 +211,7 @@ impl CommandHandler for TableView {
             Some(Action::SortByModified) => self.sort_by(SortColumn::Modified),
             Some(Action::SortBySize) => self.sort_by(SortColumn::Size),
             Some(Action::ToggleShowHidden) => self.toggle_show_hidden(),
+            Some(Action::ToggleDirectorySizes) => self.toggle_directory_sizes(),
             _ => CommandResult::NotHandled,
         }
     }
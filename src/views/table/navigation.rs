@@ -1,5 +1,10 @@
+use std::path::{Path, PathBuf};
+
 use super::{TableView, columns::SortColumn};
-use crate::{command::result::CommandResult, file_system::path_info::PathInfo};
+use crate::{
+    command::{Command, result::CommandResult},
+    file_system::path_info::PathInfo,
+};
 
 /// What to select after the visible items change.
 #[derive(Clone, Copy, Default)]
@@ -77,7 +82,47 @@ impl TableView {
             self.marks.insert(index);
         }
         // Ends in `select`, whose snapshot then carries the restored count.
-        self.restore_selection()
+        let restored = self.restore_selection();
+        let directories = self.content.directories_to_size();
+        if directories.is_empty() {
+            return restored;
+        }
+        let mut commands = restored.into_commands();
+        commands.push(Command::SizeDirectories {
+            directories,
+            generation: self.stream_generation,
+        });
+        commands.into()
+    }
+
+    /// Records directory sizes counted for the current listing, re-sorting
+    /// when they are what it is sorted by.
+    pub(super) fn directory_sizes(
+        &mut self,
+        sizes: &[(PathBuf, u64)],
+        generation: u64,
+    ) -> CommandResult {
+        if generation != self.stream_generation || self.content.is_loading() {
+            return CommandResult::Handled;
+        }
+        self.content.set_directory_sizes(sizes);
+        if self.columns.sort_column() == SortColumn::Size {
+            return self.sort_keeping_marks(Reselect::Keep);
+        }
+        CommandResult::Handled
+    }
+
+    /// Select an entry just created, now if the listing already holds it,
+    /// otherwise once a load brings it in.
+    pub(super) fn reveal(&mut self, path: &Path) -> CommandResult {
+        if !self.content.is_loading()
+            && let Some(index) = self.content.find_by_path(path)
+        {
+            self.reveal = None;
+            return self.select(index);
+        }
+        self.reveal = Some(path.to_path_buf());
+        CommandResult::Handled
     }
 
     /// Restore the selection captured by `begin_directory`: prefer the child we
@@ -87,6 +132,15 @@ impl TableView {
     fn restore_selection(&mut self) -> CommandResult {
         let pending = std::mem::take(&mut self.pending_load);
 
+        // An entry just created wins over all of it: it is what the user was
+        // doing.
+        if let Some(reveal) = &self.reveal
+            && let Some(index) = self.content.find_by_path(reveal)
+        {
+            self.reveal = None;
+            return self.select(index);
+        }
+
         // If we navigated to an ancestor directory, select the child we came from.
         if let Some(prev_directory) = pending.prev_directory
             && let Some(new_directory) = self.content.directory()
@@ -199,6 +253,38 @@ impl TableView {
         self.sort(Reselect::Top)
     }
 
+    /// Turning sizes on counts the listing's directories; turning them off
+    /// stops the count and puts their own sizes back. A listing still loading
+    /// is counted once it completes.
+    pub(super) fn toggle_directory_sizes(&mut self) -> CommandResult {
+        self.content.toggle_directory_sizes();
+        if self.content.shows_directory_sizes() {
+            let directories = if self.content.is_loading() {
+                Vec::new()
+            } else {
+                self.content.directories_to_size()
+            };
+            if directories.is_empty() {
+                return CommandResult::Handled;
+            }
+            return Command::SizeDirectories {
+                directories,
+                generation: self.stream_generation,
+            }
+            .into();
+        }
+        let mut commands = if self.columns.sort_column() == SortColumn::Size {
+            self.sort_keeping_marks(Reselect::Keep).into_commands()
+        } else {
+            Vec::new()
+        };
+        commands.push(Command::SizeDirectories {
+            directories: Vec::new(),
+            generation: self.stream_generation,
+        });
+        commands.into()
+    }
+
     pub(super) fn toggle_show_hidden(&mut self) -> CommandResult {
         // Search results always show hidden entries, so the setting has no
         // visible effect during a search; toggling then would only cause
@@ -302,6 +388,27 @@ mod tests {
         assert_eq!(selected_basename(&table).as_deref(), Some("a"));
     }
 
+    #[test]
+    fn a_created_entry_is_selected_once_a_reload_lists_it() {
+        Config::init_test();
+        let fx = Fixture::new();
+        let mut table = TableView::default();
+        let (a, c) = (fx.file("a", 1), fx.file("c", 1));
+        table.set_directory(fx.directory(), &[a.clone(), c.clone()], Reselect::Top);
+        table.select(1); // "c"
+        let b = fx.file("b", 1);
+
+        // Arrives before the refresh that lists it, and outlasts a load that
+        // finishes without it.
+        table.reveal(b.as_path());
+        table.set_directory(fx.directory(), &[a.clone(), c.clone()], Reselect::Keep);
+        assert_eq!(selected_basename(&table).as_deref(), Some("c"));
+        table.set_directory(fx.directory(), &[a, b, c], Reselect::Keep);
+
+        assert_eq!(selected_basename(&table).as_deref(), Some("b"));
+        assert!(table.reveal.is_none());
+    }
+
     #[test]
     fn sort_keeps_the_selected_file_when_it_moves_position() {
         Config::init_test();
@@ -553,7 +660,7 @@ mod tests {
         let mut table = table_with_two_marks(&fx);
         // start_search is called directly, so the two marks carry into the
         // search listing exactly as they would after marking results.
-        table.content.start_search();
+        table.content.start_search("a");
         assert_eq!(table.marks.len(), 2);
 
         // A watcher event fires while search results are displayed. The listing
@@ -729,6 +836,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn directory_sizes_are_asked_for_and_sort_the_listing_once_counted() {
+        Config::init_test();
+        let fx = Fixture::new();
+        let mut table = TableView::default();
+        let big = fx.nested("big", "inside");
+        let big = big.parent().unwrap();
+        let children = [big.clone(), fx.file("medium", 5000)];
+        table.set_directory(fx.directory(), &children, Reselect::Top);
+        // Descending, biggest first.
+        table.sort_by(SortColumn::Size);
+        assert_eq!(table.content.get(0).unwrap().display_name, "medium");
+
+        let result = table.toggle_directory_sizes();
+        assert_eq!(
+            result,
+            Command::SizeDirectories {
+                directories: vec![big.clone()],
+                generation: table.stream_generation,
+            }
+            .into()
+        );
+
+        // A count for a superseded listing changes nothing.
+        table.handle_command(&Command::DirectorySizes {
+            sizes: vec![(big.path.clone(), 1_000_000)],
+            generation: table.stream_generation + 1,
+        });
+        assert_eq!(table.content.get(0).unwrap().display_name, "medium");
+
+        table.handle_command(&Command::DirectorySizes {
+            sizes: vec![(big.path.clone(), 1_000_000)],
+            generation: table.stream_generation,
+        });
+        assert_eq!(table.content.get(0).unwrap().display_name, "big");
+
+        // A reload asks again, for the cache to answer.
+        let result = table.set_directory(fx.directory(), &children, Reselect::Keep);
+        assert!(result.into_commands().contains(&Command::SizeDirectories {
+            directories: vec![big.clone()],
+            generation: table.stream_generation,
+        }));
+
+        // Off stops the count and forgets it.
+        let result = table.toggle_directory_sizes();
+        assert!(result.into_commands().contains(&Command::SizeDirectories {
+            directories: Vec::new(),
+            generation: table.stream_generation,
+        }));
+        assert_eq!(table.content.get(0).unwrap().display_name, "medium");
+    }
+
     #[test]
     fn toggle_show_hidden_is_a_noop_during_a_search() {
         Config::init_test();
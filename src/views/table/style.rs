@@ -1,11 +1,11 @@
 use chrono::{DateTime, Local};
 use ratatui::style::Style;
 
-use super::columns::SortColumn;
+use super::{columns::SortColumn, content::ShownSize};
 use crate::{
     app::clipboard::ClipboardEntry,
     app::config::theme::{Clipboard, FileModifiedDate, FileSize, FileType, Table},
-    file_system::path_info::{DateTimeAge, PathInfo, datetime_age},
+    file_system::path_info::{DateTimeAge, PathInfo, datetime_age, unit_index},
 };
 
 pub(super) fn clipboard_style(
@@ -31,7 +31,7 @@ pub(super) fn header_style(table: &Table, sort_column: SortColumn, column: SortC
     }
 }
 
-pub(super) fn name_style(theme: &FileType, path: &PathInfo) -> Style {
+pub(crate) fn name_style(theme: &FileType, path: &PathInfo) -> Style {
     // Symlinks should be checked first (highest precedence in ls)
     if path.is_symlink_broken() {
         return theme.symlink_broken();
@@ -114,8 +114,14 @@ pub(super) fn modified_date_style(
     }
 }
 
-pub(super) fn size_style(file_size: &FileSize, item: &PathInfo) -> Style {
-    match item.size_unit_index() {
+/// A directory still being counted takes the bytes style, as an empty one
+/// would.
+pub(super) fn size_style(file_size: &FileSize, size: ShownSize) -> Style {
+    let unit_index = match size {
+        ShownSize::Counting => 0,
+        ShownSize::Bytes(bytes) => unit_index(bytes),
+    };
+    match unit_index {
         0 => file_size.bytes(),
         1 => file_size.kib(),
         2 => file_size.mib(),
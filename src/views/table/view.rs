@@ -111,6 +111,7 @@ impl TableView {
                     name_width,
                     relative_to_datetime,
                     item,
+                    self.content.shown_size(item),
                     self.marks.contains(i),
                     is_pending_delete,
                     is_bookmarks,
@@ -133,6 +134,7 @@ impl TableView {
         // Using a local state keeps ratatui from mutating our own offset model.
         let mut render_state = TableState::default();
         if let Some(selected) = selected
+            && !self.is_out_of_focus
             && selected >= start
             && selected < end
         {
@@ -9,12 +9,12 @@ use ratatui::{
 
 use super::{
     columns::{SortColumn, SortDirection},
-    content::displayed_name,
+    content::{ShownSize, displayed_name},
     style::{clipboard_style, header_style, modified_date_style, name_style, size_style},
 };
 use crate::{
     app::{clipboard::ClipboardEntry, config::theme::Theme},
-    file_system::path_info::PathInfo,
+    file_system::path_info::{PathInfo, humanize_size},
     views::{as_dimension, unicode::split_with_ellipsis},
 };
 
@@ -78,6 +78,20 @@ fn header_cell_widget(
     Cell::from(label.style(header_style(&theme.table, sort_column, column)))
 }
 
+/// The frames a directory still being counted shows in the Size column.
+const COUNTING_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each counting frame is shown. The frame is derived from the clock
+/// the row is drawn at, so this sets the speed; the sizing walk's ticks are
+/// what redraw it.
+const COUNTING_FRAME_MILLIS: i64 = 80;
+
+fn counting_frame(now: DateTime<Local>) -> &'static str {
+    let frames = i64::try_from(COUNTING_FRAMES.len()).unwrap_or(1);
+    let index = (now.timestamp_millis() / COUNTING_FRAME_MILLIS).rem_euclid(frames);
+    COUNTING_FRAMES[usize::try_from(index).unwrap_or(0)]
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(super) fn row_widget_and_height<'a>(
     theme: &'a Theme,
@@ -85,6 +99,7 @@ pub(super) fn row_widget_and_height<'a>(
     name_column_width: u16,
     relative_to_datetime: DateTime<Local>,
     item: &'a PathInfo,
+    size: ShownSize,
     is_marked: bool,
     is_pending_delete: bool,
     is_bookmarks: bool,
@@ -105,7 +120,7 @@ pub(super) fn row_widget_and_height<'a>(
         (
             name_style(&theme.file_type, item),
             modified_date_style(&theme.file_modified_date, item, relative_to_datetime),
-            size_style(&theme.file_size, item),
+            size_style(&theme.file_size, size),
             theme.table.body(),
         )
     };
@@ -118,7 +133,11 @@ pub(super) fn row_widget_and_height<'a>(
     let row = Row::new([
         Cell::from(name).style(name_style),
         Cell::from(item.modified(relative_to_datetime).unwrap_or_default()).style(date_style),
-        Cell::from(item.size()).style(size_style),
+        Cell::from(match size {
+            ShownSize::Counting => counting_frame(relative_to_datetime).to_string(),
+            ShownSize::Bytes(bytes) => humanize_size(bytes),
+        })
+        .style(size_style),
         Cell::from(item.unix_mode()),
     ])
     .height(height)
@@ -158,7 +177,7 @@ mod tests {
     use chrono::Local;
     use test_case::test_case;
 
-    use super::{item_height, row_widget_and_height};
+    use super::{ShownSize, item_height, row_widget_and_height};
     use crate::{app::config::Config, file_system::path_info::PathInfo};
 
     // `item_height` must always agree with the height `row_widget_and_height`
@@ -178,6 +197,7 @@ mod tests {
             width,
             Local::now(),
             &item,
+            ShownSize::Bytes(item.size),
             false,
             false,
             false,
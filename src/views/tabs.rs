@@ -0,0 +1,282 @@
+mod handler;
+mod view;
+
+use std::{mem, path::MAIN_SEPARATOR_STR};
+
+use ratatui::layout::Rect;
+
+use super::panes::PanesView;
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, result::CommandResult},
+    file_system::path_info::PathInfo,
+};
+
+/// The tab bar, and the tabs out of view. Each tab is a `PanesView` of its
+/// own, with its panes' directories, sorts, filters, marks, history, and
+/// whether it shows search results or the bookmarks. FileSystem lists and
+/// watches one directory at a time, so the active tab lives in `RootView`
+/// where the listing is drawn and fed, and the others are kept here untouched
+/// until one is brought back, when it is read again as the pane out of focus
+/// is, its search run again and its bookmarks read again.
+#[derive(Default)]
+pub(super) struct TabsView {
+    /// Every tab in bar order. The active one's slot holds a placeholder
+    /// while `RootView` has the tab itself.
+    tabs: Vec<PanesView>,
+    active: usize,
+    /// The active tab's title, followed from the listings broadcast to it.
+    active_title: String,
+    /// Where each tab's title is drawn, for a click that activates it.
+    title_areas: Vec<Rect>,
+}
+
+impl TabsView {
+    pub(super) fn new() -> Self {
+        Self {
+            tabs: vec![PanesView::default()],
+            ..Self::default()
+        }
+    }
+
+    /// Opens a tab on the active tab's directory, after it, and activates it.
+    pub(super) fn open(&mut self, panes: &mut PanesView) -> CommandResult {
+        let Some(directory) = panes.directory().cloned() else {
+            return CommandResult::Handled;
+        };
+        let opened = panes.opened_from();
+        self.tabs[self.active] = mem::replace(panes, opened);
+        self.active += 1;
+        self.tabs.insert(self.active, PanesView::default());
+        self.active_title = title(&directory);
+        Command::SwitchedPane {
+            directory,
+            filter: String::new(),
+            mark_count: 0,
+            previous_directory: None,
+        }
+        .into()
+    }
+
+    /// Closes the active tab and activates the one to its right, or the one
+    /// to its left when it was the last.
+    pub(super) fn close(&mut self, panes: &mut PanesView) -> CommandResult {
+        if self.tabs.len() == 1 {
+            return Command::AlertWarn("Cannot close the only tab".into()).into();
+        }
+        self.tabs.remove(self.active);
+        self.active = self.active.min(self.tabs.len() - 1);
+        *panes = mem::take(&mut self.tabs[self.active]);
+        self.brought_in(panes)
+    }
+
+    /// Activates the tab at `index`.
+    pub(super) fn switch_to(&mut self, index: usize, panes: &mut PanesView) -> CommandResult {
+        if index == self.active {
+            return CommandResult::Handled;
+        }
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return Command::AlertWarn(format!("No tab {}", index + 1)).into();
+        };
+        self.tabs[self.active] = mem::replace(panes, mem::take(tab));
+        self.active = index;
+        self.brought_in(panes)
+    }
+
+    /// Has FileSystem take up the tab just activated.
+    fn brought_in(&mut self, panes: &PanesView) -> CommandResult {
+        self.active_title = panes.directory().map(title).unwrap_or_default();
+        panes.switched_to()
+    }
+
+    /// What each tab is titled in the bar: its focused pane's directory.
+    fn titles(&self) -> Vec<String> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| {
+                if index == self.active {
+                    self.active_title.clone()
+                } else {
+                    tab.directory().map(title).unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+
+    /// The tab `step` places after the active one, wrapping around the bar,
+    /// or a hint to open one when there is no other.
+    fn cycle(&self, step: usize) -> CommandResult {
+        let count = self.tabs.len();
+        if count == 1 {
+            return Command::AlertWarn(format!(
+                "No other tab: press {} to open one",
+                Config::global().keybindings.hint_for(&[Action::NewTab])
+            ))
+            .into();
+        }
+        Command::SwitchTab((self.active + step) % count).into()
+    }
+}
+
+fn title(directory: &PathInfo) -> String {
+    if directory.display_name.is_empty() {
+        MAIN_SEPARATOR_STR.to_string()
+    } else {
+        directory.display_name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{command::handler::CommandHandler, test_support::TempDir};
+
+    /// Hands `command` to the panes' tables, as dispatch does.
+    fn broadcast(panes: &mut PanesView, command: &Command) {
+        panes.visit_command_handlers(&mut |table| {
+            table.handle_command(command);
+        });
+    }
+
+    /// `count` tabs on `fx`, with the last one active, as opening them leaves
+    /// them. Each listing is stood in for by the RefreshedDirectory that
+    /// FileSystem answers SwitchedPane with.
+    fn tabs(fx: &TempDir, count: usize) -> (TabsView, PanesView) {
+        Config::init_test();
+        let directory = PathInfo::try_from(fx.path()).unwrap();
+        let mut tabs = TabsView::new();
+        let mut panes = PanesView::default();
+        let navigated = Command::NavigatedDirectory {
+            directory: directory.clone(),
+            generation: 1,
+        };
+        tabs.handle_command(&navigated);
+        broadcast(&mut panes, &navigated);
+        for _ in 1..count {
+            tabs.open(&mut panes);
+            broadcast(
+                &mut panes,
+                &Command::RefreshedDirectory {
+                    directory: directory.clone(),
+                    generation: 1,
+                },
+            );
+        }
+        (tabs, panes)
+    }
+
+    #[test]
+    fn a_tab_opens_after_the_active_one_on_its_directory() {
+        let fx = TempDir::new("tabs");
+        let (mut tabs, mut panes) = tabs(&fx, 2);
+        tabs.switch_to(0, &mut panes);
+
+        let result = tabs.open(&mut panes);
+
+        assert_eq!(
+            Some(Command::SwitchedPane {
+                directory: PathInfo::try_from(fx.path()).unwrap(),
+                filter: String::new(),
+                mark_count: 0,
+                previous_directory: None,
+            }),
+            Command::try_from(result).ok()
+        );
+        assert_eq!((3, 1), (tabs.tabs.len(), tabs.active));
+    }
+
+    #[test]
+    fn switching_brings_back_the_tabs_own_directory_and_history() {
+        let fx = TempDir::new("tabs");
+        std::fs::create_dir(fx.join("sub")).unwrap();
+        let (mut tabs, mut panes) = tabs(&fx, 2);
+        let sub = PathInfo::try_from(fx.join("sub").as_path()).unwrap();
+        let navigated = Command::NavigatedDirectory {
+            directory: sub.clone(),
+            generation: 2,
+        };
+        tabs.handle_command(&navigated);
+        broadcast(&mut panes, &navigated);
+        let root = PathInfo::try_from(fx.path()).unwrap();
+        assert_eq!(vec![title(&root), "sub".into()], tabs.titles());
+
+        let result = tabs.switch_to(0, &mut panes);
+        assert!(matches!(
+            Command::try_from(result),
+            Ok(Command::SwitchedPane {
+                previous_directory: None,
+                ..
+            })
+        ));
+
+        let result = tabs.switch_to(1, &mut panes);
+        assert_eq!(
+            Some(Command::SwitchedPane {
+                directory: sub,
+                filter: String::new(),
+                mark_count: 0,
+                previous_directory: Some(root),
+            }),
+            Command::try_from(result).ok()
+        );
+    }
+
+    #[test]
+    fn a_tab_left_on_a_search_runs_it_again_when_brought_back() {
+        let fx = TempDir::new("tabs");
+        let (mut tabs, mut panes) = tabs(&fx, 2);
+        broadcast(&mut panes, &Command::StartSearch("txt".into()));
+
+        let result = tabs.switch_to(0, &mut panes);
+        assert!(matches!(
+            Command::try_from(result),
+            Ok(Command::SwitchedPane { .. })
+        ));
+
+        let commands = tabs.switch_to(1, &mut panes).into_commands();
+        assert!(matches!(commands[0], Command::SwitchedPane { .. }));
+        assert_eq!(commands[1..], [Command::StartSearch("txt".into())]);
+    }
+
+    #[test]
+    fn a_tab_left_on_the_bookmarks_reads_them_again_when_brought_back() {
+        let fx = TempDir::new("tabs");
+        let (mut tabs, mut panes) = tabs(&fx, 2);
+        broadcast(
+            &mut panes,
+            &Command::Bookmarks {
+                bookmarks: Vec::new(),
+            },
+        );
+        tabs.switch_to(0, &mut panes);
+
+        let commands = tabs.switch_to(1, &mut panes).into_commands();
+
+        assert!(matches!(commands[0], Command::SwitchedPane { .. }));
+        assert_eq!(commands[1..], [Command::GetBookmarks]);
+    }
+
+    #[test]
+    fn closing_a_tab_activates_its_neighbour() {
+        let fx = TempDir::new("tabs");
+        let (mut tabs, mut panes) = tabs(&fx, 3);
+
+        // The last tab gives way to the one on its left...
+        tabs.close(&mut panes);
+        assert_eq!((2, 1), (tabs.tabs.len(), tabs.active));
+        // ...any other to the one on its right, which takes its place.
+        tabs.switch_to(0, &mut panes);
+        tabs.close(&mut panes);
+        assert_eq!((1, 0), (tabs.tabs.len(), tabs.active));
+
+        assert!(matches!(
+            Command::try_from(tabs.close(&mut panes)),
+            Ok(Command::AlertWarn(_))
+        ));
+        assert!(matches!(
+            Command::try_from(tabs.switch_to(4, &mut panes)),
+            Ok(Command::AlertWarn(_))
+        ));
+    }
+}
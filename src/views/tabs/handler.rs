@@ -0,0 +1,73 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use super::{TabsView, title};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, handler::CommandHandler, result::CommandResult},
+};
+
+impl CommandHandler for TabsView {
+    fn handle_command(&mut self, command: &Command) -> CommandResult {
+        match command {
+            Command::NavigatedDirectory { directory, .. }
+            | Command::RefreshedDirectory { directory, .. } => {
+                self.active_title = title(directory);
+                CommandResult::NotHandled
+            }
+            // The clipboard is shared, and a tab out of view shows what is on
+            // it and pastes it once active again, so it must not miss a
+            // change.
+            Command::SetClipboardEntry(_) => {
+                for (index, tab) in self.tabs.iter_mut().enumerate() {
+                    if index != self.active {
+                        tab.share_clipboard(command);
+                    }
+                }
+                CommandResult::NotHandled
+            }
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> CommandResult {
+        match Config::global().keybindings.normal_action(code, modifiers) {
+            Some(Action::NewTab) => Command::NewTab.into(),
+            Some(Action::CloseTab) => Command::CloseTab.into(),
+            Some(Action::NextTab) => self.cycle(1),
+            Some(Action::PreviousTab) => self.cycle(self.tabs.len() - 1),
+            Some(action) => Action::GO_TO_TAB
+                .iter()
+                .position(|go_to_tab| *go_to_tab == action)
+                .map_or(CommandResult::NotHandled, |index| {
+                    Command::SwitchTab(index).into()
+                }),
+            None => CommandResult::NotHandled,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> CommandResult {
+        let position = Position {
+            x: event.column,
+            y: event.row,
+        };
+        self.title_areas
+            .iter()
+            .position(|area| area.contains(position))
+            .map_or(CommandResult::Handled, |index| {
+                Command::SwitchTab(index).into()
+            })
+    }
+
+    fn should_handle_mouse(&self, event: MouseEvent) -> bool {
+        matches!(event.kind, MouseEventKind::Down(MouseButton::Left))
+            && self.title_areas.iter().any(|area| {
+                area.contains(Position {
+                    x: event.column,
+                    y: event.row,
+                })
+            })
+    }
+}
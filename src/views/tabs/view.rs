@@ -0,0 +1,48 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use super::TabsView;
+use crate::{
+    app::config::Config,
+    views::{View, as_dimension},
+};
+
+impl View for TabsView {
+    /// The bar is only shown once there is a second tab, so a single tab
+    /// looks as it did before there were tabs.
+    fn constraint(&self, _: Rect) -> Constraint {
+        Constraint::Length(u16::from(self.tabs.len() > 1))
+    }
+
+    fn render(&mut self, area: Rect, frame: &mut Frame<'_>) {
+        self.title_areas.clear();
+        if area.is_empty() {
+            return;
+        }
+        let theme = Config::global().theme();
+        let mut spans = Vec::new();
+        let mut x = area.x;
+        for (index, title) in self.titles().into_iter().enumerate() {
+            let span = Span::styled(
+                format!(" {} {title} ", index + 1),
+                if index == self.active {
+                    theme.tabs.active()
+                } else {
+                    theme.tabs.base()
+                },
+            );
+            let width = as_dimension(span.width());
+            self.title_areas
+                .push(Rect { x, width, ..area }.intersection(area));
+            x = x.saturating_add(width);
+            spans.push(span);
+        }
+        Paragraph::new(Line::from(spans))
+            .style(theme.tabs.base())
+            .render(area, frame.buffer_mut());
+    }
+}
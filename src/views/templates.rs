@@ -0,0 +1,181 @@
+mod handler;
+mod view;
+mod widget;
+
+use std::path::PathBuf;
+
+use ratatui::layout::Rect;
+
+use super::{
+    ScrollbarView,
+    open_with::{clamp_scroll, clamp_selection},
+};
+use crate::{
+    app::config::{Config, keybindings::Action},
+    command::{Command, PromptAction, result::CommandResult},
+    file_system::{
+        path_info::PathInfo,
+        templates::{templates_dir, templates_in},
+    },
+};
+
+const MIN_HEIGHT: u16 = 3; // border + 1 row + border
+/// Rows past this many have no digit shortcut and are reached by scrolling.
+const MAX_SHORTCUT: usize = 9;
+
+/// Lists the templates directory and resolves the chosen template into the
+/// prompt that names its copy. Shown in place of the table, like the "open
+/// with" picker, whose scrolling and styles it shares.
+pub(super) struct TemplatesView {
+    area: Rect,
+    /// The rows' area, for hit testing a click on a row.
+    content_area: Rect,
+    /// Where the templates were read from, named when there are none so the
+    /// user knows where to put some.
+    directory: Option<PathBuf>,
+    /// Bordered header hint, cached at construction.
+    hint: String,
+    inner_height: usize,
+    is_visible: bool,
+    scroll_offset: usize,
+    scrollbar_view: ScrollbarView,
+    selected: usize,
+    templates: Vec<PathInfo>,
+}
+
+impl TemplatesView {
+    pub(super) fn new() -> Self {
+        let kb = &Config::global().keybindings;
+        Self {
+            area: Rect::default(),
+            content_area: Rect::default(),
+            directory: None,
+            hint: format!(
+                "(Press {} to close)",
+                kb.hint_for(&[Action::NewFromTemplate, Action::ResetView])
+            ),
+            inner_height: 0,
+            is_visible: false,
+            scroll_offset: 0,
+            scrollbar_view: ScrollbarView::default(),
+            selected: 0,
+            templates: Vec::new(),
+        }
+    }
+
+    pub(super) fn is_visible(&self) -> bool {
+        self.is_visible
+    }
+
+    /// Re-read the templates directory and show the picker. Read on every
+    /// show, so a template saved since the last one is offered.
+    pub(super) fn show(&mut self) {
+        self.directory = templates_dir();
+        self.templates = self
+            .directory
+            .as_deref()
+            .map(templates_in)
+            .unwrap_or_default();
+        self.inner_height = 0;
+        self.is_visible = true;
+        self.scroll_offset = 0;
+        self.selected = 0;
+    }
+
+    pub(super) fn hide(&mut self) {
+        self.templates = Vec::new();
+        self.is_visible = false;
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.templates.len().saturating_sub(self.inner_height)
+    }
+
+    fn select(&mut self, index: usize) -> CommandResult {
+        if self.templates.is_empty() {
+            return CommandResult::Handled;
+        }
+        self.selected = index.min(self.templates.len() - 1);
+        self.scroll_offset = clamp_scroll(self.inner_height, self.selected, self.scroll_offset);
+        CommandResult::Handled
+    }
+
+    fn handle_scroll_action(&mut self, action: Action) -> CommandResult {
+        let last = self.templates.len().saturating_sub(1);
+        let page = self.inner_height.max(1);
+        match action {
+            Action::SelectNext => self.select(self.selected.saturating_add(1)),
+            Action::SelectPrevious => self.select(self.selected.saturating_sub(1)),
+            Action::PageDown => self.select(self.selected.saturating_add(page)),
+            Action::PageUp => self.select(self.selected.saturating_sub(page)),
+            Action::SelectFirst => self.select(0),
+            Action::SelectLast => self.select(last),
+            _ => CommandResult::NotHandled,
+        }
+    }
+
+    fn choose_selected(&mut self) -> CommandResult {
+        self.choose_row(self.selected)
+    }
+
+    /// Ask for the name of a copy of the template in `index`, pre-filled with
+    /// the template's own, or do nothing when the row does not exist (a digit
+    /// beyond the end of a short list).
+    fn choose_row(&mut self, index: usize) -> CommandResult {
+        let Some(template) = self.templates.get(index) else {
+            return CommandResult::Handled;
+        };
+        let command = Command::OpenPrompt(PromptAction::CreateFromTemplate {
+            template: template.clone(),
+            name: template.name().into_owned(),
+        });
+        self.hide();
+        command.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::TemplatesView;
+    use crate::{
+        app::config::Config,
+        command::{Command, PromptAction},
+        file_system::path_info::PathInfo,
+        test_support::TempDir,
+    };
+
+    #[test]
+    fn choosing_a_template_asks_for_a_name_starting_from_its_own() {
+        Config::init_test();
+        let fx = TempDir::new("templates_view");
+        fs::write(fx.join("Text.txt"), b"").unwrap();
+        let template = PathInfo::try_from(fx.join("Text.txt").as_path()).unwrap();
+        let mut view = TemplatesView::new();
+        view.is_visible = true;
+        view.templates = vec![template.clone()];
+
+        let command = Command::try_from(view.choose_selected()).unwrap();
+
+        assert_eq!(
+            Command::OpenPrompt(PromptAction::CreateFromTemplate {
+                template,
+                name: "Text.txt".to_string(),
+            }),
+            command
+        );
+        // The prompt takes over from here.
+        assert!(!view.is_visible());
+    }
+
+    #[test]
+    fn a_digit_past_the_end_of_the_list_chooses_nothing() {
+        Config::init_test();
+        let mut view = TemplatesView::new();
+        view.is_visible = true;
+
+        assert!(Command::try_from(view.choose_row(3)).is_err());
+        assert!(view.is_visible());
+    }
+}
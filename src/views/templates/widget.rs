@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use ratatui::{
+    buffer::CellWidth,
+    text::{Line, Span},
+};
+
+use super::MAX_SHORTCUT;
+use crate::{
+    app::config::theme::OpenWith,
+    file_system::path_info::{PathInfo, compact},
+};
+
+/// One line per template: a digit shortcut, the file name, and its size. With
+/// no templates, a line saying where they would have been found.
+pub(super) fn build_rows(
+    theme: &OpenWith,
+    selected: usize,
+    width: u16,
+    directory: Option<&Path>,
+    templates: &[PathInfo],
+) -> Vec<Line<'static>> {
+    if templates.is_empty() {
+        let message = match directory {
+            Some(directory) => format!(" No templates in {}", compact(directory)),
+            None => " No templates directory".to_string(),
+        };
+        return vec![Line::styled(message, theme.detail())];
+    }
+    templates
+        .iter()
+        .enumerate()
+        .map(|(index, template)| build_row(theme, index == selected, width, index, template))
+        .collect()
+}
+
+fn build_row(
+    theme: &OpenWith,
+    is_selected: bool,
+    width: u16,
+    index: usize,
+    template: &PathInfo,
+) -> Line<'static> {
+    let shortcut = if index < MAX_SHORTCUT {
+        format!("{}. ", index + 1)
+    } else {
+        " ".repeat(3)
+    };
+    let name = template.name().into_owned();
+    let size = template.size();
+    let used = 1
+        + shortcut.cell_width() as usize
+        + name.cell_width() as usize
+        + 2
+        + size.cell_width() as usize;
+    let padding = " ".repeat((width as usize).saturating_sub(used));
+
+    if is_selected {
+        // One span inheriting the line style, as in the "open with" picker.
+        return Line::styled(
+            format!(" {shortcut}{name}  {size}{padding}"),
+            theme.selected(),
+        );
+    }
+    Line::from(vec![
+        Span::raw(" "),
+        Span::styled(shortcut, theme.shortcut()),
+        Span::raw(name),
+        Span::raw("  "),
+        Span::styled(size, theme.detail()),
+        Span::raw(padding),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::build_rows;
+    use crate::{
+        app::config::{Config, theme::OpenWith},
+        file_system::path_info::PathInfo,
+        test_support::TempDir,
+    };
+
+    fn theme() -> &'static OpenWith {
+        Config::init_test();
+        &Config::global().theme().open_with
+    }
+
+    fn text(line: &ratatui::text::Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn an_empty_list_names_the_directory_to_put_templates_in() {
+        let fx = TempDir::new("templates_widget_empty");
+
+        let rows = build_rows(theme(), 0, 40, Some(fx.path()), &[]);
+
+        assert_eq!(1, rows.len());
+        assert!(
+            text(&rows[0]).contains("templates_widget_empty"),
+            "{:?}",
+            text(&rows[0])
+        );
+    }
+
+    #[test]
+    fn a_row_shows_the_name_and_size() {
+        let fx = TempDir::new("templates_widget");
+        fs::write(fx.join("Text.txt"), b"hello").unwrap();
+        let template = PathInfo::try_from(fx.join("Text.txt").as_path()).unwrap();
+
+        let rows = build_rows(
+            theme(),
+            1,
+            40,
+            Some(fx.path()),
+            std::slice::from_ref(&template),
+        );
+
+        assert_eq!(
+            format!(" 1. Text.txt  {}", template.size()),
+            text(&rows[0]).trim_end()
+        );
+    }
+}
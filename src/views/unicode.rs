@@ -12,6 +12,16 @@ pub(super) fn pluralize_items(count: usize) -> String {
     }
 }
 
+/// `pluralize_items` for any noun, spelled out in both forms since they are
+/// not all a trailing "s" apart ("directory", "directories").
+pub(super) fn pluralize(count: u64, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
 pub(super) fn split_with_ellipsis(line: &str, width: usize) -> Vec<String> {
     assert!(width > ELLIPSIS_WIDTH, "width > ELLIPSIS_WIDTH");
 